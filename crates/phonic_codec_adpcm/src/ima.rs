@@ -0,0 +1,128 @@
+use crate::ChannelState;
+use phonic_core::PhonicError;
+
+const STEP_TABLE: [i32; 89] = [
+    7, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 21, 23, 25, 28, 31, 34, 37, 41, 45, 50, 55, 60, 66,
+    73, 80, 88, 97, 107, 118, 130, 143, 157, 173, 190, 209, 230, 253, 279, 307, 337, 371, 408, 449,
+    494, 544, 598, 658, 724, 796, 876, 963, 1060, 1166, 1282, 1411, 1552, 1707, 1878, 2066, 2272,
+    2499, 2749, 3024, 3327, 3660, 4026, 4428, 4871, 5358, 5894, 6484, 7132, 7845, 8630, 9493,
+    10442, 11487, 12635, 13899, 15289, 16818, 18500, 20350, 22385, 24623, 27086, 29794, 32767,
+];
+
+const INDEX_TABLE: [i32; 16] = [-1, -1, -1, -1, 2, 4, 6, 8, -1, -1, -1, -1, 2, 4, 6, 8];
+
+const MAX_STEP_INDEX: i32 = STEP_TABLE.len() as i32 - 1;
+
+/// The number of samples of a channel packed into each group of 4 bytes.
+const GROUP_LEN: usize = 8;
+
+fn decode_nibble(state: &mut ChannelState, nibble: u8) -> i16 {
+    let step = STEP_TABLE[state.step as usize];
+    let mut diff = step >> 3;
+    if nibble & 1 != 0 {
+        diff += step >> 2;
+    }
+    if nibble & 2 != 0 {
+        diff += step >> 1;
+    }
+    if nibble & 4 != 0 {
+        diff += step;
+    }
+    if nibble & 8 != 0 {
+        diff = -diff;
+    }
+
+    state.sample1 = (state.sample1 as i32 + diff).clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+    state.step = (state.step + INDEX_TABLE[nibble as usize]).clamp(0, MAX_STEP_INDEX);
+    state.sample1
+}
+
+fn encode_nibble(state: &mut ChannelState, sample: i16) -> u8 {
+    let mut diff = sample as i32 - state.sample1 as i32;
+    let mut nibble = 0;
+    if diff < 0 {
+        nibble = 8;
+        diff = -diff;
+    }
+
+    let mut step = STEP_TABLE[state.step as usize];
+    for bit in [4, 2, 1] {
+        if diff >= step {
+            nibble |= bit;
+            diff -= step;
+        }
+
+        step >>= 1;
+    }
+
+    // the decoder's reconstruction is followed so that rounding errors do not accumulate
+    decode_nibble(state, nibble);
+    nibble
+}
+
+pub(crate) fn decode_block(
+    block: &[u8],
+    states: &mut [ChannelState],
+    samples: &mut [i16],
+) -> Result<usize, PhonicError> {
+    let n_channels = states.len();
+    let header_len = 4 * n_channels;
+    if block.len() < header_len {
+        return Err(PhonicError::InvalidData);
+    }
+
+    for (ch, state) in states.iter_mut().enumerate() {
+        let header = &block[4 * ch..4 * ch + 4];
+        state.sample1 = i16::from_le_bytes([header[0], header[1]]);
+        state.step = header[2] as i32;
+        if state.step > MAX_STEP_INDEX {
+            return Err(PhonicError::InvalidData);
+        }
+
+        samples[ch] = state.sample1;
+    }
+
+    // each group holds 4 bytes for every channel in turn, with the earlier sample of each byte in
+    // the low nibble
+    let n_groups = (block.len() - header_len) / (4 * n_channels);
+    for (group_i, group) in block[header_len..].chunks_exact(4 * n_channels).enumerate() {
+        for (ch, bytes) in group.chunks_exact(4).enumerate() {
+            let state = &mut states[ch];
+            for (i, byte) in bytes.iter().enumerate() {
+                let frame_i = 1 + group_i * GROUP_LEN + i * 2;
+                samples[frame_i * n_channels + ch] = decode_nibble(state, byte & 0x0f);
+                samples[(frame_i + 1) * n_channels + ch] = decode_nibble(state, byte >> 4);
+            }
+        }
+    }
+
+    Ok(1 + n_groups * GROUP_LEN)
+}
+
+pub(crate) fn encode_block(samples: &[i16], states: &mut [ChannelState], block: &mut [u8]) {
+    let n_channels = states.len();
+    let header_len = 4 * n_channels;
+
+    // the first frame is stored in full, and the step index carries over from the last block
+    for (ch, state) in states.iter_mut().enumerate() {
+        state.sample1 = samples[ch];
+        block[4 * ch..4 * ch + 2].copy_from_slice(&state.sample1.to_le_bytes());
+        block[4 * ch + 2] = state.step as u8;
+        block[4 * ch + 3] = 0;
+    }
+
+    for (group_i, group) in block[header_len..]
+        .chunks_exact_mut(4 * n_channels)
+        .enumerate()
+    {
+        for (ch, bytes) in group.chunks_exact_mut(4).enumerate() {
+            let state = &mut states[ch];
+            for (i, byte) in bytes.iter_mut().enumerate() {
+                let frame_i = 1 + group_i * GROUP_LEN + i * 2;
+                let low = encode_nibble(state, samples[frame_i * n_channels + ch]);
+                let high = encode_nibble(state, samples[(frame_i + 1) * n_channels + ch]);
+                *byte = low | high << 4;
+            }
+        }
+    }
+}
@@ -0,0 +1,587 @@
+use std::any::TypeId;
+use phonic_core::PhonicError;
+use phonic_io_core::{
+    utils::TaggedSignal, CodecTag, DynCodecConstructor, DynStream, Stream, StreamObserver,
+    StreamReader, StreamSeeker, StreamSpec, StreamWriter,
+};
+use phonic_signal::{Signal, SignalObserver, SignalReader, SignalSeeker, SignalSpec, SignalWriter};
+
+mod ima;
+mod ms;
+mod tag;
+
+pub use tag::*;
+
+/// Codes 16 bit samples a block at a time. Blocks of `block_align` bytes are gathered from or
+/// passed to the byte stream whole, and the padding that fills out the last block is dropped
+/// once the number of frames in the signal spec has been reached.
+pub struct AdpcmCodec<T, C: CodecTag = AdpcmCodecTag> {
+    inner: T,
+    stream_spec: StreamSpec<C>,
+    signal_spec: SignalSpec,
+    tag: AdpcmCodecTag,
+    states: Vec<ChannelState>,
+    bytes: BlockBuffer<u8>,
+    samples: BlockBuffer<i16>,
+    writing: bool,
+    block_i: u64,
+    inner_i: u64,
+    position: u64,
+    skip: usize,
+}
+
+/// The predictor state of a single channel. Decoders reset it from the header of every block,
+/// while encoders carry the step size over from one block to the next.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct ChannelState {
+    sample1: i16,
+    sample2: i16,
+    step: i32,
+    predictor: usize,
+}
+
+/// A block of bytes or samples. While it is being filled the range covers the filled part, and
+/// once it has been coded the range covers the part that is still to be passed on.
+struct BlockBuffer<S> {
+    buf: Vec<S>,
+    range: (usize, usize),
+}
+
+impl<S: Copy + Default> BlockBuffer<S> {
+    fn new(len: usize) -> Self {
+        Self {
+            buf: vec![S::default(); len],
+            range: (0, 0),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    fn pending(&self) -> &[S] {
+        &self.buf[self.range.0..self.range.1]
+    }
+
+    fn spare_mut(&mut self) -> &mut [S] {
+        &mut self.buf[self.range.1..]
+    }
+
+    fn is_empty(&self) -> bool {
+        self.range.0 == self.range.1
+    }
+
+    fn is_full(&self) -> bool {
+        self.range.1 == self.buf.len()
+    }
+
+    fn fill(&mut self, n: usize) {
+        self.range.1 += n;
+    }
+
+    fn consume(&mut self, n: usize) {
+        self.range.0 += n;
+        if self.is_empty() {
+            self.clear();
+        }
+    }
+
+    fn clear(&mut self) {
+        self.range = (0, 0);
+    }
+}
+
+pub fn fill_adpcm_spec<C>(spec: &mut StreamSpec<C>) -> Result<(), PhonicError>
+where
+    C: CodecTag + TryInto<AdpcmCodecTag>,
+{
+    let codec = spec.codec.ok_or(PhonicError::MissingData)?;
+    let tag: AdpcmCodecTag = codec.try_into().map_err(|_| PhonicError::InvalidData)?;
+
+    let sample_type = TypeId::of::<i16>();
+    if *spec.sample_type.get_or_insert(sample_type) != sample_type {
+        return Err(PhonicError::Unsupported);
+    }
+
+    let Some(n_channels) = spec.decoded_spec.channels.map(|c| c.count()) else {
+        return Ok(());
+    };
+
+    let frame_rate = spec.decoded_spec.frame_rate;
+    if spec.block_align.is_none() {
+        spec.block_align = frame_rate.and_then(|rate| tag.default_block_align(rate, n_channels));
+    }
+
+    let Some(block_align) = spec.block_align else {
+        return Ok(());
+    };
+
+    let frames_per_block = tag
+        .frames_per_block(block_align, n_channels)
+        .ok_or(PhonicError::InvalidData)?;
+
+    // the byte rate of a block coded stream is rounded down, as it is in wave headers
+    if let Some(rate) = frame_rate {
+        let byte_rate = rate as u64 * block_align as u64 / frames_per_block as u64;
+        spec.avg_bitrate.get_or_insert(byte_rate as f64 * 8.0);
+    }
+
+    Ok(())
+}
+
+pub fn adpcm_codec_from_stream<S>(stream: S) -> Result<TaggedSignal, PhonicError>
+where
+    S: DynStream + 'static,
+    S::Tag: TryInto<AdpcmCodecTag>,
+{
+    Ok(TaggedSignal::I16(Box::new(AdpcmCodec::from_stream(
+        stream,
+    )?)))
+}
+
+pub fn adpcm_codec_from_signal<C>(
+    signal: TaggedSignal,
+    tag: AdpcmCodecTag,
+) -> Result<Box<dyn DynStream<Tag = C>>, PhonicError>
+where
+    C: CodecTag + TryInto<AdpcmCodecTag> + 'static,
+    AdpcmCodecTag: TryInto<C>,
+{
+    let inner = signal.unwrap_i16_signal()?;
+    Ok(Box::new(AdpcmCodec::from_signal(inner, tag)?))
+}
+
+impl CodecTag for AdpcmCodecTag {
+    fn fill_spec(spec: &mut StreamSpec<Self>) -> Result<(), PhonicError> {
+        fill_adpcm_spec(spec)
+    }
+}
+
+impl DynCodecConstructor for AdpcmCodecTag {
+    fn from_signal(
+        &self,
+        signal: TaggedSignal,
+    ) -> Result<Box<dyn DynStream<Tag = Self>>, PhonicError> {
+        adpcm_codec_from_signal(signal, *self)
+    }
+
+    fn from_stream<S: DynStream<Tag = Self> + 'static>(
+        stream: S,
+    ) -> Result<TaggedSignal, PhonicError> {
+        adpcm_codec_from_stream(stream)
+    }
+}
+
+impl<T, C: CodecTag> AdpcmCodec<T, C> {
+    pub fn from_stream(inner: T) -> Result<Self, PhonicError>
+    where
+        T: Stream<Tag = C>,
+        C: TryInto<AdpcmCodecTag>,
+    {
+        let mut stream_spec = *inner.spec();
+        fill_adpcm_spec(&mut stream_spec)?;
+        let signal_spec = stream_spec.decoded_spec.build()?;
+        let tag = stream_spec
+            .codec
+            .and_then(|codec| codec.try_into().ok())
+            .ok_or(PhonicError::InvalidData)?;
+
+        Self::new(inner, stream_spec, signal_spec, tag)
+    }
+
+    /// Creates an encoder that codes the samples of `inner` in blocks of the default size for
+    /// its frame rate.
+    pub fn from_signal(inner: T, tag: AdpcmCodecTag) -> Result<Self, PhonicError>
+    where
+        T: Signal<Sample = i16>,
+        C: TryInto<AdpcmCodecTag>,
+        AdpcmCodecTag: TryInto<C>,
+    {
+        let spec = *inner.spec();
+        let block_align = tag
+            .default_block_align(spec.frame_rate, spec.channels.count())
+            .ok_or(PhonicError::Unsupported)?;
+
+        Self::from_signal_with_block_align(inner, tag, block_align)
+    }
+
+    /// Creates an encoder that codes the samples of `inner` in blocks of `block_align` bytes.
+    pub fn from_signal_with_block_align(
+        inner: T,
+        tag: AdpcmCodecTag,
+        block_align: u16,
+    ) -> Result<Self, PhonicError>
+    where
+        T: Signal<Sample = i16>,
+        C: TryInto<AdpcmCodecTag>,
+        AdpcmCodecTag: TryInto<C>,
+    {
+        let signal_spec = *inner.spec();
+        let mut stream_spec = StreamSpec::<C>::from(&inner).with_block_align(block_align);
+        stream_spec.codec = Some(tag.try_into().map_err(|_| PhonicError::Unsupported)?);
+        fill_adpcm_spec(&mut stream_spec)?;
+
+        Self::new(inner, stream_spec, signal_spec, tag)
+    }
+
+    fn new(
+        inner: T,
+        stream_spec: StreamSpec<C>,
+        signal_spec: SignalSpec,
+        tag: AdpcmCodecTag,
+    ) -> Result<Self, PhonicError> {
+        let block_align = stream_spec.block_align.ok_or(PhonicError::MissingData)?;
+        let n_channels = signal_spec.channels.count();
+        let frames_per_block = tag
+            .frames_per_block(block_align, n_channels)
+            .ok_or(PhonicError::InvalidData)?;
+
+        Ok(Self {
+            inner,
+            stream_spec,
+            signal_spec,
+            tag,
+            states: vec![ChannelState::default(); n_channels as usize],
+            bytes: BlockBuffer::new(block_align as usize),
+            samples: BlockBuffer::new(frames_per_block * n_channels as usize),
+            writing: false,
+            block_i: 0,
+            inner_i: 0,
+            position: 0,
+            skip: 0,
+        })
+    }
+
+    pub fn tag(&self) -> &AdpcmCodecTag {
+        &self.tag
+    }
+
+    pub fn as_inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Decodes the gathered bytes, leaving out any samples past the end of the signal.
+    fn decode_block(&mut self) -> Result<(), PhonicError> {
+        let block = self.bytes.pending();
+        let n_frames = match self.tag {
+            AdpcmCodecTag::Ima => ima::decode_block(block, &mut self.states, &mut self.samples.buf),
+            AdpcmCodecTag::Microsoft => {
+                ms::decode_block(block, &mut self.states, &mut self.samples.buf)
+            }
+        }?;
+
+        let block_len = self.samples.len() as u64;
+        let start = self.block_i * block_len;
+        let mut end = start + (n_frames * self.states.len()) as u64;
+        if let Some(n_frames) = self.signal_spec.n_frames {
+            end = end.min(n_frames * self.states.len() as u64);
+        }
+
+        self.bytes.clear();
+        self.samples.range = (0, end.saturating_sub(start) as usize);
+        self.block_i += 1;
+
+        Ok(())
+    }
+
+    /// Encodes the gathered samples, padding the block with silence if it is not full.
+    fn encode_block(&mut self) {
+        self.samples.spare_mut().fill(0);
+        match self.tag {
+            AdpcmCodecTag::Ima => {
+                ima::encode_block(&self.samples.buf, &mut self.states, &mut self.bytes.buf)
+            }
+            AdpcmCodecTag::Microsoft => {
+                ms::encode_block(&self.samples.buf, &mut self.states, &mut self.bytes.buf)
+            }
+        }
+
+        self.samples.clear();
+        self.bytes.range = (0, self.bytes.len());
+        self.block_i += 1;
+    }
+}
+
+impl<T, C: CodecTag> Signal for AdpcmCodec<T, C> {
+    type Sample = i16;
+
+    fn spec(&self) -> &SignalSpec {
+        &self.signal_spec
+    }
+}
+
+impl<T: StreamObserver, C: CodecTag> SignalObserver for AdpcmCodec<T, C> {
+    fn position(&self) -> Result<u64, PhonicError> {
+        Ok(self.position)
+    }
+}
+
+impl<T: StreamReader, C: CodecTag> SignalReader for AdpcmCodec<T, C> {
+    fn read(&mut self, buf: &mut [Self::Sample]) -> Result<usize, PhonicError> {
+        while self.samples.is_empty() {
+            while !self.bytes.is_full() {
+                match self.inner.read(self.bytes.spare_mut())? {
+                    0 => break,
+                    n => {
+                        self.bytes.fill(n);
+                        self.inner_i += n as u64;
+                    }
+                }
+            }
+
+            if self.bytes.is_empty() {
+                return Ok(0);
+            }
+
+            self.decode_block()?;
+            if self.samples.is_empty() {
+                return Ok(0);
+            }
+
+            // a seek may land part of the way into the block
+            let skip = self.skip.min(self.samples.pending().len());
+            self.samples.consume(skip);
+            self.skip = 0;
+        }
+
+        let n = buf.len().min(self.samples.pending().len());
+        buf[..n].copy_from_slice(&self.samples.pending()[..n]);
+        self.samples.consume(n);
+        self.position += n as u64;
+
+        Ok(n)
+    }
+}
+
+impl<T: StreamWriter, C: CodecTag> AdpcmCodec<T, C> {
+    /// Writes the encoded block to the inner stream, returning false if it did not accept all of
+    /// it.
+    fn write_block(&mut self) -> Result<bool, PhonicError> {
+        while !self.bytes.is_empty() {
+            match self.inner.write(self.bytes.pending())? {
+                0 => return Ok(false),
+                n => {
+                    self.bytes.consume(n);
+                    self.inner_i += n as u64;
+                }
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+impl<T: StreamWriter, C: CodecTag> SignalWriter for AdpcmCodec<T, C> {
+    fn write(&mut self, buf: &[Self::Sample]) -> Result<usize, PhonicError> {
+        self.writing = true;
+        if !self.write_block()? {
+            return Ok(0);
+        }
+
+        let spare = self.samples.spare_mut();
+        let n = buf.len().min(spare.len());
+        spare[..n].copy_from_slice(&buf[..n]);
+        self.samples.fill(n);
+        self.position += n as u64;
+
+        if self.samples.is_full() {
+            self.encode_block();
+            match self.write_block() {
+                Ok(_) | Err(PhonicError::NotReady) | Err(PhonicError::Interrupted) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<(), PhonicError> {
+        if !self.write_block()? {
+            return Err(PhonicError::EndOfStream);
+        }
+
+        if !self.samples.is_empty() {
+            self.encode_block();
+            if !self.write_block()? {
+                return Err(PhonicError::EndOfStream);
+            }
+        }
+
+        self.inner.flush()
+    }
+}
+
+impl<T: StreamSeeker, C: CodecTag> SignalSeeker for AdpcmCodec<T, C> {
+    fn seek(&mut self, offset: i64) -> Result<(), PhonicError> {
+        if self.writing {
+            return Err(PhonicError::Unsupported);
+        }
+
+        // decoding starts from the block holding the target sample
+        let position = self
+            .position
+            .checked_add_signed(offset)
+            .ok_or(PhonicError::InvalidData)?;
+
+        let block_i = position / self.samples.len() as u64;
+        let inner_i = block_i * self.bytes.len() as u64;
+        self.inner.seek(inner_i as i64 - self.inner_i as i64)?;
+
+        self.bytes.clear();
+        self.samples.clear();
+        self.block_i = block_i;
+        self.inner_i = inner_i;
+        self.position = position;
+        self.skip = (position % self.samples.len() as u64) as usize;
+
+        Ok(())
+    }
+}
+
+impl<T, C: CodecTag> Stream for AdpcmCodec<T, C> {
+    type Tag = C;
+
+    fn spec(&self) -> &StreamSpec<Self::Tag> {
+        &self.stream_spec
+    }
+}
+
+impl<T: SignalObserver, C: CodecTag> StreamObserver for AdpcmCodec<T, C> {
+    fn position(&self) -> Result<u64, PhonicError> {
+        Ok(self.position)
+    }
+}
+
+impl<T, C> StreamReader for AdpcmCodec<T, C>
+where
+    T: SignalReader<Sample = i16>,
+    C: CodecTag,
+{
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, PhonicError> {
+        if self.bytes.is_empty() {
+            while !self.samples.is_full() {
+                match self.inner.read(self.samples.spare_mut())? {
+                    0 => break,
+                    n => {
+                        self.samples.fill(n);
+                        self.inner_i += n as u64;
+                    }
+                }
+            }
+
+            if self.samples.is_empty() {
+                return Ok(0);
+            }
+
+            self.encode_block();
+        }
+
+        let n = buf.len().min(self.bytes.pending().len());
+        buf[..n].copy_from_slice(&self.bytes.pending()[..n]);
+        self.bytes.consume(n);
+        self.position += n as u64;
+
+        Ok(n)
+    }
+}
+
+impl<T, C> AdpcmCodec<T, C>
+where
+    T: SignalWriter<Sample = i16>,
+    C: CodecTag,
+{
+    /// Writes the decoded block to the inner signal, returning false if it did not accept all of
+    /// it.
+    fn write_samples(&mut self) -> Result<bool, PhonicError> {
+        while !self.samples.is_empty() {
+            match self.inner.write(self.samples.pending())? {
+                0 => return Ok(false),
+                n => {
+                    self.samples.consume(n);
+                    self.inner_i += n as u64;
+                }
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+impl<T, C> StreamWriter for AdpcmCodec<T, C>
+where
+    T: SignalWriter<Sample = i16>,
+    C: CodecTag,
+{
+    fn write(&mut self, buf: &[u8]) -> Result<usize, PhonicError> {
+        self.writing = true;
+        if !self.write_samples()? {
+            return Ok(0);
+        }
+
+        let spare = self.bytes.spare_mut();
+        let n = buf.len().min(spare.len());
+        spare[..n].copy_from_slice(&buf[..n]);
+        self.bytes.fill(n);
+        self.position += n as u64;
+
+        if self.bytes.is_full() {
+            self.decode_block()?;
+            match self.write_samples() {
+                Ok(_) | Err(PhonicError::NotReady) | Err(PhonicError::Interrupted) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<(), PhonicError> {
+        if !self.write_samples()? {
+            return Err(PhonicError::EndOfStream);
+        }
+
+        // a short final block still holds whole groups of samples
+        if !self.bytes.is_empty() {
+            self.decode_block()?;
+            if !self.write_samples()? {
+                return Err(PhonicError::EndOfStream);
+            }
+        }
+
+        self.inner.flush()
+    }
+}
+
+impl<T: SignalSeeker, C: CodecTag> StreamSeeker for AdpcmCodec<T, C> {
+    fn seek(&mut self, offset: i64) -> Result<(), PhonicError> {
+        if self.writing {
+            return Err(PhonicError::Unsupported);
+        }
+
+        // blocks can only be encoded from their first sample
+        let position = self
+            .position
+            .checked_add_signed(offset)
+            .ok_or(PhonicError::InvalidData)?;
+
+        if position % self.bytes.len() as u64 != 0 {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let block_i = position / self.bytes.len() as u64;
+        let inner_i = block_i * self.samples.len() as u64;
+        self.inner.seek(inner_i as i64 - self.inner_i as i64)?;
+
+        self.bytes.clear();
+        self.samples.clear();
+        self.block_i = block_i;
+        self.inner_i = inner_i;
+        self.position = position;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+use crate::{ChannelState, MS_ADPCM_COEFFICIENTS as COEFFICIENTS};
+use phonic_core::PhonicError;
+
+const ADAPTATION_TABLE: [i32; 16] = [
+    230, 230, 230, 230, 307, 409, 512, 614, 768, 614, 512, 409, 307, 230, 230, 230,
+];
+
+const MIN_DELTA: i32 = 16;
+const MAX_DELTA: i32 = i32::MAX / 768;
+
+fn predict(state: &ChannelState) -> i32 {
+    let (coef1, coef2) = COEFFICIENTS[state.predictor];
+    (state.sample1 as i32 * coef1 as i32 + state.sample2 as i32 * coef2 as i32) / 256
+}
+
+fn update(state: &mut ChannelState, nibble: u8) -> i16 {
+    let delta = (nibble as i8) << 4 >> 4;
+    let sample = predict(state) + delta as i32 * state.step;
+
+    state.sample2 = state.sample1;
+    state.sample1 = sample.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+    state.step =
+        ((ADAPTATION_TABLE[nibble as usize] * state.step) >> 8).clamp(MIN_DELTA, MAX_DELTA);
+    state.sample1
+}
+
+fn encode_nibble(state: &mut ChannelState, sample: i16) -> u8 {
+    // the error is rounded to the nearest multiple of the step
+    let error = sample as i32 - predict(state);
+    let bias = if error >= 0 {
+        state.step / 2
+    } else {
+        -state.step / 2
+    };
+    let nibble = ((error + bias) / state.step).clamp(-8, 7) as u8 & 0x0f;
+
+    update(state, nibble);
+    nibble
+}
+
+pub(crate) fn decode_block(
+    block: &[u8],
+    states: &mut [ChannelState],
+    samples: &mut [i16],
+) -> Result<usize, PhonicError> {
+    let n_channels = states.len();
+    let header_len = 7 * n_channels;
+    if block.len() < header_len {
+        return Err(PhonicError::InvalidData);
+    }
+
+    // the header stores each field for every channel before moving on to the next field
+    let read_i16 = |i: usize| i16::from_le_bytes([block[i], block[i + 1]]);
+    for (ch, state) in states.iter_mut().enumerate() {
+        state.predictor = block[ch] as usize;
+        if state.predictor >= COEFFICIENTS.len() {
+            return Err(PhonicError::InvalidData);
+        }
+
+        state.step = read_i16(n_channels + ch * 2) as i32;
+        state.sample1 = read_i16(3 * n_channels + ch * 2);
+        state.sample2 = read_i16(5 * n_channels + ch * 2);
+
+        samples[ch] = state.sample2;
+        samples[n_channels + ch] = state.sample1;
+    }
+
+    // every byte holds two samples, with the earlier one in the high nibble
+    let data = &block[header_len..];
+    let n_frames = 2 + data.len() * 2 / n_channels;
+    let nibbles = data.iter().flat_map(|byte| [byte >> 4, byte & 0x0f]);
+    for (i, nibble) in nibbles.take((n_frames - 2) * n_channels).enumerate() {
+        samples[2 * n_channels + i] = update(&mut states[i % n_channels], nibble);
+    }
+
+    Ok(n_frames)
+}
+
+/// The squared error of encoding the samples of a single channel with a predictor.
+fn prediction_error(samples: &[i16], n_channels: usize, ch: usize, state: ChannelState) -> u64 {
+    let mut state = state;
+    samples
+        .iter()
+        .skip(2 * n_channels + ch)
+        .step_by(n_channels)
+        .map(|sample| {
+            encode_nibble(&mut state, *sample);
+            (state.sample1 as i64 - *sample as i64).pow(2) as u64
+        })
+        .sum()
+}
+
+pub(crate) fn encode_block(samples: &[i16], states: &mut [ChannelState], block: &mut [u8]) {
+    let n_channels = states.len();
+    let header_len = 7 * n_channels;
+
+    // the first two frames are stored in full, and each channel uses whichever predictor fits
+    // the rest of the block best
+    for (ch, state) in states.iter_mut().enumerate() {
+        state.sample2 = samples[ch];
+        state.sample1 = samples[n_channels + ch];
+        state.step = state.step.clamp(MIN_DELTA, i16::MAX as i32);
+        state.predictor = (0..COEFFICIENTS.len())
+            .min_by_key(|predictor| {
+                let state = ChannelState {
+                    predictor: *predictor,
+                    ..*state
+                };
+
+                prediction_error(samples, n_channels, ch, state)
+            })
+            .unwrap_or_default();
+
+        block[ch] = state.predictor as u8;
+        block[n_channels + ch * 2..][..2].copy_from_slice(&(state.step as i16).to_le_bytes());
+        block[3 * n_channels + ch * 2..][..2].copy_from_slice(&state.sample1.to_le_bytes());
+        block[5 * n_channels + ch * 2..][..2].copy_from_slice(&state.sample2.to_le_bytes());
+    }
+
+    for (i, byte) in block[header_len..].iter_mut().enumerate() {
+        let high_i = 2 * n_channels + i * 2;
+        let high = encode_nibble(&mut states[high_i % n_channels], samples[high_i]);
+        let low = encode_nibble(&mut states[(high_i + 1) % n_channels], samples[high_i + 1]);
+        *byte = high << 4 | low;
+    }
+}
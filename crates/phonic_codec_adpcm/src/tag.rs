@@ -0,0 +1,62 @@
+/// The predictor coefficients of microsoft adpcm, in units of 1/256. The format allows streams to
+/// define their own, but in practice they all use these.
+pub const MS_ADPCM_COEFFICIENTS: [(i16, i16); 7] = [
+    (256, 0),
+    (512, -256),
+    (0, 0),
+    (192, 64),
+    (240, 0),
+    (460, -208),
+    (392, -232),
+];
+
+/// The flavour of adaptive differential pcm a stream is coded with. Both store 4 bits per sample
+/// in blocks that start with a header holding the predictor state of every channel.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum AdpcmCodecTag {
+    Ima,
+    Microsoft,
+}
+
+impl AdpcmCodecTag {
+    /// The number of bytes at the start of each block used for the channel headers.
+    pub fn header_len(self, n_channels: u16) -> usize {
+        let n_channels = n_channels as usize;
+        match self {
+            Self::Ima => 4 * n_channels,
+            Self::Microsoft => 7 * n_channels,
+        }
+    }
+
+    /// The number of frames in a block of `block_align` bytes, or `None` if the block can not be
+    /// evenly split between the channels.
+    pub fn frames_per_block(self, block_align: u16, n_channels: u16) -> Option<usize> {
+        let block_align = block_align as usize;
+        let header_len = self.header_len(n_channels);
+        let n_channels = n_channels as usize;
+
+        if n_channels == 0 || block_align <= header_len {
+            return None;
+        }
+
+        let data_len = block_align - header_len;
+        match self {
+            // samples are packed in groups of 8 per channel
+            Self::Ima if data_len.is_multiple_of(4 * n_channels) => {
+                Some(1 + data_len * 2 / n_channels)
+            }
+            Self::Microsoft if (data_len * 2).is_multiple_of(n_channels) => {
+                Some(2 + data_len * 2 / n_channels)
+            }
+            _ => None,
+        }
+    }
+
+    /// The block size used when a stream does not specify one, which grows with the frame rate
+    /// to keep the overhead of the headers roughly constant. Returns `None` if there are too many
+    /// channels for the block size to fit in a `u16`.
+    pub fn default_block_align(self, frame_rate: u32, n_channels: u16) -> Option<u16> {
+        let scale = (frame_rate / 11_025).clamp(1, 8).next_power_of_two();
+        (256 * scale * n_channels as u32).try_into().ok()
+    }
+}
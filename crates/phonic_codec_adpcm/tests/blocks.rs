@@ -0,0 +1,193 @@
+use phonic_codec_adpcm::{fill_adpcm_spec, AdpcmCodec, AdpcmCodecTag};
+use phonic_core::PhonicError;
+use phonic_io_core::{test_utils::BufferStream, Stream, StreamReader, StreamSpec, StreamWriter};
+use phonic_signal::{
+    test_utils::BufferSignal, SignalReader, SignalSeeker, SignalSpecBuilder, SignalWriter,
+};
+
+const TAGS: [AdpcmCodecTag; 2] = [AdpcmCodecTag::Ima, AdpcmCodecTag::Microsoft];
+const FRAME_RATE: u32 = 8_000;
+
+fn stream_spec(
+    tag: AdpcmCodecTag,
+    n_channels: u16,
+    n_frames: Option<u64>,
+) -> StreamSpec<AdpcmCodecTag> {
+    let decoded_spec = SignalSpecBuilder::new()
+        .with_frame_rate(FRAME_RATE)
+        .with_channels(n_channels)
+        .with_n_frames(n_frames);
+
+    StreamSpec::new()
+        .with_codec(tag)
+        .with_decoded_spec(decoded_spec)
+}
+
+fn test_signal(n_channels: u16, n_frames: usize) -> Vec<i16> {
+    (0..n_frames * n_channels as usize)
+        .map(|i| {
+            let t = (i / n_channels as usize) as f64 / FRAME_RATE as f64;
+            let phase = (i % n_channels as usize) as f64;
+            let tone = (t * 440.0 * std::f64::consts::TAU + phase).sin() * 12_000.0
+                + (t * 1_250.0 * std::f64::consts::TAU).sin() * 4_000.0;
+
+            tone as i16
+        })
+        .collect()
+}
+
+fn read_to_end<S: Copy + Default>(
+    mut read: impl FnMut(&mut [S]) -> Result<usize, PhonicError>,
+) -> Vec<S> {
+    let mut out = Vec::new();
+    let mut buf = [S::default(); 300];
+    loop {
+        match read(&mut buf).unwrap() {
+            0 => return out,
+            n => out.extend_from_slice(&buf[..n]),
+        }
+    }
+}
+
+fn encode(tag: AdpcmCodecTag, n_channels: u16, samples: &[i16]) -> Vec<u8> {
+    let signal = BufferSignal::new(FRAME_RATE, n_channels, samples.to_vec());
+    let mut encoder = AdpcmCodec::<_>::from_signal(signal, tag).unwrap();
+    read_to_end(|buf| StreamReader::read(&mut encoder, buf))
+}
+
+fn snr(reference: &[i16], decoded: &[i16]) -> f64 {
+    let signal: f64 = reference.iter().map(|s| (*s as f64).powi(2)).sum();
+    let noise: f64 = reference
+        .iter()
+        .zip(decoded)
+        .map(|(a, b)| (*a as f64 - *b as f64).powi(2))
+        .sum();
+
+    10.0 * (signal / noise).log10()
+}
+
+#[test]
+fn block_geometry() {
+    assert_eq!(AdpcmCodecTag::Ima.frames_per_block(256, 1), Some(505));
+    assert_eq!(AdpcmCodecTag::Ima.frames_per_block(512, 2), Some(505));
+    assert_eq!(AdpcmCodecTag::Microsoft.frames_per_block(256, 1), Some(500));
+    assert_eq!(AdpcmCodecTag::Microsoft.frames_per_block(512, 2), Some(500));
+
+    assert_eq!(AdpcmCodecTag::Ima.frames_per_block(258, 1), None);
+    assert_eq!(AdpcmCodecTag::Microsoft.frames_per_block(4, 1), None);
+
+    assert_eq!(AdpcmCodecTag::Ima.default_block_align(8_000, 1), Some(256));
+    assert_eq!(
+        AdpcmCodecTag::Ima.default_block_align(44_100, 2),
+        Some(2_048)
+    );
+    assert_eq!(AdpcmCodecTag::Ima.default_block_align(48_000, 255), None);
+}
+
+#[test]
+fn round_trip_is_close() {
+    for tag in TAGS {
+        for n_channels in [1, 2] {
+            let n_frames = 3_000;
+            let samples = test_signal(n_channels, n_frames);
+            let bytes = encode(tag, n_channels, &samples);
+
+            let block_align = tag.default_block_align(FRAME_RATE, n_channels).unwrap();
+            let frames_per_block = tag.frames_per_block(block_align, n_channels).unwrap();
+            let n_blocks = n_frames.div_ceil(frames_per_block);
+            assert_eq!(bytes.len(), n_blocks * block_align as usize);
+
+            let spec = stream_spec(tag, n_channels, Some(n_frames as u64));
+            let mut decoder = AdpcmCodec::from_stream(BufferStream::new(spec, bytes)).unwrap();
+            let decoded = read_to_end(|buf| SignalReader::read(&mut decoder, buf));
+            assert_eq!(decoded.len(), samples.len(), "{tag:?} {n_channels}");
+
+            let snr = snr(&samples, &decoded);
+            assert!(snr > 20.0, "{tag:?} {n_channels}: {snr:.1} db");
+        }
+    }
+}
+
+#[test]
+fn writers_match_readers() {
+    for tag in TAGS {
+        let samples = test_signal(2, 1_200);
+        let bytes = encode(tag, 2, &samples);
+
+        let spec = stream_spec(tag, 2, None);
+        let mut decoder = AdpcmCodec::from_stream(BufferStream::new(spec, bytes.clone())).unwrap();
+        let decoded = read_to_end(|buf| SignalReader::read(&mut decoder, buf));
+
+        let mut decoder = AdpcmCodec::from_stream(BufferStream::new(spec, Vec::new())).unwrap();
+        decoder.write_exact(&samples).unwrap();
+        SignalWriter::flush(&mut decoder).unwrap();
+        assert_eq!(
+            decoder.into_inner().into_bytes(),
+            bytes,
+            "{tag:?} decoder write"
+        );
+
+        let signal = BufferSignal::new(FRAME_RATE, 2, Vec::new());
+        let mut encoder = AdpcmCodec::<_>::from_signal(signal, tag).unwrap();
+        encoder.write_exact(&bytes).unwrap();
+        StreamWriter::flush(&mut encoder).unwrap();
+        assert_eq!(
+            encoder.into_inner().into_samples(),
+            decoded,
+            "{tag:?} encoder write"
+        );
+    }
+}
+
+#[test]
+fn seeking_decodes_from_block_start() {
+    for tag in TAGS {
+        let n_frames = 2_000;
+        let samples = test_signal(1, n_frames);
+        let bytes = encode(tag, 1, &samples);
+
+        let spec = stream_spec(tag, 1, Some(n_frames as u64));
+        let mut decoder = AdpcmCodec::from_stream(BufferStream::new(spec, bytes.clone())).unwrap();
+        let decoded = read_to_end(|buf| SignalReader::read(&mut decoder, buf));
+
+        let mut decoder = AdpcmCodec::from_stream(BufferStream::new(spec, bytes.clone())).unwrap();
+        let mut buf = [0; 50];
+        for target in [700, 1_999 - 50, 3, 505] {
+            decoder.set_position(target).unwrap();
+            decoder.read_exact(&mut buf).unwrap();
+
+            let target = target as usize;
+            assert_eq!(buf, decoded[target..target + 50], "{tag:?} {target}");
+        }
+
+        let mut decoder = AdpcmCodec::from_stream(BufferStream::new(spec, bytes)).unwrap();
+        decoder.write_exact(&samples[..10]).unwrap();
+        assert!(matches!(
+            SignalSeeker::seek(&mut decoder, 0),
+            Err(PhonicError::Unsupported)
+        ));
+    }
+}
+
+#[test]
+fn spec_is_filled() {
+    let spec = stream_spec(AdpcmCodecTag::Ima, 1, None);
+    let codec = AdpcmCodec::from_stream(BufferStream::new(spec, Vec::new())).unwrap();
+    let spec = Stream::spec(&codec);
+
+    assert_eq!(spec.block_align, Some(256));
+    assert_eq!(spec.avg_bitrate, Some(4_055.0 * 8.0));
+    assert_eq!(spec.sample_type, Some(std::any::TypeId::of::<i16>()));
+
+    let mut spec = stream_spec(AdpcmCodecTag::Ima, 1, None).with_block_align(258);
+    assert!(matches!(
+        fill_adpcm_spec(&mut spec),
+        Err(PhonicError::InvalidData)
+    ));
+
+    let mut spec = stream_spec(AdpcmCodecTag::Ima, 1, None).with_sample_type::<f32>();
+    assert!(matches!(
+        fill_adpcm_spec(&mut spec),
+        Err(PhonicError::Unsupported)
+    ));
+}
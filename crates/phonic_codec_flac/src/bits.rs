@@ -0,0 +1,143 @@
+use phonic_core::PhonicError;
+
+/// Reads big endian bit fields from a byte slice. Running out of bytes is reported as
+/// `EndOfStream`, which tells the codec to gather more of the stream and try again.
+pub(crate) struct BitReader<'a> {
+    buf: &'a [u8],
+    bit_i: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf, bit_i: 0 }
+    }
+
+    /// The number of whole bytes that have been read, counting a partly read byte.
+    pub fn byte_pos(&self) -> usize {
+        self.bit_i.div_ceil(8)
+    }
+
+    pub fn align(&mut self) {
+        self.bit_i = self.byte_pos() * 8;
+    }
+
+    pub fn read(&mut self, mut n_bits: u32) -> Result<u64, PhonicError> {
+        if self.bit_i + n_bits as usize > self.buf.len() * 8 {
+            return Err(PhonicError::EndOfStream);
+        }
+
+        let mut value = 0u64;
+        while n_bits > 0 {
+            let byte = self.buf[self.bit_i / 8];
+            let available = 8 - (self.bit_i % 8) as u32;
+            let n = available.min(n_bits);
+            let bits = (byte >> (available - n)) & (0xff >> (8 - n));
+
+            value = (value << n) | bits as u64;
+            self.bit_i += n as usize;
+            n_bits -= n;
+        }
+
+        Ok(value)
+    }
+
+    pub fn read_bit(&mut self) -> Result<bool, PhonicError> {
+        self.read(1).map(|bit| bit == 1)
+    }
+
+    pub fn read_signed(&mut self, n_bits: u32) -> Result<i64, PhonicError> {
+        if n_bits == 0 {
+            return Ok(0);
+        }
+
+        let value = self.read(n_bits)?;
+        let shift = 64 - n_bits;
+        Ok(((value << shift) as i64) >> shift)
+    }
+
+    /// Reads the number of zero bits before the next set bit, consuming the set bit.
+    pub fn read_unary(&mut self) -> Result<u32, PhonicError> {
+        let mut count = 0;
+        loop {
+            let Some(byte) = self.buf.get(self.bit_i / 8) else {
+                return Err(PhonicError::EndOfStream);
+            };
+
+            let offset = (self.bit_i % 8) as u32;
+            let bits = byte << offset;
+            if bits == 0 {
+                count += 8 - offset;
+                self.bit_i += (8 - offset) as usize;
+                continue;
+            }
+
+            let zeros = bits.leading_zeros();
+            count += zeros;
+            self.bit_i += zeros as usize + 1;
+            return Ok(count);
+        }
+    }
+}
+
+/// Writes big endian bit fields, padding the last byte with zeros once it is aligned.
+#[derive(Default)]
+pub(crate) struct BitWriter {
+    buf: Vec<u8>,
+    acc: u64,
+    n_bits: u32,
+}
+
+impl BitWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.buf
+    }
+
+    pub fn write(&mut self, value: u64, n_bits: u32) {
+        if n_bits > 32 {
+            self.write(value >> 32, n_bits - 32);
+            self.write(value & 0xffff_ffff, 32);
+            return;
+        }
+
+        if n_bits == 0 {
+            return;
+        }
+
+        self.acc = (self.acc << n_bits) | (value & ((1 << n_bits) - 1));
+        self.n_bits += n_bits;
+        while self.n_bits >= 8 {
+            self.n_bits -= 8;
+            self.buf.push((self.acc >> self.n_bits) as u8);
+        }
+
+        self.acc &= (1 << self.n_bits) - 1;
+    }
+
+    pub fn write_bit(&mut self, bit: bool) {
+        self.write(bit as u64, 1);
+    }
+
+    pub fn write_signed(&mut self, value: i64, n_bits: u32) {
+        self.write(value as u64, n_bits);
+    }
+
+    /// Writes `count` zero bits followed by a set bit.
+    pub fn write_unary(&mut self, mut count: u32) {
+        while count >= 32 {
+            self.write(0, 32);
+            count -= 32;
+        }
+
+        self.write(1, count + 1);
+    }
+
+    pub fn align(&mut self) {
+        if self.n_bits > 0 {
+            self.write(0, 8 - self.n_bits);
+        }
+    }
+}
@@ -0,0 +1,505 @@
+use crate::{
+    bits::{BitReader, BitWriter},
+    checksum::{crc16, crc8},
+    encoder::{analyse_channel, Subframe},
+    EncoderParams, StreamInfo,
+};
+use phonic_core::PhonicError;
+
+const SYNC_CODE: u64 = 0b11_1111_1111_1110;
+
+const SAMPLE_RATES: [u32; 12] = [
+    0, 88_200, 176_400, 192_000, 8_000, 16_000, 22_050, 24_000, 32_000, 44_100, 48_000, 96_000,
+];
+
+const BITS_PER_SAMPLE: [Option<u8>; 8] = [
+    None,
+    Some(8),
+    Some(12),
+    None,
+    Some(16),
+    Some(20),
+    Some(24),
+    Some(32),
+];
+
+/// How the channels of a block are coded. Stereo blocks can store the difference between the
+/// channels in place of one of them, which needs one extra bit per sample.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelAssignment {
+    Independent(u8),
+    LeftSide,
+    RightSide,
+    MidSide,
+}
+
+/// The header at the start of every coded block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockHeader {
+    pub variable_size: bool,
+
+    /// The index of the block when the block size is fixed, or of its first frame otherwise.
+    pub number: u64,
+    pub block_size: usize,
+    pub channels: ChannelAssignment,
+    pub bits_per_sample: Option<u8>,
+}
+
+impl ChannelAssignment {
+    fn from_code(code: u8) -> Result<Self, PhonicError> {
+        match code {
+            0..=7 => Ok(Self::Independent(code + 1)),
+            8 => Ok(Self::LeftSide),
+            9 => Ok(Self::RightSide),
+            10 => Ok(Self::MidSide),
+            _ => Err(PhonicError::InvalidData),
+        }
+    }
+
+    fn code(self) -> u8 {
+        match self {
+            Self::Independent(n) => n - 1,
+            Self::LeftSide => 8,
+            Self::RightSide => 9,
+            Self::MidSide => 10,
+        }
+    }
+
+    pub fn n_channels(self) -> usize {
+        match self {
+            Self::Independent(n) => n as usize,
+            _ => 2,
+        }
+    }
+
+    /// The number of bits added to the samples of the channel at `channel_i`.
+    fn side_bits(self, channel_i: usize) -> u32 {
+        match (self, channel_i) {
+            (Self::LeftSide | Self::MidSide, 1) | (Self::RightSide, 0) => 1,
+            _ => 0,
+        }
+    }
+}
+
+impl BlockHeader {
+    /// Returns the index of the first frame of the block, if the stream info describes how the
+    /// blocks of a fixed size stream are numbered.
+    pub fn frame_i(&self, info: &StreamInfo) -> Option<u64> {
+        if self.variable_size {
+            Some(self.number)
+        } else if info.min_block_size == info.max_block_size {
+            Some(self.number * info.max_block_size as u64)
+        } else {
+            None
+        }
+    }
+
+    /// Reads the header at the start of `buf`, checking its sync code and crc.
+    pub fn read(buf: &[u8]) -> Result<Self, PhonicError> {
+        Self::read_bits(&mut BitReader::new(buf), buf)
+    }
+
+    fn read_bits(reader: &mut BitReader, buf: &[u8]) -> Result<Self, PhonicError> {
+        if reader.read(14)? != SYNC_CODE || reader.read_bit()? {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let variable_size = reader.read_bit()?;
+        let block_size_code = reader.read(4)? as u8;
+        let sample_rate_code = reader.read(4)? as u8;
+        let channels = ChannelAssignment::from_code(reader.read(4)? as u8)?;
+        let bits_per_sample_code = reader.read(3)? as usize;
+        if reader.read_bit()? || bits_per_sample_code == 3 || sample_rate_code == 15 {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let number = read_utf8(reader)?;
+        let block_size = match block_size_code {
+            0 => return Err(PhonicError::InvalidData),
+            1 => 192,
+            2..=5 => 576 << (block_size_code - 2),
+            6 => reader.read(8)? as usize + 1,
+            7 => reader.read(16)? as usize + 1,
+            _ => 256 << (block_size_code - 8),
+        };
+
+        match sample_rate_code {
+            12 => reader.read(8).map(drop)?,
+            13 | 14 => reader.read(16).map(drop)?,
+            _ => {}
+        }
+
+        let header_len = reader.byte_pos();
+        if reader.read(8)? as u8 != crc8(&buf[..header_len]) {
+            return Err(PhonicError::InvalidData);
+        }
+
+        Ok(Self {
+            variable_size,
+            number,
+            block_size,
+            channels,
+            bits_per_sample: BITS_PER_SAMPLE[bits_per_sample_code],
+        })
+    }
+
+    fn write(&self, writer: &mut BitWriter, sample_rate: u32) {
+        let (block_size_code, block_size_bits) = match self.block_size {
+            192 => (1, 0),
+            576 | 1152 | 2304 | 4608 => (2 + (self.block_size / 576).trailing_zeros(), 0),
+            256 | 512 | 1024 | 2048 | 4096 | 8192 | 16384 | 32768 => {
+                (8 + (self.block_size / 256).trailing_zeros(), 0)
+            }
+            1..=256 => (6, 8),
+            _ => (7, 16),
+        };
+
+        let (sample_rate_code, sample_rate_bits, sample_rate_value) = match SAMPLE_RATES[1..]
+            .iter()
+            .position(|rate| *rate == sample_rate)
+        {
+            Some(i) => (i as u64 + 1, 0, 0),
+            None if sample_rate.is_multiple_of(1000) && sample_rate / 1000 < 256 => {
+                (12, 8, sample_rate / 1000)
+            }
+            None if sample_rate < 1 << 16 => (13, 16, sample_rate),
+            None if sample_rate.is_multiple_of(10) && sample_rate / 10 < 1 << 16 => {
+                (14, 16, sample_rate / 10)
+            }
+            None => (0, 0, 0),
+        };
+
+        let bits_per_sample_code = BITS_PER_SAMPLE
+            .iter()
+            .position(|bits| bits.is_some() && *bits == self.bits_per_sample)
+            .unwrap_or(0);
+
+        writer.write(SYNC_CODE, 14);
+        writer.write_bit(false);
+        writer.write_bit(self.variable_size);
+        writer.write(block_size_code as u64, 4);
+        writer.write(sample_rate_code, 4);
+        writer.write(self.channels.code() as u64, 4);
+        writer.write(bits_per_sample_code as u64, 3);
+        writer.write_bit(false);
+        write_utf8(writer, self.number);
+        writer.write(self.block_size as u64 - 1, block_size_bits);
+        writer.write(sample_rate_value as u64, sample_rate_bits);
+
+        let crc = crc8(writer.bytes());
+        writer.write(crc as u64, 8);
+    }
+}
+
+/// Reads the block or frame number, which is coded like a utf-8 character that can hold up to 36
+/// bits.
+fn read_utf8(reader: &mut BitReader) -> Result<u64, PhonicError> {
+    let first = reader.read(8)? as u8;
+    let n_bytes = first.leading_ones();
+    let mut value = match n_bytes {
+        0 => return Ok(first as u64),
+        2..=7 => (first & (0x7f >> n_bytes)) as u64,
+        _ => return Err(PhonicError::InvalidData),
+    };
+
+    for _ in 1..n_bytes {
+        let byte = reader.read(8)? as u8;
+        if byte & 0xc0 != 0x80 {
+            return Err(PhonicError::InvalidData);
+        }
+
+        value = (value << 6) | (byte & 0x3f) as u64;
+    }
+
+    Ok(value)
+}
+
+fn write_utf8(writer: &mut BitWriter, value: u64) {
+    if value < 0x80 {
+        writer.write(value, 8);
+        return;
+    }
+
+    let n_bytes = match value {
+        0x80..0x800 => 2,
+        0x800..0x1_0000 => 3,
+        0x1_0000..0x20_0000 => 4,
+        0x20_0000..0x400_0000 => 5,
+        0x400_0000..0x8000_0000 => 6,
+        _ => 7,
+    };
+
+    let prefix = (0xff00u16 >> n_bytes) as u8;
+    writer.write((prefix | (value >> (6 * (n_bytes - 1))) as u8) as u64, 8);
+    for i in (0..n_bytes - 1).rev() {
+        writer.write(0x80 | (value >> (6 * i)) & 0x3f, 8);
+    }
+}
+
+/// Decodes the block at the start of `buf` into the first `block_size` samples of each channel,
+/// returning its header and byte length.
+pub(crate) fn decode_block(
+    buf: &[u8],
+    info: &StreamInfo,
+    channels: &mut [Vec<i64>],
+) -> Result<(BlockHeader, usize), PhonicError> {
+    let mut reader = BitReader::new(buf);
+    let header = BlockHeader::read_bits(&mut reader, buf)?;
+
+    let n_channels = header.channels.n_channels();
+    if n_channels != channels.len() {
+        return Err(PhonicError::InvalidData);
+    }
+
+    let bits_per_sample = header.bits_per_sample.unwrap_or(info.bits_per_sample);
+    if bits_per_sample != info.bits_per_sample {
+        return Err(PhonicError::Unsupported);
+    }
+
+    for (i, channel) in channels.iter_mut().enumerate() {
+        channel.resize(header.block_size, 0);
+        let bits = bits_per_sample as u32 + header.channels.side_bits(i);
+        decode_subframe(&mut reader, bits, channel)?;
+    }
+
+    reader.align();
+    let len = reader.byte_pos();
+    if reader.read(16)? as u16 != crc16(&buf[..len]) {
+        return Err(PhonicError::InvalidData);
+    }
+
+    if let [first, second] = channels {
+        match header.channels {
+            ChannelAssignment::Independent(_) => {}
+            ChannelAssignment::LeftSide => {
+                for (left, side) in first.iter().zip(second.iter_mut()) {
+                    *side = left - *side;
+                }
+            }
+            ChannelAssignment::RightSide => {
+                for (side, right) in first.iter_mut().zip(second.iter()) {
+                    *side += right;
+                }
+            }
+            ChannelAssignment::MidSide => {
+                for (mid, side) in first.iter_mut().zip(second.iter_mut()) {
+                    let sum = (*mid << 1) | (*side & 1);
+                    (*mid, *side) = ((sum + *side) >> 1, (sum - *side) >> 1);
+                }
+            }
+        }
+    }
+
+    Ok((header, len + 2))
+}
+
+fn decode_subframe(
+    reader: &mut BitReader,
+    bits_per_sample: u32,
+    out: &mut [i64],
+) -> Result<(), PhonicError> {
+    if reader.read_bit()? {
+        return Err(PhonicError::InvalidData);
+    }
+
+    let kind = reader.read(6)? as usize;
+    let wasted_bits = match reader.read_bit()? {
+        true => reader.read_unary()? + 1,
+        false => 0,
+    };
+
+    let bits = bits_per_sample
+        .checked_sub(wasted_bits)
+        .filter(|bits| *bits > 0)
+        .ok_or(PhonicError::InvalidData)?;
+
+    match kind {
+        0 => out.fill(reader.read_signed(bits)?),
+        1 => {
+            for sample in out.iter_mut() {
+                *sample = reader.read_signed(bits)?;
+            }
+        }
+        8..=12 => {
+            let order = kind - 8;
+            read_warmup(reader, bits, order, out)?;
+            read_residual(reader, order, out)?;
+            restore_fixed(order, out);
+        }
+        32..=63 => {
+            let order = kind - 31;
+            read_warmup(reader, bits, order, out)?;
+
+            let precision = reader.read(4)? as u32 + 1;
+            let shift = reader.read_signed(5)?;
+            if precision == 16 || shift < 0 {
+                return Err(PhonicError::InvalidData);
+            }
+
+            let mut coefs = [0; 32];
+            for coef in coefs[..order].iter_mut() {
+                *coef = reader.read_signed(precision)?;
+            }
+
+            read_residual(reader, order, out)?;
+            restore_lpc(&coefs[..order], shift as u32, out);
+        }
+        _ => return Err(PhonicError::InvalidData),
+    }
+
+    if wasted_bits > 0 {
+        for sample in out.iter_mut() {
+            *sample <<= wasted_bits;
+        }
+    }
+
+    Ok(())
+}
+
+fn read_warmup(
+    reader: &mut BitReader,
+    bits: u32,
+    order: usize,
+    out: &mut [i64],
+) -> Result<(), PhonicError> {
+    if order > out.len() {
+        return Err(PhonicError::InvalidData);
+    }
+
+    for sample in out[..order].iter_mut() {
+        *sample = reader.read_signed(bits)?;
+    }
+
+    Ok(())
+}
+
+/// Reads the rice coded residual into the samples after the first `order`.
+fn read_residual(reader: &mut BitReader, order: usize, out: &mut [i64]) -> Result<(), PhonicError> {
+    let (param_bits, escape) = match reader.read(2)? {
+        0 => (4, 0b1111),
+        1 => (5, 0b1_1111),
+        _ => return Err(PhonicError::InvalidData),
+    };
+
+    let partition_order = reader.read(4)? as u32;
+    let n_partitions = 1 << partition_order;
+    let partition_len = out.len() >> partition_order;
+    if !out.len().is_multiple_of(n_partitions) || partition_len < order {
+        return Err(PhonicError::InvalidData);
+    }
+
+    let mut i = order;
+    for partition_i in 0..n_partitions {
+        let end = (partition_i + 1) * partition_len;
+        let param = reader.read(param_bits)? as u32;
+
+        if param == escape {
+            let bits = reader.read(5)? as u32;
+            for sample in out[i..end].iter_mut() {
+                *sample = reader.read_signed(bits)?;
+            }
+        } else {
+            for sample in out[i..end].iter_mut() {
+                let quotient = reader.read_unary()? as u64;
+                let value = (quotient << param) | reader.read(param)?;
+                *sample = (value >> 1) as i64 ^ -((value & 1) as i64);
+            }
+        }
+
+        i = end;
+    }
+
+    Ok(())
+}
+
+fn restore_fixed(order: usize, out: &mut [i64]) {
+    for i in order..out.len() {
+        out[i] += match order {
+            0 => 0,
+            1 => out[i - 1],
+            2 => 2 * out[i - 1] - out[i - 2],
+            3 => 3 * out[i - 1] - 3 * out[i - 2] + out[i - 3],
+            _ => 4 * out[i - 1] - 6 * out[i - 2] + 4 * out[i - 3] - out[i - 4],
+        };
+    }
+}
+
+fn restore_lpc(coefs: &[i64], shift: u32, out: &mut [i64]) {
+    for i in coefs.len()..out.len() {
+        let prediction: i64 = coefs
+            .iter()
+            .zip(out[..i].iter().rev())
+            .map(|(coef, sample)| coef * sample)
+            .sum();
+
+        out[i] += prediction >> shift;
+    }
+}
+
+/// Encodes a block of samples into `writer`, which should be empty. Stereo blocks are coded with
+/// whichever pairing of the channels and their difference takes the fewest bits.
+pub(crate) fn encode_block(
+    writer: &mut BitWriter,
+    number: u64,
+    info: &StreamInfo,
+    channels: &[Vec<i64>],
+    params: &EncoderParams,
+) {
+    let bits_per_sample = info.bits_per_sample as u32;
+    let (assignment, subframes) = match channels {
+        [left, right] if params.stereo_decorrelation => {
+            let side: Vec<_> = left.iter().zip(right).map(|(l, r)| l - r).collect();
+            let mid: Vec<_> = left.iter().zip(right).map(|(l, r)| (l + r) >> 1).collect();
+
+            let left = analyse_channel(left, bits_per_sample, params);
+            let right = analyse_channel(right, bits_per_sample, params);
+            let side = analyse_channel(&side, bits_per_sample + 1, params);
+            let mid = analyse_channel(&mid, bits_per_sample, params);
+
+            let options = [
+                (ChannelAssignment::Independent(2), left.bits + right.bits),
+                (ChannelAssignment::LeftSide, left.bits + side.bits),
+                (ChannelAssignment::RightSide, side.bits + right.bits),
+                (ChannelAssignment::MidSide, mid.bits + side.bits),
+            ];
+
+            let (assignment, _) = options.into_iter().min_by_key(|(_, bits)| *bits).unwrap();
+            let subframes = match assignment {
+                ChannelAssignment::LeftSide => vec![left, side],
+                ChannelAssignment::RightSide => vec![side, right],
+                ChannelAssignment::MidSide => vec![mid, side],
+                ChannelAssignment::Independent(_) => vec![left, right],
+            };
+
+            (assignment, subframes)
+        }
+        _ => {
+            let subframes: Vec<Subframe> = channels
+                .iter()
+                .map(|channel| analyse_channel(channel, bits_per_sample, params))
+                .collect();
+
+            (
+                ChannelAssignment::Independent(channels.len() as u8),
+                subframes,
+            )
+        }
+    };
+
+    let header = BlockHeader {
+        variable_size: false,
+        number,
+        block_size: channels[0].len(),
+        channels: assignment,
+        bits_per_sample: Some(info.bits_per_sample),
+    };
+
+    header.write(writer, info.sample_rate);
+    for subframe in subframes.iter() {
+        subframe.write(writer);
+    }
+
+    writer.align();
+    let crc = crc16(writer.bytes());
+    writer.write(crc as u64, 16);
+}
@@ -0,0 +1,487 @@
+use crate::{bits::BitWriter, EncoderParams};
+
+const MAX_FIXED_ORDER: usize = 4;
+const MAX_RICE_PARAM: u32 = 30;
+
+/// The coding picked for one channel of a block, along with the samples it codes. Samples have
+/// their wasted bits shifted out.
+pub(crate) struct Subframe {
+    kind: SubframeKind,
+    samples: Vec<i64>,
+    residual: Vec<i64>,
+    rice: RicePlan,
+    bits_per_sample: u32,
+    wasted_bits: u32,
+
+    /// The length of the coded subframe in bits.
+    pub bits: usize,
+}
+
+enum SubframeKind {
+    Constant,
+    Verbatim,
+    Fixed {
+        order: usize,
+    },
+    Lpc {
+        precision: u32,
+        shift: u32,
+        coefs: Vec<i64>,
+    },
+}
+
+#[derive(Default, Clone)]
+struct RicePlan {
+    partition_order: u32,
+    params: Vec<u32>,
+}
+
+impl Subframe {
+    fn order(&self) -> usize {
+        match &self.kind {
+            SubframeKind::Constant | SubframeKind::Verbatim => 0,
+            SubframeKind::Fixed { order } => *order,
+            SubframeKind::Lpc { coefs, .. } => coefs.len(),
+        }
+    }
+
+    pub fn write(&self, writer: &mut BitWriter) {
+        let kind = match &self.kind {
+            SubframeKind::Constant => 0,
+            SubframeKind::Verbatim => 1,
+            SubframeKind::Fixed { order } => 8 + *order as u64,
+            SubframeKind::Lpc { coefs, .. } => 31 + coefs.len() as u64,
+        };
+
+        writer.write_bit(false);
+        writer.write(kind, 6);
+        writer.write_bit(self.wasted_bits > 0);
+        if self.wasted_bits > 0 {
+            writer.write_unary(self.wasted_bits - 1);
+        }
+
+        let bits = self.bits_per_sample;
+        match &self.kind {
+            SubframeKind::Constant => writer.write_signed(self.samples[0], bits),
+            SubframeKind::Verbatim => {
+                for sample in self.samples.iter() {
+                    writer.write_signed(*sample, bits);
+                }
+            }
+            SubframeKind::Fixed { order } => {
+                for sample in self.samples[..*order].iter() {
+                    writer.write_signed(*sample, bits);
+                }
+
+                self.write_residual(writer);
+            }
+            SubframeKind::Lpc {
+                precision,
+                shift,
+                coefs,
+            } => {
+                for sample in self.samples[..coefs.len()].iter() {
+                    writer.write_signed(*sample, bits);
+                }
+
+                writer.write(*precision as u64 - 1, 4);
+                writer.write(*shift as u64, 5);
+                for coef in coefs.iter() {
+                    writer.write_signed(*coef, *precision);
+                }
+
+                self.write_residual(writer);
+            }
+        }
+    }
+
+    fn write_residual(&self, writer: &mut BitWriter) {
+        let wide_params = self.rice.params.iter().any(|param| *param > 14);
+        let param_bits = if wide_params { 5 } else { 4 };
+
+        writer.write(wide_params as u64, 2);
+        writer.write(self.rice.partition_order as u64, 4);
+
+        let partition_len = self.residual.len() >> self.rice.partition_order;
+        for (i, param) in self.rice.params.iter().enumerate() {
+            let start = if i == 0 {
+                self.order()
+            } else {
+                i * partition_len
+            };
+            writer.write(*param as u64, param_bits);
+
+            for value in self.residual[start..(i + 1) * partition_len].iter() {
+                let value = zigzag(*value);
+                writer.write_unary((value >> param) as u32);
+                writer.write(value, *param);
+            }
+        }
+    }
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+/// Picks the coding of a channel that takes the fewest bits, out of a constant value, the raw
+/// samples, the fixed predictors and, if the parameters allow it, linear prediction.
+pub(crate) fn analyse_channel(
+    channel: &[i64],
+    bits_per_sample: u32,
+    params: &EncoderParams,
+) -> Subframe {
+    let all_bits = channel.iter().fold(0, |bits, sample| bits | sample);
+    let wasted_bits = match all_bits {
+        0 => 0,
+        _ => all_bits.trailing_zeros().min(bits_per_sample - 1),
+    };
+
+    let samples: Vec<i64> = channel.iter().map(|s| s >> wasted_bits).collect();
+    let bits = bits_per_sample - wasted_bits;
+    let header_bits = 8 + wasted_bits as usize;
+    let n = samples.len();
+
+    let subframe = |kind, residual, rice, bits: usize| Subframe {
+        kind,
+        samples: Vec::new(),
+        residual,
+        rice,
+        bits_per_sample: bits_per_sample - wasted_bits,
+        wasted_bits,
+        bits: header_bits + bits,
+    };
+
+    let mut best = if samples.iter().all(|s| *s == samples[0]) {
+        subframe(
+            SubframeKind::Constant,
+            Vec::new(),
+            RicePlan::default(),
+            bits as usize,
+        )
+    } else {
+        subframe(
+            SubframeKind::Verbatim,
+            Vec::new(),
+            RicePlan::default(),
+            n * bits as usize,
+        )
+    };
+
+    if matches!(best.kind, SubframeKind::Verbatim) {
+        let mut residual = vec![0; n];
+        for order in 0..=MAX_FIXED_ORDER.min(n - 1) {
+            if !fixed_residual(&samples, order, &mut residual) {
+                continue;
+            }
+
+            let (rice, rice_bits) = plan_rice(&residual, order, params.max_partition_order);
+            let total = header_bits + order * bits as usize + rice_bits;
+            if total < best.bits {
+                best = subframe(
+                    SubframeKind::Fixed { order },
+                    residual.clone(),
+                    rice,
+                    total - header_bits,
+                );
+            }
+        }
+
+        let max_order = params.max_lpc_order.min(n - 1);
+        if max_order > 0 {
+            if let Some(lpc) = analyse_lpc(&samples, bits, max_order, params) {
+                let total = header_bits + lpc.bits;
+                if total < best.bits {
+                    best = Subframe {
+                        bits: total,
+                        ..subframe(lpc.kind, lpc.residual, lpc.rice, 0)
+                    };
+                }
+            }
+        }
+    }
+
+    best.samples = samples;
+    best
+}
+
+/// Computes the residual of a fixed polynomial predictor, returning false if it does not fit in
+/// the 32 bits the format allows.
+fn fixed_residual(samples: &[i64], order: usize, residual: &mut [i64]) -> bool {
+    for i in order..samples.len() {
+        let prediction = match order {
+            0 => 0,
+            1 => samples[i - 1],
+            2 => 2 * samples[i - 1] - samples[i - 2],
+            3 => 3 * samples[i - 1] - 3 * samples[i - 2] + samples[i - 3],
+            _ => 4 * samples[i - 1] - 6 * samples[i - 2] + 4 * samples[i - 3] - samples[i - 4],
+        };
+
+        residual[i] = samples[i] - prediction;
+    }
+
+    fits_residual(&residual[order..])
+}
+
+fn fits_residual(residual: &[i64]) -> bool {
+    residual.iter().all(|value| i32::try_from(*value).is_ok())
+}
+
+struct LpcCandidate {
+    kind: SubframeKind,
+    residual: Vec<i64>,
+    rice: RicePlan,
+    bits: usize,
+}
+
+/// Finds the linear predictor that codes the samples in the fewest bits. The coefficients of
+/// every order come from the autocorrelation of the windowed samples, and unless the search is
+/// exhaustive only the order with the lowest estimated cost is coded.
+fn analyse_lpc(
+    samples: &[i64],
+    bits_per_sample: u32,
+    max_order: usize,
+    params: &EncoderParams,
+) -> Option<LpcCandidate> {
+    let n = samples.len();
+    let windowed: Vec<f64> = samples
+        .iter()
+        .enumerate()
+        .map(|(i, s)| *s as f64 * tukey_window(i, n))
+        .collect();
+
+    let autocorrelation: Vec<f64> = (0..=max_order)
+        .map(|lag| {
+            windowed[lag..]
+                .iter()
+                .zip(windowed.iter())
+                .map(|(a, b)| a * b)
+                .sum()
+        })
+        .collect();
+
+    let predictors = levinson_durbin(&autocorrelation, max_order);
+    if predictors.is_empty() {
+        return None;
+    }
+
+    let precision = match n {
+        0..=192 => 7,
+        193..=384 => 8,
+        385..=576 => 9,
+        577..=1152 => 10,
+        1153..=2304 => 11,
+        2305..=4608 => 12,
+        _ => 13,
+    };
+
+    let orders: Vec<usize> = if params.exhaustive_lpc {
+        (1..=predictors.len()).collect()
+    } else {
+        let estimate = |(coefs, error): &(Vec<f64>, f64)| {
+            let variance = (error / n as f64).max(1e-9);
+            let residual_bits = (0.5 * variance.log2()).max(0.0) * (n - coefs.len()) as f64;
+            residual_bits + (coefs.len() as u32 * (precision + bits_per_sample)) as f64
+        };
+
+        let best = predictors
+            .iter()
+            .map(estimate)
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(i, _)| i + 1);
+
+        best.into_iter().collect()
+    };
+
+    let mut best: Option<LpcCandidate> = None;
+    let mut residual = vec![0; n];
+    for order in orders {
+        let Some((coefs, shift)) = quantize(&predictors[order - 1].0, precision) else {
+            continue;
+        };
+
+        if !lpc_residual(samples, &coefs, shift, &mut residual) {
+            continue;
+        }
+
+        let (rice, rice_bits) = plan_rice(&residual, order, params.max_partition_order);
+        let bits = order * (bits_per_sample + precision) as usize + 4 + 5 + rice_bits;
+        if best.as_ref().is_none_or(|best| bits < best.bits) {
+            best = Some(LpcCandidate {
+                kind: SubframeKind::Lpc {
+                    precision,
+                    shift,
+                    coefs,
+                },
+                residual: residual.clone(),
+                rice,
+                bits,
+            });
+        }
+    }
+
+    best
+}
+
+/// A window that tapers the first and last quarter of the block with a cosine.
+fn tukey_window(i: usize, n: usize) -> f64 {
+    let taper = n / 4;
+    let distance = i.min(n - 1 - i);
+    if distance >= taper {
+        return 1.0;
+    }
+
+    0.5 - 0.5 * (std::f64::consts::PI * distance as f64 / taper as f64).cos()
+}
+
+/// Returns the prediction coefficients and error of every order up to `max_order`, stopping
+/// early if the error vanishes.
+fn levinson_durbin(autocorrelation: &[f64], max_order: usize) -> Vec<(Vec<f64>, f64)> {
+    let mut predictors = Vec::with_capacity(max_order);
+    let mut coefs = vec![0.0; max_order];
+    let mut error = autocorrelation[0];
+
+    for i in 0..max_order {
+        if error <= 0.0 {
+            break;
+        }
+
+        let mut acc = autocorrelation[i + 1];
+        for j in 0..i {
+            acc -= coefs[j] * autocorrelation[i - j];
+        }
+
+        let reflection = acc / error;
+        let previous = coefs.clone();
+        coefs[i] = reflection;
+        for j in 0..i {
+            coefs[j] = previous[j] - reflection * previous[i - 1 - j];
+        }
+
+        error *= 1.0 - reflection * reflection;
+        predictors.push((coefs[..=i].to_vec(), error));
+    }
+
+    predictors
+}
+
+/// Rounds the coefficients to integers of `precision` bits scaled by a power of two, carrying
+/// the rounding error over to the next coefficient.
+fn quantize(coefs: &[f64], precision: u32) -> Option<(Vec<i64>, u32)> {
+    let max = coefs.iter().fold(0.0f64, |max, coef| max.max(coef.abs()));
+    if max <= 0.0 || !max.is_finite() {
+        return None;
+    }
+
+    let log2_max = max.log2().floor() as i32 + 1;
+    let shift = (precision as i32 - 1 - log2_max).min(15);
+    if shift < 0 {
+        return None;
+    }
+
+    let limit = 1i64 << (precision - 1);
+    let scale = (1u32 << shift) as f64;
+    let mut error = 0.0;
+    let quantized = coefs
+        .iter()
+        .map(|coef| {
+            let value = coef * scale + error;
+            let rounded = (value.round() as i64).clamp(-limit, limit - 1);
+            error = value - rounded as f64;
+            rounded
+        })
+        .collect();
+
+    Some((quantized, shift as u32))
+}
+
+fn lpc_residual(samples: &[i64], coefs: &[i64], shift: u32, residual: &mut [i64]) -> bool {
+    for i in coefs.len()..samples.len() {
+        let prediction: i64 = coefs
+            .iter()
+            .zip(samples[..i].iter().rev())
+            .map(|(coef, sample)| coef * sample)
+            .sum();
+
+        residual[i] = samples[i] - (prediction >> shift);
+    }
+
+    fits_residual(&residual[coefs.len()..])
+}
+
+/// Picks the partition order and rice parameters for the residual after the first `order`
+/// samples, returning them with the exact number of bits they code the residual in.
+fn plan_rice(residual: &[i64], order: usize, max_partition_order: u32) -> (RicePlan, usize) {
+    let n = residual.len();
+    let mut top_order = max_partition_order;
+    while top_order > 0 && (!n.is_multiple_of(1 << top_order) || (n >> top_order) <= order) {
+        top_order -= 1;
+    }
+
+    let partition_len = n >> top_order;
+    let mut sums: Vec<(u64, usize)> = (0..1 << top_order)
+        .map(|i| {
+            let start = if i == 0 { order } else { i * partition_len };
+            let values = &residual[start..(i + 1) * partition_len];
+            (values.iter().map(|v| zigzag(*v)).sum(), values.len())
+        })
+        .collect();
+
+    let mut best = (0, usize::MAX, Vec::new());
+    for partition_order in (0..=top_order).rev() {
+        let params: Vec<(u32, usize)> = sums
+            .iter()
+            .map(|(sum, len)| estimate_param(*sum, *len))
+            .collect();
+
+        let estimate = params.iter().map(|(_, bits)| bits + 4).sum::<usize>();
+        if estimate < best.1 {
+            best = (
+                partition_order,
+                estimate,
+                params.iter().map(|(param, _)| *param).collect(),
+            );
+        }
+
+        sums = sums
+            .chunks(2)
+            .map(|pair| pair.iter().fold((0, 0), |a, b| (a.0 + b.0, a.1 + b.1)))
+            .collect();
+    }
+
+    let plan = RicePlan {
+        partition_order: best.0,
+        params: best.2,
+    };
+
+    let param_bits = if plan.params.iter().any(|p| *p > 14) {
+        5
+    } else {
+        4
+    };
+    let partition_len = n >> plan.partition_order;
+    let mut bits = 6;
+    for (i, param) in plan.params.iter().enumerate() {
+        let start = if i == 0 { order } else { i * partition_len };
+        let values = &residual[start..(i + 1) * partition_len];
+        bits += param_bits + values.len() * (*param as usize + 1);
+        bits += values
+            .iter()
+            .map(|v| (zigzag(*v) >> param) as usize)
+            .sum::<usize>();
+    }
+
+    (plan, bits)
+}
+
+/// Estimates the rice parameter that codes `len` values adding up to `sum` in the fewest bits.
+fn estimate_param(sum: u64, len: usize) -> (u32, usize) {
+    (0..=MAX_RICE_PARAM)
+        .map(|param| {
+            let bits = len as u64 * (param as u64 + 1) + (sum >> param);
+            (param, bits as usize)
+        })
+        .min_by_key(|(_, bits)| *bits)
+        .unwrap()
+}
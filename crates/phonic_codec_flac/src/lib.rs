@@ -0,0 +1,939 @@
+use crate::{
+    bits::BitWriter,
+    block::{decode_block, encode_block},
+    checksum::Md5,
+};
+use std::any::TypeId;
+use phonic_core::PhonicError;
+use phonic_io_core::{
+    utils::TaggedSignal, CodecTag, DynCodecConstructor, DynStream, Stream, StreamObserver,
+    StreamReader, StreamSeeker, StreamSpec, StreamWriter,
+};
+use phonic_signal::{
+    KnownSampleType, Sample, Signal, SignalObserver, SignalReader, SignalSeeker, SignalSpec,
+    SignalWriter, I24,
+};
+
+mod bits;
+mod block;
+mod checksum;
+mod encoder;
+mod metadata;
+mod sample;
+mod tag;
+
+pub use block::{BlockHeader, ChannelAssignment};
+pub use metadata::*;
+pub use sample::*;
+pub use tag::*;
+
+use tag::EncoderParams;
+
+/// The fewest bytes read from the inner stream at a time while gathering a block.
+const READ_LEN: usize = 16 * 1024;
+
+/// The number of seconds between the seek points an encoder reserves.
+const SEEK_POINT_SPACING: u64 = 10;
+
+/// Codes a native flac stream, which starts with the `fLaC` marker and the metadata blocks. The
+/// decoder reads the stream info and seek table from the stream itself, checks the md5 digest of
+/// the samples once it has decoded the whole stream, and seeks from the closest seek point. The
+/// encoder writes a stream info block and reserves seek points when the number of frames is
+/// known, filling both in on flush if the inner stream can seek back to them.
+pub struct FlacCodec<T, S: Sample, C: CodecTag = FlacCodecTag> {
+    inner: T,
+    stream_spec: StreamSpec<C>,
+    signal_spec: SignalSpec,
+    tag: FlacCodecTag,
+    metadata: Option<FlacMetadata>,
+    bytes: ByteBuffer,
+    samples: BlockBuffer<S>,
+    channels: Vec<Vec<i64>>,
+    md5: Option<Md5>,
+    lost_sync: bool,
+    writing: bool,
+    block_i: u64,
+    frame_i: u64,
+    coded_len: u64,
+    inner_i: u64,
+    position: u64,
+    skip: u64,
+}
+
+/// Coded bytes that have been gathered but not yet decoded or passed on.
+#[derive(Default)]
+struct ByteBuffer {
+    buf: Vec<u8>,
+    start: usize,
+}
+
+impl ByteBuffer {
+    fn pending(&self) -> &[u8] {
+        &self.buf[self.start..]
+    }
+
+    fn is_empty(&self) -> bool {
+        self.start == self.buf.len()
+    }
+
+    fn consume(&mut self, n: usize) {
+        self.start += n;
+        if self.is_empty() {
+            self.clear();
+        }
+    }
+
+    fn clear(&mut self) {
+        self.buf.clear();
+        self.start = 0;
+    }
+
+    fn compact(&mut self) {
+        if self.start > 0 {
+            self.buf.drain(..self.start);
+            self.start = 0;
+        }
+    }
+
+    fn extend(&mut self, bytes: &[u8]) {
+        self.compact();
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Appends up to `len` bytes from `read`, returning the number it produced.
+    fn read_from(
+        &mut self,
+        len: usize,
+        read: impl FnOnce(&mut [u8]) -> Result<usize, PhonicError>,
+    ) -> Result<usize, PhonicError> {
+        self.compact();
+        let filled = self.buf.len();
+        self.buf.resize(filled + len, 0);
+
+        let result = read(&mut self.buf[filled..]);
+        self.buf.truncate(filled + *result.as_ref().unwrap_or(&0));
+        result
+    }
+}
+
+/// A block of interleaved samples. While it is being filled the range covers the filled part,
+/// and once it has been coded the range covers the part that is still to be passed on.
+struct BlockBuffer<S> {
+    buf: Vec<S>,
+    range: (usize, usize),
+}
+
+impl<S: Sample> BlockBuffer<S> {
+    fn new(len: usize) -> Self {
+        Self {
+            buf: vec![S::ORIGIN; len],
+            range: (0, 0),
+        }
+    }
+
+    fn pending(&self) -> &[S] {
+        &self.buf[self.range.0..self.range.1]
+    }
+
+    fn spare_mut(&mut self) -> &mut [S] {
+        &mut self.buf[self.range.1..]
+    }
+
+    fn is_empty(&self) -> bool {
+        self.range.0 == self.range.1
+    }
+
+    fn is_full(&self) -> bool {
+        self.range.1 == self.buf.len()
+    }
+
+    fn fill(&mut self, n: usize) {
+        self.range.1 += n;
+    }
+
+    fn consume(&mut self, n: usize) {
+        self.range.0 += n;
+        if self.is_empty() {
+            self.clear();
+        }
+    }
+
+    fn clear(&mut self) {
+        self.range = (0, 0);
+    }
+}
+
+pub fn fill_flac_spec<C>(spec: &mut StreamSpec<C>) -> Result<(), PhonicError>
+where
+    C: CodecTag + TryInto<FlacCodecTag>,
+{
+    let codec = spec.codec.ok_or(PhonicError::MissingData)?;
+    let tag: FlacCodecTag = codec.try_into().map_err(|_| PhonicError::InvalidData)?;
+    tag.encoder_params()?;
+
+    let sample_type = *spec.sample_type.get_or_insert(TypeId::of::<i16>());
+    let supported = [
+        TypeId::of::<i8>(),
+        TypeId::of::<i16>(),
+        TypeId::of::<I24>(),
+        TypeId::of::<i32>(),
+    ];
+
+    if !supported.contains(&sample_type) {
+        return Err(PhonicError::Unsupported);
+    }
+
+    if spec
+        .decoded_spec
+        .channels
+        .is_some_and(|channels| !(1..=8).contains(&channels.count()))
+    {
+        return Err(PhonicError::Unsupported);
+    }
+
+    if spec
+        .decoded_spec
+        .frame_rate
+        .is_some_and(|rate| rate == 0 || rate >= 1 << 20)
+    {
+        return Err(PhonicError::Unsupported);
+    }
+
+    Ok(())
+}
+
+pub fn flac_codec_from_stream<S>(stream: S) -> Result<TaggedSignal, PhonicError>
+where
+    S: DynStream + 'static,
+    S::Tag: TryInto<FlacCodecTag>,
+{
+    let signal = match stream
+        .spec()
+        .sample_type
+        .ok_or(PhonicError::MissingData)?
+        .try_into()?
+    {
+        KnownSampleType::I8 => {
+            TaggedSignal::I8(Box::new(FlacCodec::<_, i8, _>::from_stream(stream)?))
+        }
+        KnownSampleType::I16 => {
+            TaggedSignal::I16(Box::new(FlacCodec::<_, i16, _>::from_stream(stream)?))
+        }
+        KnownSampleType::I24 => {
+            TaggedSignal::I24(Box::new(FlacCodec::<_, I24, _>::from_stream(stream)?))
+        }
+        KnownSampleType::I32 => {
+            TaggedSignal::I32(Box::new(FlacCodec::<_, i32, _>::from_stream(stream)?))
+        }
+        _ => return Err(PhonicError::Unsupported),
+    };
+
+    Ok(signal)
+}
+
+pub fn flac_codec_from_signal<C>(
+    signal: TaggedSignal,
+    tag: FlacCodecTag,
+) -> Result<Box<dyn DynStream<Tag = C>>, PhonicError>
+where
+    C: CodecTag + TryInto<FlacCodecTag> + 'static,
+    FlacCodecTag: TryInto<C>,
+{
+    match signal {
+        TaggedSignal::I8(inner) => Ok(Box::new(FlacCodec::from_signal_with_tag(inner, tag)?)),
+        TaggedSignal::I16(inner) => Ok(Box::new(FlacCodec::from_signal_with_tag(inner, tag)?)),
+        TaggedSignal::I24(inner) => Ok(Box::new(FlacCodec::from_signal_with_tag(inner, tag)?)),
+        TaggedSignal::I32(inner) => Ok(Box::new(FlacCodec::from_signal_with_tag(inner, tag)?)),
+        _ => Err(PhonicError::Unsupported),
+    }
+}
+
+impl CodecTag for FlacCodecTag {
+    fn fill_spec(spec: &mut StreamSpec<Self>) -> Result<(), PhonicError> {
+        fill_flac_spec(spec)
+    }
+}
+
+impl DynCodecConstructor for FlacCodecTag {
+    fn from_signal(
+        &self,
+        signal: TaggedSignal,
+    ) -> Result<Box<dyn DynStream<Tag = Self>>, PhonicError> {
+        flac_codec_from_signal(signal, *self)
+    }
+
+    fn from_stream<S: DynStream<Tag = Self> + 'static>(
+        stream: S,
+    ) -> Result<TaggedSignal, PhonicError> {
+        flac_codec_from_stream(stream)
+    }
+}
+
+impl<T, S: FlacSample, C: CodecTag> FlacCodec<T, S, C> {
+    pub fn from_stream(inner: T) -> Result<Self, PhonicError>
+    where
+        T: Stream<Tag = C>,
+        C: TryInto<FlacCodecTag>,
+    {
+        let mut stream_spec = *inner.spec();
+        fill_flac_spec(&mut stream_spec)?;
+        if stream_spec.sample_type != Some(TypeId::of::<S>()) {
+            return Err(PhonicError::SignalMismatch);
+        }
+
+        let signal_spec = stream_spec.decoded_spec.build()?;
+        let tag = stream_spec
+            .codec
+            .and_then(|codec| codec.try_into().ok())
+            .ok_or(PhonicError::InvalidData)?;
+
+        Self::new(inner, stream_spec, signal_spec, tag)
+    }
+
+    pub fn from_signal(inner: T) -> Result<Self, PhonicError>
+    where
+        T: Signal<Sample = S>,
+        C: TryInto<FlacCodecTag>,
+        FlacCodecTag: TryInto<C>,
+    {
+        Self::from_signal_with_tag(inner, FlacCodecTag::default())
+    }
+
+    /// Creates an encoder that codes the samples of `inner` with the compression level of `tag`.
+    pub fn from_signal_with_tag(inner: T, tag: FlacCodecTag) -> Result<Self, PhonicError>
+    where
+        T: Signal<Sample = S>,
+        C: TryInto<FlacCodecTag>,
+        FlacCodecTag: TryInto<C>,
+    {
+        let signal_spec = *inner.spec();
+        let mut stream_spec = StreamSpec::<C>::from(&inner);
+        stream_spec.codec = Some(tag.try_into().map_err(|_| PhonicError::Unsupported)?);
+        fill_flac_spec(&mut stream_spec)?;
+
+        Self::new(inner, stream_spec, signal_spec, tag)
+    }
+
+    fn new(
+        inner: T,
+        stream_spec: StreamSpec<C>,
+        signal_spec: SignalSpec,
+        tag: FlacCodecTag,
+    ) -> Result<Self, PhonicError> {
+        let n_channels = signal_spec.channels.count() as usize;
+
+        Ok(Self {
+            inner,
+            stream_spec,
+            signal_spec,
+            tag,
+            metadata: None,
+            bytes: ByteBuffer::default(),
+            samples: BlockBuffer::new(0),
+            channels: vec![Vec::new(); n_channels],
+            md5: None,
+            lost_sync: false,
+            writing: false,
+            block_i: 0,
+            frame_i: 0,
+            coded_len: 0,
+            inner_i: 0,
+            position: 0,
+            skip: 0,
+        })
+    }
+
+    pub fn tag(&self) -> &FlacCodecTag {
+        &self.tag
+    }
+
+    /// Returns the metadata that has been read from or written to the stream.
+    pub fn metadata(&self) -> Option<&FlacMetadata> {
+        self.metadata.as_ref()
+    }
+
+    pub fn as_inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn check_stream_info(&self, info: &StreamInfo) -> Result<(), PhonicError> {
+        if info.sample_rate != self.signal_spec.frame_rate
+            || info.n_channels as usize != self.channels.len()
+            || info.bits_per_sample > S::BITS_PER_SAMPLE
+        {
+            return Err(PhonicError::SignalMismatch);
+        }
+
+        Ok(())
+    }
+
+    /// Decodes the metadata or the next block from the gathered bytes, returning `EndOfStream` if
+    /// more bytes are needed.
+    fn decode_next(&mut self) -> Result<(), PhonicError> {
+        let Some(metadata) = &self.metadata else {
+            let mut reader = self.bytes.pending();
+            let metadata = FlacMetadata::read(&mut reader)?;
+            let len = self.bytes.pending().len() - reader.len();
+            self.check_stream_info(&metadata.stream_info)?;
+
+            self.bytes.consume(len);
+            self.metadata = Some(metadata);
+            self.md5 = Some(Md5::new());
+            return Ok(());
+        };
+
+        // anything before the next sync code is skipped, such as tags appended to the file
+        let info = metadata.stream_info;
+        let pending = self.bytes.pending();
+        let sync_i = pending
+            .windows(2)
+            .position(|pair| pair[0] == 0xff && pair[1] & 0xfe == 0xf8);
+
+        match sync_i {
+            Some(0) => {}
+            Some(i) => {
+                self.bytes.consume(i);
+                self.lost_sync = true;
+            }
+            None => {
+                let keep = (pending.last() == Some(&0xff)) as usize;
+                self.bytes.consume(pending.len() - keep);
+                self.lost_sync = true;
+                return Err(PhonicError::EndOfStream);
+            }
+        }
+
+        match decode_block(self.bytes.pending(), &info, &mut self.channels) {
+            Ok((header, len)) => {
+                self.bytes.consume(len);
+                self.lost_sync = false;
+
+                let frame_i = header.frame_i(&info).unwrap_or(self.frame_i);
+                self.output_block(frame_i, &info);
+                Ok(())
+            }
+            Err(PhonicError::InvalidData) if self.lost_sync => {
+                // what looked like a sync code was part of something else
+                self.bytes.consume(1);
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Interleaves the decoded channels into the sample buffer, leaving out any frames past the
+    /// end of the signal.
+    fn output_block(&mut self, frame_i: u64, info: &StreamInfo) {
+        let block_size = self.channels[0].len();
+        self.frame_i = frame_i + block_size as u64;
+
+        if let Some(md5) = &mut self.md5 {
+            let n_bytes = info.bits_per_sample.div_ceil(8) as usize;
+            let mut bytes = Vec::with_capacity(block_size * self.channels.len() * n_bytes);
+            for i in 0..block_size {
+                for channel in self.channels.iter() {
+                    bytes.extend_from_slice(&channel[i].to_le_bytes()[..n_bytes]);
+                }
+            }
+
+            md5.update(&bytes);
+        }
+
+        let mut n_frames = block_size;
+        if let Some(total) = self.signal_spec.n_frames {
+            n_frames = n_frames.min(total.saturating_sub(frame_i) as usize);
+        }
+
+        let shift = S::BITS_PER_SAMPLE - info.bits_per_sample;
+        let n_samples = n_frames * self.channels.len();
+        if self.samples.buf.len() < n_samples {
+            self.samples.buf.resize(n_samples, S::ORIGIN);
+        }
+
+        let frames = self.samples.buf[..n_samples].chunks_exact_mut(self.channels.len());
+        for (i, frame) in frames.enumerate() {
+            for (sample, channel) in frame.iter_mut().zip(self.channels.iter()) {
+                *sample = S::from_i64(channel[i] << shift);
+            }
+        }
+
+        self.samples.range = (0, n_samples);
+    }
+
+    /// Checks the md5 digest of the decoded samples against the stream info, if the whole
+    /// stream was decoded in order.
+    fn finish_decoding(&mut self) -> Result<(), PhonicError> {
+        let Some((md5, metadata)) = self.md5.take().zip(self.metadata.as_ref()) else {
+            return Ok(());
+        };
+
+        let expected = metadata.stream_info.md5;
+        if expected != [0; 16] && md5.finish() != expected {
+            return Err(PhonicError::InvalidData);
+        }
+
+        Ok(())
+    }
+
+    /// Writes the metadata to the byte buffer ahead of the first block.
+    fn start_encoding(&mut self) -> Result<(), PhonicError> {
+        let params = self.tag.encoder_params()?;
+        let info = StreamInfo {
+            min_block_size: params.block_size,
+            max_block_size: params.block_size,
+            min_block_len: 0,
+            max_block_len: 0,
+            sample_rate: self.signal_spec.frame_rate,
+            n_channels: self.channels.len() as u8,
+            bits_per_sample: S::BITS_PER_SAMPLE,
+            n_frames: self.signal_spec.n_frames,
+            md5: [0; 16],
+        };
+
+        let mut metadata = FlacMetadata::new(info);
+        if let Some(n_frames) = self.signal_spec.n_frames {
+            let n_points = n_frames.div_ceil(self.seek_point_spacing(&params));
+            metadata = metadata.with_seek_table(vec![SeekPoint::placeholder(); n_points as usize]);
+        }
+
+        let mut header = Vec::new();
+        metadata.write(&mut header)?;
+        self.bytes.extend(&header);
+        self.coded_len = header.len() as u64;
+
+        let n_channels = self.channels.len();
+        self.samples = BlockBuffer::new(params.block_size as usize * n_channels);
+        self.metadata = Some(metadata);
+        self.md5 = Some(Md5::new());
+
+        Ok(())
+    }
+
+    /// The number of frames between seek points, rounded up to a whole number of blocks.
+    fn seek_point_spacing(&self, params: &EncoderParams) -> u64 {
+        let block_size = params.block_size as u64;
+        let spacing = SEEK_POINT_SPACING * self.signal_spec.frame_rate as u64;
+        spacing.div_ceil(block_size) * block_size
+    }
+
+    /// Encodes the whole frames in the sample buffer as a block.
+    fn encode_block(&mut self) -> Result<(), PhonicError> {
+        let params = self.tag.encoder_params()?;
+        let spacing = self.seek_point_spacing(&params);
+        let Some(metadata) = &mut self.metadata else {
+            return Err(PhonicError::NotReady);
+        };
+
+        let n_channels = self.channels.len();
+        let n_frames = self.samples.pending().len() / n_channels;
+        for (i, channel) in self.channels.iter_mut().enumerate() {
+            channel.clear();
+            channel.extend(
+                self.samples
+                    .pending()
+                    .iter()
+                    .skip(i)
+                    .step_by(n_channels)
+                    .take(n_frames)
+                    .map(|sample| sample.to_i64()),
+            );
+        }
+
+        if let Some(md5) = &mut self.md5 {
+            let n_bytes = S::BITS_PER_SAMPLE as usize / 8;
+            let mut bytes = Vec::with_capacity(n_frames * n_channels * n_bytes);
+            for sample in self.samples.pending()[..n_frames * n_channels].iter() {
+                bytes.extend_from_slice(&sample.to_i64().to_le_bytes()[..n_bytes]);
+            }
+
+            md5.update(&bytes);
+        }
+
+        let info = &mut metadata.stream_info;
+        let mut writer = BitWriter::new();
+        encode_block(&mut writer, self.block_i, info, &self.channels, &params);
+
+        let len = writer.bytes().len() as u32;
+        if self.block_i == 0 {
+            info.min_block_len = len;
+        }
+
+        info.min_block_len = info.min_block_len.min(len);
+        info.max_block_len = info.max_block_len.max(len);
+
+        let point_i = (self.frame_i / spacing) as usize;
+        if self.frame_i.is_multiple_of(spacing) && point_i < metadata.seek_table.len() {
+            metadata.seek_table[point_i] = SeekPoint {
+                frame_i: self.frame_i,
+                byte_offset: self.coded_len - metadata.frames_offset,
+                n_frames: n_frames as u16,
+            };
+        }
+
+        self.bytes.extend(writer.bytes());
+        self.samples.clear();
+        self.coded_len += len as u64;
+        self.frame_i += n_frames as u64;
+        self.block_i += 1;
+
+        Ok(())
+    }
+
+    /// Returns the metadata with the stream info and seek points of everything encoded so far.
+    fn encoded_metadata(&self) -> Option<FlacMetadata> {
+        let mut metadata = self.metadata.clone()?;
+        metadata.stream_info.n_frames = Some(self.frame_i);
+        metadata.stream_info.md5 = self.md5.clone().map_or([0; 16], Md5::finish);
+        Some(metadata)
+    }
+}
+
+impl<T, S: Sample, C: CodecTag> Signal for FlacCodec<T, S, C> {
+    type Sample = S;
+
+    fn spec(&self) -> &SignalSpec {
+        &self.signal_spec
+    }
+}
+
+impl<T: StreamObserver, S: Sample, C: CodecTag> SignalObserver for FlacCodec<T, S, C> {
+    fn position(&self) -> Result<u64, PhonicError> {
+        Ok(self.position)
+    }
+}
+
+impl<T: StreamReader, S: FlacSample, C: CodecTag> FlacCodec<T, S, C> {
+    /// Reads more of the inner stream into the byte buffer, returning false at the end of it.
+    fn read_bytes(&mut self) -> Result<bool, PhonicError> {
+        let len = self
+            .metadata
+            .as_ref()
+            .map_or(0, |metadata| metadata.stream_info.max_block_len as usize)
+            .max(READ_LEN);
+
+        let n = self.bytes.read_from(len, |buf| self.inner.read(buf))?;
+        self.inner_i += n as u64;
+
+        Ok(n > 0)
+    }
+
+    fn read_metadata(&mut self) -> Result<&FlacMetadata, PhonicError> {
+        while self.metadata.is_none() {
+            match self.decode_next() {
+                Ok(()) => {}
+                Err(PhonicError::EndOfStream) if self.read_bytes()? => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        self.metadata.as_ref().ok_or(PhonicError::Unreachable)
+    }
+}
+
+impl<T: StreamReader, S: FlacSample, C: CodecTag> SignalReader for FlacCodec<T, S, C> {
+    fn read(&mut self, buf: &mut [Self::Sample]) -> Result<usize, PhonicError> {
+        loop {
+            // a seek may land part of the way into a block
+            let skip = self.skip.min(self.samples.pending().len() as u64);
+            self.samples.consume(skip as usize);
+            self.skip -= skip;
+
+            if !self.samples.is_empty() {
+                break;
+            }
+
+            match self.decode_next() {
+                Ok(()) => {}
+                Err(PhonicError::EndOfStream) => {
+                    if !self.read_bytes()? {
+                        self.finish_decoding()?;
+                        return Ok(0);
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let n = buf.len().min(self.samples.pending().len());
+        buf[..n].copy_from_slice(&self.samples.pending()[..n]);
+        self.samples.consume(n);
+        self.position += n as u64;
+
+        Ok(n)
+    }
+}
+
+impl<T: StreamWriter, S: FlacSample, C: CodecTag> FlacCodec<T, S, C> {
+    /// Writes the encoded bytes to the inner stream, returning false if it did not accept all of
+    /// them.
+    fn write_bytes(&mut self) -> Result<bool, PhonicError> {
+        while !self.bytes.is_empty() {
+            match self.inner.write(self.bytes.pending())? {
+                0 => return Ok(false),
+                n => {
+                    self.bytes.consume(n);
+                    self.inner_i += n as u64;
+                }
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+impl<T, S, C> SignalWriter for FlacCodec<T, S, C>
+where
+    T: StreamWriter + StreamSeeker,
+    S: FlacSample,
+    C: CodecTag,
+{
+    fn write(&mut self, buf: &[Self::Sample]) -> Result<usize, PhonicError> {
+        if !self.writing {
+            self.start_encoding()?;
+            self.writing = true;
+        }
+
+        if !self.write_bytes()? {
+            return Ok(0);
+        }
+
+        let spare = self.samples.spare_mut();
+        let n = buf.len().min(spare.len());
+        spare[..n].copy_from_slice(&buf[..n]);
+        self.samples.fill(n);
+        self.position += n as u64;
+
+        if self.samples.is_full() {
+            self.encode_block()?;
+            match self.write_bytes() {
+                Ok(_) | Err(PhonicError::NotReady) | Err(PhonicError::Interrupted) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(n)
+    }
+
+    /// Encodes the last block and, if the inner stream can seek, goes back to fill in the
+    /// stream info and seek table.
+    fn flush(&mut self) -> Result<(), PhonicError> {
+        if !self.writing {
+            if self.metadata.is_some() {
+                return self.inner.flush();
+            }
+
+            self.start_encoding()?;
+            self.writing = true;
+        }
+
+        if !self.write_bytes()? {
+            return Err(PhonicError::EndOfStream);
+        }
+
+        if !self.samples.is_empty() {
+            self.encode_block()?;
+            if !self.write_bytes()? {
+                return Err(PhonicError::EndOfStream);
+            }
+        }
+
+        let Some(metadata) = self.encoded_metadata() else {
+            return Err(PhonicError::Unreachable);
+        };
+
+        let mut header = Vec::new();
+        metadata.write(&mut header)?;
+
+        match self.inner.seek(-(self.inner_i as i64)) {
+            Ok(()) => {
+                self.inner.write_exact(&header)?;
+                self.inner.seek(self.inner_i as i64 - header.len() as i64)?;
+            }
+            Err(PhonicError::Unsupported) => {}
+            Err(e) => return Err(e),
+        }
+
+        self.inner.flush()
+    }
+}
+
+impl<T, S, C> SignalSeeker for FlacCodec<T, S, C>
+where
+    T: StreamReader + StreamSeeker,
+    S: FlacSample,
+    C: CodecTag,
+{
+    fn seek(&mut self, offset: i64) -> Result<(), PhonicError> {
+        if self.writing {
+            return Err(PhonicError::Unsupported);
+        }
+
+        let position = self
+            .position
+            .checked_add_signed(offset)
+            .ok_or(PhonicError::InvalidData)?;
+
+        let n_channels = self.channels.len() as u64;
+        let metadata = self.read_metadata()?;
+        let (start_frame, start_byte) = match metadata.seek_point(position / n_channels) {
+            Some(point) => (point.frame_i, metadata.frames_offset + point.byte_offset),
+            None => (0, metadata.frames_offset),
+        };
+
+        // decoding carries on from where it is if that is no further from the target
+        if position >= self.position && start_frame * n_channels <= self.position {
+            self.skip += position - self.position;
+            self.position = position;
+            return Ok(());
+        }
+
+        self.inner.seek(start_byte as i64 - self.inner_i as i64)?;
+        self.inner_i = start_byte;
+        self.bytes.clear();
+        self.samples.clear();
+        self.md5 = None;
+        self.lost_sync = false;
+        self.frame_i = start_frame;
+        self.skip = position - start_frame * n_channels;
+        self.position = position;
+
+        Ok(())
+    }
+}
+
+impl<T, S: Sample, C: CodecTag> Stream for FlacCodec<T, S, C> {
+    type Tag = C;
+
+    fn spec(&self) -> &StreamSpec<Self::Tag> {
+        &self.stream_spec
+    }
+}
+
+impl<T: SignalObserver, S: Sample, C: CodecTag> StreamObserver for FlacCodec<T, S, C> {
+    fn position(&self) -> Result<u64, PhonicError> {
+        Ok(self.position)
+    }
+}
+
+impl<T, S, C> StreamReader for FlacCodec<T, S, C>
+where
+    T: SignalReader<Sample = S>,
+    S: FlacSample,
+    C: CodecTag,
+{
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, PhonicError> {
+        if self.metadata.is_none() {
+            self.start_encoding()?;
+        }
+
+        if self.bytes.is_empty() {
+            while !self.samples.is_full() {
+                match self.inner.read(self.samples.spare_mut())? {
+                    0 => break,
+                    n => {
+                        self.samples.fill(n);
+                        self.inner_i += n as u64;
+                    }
+                }
+            }
+
+            if self.samples.is_empty() {
+                return Ok(0);
+            }
+
+            self.encode_block()?;
+        }
+
+        let n = buf.len().min(self.bytes.pending().len());
+        buf[..n].copy_from_slice(&self.bytes.pending()[..n]);
+        self.bytes.consume(n);
+        self.position += n as u64;
+
+        Ok(n)
+    }
+}
+
+impl<T, S, C> FlacCodec<T, S, C>
+where
+    T: SignalWriter<Sample = S>,
+    S: FlacSample,
+    C: CodecTag,
+{
+    /// Writes the decoded samples to the inner signal, returning false if it did not accept all
+    /// of them.
+    fn write_samples(&mut self) -> Result<bool, PhonicError> {
+        while !self.samples.is_empty() {
+            match self.inner.write(self.samples.pending())? {
+                0 => return Ok(false),
+                n => {
+                    self.samples.consume(n);
+                    self.inner_i += n as u64;
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Decodes as many of the gathered blocks as the inner signal accepts.
+    fn decode_gathered(&mut self) -> Result<bool, PhonicError> {
+        loop {
+            if !self.write_samples()? {
+                return Ok(false);
+            }
+
+            match self.decode_next() {
+                Ok(()) => {}
+                Err(PhonicError::EndOfStream) => return Ok(true),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl<T, S, C> StreamWriter for FlacCodec<T, S, C>
+where
+    T: SignalWriter<Sample = S>,
+    S: FlacSample,
+    C: CodecTag,
+{
+    fn write(&mut self, buf: &[u8]) -> Result<usize, PhonicError> {
+        self.writing = true;
+        if !self.write_samples()? {
+            return Ok(0);
+        }
+
+        self.bytes.extend(buf);
+        self.position += buf.len() as u64;
+
+        match self.decode_gathered() {
+            Ok(_) | Err(PhonicError::NotReady) | Err(PhonicError::Interrupted) => {}
+            Err(e) => return Err(e),
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), PhonicError> {
+        if !self.decode_gathered()? {
+            return Err(PhonicError::EndOfStream);
+        }
+
+        self.finish_decoding()?;
+        self.inner.flush()
+    }
+}
+
+impl<T: SignalSeeker, S: FlacSample, C: CodecTag> StreamSeeker for FlacCodec<T, S, C> {
+    fn seek(&mut self, offset: i64) -> Result<(), PhonicError> {
+        // blocks only have a known position once they have been encoded
+        match offset {
+            0 => Ok(()),
+            _ => Err(PhonicError::Unsupported),
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use crate::FlacCodecTag;
+use std::io::{self, Read, Write};
+use phonic_core::PhonicError;
+use phonic_io_core::{CodecTag, StreamSpec};
+use phonic_signal::{SignalSpecBuilder, I24};
+
+pub const FLAC_MARKER: [u8; 4] = *b"fLaC";
+
+const STREAM_INFO_TYPE: u8 = 0;
+const SEEK_TABLE_TYPE: u8 = 3;
+const INVALID_TYPE: u8 = 127;
+
+const STREAM_INFO_LEN: usize = 34;
+const SEEK_POINT_LEN: usize = 18;
+
+/// The properties of a flac stream, which its first metadata block holds. What flac calls frames
+/// are called blocks here, to tell them apart from the frames of a signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamInfo {
+    /// The fewest frames in a block, not counting the last block of the stream.
+    pub min_block_size: u16,
+    pub max_block_size: u16,
+
+    /// The byte length of the shortest coded block, or zero if it is not known.
+    pub min_block_len: u32,
+    pub max_block_len: u32,
+
+    pub sample_rate: u32,
+    pub n_channels: u8,
+    pub bits_per_sample: u8,
+    pub n_frames: Option<u64>,
+
+    /// The md5 digest of the decoded samples, stored as little endian integers of the smallest
+    /// whole number of bytes that holds `bits_per_sample`. All zeros if it is not known.
+    pub md5: [u8; 16],
+}
+
+/// Points to the block that starts with `frame_i`. The byte offset is counted from the start of
+/// the first block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeekPoint {
+    pub frame_i: u64,
+    pub byte_offset: u64,
+    pub n_frames: u16,
+}
+
+/// The metadata at the start of a flac stream. Blocks other than the stream info and seek table
+/// are skipped over.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlacMetadata {
+    pub stream_info: StreamInfo,
+    pub seek_table: Vec<SeekPoint>,
+
+    /// The byte length of the marker and every metadata block, which is where the first block of
+    /// samples starts.
+    pub frames_offset: u64,
+}
+
+impl StreamInfo {
+    pub fn read(buf: &[u8; STREAM_INFO_LEN]) -> Result<Self, PhonicError> {
+        let packed = u64::from_be_bytes(buf[10..18].try_into().unwrap());
+        let n_frames = packed & 0xf_ffff_ffff;
+
+        let info = Self {
+            min_block_size: u16::from_be_bytes([buf[0], buf[1]]),
+            max_block_size: u16::from_be_bytes([buf[2], buf[3]]),
+            min_block_len: u32::from_be_bytes([0, buf[4], buf[5], buf[6]]),
+            max_block_len: u32::from_be_bytes([0, buf[7], buf[8], buf[9]]),
+            sample_rate: (packed >> 44) as u32,
+            n_channels: ((packed >> 41) & 0x7) as u8 + 1,
+            bits_per_sample: ((packed >> 36) & 0x1f) as u8 + 1,
+            n_frames: (n_frames > 0).then_some(n_frames),
+            md5: buf[18..].try_into().unwrap(),
+        };
+
+        if info.sample_rate == 0 || info.min_block_size > info.max_block_size {
+            return Err(PhonicError::InvalidData);
+        }
+
+        if info.bits_per_sample < 4 {
+            return Err(PhonicError::Unsupported);
+        }
+
+        Ok(info)
+    }
+
+    pub fn write(&self) -> [u8; STREAM_INFO_LEN] {
+        let packed = (self.sample_rate as u64) << 44
+            | ((self.n_channels - 1) as u64) << 41
+            | ((self.bits_per_sample - 1) as u64) << 36
+            | self.n_frames.unwrap_or(0) & 0xf_ffff_ffff;
+
+        let mut buf = [0; STREAM_INFO_LEN];
+        buf[0..2].copy_from_slice(&self.min_block_size.to_be_bytes());
+        buf[2..4].copy_from_slice(&self.max_block_size.to_be_bytes());
+        buf[4..7].copy_from_slice(&self.min_block_len.to_be_bytes()[1..]);
+        buf[7..10].copy_from_slice(&self.max_block_len.to_be_bytes()[1..]);
+        buf[10..18].copy_from_slice(&packed.to_be_bytes());
+        buf[18..].copy_from_slice(&self.md5);
+        buf
+    }
+
+    /// Returns the spec of a stream with these properties. Samples are decoded to the narrowest
+    /// integer type that holds them.
+    pub fn stream_spec<C>(&self) -> StreamSpec<C>
+    where
+        C: CodecTag,
+        FlacCodecTag: TryInto<C>,
+    {
+        let decoded_spec = SignalSpecBuilder::new()
+            .with_frame_rate(self.sample_rate)
+            .with_channels(self.n_channels as u16)
+            .with_n_frames(self.n_frames);
+
+        let spec = StreamSpec {
+            codec: FlacCodecTag::new().try_into().ok(),
+            ..StreamSpec::new()
+        };
+
+        let spec = match self.bits_per_sample.div_ceil(8) {
+            1 => spec.with_sample_type::<i8>(),
+            2 => spec.with_sample_type::<i16>(),
+            3 => spec.with_sample_type::<I24>(),
+            _ => spec.with_sample_type::<i32>(),
+        };
+
+        spec.with_decoded_spec(decoded_spec)
+    }
+}
+
+impl SeekPoint {
+    /// The frame index of a point that has been reserved but not filled in.
+    pub const PLACEHOLDER: u64 = u64::MAX;
+
+    pub fn placeholder() -> Self {
+        Self {
+            frame_i: Self::PLACEHOLDER,
+            byte_offset: 0,
+            n_frames: 0,
+        }
+    }
+
+    pub fn is_placeholder(&self) -> bool {
+        self.frame_i == Self::PLACEHOLDER
+    }
+}
+
+impl FlacMetadata {
+    pub fn new(stream_info: StreamInfo) -> Self {
+        let mut metadata = Self {
+            stream_info,
+            seek_table: Vec::new(),
+            frames_offset: 0,
+        };
+
+        metadata.frames_offset = metadata.byte_len();
+        metadata
+    }
+
+    pub fn with_seek_table(mut self, seek_table: Vec<SeekPoint>) -> Self {
+        self.seek_table = seek_table;
+        self.frames_offset = self.byte_len();
+        self
+    }
+
+    /// The byte length of the metadata as it is written, which only holds the stream info and
+    /// seek table.
+    pub fn byte_len(&self) -> u64 {
+        let mut len = FLAC_MARKER.len() + 4 + STREAM_INFO_LEN;
+        if !self.seek_table.is_empty() {
+            len += 4 + self.seek_table.len() * SEEK_POINT_LEN;
+        }
+
+        len as u64
+    }
+
+    /// Returns the last seek point at or before `frame_i`.
+    pub fn seek_point(&self, frame_i: u64) -> Option<&SeekPoint> {
+        self.seek_table
+            .iter()
+            .filter(|point| !point.is_placeholder() && point.frame_i <= frame_i)
+            .max_by_key(|point| point.frame_i)
+    }
+
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self, PhonicError> {
+        let mut marker = [0; 4];
+        reader.read_exact(&mut marker)?;
+        if marker != FLAC_MARKER {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let mut stream_info = None;
+        let mut seek_table = Vec::new();
+        let mut frames_offset = marker.len() as u64;
+
+        loop {
+            let mut header = [0; 4];
+            reader.read_exact(&mut header)?;
+
+            let is_last = header[0] & 0x80 != 0;
+            let block_type = header[0] & 0x7f;
+            let len = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+            frames_offset += 4 + len as u64;
+
+            match block_type {
+                STREAM_INFO_TYPE if stream_info.is_none() && len == STREAM_INFO_LEN => {
+                    let mut buf = [0; STREAM_INFO_LEN];
+                    reader.read_exact(&mut buf)?;
+                    stream_info = Some(StreamInfo::read(&buf)?);
+                }
+                SEEK_TABLE_TYPE if stream_info.is_some() && len.is_multiple_of(SEEK_POINT_LEN) => {
+                    let mut buf = vec![0; len];
+                    reader.read_exact(&mut buf)?;
+                    seek_table.extend(buf.chunks_exact(SEEK_POINT_LEN).map(|point| SeekPoint {
+                        frame_i: u64::from_be_bytes(point[0..8].try_into().unwrap()),
+                        byte_offset: u64::from_be_bytes(point[8..16].try_into().unwrap()),
+                        n_frames: u16::from_be_bytes([point[16], point[17]]),
+                    }));
+                }
+                STREAM_INFO_TYPE | SEEK_TABLE_TYPE | INVALID_TYPE => {
+                    return Err(PhonicError::InvalidData)
+                }
+                _ if stream_info.is_none() => return Err(PhonicError::InvalidData),
+                _ => {
+                    let skipped = io::copy(&mut reader.take(len as u64), &mut io::sink())?;
+                    if skipped != len as u64 {
+                        return Err(PhonicError::EndOfStream);
+                    }
+                }
+            }
+
+            if is_last {
+                break;
+            }
+        }
+
+        Ok(Self {
+            stream_info: stream_info.ok_or(PhonicError::MissingData)?,
+            seek_table,
+            frames_offset,
+        })
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), PhonicError> {
+        let has_seek_table = !self.seek_table.is_empty();
+
+        writer.write_all(&FLAC_MARKER)?;
+        write_block_header(writer, STREAM_INFO_TYPE, STREAM_INFO_LEN, !has_seek_table)?;
+        writer.write_all(&self.stream_info.write())?;
+
+        if has_seek_table {
+            let len = self.seek_table.len() * SEEK_POINT_LEN;
+            write_block_header(writer, SEEK_TABLE_TYPE, len, true)?;
+
+            for point in self.seek_table.iter() {
+                writer.write_all(&point.frame_i.to_be_bytes())?;
+                writer.write_all(&point.byte_offset.to_be_bytes())?;
+                writer.write_all(&point.n_frames.to_be_bytes())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn write_block_header<W: Write>(
+    writer: &mut W,
+    block_type: u8,
+    len: usize,
+    is_last: bool,
+) -> Result<(), PhonicError> {
+    let len = u32::try_from(len)
+        .ok()
+        .filter(|len| *len < 1 << 24)
+        .ok_or(PhonicError::Unsupported)?;
+
+    let mut header = len.to_be_bytes();
+    header[0] = block_type | if is_last { 0x80 } else { 0 };
+    writer.write_all(&header)?;
+
+    Ok(())
+}
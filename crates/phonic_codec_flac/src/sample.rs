@@ -0,0 +1,42 @@
+use phonic_signal::{Sample, I24};
+
+/// A signed integer sample type that flac streams can be decoded to. Streams with fewer bits per
+/// sample than the type are aligned to its most significant bits.
+pub trait FlacSample: Sample + 'static {
+    const BITS_PER_SAMPLE: u8;
+
+    fn from_i64(value: i64) -> Self;
+    fn to_i64(self) -> i64;
+}
+
+macro_rules! impl_flac_sample {
+    ($($sample:ty),*) => {
+        $(
+            impl FlacSample for $sample {
+                const BITS_PER_SAMPLE: u8 = <$sample>::BITS as u8;
+
+                fn from_i64(value: i64) -> Self {
+                    value as Self
+                }
+
+                fn to_i64(self) -> i64 {
+                    self as i64
+                }
+            }
+        )*
+    };
+}
+
+impl_flac_sample!(i8, i16, i32);
+
+impl FlacSample for I24 {
+    const BITS_PER_SAMPLE: u8 = I24::BITS as u8;
+
+    fn from_i64(value: i64) -> Self {
+        I24::new(value as i32)
+    }
+
+    fn to_i64(self) -> i64 {
+        self.get() as i64
+    }
+}
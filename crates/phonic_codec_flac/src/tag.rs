@@ -0,0 +1,68 @@
+use phonic_core::PhonicError;
+
+/// Identifies a flac stream. The compression level only affects encoding, where higher levels
+/// search harder for a good predictor in exchange for speed. Every level is decoded the same way.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct FlacCodecTag {
+    pub compression_level: u8,
+}
+
+/// The search an encoder does for each block, following the presets of the reference encoder.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct EncoderParams {
+    pub block_size: u16,
+    pub max_lpc_order: usize,
+    pub exhaustive_lpc: bool,
+    pub stereo_decorrelation: bool,
+    pub max_partition_order: u32,
+}
+
+impl FlacCodecTag {
+    pub const DEFAULT_COMPRESSION_LEVEL: u8 = 5;
+    pub const MAX_COMPRESSION_LEVEL: u8 = 8;
+
+    pub fn new() -> Self {
+        Self {
+            compression_level: Self::DEFAULT_COMPRESSION_LEVEL,
+        }
+    }
+
+    pub fn with_compression_level(mut self, level: u8) -> Self {
+        self.compression_level = level;
+        self
+    }
+
+    /// The number of frames an encoder puts in each block.
+    pub fn block_size(&self) -> Result<u16, PhonicError> {
+        self.encoder_params().map(|params| params.block_size)
+    }
+
+    pub(crate) fn encoder_params(&self) -> Result<EncoderParams, PhonicError> {
+        let (block_size, max_lpc_order, stereo_decorrelation, max_partition_order) =
+            match self.compression_level {
+                0 => (1152, 0, false, 3),
+                1 => (1152, 0, true, 3),
+                2 => (1152, 0, true, 4),
+                3 => (4096, 6, false, 4),
+                4 => (4096, 8, true, 4),
+                5 => (4096, 8, true, 5),
+                6 => (4096, 8, true, 6),
+                7 | 8 => (4096, 12, true, 6),
+                _ => return Err(PhonicError::Unsupported),
+            };
+
+        Ok(EncoderParams {
+            block_size,
+            max_lpc_order,
+            exhaustive_lpc: self.compression_level == 8,
+            stereo_decorrelation,
+            max_partition_order,
+        })
+    }
+}
+
+impl Default for FlacCodecTag {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,253 @@
+use phonic_codec_flac::{fill_flac_spec, FlacCodec, FlacCodecTag, FlacMetadata, SeekPoint};
+use phonic_core::PhonicError;
+use phonic_io_core::{test_utils::BufferStream, Stream, StreamReader, StreamSpec, StreamWriter};
+use phonic_signal::{
+    test_utils::BufferSignal, SignalReader, SignalSeeker, SignalSpecBuilder, SignalWriter,
+};
+
+const FRAME_RATE: u32 = 8_000;
+
+fn stream_spec(n_channels: u16) -> StreamSpec<FlacCodecTag> {
+    let decoded_spec = SignalSpecBuilder::new()
+        .with_frame_rate(FRAME_RATE)
+        .with_channels(n_channels);
+
+    StreamSpec::new()
+        .with_codec(FlacCodecTag::new())
+        .with_decoded_spec(decoded_spec)
+}
+
+fn test_signal(n_channels: u16, n_frames: usize) -> Vec<i16> {
+    let mut noise = 0x1234_5678u32;
+    (0..n_frames * n_channels as usize)
+        .map(|i| {
+            noise ^= noise << 13;
+            noise ^= noise >> 17;
+            noise ^= noise << 5;
+
+            let t = (i / n_channels as usize) as f64 / FRAME_RATE as f64;
+            let phase = (i % n_channels as usize) as f64;
+            let tone = (t * 440.0 * std::f64::consts::TAU + phase).sin() * 12_000.0
+                + (t * 1_250.0 * std::f64::consts::TAU).sin() * 4_000.0;
+
+            tone as i16 + (noise % 64) as i16 - 32
+        })
+        .collect()
+}
+
+fn read_to_end<S: Copy + Default>(
+    mut read: impl FnMut(&mut [S]) -> Result<usize, PhonicError>,
+) -> Vec<S> {
+    let mut out = Vec::new();
+    let mut buf = [S::default(); 300];
+    loop {
+        match read(&mut buf).unwrap() {
+            0 => return out,
+            n => out.extend_from_slice(&buf[..n]),
+        }
+    }
+}
+
+/// Encodes through the signal writer, which fills in the metadata once it is flushed.
+fn encode(tag: FlacCodecTag, n_channels: u16, samples: &[i16]) -> Vec<u8> {
+    let mut spec = stream_spec(n_channels).with_codec(tag);
+    spec.decoded_spec.n_frames = Some((samples.len() / n_channels as usize) as u64);
+
+    let mut encoder =
+        FlacCodec::<_, i16>::from_stream(BufferStream::new(spec, Vec::new())).unwrap();
+    encoder.write_exact(samples).unwrap();
+    SignalWriter::flush(&mut encoder).unwrap();
+    encoder.into_inner().into_bytes()
+}
+
+fn decode(n_channels: u16, bytes: Vec<u8>) -> Result<Vec<i16>, PhonicError> {
+    let stream = BufferStream::new(stream_spec(n_channels), bytes);
+    let mut decoder = FlacCodec::<_, i16>::from_stream(stream)?;
+
+    let mut out = Vec::new();
+    let mut buf = [0; 300];
+    loop {
+        match SignalReader::read(&mut decoder, &mut buf)? {
+            0 => return Ok(out),
+            n => out.extend_from_slice(&buf[..n]),
+        }
+    }
+}
+
+#[test]
+fn round_trip_is_lossless() {
+    for level in 0..=FlacCodecTag::MAX_COMPRESSION_LEVEL {
+        let tag = FlacCodecTag::new().with_compression_level(level);
+        for n_channels in [1, 2] {
+            let samples = test_signal(n_channels, 9_000);
+            let bytes = encode(tag, n_channels, &samples);
+            assert!(
+                bytes.len() < samples.len() * 2,
+                "level {level} {n_channels}: {} bytes",
+                bytes.len()
+            );
+
+            let decoded = decode(n_channels, bytes).unwrap();
+            assert_eq!(decoded, samples, "level {level} {n_channels}");
+        }
+    }
+}
+
+#[test]
+fn silence_and_extremes_round_trip() {
+    let tag = FlacCodecTag::new();
+    let mut samples = vec![0; 5_000];
+    samples.extend((0..5_000).map(|i| if i % 2 == 0 { i16::MAX } else { i16::MIN }));
+    samples.extend((0..3_000).map(|i| (i as i16) << 4));
+
+    let bytes = encode(tag, 1, &samples);
+    assert_eq!(decode(1, bytes).unwrap(), samples);
+
+    let bytes = encode(tag, 2, &samples);
+    assert_eq!(decode(2, bytes).unwrap(), samples);
+}
+
+#[test]
+fn metadata_is_filled_on_flush() {
+    let samples: Vec<i16> = (0..1_000).map(|i| i * 23 - 12_000).collect();
+    let bytes = encode(FlacCodecTag::new(), 1, &samples);
+
+    let metadata = FlacMetadata::read(&mut bytes.as_slice()).unwrap();
+    let info = metadata.stream_info;
+    assert_eq!(info.sample_rate, FRAME_RATE);
+    assert_eq!(info.n_channels, 1);
+    assert_eq!(info.bits_per_sample, 16);
+    assert_eq!(info.n_frames, Some(1_000));
+    assert_eq!(info.min_block_size, 4_096);
+    assert!(info.min_block_len > 0 && info.min_block_len <= info.max_block_len);
+
+    let md5 = [
+        0x6b, 0x09, 0x70, 0x3f, 0xf0, 0x05, 0x6b, 0xf9, 0x68, 0xdb, 0xc2, 0x4f, 0xac, 0x3a, 0xbb,
+        0x6a,
+    ];
+    assert_eq!(info.md5, md5);
+
+    assert_eq!(
+        metadata.seek_table,
+        [SeekPoint {
+            frame_i: 0,
+            byte_offset: 0,
+            n_frames: 1_000,
+        }]
+    );
+}
+
+#[test]
+fn mismatched_digest_is_an_error() {
+    let samples = test_signal(2, 2_000);
+    let mut bytes = encode(FlacCodecTag::new(), 2, &samples);
+
+    // the digest takes up the last 16 bytes of the stream info block
+    bytes[8 + 18] ^= 1;
+    assert!(matches!(decode(2, bytes), Err(PhonicError::InvalidData)));
+}
+
+#[test]
+fn junk_before_a_block_is_skipped() {
+    let samples = test_signal(1, 2_000);
+    let bytes = encode(FlacCodecTag::new().with_compression_level(0), 1, &samples);
+    let metadata = FlacMetadata::read(&mut bytes.as_slice()).unwrap();
+
+    let frames_offset = metadata.frames_offset as usize;
+    let mut damaged = bytes[..frames_offset].to_vec();
+    damaged.extend_from_slice(&[0x00, 0x12, 0xff, 0xf8, 0x00, 0xff]);
+    damaged.extend_from_slice(&bytes[frames_offset..]);
+
+    assert_eq!(decode(1, damaged).unwrap(), samples);
+}
+
+#[test]
+fn writers_match_readers() {
+    let samples = test_signal(2, 5_000);
+    let bytes = encode(FlacCodecTag::new(), 2, &samples);
+
+    let signal = BufferSignal::new(FRAME_RATE, 2, Vec::new());
+    let mut decoder = FlacCodec::<_, i16>::from_signal(signal).unwrap();
+    for chunk in bytes.chunks(1_000) {
+        StreamWriter::write(&mut decoder, chunk).unwrap();
+    }
+
+    StreamWriter::flush(&mut decoder).unwrap();
+    assert_eq!(decoder.into_inner().into_samples(), samples);
+
+    // read encoders cannot go back to the metadata, so it is left without a digest
+    let signal = BufferSignal::new(FRAME_RATE, 2, samples.clone()).with_n_frames(Some(5_000));
+    let mut encoder = FlacCodec::<_, i16>::from_signal(signal).unwrap();
+    assert_eq!(Stream::spec(&encoder).codec, Some(FlacCodecTag::new()));
+
+    let encoded = read_to_end(|buf| StreamReader::read(&mut encoder, buf));
+    let metadata = FlacMetadata::read(&mut encoded.as_slice()).unwrap();
+    assert_eq!(metadata.stream_info.md5, [0; 16]);
+    assert_eq!(decode(2, encoded).unwrap(), samples);
+}
+
+#[test]
+fn seeking_uses_the_seek_table() {
+    let n_frames = 25 * FRAME_RATE as usize;
+    let samples = test_signal(1, n_frames);
+    let bytes = encode(FlacCodecTag::new().with_compression_level(1), 1, &samples);
+
+    let metadata = FlacMetadata::read(&mut bytes.as_slice()).unwrap();
+    let frame_is: Vec<u64> = metadata
+        .seek_table
+        .iter()
+        .map(|point| point.frame_i)
+        .collect();
+    assert_eq!(frame_is, [0, 80_640, 161_280]);
+
+    let stream = BufferStream::new(stream_spec(1), bytes.clone());
+    let mut decoder = FlacCodec::<_, i16>::from_stream(stream).unwrap();
+    let mut buf = [0; 50];
+    for target in [170_000, 90_000, 3, 100_000, n_frames as u64 - 50, 80_640] {
+        decoder.set_position(target).unwrap();
+        decoder.read_exact(&mut buf).unwrap();
+
+        let target = target as usize;
+        assert_eq!(buf, samples[target..target + 50], "{target}");
+    }
+
+    let stream = BufferStream::new(stream_spec(1), Vec::new());
+    let mut encoder = FlacCodec::<_, i16>::from_stream(stream).unwrap();
+    encoder.write_exact(&samples[..10]).unwrap();
+    assert!(matches!(
+        SignalSeeker::seek(&mut encoder, 0),
+        Err(PhonicError::Unsupported)
+    ));
+}
+
+#[test]
+fn spec_is_filled() {
+    let codec =
+        FlacCodec::<_, i16>::from_stream(BufferStream::new(stream_spec(2), Vec::new())).unwrap();
+    let spec = Stream::spec(&codec);
+    assert_eq!(spec.sample_type, Some(std::any::TypeId::of::<i16>()));
+
+    let mut spec = stream_spec(1).with_codec(FlacCodecTag::new().with_compression_level(9));
+    assert!(matches!(
+        fill_flac_spec(&mut spec),
+        Err(PhonicError::Unsupported)
+    ));
+
+    let mut spec = stream_spec(1).with_sample_type::<f32>();
+    assert!(matches!(
+        fill_flac_spec(&mut spec),
+        Err(PhonicError::Unsupported)
+    ));
+
+    let mut spec = stream_spec(9);
+    assert!(matches!(
+        fill_flac_spec(&mut spec),
+        Err(PhonicError::Unsupported)
+    ));
+
+    let stream = BufferStream::new(stream_spec(1).with_sample_type::<i8>(), Vec::new());
+    assert!(matches!(
+        FlacCodec::<_, i16>::from_stream(stream),
+        Err(PhonicError::SignalMismatch)
+    ));
+}
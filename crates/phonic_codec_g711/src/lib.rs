@@ -0,0 +1,255 @@
+use std::any::TypeId;
+use phonic_core::PhonicError;
+use phonic_io_core::{
+    utils::TaggedSignal, CodecTag, DynCodecConstructor, DynStream, Stream, StreamObserver,
+    StreamReader, StreamSeeker, StreamSpec, StreamWriter,
+};
+use phonic_signal::{Signal, SignalObserver, SignalReader, SignalSeeker, SignalSpec, SignalWriter};
+
+mod tag;
+
+pub use tag::*;
+
+const BUF_SAMPLES: usize = 1024;
+
+/// Stores 16 bit samples in a single byte each. Both companding laws map every byte to a single
+/// sample, so positions in the signal and the stream are always equal.
+pub struct G711Codec<T, C: CodecTag = G711CodecTag> {
+    inner: T,
+    stream_spec: StreamSpec<C>,
+    signal_spec: SignalSpec,
+    tag: G711CodecTag,
+}
+
+pub fn fill_g711_spec<C>(spec: &mut StreamSpec<C>) -> Result<(), PhonicError>
+where
+    C: CodecTag + TryInto<G711CodecTag>,
+{
+    let codec = spec.codec.ok_or(PhonicError::MissingData)?;
+    codec.try_into().map_err(|_| PhonicError::InvalidData)?;
+
+    let sample_type = TypeId::of::<i16>();
+    if *spec.sample_type.get_or_insert(sample_type) != sample_type {
+        return Err(PhonicError::Unsupported);
+    }
+
+    let calculated_bitrate = spec.decoded_spec.sample_rate().map(|r| 8.0 * r as f64);
+    if calculated_bitrate.is_some_and(|rate| spec.avg_bitrate.get_or_insert(rate) != &rate) {
+        return Err(PhonicError::InvalidData);
+    }
+
+    let calculated_block_align = spec.decoded_spec.channels.map(|c| c.count());
+    if calculated_block_align
+        .is_some_and(|align| !spec.block_align.get_or_insert(align).is_multiple_of(align))
+    {
+        return Err(PhonicError::InvalidData);
+    }
+
+    Ok(())
+}
+
+pub fn g711_codec_from_stream<S>(stream: S) -> Result<TaggedSignal, PhonicError>
+where
+    S: DynStream + 'static,
+    S::Tag: TryInto<G711CodecTag>,
+{
+    Ok(TaggedSignal::I16(Box::new(G711Codec::from_stream(stream)?)))
+}
+
+pub fn g711_codec_from_signal<C>(
+    signal: TaggedSignal,
+    tag: G711CodecTag,
+) -> Result<Box<dyn DynStream<Tag = C>>, PhonicError>
+where
+    C: CodecTag + TryInto<G711CodecTag> + 'static,
+    G711CodecTag: TryInto<C>,
+{
+    let inner = signal.unwrap_i16_signal()?;
+    Ok(Box::new(G711Codec::from_signal(inner, tag)?))
+}
+
+impl CodecTag for G711CodecTag {
+    fn fill_spec(spec: &mut StreamSpec<Self>) -> Result<(), PhonicError> {
+        fill_g711_spec(spec)
+    }
+}
+
+impl DynCodecConstructor for G711CodecTag {
+    fn from_signal(
+        &self,
+        signal: TaggedSignal,
+    ) -> Result<Box<dyn DynStream<Tag = Self>>, PhonicError> {
+        g711_codec_from_signal(signal, *self)
+    }
+
+    fn from_stream<S: DynStream<Tag = Self> + 'static>(
+        stream: S,
+    ) -> Result<TaggedSignal, PhonicError> {
+        g711_codec_from_stream(stream)
+    }
+}
+
+impl<T, C: CodecTag> G711Codec<T, C> {
+    pub fn from_stream(inner: T) -> Result<Self, PhonicError>
+    where
+        T: Stream<Tag = C>,
+        C: TryInto<G711CodecTag>,
+    {
+        let mut stream_spec = *inner.spec();
+        fill_g711_spec(&mut stream_spec)?;
+        let signal_spec = stream_spec.decoded_spec.build()?;
+        let tag = stream_spec
+            .codec
+            .and_then(|codec| codec.try_into().ok())
+            .ok_or(PhonicError::InvalidData)?;
+
+        Ok(Self {
+            inner,
+            stream_spec,
+            signal_spec,
+            tag,
+        })
+    }
+
+    /// Creates an encoder that compands the samples of `inner` with the law described by `tag`.
+    pub fn from_signal(inner: T, tag: G711CodecTag) -> Result<Self, PhonicError>
+    where
+        T: Signal<Sample = i16>,
+        C: TryInto<G711CodecTag>,
+        G711CodecTag: TryInto<C>,
+    {
+        let signal_spec = *inner.spec();
+        let mut stream_spec = StreamSpec::<C>::from(&inner);
+        stream_spec.codec = Some(tag.try_into().map_err(|_| PhonicError::Unsupported)?);
+        fill_g711_spec(&mut stream_spec)?;
+
+        Ok(Self {
+            inner,
+            stream_spec,
+            signal_spec,
+            tag,
+        })
+    }
+
+    pub fn tag(&self) -> &G711CodecTag {
+        &self.tag
+    }
+
+    pub fn as_inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T, C: CodecTag> Signal for G711Codec<T, C> {
+    type Sample = i16;
+
+    fn spec(&self) -> &SignalSpec {
+        &self.signal_spec
+    }
+}
+
+impl<T: StreamObserver, C: CodecTag> SignalObserver for G711Codec<T, C> {
+    fn position(&self) -> Result<u64, PhonicError> {
+        self.inner.position()
+    }
+}
+
+impl<T: StreamReader, C: CodecTag> SignalReader for G711Codec<T, C> {
+    fn read(&mut self, buf: &mut [Self::Sample]) -> Result<usize, PhonicError> {
+        let mut byte_buf = [0u8; BUF_SAMPLES];
+        let len = buf.len().min(BUF_SAMPLES);
+        let n = self.inner.read(&mut byte_buf[..len])?;
+
+        for (sample, byte) in buf.iter_mut().zip(&byte_buf[..n]) {
+            *sample = self.tag.decode(*byte);
+        }
+
+        Ok(n)
+    }
+}
+
+impl<T: StreamWriter, C: CodecTag> SignalWriter for G711Codec<T, C> {
+    fn write(&mut self, buf: &[Self::Sample]) -> Result<usize, PhonicError> {
+        let mut byte_buf = [0u8; BUF_SAMPLES];
+        let len = buf.len().min(BUF_SAMPLES);
+
+        for (byte, sample) in byte_buf.iter_mut().zip(&buf[..len]) {
+            *byte = self.tag.encode(*sample);
+        }
+
+        self.inner.write(&byte_buf[..len])
+    }
+
+    fn flush(&mut self) -> Result<(), PhonicError> {
+        self.inner.flush()
+    }
+}
+
+impl<T: StreamSeeker, C: CodecTag> SignalSeeker for G711Codec<T, C> {
+    fn seek(&mut self, offset: i64) -> Result<(), PhonicError> {
+        self.inner.seek(offset)
+    }
+}
+
+impl<T, C: CodecTag> Stream for G711Codec<T, C> {
+    type Tag = C;
+
+    fn spec(&self) -> &StreamSpec<Self::Tag> {
+        &self.stream_spec
+    }
+}
+
+impl<T: SignalObserver, C: CodecTag> StreamObserver for G711Codec<T, C> {
+    fn position(&self) -> Result<u64, PhonicError> {
+        self.inner.position()
+    }
+}
+
+impl<T, C> StreamReader for G711Codec<T, C>
+where
+    T: SignalReader<Sample = i16>,
+    C: CodecTag,
+{
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, PhonicError> {
+        let mut sample_buf = [0i16; BUF_SAMPLES];
+        let len = buf.len().min(BUF_SAMPLES);
+        let n = self.inner.read(&mut sample_buf[..len])?;
+
+        for (byte, sample) in buf.iter_mut().zip(&sample_buf[..n]) {
+            *byte = self.tag.encode(*sample);
+        }
+
+        Ok(n)
+    }
+}
+
+impl<T, C> StreamWriter for G711Codec<T, C>
+where
+    T: SignalWriter<Sample = i16>,
+    C: CodecTag,
+{
+    fn write(&mut self, buf: &[u8]) -> Result<usize, PhonicError> {
+        let mut sample_buf = [0i16; BUF_SAMPLES];
+        let len = buf.len().min(BUF_SAMPLES);
+
+        for (sample, byte) in sample_buf.iter_mut().zip(&buf[..len]) {
+            *sample = self.tag.decode(*byte);
+        }
+
+        self.inner.write(&sample_buf[..len])
+    }
+
+    fn flush(&mut self) -> Result<(), PhonicError> {
+        self.inner.flush()
+    }
+}
+
+impl<T: SignalSeeker, C: CodecTag> StreamSeeker for G711Codec<T, C> {
+    fn seek(&mut self, offset: i64) -> Result<(), PhonicError> {
+        self.inner.seek(offset)
+    }
+}
@@ -0,0 +1,91 @@
+/// The companding law used to store each sample in a byte, as defined by ITU-T G.711.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum G711CodecTag {
+    ALaw,
+    MuLaw,
+}
+
+const SIGN_BIT: u8 = 0x80;
+const SEGMENT_MASK: u8 = 0x70;
+const QUANT_MASK: u8 = 0x0f;
+
+const ALAW_INVERT: u8 = 0x55;
+
+const MULAW_BIAS: i16 = 0x84;
+const MULAW_CLIP: i16 = 0x1fff - (MULAW_BIAS >> 2);
+
+impl G711CodecTag {
+    pub fn encode(self, sample: i16) -> u8 {
+        match self {
+            Self::ALaw => encode_alaw(sample),
+            Self::MuLaw => encode_mulaw(sample),
+        }
+    }
+
+    pub fn decode(self, byte: u8) -> i16 {
+        match self {
+            Self::ALaw => decode_alaw(byte),
+            Self::MuLaw => decode_mulaw(byte),
+        }
+    }
+}
+
+/// The segment of a magnitude, where each segment after the first doubles the step size.
+fn segment(magnitude: i16, first_bits: u32) -> u8 {
+    let bits = i16::BITS - magnitude.leading_zeros();
+    bits.saturating_sub(first_bits) as u8
+}
+
+fn encode_alaw(sample: i16) -> u8 {
+    // a-law has 13 bits of precision, and negative values are stored in one's complement
+    let (sign, magnitude) = match sample >> 3 {
+        s if s >= 0 => (SIGN_BIT, s),
+        s => (0, !s),
+    };
+
+    let segment = segment(magnitude, 5);
+    let shift = segment.max(1);
+    let quant = (magnitude >> shift) as u8 & QUANT_MASK;
+
+    (sign | segment << 4 | quant) ^ ALAW_INVERT
+}
+
+fn decode_alaw(byte: u8) -> i16 {
+    let byte = byte ^ ALAW_INVERT;
+    let segment = (byte & SEGMENT_MASK) >> 4;
+    let mut magnitude = ((byte & QUANT_MASK) as i16) << 4 | 0x08;
+
+    if segment > 0 {
+        magnitude = (magnitude + 0x100) << (segment - 1);
+    }
+
+    match byte & SIGN_BIT {
+        0 => -magnitude,
+        _ => magnitude,
+    }
+}
+
+fn encode_mulaw(sample: i16) -> u8 {
+    // µ-law has 14 bits of precision, and is biased so that every segment starts at a power of 2
+    let (sign, magnitude) = match sample >> 2 {
+        s if s >= 0 => (0, s),
+        s => (SIGN_BIT, -s),
+    };
+
+    let magnitude = magnitude.min(MULAW_CLIP) + (MULAW_BIAS >> 2);
+    let segment = segment(magnitude, 6);
+    let quant = (magnitude >> (segment + 1)) as u8 & QUANT_MASK;
+
+    !(sign | segment << 4 | quant)
+}
+
+fn decode_mulaw(byte: u8) -> i16 {
+    let byte = !byte;
+    let segment = (byte & SEGMENT_MASK) >> 4;
+    let magnitude = ((((byte & QUANT_MASK) as i16) << 3) + MULAW_BIAS) << segment;
+
+    match byte & SIGN_BIT {
+        0 => magnitude - MULAW_BIAS,
+        _ => MULAW_BIAS - magnitude,
+    }
+}
@@ -0,0 +1,136 @@
+use phonic_codec_g711::{fill_g711_spec, G711Codec, G711CodecTag};
+use phonic_core::PhonicError;
+use phonic_io_core::{test_utils::BufferStream, Stream, StreamReader, StreamSpec, StreamWriter};
+use phonic_signal::{test_utils::BufferSignal, SignalReader, SignalSpecBuilder, SignalWriter};
+
+const TAGS: [G711CodecTag; 2] = [G711CodecTag::ALaw, G711CodecTag::MuLaw];
+
+fn stream_spec(tag: G711CodecTag) -> StreamSpec<G711CodecTag> {
+    let signal_spec = SignalSpecBuilder::new()
+        .with_frame_rate(8_000)
+        .with_channels(2);
+
+    StreamSpec::new()
+        .with_codec(tag)
+        .with_decoded_spec(signal_spec)
+}
+
+#[test]
+fn reference_values() {
+    assert_eq!(G711CodecTag::ALaw.encode(0), 0xd5);
+    assert_eq!(G711CodecTag::ALaw.decode(0xd5), 8);
+    assert_eq!(G711CodecTag::ALaw.decode(0x55), -8);
+    assert_eq!(G711CodecTag::ALaw.decode(0xaa), 32_256);
+    assert_eq!(G711CodecTag::ALaw.decode(0x2a), -32_256);
+    assert_eq!(G711CodecTag::ALaw.encode(i16::MAX), 0xaa);
+    assert_eq!(G711CodecTag::ALaw.encode(i16::MIN), 0x2a);
+
+    assert_eq!(G711CodecTag::MuLaw.encode(0), 0xff);
+    assert_eq!(G711CodecTag::MuLaw.decode(0xff), 0);
+    assert_eq!(G711CodecTag::MuLaw.decode(0x7f), 0);
+    assert_eq!(G711CodecTag::MuLaw.decode(0x80), 32_124);
+    assert_eq!(G711CodecTag::MuLaw.decode(0x00), -32_124);
+    assert_eq!(G711CodecTag::MuLaw.encode(i16::MAX), 0x80);
+    assert_eq!(G711CodecTag::MuLaw.encode(i16::MIN), 0x00);
+}
+
+#[test]
+fn every_code_round_trips() {
+    for tag in TAGS {
+        for byte in 0..=u8::MAX {
+            // µ-law has a negative zero, which is encoded as a positive one
+            let expected = match (tag, byte) {
+                (G711CodecTag::MuLaw, 0x7f) => 0xff,
+                _ => byte,
+            };
+
+            assert_eq!(
+                tag.encode(tag.decode(byte)),
+                expected,
+                "{tag:?} {byte:#04x}"
+            );
+        }
+    }
+}
+
+#[test]
+fn quantization_error_is_bounded() {
+    for tag in TAGS {
+        let mut prev = i16::MIN;
+        for sample in i16::MIN..=i16::MAX {
+            let decoded = tag.decode(tag.encode(sample));
+            let error = (decoded as i32 - sample as i32).abs();
+            let bound = (sample as i32).abs() / 32 + 16;
+
+            assert!(error <= bound, "{tag:?} {sample} -> {decoded}");
+            assert!(decoded >= prev, "{tag:?} {sample} is not monotonic");
+            prev = decoded;
+        }
+    }
+}
+
+#[test]
+fn codec_round_trip() {
+    let samples: Vec<i16> = (0..3_000).map(|i| (i as i16).wrapping_mul(4_099)).collect();
+
+    for tag in TAGS {
+        let bytes: Vec<u8> = samples.iter().map(|s| tag.encode(*s)).collect();
+        let decoded: Vec<i16> = bytes.iter().map(|b| tag.decode(*b)).collect();
+
+        let signal = BufferSignal::new(8_000, 2, samples.clone());
+        let mut encoder = G711Codec::<_>::from_signal(signal, tag).unwrap();
+        let mut encoded = vec![0; bytes.len()];
+        encoder.read_exact(&mut encoded).unwrap();
+        assert_eq!(encoded, bytes, "{tag:?} encode");
+
+        let stream = BufferStream::new(stream_spec(tag), bytes.clone());
+        let mut decoder = G711Codec::from_stream(stream).unwrap();
+        let mut output = vec![0; decoded.len()];
+        decoder.read_exact(&mut output).unwrap();
+        assert_eq!(output, decoded, "{tag:?} decode");
+
+        let stream = BufferStream::new(stream_spec(tag), Vec::new());
+        let mut decoder = G711Codec::from_stream(stream).unwrap();
+        decoder.write_exact(&samples).unwrap();
+        assert_eq!(
+            decoder.into_inner().into_bytes(),
+            bytes,
+            "{tag:?} decoder write"
+        );
+
+        let signal = BufferSignal::new(8_000, 2, Vec::new());
+        let mut encoder = G711Codec::<_>::from_signal(signal, tag).unwrap();
+        encoder.write_exact(&bytes).unwrap();
+        assert_eq!(
+            encoder.into_inner().into_samples(),
+            decoded,
+            "{tag:?} encoder write"
+        );
+    }
+}
+
+#[test]
+fn spec_is_filled() {
+    let stream = BufferStream::new(stream_spec(G711CodecTag::MuLaw), Vec::new());
+    let codec = G711Codec::from_stream(stream).unwrap();
+    let spec = Stream::spec(&codec);
+
+    assert_eq!(spec.avg_bitrate, Some(128_000.0));
+    assert_eq!(spec.block_align, Some(2));
+    assert_eq!(spec.sample_type, Some(std::any::TypeId::of::<i16>()));
+
+    let mut spec = StreamSpec::<G711CodecTag>::new()
+        .with_codec(G711CodecTag::ALaw)
+        .with_sample_type::<f32>();
+
+    assert!(matches!(
+        fill_g711_spec(&mut spec),
+        Err(PhonicError::Unsupported)
+    ));
+
+    let mut spec = StreamSpec::<G711CodecTag>::new();
+    assert!(matches!(
+        fill_g711_spec(&mut spec),
+        Err(PhonicError::MissingData)
+    ));
+}
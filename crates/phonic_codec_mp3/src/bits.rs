@@ -0,0 +1,67 @@
+/// Reads big endian bit fields from a byte slice. Bits past the end of the slice read as zeros,
+/// so a granule whose coded length overruns its data decodes the way the reference decoder does,
+/// and callers compare the position against their own limits instead.
+pub(crate) struct BitReader<'a> {
+    buf: &'a [u8],
+    bit_i: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf, bit_i: 0 }
+    }
+
+    pub fn position(&self) -> usize {
+        self.bit_i
+    }
+
+    pub fn set_position(&mut self, bit_i: usize) {
+        self.bit_i = bit_i;
+    }
+
+    pub fn len(&self) -> usize {
+        self.buf.len() * 8
+    }
+
+    /// Returns the next `n_bits` bits without moving past them. At most 32 bits can be peeked.
+    pub fn peek(&self, n_bits: u32) -> u32 {
+        if n_bits == 0 {
+            return 0;
+        }
+
+        let byte_i = self.bit_i / 8;
+        let mut window = 0u64;
+        for i in 0..5 {
+            let byte = self.buf.get(byte_i + i).copied().unwrap_or(0);
+            window |= (byte as u64) << (56 - 8 * i);
+        }
+
+        ((window << (self.bit_i % 8)) >> (64 - n_bits)) as u32
+    }
+
+    pub fn skip(&mut self, n_bits: u32) {
+        self.bit_i += n_bits as usize;
+    }
+
+    pub fn read(&mut self, n_bits: u32) -> u32 {
+        let value = self.peek(n_bits);
+        self.skip(n_bits);
+        value
+    }
+
+    /// Reads a field that has to fit in the slice as a whole, returning 0 for one that runs past
+    /// the end. Scalefactors are read this way, where the reference decoder drops a partial field.
+    pub fn read_whole(&mut self, n_bits: u32) -> u32 {
+        let fits = self.bit_i + n_bits as usize <= self.len();
+        let value = self.read(n_bits);
+        if fits {
+            value
+        } else {
+            0
+        }
+    }
+
+    pub fn read_bit(&mut self) -> bool {
+        self.read(1) == 1
+    }
+}
@@ -0,0 +1,147 @@
+use crate::{
+    bits::BitReader,
+    header::FrameHeader,
+    huffman::read_lines,
+    hybrid::{hybrid, Overlap},
+    scalefactors::read_scalefactors,
+    side_info::SideInfo,
+    stereo,
+    synthesis::{synth_granule, QmfState},
+};
+use phonic_core::PhonicError;
+
+/// The most bytes of main data a frame can take from the ones before it.
+const MAX_RESERVOIR_LEN: usize = 511;
+
+/// Decodes layer III frames into interleaved samples. The decoder carries the bit reservoir and
+/// the state of its filterbanks from frame to frame, so frames must be decoded in order.
+pub struct Mp3Decoder {
+    reservoir: Vec<u8>,
+    overlap: [Overlap; 2],
+    qmf_state: QmfState,
+}
+
+impl Mp3Decoder {
+    /// The most samples a frame decodes to, which is 1152 frames of two channels.
+    pub const MAX_FRAME_SAMPLES: usize = 1152 * 2;
+
+    pub fn new() -> Self {
+        Self {
+            reservoir: Vec::with_capacity(MAX_RESERVOIR_LEN),
+            overlap: [[0.0; 9 * 32]; 2],
+            qmf_state: [0.0; 15 * 64],
+        }
+    }
+
+    /// Forgets the frames decoded so far, as if the next frame started the stream.
+    pub fn reset(&mut self) {
+        self.reservoir.clear();
+        self.overlap = [[0.0; 9 * 32]; 2];
+        self.qmf_state = [0.0; 15 * 64];
+    }
+
+    /// Decodes the frame at the start of `frame` into `pcm`, and returns the number of samples
+    /// written. A frame whose main data starts in a reservoir the decoder never saw, which
+    /// happens after a seek, decodes to silence, as does a frame whose side info is damaged.
+    pub fn decode(&mut self, frame: &[u8], pcm: &mut [f32]) -> Result<usize, PhonicError> {
+        let header = FrameHeader::read(frame)?;
+        if header.layer != 3 {
+            return Err(PhonicError::Unsupported);
+        }
+
+        let frame_len = header.frame_len().ok_or(PhonicError::Unsupported)?;
+        let n_channels = header.n_channels();
+        let n_samples = header.n_frames() * n_channels;
+        if frame.len() < frame_len || pcm.len() < n_samples {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let payload_i = FrameHeader::LEN + if header.protected { 2 } else { 0 };
+        let payload = &frame[payload_i..frame_len];
+        let pcm = &mut pcm[..n_samples];
+
+        let mut bits = BitReader::new(payload);
+        let Ok(side_info) = SideInfo::read(&mut bits, &header) else {
+            self.reset();
+            pcm.fill(0.0);
+            return Ok(n_samples);
+        };
+
+        // the main data starts in the reservoir of the frames before, and carries on after the
+        // side info of this one
+        let main_data_begin = side_info.main_data_begin;
+        let reservoir_i = self.reservoir.len().saturating_sub(main_data_begin);
+        let mut main_data = self.reservoir[reservoir_i..].to_vec();
+        main_data.extend_from_slice(&payload[header.side_info_len()..]);
+
+        let mut bits = BitReader::new(&main_data);
+        match self.reservoir.len() >= main_data_begin {
+            true => self.decode_granules(&mut bits, &header, &side_info, pcm),
+            false => pcm.fill(0.0),
+        }
+
+        let main_data_i = bits.position().div_ceil(8);
+        let saved_i = main_data_i.max(main_data.len().saturating_sub(MAX_RESERVOIR_LEN));
+        self.reservoir.clear();
+        if let Some(saved) = main_data.get(saved_i..) {
+            self.reservoir.extend_from_slice(saved);
+        }
+
+        Ok(n_samples)
+    }
+
+    fn decode_granules(
+        &mut self,
+        bits: &mut BitReader,
+        header: &FrameHeader,
+        side_info: &SideInfo,
+        pcm: &mut [f32],
+    ) {
+        let n_channels = header.n_channels();
+        let n_granules = if header.is_lsf() { 1 } else { 2 };
+        let mut ist_pos = [[0u8; 39]; 2];
+        for gr_i in 0..n_granules {
+            let granules = &side_info.granules[gr_i * n_channels..(gr_i + 1) * n_channels];
+            let mut lines = [[0.0f32; 576]; 2];
+            for (ch, granule) in granules.iter().enumerate() {
+                let limit = bits.position() + granule.part2_3_len;
+                let mut gains = [0.0f32; 40];
+                read_scalefactors(bits, header, granule, ch, &mut ist_pos[ch], &mut gains);
+                read_lines(bits, granule, &gains, &mut lines[ch], limit);
+            }
+
+            if header.is_intensity_stereo() {
+                let right_compress = granules[1].scalefac_compress;
+                stereo::intensity(
+                    &mut lines,
+                    &mut ist_pos[1],
+                    &granules[0],
+                    right_compress,
+                    header,
+                );
+            } else if header.is_ms_stereo() {
+                let [left, right] = &mut lines;
+                stereo::midside(left, right);
+            }
+
+            for (ch, granule) in granules.iter().enumerate() {
+                hybrid(
+                    &mut lines[ch],
+                    &mut self.overlap[ch],
+                    granule,
+                    header.rate_index(),
+                );
+            }
+
+            let n_granule_samples = 576 * n_channels;
+            let pcm = &mut pcm[gr_i * n_granule_samples..(gr_i + 1) * n_granule_samples];
+            synth_granule(&mut self.qmf_state, &mut lines, n_channels, pcm);
+        }
+    }
+}
+
+impl Default for Mp3Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
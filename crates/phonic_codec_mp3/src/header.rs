@@ -0,0 +1,206 @@
+use phonic_core::PhonicError;
+
+/// The bitrates of each bitrate index in kbit/s, for layers I, II and III of MPEG-1, then for
+/// layer I and for layers II and III of the lower sampling frequencies. Index 0 is the free
+/// format, which has no fixed frame length.
+const BITRATES: [[u16; 15]; 5] = [
+    [
+        0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448,
+    ],
+    [
+        0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384,
+    ],
+    [
+        0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+    ],
+    [
+        0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256,
+    ],
+    [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160],
+];
+
+const FRAME_RATES: [u32; 3] = [44_100, 48_000, 32_000];
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum MpegVersion {
+    Mpeg1,
+    Mpeg2,
+    /// The unofficial extension of MPEG-2 to the lowest sampling frequencies.
+    Mpeg25,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum ChannelMode {
+    Stereo,
+    /// Stereo where the mode extension picks mid/side and intensity coding.
+    JointStereo,
+    DualChannel,
+    Mono,
+}
+
+/// The four byte header that starts every frame, following an 11 bit sync word.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct FrameHeader {
+    pub version: MpegVersion,
+    pub layer: u8,
+    /// Whether a 16 bit checksum follows the header.
+    pub protected: bool,
+    pub bitrate_index: u8,
+    pub frame_rate: u32,
+    pub padding: bool,
+    pub channel_mode: ChannelMode,
+    pub mode_extension: u8,
+}
+
+impl FrameHeader {
+    pub const LEN: usize = 4;
+
+    pub fn read(bytes: &[u8]) -> Result<Self, PhonicError> {
+        let &[b0, b1, b2, b3, ..] = bytes else {
+            return Err(PhonicError::InvalidData);
+        };
+
+        if b0 != 0xff || b1 & 0xe0 != 0xe0 {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let version = match (b1 >> 3) & 3 {
+            0 => MpegVersion::Mpeg25,
+            2 => MpegVersion::Mpeg2,
+            3 => MpegVersion::Mpeg1,
+            _ => return Err(PhonicError::InvalidData),
+        };
+
+        let layer = match (b1 >> 1) & 3 {
+            0 => return Err(PhonicError::InvalidData),
+            layer_bits => 4 - layer_bits,
+        };
+
+        // the extension to lower sampling frequencies only covers layer III
+        if version == MpegVersion::Mpeg25 && layer != 3 {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let bitrate_index = b2 >> 4;
+        let rate_index = (b2 >> 2) & 3;
+        if bitrate_index == 15 || rate_index == 3 {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let frame_rate = match version {
+            MpegVersion::Mpeg1 => FRAME_RATES[rate_index as usize],
+            MpegVersion::Mpeg2 => FRAME_RATES[rate_index as usize] / 2,
+            MpegVersion::Mpeg25 => FRAME_RATES[rate_index as usize] / 4,
+        };
+
+        let channel_mode = match b3 >> 6 {
+            0 => ChannelMode::Stereo,
+            1 => ChannelMode::JointStereo,
+            2 => ChannelMode::DualChannel,
+            _ => ChannelMode::Mono,
+        };
+
+        Ok(Self {
+            version,
+            layer,
+            protected: b1 & 1 == 0,
+            bitrate_index,
+            frame_rate,
+            padding: b2 & 2 != 0,
+            channel_mode,
+            mode_extension: (b3 >> 4) & 3,
+        })
+    }
+
+    /// Whether a frame with this header can follow a frame with the other in the same stream.
+    /// The version, layer and frame rate are fixed for a whole stream, and so is the use of the
+    /// free format.
+    pub fn is_compatible(&self, other: &Self) -> bool {
+        self.version == other.version
+            && self.layer == other.layer
+            && self.frame_rate == other.frame_rate
+            && (self.bitrate_index == 0) == (other.bitrate_index == 0)
+    }
+
+    /// Whether the frame uses one of the lower sampling frequencies of MPEG-2 and MPEG-2.5.
+    pub fn is_lsf(&self) -> bool {
+        self.version != MpegVersion::Mpeg1
+    }
+
+    /// The position of the frame rate among the nine rates, from the MPEG-2.5 rates at 0 to the
+    /// MPEG-1 rates at 6, which picks the scalefactor bands.
+    pub(crate) fn rate_index(&self) -> usize {
+        let (scale, offset) = match self.version {
+            MpegVersion::Mpeg25 => (4, 0),
+            MpegVersion::Mpeg2 => (2, 3),
+            MpegVersion::Mpeg1 => (1, 6),
+        };
+
+        let base_rate = self.frame_rate * scale;
+        offset
+            + FRAME_RATES
+                .iter()
+                .position(|&rate| rate == base_rate)
+                .unwrap_or(0)
+    }
+
+    pub fn n_channels(&self) -> usize {
+        match self.channel_mode {
+            ChannelMode::Mono => 1,
+            _ => 2,
+        }
+    }
+
+    /// The number of frames of samples that a frame holds.
+    pub fn n_frames(&self) -> usize {
+        match (self.layer, self.version) {
+            (1, _) => 384,
+            (2, _) | (3, MpegVersion::Mpeg1) => 1152,
+            _ => 576,
+        }
+    }
+
+    /// The bitrate in kbit/s, or 0 for the free format.
+    pub fn bitrate(&self) -> u32 {
+        let row = match (self.is_lsf(), self.layer) {
+            (false, layer) => layer as usize - 1,
+            (true, 1) => 3,
+            (true, _) => 4,
+        };
+
+        BITRATES[row][self.bitrate_index as usize] as u32
+    }
+
+    /// The length of the whole frame in bytes, including the header, or `None` for the free
+    /// format, where it can only be found from the distance to the next frame.
+    pub fn frame_len(&self) -> Option<usize> {
+        let bytes_per_second = self.bitrate() as usize * 125;
+        if bytes_per_second == 0 {
+            return None;
+        }
+
+        let len = self.n_frames() * bytes_per_second / self.frame_rate as usize;
+        Some(match self.layer {
+            1 => (len & !3) + 4 * self.padding as usize,
+            _ => len + self.padding as usize,
+        })
+    }
+
+    /// The length of the layer III side info that follows the header and checksum.
+    pub fn side_info_len(&self) -> usize {
+        match (self.is_lsf(), self.n_channels()) {
+            (false, 1) => 17,
+            (false, _) => 32,
+            (true, 1) => 9,
+            (true, _) => 17,
+        }
+    }
+
+    pub fn is_ms_stereo(&self) -> bool {
+        self.channel_mode == ChannelMode::JointStereo && self.mode_extension & 2 != 0
+    }
+
+    pub fn is_intensity_stereo(&self) -> bool {
+        self.channel_mode == ChannelMode::JointStereo && self.mode_extension & 1 != 0
+    }
+}
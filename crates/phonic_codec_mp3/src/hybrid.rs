@@ -0,0 +1,305 @@
+use crate::side_info::{GranuleInfo, SHORT_BLOCK, STOP_BLOCK};
+
+/// The butterfly coefficients of the alias reduction between neighbouring subbands.
+static ANTIALIAS: [[f32; 8]; 2] = [
+    [
+        0.857_492_9,
+        0.881_742,
+        0.949_628_65,
+        0.983_314_6,
+        0.995_517_8,
+        0.999_160_6,
+        0.999_899_2,
+        0.999_993_15,
+    ],
+    [
+        0.514_495_73,
+        0.471_731_96,
+        0.313_377_44,
+        0.181_913_2,
+        0.094_574_19,
+        0.040_965_58,
+        0.014_198_56,
+        0.003_699_97,
+    ],
+];
+
+static TWIDDLE_9: [f32; 18] = [
+    0.737_277_3,
+    0.793_353_3,
+    0.843_391_5,
+    0.887_010_8,
+    0.923_879_5,
+    0.953_716_93,
+    0.976_296,
+    0.991_444_9,
+    0.999_048_23,
+    0.675_590_2,
+    0.608_761_4,
+    0.537_299_63,
+    0.461_748_6,
+    0.382_683_43,
+    0.300_705_8,
+    0.216_439_6,
+    0.130_526_19,
+    0.043_619_38,
+];
+
+static TWIDDLE_3: [f32; 6] = [
+    0.793_353_3,
+    0.923_879_5,
+    0.991_444_9,
+    0.608_761_4,
+    0.382_683_43,
+    0.130_526_19,
+];
+
+/// The windows of long blocks, and of the stop blocks that lead back to them from short blocks.
+static MDCT_WINDOWS: [[f32; 18]; 2] = [
+    [
+        0.999_048_23,
+        0.991_444_9,
+        0.976_296,
+        0.953_716_93,
+        0.923_879_5,
+        0.887_010_8,
+        0.843_391_5,
+        0.793_353_3,
+        0.737_277_3,
+        0.043_619_38,
+        0.130_526_19,
+        0.216_439_6,
+        0.300_705_8,
+        0.382_683_43,
+        0.461_748_6,
+        0.537_299_63,
+        0.608_761_4,
+        0.675_590_2,
+    ],
+    [
+        1.0,
+        1.0,
+        1.0,
+        1.0,
+        1.0,
+        1.0,
+        0.991_444_9,
+        0.923_879_5,
+        0.793_353_3,
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        0.130_526_19,
+        0.382_683_43,
+        0.608_761_4,
+    ],
+];
+
+/// The state one channel carries from granule to granule, which is the second half of the
+/// output of each subband's inverse transform.
+pub(crate) type Overlap = [f32; 9 * 32];
+
+/// Turns the decoded lines of one channel of a granule into the 18 samples of each of its 32
+/// subbands, laid out subband after subband.
+pub(crate) fn hybrid(
+    lines: &mut [f32; 576],
+    overlap: &mut Overlap,
+    granule: &GranuleInfo,
+    rate_index: usize,
+) {
+    // the lowest 8 kHz rate fits its 36 long lines into four subbands instead of two
+    let n_long_bands: usize = match granule.mixed_block {
+        true => 2 << (rate_index == 2) as usize,
+        false => 0,
+    };
+
+    let mut n_antialias_bands = 31;
+    if granule.n_short_sfb != 0 {
+        n_antialias_bands = n_long_bands.saturating_sub(1);
+        reorder(
+            &mut lines[n_long_bands * 18..],
+            &granule.sfb_widths[granule.n_long_sfb..],
+        );
+    }
+
+    antialias(lines, n_antialias_bands);
+    imdct(lines, overlap, granule.block_type, n_long_bands);
+    change_sign(lines);
+}
+
+/// Interleaves the windows of the short bands, which are coded one window after the other in each
+/// band, so that the lines of each subband sit together. The mixed bands of the lowest rate reach
+/// past the end of the granule, and the bands that don't fit are left as they are.
+fn reorder(lines: &mut [f32], widths: &[u8]) {
+    let mut scratch = [0.0f32; 576];
+    let mut src_i = 0;
+    let mut dst_i = 0;
+    for &width in widths.iter().step_by(3).take_while(|&&width| width != 0) {
+        let width = width as usize;
+        if src_i + 3 * width > lines.len() {
+            break;
+        }
+
+        for i in src_i..src_i + width {
+            scratch[dst_i] = lines[i];
+            scratch[dst_i + 1] = lines[i + width];
+            scratch[dst_i + 2] = lines[i + 2 * width];
+            dst_i += 3;
+        }
+
+        src_i += 3 * width;
+    }
+
+    lines[..dst_i].copy_from_slice(&scratch[..dst_i]);
+}
+
+/// Undoes the aliasing between each of the first `n_bands` subbands and the one above it, with
+/// butterflies across the boundary of the two.
+fn antialias(lines: &mut [f32], n_bands: usize) {
+    for band_i in 0..n_bands {
+        let lines = &mut lines[band_i * 18..];
+        for i in 0..8 {
+            let u = lines[18 + i];
+            let d = lines[17 - i];
+            lines[18 + i] = u * ANTIALIAS[0][i] - d * ANTIALIAS[1][i];
+            lines[17 - i] = u * ANTIALIAS[1][i] + d * ANTIALIAS[0][i];
+        }
+    }
+}
+
+fn dct3_9(y: &mut [f32; 9]) {
+    let (mut s0, mut s2, mut s4, mut s6, mut s8) = (y[0], y[2], y[4], y[6], y[8]);
+    let mut t0 = s0 + s6 * 0.5;
+    s0 -= s6;
+    let mut t4 = (s4 + s2) * 0.939_692_6;
+    let mut t2 = (s8 + s2) * 0.766_044_44;
+    s6 = (s4 - s8) * 0.173_648_18;
+    s4 += s8 - s2;
+
+    s2 = s0 - s4 * 0.5;
+    y[4] = s4 + s0;
+    s8 = t0 - t2 + s6;
+    s0 = t0 - t4 + t2;
+    s4 = t0 + t4 - s6;
+
+    let (mut s1, mut s3, mut s5, mut s7) = (y[1], y[3], y[5], y[7]);
+    s3 *= 0.866_025_4;
+    t0 = (s5 + s1) * 0.984_807_7;
+    t4 = (s5 - s7) * 0.342_020_15;
+    t2 = (s1 + s7) * 0.642_787_64;
+    s1 = (s1 - s5 - s7) * 0.866_025_4;
+
+    s5 = t0 - s3 - t2;
+    s7 = t4 - s3 - t0;
+    s3 = t4 + s3 - t2;
+
+    y[0] = s4 - s7;
+    y[1] = s2 + s1;
+    y[2] = s0 - s3;
+    y[3] = s8 + s5;
+    y[5] = s8 - s5;
+    y[6] = s0 + s3;
+    y[7] = s2 - s1;
+    y[8] = s4 + s7;
+}
+
+fn imdct36(lines: &mut [f32], overlap: &mut [f32], window: &[f32; 18], n_bands: usize) {
+    for (band, overlap) in lines
+        .chunks_exact_mut(18)
+        .zip(overlap.chunks_exact_mut(9))
+        .take(n_bands)
+    {
+        let mut co = [0.0f32; 9];
+        let mut si = [0.0f32; 9];
+        co[0] = -band[0];
+        si[0] = band[17];
+        for i in 0..4 {
+            si[8 - 2 * i] = band[4 * i + 1] - band[4 * i + 2];
+            co[1 + 2 * i] = band[4 * i + 1] + band[4 * i + 2];
+            si[7 - 2 * i] = band[4 * i + 4] - band[4 * i + 3];
+            co[2 + 2 * i] = -(band[4 * i + 3] + band[4 * i + 4]);
+        }
+
+        dct3_9(&mut co);
+        dct3_9(&mut si);
+        for i in [1, 3, 5, 7] {
+            si[i] = -si[i];
+        }
+
+        for i in 0..9 {
+            let ovl = overlap[i];
+            let sum = co[i] * TWIDDLE_9[9 + i] + si[i] * TWIDDLE_9[i];
+            overlap[i] = co[i] * TWIDDLE_9[i] - si[i] * TWIDDLE_9[9 + i];
+            band[i] = ovl * window[i] - sum * window[9 + i];
+            band[17 - i] = ovl * window[9 + i] + sum * window[i];
+        }
+    }
+}
+
+fn idct3(x0: f32, x1: f32, x2: f32) -> [f32; 3] {
+    let m1 = x1 * 0.866_025_4;
+    let a1 = x0 - x2 * 0.5;
+    [a1 + m1, x0 + x2, a1 - m1]
+}
+
+/// Transforms the 6 lines of one short window, which are every third line from `x`.
+fn imdct12(x: &[f32], dst: &mut [f32], overlap: &mut [f32]) {
+    let co = idct3(-x[0], x[6] + x[3], x[12] + x[9]);
+    let mut si = idct3(x[15], x[12] - x[9], x[6] - x[3]);
+    si[1] = -si[1];
+
+    for i in 0..3 {
+        let ovl = overlap[i];
+        let sum = co[i] * TWIDDLE_3[3 + i] + si[i] * TWIDDLE_3[i];
+        overlap[i] = co[i] * TWIDDLE_3[i] - si[i] * TWIDDLE_3[3 + i];
+        dst[i] = ovl * TWIDDLE_3[2 - i] - sum * TWIDDLE_3[5 - i];
+        dst[5 - i] = ovl * TWIDDLE_3[5 - i] + sum * TWIDDLE_3[2 - i];
+    }
+}
+
+fn imdct_short(lines: &mut [f32], overlap: &mut [f32], n_bands: usize) {
+    for (band, overlap) in lines
+        .chunks_exact_mut(18)
+        .zip(overlap.chunks_exact_mut(9))
+        .take(n_bands)
+    {
+        let mut tmp = [0.0f32; 18];
+        tmp.copy_from_slice(band);
+        band[..6].copy_from_slice(&overlap[..6]);
+
+        let (start, end) = overlap.split_at_mut(6);
+        imdct12(&tmp, &mut band[6..12], end);
+        imdct12(&tmp[1..], &mut band[12..18], end);
+        imdct12(&tmp[2..], start, end);
+    }
+}
+
+/// Negates the odd samples of the odd subbands, which the synthesis expects to be inverted.
+fn change_sign(lines: &mut [f32; 576]) {
+    for band in lines.chunks_exact_mut(18).skip(1).step_by(2) {
+        for sample in band.iter_mut().skip(1).step_by(2) {
+            *sample = -*sample;
+        }
+    }
+}
+
+fn imdct(lines: &mut [f32; 576], overlap: &mut Overlap, block_type: u8, n_long_bands: usize) {
+    let split = n_long_bands * 18;
+    imdct36(
+        &mut lines[..split],
+        &mut overlap[..n_long_bands * 9],
+        &MDCT_WINDOWS[0],
+        n_long_bands,
+    );
+
+    let lines = &mut lines[split..];
+    let overlap = &mut overlap[n_long_bands * 9..];
+    match block_type {
+        SHORT_BLOCK => imdct_short(lines, overlap, 32 - n_long_bands),
+        STOP_BLOCK => imdct36(lines, overlap, &MDCT_WINDOWS[1], 32 - n_long_bands),
+        _ => imdct36(lines, overlap, &MDCT_WINDOWS[0], 32 - n_long_bands),
+    }
+}
@@ -0,0 +1,314 @@
+use std::{any::TypeId, marker::PhantomData};
+use phonic_core::PhonicError;
+use phonic_io_core::{
+    utils::TaggedSignal, CodecTag, DynCodecConstructor, DynStream, Stream, StreamObserver,
+    StreamReader, StreamSeeker, StreamSpec,
+};
+use phonic_signal::{Signal, SignalObserver, SignalReader, SignalSeeker, SignalSpec, SignalWriter};
+
+mod bits;
+mod decoder;
+mod header;
+mod huffman;
+mod hybrid;
+mod scalefactors;
+mod side_info;
+mod stereo;
+mod synthesis;
+mod tag;
+
+pub use decoder::Mp3Decoder;
+pub use header::*;
+pub use tag::*;
+
+/// The fewest bytes read from the inner stream at a time while gathering a frame.
+const READ_LEN: usize = 64 * 1024;
+
+/// The number of frames decoded ahead of a seek target, so the decoder has settled by the time it
+/// reaches it. This covers ten frames of MPEG-1, which is enough to refill the bit reservoir that
+/// frames take their main data from.
+const SEEK_PREROLL: u64 = 11_520;
+
+/// The frame rates of MPEG-1, MPEG-2 and MPEG-2.5.
+const FRAME_RATES: [u32; 9] = [
+    44_100, 48_000, 32_000, 22_050, 24_000, 16_000, 11_025, 12_000, 8_000,
+];
+
+/// Decodes an mp3 stream, where each read of the inner stream gives one frame, as the mp3 format
+/// passes them on. The inner stream is positioned by frame, counting the frames of samples that
+/// the frames read so far decode to. The delay of the codec tag is dropped from the start of the
+/// decoded samples, and the end is cut to the number of frames in the stream spec.
+pub struct Mp3Codec<T, C: CodecTag = Mp3CodecTag> {
+    inner: T,
+    signal_spec: SignalSpec,
+    delay: u64,
+    decoder: Mp3Decoder,
+    packet: Vec<u8>,
+    samples: Vec<f32>,
+    range: (usize, usize),
+    granule: u64,
+    buffered_i: u64,
+    position: u64,
+    _tag: PhantomData<C>,
+}
+
+pub fn fill_mp3_spec<C>(spec: &mut StreamSpec<C>) -> Result<(), PhonicError>
+where
+    C: CodecTag + TryInto<Mp3CodecTag>,
+{
+    let codec = spec.codec.ok_or(PhonicError::MissingData)?;
+    let _: Mp3CodecTag = codec.try_into().map_err(|_| PhonicError::InvalidData)?;
+
+    if *spec.sample_type.get_or_insert(TypeId::of::<f32>()) != TypeId::of::<f32>() {
+        return Err(PhonicError::Unsupported);
+    }
+
+    if spec
+        .decoded_spec
+        .frame_rate
+        .is_some_and(|rate| !FRAME_RATES.contains(&rate))
+    {
+        return Err(PhonicError::Unsupported);
+    }
+
+    if spec
+        .decoded_spec
+        .channels
+        .is_some_and(|channels| !(1..=2).contains(&channels.count()))
+    {
+        return Err(PhonicError::Unsupported);
+    }
+
+    Ok(())
+}
+
+pub fn mp3_codec_from_stream<S>(stream: S) -> Result<TaggedSignal, PhonicError>
+where
+    S: DynStream + 'static,
+    S::Tag: TryInto<Mp3CodecTag>,
+{
+    Ok(TaggedSignal::F32(Box::new(Mp3Codec::from_stream(stream)?)))
+}
+
+/// Mp3 streams can only be decoded.
+pub fn mp3_codec_from_signal<C>(
+    _signal: TaggedSignal,
+    _tag: Mp3CodecTag,
+) -> Result<Box<dyn DynStream<Tag = C>>, PhonicError>
+where
+    C: CodecTag + TryInto<Mp3CodecTag> + 'static,
+{
+    Err(PhonicError::Unsupported)
+}
+
+impl CodecTag for Mp3CodecTag {
+    fn fill_spec(spec: &mut StreamSpec<Self>) -> Result<(), PhonicError> {
+        fill_mp3_spec(spec)
+    }
+}
+
+impl DynCodecConstructor for Mp3CodecTag {
+    fn from_signal(
+        &self,
+        signal: TaggedSignal,
+    ) -> Result<Box<dyn DynStream<Tag = Self>>, PhonicError> {
+        mp3_codec_from_signal(signal, *self)
+    }
+
+    fn from_stream<S: DynStream<Tag = Self> + 'static>(
+        stream: S,
+    ) -> Result<TaggedSignal, PhonicError> {
+        mp3_codec_from_stream(stream)
+    }
+}
+
+impl<T, C: CodecTag> Mp3Codec<T, C> {
+    pub fn from_stream(inner: T) -> Result<Self, PhonicError>
+    where
+        T: Stream<Tag = C>,
+        C: TryInto<Mp3CodecTag>,
+    {
+        let mut stream_spec = *inner.spec();
+        fill_mp3_spec(&mut stream_spec)?;
+        let signal_spec = stream_spec.decoded_spec.build()?;
+
+        let codec = stream_spec.codec.ok_or(PhonicError::MissingData)?;
+        let tag: Mp3CodecTag = codec.try_into().map_err(|_| PhonicError::InvalidData)?;
+
+        Ok(Self {
+            inner,
+            signal_spec,
+            delay: tag.delay as u64,
+            decoder: Mp3Decoder::new(),
+            packet: Vec::new(),
+            samples: vec![0.0; Mp3Decoder::MAX_FRAME_SAMPLES],
+            range: (0, 0),
+            granule: 0,
+            buffered_i: 0,
+            position: 0,
+            _tag: PhantomData,
+        })
+    }
+
+    pub fn as_inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn n_channels(&self) -> u64 {
+        self.signal_spec.channels.count() as u64
+    }
+
+    /// Decodes a frame into the sample buffer, leaving out the frames past the end of the
+    /// signal.
+    fn decode_packet(&mut self, inner_granule: u64) -> Result<(), PhonicError> {
+        let header = FrameHeader::read(&self.packet)?;
+        if header.n_channels() as u64 != self.n_channels()
+            || header.frame_rate != self.signal_spec.frame_rate
+        {
+            return Err(PhonicError::SignalMismatch);
+        }
+
+        let n_channels = header.n_channels();
+        let n_frames = self.decoder.decode(&self.packet, &mut self.samples)? / n_channels;
+        let mut start = self.granule;
+
+        // frames that were skipped by a seek show in the position of the inner stream
+        if inner_granule > start + n_frames as u64 {
+            start = inner_granule - n_frames as u64;
+        }
+
+        self.granule = start + n_frames as u64;
+
+        let mut keep = n_frames;
+        if let Some(total) = self.signal_spec.n_frames {
+            let end = self.delay + total;
+            keep = keep.min(end.saturating_sub(start) as usize);
+        }
+
+        self.range = (0, keep * n_channels);
+        self.buffered_i = start * n_channels as u64;
+        Ok(())
+    }
+}
+
+impl<T, C: CodecTag> Signal for Mp3Codec<T, C> {
+    type Sample = f32;
+
+    fn spec(&self) -> &SignalSpec {
+        &self.signal_spec
+    }
+}
+
+impl<T, C: CodecTag> SignalObserver for Mp3Codec<T, C> {
+    fn position(&self) -> Result<u64, PhonicError> {
+        Ok(self.position)
+    }
+}
+
+impl<T: StreamReader + StreamObserver, C: CodecTag> Mp3Codec<T, C> {
+    /// Reads the next frame of the inner stream, returning false at the end of it. A read that
+    /// fills the whole buffer may have cut the frame short, so the rest of it is read as well.
+    fn read_packet(&mut self) -> Result<bool, PhonicError> {
+        self.packet.clear();
+        loop {
+            let filled = self.packet.len();
+            let len = filled.max(READ_LEN);
+            self.packet.resize(filled + len, 0);
+
+            let result = self.inner.read(&mut self.packet[filled..]);
+            let n = *result.as_ref().unwrap_or(&0);
+            self.packet.truncate(filled + n);
+            result?;
+
+            if n < len {
+                return Ok(!self.packet.is_empty());
+            }
+        }
+    }
+}
+
+impl<T: StreamReader + StreamObserver, C: CodecTag> SignalReader for Mp3Codec<T, C> {
+    fn read(&mut self, buf: &mut [Self::Sample]) -> Result<usize, PhonicError> {
+        loop {
+            // drop what comes before the position, which is the delay at the start of the
+            // stream and the preroll after a seek
+            let target_i = self.position + self.delay * self.n_channels();
+            let pending = (self.range.1 - self.range.0) as u64;
+            let skip = target_i.saturating_sub(self.buffered_i).min(pending);
+            self.range.0 += skip as usize;
+            self.buffered_i += skip;
+
+            if self.range.0 < self.range.1 {
+                break;
+            }
+
+            if !self.read_packet()? {
+                return Ok(0);
+            }
+
+            let inner_granule = self.inner.position()?;
+            self.decode_packet(inner_granule)?;
+        }
+
+        let pending = &self.samples[self.range.0..self.range.1];
+        let n = buf.len().min(pending.len());
+        buf[..n].copy_from_slice(&pending[..n]);
+        self.range.0 += n;
+        self.buffered_i += n as u64;
+        self.position += n as u64;
+
+        Ok(n)
+    }
+}
+
+impl<T, C: CodecTag> SignalWriter for Mp3Codec<T, C> {
+    fn write(&mut self, _buf: &[Self::Sample]) -> Result<usize, PhonicError> {
+        Err(PhonicError::Unsupported)
+    }
+
+    fn flush(&mut self) -> Result<(), PhonicError> {
+        Ok(())
+    }
+}
+
+impl<T, C> SignalSeeker for Mp3Codec<T, C>
+where
+    T: StreamReader + StreamObserver + StreamSeeker,
+    C: CodecTag,
+{
+    fn seek(&mut self, offset: i64) -> Result<(), PhonicError> {
+        let position = self
+            .position
+            .checked_add_signed(offset)
+            .ok_or(PhonicError::InvalidData)?;
+
+        let n_channels = self.n_channels();
+        let target = position / n_channels + self.delay;
+        let start = target.saturating_sub(SEEK_PREROLL);
+
+        // decoding carries on from where it is if that is no further from the target
+        if position >= self.position && start <= self.granule {
+            self.position = position;
+            return Ok(());
+        }
+
+        let inner_position = self.inner.position()?;
+        self.inner.seek(start as i64 - inner_position as i64)?;
+        let granule = self.inner.position()?;
+        if granule > target {
+            return Err(PhonicError::InvalidData);
+        }
+
+        self.decoder.reset();
+        self.range = (0, 0);
+        self.granule = granule;
+        self.buffered_i = granule * n_channels;
+        self.position = position;
+
+        Ok(())
+    }
+}
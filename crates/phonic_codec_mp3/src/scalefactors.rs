@@ -0,0 +1,154 @@
+use crate::{bits::BitReader, header::FrameHeader, side_info::GranuleInfo};
+
+/// The number of scalefactors in each group that shares a length, for long, mixed and short
+/// blocks. The first four are for MPEG-1, followed by the six partitions of the lower sampling
+/// frequencies, the last three of which are for the right channel of intensity stereo.
+static PARTITIONS: [[u8; 28]; 3] = [
+    [
+        6, 5, 5, 5, 6, 5, 5, 5, 6, 5, 7, 3, 11, 10, 0, 0, 7, 7, 7, 0, 6, 6, 6, 3, 8, 8, 5, 0,
+    ],
+    [
+        8, 9, 6, 12, 6, 9, 9, 9, 6, 9, 12, 6, 15, 18, 0, 0, 6, 15, 12, 0, 6, 12, 9, 6, 6, 18, 9, 0,
+    ],
+    [
+        9, 9, 6, 12, 9, 9, 9, 9, 9, 9, 12, 6, 18, 18, 0, 0, 12, 12, 12, 0, 12, 9, 9, 6, 15, 12, 9,
+        0,
+    ],
+];
+
+/// The scalefactor lengths of MPEG-1, by `scalefac_compress`, with the length of the first two
+/// groups in the upper bits and of the last two in the lower two bits.
+static MPEG1_LENGTHS: [u8; 16] = [0, 1, 2, 3, 12, 5, 6, 7, 9, 10, 11, 13, 14, 15, 18, 19];
+
+/// The moduli that unpack `scalefac_compress` into lengths at the lower sampling frequencies,
+/// for each of the partitions.
+static LSF_MODULI: [u8; 24] = [
+    5, 5, 4, 4, 5, 5, 4, 1, 4, 3, 1, 1, 5, 6, 6, 1, 4, 4, 4, 1, 4, 3, 1, 1,
+];
+
+/// The amounts added to the upper long scalefactors when the preflag is set.
+static PRETAB: [u8; 10] = [1, 1, 1, 1, 2, 2, 3, 3, 3, 2];
+
+/// The exponent of the gain at the largest global gain, in quarter steps, from which the gains
+/// are scaled down.
+const MAX_SCF: i32 = 44;
+
+/// Scales `y` by 2 to the power of `-exp_q2 / 4`, in steps small enough not to overflow.
+pub(crate) fn ldexp_q2(y: f32, exp_q2: i32) -> f32 {
+    const EXP_FRAC: [f32; 4] = [9.313_226e-10, 7.831_458e-10, 6.585_445e-10, 5.537_677e-10];
+
+    let mut y = y;
+    let mut exp_q2 = exp_q2;
+    loop {
+        let e = exp_q2.min(30 * 4);
+        y *= EXP_FRAC[(e & 3) as usize] * ((1 << 30 >> (e >> 2)) as f32);
+        exp_q2 -= e;
+        if exp_q2 <= 0 {
+            return y;
+        }
+    }
+}
+
+/// Reads the scalefactors of a granule and turns them into the gain of each band. The raw
+/// scalefactors are kept in `ist_pos`, where the second granule finds the ones it shares with the
+/// first, and where the right channel keeps its intensity positions. At the lower sampling
+/// frequencies a scalefactor with every bit set marks an illegal intensity position, which is
+/// stored as 255.
+pub(crate) fn read_scalefactors(
+    bits: &mut BitReader,
+    header: &FrameHeader,
+    granule: &GranuleInfo,
+    ch: usize,
+    ist_pos: &mut [u8; 39],
+    gains: &mut [f32; 40],
+) {
+    let partitions =
+        &PARTITIONS[(granule.n_short_sfb != 0) as usize + (granule.n_long_sfb == 0) as usize];
+    let mut lengths = [0u8; 4];
+    let counts = match header.is_lsf() {
+        false => {
+            let packed = MPEG1_LENGTHS[granule.scalefac_compress as usize];
+            lengths = [packed >> 2, packed >> 2, packed & 3, packed & 3];
+            &partitions[..4]
+        }
+        true => {
+            let intensity = (header.is_intensity_stereo() && ch == 1) as usize;
+            let mut sfc = (granule.scalefac_compress >> intensity) as i32;
+            let mut k = intensity * 12;
+            loop {
+                let mut product = 1;
+                for i in (0..4).rev() {
+                    let modulus = LSF_MODULI[k + i] as i32;
+                    lengths[i] = (sfc / product % modulus) as u8;
+                    product *= modulus;
+                }
+
+                sfc -= product;
+                k += 4;
+                if sfc < 0 {
+                    break;
+                }
+            }
+
+            &partitions[k..k + 4]
+        }
+    };
+
+    let mut scalefactors = [0u8; 40];
+    let mut i = 0;
+    for (group, (&count, &len)) in counts.iter().zip(&lengths).enumerate() {
+        let count = count as usize;
+        if count == 0 {
+            break;
+        }
+
+        let range = i..i + count;
+        if granule.scfsi & (8 >> group) != 0 {
+            scalefactors[range.clone()].copy_from_slice(&ist_pos[range]);
+        } else if len == 0 {
+            scalefactors[range.clone()].fill(0);
+            ist_pos[range].fill(0);
+        } else {
+            let illegal = header.is_lsf().then(|| (1 << len) - 1);
+            for j in range {
+                let scalefactor = bits.read_whole(len as u32);
+                ist_pos[j] = match Some(scalefactor) == illegal {
+                    true => 255,
+                    false => scalefactor as u8,
+                };
+
+                scalefactors[j] = scalefactor as u8;
+            }
+        }
+
+        i += count;
+    }
+
+    if granule.n_short_sfb != 0 {
+        let shift = 2 - granule.scalefac_scale as u8;
+        let short = &mut scalefactors[granule.n_long_sfb..granule.n_long_sfb + granule.n_short_sfb];
+        for window in short.chunks_exact_mut(3) {
+            for (scalefactor, gain) in window.iter_mut().zip(&granule.subblock_gain) {
+                *scalefactor += gain << shift;
+            }
+        }
+    } else if granule.preflag {
+        for (scalefactor, pre) in scalefactors[11..21].iter_mut().zip(&PRETAB) {
+            *scalefactor += pre;
+        }
+    }
+
+    // the gains are scaled down by half, for the output of the synthesis, and mid/side stereo
+    // takes another 1/sqrt(2) off for the sum and difference
+    let ms_shift = if header.is_ms_stereo() { 2 } else { 0 };
+    let gain_exp = granule.global_gain as i32 - 4 - 210 - ms_shift;
+    let gain = ldexp_q2((1 << (MAX_SCF / 4)) as f32, MAX_SCF - gain_exp);
+    let scale_shift = 1 + granule.scalefac_scale as u32;
+    for (gain_i, scalefactor) in gains
+        .iter_mut()
+        .zip(&scalefactors)
+        .take(granule.n_long_sfb + granule.n_short_sfb)
+    {
+        *gain_i = ldexp_q2(gain, (*scalefactor as i32) << scale_shift);
+    }
+}
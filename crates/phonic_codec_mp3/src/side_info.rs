@@ -0,0 +1,260 @@
+use crate::{bits::BitReader, header::FrameHeader};
+use phonic_core::PhonicError;
+
+pub(crate) const SHORT_BLOCK: u8 = 2;
+pub(crate) const STOP_BLOCK: u8 = 3;
+
+/// The widths of the scalefactor bands of long blocks, by rate index less one for every rate but
+/// the first, ending with a zero.
+static SFB_LONG: [[u8; 23]; 8] = [
+    [
+        6, 6, 6, 6, 6, 6, 8, 10, 12, 14, 16, 20, 24, 28, 32, 38, 46, 52, 60, 68, 58, 54, 0,
+    ],
+    [
+        12, 12, 12, 12, 12, 12, 16, 20, 24, 28, 32, 40, 48, 56, 64, 76, 90, 2, 2, 2, 2, 2, 0,
+    ],
+    [
+        6, 6, 6, 6, 6, 6, 8, 10, 12, 14, 16, 20, 24, 28, 32, 38, 46, 52, 60, 68, 58, 54, 0,
+    ],
+    [
+        6, 6, 6, 6, 6, 6, 8, 10, 12, 14, 16, 18, 22, 26, 32, 38, 46, 54, 62, 70, 76, 36, 0,
+    ],
+    [
+        6, 6, 6, 6, 6, 6, 8, 10, 12, 14, 16, 20, 24, 28, 32, 38, 46, 52, 60, 68, 58, 54, 0,
+    ],
+    [
+        4, 4, 4, 4, 4, 4, 6, 6, 8, 8, 10, 12, 16, 20, 24, 28, 34, 42, 50, 54, 76, 158, 0,
+    ],
+    [
+        4, 4, 4, 4, 4, 4, 6, 6, 6, 8, 10, 12, 16, 18, 22, 28, 34, 40, 46, 54, 54, 192, 0,
+    ],
+    [
+        4, 4, 4, 4, 4, 4, 6, 6, 8, 10, 12, 16, 20, 24, 30, 38, 46, 56, 68, 84, 102, 26, 0,
+    ],
+];
+
+/// The widths of the scalefactor bands of short blocks, repeated for each of the three windows.
+static SFB_SHORT: [[u8; 40]; 8] = [
+    [
+        4, 4, 4, 4, 4, 4, 4, 4, 4, 6, 6, 6, 8, 8, 8, 10, 10, 10, 12, 12, 12, 14, 14, 14, 18, 18,
+        18, 24, 24, 24, 30, 30, 30, 40, 40, 40, 18, 18, 18, 0,
+    ],
+    [
+        8, 8, 8, 8, 8, 8, 8, 8, 8, 12, 12, 12, 16, 16, 16, 20, 20, 20, 24, 24, 24, 28, 28, 28, 36,
+        36, 36, 2, 2, 2, 2, 2, 2, 2, 2, 2, 26, 26, 26, 0,
+    ],
+    [
+        4, 4, 4, 4, 4, 4, 4, 4, 4, 6, 6, 6, 6, 6, 6, 8, 8, 8, 10, 10, 10, 14, 14, 14, 18, 18, 18,
+        26, 26, 26, 32, 32, 32, 42, 42, 42, 18, 18, 18, 0,
+    ],
+    [
+        4, 4, 4, 4, 4, 4, 4, 4, 4, 6, 6, 6, 8, 8, 8, 10, 10, 10, 12, 12, 12, 14, 14, 14, 18, 18,
+        18, 24, 24, 24, 32, 32, 32, 44, 44, 44, 12, 12, 12, 0,
+    ],
+    [
+        4, 4, 4, 4, 4, 4, 4, 4, 4, 6, 6, 6, 8, 8, 8, 10, 10, 10, 12, 12, 12, 14, 14, 14, 18, 18,
+        18, 24, 24, 24, 30, 30, 30, 40, 40, 40, 18, 18, 18, 0,
+    ],
+    [
+        4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 6, 6, 6, 8, 8, 8, 10, 10, 10, 12, 12, 12, 14, 14, 14,
+        18, 18, 18, 22, 22, 22, 30, 30, 30, 56, 56, 56, 0,
+    ],
+    [
+        4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 6, 6, 6, 6, 6, 6, 10, 10, 10, 12, 12, 12, 14, 14, 14,
+        16, 16, 16, 20, 20, 20, 26, 26, 26, 66, 66, 66, 0,
+    ],
+    [
+        4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 6, 6, 6, 8, 8, 8, 12, 12, 12, 16, 16, 16, 20, 20, 20,
+        26, 26, 26, 34, 34, 34, 42, 42, 42, 12, 12, 12, 0,
+    ],
+];
+
+/// The widths of the bands of mixed blocks, which start with the long bands below the first
+/// 36 lines and carry on with the short bands above them.
+static SFB_MIXED: [[u8; 40]; 8] = [
+    [
+        6, 6, 6, 6, 6, 6, 6, 6, 6, 8, 8, 8, 10, 10, 10, 12, 12, 12, 14, 14, 14, 18, 18, 18, 24, 24,
+        24, 30, 30, 30, 40, 40, 40, 18, 18, 18, 0, 0, 0, 0,
+    ],
+    [
+        12, 12, 12, 4, 4, 4, 8, 8, 8, 12, 12, 12, 16, 16, 16, 20, 20, 20, 24, 24, 24, 28, 28, 28,
+        36, 36, 36, 2, 2, 2, 2, 2, 2, 2, 2, 2, 26, 26, 26, 0,
+    ],
+    [
+        6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 8, 8, 8, 10, 10, 10, 14, 14, 14, 18, 18, 18, 26, 26,
+        26, 32, 32, 32, 42, 42, 42, 18, 18, 18, 0, 0, 0, 0,
+    ],
+    [
+        6, 6, 6, 6, 6, 6, 6, 6, 6, 8, 8, 8, 10, 10, 10, 12, 12, 12, 14, 14, 14, 18, 18, 18, 24, 24,
+        24, 32, 32, 32, 44, 44, 44, 12, 12, 12, 0, 0, 0, 0,
+    ],
+    [
+        6, 6, 6, 6, 6, 6, 6, 6, 6, 8, 8, 8, 10, 10, 10, 12, 12, 12, 14, 14, 14, 18, 18, 18, 24, 24,
+        24, 30, 30, 30, 40, 40, 40, 18, 18, 18, 0, 0, 0, 0,
+    ],
+    [
+        4, 4, 4, 4, 4, 4, 6, 6, 4, 4, 4, 6, 6, 6, 8, 8, 8, 10, 10, 10, 12, 12, 12, 14, 14, 14, 18,
+        18, 18, 22, 22, 22, 30, 30, 30, 56, 56, 56, 0, 0,
+    ],
+    [
+        4, 4, 4, 4, 4, 4, 6, 6, 4, 4, 4, 6, 6, 6, 6, 6, 6, 10, 10, 10, 12, 12, 12, 14, 14, 14, 16,
+        16, 16, 20, 20, 20, 26, 26, 26, 66, 66, 66, 0, 0,
+    ],
+    [
+        4, 4, 4, 4, 4, 4, 6, 6, 4, 4, 4, 6, 6, 6, 8, 8, 8, 12, 12, 12, 16, 16, 16, 20, 20, 20, 26,
+        26, 26, 34, 34, 34, 42, 42, 42, 12, 12, 12, 0, 0,
+    ],
+];
+
+/// The side info of one channel of a granule.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct GranuleInfo {
+    /// The widths of the scalefactor bands, ending with a zero. Short bands are listed once for
+    /// each window.
+    pub sfb_widths: &'static [u8],
+    pub part2_3_len: usize,
+    pub big_values: usize,
+    pub scalefac_compress: u16,
+    pub global_gain: u8,
+    pub block_type: u8,
+    pub mixed_block: bool,
+    pub n_long_sfb: usize,
+    pub n_short_sfb: usize,
+    pub table_select: [u8; 3],
+    /// The number of scalefactor bands in each region of the big values, less one.
+    pub region_count: [u8; 3],
+    pub subblock_gain: [u8; 3],
+    pub preflag: bool,
+    pub scalefac_scale: bool,
+    pub count1_table: bool,
+    /// The groups of scalefactor bands whose scalefactors are shared with the first granule, as
+    /// four bits from the first group down.
+    pub scfsi: u8,
+}
+
+/// The side info of a frame, which describes how the main data is laid out.
+pub(crate) struct SideInfo {
+    /// The number of bytes before the end of the side info at which the main data starts, which
+    /// reach back into the bit reservoir left by earlier frames.
+    pub main_data_begin: usize,
+    /// The granules of each channel, with the channels of a granule next to each other.
+    pub granules: [GranuleInfo; 4],
+}
+
+impl SideInfo {
+    /// Reads the side info from the bits that follow the header and checksum. `bits` must only
+    /// hold the rest of the frame, so that the coded lengths can be checked against it.
+    pub fn read(bits: &mut BitReader, header: &FrameHeader) -> Result<Self, PhonicError> {
+        let n_channels = header.n_channels();
+        let (main_data_begin, n_granules, channel_scfsi) = match header.is_lsf() {
+            false => {
+                let main_data_begin = bits.read(9) as usize;
+                bits.skip(if n_channels == 1 { 5 } else { 3 });
+
+                let mut scfsi = [0; 2];
+                for scfsi in scfsi[..n_channels].iter_mut() {
+                    *scfsi = bits.read(4) as u8;
+                }
+
+                (main_data_begin, 2, scfsi)
+            }
+            true => {
+                let main_data_begin = bits.read(8) as usize;
+                bits.skip(n_channels as u32);
+                (main_data_begin, 1, [0; 2])
+            }
+        };
+
+        let sfb_i = header.rate_index().saturating_sub(1);
+        let mut granules = [GranuleInfo::default(); 4];
+        let mut part2_3_sum = 0;
+        for gr_i in 0..n_granules {
+            for ch in 0..n_channels {
+                let granule = &mut granules[gr_i * n_channels + ch];
+                granule.part2_3_len = bits.read(12) as usize;
+                part2_3_sum += granule.part2_3_len;
+
+                granule.big_values = bits.read(9) as usize;
+                if granule.big_values > 288 {
+                    return Err(PhonicError::InvalidData);
+                }
+
+                granule.global_gain = bits.read(8) as u8;
+                granule.scalefac_compress = bits.read(if header.is_lsf() { 9 } else { 4 }) as u16;
+                granule.sfb_widths = &SFB_LONG[sfb_i];
+                granule.n_long_sfb = 22;
+
+                let table_select;
+                if bits.read_bit() {
+                    granule.block_type = bits.read(2) as u8;
+                    if granule.block_type == 0 {
+                        return Err(PhonicError::InvalidData);
+                    }
+
+                    granule.mixed_block = bits.read_bit();
+                    granule.region_count = [7, 255, 255];
+                    if granule.block_type == SHORT_BLOCK {
+                        match granule.mixed_block {
+                            false => {
+                                granule.region_count[0] = 8;
+                                granule.sfb_widths = &SFB_SHORT[sfb_i];
+                                granule.n_long_sfb = 0;
+                                granule.n_short_sfb = 39;
+                            }
+                            true => {
+                                granule.sfb_widths = &SFB_MIXED[sfb_i];
+                                granule.n_long_sfb = if header.is_lsf() { 6 } else { 8 };
+                                granule.n_short_sfb = 30;
+                            }
+                        }
+                    }
+
+                    table_select = bits.read(10) << 5;
+                    for gain in granule.subblock_gain.iter_mut() {
+                        *gain = bits.read(3) as u8;
+                    }
+                } else {
+                    table_select = bits.read(15);
+                    granule.region_count[0] = bits.read(4) as u8;
+                    granule.region_count[1] = bits.read(3) as u8;
+                    granule.region_count[2] = 255;
+                }
+
+                granule.table_select = [
+                    (table_select >> 10) as u8,
+                    ((table_select >> 5) & 31) as u8,
+                    (table_select & 31) as u8,
+                ];
+
+                granule.preflag = match header.is_lsf() {
+                    false => bits.read_bit(),
+                    true => granule.scalefac_compress >= 500,
+                };
+
+                granule.scalefac_scale = bits.read_bit();
+                granule.count1_table = bits.read_bit();
+            }
+        }
+
+        // scalefactors are only shared with the first granule when neither uses short blocks
+        if n_granules == 2 {
+            for (ch, scfsi) in channel_scfsi[..n_channels].iter().enumerate() {
+                let short = |g: &GranuleInfo| g.block_type == SHORT_BLOCK;
+                if !short(&granules[ch]) && !short(&granules[n_channels + ch]) {
+                    granules[n_channels + ch].scfsi = *scfsi;
+                }
+            }
+        }
+
+        if bits.position() > bits.len()
+            || part2_3_sum > bits.len() - bits.position() + main_data_begin * 8
+        {
+            return Err(PhonicError::InvalidData);
+        }
+
+        Ok(Self {
+            main_data_begin,
+            granules,
+        })
+    }
+}
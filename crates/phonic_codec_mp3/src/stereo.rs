@@ -0,0 +1,113 @@
+use crate::{header::FrameHeader, scalefactors::ldexp_q2, side_info::GranuleInfo};
+
+/// The gains of the left and right channels for each intensity position of MPEG-1.
+static PAN: [[f32; 2]; 7] = [
+    [0.0, 1.0],
+    [0.211_324_87, 0.788_675_1],
+    [0.366_025_4, 0.633_974_6],
+    [0.5, 0.5],
+    [0.633_974_6, 0.366_025_4],
+    [0.788_675_1, 0.211_324_87],
+    [1.0, 0.0],
+];
+
+/// Turns the mid and side lines of a granule into left and right.
+pub(crate) fn midside(left: &mut [f32], right: &mut [f32]) {
+    for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+        let (mid, side) = (*l, *r);
+        *l = mid + side;
+        *r = mid - side;
+    }
+}
+
+/// Finds the last band of each window of the right channel with a nonzero line. Intensity stereo
+/// only applies above it, where the right channel carries positions instead of lines.
+fn top_bands(right: &[f32], widths: &[u8], n_bands: usize) -> [isize; 3] {
+    let mut top_bands = [-1; 3];
+    let mut line_i = 0;
+    for (band_i, &width) in widths[..n_bands].iter().enumerate() {
+        let width = width as usize;
+        if right[line_i..line_i + width]
+            .iter()
+            .any(|&line| line != 0.0)
+        {
+            top_bands[band_i % 3] = band_i as isize;
+        }
+
+        line_i += width;
+    }
+
+    top_bands
+}
+
+/// Applies intensity stereo, and mid/side stereo in the bands below it, to the lines of a
+/// granule. The positions are the scalefactors of the right channel, and `right_compress` is its
+/// `scalefac_compress`, which picks the steps between positions at the lower sampling frequencies.
+pub(crate) fn intensity(
+    lines: &mut [[f32; 576]; 2],
+    ist_pos: &mut [u8; 39],
+    granule: &GranuleInfo,
+    right_compress: u16,
+    header: &FrameHeader,
+) {
+    let n_sfb = granule.n_long_sfb + granule.n_short_sfb;
+    let mut top_bands = top_bands(&lines[1], granule.sfb_widths, n_sfb);
+    if granule.n_long_sfb != 0 {
+        let top_band = top_bands.into_iter().max().unwrap_or(-1);
+        top_bands = [top_band; 3];
+    }
+
+    // the top band of each window has no scalefactor of its own, and takes the position of the
+    // band below it, unless that one is below the intensity stereo
+    let n_windows = if granule.n_short_sfb != 0 { 3 } else { 1 };
+    let default_pos = if header.is_lsf() { 0 } else { 3 };
+    for (window, &top_band) in top_bands[..n_windows].iter().enumerate() {
+        let top = n_sfb - n_windows + window;
+        let prev = top - n_windows;
+        ist_pos[top] = match top_band >= prev as isize {
+            true => default_pos,
+            false => ist_pos[prev],
+        };
+    }
+
+    let max_pos = if header.is_lsf() { 64 } else { 7 };
+    let scale = if header.is_ms_stereo() {
+        std::f32::consts::SQRT_2
+    } else {
+        1.0
+    };
+    let [left, right] = lines;
+    let mut line_i = 0;
+    for (band_i, &width) in granule
+        .sfb_widths
+        .iter()
+        .take_while(|&&width| width != 0)
+        .enumerate()
+    {
+        let band = line_i..line_i + width as usize;
+        let pos = ist_pos[band_i] as usize;
+        if band_i as isize > top_bands[band_i % 3] && pos < max_pos {
+            let (kl, kr) = match header.is_lsf() {
+                false => (PAN[pos][0], PAN[pos][1]),
+                true => {
+                    let shift = right_compress & 1;
+                    let k = ldexp_q2(1.0, (((pos + 1) >> 1) << shift) as i32);
+                    match pos & 1 {
+                        0 => (1.0, k),
+                        _ => (k, 1.0),
+                    }
+                }
+            };
+
+            let (kl, kr) = (kl * scale, kr * scale);
+            for (l, r) in left[band.clone()].iter_mut().zip(right[band].iter_mut()) {
+                *r = *l * kr;
+                *l *= kl;
+            }
+        } else if header.is_ms_stereo() {
+            midside(&mut left[band.clone()], &mut right[band]);
+        }
+
+        line_i += width as usize;
+    }
+}
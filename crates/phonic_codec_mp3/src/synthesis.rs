@@ -0,0 +1,277 @@
+/// The factors of the butterflies of the 32 point DCT-II, three for each of the first 8 stages.
+static DCT_FACTORS: [f32; 24] = [
+    10.190_008,
+    0.500_603,
+    0.502_419_3,
+    3.407_608_5,
+    0.505_470_93,
+    0.522_498_6,
+    2.057_781,
+    0.515_447_3,
+    0.566_944_06,
+    1.484_164_6,
+    0.531_042_6,
+    0.646_821_8,
+    1.169_439_9,
+    0.553_103_9,
+    0.788_154_6,
+    0.972_568_2,
+    0.582_935,
+    1.060_677_6,
+    0.839_349_6,
+    0.622_504_1,
+    1.722_447_2,
+    0.744_536_3,
+    0.674_808_3,
+    5.101_148_6,
+];
+
+/// The history the filterbank keeps of earlier granules, as 15 rows of 64 values.
+pub(crate) type QmfState = [f32; 15 * 64];
+
+/// Runs the polyphase filterbank over the subband samples of both channels of a granule, and
+/// writes the 576 frames of the granule interleaved into `pcm`.
+pub(crate) fn synth_granule(
+    state: &mut QmfState,
+    lines: &mut [[f32; 576]; 2],
+    n_channels: usize,
+    pcm: &mut [f32],
+) {
+    for lines in lines[..n_channels].iter_mut() {
+        dct_ii(lines);
+    }
+
+    let mut lins = [0.0f32; 33 * 64];
+    lins[..15 * 64].copy_from_slice(state);
+    for band_i in (0..18).step_by(2) {
+        synth(
+            lines,
+            band_i,
+            n_channels,
+            &mut pcm[32 * n_channels * band_i..],
+            &mut lins[band_i * 64..],
+        );
+    }
+
+    // a mono stream only carries the even values of the state on to the next granule, like the
+    // reference decoder does
+    let lins = &lins[18 * 64..18 * 64 + 15 * 64];
+    match n_channels {
+        1 => {
+            for (state, lin) in state.iter_mut().zip(lins).step_by(2) {
+                *state = *lin;
+            }
+        }
+        _ => state.copy_from_slice(lins),
+    }
+}
+
+/// Transforms the samples at one position in each of the 32 subbands, for each of the 18
+/// positions of the granule.
+fn dct_ii(lines: &mut [f32; 576]) {
+    for k in 0..18 {
+        let mut t = [[0.0f32; 8]; 4];
+        for i in 0..8 {
+            let x0 = lines[i * 18 + k];
+            let x1 = lines[(15 - i) * 18 + k];
+            let x2 = lines[(16 + i) * 18 + k];
+            let x3 = lines[(31 - i) * 18 + k];
+            let t0 = x0 + x3;
+            let t1 = x1 + x2;
+            let t2 = (x1 - x2) * DCT_FACTORS[3 * i];
+            let t3 = (x0 - x3) * DCT_FACTORS[3 * i + 1];
+            t[0][i] = t0 + t1;
+            t[1][i] = (t0 - t1) * DCT_FACTORS[3 * i + 2];
+            t[2][i] = t3 + t2;
+            t[3][i] = (t3 - t2) * DCT_FACTORS[3 * i + 2];
+        }
+
+        for x in t.iter_mut() {
+            let [mut x0, mut x1, mut x2, mut x3, mut x4, mut x5, mut x6, mut x7] = *x;
+            let mut xt = x0 - x7;
+            x0 += x7;
+            x7 = x1 - x6;
+            x1 += x6;
+            x6 = x2 - x5;
+            x2 += x5;
+            x5 = x3 - x4;
+            x3 += x4;
+            x4 = x0 - x3;
+            x0 += x3;
+            x3 = x1 - x2;
+            x1 += x2;
+            x[0] = x0 + x1;
+            x[4] = (x0 - x1) * 0.707_106_77;
+            x5 += x6;
+            x6 = (x6 + x7) * 0.707_106_77;
+            x7 += xt;
+            x3 = (x3 + x4) * 0.707_106_77;
+
+            // rotate by pi/8
+            x5 -= x7 * 0.198_912_37;
+            x7 += x5 * 0.382_683_43;
+            x5 -= x7 * 0.198_912_37;
+
+            x0 = xt - x6;
+            xt += x6;
+            x[1] = (xt + x7) * 0.509_795_6;
+            x[2] = (x4 + x3) * 0.541_196_1;
+            x[3] = (x0 - x5) * 0.601_344_9;
+            x[5] = (x0 + x5) * 0.899_976_2;
+            x[6] = (x4 - x3) * 1.306_563;
+            x[7] = (xt - x7) * 2.562_915_6;
+        }
+
+        for i in 0..7 {
+            let y = &mut lines[4 * 18 * i + k..];
+            y[0] = t[0][i];
+            y[18] = t[2][i] + t[3][i] + t[3][i + 1];
+            y[2 * 18] = t[1][i] + t[1][i + 1];
+            y[3 * 18] = t[2][i + 1] + t[3][i] + t[3][i + 1];
+        }
+
+        let y = &mut lines[4 * 18 * 7 + k..];
+        y[0] = t[0][7];
+        y[18] = t[2][7] + t[3][7];
+        y[2 * 18] = t[1][7];
+        y[3 * 18] = t[3][7];
+    }
+}
+
+fn scale_pcm(sample: f32) -> f32 {
+    sample * (1.0 / 32768.0)
+}
+
+/// Computes the two samples of one channel that sit at the ends of a half of the window, where
+/// its symmetry leaves only half the taps.
+fn synth_pair(pcm: &mut [f32], n_channels: usize, z: &[f32]) {
+    let mut a = (z[14 * 64] - z[0]) * 29.0;
+    a += (z[64] + z[13 * 64]) * 213.0;
+    a += (z[12 * 64] - z[2 * 64]) * 459.0;
+    a += (z[3 * 64] + z[11 * 64]) * 2037.0;
+    a += (z[10 * 64] - z[4 * 64]) * 5153.0;
+    a += (z[5 * 64] + z[9 * 64]) * 6574.0;
+    a += (z[8 * 64] - z[6 * 64]) * 37489.0;
+    a += z[7 * 64] * 75038.0;
+    pcm[0] = scale_pcm(a);
+
+    let z = &z[2..];
+    let mut a = z[14 * 64] * 104.0;
+    a += z[12 * 64] * 1567.0;
+    a += z[10 * 64] * 9727.0;
+    a += z[8 * 64] * 64019.0;
+    a += z[6 * 64] * -9975.0;
+    a += z[4 * 64] * -45.0;
+    a += z[2 * 64] * 146.0;
+    a += z[0] * -5.0;
+    pcm[16 * n_channels] = scale_pcm(a);
+}
+
+/// Filters two neighbouring positions of the subbands into 64 frames. The new subband samples
+/// are pushed into `lins` after the 15 rows of history that it starts with.
+fn synth(
+    lines: &[[f32; 576]; 2],
+    band_i: usize,
+    n_channels: usize,
+    pcm: &mut [f32],
+    lins: &mut [f32],
+) {
+    let xl = &lines[0][band_i..];
+    let xr = &lines[n_channels - 1][band_i..];
+    let (left, right) = (0, n_channels - 1);
+    let zlin = 15 * 64;
+
+    lins[zlin + 4 * 15] = xl[18 * 16];
+    lins[zlin + 4 * 15 + 1] = xr[18 * 16];
+    lins[zlin + 4 * 15 + 2] = xl[0];
+    lins[zlin + 4 * 15 + 3] = xr[0];
+
+    lins[zlin + 4 * 31] = xl[1 + 18 * 16];
+    lins[zlin + 4 * 31 + 1] = xr[1 + 18 * 16];
+    lins[zlin + 4 * 31 + 2] = xl[1];
+    lins[zlin + 4 * 31 + 3] = xr[1];
+
+    synth_pair(&mut pcm[right..], n_channels, &lins[4 * 15 + 1..]);
+    synth_pair(
+        &mut pcm[right + 32 * n_channels..],
+        n_channels,
+        &lins[4 * 15 + 64 + 1..],
+    );
+    synth_pair(&mut pcm[left..], n_channels, &lins[4 * 15..]);
+    synth_pair(
+        &mut pcm[left + 32 * n_channels..],
+        n_channels,
+        &lins[4 * 15 + 64..],
+    );
+
+    for i in (0..15).rev() {
+        lins[zlin + 4 * i] = xl[18 * (31 - i)];
+        lins[zlin + 4 * i + 1] = xr[18 * (31 - i)];
+        lins[zlin + 4 * i + 2] = xl[1 + 18 * (31 - i)];
+        lins[zlin + 4 * i + 3] = xr[1 + 18 * (31 - i)];
+        lins[zlin + 4 * (i + 16)] = xl[1 + 18 * (1 + i)];
+        lins[zlin + 4 * (i + 16) + 1] = xr[1 + 18 * (1 + i)];
+        lins[zlin + 4 * i - 64 + 2] = xl[18 * (1 + i)];
+        lins[zlin + 4 * i - 64 + 3] = xr[18 * (1 + i)];
+
+        let mut a = [0.0f32; 4];
+        let mut b = [0.0f32; 4];
+        for k in 0..8 {
+            let w = (14 - i) * 16 + 2 * k;
+            let (w0, w1) = (WINDOW[w], WINDOW[w + 1]);
+
+            let vz = zlin + 4 * i - k * 64;
+            let vy = zlin + 4 * i - (15 - k) * 64;
+            for j in 0..4 {
+                let (z, y) = (lins[vz + j], lins[vy + j]);
+                match k {
+                    0 => {
+                        b[j] = z * w1 + y * w0;
+                        a[j] = z * w0 - y * w1;
+                    }
+                    _ if k % 2 == 1 => {
+                        b[j] += z * w1 + y * w0;
+                        a[j] += y * w1 - z * w0;
+                    }
+                    _ => {
+                        b[j] += z * w1 + y * w0;
+                        a[j] += z * w0 - y * w1;
+                    }
+                }
+            }
+        }
+
+        pcm[right + (15 - i) * n_channels] = scale_pcm(a[1]);
+        pcm[right + (17 + i) * n_channels] = scale_pcm(b[1]);
+        pcm[left + (15 - i) * n_channels] = scale_pcm(a[0]);
+        pcm[left + (17 + i) * n_channels] = scale_pcm(b[0]);
+        pcm[right + (47 - i) * n_channels] = scale_pcm(a[3]);
+        pcm[right + (49 + i) * n_channels] = scale_pcm(b[3]);
+        pcm[left + (47 - i) * n_channels] = scale_pcm(a[2]);
+        pcm[left + (49 + i) * n_channels] = scale_pcm(b[2]);
+    }
+}
+
+static WINDOW: [f32; 240] = [
+    -1.0, 26.0, -31.0, 208.0, 218.0, 401.0, -519.0, 2063.0, 2000.0, 4788.0, -5517.0, 7134.0,
+    5959.0, 35640.0, -39336.0, 74992.0, -1.0, 24.0, -35.0, 202.0, 222.0, 347.0, -581.0, 2080.0,
+    1952.0, 4425.0, -5879.0, 7640.0, 5288.0, 33791.0, -41176.0, 74856.0, -1.0, 21.0, -38.0, 196.0,
+    225.0, 294.0, -645.0, 2087.0, 1893.0, 4063.0, -6237.0, 8092.0, 4561.0, 31947.0, -43006.0,
+    74630.0, -1.0, 19.0, -41.0, 190.0, 227.0, 244.0, -711.0, 2085.0, 1822.0, 3705.0, -6589.0,
+    8492.0, 3776.0, 30112.0, -44821.0, 74313.0, -1.0, 17.0, -45.0, 183.0, 228.0, 197.0, -779.0,
+    2075.0, 1739.0, 3351.0, -6935.0, 8840.0, 2935.0, 28289.0, -46617.0, 73908.0, -1.0, 16.0, -49.0,
+    176.0, 228.0, 153.0, -848.0, 2057.0, 1644.0, 3004.0, -7271.0, 9139.0, 2037.0, 26482.0,
+    -48390.0, 73415.0, -2.0, 14.0, -53.0, 169.0, 227.0, 111.0, -919.0, 2032.0, 1535.0, 2663.0,
+    -7597.0, 9389.0, 1082.0, 24694.0, -50137.0, 72835.0, -2.0, 13.0, -58.0, 161.0, 224.0, 72.0,
+    -991.0, 2001.0, 1414.0, 2330.0, -7910.0, 9592.0, 70.0, 22929.0, -51853.0, 72169.0, -2.0, 11.0,
+    -63.0, 154.0, 221.0, 36.0, -1064.0, 1962.0, 1280.0, 2006.0, -8209.0, 9750.0, -998.0, 21189.0,
+    -53534.0, 71420.0, -2.0, 10.0, -68.0, 147.0, 215.0, 2.0, -1137.0, 1919.0, 1131.0, 1692.0,
+    -8491.0, 9863.0, -2122.0, 19478.0, -55178.0, 70590.0, -3.0, 9.0, -73.0, 139.0, 208.0, -29.0,
+    -1210.0, 1870.0, 970.0, 1388.0, -8755.0, 9935.0, -3300.0, 17799.0, -56778.0, 69679.0, -3.0,
+    8.0, -79.0, 132.0, 200.0, -57.0, -1283.0, 1817.0, 794.0, 1095.0, -8998.0, 9966.0, -4533.0,
+    16155.0, -58333.0, 68692.0, -4.0, 7.0, -85.0, 125.0, 189.0, -83.0, -1356.0, 1759.0, 605.0,
+    814.0, -9219.0, 9959.0, -5818.0, 14548.0, -59838.0, 67629.0, -4.0, 7.0, -91.0, 117.0, 177.0,
+    -106.0, -1428.0, 1698.0, 402.0, 545.0, -9416.0, 9916.0, -7154.0, 12980.0, -61289.0, 66494.0,
+    -5.0, 6.0, -97.0, 111.0, 163.0, -127.0, -1498.0, 1634.0, 185.0, 288.0, -9585.0, 9838.0,
+    -8540.0, 11455.0, -62684.0, 65290.0,
+];
@@ -0,0 +1,26 @@
+/// Identifies an mp3 stream, which is MPEG-1, MPEG-2 or MPEG-2.5 audio layer III. Every stream
+/// decodes to `f32` samples, with the frame rate and channels of its frame headers.
+///
+/// Encoders add frames of silence at the start and end of a stream, which a LAME tag records so
+/// that they can be dropped again. The delay is the number of frames dropped from the start of
+/// the decoded output, counting the delay of the decoder itself, and is 0 for streams that don't
+/// record one. The end of the stream is cut by the number of frames in the stream spec.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+pub struct Mp3CodecTag {
+    pub delay: u32,
+}
+
+impl Mp3CodecTag {
+    /// The number of frames the synthesis of the decoder lags behind its input. A LAME tag counts
+    /// the delay of the encoder alone, so this is added to it.
+    pub const DECODER_DELAY: u32 = 529;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_delay(mut self, delay: u32) -> Self {
+        self.delay = delay;
+        self
+    }
+}
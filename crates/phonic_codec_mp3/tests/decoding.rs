@@ -0,0 +1,328 @@
+use phonic_codec_mp3::{fill_mp3_spec, Mp3Codec, Mp3CodecTag, Mp3Decoder};
+use phonic_core::PhonicError;
+use phonic_io_core::{
+    test_utils::{BitOrder, BitWriter},
+    Stream, StreamObserver, StreamReader, StreamSeeker, StreamSpec,
+};
+use phonic_signal::{Signal, SignalObserver, SignalReader, SignalSeeker, SignalSpecBuilder};
+
+/// The length of a frame of MPEG-1 at 128 kbit/s and 44.1 kHz, without padding.
+const FRAME_LEN: usize = 417;
+const N_FRAMES: usize = 12;
+
+/// Codes a single quantized line of 1 at `line_i` with table 1, where a pair of zeros is "1",
+/// a pair of (1, 0) is "01" and a pair of (0, 1) is "001", each 1 followed by its sign.
+fn write_line(bits: &mut BitWriter, line_i: usize, negative: bool) {
+    for _ in 0..line_i / 2 {
+        bits.write(1, 1);
+    }
+
+    match line_i % 2 {
+        0 => bits.write(0b01, 2),
+        _ => bits.write(0b001, 3),
+    }
+
+    bits.write(negative as u32, 1);
+}
+
+/// Builds an MPEG-1 layer III frame at 128 kbit/s and 44.1 kHz where each channel holds one
+/// line in both granules, so that it decodes to a tone. Joint stereo frames code the lines as
+/// mid and side.
+fn tone_frame(lines: &[usize], joint_stereo: bool, main_data_begin: u32) -> Vec<u8> {
+    let n_channels = lines.len();
+    let mode = match (n_channels, joint_stereo) {
+        (1, _) => 3,
+        (_, false) => 0,
+        (_, true) => 1,
+    };
+
+    let mut bits = BitWriter::new(BitOrder::MsbFirst);
+    bits.write(0xfffb, 16);
+    bits.write(0x90, 8);
+    bits.write((mode << 6) | if joint_stereo { 0x20 } else { 0 }, 8);
+
+    bits.write(main_data_begin, 9);
+    bits.write(0, if n_channels == 1 { 5 } else { 3 });
+    bits.write(0, 4 * n_channels as u32);
+
+    for gr in 0..2 {
+        for (ch, &line_i) in lines.iter().enumerate() {
+            let part2_3_len = line_i / 2 + 3 + line_i % 2;
+            bits.write(part2_3_len as u32, 12);
+            bits.write(line_i as u32 / 2 + 1, 9);
+            bits.write(200 - 4 * ch as u32 + gr, 8);
+            bits.write(0, 4);
+            bits.write(0, 1);
+            bits.write(0b00001_00001_00001, 15);
+            bits.write(7, 4);
+            bits.write(3, 3);
+            bits.write(0, 3);
+        }
+    }
+
+    for gr in 0..2 {
+        for &line_i in lines {
+            write_line(&mut bits, line_i, gr == 1);
+        }
+    }
+
+    let mut frame = bits.into_bytes();
+    frame.resize(FRAME_LEN, 0);
+    frame
+}
+
+fn tone_frames(lines: &[usize], joint_stereo: bool, n_frames: usize) -> Vec<Vec<u8>> {
+    (0..n_frames)
+        .map(|_| tone_frame(lines, joint_stereo, 0))
+        .collect()
+}
+
+/// A stream that gives one frame per read and is positioned by frame, the way the mp3 format
+/// passes frames on.
+struct FrameStream {
+    spec: StreamSpec<Mp3CodecTag>,
+    frames: Vec<Vec<u8>>,
+    i: usize,
+}
+
+impl FrameStream {
+    fn new(n_channels: u16, tag: Mp3CodecTag, n_frames: u64, frames: Vec<Vec<u8>>) -> Self {
+        let decoded_spec = SignalSpecBuilder::new()
+            .with_frame_rate(44_100)
+            .with_channels(n_channels)
+            .with_n_frames(n_frames);
+
+        let spec = StreamSpec::new()
+            .with_codec(tag)
+            .with_decoded_spec(decoded_spec);
+
+        Self { spec, frames, i: 0 }
+    }
+}
+
+impl Stream for FrameStream {
+    type Tag = Mp3CodecTag;
+
+    fn spec(&self) -> &StreamSpec<Self::Tag> {
+        &self.spec
+    }
+}
+
+impl StreamObserver for FrameStream {
+    fn position(&self) -> Result<u64, PhonicError> {
+        Ok(self.i as u64 * 1152)
+    }
+}
+
+impl StreamReader for FrameStream {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, PhonicError> {
+        let Some(frame) = self.frames.get(self.i) else {
+            return Ok(0);
+        };
+
+        buf[..frame.len()].copy_from_slice(frame);
+        self.i += 1;
+        Ok(frame.len())
+    }
+}
+
+impl StreamSeeker for FrameStream {
+    fn seek(&mut self, offset: i64) -> Result<(), PhonicError> {
+        let position = self
+            .position()?
+            .checked_add_signed(offset)
+            .ok_or(PhonicError::InvalidData)?;
+
+        // resume from the frame that holds the position
+        self.i = (position as usize / 1152).min(self.frames.len());
+        Ok(())
+    }
+}
+
+fn decode_all(frames: &[Vec<u8>]) -> Vec<f32> {
+    let mut decoder = Mp3Decoder::new();
+    let mut pcm = [0.0; Mp3Decoder::MAX_FRAME_SAMPLES];
+    let mut all = Vec::new();
+    for frame in frames {
+        let n = decoder.decode(frame, &mut pcm).unwrap();
+        all.extend_from_slice(&pcm[..n]);
+    }
+
+    all
+}
+
+fn read_all(codec: &mut impl SignalReader<Sample = f32>) -> Vec<f32> {
+    let mut all = Vec::new();
+    let mut buf = [0.0; 700];
+    loop {
+        match codec.read(&mut buf).unwrap() {
+            0 => return all,
+            n => all.extend_from_slice(&buf[..n]),
+        }
+    }
+}
+
+#[test]
+fn decoder_matches_reference() {
+    // samples decoded by minimp3 from the same frames
+    let cases: [(&[usize], bool, [f32; 4]); 3] = [
+        (
+            &[18],
+            false,
+            [-0.18185848, -0.2139623, -0.18488574, -0.1023882],
+        ),
+        (
+            &[40, 7],
+            false,
+            [-0.08624479, 0.10397669, -0.1368888, -0.034435816],
+        ),
+        (
+            &[30, 30],
+            true,
+            [0.08320105, 0.031505484, -0.121378146, -0.05254198],
+        ),
+    ];
+
+    for (lines, joint_stereo, expected) in cases {
+        let decoded = decode_all(&tone_frames(lines, joint_stereo, N_FRAMES));
+        assert_eq!(decoded.len(), N_FRAMES * 1152 * lines.len());
+
+        let samples = [1000, 5001, 9000, 12345].map(|i| decoded[i]);
+        assert_eq!(samples, expected);
+    }
+}
+
+#[test]
+fn decoder_decodes_silence() {
+    let mut frame = vec![0xff, 0xfb, 0x90, 0xc0];
+    frame.resize(FRAME_LEN, 0);
+
+    let mut decoder = Mp3Decoder::new();
+    let mut pcm = [1.0; Mp3Decoder::MAX_FRAME_SAMPLES];
+    assert_eq!(decoder.decode(&frame, &mut pcm).unwrap(), 1152);
+    assert!(pcm[..1152].iter().all(|&sample| sample == 0.0));
+}
+
+#[test]
+fn decoder_skips_missing_reservoir() {
+    // the first frame takes its main data from frames the decoder never saw
+    let mut frames = vec![tone_frame(&[18], false, 100)];
+    frames.extend(tone_frames(&[18], false, 2));
+
+    let mut decoder = Mp3Decoder::new();
+    let mut pcm = [1.0; Mp3Decoder::MAX_FRAME_SAMPLES];
+    assert_eq!(decoder.decode(&frames[0], &mut pcm).unwrap(), 1152);
+    assert!(pcm[..1152].iter().all(|&sample| sample == 0.0));
+
+    decoder.decode(&frames[1], &mut pcm).unwrap();
+    decoder.decode(&frames[2], &mut pcm).unwrap();
+    assert!(pcm[..1152].iter().any(|&sample| sample != 0.0));
+}
+
+#[test]
+fn decoder_rejects_unsupported_frames() {
+    let frame = tone_frame(&[18], false, 0);
+    let mut decoder = Mp3Decoder::new();
+    let mut pcm = [0.0; Mp3Decoder::MAX_FRAME_SAMPLES];
+
+    let mut layer_2 = frame.clone();
+    layer_2[1] = 0xfd;
+    assert_eq!(
+        decoder.decode(&layer_2, &mut pcm),
+        Err(PhonicError::Unsupported)
+    );
+
+    let mut free_format = frame.clone();
+    free_format[2] = 0x00;
+    assert_eq!(
+        decoder.decode(&free_format, &mut pcm),
+        Err(PhonicError::Unsupported)
+    );
+
+    assert_eq!(
+        decoder.decode(&frame[..FRAME_LEN - 1], &mut pcm),
+        Err(PhonicError::InvalidData)
+    );
+    assert_eq!(
+        decoder.decode(&frame, &mut pcm[..1000]),
+        Err(PhonicError::InvalidData)
+    );
+}
+
+#[test]
+fn fills_spec() {
+    let mut spec = StreamSpec::new().with_codec(Mp3CodecTag::new());
+    fill_mp3_spec(&mut spec).unwrap();
+    assert_eq!(spec.sample_type, Some(std::any::TypeId::of::<f32>()));
+
+    let mut spec = StreamSpec::new()
+        .with_codec(Mp3CodecTag::new())
+        .with_sample_type::<i16>();
+
+    assert_eq!(fill_mp3_spec(&mut spec), Err(PhonicError::Unsupported));
+
+    let mut spec = StreamSpec::new()
+        .with_codec(Mp3CodecTag::new())
+        .with_decoded_spec(SignalSpecBuilder::new().with_frame_rate(96_000));
+
+    assert_eq!(fill_mp3_spec(&mut spec), Err(PhonicError::Unsupported));
+}
+
+#[test]
+fn codec_drops_delay_and_trims_end() {
+    let frames = tone_frames(&[18], false, N_FRAMES);
+    let tag = Mp3CodecTag::new().with_delay(576 + Mp3CodecTag::DECODER_DELAY);
+    let n_frames = (N_FRAMES * 1152) as u64 - tag.delay as u64 - 500;
+    let stream = FrameStream::new(1, tag, n_frames, frames.clone());
+    let mut codec = Mp3Codec::from_stream(stream).unwrap();
+
+    let decoded = read_all(&mut codec);
+    let expected = decode_all(&frames);
+    assert_eq!(decoded, expected[tag.delay as usize..][..n_frames as usize]);
+    assert_eq!(codec.position().unwrap(), n_frames);
+}
+
+#[test]
+fn codec_seeks() {
+    let n_frames = 30 * 1152;
+    let frames = tone_frames(&[40, 7], false, 30);
+    let stream = FrameStream::new(2, Mp3CodecTag::new(), n_frames, frames);
+    let mut codec = Mp3Codec::from_stream(stream).unwrap();
+    let expected = read_all(&mut codec);
+
+    // targets within the preroll of the start decode from the start, so they match exactly
+    let mut buf = [0.0; 500];
+    for target in [0, 4000, 5000, 200] {
+        codec
+            .seek(target as i64 - codec.position().unwrap() as i64)
+            .unwrap();
+        codec.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, expected[target..target + 500]);
+        assert_eq!(codec.position().unwrap(), target as u64 + 500);
+    }
+
+    // further in, decoding resumes from the preroll ahead of the target, and every frame of
+    // the tone is the same, so the decoder settles on the same output
+    codec
+        .seek(50_000 - codec.position().unwrap() as i64)
+        .unwrap();
+    let rest = read_all(&mut codec);
+    assert_eq!(rest, expected[50_000..]);
+    assert_eq!(codec.position().unwrap(), n_frames * 2);
+    assert_eq!(codec.as_inner().position().unwrap(), n_frames);
+
+    assert!(codec.seek(-(n_frames as i64 * 2) - 1).is_err());
+}
+
+#[test]
+fn codec_rejects_mismatched_channels() {
+    let frames = tone_frames(&[18], false, 2);
+    let stream = FrameStream::new(2, Mp3CodecTag::new(), 1000, frames);
+    let mut codec = Mp3Codec::from_stream(stream).unwrap();
+
+    assert_eq!(
+        codec.read(&mut [0.0; 100]),
+        Err(PhonicError::SignalMismatch)
+    );
+    assert_eq!(codec.spec().frame_rate, 44_100);
+}
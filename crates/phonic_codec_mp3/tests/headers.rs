@@ -0,0 +1,97 @@
+use phonic_codec_mp3::{ChannelMode, FrameHeader, MpegVersion};
+use phonic_core::PhonicError;
+
+#[test]
+fn reads_mpeg_1_header() {
+    let header = FrameHeader::read(&[0xff, 0xfb, 0x90, 0x64]).unwrap();
+
+    assert_eq!(header.version, MpegVersion::Mpeg1);
+    assert_eq!(header.layer, 3);
+    assert!(!header.protected);
+    assert_eq!(header.bitrate(), 128);
+    assert_eq!(header.frame_rate, 44_100);
+    assert_eq!(header.channel_mode, ChannelMode::JointStereo);
+    assert!(header.is_ms_stereo());
+    assert!(!header.is_intensity_stereo());
+    assert_eq!(header.n_channels(), 2);
+    assert_eq!(header.n_frames(), 1152);
+    assert_eq!(header.side_info_len(), 32);
+    assert_eq!(header.frame_len(), Some(417));
+
+    let padded = FrameHeader::read(&[0xff, 0xfb, 0x92, 0x64]).unwrap();
+    assert!(padded.padding);
+    assert_eq!(padded.frame_len(), Some(418));
+}
+
+#[test]
+fn reads_lsf_headers() {
+    let mpeg_2 = FrameHeader::read(&[0xff, 0xf3, 0x84, 0xc0]).unwrap();
+    assert_eq!(mpeg_2.version, MpegVersion::Mpeg2);
+    assert!(mpeg_2.is_lsf());
+    assert_eq!(mpeg_2.bitrate(), 64);
+    assert_eq!(mpeg_2.frame_rate, 24_000);
+    assert_eq!(mpeg_2.channel_mode, ChannelMode::Mono);
+    assert_eq!(mpeg_2.n_frames(), 576);
+    assert_eq!(mpeg_2.side_info_len(), 9);
+    assert_eq!(mpeg_2.frame_len(), Some(192));
+
+    let mpeg_25 = FrameHeader::read(&[0xff, 0xe2, 0x18, 0x00]).unwrap();
+    assert_eq!(mpeg_25.version, MpegVersion::Mpeg25);
+    assert!(mpeg_25.protected);
+    assert_eq!(mpeg_25.bitrate(), 8);
+    assert_eq!(mpeg_25.frame_rate, 8_000);
+    assert_eq!(mpeg_25.channel_mode, ChannelMode::Stereo);
+    assert_eq!(mpeg_25.side_info_len(), 17);
+    assert_eq!(mpeg_25.frame_len(), Some(72));
+}
+
+#[test]
+fn reads_other_layers() {
+    let layer_1 = FrameHeader::read(&[0xff, 0xff, 0x92, 0x00]).unwrap();
+    assert_eq!(layer_1.layer, 1);
+    assert_eq!(layer_1.bitrate(), 288);
+    assert_eq!(layer_1.n_frames(), 384);
+    assert_eq!(layer_1.frame_len(), Some(316));
+
+    let layer_2 = FrameHeader::read(&[0xff, 0xfd, 0x90, 0x00]).unwrap();
+    assert_eq!(layer_2.layer, 2);
+    assert_eq!(layer_2.bitrate(), 160);
+    assert_eq!(layer_2.n_frames(), 1152);
+}
+
+#[test]
+fn free_format_has_no_frame_len() {
+    let header = FrameHeader::read(&[0xff, 0xfb, 0x00, 0x00]).unwrap();
+    assert_eq!(header.bitrate(), 0);
+    assert_eq!(header.frame_len(), None);
+}
+
+#[test]
+fn checks_compatibility() {
+    let header = FrameHeader::read(&[0xff, 0xfb, 0x90, 0x64]).unwrap();
+    let other_bitrate = FrameHeader::read(&[0xff, 0xfb, 0x52, 0xc0]).unwrap();
+    let other_rate = FrameHeader::read(&[0xff, 0xfb, 0x94, 0x64]).unwrap();
+    let free_format = FrameHeader::read(&[0xff, 0xfb, 0x00, 0x64]).unwrap();
+    let mpeg_2 = FrameHeader::read(&[0xff, 0xf3, 0x90, 0x64]).unwrap();
+
+    assert!(header.is_compatible(&other_bitrate));
+    assert!(!header.is_compatible(&other_rate));
+    assert!(!header.is_compatible(&free_format));
+    assert!(!header.is_compatible(&mpeg_2));
+}
+
+#[test]
+fn rejects_invalid_headers() {
+    for bytes in [
+        &[0xff, 0xfb, 0x90][..],
+        &[0xfe, 0xfb, 0x90, 0x64],
+        &[0xff, 0x7b, 0x90, 0x64],
+        &[0xff, 0xeb, 0x90, 0x64],
+        &[0xff, 0xf9, 0x90, 0x64],
+        &[0xff, 0xe5, 0x90, 0x64],
+        &[0xff, 0xfb, 0xf0, 0x64],
+        &[0xff, 0xfb, 0x9c, 0x64],
+    ] {
+        assert_eq!(FrameHeader::read(bytes), Err(PhonicError::InvalidData));
+    }
+}
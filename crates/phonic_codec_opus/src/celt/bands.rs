@@ -0,0 +1,918 @@
+use crate::{
+    celt::{
+        exp2,
+        rate::{bits2pulses, get_pulses, pulses2bits, QTHETA_OFFSET, QTHETA_OFFSET_TWOPHASE},
+        tables::{CACHE_BITS, CACHE_INDEX, EBANDS, E_MEANS, LOG_N},
+        vq::{alg_unquant, renormalise_vector},
+        NB_EBANDS, SPREAD_AGGRESSIVE,
+    },
+    range::{ilog, RangeDecoder, BITRES},
+};
+
+const MAX_BAND_SIZE: usize = 176;
+
+const ORDERY_TABLE: [usize; 30] = [
+    1, 0, 3, 0, 2, 1, 7, 0, 4, 3, 6, 1, 5, 2, 15, 0, 8, 7, 12, 3, 11, 4, 14, 1, 9, 6, 13, 2, 10, 5,
+];
+
+const BIT_INTERLEAVE_TABLE: [u32; 16] = [0, 1, 1, 1, 2, 3, 3, 3, 2, 3, 3, 3, 2, 3, 3, 3];
+
+const BIT_DEINTERLEAVE_TABLE: [u32; 16] = [
+    0x00, 0x03, 0x0C, 0x0F, 0x30, 0x33, 0x3C, 0x3F, 0xC0, 0xC3, 0xCC, 0xCF, 0xF0, 0xF3, 0xFC, 0xFF,
+];
+
+pub(crate) fn lcg_rand(seed: u32) -> u32 {
+    seed.wrapping_mul(1664525).wrapping_add(1013904223)
+}
+
+fn frac_mul16(a: i32, b: i32) -> i32 {
+    (16384 + (a as i16 as i32) * (b as i16 as i32)) >> 15
+}
+
+fn bitexact_cos(x: i32) -> i32 {
+    let tmp = (4096 + x * x) >> 13;
+    let x2 = tmp;
+    let x2 = (32767 - x2) + frac_mul16(x2, -7651 + frac_mul16(x2, 8277 + frac_mul16(-626, x2)));
+    1 + x2
+}
+
+fn bitexact_log2tan(isin: i32, icos: i32) -> i32 {
+    let lc = ilog(icos as u32);
+    let ls = ilog(isin as u32);
+    let icos = icos << (15 - lc);
+    let isin = isin << (15 - ls);
+    (ls - lc) * (1 << 11) + frac_mul16(isin, frac_mul16(isin, -2597) + 7932)
+        - frac_mul16(icos, frac_mul16(icos, -2597) + 7932)
+}
+
+fn isqrt32(mut val: u32) -> u32 {
+    let mut g = 0;
+    let mut bshift = (ilog(val) - 1) >> 1;
+    let mut b = 1u32 << bshift;
+    loop {
+        let t = ((g << 1) + b) << bshift;
+        if t <= val {
+            g += b;
+            val -= t;
+        }
+
+        b >>= 1;
+        bshift -= 1;
+        if bshift < 0 {
+            break;
+        }
+    }
+
+    g
+}
+
+fn haar1(x: &mut [f32], n0: usize, stride: usize) {
+    let n0 = n0 >> 1;
+    for i in 0..stride {
+        for j in 0..n0 {
+            let tmp1 = std::f32::consts::FRAC_1_SQRT_2 * x[stride * 2 * j + i];
+            let tmp2 = std::f32::consts::FRAC_1_SQRT_2 * x[stride * (2 * j + 1) + i];
+            x[stride * 2 * j + i] = tmp1 + tmp2;
+            x[stride * (2 * j + 1) + i] = tmp1 - tmp2;
+        }
+    }
+}
+
+fn deinterleave_hadamard(x: &mut [f32], n0: usize, stride: usize, hadamard: bool) {
+    let n = n0 * stride;
+    let mut tmp = [0.0; MAX_BAND_SIZE];
+    for i in 0..stride {
+        let row = match hadamard {
+            true => ORDERY_TABLE[stride - 2 + i],
+            false => i,
+        };
+
+        for j in 0..n0 {
+            tmp[row * n0 + j] = x[j * stride + i];
+        }
+    }
+
+    x[..n].copy_from_slice(&tmp[..n]);
+}
+
+fn interleave_hadamard(x: &mut [f32], n0: usize, stride: usize, hadamard: bool) {
+    let n = n0 * stride;
+    let mut tmp = [0.0; MAX_BAND_SIZE];
+    for i in 0..stride {
+        let row = match hadamard {
+            true => ORDERY_TABLE[stride - 2 + i],
+            false => i,
+        };
+
+        for j in 0..n0 {
+            tmp[j * stride + i] = x[row * n0 + j];
+        }
+    }
+
+    x[..n].copy_from_slice(&tmp[..n]);
+}
+
+fn compute_qn(n: usize, b: i32, offset: i32, pulse_cap: i32, stereo: bool) -> i32 {
+    const EXP2_TABLE8: [i32; 8] = [16384, 17866, 19483, 21247, 23170, 25267, 27554, 30048];
+
+    let mut n2 = 2 * n as i32 - 1;
+    if stereo && n == 2 {
+        n2 -= 1;
+    }
+
+    let qb = (b + n2 * offset) / n2;
+    let qb = qb.min(b - pulse_cap - (4 << BITRES)).min(8 << BITRES);
+    if qb < (1 << BITRES >> 1) {
+        1
+    } else {
+        let qn = EXP2_TABLE8[(qb & 0x7) as usize] >> (14 - (qb >> BITRES));
+        (qn + 1) >> 1 << 1
+    }
+}
+
+fn stereo_merge(x: &mut [f32], y: &mut [f32], mid: f32, n: usize) {
+    let mut xp = 0.0;
+    let mut side = 0.0;
+    for j in 0..n {
+        xp += y[j] * x[j];
+        side += y[j] * y[j];
+    }
+
+    let xp = mid * xp;
+    let el = mid * mid + side - 2.0 * xp;
+    let er = mid * mid + side + 2.0 * xp;
+    if er < 6e-4 || el < 6e-4 {
+        y[..n].copy_from_slice(&x[..n]);
+        return;
+    }
+
+    let lgain = 1.0 / el.sqrt();
+    let rgain = 1.0 / er.sqrt();
+    for j in 0..n {
+        let l = mid * x[j];
+        let r = y[j];
+        x[j] = lgain * (l - r);
+        y[j] = rgain * (l + r);
+    }
+}
+
+struct BandCtx<'a, 'b> {
+    dec: &'a mut RangeDecoder<'b>,
+    i: usize,
+    intensity: usize,
+    spread: i32,
+    tf_change: i32,
+    remaining_bits: i32,
+    seed: u32,
+    disable_inv: bool,
+}
+
+struct Split {
+    inv: bool,
+    imid: i32,
+    iside: i32,
+    delta: i32,
+    itheta: i32,
+    qalloc: i32,
+}
+
+impl BandCtx<'_, '_> {
+    #[allow(clippy::too_many_arguments)]
+    fn compute_theta(
+        &mut self,
+        n: usize,
+        b: &mut i32,
+        blocks: usize,
+        blocks0: usize,
+        lm: i32,
+        stereo: bool,
+        fill: &mut u32,
+    ) -> Split {
+        let i = self.i;
+        let pulse_cap = LOG_N[i] as i32 + lm * (1 << BITRES);
+        let offset = (pulse_cap >> 1)
+            - match stereo && n == 2 {
+                true => QTHETA_OFFSET_TWOPHASE,
+                false => QTHETA_OFFSET,
+            };
+
+        let mut qn = compute_qn(n, *b, offset, pulse_cap, stereo);
+        if stereo && i >= self.intensity {
+            qn = 1;
+        }
+
+        let tell = self.dec.tell_frac() as i32;
+        let mut itheta = 0;
+        let mut inv = false;
+        if qn != 1 {
+            if stereo && n > 2 {
+                let p0 = 3;
+                let x0 = qn / 2;
+                let ft = p0 * (x0 + 1) + x0;
+                let fs = self.dec.decode(ft as u32) as i32;
+                let x = match fs < (x0 + 1) * p0 {
+                    true => fs / p0,
+                    false => x0 + 1 + (fs - (x0 + 1) * p0),
+                };
+
+                let (fl, fh) = match x <= x0 {
+                    true => (p0 * x, p0 * (x + 1)),
+                    false => ((x - 1 - x0) + (x0 + 1) * p0, (x - x0) + (x0 + 1) * p0),
+                };
+
+                self.dec.update(fl as u32, fh as u32, ft as u32);
+                itheta = x;
+            } else if blocks0 > 1 || stereo {
+                itheta = self.dec.uint(qn as u32 + 1) as i32;
+            } else {
+                let half = qn >> 1;
+                let ft = (half + 1) * (half + 1);
+                let fm = self.dec.decode(ft as u32) as i32;
+                let (fl, fs);
+                if fm < ((half * (half + 1)) >> 1) {
+                    itheta = (isqrt32(8 * fm as u32 + 1) as i32 - 1) >> 1;
+                    fs = itheta + 1;
+                    fl = (itheta * (itheta + 1)) >> 1;
+                } else {
+                    itheta = (2 * (qn + 1) - isqrt32(8 * (ft - fm - 1) as u32 + 1) as i32) >> 1;
+                    fs = qn + 1 - itheta;
+                    fl = ft - (((qn + 1 - itheta) * (qn + 2 - itheta)) >> 1);
+                }
+
+                self.dec.update(fl as u32, (fl + fs) as u32, ft as u32);
+            }
+
+            itheta = ((itheta as u32 * 16384) / qn as u32) as i32;
+        } else if stereo {
+            if *b > 2 << BITRES && self.remaining_bits > 2 << BITRES {
+                inv = self.dec.bit_logp(2);
+            }
+
+            if self.disable_inv {
+                inv = false;
+            }
+        }
+
+        let qalloc = self.dec.tell_frac() as i32 - tell;
+        *b -= qalloc;
+
+        let (imid, iside, delta);
+        if itheta == 0 {
+            imid = 32767;
+            iside = 0;
+            *fill &= (1 << blocks) - 1;
+            delta = -16384;
+        } else if itheta == 16384 {
+            imid = 0;
+            iside = 32767;
+            *fill &= ((1 << blocks) - 1) << blocks;
+            delta = 16384;
+        } else {
+            imid = bitexact_cos(itheta);
+            iside = bitexact_cos(16384 - itheta);
+            delta = frac_mul16((n as i32 - 1) << 7, bitexact_log2tan(iside, imid));
+        }
+
+        Split {
+            inv,
+            imid,
+            iside,
+            delta,
+            itheta,
+            qalloc,
+        }
+    }
+
+    fn quant_band_n1(
+        &mut self,
+        x: &mut [f32],
+        y: Option<&mut [f32]>,
+        lowband_out: Option<&mut [f32]>,
+    ) -> u32 {
+        let mut decode_sign = |x: &mut [f32]| {
+            let mut sign = 0;
+            if self.remaining_bits >= 1 << BITRES {
+                sign = self.dec.bits(1);
+                self.remaining_bits -= 1 << BITRES;
+            }
+
+            x[0] = if sign != 0 { -1.0 } else { 1.0 };
+        };
+
+        decode_sign(x);
+        if let Some(y) = y {
+            decode_sign(y);
+        }
+
+        if let Some(lowband_out) = lowband_out {
+            lowband_out[0] = x[0];
+        }
+
+        1
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn quant_partition(
+        &mut self,
+        x: &mut [f32],
+        n: usize,
+        mut b: i32,
+        mut blocks: usize,
+        lowband: Option<&[f32]>,
+        mut lm: i32,
+        gain: f32,
+        mut fill: u32,
+    ) -> u32 {
+        let i = self.i;
+        let blocks0 = blocks;
+        let cache = &CACHE_BITS[CACHE_INDEX[(lm + 1) as usize * NB_EBANDS + i] as usize..];
+        if lm != -1 && b > cache[cache[0] as usize] as i32 + 12 && n > 2 {
+            let n = n >> 1;
+            let (x, y) = x.split_at_mut(n);
+            lm -= 1;
+            if blocks == 1 {
+                fill = (fill & 1) | (fill << 1);
+            }
+
+            blocks = (blocks + 1) >> 1;
+            let split = self.compute_theta(n, &mut b, blocks, blocks0, lm, false, &mut fill);
+            let mid = (1.0 / 32768.0) * split.imid as f32;
+            let side = (1.0 / 32768.0) * split.iside as f32;
+            let mut delta = split.delta;
+            if blocks0 > 1 && (split.itheta & 0x3fff) != 0 {
+                if split.itheta > 8192 {
+                    delta -= delta >> (4 - lm);
+                } else {
+                    delta = 0.min(delta + ((n as i32) << BITRES >> (5 - lm)));
+                }
+            }
+
+            let mut mbits = 0.max(b.min((b - delta) / 2));
+            let mut sbits = b - mbits;
+            self.remaining_bits -= split.qalloc;
+
+            let next_lowband2 = lowband.map(|lowband| &lowband[n..]);
+            let mut rebalance = self.remaining_bits;
+            let mut cm;
+            if mbits >= sbits {
+                cm = self.quant_partition(x, n, mbits, blocks, lowband, lm, gain * mid, fill);
+                rebalance = mbits - (rebalance - self.remaining_bits);
+                if rebalance > 3 << BITRES && split.itheta != 0 {
+                    sbits += rebalance - (3 << BITRES);
+                }
+
+                cm |= self.quant_partition(
+                    y,
+                    n,
+                    sbits,
+                    blocks,
+                    next_lowband2,
+                    lm,
+                    gain * side,
+                    fill >> blocks,
+                ) << (blocks0 >> 1);
+            } else {
+                cm = self.quant_partition(
+                    y,
+                    n,
+                    sbits,
+                    blocks,
+                    next_lowband2,
+                    lm,
+                    gain * side,
+                    fill >> blocks,
+                ) << (blocks0 >> 1);
+                rebalance = sbits - (rebalance - self.remaining_bits);
+                if rebalance > 3 << BITRES && split.itheta != 16384 {
+                    mbits += rebalance - (3 << BITRES);
+                }
+
+                cm |= self.quant_partition(x, n, mbits, blocks, lowband, lm, gain * mid, fill);
+            }
+
+            return cm;
+        }
+
+        let mut q = bits2pulses(i, lm, b);
+        let mut curr_bits = pulses2bits(i, lm, q);
+        self.remaining_bits -= curr_bits;
+        while self.remaining_bits < 0 && q > 0 {
+            self.remaining_bits += curr_bits;
+            q -= 1;
+            curr_bits = pulses2bits(i, lm, q);
+            self.remaining_bits -= curr_bits;
+        }
+
+        if q != 0 {
+            let k = get_pulses(q);
+            return alg_unquant(x, n, k, self.spread, blocks, self.dec, gain);
+        }
+
+        let cm_mask = (1u32 << blocks) - 1;
+        fill &= cm_mask;
+        if fill == 0 {
+            x[..n].fill(0.0);
+            return 0;
+        }
+
+        let cm = match lowband {
+            None => {
+                for x in x[..n].iter_mut() {
+                    self.seed = lcg_rand(self.seed);
+                    *x = ((self.seed as i32) >> 20) as f32;
+                }
+
+                cm_mask
+            }
+            Some(lowband) => {
+                for (x, &l) in x[..n].iter_mut().zip(lowband) {
+                    self.seed = lcg_rand(self.seed);
+                    let tmp = match self.seed & 0x8000 {
+                        0 => -1.0 / 256.0,
+                        _ => 1.0 / 256.0,
+                    };
+
+                    *x = l + tmp;
+                }
+
+                fill
+            }
+        };
+
+        renormalise_vector(&mut x[..n], gain);
+        cm
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn quant_band(
+        &mut self,
+        x: &mut [f32],
+        n: usize,
+        b: i32,
+        mut blocks: usize,
+        mut lowband: Option<&mut [f32]>,
+        lm: i32,
+        lowband_out: Option<&mut [f32]>,
+        gain: f32,
+        mut fill: u32,
+    ) -> u32 {
+        let n0 = n;
+        let blocks0 = blocks;
+        let long_blocks = blocks0 == 1;
+        let mut n_b = n / blocks;
+        let mut tf_change = self.tf_change;
+        let mut time_divide = 0;
+
+        if n == 1 {
+            return self.quant_band_n1(x, None, lowband_out);
+        }
+
+        let recombine = tf_change.max(0) as usize;
+        for k in 0..recombine {
+            if let Some(lowband) = lowband.as_deref_mut() {
+                haar1(lowband, n >> k, 1 << k);
+            }
+
+            fill = BIT_INTERLEAVE_TABLE[(fill & 0xF) as usize]
+                | BIT_INTERLEAVE_TABLE[(fill >> 4) as usize] << 2;
+        }
+
+        blocks >>= recombine;
+        n_b <<= recombine;
+
+        while (n_b & 1) == 0 && tf_change < 0 {
+            if let Some(lowband) = lowband.as_deref_mut() {
+                haar1(lowband, n_b, blocks);
+            }
+
+            fill |= fill << blocks;
+            blocks <<= 1;
+            n_b >>= 1;
+            time_divide += 1;
+            tf_change += 1;
+        }
+
+        let blocks0 = blocks;
+        let n_b0 = n_b;
+        if blocks0 > 1 {
+            if let Some(lowband) = lowband.as_deref_mut() {
+                deinterleave_hadamard(lowband, n_b >> recombine, blocks0 << recombine, long_blocks);
+            }
+        }
+
+        let mut cm = self.quant_partition(x, n, b, blocks, lowband.as_deref(), lm, gain, fill);
+
+        if blocks0 > 1 {
+            interleave_hadamard(x, n_b >> recombine, blocks0 << recombine, long_blocks);
+        }
+
+        let mut n_b = n_b0;
+        let mut blocks = blocks0;
+        for _ in 0..time_divide {
+            blocks >>= 1;
+            n_b <<= 1;
+            cm |= cm >> blocks;
+            haar1(x, n_b, blocks);
+        }
+
+        for k in 0..recombine {
+            cm = BIT_DEINTERLEAVE_TABLE[cm as usize];
+            haar1(x, n0 >> k, 1 << k);
+        }
+
+        blocks <<= recombine;
+        if let Some(lowband_out) = lowband_out {
+            let n = (n0 as f32).sqrt();
+            for (out, &x) in lowband_out[..n0].iter_mut().zip(x.iter()) {
+                *out = n * x;
+            }
+        }
+
+        cm & ((1 << blocks) - 1)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn quant_band_stereo(
+        &mut self,
+        x: &mut [f32],
+        y: &mut [f32],
+        n: usize,
+        mut b: i32,
+        blocks: usize,
+        lowband: Option<&mut [f32]>,
+        lm: i32,
+        lowband_out: Option<&mut [f32]>,
+        mut fill: u32,
+    ) -> u32 {
+        if n == 1 {
+            return self.quant_band_n1(x, Some(y), lowband_out);
+        }
+
+        let orig_fill = fill;
+        let split = self.compute_theta(n, &mut b, blocks, blocks, lm, true, &mut fill);
+        let mid = (1.0 / 32768.0) * split.imid as f32;
+        let side = (1.0 / 32768.0) * split.iside as f32;
+
+        let mut cm;
+        if n == 2 {
+            let mut mbits = b;
+            let mut sbits = 0;
+            if split.itheta != 0 && split.itheta != 16384 {
+                sbits = 1 << BITRES;
+            }
+
+            mbits -= sbits;
+            let swap = split.itheta > 8192;
+            self.remaining_bits -= split.qalloc + sbits;
+
+            let mut sign = 0;
+            if sbits != 0 {
+                sign = self.dec.bits(1) as i32;
+            }
+
+            let sign = (1 - 2 * sign) as f32;
+            let (x2, y2) = match swap {
+                true => (&mut *y, &mut *x),
+                false => (&mut *x, &mut *y),
+            };
+
+            cm = self.quant_band(
+                x2,
+                n,
+                mbits,
+                blocks,
+                lowband,
+                lm,
+                lowband_out,
+                1.0,
+                orig_fill,
+            );
+            y2[0] = -sign * x2[1];
+            y2[1] = sign * x2[0];
+
+            x[0] *= mid;
+            x[1] *= mid;
+            y[0] *= side;
+            y[1] *= side;
+            let tmp = x[0];
+            x[0] = tmp - y[0];
+            y[0] += tmp;
+            let tmp = x[1];
+            x[1] = tmp - y[1];
+            y[1] += tmp;
+        } else {
+            let mut mbits = 0.max(b.min((b - split.delta) / 2));
+            let mut sbits = b - mbits;
+            self.remaining_bits -= split.qalloc;
+
+            let mut rebalance = self.remaining_bits;
+            if mbits >= sbits {
+                cm = self.quant_band(x, n, mbits, blocks, lowband, lm, lowband_out, 1.0, fill);
+                rebalance = mbits - (rebalance - self.remaining_bits);
+                if rebalance > 3 << BITRES && split.itheta != 0 {
+                    sbits += rebalance - (3 << BITRES);
+                }
+
+                cm |= self.quant_band(y, n, sbits, blocks, None, lm, None, side, fill >> blocks);
+            } else {
+                cm = self.quant_band(y, n, sbits, blocks, None, lm, None, side, fill >> blocks);
+                rebalance = sbits - (rebalance - self.remaining_bits);
+                if rebalance > 3 << BITRES && split.itheta != 16384 {
+                    mbits += rebalance - (3 << BITRES);
+                }
+
+                cm |= self.quant_band(x, n, mbits, blocks, lowband, lm, lowband_out, 1.0, fill);
+            }
+
+            stereo_merge(x, y, mid, n);
+        }
+
+        if split.inv {
+            y[..n].iter_mut().for_each(|y| *y = -*y);
+        }
+
+        cm
+    }
+}
+
+/// Copies the band that gets folded into the current one, since decoding may reorder it in place.
+fn copy_lowband<'a>(
+    buf: &'a mut [f32; MAX_BAND_SIZE],
+    norm: &[f32],
+    lowband: Option<usize>,
+    n: usize,
+) -> Option<&'a mut [f32]> {
+    lowband.map(|lowband| {
+        buf[..n].copy_from_slice(&norm[lowband..lowband + n]);
+        &mut buf[..n]
+    })
+}
+
+/// The decoded shape of every band, along with the state that carries over to the next frame.
+pub(crate) struct BandsParams<'a> {
+    pub start: usize,
+    pub end: usize,
+    pub pulses: &'a [i32; NB_EBANDS],
+    pub short_blocks: bool,
+    pub spread: i32,
+    pub dual_stereo: bool,
+    pub intensity: usize,
+    pub tf_res: &'a [i32; NB_EBANDS],
+    pub total_bits: i32,
+    pub balance: i32,
+    pub lm: i32,
+    pub coded_bands: usize,
+    pub disable_inv: bool,
+}
+
+/// Decodes the normalised spectrum of every band in `start..end`, folding lower bands into any
+/// band that received no pulses.
+pub(crate) fn quant_all_bands(
+    params: &BandsParams,
+    x_: &mut [f32],
+    mut y_: Option<&mut [f32]>,
+    collapse_masks: &mut [u8],
+    seed: &mut u32,
+    dec: &mut RangeDecoder,
+) {
+    let &BandsParams {
+        start,
+        end,
+        pulses,
+        short_blocks,
+        spread,
+        mut dual_stereo,
+        intensity,
+        tf_res,
+        total_bits,
+        mut balance,
+        lm,
+        coded_bands,
+        disable_inv,
+    } = params;
+
+    let m = 1usize << lm;
+    let c = if y_.is_some() { 2 } else { 1 };
+    let blocks = if short_blocks { m } else { 1 };
+    let norm_offset = m * EBANDS[start] as usize;
+    let norm_len = m * EBANDS[NB_EBANDS - 1] as usize - norm_offset;
+    let mut norm = vec![0.0f32; norm_len];
+    let mut norm2 = vec![0.0f32; norm_len];
+    let mut lowband_offset = 0;
+    let mut update_lowband = true;
+
+    let mut ctx = BandCtx {
+        dec,
+        i: 0,
+        intensity,
+        spread,
+        tf_change: 0,
+        remaining_bits: 0,
+        seed: *seed,
+        disable_inv,
+    };
+
+    for i in start..end {
+        ctx.i = i;
+        let last = i == end - 1;
+        let band_start = m * EBANDS[i] as usize;
+        let n = m * EBANDS[i + 1] as usize - band_start;
+
+        let tell = ctx.dec.tell_frac() as i32;
+        if i != start {
+            balance -= tell;
+        }
+
+        let remaining_bits = total_bits - tell - 1;
+        ctx.remaining_bits = remaining_bits;
+        let b = match i < coded_bands {
+            true => {
+                let curr_balance = balance / 3.min(coded_bands as i32 - i as i32);
+                0.max(16383.min((remaining_bits + 1).min(pulses[i] + curr_balance)))
+            }
+            false => 0,
+        };
+
+        if (band_start as i32 - n as i32 >= norm_offset as i32 || i == start + 1)
+            && (update_lowband || lowband_offset == 0)
+        {
+            lowband_offset = i;
+        }
+
+        if i == start + 1 {
+            let n1 = m * (EBANDS[start + 1] - EBANDS[start]) as usize;
+            let n2 = m * (EBANDS[start + 2] - EBANDS[start + 1]) as usize;
+            if n2 > n1 {
+                norm.copy_within(2 * n1 - n2..n1, n1);
+                if dual_stereo {
+                    norm2.copy_within(2 * n1 - n2..n1, n1);
+                }
+            }
+        }
+
+        let tf_change = tf_res[i];
+        ctx.tf_change = tf_change;
+
+        let mut effective_lowband = None;
+        let mut x_cm;
+        let mut y_cm;
+        if lowband_offset != 0 && (spread != SPREAD_AGGRESSIVE || blocks > 1 || tf_change < 0) {
+            let lowband = 0
+                .max(m as i32 * EBANDS[lowband_offset] as i32 - norm_offset as i32 - n as i32)
+                as usize;
+            effective_lowband = Some(lowband);
+
+            let mut fold_start = lowband_offset;
+            loop {
+                fold_start -= 1;
+                if m * (EBANDS[fold_start] as usize) <= lowband + norm_offset {
+                    break;
+                }
+            }
+
+            let mut fold_end = lowband_offset - 1;
+            loop {
+                fold_end += 1;
+                if fold_end >= i || m * EBANDS[fold_end] as usize >= lowband + norm_offset + n {
+                    break;
+                }
+            }
+
+            x_cm = 0;
+            y_cm = 0;
+            for fold_i in fold_start..fold_end.max(fold_start + 1) {
+                x_cm |= collapse_masks[fold_i * c] as u32;
+                y_cm |= collapse_masks[fold_i * c + c - 1] as u32;
+            }
+        } else {
+            x_cm = (1 << blocks) - 1;
+            y_cm = x_cm;
+        }
+
+        if dual_stereo && i == intensity {
+            dual_stereo = false;
+            for j in 0..band_start - norm_offset {
+                norm[j] = 0.5 * (norm[j] + norm2[j]);
+            }
+        }
+
+        let x = &mut x_[band_start..band_start + n];
+        let out_start = band_start - norm_offset;
+        let mut lowband_buf = [0.0; MAX_BAND_SIZE];
+        if dual_stereo {
+            let y = &mut y_.as_deref_mut().unwrap()[band_start..band_start + n];
+            let lowband = copy_lowband(&mut lowband_buf, &norm, effective_lowband, n);
+            let out = (!last).then(|| &mut norm[out_start..out_start + n]);
+            x_cm = ctx.quant_band(x, n, b / 2, blocks, lowband, lm, out, 1.0, x_cm);
+
+            let lowband = copy_lowband(&mut lowband_buf, &norm2, effective_lowband, n);
+            let out = (!last).then(|| &mut norm2[out_start..out_start + n]);
+            y_cm = ctx.quant_band(y, n, b / 2, blocks, lowband, lm, out, 1.0, y_cm);
+        } else {
+            let lowband = copy_lowband(&mut lowband_buf, &norm, effective_lowband, n);
+            let out = (!last).then(|| &mut norm[out_start..out_start + n]);
+            x_cm = match y_.as_deref_mut() {
+                Some(y_) => {
+                    let y = &mut y_[band_start..band_start + n];
+                    ctx.quant_band_stereo(x, y, n, b, blocks, lowband, lm, out, x_cm | y_cm)
+                }
+                None => ctx.quant_band(x, n, b, blocks, lowband, lm, out, 1.0, x_cm | y_cm),
+            };
+
+            y_cm = x_cm;
+        }
+
+        collapse_masks[i * c] = x_cm as u8;
+        collapse_masks[i * c + c - 1] = y_cm as u8;
+        balance += pulses[i] + tell;
+        update_lowband = b > (n << BITRES) as i32;
+    }
+
+    *seed = ctx.seed;
+}
+
+/// Scales the normalised spectrum of each band by its decoded energy.
+pub(crate) fn denormalise_bands(
+    x: &[f32],
+    freq: &mut [f32],
+    band_log_e: &[f32],
+    start: usize,
+    end: usize,
+    m: usize,
+    silence: bool,
+) {
+    let n = m * 120;
+    let (start, end, bound) = match silence {
+        true => (0, 0, 0),
+        false => (start, end, m * EBANDS[end] as usize),
+    };
+
+    freq[..m * EBANDS[start] as usize].fill(0.0);
+    for i in start..end {
+        let band = m * EBANDS[i] as usize..m * EBANDS[i + 1] as usize;
+        let lg = band_log_e[i] + E_MEANS[i];
+        let g = exp2(lg.min(32.0));
+        for j in band {
+            freq[j] = x[j] * g;
+        }
+    }
+
+    freq[bound..n].fill(0.0);
+}
+
+/// Fills short blocks that received no pulses with noise, so transients do not leave holes.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn anti_collapse(
+    x_: &mut [f32],
+    collapse_masks: &[u8],
+    lm: i32,
+    channels: usize,
+    size: usize,
+    start: usize,
+    end: usize,
+    log_e: &[f32],
+    prev1_log_e: &[f32],
+    prev2_log_e: &[f32],
+    pulses: &[i32],
+    mut seed: u32,
+) {
+    for i in start..end {
+        let n0 = (EBANDS[i + 1] - EBANDS[i]) as usize;
+        let depth = (((1 + pulses[i]) as u32 / n0 as u32) >> lm) as i32;
+        let thresh = 0.5 * exp2(-0.125 * depth as f32);
+        let sqrt_1 = 1.0 / ((n0 << lm) as f64).sqrt() as f32;
+
+        for c in 0..channels {
+            let mut prev1 = prev1_log_e[c * NB_EBANDS + i];
+            let mut prev2 = prev2_log_e[c * NB_EBANDS + i];
+            if channels == 1 {
+                prev1 = prev1.max(prev1_log_e[NB_EBANDS + i]);
+                prev2 = prev2.max(prev2_log_e[NB_EBANDS + i]);
+            }
+
+            let ediff = (log_e[c * NB_EBANDS + i] - prev1.min(prev2)).max(0.0);
+            let mut r = 2.0 * exp2(-ediff);
+            if lm == 3 {
+                r *= std::f32::consts::SQRT_2;
+            }
+
+            let r = thresh.min(r) * sqrt_1;
+            let x = &mut x_[c * size + ((EBANDS[i] as usize) << lm)..];
+            let mut renormalize = false;
+            for k in 0..1 << lm {
+                if collapse_masks[i * channels + c] & (1 << k) == 0 {
+                    for j in 0..n0 {
+                        seed = lcg_rand(seed);
+                        x[(j << lm) + k] = if seed & 0x8000 != 0 { r } else { -r };
+                    }
+
+                    renormalize = true;
+                }
+            }
+
+            if renormalize {
+                renormalise_vector(&mut x[..n0 << lm], 1.0);
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use crate::range::RangeDecoder;
+
+const MAX_PULSES: usize = 128;
+
+/// Computes the next row of a recurrence of the form `u[i][j] = u[i-1][j] + u[i][j-1] +
+/// u[i-1][j-1]`, where `ui0` is the first value of the new row.
+fn unext(u: &mut [u32], mut ui0: u32) {
+    for j in 1..u.len() {
+        let ui1 = u[j].wrapping_add(u[j - 1]).wrapping_add(ui0);
+        u[j - 1] = ui0;
+        ui0 = ui1;
+    }
+
+    u[u.len() - 1] = ui0;
+}
+
+/// Computes the previous row of the same recurrence as [`unext`].
+fn uprev(u: &mut [u32], mut ui0: u32) {
+    for j in 1..u.len() {
+        let ui1 = u[j].wrapping_sub(u[j - 1]).wrapping_sub(ui0);
+        u[j - 1] = ui0;
+        ui0 = ui1;
+    }
+
+    u[u.len() - 1] = ui0;
+}
+
+/// Fills `u` with `U(n, 0..=k+1)` and returns `V(n, k)`, the number of codewords.
+fn ncwrs_urow(n: usize, k: usize, u: &mut [u32]) -> u32 {
+    let len = k + 2;
+    u[0] = 0;
+    u[1] = 1;
+    for (i, u) in u.iter_mut().enumerate().take(len).skip(2) {
+        *u = ((i << 1) - 1) as u32;
+    }
+
+    for _ in 2..n {
+        unext(&mut u[1..k + 2], 1);
+    }
+
+    u[k].wrapping_add(u[k + 1])
+}
+
+/// Expands codeword `i` into `n` signed pulses summing to `k` in magnitude, returning the energy
+/// of the resulting vector.
+fn cwrsi(n: usize, mut k: usize, mut i: u32, y: &mut [i32], u: &mut [u32]) -> f32 {
+    let mut yy = 0.0;
+    for y in y.iter_mut().take(n) {
+        let p = u[k + 1];
+        let s = -((i >= p) as i32);
+        i -= p & s as u32;
+
+        let yj = k as i32;
+        let mut p = u[k];
+        while p > i {
+            k -= 1;
+            p = u[k];
+        }
+
+        i -= p;
+        let val = ((yj - k as i32) + s) ^ s;
+        *y = val;
+        yy += (val * val) as f32;
+        uprev(&mut u[..k + 2], 0);
+    }
+
+    yy
+}
+
+/// Decodes the pulse vector of a band with `n` coefficients and `k` pulses.
+pub(crate) fn decode_pulses(y: &mut [i32], n: usize, k: usize, dec: &mut RangeDecoder) -> f32 {
+    let mut u = [0u32; MAX_PULSES + 2];
+    let total = ncwrs_urow(n, k, &mut u);
+    let i = dec.uint(total);
+    cwrsi(n, k, i, y, &mut u)
+}
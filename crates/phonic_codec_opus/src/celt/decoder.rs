@@ -0,0 +1,784 @@
+use crate::{
+    celt::{
+        bands::{anti_collapse, denormalise_bands, lcg_rand, quant_all_bands, BandsParams},
+        energy::{unquant_coarse_energy, unquant_energy_finalise, unquant_fine_energy},
+        lpc::{autocorr, fir, iir, lpc, pitch_downsample, pitch_search, LPC_ORDER},
+        mdct::Mdct,
+        rate::compute_allocation,
+        tables::{CACHE_CAPS, EBANDS, WINDOW},
+        vq::renormalise_vector,
+        NB_EBANDS, SPREAD_NORMAL,
+    },
+    range::{RangeDecoder, BITRES},
+};
+use std::cmp::Ordering;
+
+const OVERLAP: usize = 120;
+const SHORT_MDCT_SIZE: usize = 120;
+const DECODE_BUFFER_SIZE: usize = 2048;
+const MAX_PERIOD: usize = 1024;
+const PLC_PITCH_LAG_MAX: usize = 720;
+const PLC_PITCH_LAG_MIN: usize = 100;
+const COMBFILTER_MINPERIOD: usize = 15;
+const PREEMPH: f32 = 0.8500061;
+const VERY_SMALL: f32 = 1e-30;
+
+const TRIM_ICDF: [u8; 11] = [126, 124, 119, 109, 87, 41, 19, 9, 4, 2, 0];
+const SPREAD_ICDF: [u8; 4] = [25, 23, 2, 0];
+const TAPSET_ICDF: [u8; 3] = [2, 1, 0];
+
+const TF_SELECT_TABLE: [[i8; 8]; 4] = [
+    [0, -1, 0, -1, 0, -1, 0, -1],
+    [0, -1, 0, -2, 1, 0, 1, -1],
+    [0, -2, 0, -3, 2, 0, 1, -1],
+    [0, -2, 0, -3, 3, 0, 1, -1],
+];
+
+const COMB_GAINS: [[f32; 3]; 3] = [
+    [0.30664062, 0.21704102, 0.12963867],
+    [0.4638672, 0.2680664, 0.0],
+    [0.7998047, 0.100097656, 0.0],
+];
+
+/// The parameters of one side of a pitch post-filter transition.
+#[derive(Clone, Copy)]
+struct PostFilter {
+    period: usize,
+    gain: f32,
+    tapset: usize,
+}
+
+/// Runs the pitch post-filter over `n` samples of `buf` starting at `x`, cross-fading from `from`
+/// to `to` over the first `overlap` samples. The filter runs in place unless `out` is given.
+fn comb_filter(
+    buf: &mut [f32],
+    x: usize,
+    mut out: Option<&mut [f32]>,
+    n: usize,
+    from: PostFilter,
+    to: PostFilter,
+    mut overlap: usize,
+) {
+    if from.gain == 0.0 && to.gain == 0.0 {
+        if let Some(out) = out {
+            out[..n].copy_from_slice(&buf[x..x + n]);
+        }
+
+        return;
+    }
+
+    let t0 = from.period.max(COMBFILTER_MINPERIOD);
+    let t1 = to.period.max(COMBFILTER_MINPERIOD);
+    let g0 = COMB_GAINS[from.tapset].map(|g| from.gain * g);
+    let g1 = COMB_GAINS[to.tapset].map(|g| to.gain * g);
+
+    let mut x1 = buf[x - t1 + 1];
+    let mut x2 = buf[x - t1];
+    let mut x3 = buf[x - t1 - 1];
+    let mut x4 = buf[x - t1 - 2];
+
+    if from.gain == to.gain && t0 == t1 && from.tapset == to.tapset {
+        overlap = 0;
+    }
+
+    for i in 0..overlap {
+        let x0 = buf[x + i - t1 + 2];
+        let f = WINDOW[i] * WINDOW[i];
+        let p = x + i - t0;
+        let y = buf[x + i]
+            + ((1.0 - f) * g0[0]) * buf[p]
+            + ((1.0 - f) * g0[1]) * (buf[p + 1] + buf[p - 1])
+            + ((1.0 - f) * g0[2]) * (buf[p + 2] + buf[p - 2])
+            + (f * g1[0]) * x2
+            + (f * g1[1]) * (x1 + x3)
+            + (f * g1[2]) * (x0 + x4);
+
+        match out {
+            Some(ref mut out) => out[i] = y,
+            None => buf[x + i] = y,
+        }
+
+        x4 = x3;
+        x3 = x2;
+        x2 = x1;
+        x1 = x0;
+    }
+
+    if to.gain == 0.0 {
+        if let Some(out) = out {
+            out[overlap..n].copy_from_slice(&buf[x + overlap..x + n]);
+        }
+
+        return;
+    }
+
+    for i in overlap..n {
+        let x0 = buf[x + i - t1 + 2];
+        let y = buf[x + i] + g1[0] * x2 + g1[1] * (x1 + x3) + g1[2] * (x0 + x4);
+        match out {
+            Some(ref mut out) => out[i] = y,
+            None => buf[x + i] = y,
+        }
+
+        x4 = x3;
+        x3 = x2;
+        x2 = x1;
+        x1 = x0;
+    }
+}
+
+fn tf_decode(
+    start: usize,
+    end: usize,
+    transient: bool,
+    tf_res: &mut [i32; NB_EBANDS],
+    lm: usize,
+    dec: &mut RangeDecoder,
+) {
+    let mut budget = dec.storage as u32 * 8;
+    let mut tell = dec.tell() as u32;
+    let mut logp = if transient { 2 } else { 4 };
+    let tf_select_rsv = lm > 0 && tell + logp < budget;
+    budget -= tf_select_rsv as u32;
+
+    let mut tf_changed = 0;
+    let mut curr = 0;
+    for tf_res in &mut tf_res[start..end] {
+        if tell + logp <= budget {
+            curr ^= dec.bit_logp(logp) as i32;
+            tell = dec.tell() as u32;
+            tf_changed |= curr;
+        }
+
+        *tf_res = curr;
+        logp = if transient { 4 } else { 5 };
+    }
+
+    let table = &TF_SELECT_TABLE[lm][4 * transient as usize..];
+    let tf_changed = tf_changed as usize;
+    let mut tf_select = 0;
+    if tf_select_rsv && table[tf_changed] != table[2 + tf_changed] {
+        tf_select = dec.bit_logp(1) as usize;
+    }
+
+    for tf_res in &mut tf_res[start..end] {
+        *tf_res = table[2 * tf_select + *tf_res as usize] as i32;
+    }
+}
+
+fn init_caps(lm: usize, channels: usize) -> [i32; NB_EBANDS] {
+    std::array::from_fn(|i| {
+        let n = ((EBANDS[i + 1] - EBANDS[i]) as i32) << lm;
+        let cap = CACHE_CAPS[NB_EBANDS * (2 * lm + channels - 1) + i] as i32;
+        ((cap + 64) * channels as i32 * n) >> 2
+    })
+}
+
+/// The celt layer of an opus decoder, always running at 48 kHz.
+pub(crate) struct CeltDecoder {
+    mdct: Mdct,
+    channels: usize,
+    stream_channels: usize,
+    start: usize,
+    end: usize,
+    disable_inv: bool,
+    rng: u32,
+    last_pitch_index: usize,
+    loss_count: u32,
+    skip_plc: bool,
+    postfilter: PostFilter,
+    postfilter_old: PostFilter,
+    preemph_mem: [f32; 2],
+    decode_mem: Vec<Vec<f32>>,
+    lpc: [[f32; LPC_ORDER]; 2],
+    old_band_e: [f32; 2 * NB_EBANDS],
+    old_log_e: [f32; 2 * NB_EBANDS],
+    old_log_e2: [f32; 2 * NB_EBANDS],
+    background_log_e: [f32; 2 * NB_EBANDS],
+}
+
+impl CeltDecoder {
+    pub fn new(channels: usize) -> Self {
+        let postfilter = PostFilter {
+            period: 0,
+            gain: 0.0,
+            tapset: 0,
+        };
+
+        let mut decoder = Self {
+            mdct: Mdct::new(),
+            channels,
+            stream_channels: channels,
+            start: 0,
+            end: NB_EBANDS,
+            disable_inv: channels == 1,
+            rng: 0,
+            last_pitch_index: 0,
+            loss_count: 0,
+            skip_plc: false,
+            postfilter,
+            postfilter_old: postfilter,
+            preemph_mem: [0.0; 2],
+            decode_mem: vec![vec![0.0; DECODE_BUFFER_SIZE + OVERLAP]; channels],
+            lpc: [[0.0; LPC_ORDER]; 2],
+            old_band_e: [0.0; 2 * NB_EBANDS],
+            old_log_e: [0.0; 2 * NB_EBANDS],
+            old_log_e2: [0.0; 2 * NB_EBANDS],
+            background_log_e: [0.0; 2 * NB_EBANDS],
+        };
+
+        decoder.reset();
+        decoder
+    }
+
+    pub fn reset(&mut self) {
+        self.rng = 0;
+        self.last_pitch_index = 0;
+        self.loss_count = 0;
+        self.skip_plc = true;
+        self.postfilter.period = 0;
+        self.postfilter.gain = 0.0;
+        self.postfilter.tapset = 0;
+        self.postfilter_old = self.postfilter;
+        self.preemph_mem = [0.0; 2];
+        self.decode_mem.iter_mut().for_each(|mem| mem.fill(0.0));
+        self.lpc = [[0.0; LPC_ORDER]; 2];
+        self.old_band_e.fill(0.0);
+        self.old_log_e.fill(-28.0);
+        self.old_log_e2.fill(-28.0);
+        self.background_log_e.fill(0.0);
+    }
+
+    /// The final state of the range decoder after the last decoded frame.
+    pub fn rng(&self) -> u32 {
+        self.rng
+    }
+
+    pub fn set_start_band(&mut self, start: usize) {
+        self.start = start;
+    }
+
+    pub fn set_end_band(&mut self, end: usize) {
+        self.end = end;
+    }
+
+    pub fn set_stream_channels(&mut self, channels: usize) {
+        self.stream_channels = channels;
+    }
+
+    /// Decodes a frame that is coded on its own.
+    pub fn decode(&mut self, data: &[u8], pcm: &mut [f32], frame_size: usize) {
+        let mut dec = RangeDecoder::new(data);
+        self.decode_with_ec(Some(&mut dec), data.len(), pcm, frame_size);
+    }
+
+    /// Decodes `frame_size` interleaved samples into `pcm` from the `len` byte frame being read by
+    /// `dec`, or conceals a lost frame if there is none.
+    pub fn decode_with_ec(
+        &mut self,
+        dec: Option<&mut RangeDecoder>,
+        len: usize,
+        pcm: &mut [f32],
+        frame_size: usize,
+    ) {
+        let lm = (frame_size / SHORT_MDCT_SIZE).trailing_zeros() as usize;
+        let m = 1 << lm;
+        let n = frame_size;
+        let c = self.stream_channels;
+        let (start, end) = (self.start, self.end);
+
+        let dec = match dec {
+            Some(dec) if len > 1 => dec,
+            _ => {
+                self.decode_lost(n, lm);
+                self.deemphasis(pcm, n);
+                return;
+            }
+        };
+
+        self.skip_plc = self.loss_count != 0;
+        if c == 1 {
+            for i in 0..NB_EBANDS {
+                self.old_band_e[i] = self.old_band_e[i].max(self.old_band_e[NB_EBANDS + i]);
+            }
+        }
+
+        let total_bits = len as i32 * 8;
+        let mut tell = dec.tell();
+        let silence = match tell {
+            _ if tell >= total_bits => true,
+            1 => dec.bit_logp(15),
+            _ => false,
+        };
+
+        if silence {
+            dec.skip_to_end(len);
+            tell = total_bits;
+        }
+
+        let mut postfilter = PostFilter {
+            period: 0,
+            gain: 0.0,
+            tapset: 0,
+        };
+
+        if start == 0 && tell + 16 <= total_bits {
+            if dec.bit_logp(1) {
+                let octave = dec.uint(6);
+                postfilter.period = ((16 << octave) + dec.bits(4 + octave) - 1) as usize;
+                let qg = dec.bits(3);
+                if dec.tell() + 2 <= total_bits {
+                    postfilter.tapset = dec.icdf(&TAPSET_ICDF, 2);
+                }
+
+                postfilter.gain = 0.09375 * (qg + 1) as f32;
+            }
+
+            tell = dec.tell();
+        }
+
+        let mut transient = false;
+        if lm > 0 && tell + 3 <= total_bits {
+            transient = dec.bit_logp(3);
+            tell = dec.tell();
+        }
+
+        let intra = tell + 3 <= total_bits && dec.bit_logp(3);
+        unquant_coarse_energy(start, end, &mut self.old_band_e, intra, dec, c, lm);
+
+        let mut tf_res = [0; NB_EBANDS];
+        tf_decode(start, end, transient, &mut tf_res, lm, dec);
+
+        let spread = match dec.tell() + 4 <= total_bits {
+            true => dec.icdf(&SPREAD_ICDF, 5) as i32,
+            false => SPREAD_NORMAL,
+        };
+
+        let cap = init_caps(lm, c);
+        let mut offsets = [0; NB_EBANDS];
+        let mut dynalloc_logp = 6;
+        let mut total_bits = total_bits << BITRES;
+        let mut tell = dec.tell_frac() as i32;
+        for i in start..end {
+            let width = (c as i32 * (EBANDS[i + 1] - EBANDS[i]) as i32) << lm;
+            let quanta = (width << BITRES).min((6 << BITRES).max(width));
+            let mut loop_logp = dynalloc_logp;
+            let mut boost = 0;
+            while tell + (loop_logp << BITRES) < total_bits && boost < cap[i] {
+                let flag = dec.bit_logp(loop_logp as u32);
+                tell = dec.tell_frac() as i32;
+                if !flag {
+                    break;
+                }
+
+                boost += quanta;
+                total_bits -= quanta;
+                loop_logp = 1;
+            }
+
+            offsets[i] = boost;
+            if boost > 0 {
+                dynalloc_logp = (dynalloc_logp - 1).max(2);
+            }
+        }
+
+        let alloc_trim = match tell + (6 << BITRES) <= total_bits {
+            true => dec.icdf(&TRIM_ICDF, 7) as i32,
+            false => 5,
+        };
+
+        let mut bits = ((len as i32 * 8) << BITRES) - dec.tell_frac() as i32 - 1;
+        let anti_collapse_rsv = match transient && lm >= 2 && bits >= (lm as i32 + 2) << BITRES {
+            true => 1 << BITRES,
+            false => 0,
+        };
+
+        bits -= anti_collapse_rsv;
+        let alloc = compute_allocation(
+            start, end, &offsets, &cap, alloc_trim, bits, c, lm as i32, dec,
+        );
+        unquant_fine_energy(start, end, &mut self.old_band_e, &alloc.fine_quant, dec, c);
+
+        for mem in &mut self.decode_mem {
+            mem.copy_within(n..DECODE_BUFFER_SIZE + OVERLAP / 2, 0);
+        }
+
+        let mut collapse_masks = [0u8; 2 * NB_EBANDS];
+        let mut x = [0.0f32; 2 * 960];
+        let (x_, y_) = x.split_at_mut(n);
+        let params = BandsParams {
+            start,
+            end,
+            pulses: &alloc.pulses,
+            short_blocks: transient,
+            spread,
+            dual_stereo: alloc.dual_stereo,
+            intensity: alloc.intensity,
+            tf_res: &tf_res,
+            total_bits: ((len as i32) << (3 + BITRES)) - anti_collapse_rsv,
+            balance: alloc.balance,
+            lm: lm as i32,
+            coded_bands: alloc.coded_bands,
+            disable_inv: self.disable_inv,
+        };
+
+        let y_ = if c == 2 { Some(&mut y_[..n]) } else { None };
+        quant_all_bands(&params, x_, y_, &mut collapse_masks, &mut self.rng, dec);
+
+        let anti_collapse_on = anti_collapse_rsv > 0 && dec.bits(1) != 0;
+        let bits_left = len as i32 * 8 - dec.tell();
+        unquant_energy_finalise(
+            start,
+            end,
+            &mut self.old_band_e,
+            &alloc.fine_quant,
+            &alloc.fine_priority,
+            bits_left,
+            dec,
+            c,
+        );
+
+        if anti_collapse_on {
+            anti_collapse(
+                &mut x,
+                &collapse_masks,
+                lm as i32,
+                c,
+                n,
+                start,
+                end,
+                &self.old_band_e,
+                &self.old_log_e,
+                &self.old_log_e2,
+                &alloc.pulses,
+                self.rng,
+            );
+        }
+
+        if silence {
+            self.old_band_e[..c * NB_EBANDS].fill(-28.0);
+        }
+
+        self.synthesis(&x, start, end, c, transient, lm, silence);
+
+        self.postfilter.period = self.postfilter.period.max(COMBFILTER_MINPERIOD);
+        self.postfilter_old.period = self.postfilter_old.period.max(COMBFILTER_MINPERIOD);
+        for mem in &mut self.decode_mem {
+            let out = DECODE_BUFFER_SIZE - n;
+            comb_filter(
+                mem,
+                out,
+                None,
+                SHORT_MDCT_SIZE,
+                self.postfilter_old,
+                self.postfilter,
+                OVERLAP,
+            );
+            if lm != 0 {
+                let out = out + SHORT_MDCT_SIZE;
+                comb_filter(
+                    mem,
+                    out,
+                    None,
+                    n - SHORT_MDCT_SIZE,
+                    self.postfilter,
+                    postfilter,
+                    OVERLAP,
+                );
+            }
+        }
+
+        self.postfilter_old = self.postfilter;
+        self.postfilter = postfilter;
+        if lm != 0 {
+            self.postfilter_old = self.postfilter;
+        }
+
+        if c == 1 {
+            self.old_band_e.copy_within(..NB_EBANDS, NB_EBANDS);
+        }
+
+        if !transient {
+            self.old_log_e2 = self.old_log_e;
+            self.old_log_e = self.old_band_e;
+            let max_background_increase = if self.loss_count < 10 {
+                m as f32 * 0.001
+            } else {
+                1.0
+            };
+            for (bg, &e) in self.background_log_e.iter_mut().zip(&self.old_band_e) {
+                *bg = (*bg + max_background_increase).min(e);
+            }
+        } else {
+            for (old, &e) in self.old_log_e.iter_mut().zip(&self.old_band_e) {
+                *old = old.min(e);
+            }
+        }
+
+        for c in 0..2 {
+            for i in (0..start).chain(end..NB_EBANDS) {
+                self.old_band_e[c * NB_EBANDS + i] = 0.0;
+                self.old_log_e[c * NB_EBANDS + i] = -28.0;
+                self.old_log_e2[c * NB_EBANDS + i] = -28.0;
+            }
+        }
+
+        self.rng = dec.rng();
+        self.deemphasis(pcm, n);
+        self.loss_count = 0;
+    }
+
+    /// Turns the decoded spectrum into time domain samples at the end of each channel's history.
+    #[allow(clippy::too_many_arguments)]
+    fn synthesis(
+        &mut self,
+        x: &[f32],
+        start: usize,
+        end: usize,
+        c: usize,
+        transient: bool,
+        lm: usize,
+        silence: bool,
+    ) {
+        let m = 1 << lm;
+        let n = SHORT_MDCT_SIZE << lm;
+        let (b, nb, shift) = match transient {
+            true => (m, SHORT_MDCT_SIZE, 3),
+            false => (1, n, 3 - lm),
+        };
+
+        let mdct = &self.mdct;
+        let out = DECODE_BUFFER_SIZE - n;
+        let backward = |freq: &[f32], mem: &mut [f32]| {
+            for i in 0..b {
+                mdct.backward(
+                    &freq[i..],
+                    &mut mem[out + nb * i..],
+                    &WINDOW,
+                    OVERLAP,
+                    shift,
+                    b,
+                );
+            }
+        };
+
+        let mut freq = [0.0f32; 960];
+        let freq = &mut freq[..n];
+        match (self.channels, c) {
+            (2, 1) => {
+                denormalise_bands(x, freq, &self.old_band_e, start, end, m, silence);
+                for mem in &mut self.decode_mem {
+                    backward(freq, mem);
+                }
+            }
+            (1, 2) => {
+                let mut freq2 = [0.0f32; 960];
+                let freq2 = &mut freq2[..n];
+                denormalise_bands(x, freq, &self.old_band_e, start, end, m, silence);
+                denormalise_bands(
+                    &x[n..],
+                    freq2,
+                    &self.old_band_e[NB_EBANDS..],
+                    start,
+                    end,
+                    m,
+                    silence,
+                );
+                for (f, f2) in freq.iter_mut().zip(freq2.iter()) {
+                    *f = 0.5 * *f + 0.5 * f2;
+                }
+
+                backward(freq, &mut self.decode_mem[0]);
+            }
+            _ => {
+                for (ch, mem) in self.decode_mem.iter_mut().enumerate() {
+                    let band_e = &self.old_band_e[ch * NB_EBANDS..];
+                    denormalise_bands(&x[ch * n..], freq, band_e, start, end, m, silence);
+                    backward(freq, mem);
+                }
+            }
+        }
+    }
+
+    fn deemphasis(&mut self, pcm: &mut [f32], n: usize) {
+        let channels = self.channels;
+        for (c, mem) in self.decode_mem.iter().enumerate() {
+            let mut m = self.preemph_mem[c];
+            for (j, &x) in mem[DECODE_BUFFER_SIZE - n..DECODE_BUFFER_SIZE]
+                .iter()
+                .enumerate()
+            {
+                let tmp = x + VERY_SMALL + m;
+                m = PREEMPH * tmp;
+                pcm[j * channels + c] = tmp * (1.0 / 32768.0);
+            }
+
+            self.preemph_mem[c] = m;
+        }
+    }
+
+    fn plc_pitch_search(&self) -> usize {
+        let mut lp = [0.0f32; DECODE_BUFFER_SIZE >> 1];
+        let channels: Vec<&[f32]> = self.decode_mem.iter().map(|mem| &mem[..]).collect();
+        pitch_downsample(&channels, &mut lp, DECODE_BUFFER_SIZE);
+
+        let pitch = pitch_search(
+            &lp[PLC_PITCH_LAG_MAX >> 1..],
+            &lp,
+            DECODE_BUFFER_SIZE - PLC_PITCH_LAG_MAX,
+            PLC_PITCH_LAG_MAX - PLC_PITCH_LAG_MIN,
+        );
+
+        PLC_PITCH_LAG_MAX - pitch
+    }
+
+    /// Conceals a lost frame, extrapolating the last pitch period for short losses and fading
+    /// into shaped noise for longer ones.
+    fn decode_lost(&mut self, n: usize, lm: usize) {
+        let channels = self.channels;
+        let (start, end) = (self.start, self.end);
+        let loss_count = self.loss_count;
+
+        if loss_count >= 5 || start != 0 || self.skip_plc {
+            let decay = if loss_count == 0 { 1.5 } else { 0.5 };
+            for c in 0..channels {
+                for i in start..end {
+                    let e = &mut self.old_band_e[c * NB_EBANDS + i];
+                    *e = self.background_log_e[c * NB_EBANDS + i].max(*e - decay);
+                }
+            }
+
+            let mut x = [0.0f32; 2 * 960];
+            let mut seed = self.rng;
+            for c in 0..channels {
+                for i in start..end {
+                    let offset = n * c + ((EBANDS[i] as usize) << lm);
+                    let len = ((EBANDS[i + 1] - EBANDS[i]) as usize) << lm;
+                    let band = &mut x[offset..offset + len];
+                    for x in band.iter_mut() {
+                        seed = lcg_rand(seed);
+                        *x = (seed as i32 >> 20) as f32;
+                    }
+
+                    renormalise_vector(band, 1.0);
+                }
+            }
+
+            self.rng = seed;
+            for mem in &mut self.decode_mem {
+                mem.copy_within(n..DECODE_BUFFER_SIZE + OVERLAP / 2, 0);
+            }
+
+            self.synthesis(&x, start, end, channels, false, lm, false);
+        } else {
+            let (pitch_index, fade) = match loss_count {
+                0 => {
+                    self.last_pitch_index = self.plc_pitch_search();
+                    (self.last_pitch_index, 1.0)
+                }
+                _ => (self.last_pitch_index, 0.8),
+            };
+
+            let exc_length = (2 * pitch_index).min(MAX_PERIOD);
+            for (c, buf) in self.decode_mem.iter_mut().enumerate() {
+                let lpc_c = &mut self.lpc[c];
+                let mut exc_buf = [0.0f32; MAX_PERIOD + LPC_ORDER];
+                exc_buf.copy_from_slice(
+                    &buf[DECODE_BUFFER_SIZE - MAX_PERIOD - LPC_ORDER..DECODE_BUFFER_SIZE],
+                );
+
+                if loss_count == 0 {
+                    let mut ac = [0.0f32; LPC_ORDER + 1];
+                    autocorr(&exc_buf[LPC_ORDER..], &mut ac, Some(&WINDOW), MAX_PERIOD);
+                    ac[0] *= 1.0001;
+                    for (i, ac) in ac.iter_mut().enumerate().skip(1) {
+                        *ac -= *ac * (0.008 * 0.008) * i as f32 * i as f32;
+                    }
+
+                    lpc(lpc_c, &ac);
+                }
+
+                let exc_start = LPC_ORDER + MAX_PERIOD - exc_length;
+                let mut fir_tmp = [0.0f32; MAX_PERIOD];
+                fir(
+                    &exc_buf[exc_start - LPC_ORDER..],
+                    lpc_c,
+                    &mut fir_tmp[..exc_length],
+                );
+                exc_buf[exc_start..exc_start + exc_length].copy_from_slice(&fir_tmp[..exc_length]);
+                let exc = &exc_buf[LPC_ORDER..];
+
+                let decay_length = exc_length >> 1;
+                let mut e1 = 1.0f32;
+                let mut e2 = 1.0f32;
+                for i in 0..decay_length {
+                    let e = exc[MAX_PERIOD - decay_length + i];
+                    e1 += e * e;
+                    let e = exc[MAX_PERIOD - 2 * decay_length + i];
+                    e2 += e * e;
+                }
+
+                let decay = (e1.min(e2) / e2).sqrt();
+
+                buf.copy_within(n..DECODE_BUFFER_SIZE, 0);
+                let extrapolation_offset = MAX_PERIOD - pitch_index;
+                let extrapolation_len = n + OVERLAP;
+                let mut attenuation = fade * decay;
+                let mut s1 = 0.0f32;
+                let mut j = 0;
+                for i in 0..extrapolation_len {
+                    if j >= pitch_index {
+                        j -= pitch_index;
+                        attenuation *= decay;
+                    }
+
+                    buf[DECODE_BUFFER_SIZE - n + i] = attenuation * exc[extrapolation_offset + j];
+                    let tmp = buf[DECODE_BUFFER_SIZE - MAX_PERIOD - n + extrapolation_offset + j];
+                    s1 += tmp * tmp;
+                    j += 1;
+                }
+
+                let mut lpc_mem: [f32; LPC_ORDER] =
+                    std::array::from_fn(|i| buf[DECODE_BUFFER_SIZE - n - 1 - i]);
+                let extrapolated =
+                    &mut buf[DECODE_BUFFER_SIZE - n..DECODE_BUFFER_SIZE - n + extrapolation_len];
+                iir(extrapolated, lpc_c, &mut lpc_mem);
+
+                let s2 = extrapolated.iter().fold(0.0f32, |s2, x| s2 + x * x);
+                if s1.partial_cmp(&(0.2 * s2)) != Some(Ordering::Greater) {
+                    extrapolated.fill(0.0);
+                } else if s1 < s2 {
+                    let ratio = ((s1 + 1.0) / (s2 + 1.0)).sqrt();
+                    for (i, x) in extrapolated.iter_mut().enumerate() {
+                        match i < OVERLAP {
+                            true => *x *= 1.0 - WINDOW[i] * (1.0 - ratio),
+                            false => *x *= ratio,
+                        }
+                    }
+                }
+
+                let filter = PostFilter {
+                    gain: -self.postfilter.gain,
+                    ..self.postfilter
+                };
+
+                let mut etmp = [0.0f32; OVERLAP];
+                comb_filter(
+                    buf,
+                    DECODE_BUFFER_SIZE,
+                    Some(&mut etmp),
+                    OVERLAP,
+                    filter,
+                    filter,
+                    0,
+                );
+                for i in 0..OVERLAP / 2 {
+                    buf[DECODE_BUFFER_SIZE + i] =
+                        WINDOW[i] * etmp[OVERLAP - 1 - i] + WINDOW[OVERLAP - i - 1] * etmp[i];
+                }
+            }
+        }
+
+        self.loss_count = loss_count + 1;
+    }
+}
@@ -0,0 +1,121 @@
+use crate::{
+    celt::{rate::MAX_FINE_BITS, tables::E_PROB_MODEL, NB_EBANDS},
+    range::RangeDecoder,
+};
+
+const PRED_COEF: [f32; 4] = [
+    29440.0 / 32768.0,
+    26112.0 / 32768.0,
+    21248.0 / 32768.0,
+    16384.0 / 32768.0,
+];
+const BETA_COEF: [f32; 4] = [
+    30147.0 / 32768.0,
+    22282.0 / 32768.0,
+    12124.0 / 32768.0,
+    6554.0 / 32768.0,
+];
+const BETA_INTRA: f32 = 4915.0 / 32768.0;
+const SMALL_ENERGY_ICDF: [u8; 3] = [2, 1, 0];
+
+/// Decodes the coarse band energies, predicted from the previous frame unless `intra` is set.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn unquant_coarse_energy(
+    start: usize,
+    end: usize,
+    old_e_bands: &mut [f32],
+    intra: bool,
+    dec: &mut RangeDecoder,
+    channels: usize,
+    lm: usize,
+) {
+    let prob_model = &E_PROB_MODEL[lm][intra as usize];
+    let (coef, beta) = match intra {
+        true => (0.0, BETA_INTRA),
+        false => (PRED_COEF[lm], BETA_COEF[lm]),
+    };
+
+    let budget = dec.storage as i32 * 8;
+    let mut prev = [0.0f32; 2];
+    for i in start..end {
+        for c in 0..channels {
+            let tell = dec.tell();
+            let qi = if budget - tell >= 15 {
+                let pi = 2 * i.min(20);
+                dec.laplace(
+                    (prob_model[pi] as u32) << 7,
+                    (prob_model[pi + 1] as u32) << 6,
+                )
+            } else if budget - tell >= 2 {
+                let qi = dec.icdf(&SMALL_ENERGY_ICDF, 2) as i32;
+                (qi >> 1) ^ -(qi & 1)
+            } else if budget - tell >= 1 {
+                -(dec.bit_logp(1) as i32)
+            } else {
+                -1
+            };
+
+            let q = qi as f32;
+            let old = &mut old_e_bands[i + c * NB_EBANDS];
+            *old = old.max(-9.0);
+            *old = coef * *old + prev[c] + q;
+            prev[c] = prev[c] + q - beta * q;
+        }
+    }
+}
+
+/// Refines the band energies with the raw bits allocated to each band.
+pub(crate) fn unquant_fine_energy(
+    start: usize,
+    end: usize,
+    old_e_bands: &mut [f32],
+    fine_quant: &[i32],
+    dec: &mut RangeDecoder,
+    channels: usize,
+) {
+    for i in start..end {
+        if fine_quant[i] <= 0 {
+            continue;
+        }
+
+        for c in 0..channels {
+            let q2 = dec.bits(fine_quant[i] as u32);
+            let offset =
+                (q2 as f32 + 0.5) * (1 << (14 - fine_quant[i])) as f32 * (1.0 / 16384.0) - 0.5;
+            old_e_bands[i + c * NB_EBANDS] += offset;
+        }
+    }
+}
+
+/// Spends any bits left at the end of the frame on one more bit of energy precision per band.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn unquant_energy_finalise(
+    start: usize,
+    end: usize,
+    old_e_bands: &mut [f32],
+    fine_quant: &[i32],
+    fine_priority: &[i32],
+    mut bits_left: i32,
+    dec: &mut RangeDecoder,
+    channels: usize,
+) {
+    for prio in 0..2 {
+        let mut i = start;
+        while i < end && bits_left >= channels as i32 {
+            if fine_quant[i] >= MAX_FINE_BITS || fine_priority[i] != prio {
+                i += 1;
+                continue;
+            }
+
+            for c in 0..channels {
+                let q2 = dec.bits(1);
+                let offset =
+                    (q2 as f32 - 0.5) * (1 << (14 - fine_quant[i] - 1)) as f32 * (1.0 / 16384.0);
+                old_e_bands[i + c * NB_EBANDS] += offset;
+                bits_left -= 1;
+            }
+
+            i += 1;
+        }
+    }
+}
@@ -0,0 +1,228 @@
+/// The order of the prediction filter used to extrapolate lost frames.
+pub(crate) const LPC_ORDER: usize = 24;
+
+fn inner_prod(x: &[f32], y: &[f32]) -> f32 {
+    x.iter().zip(y).fold(0.0, |sum, (x, y)| sum + x * y)
+}
+
+/// Computes the autocorrelation of the first `n` samples of `x` for every lag up to `ac.len() - 1`,
+/// tapering both ends of the input with `window` first.
+pub(crate) fn autocorr(x: &[f32], ac: &mut [f32], window: Option<&[f32]>, n: usize) {
+    let lag = ac.len() - 1;
+    let fast_n = n - lag;
+    let mut xx = x[..n].to_vec();
+    if let Some(window) = window {
+        for (i, &w) in window.iter().enumerate() {
+            xx[i] = x[i] * w;
+            xx[n - i - 1] = x[n - i - 1] * w;
+        }
+    }
+
+    for (k, ac) in ac.iter_mut().enumerate() {
+        let d = (k + fast_n..n).fold(0.0, |d, i| d + xx[i] * xx[i - k]);
+        *ac = inner_prod(&xx[..fast_n], &xx[k..]) + d;
+    }
+}
+
+/// Derives prediction coefficients from an autocorrelation with the Levinson-Durbin recursion.
+pub(crate) fn lpc(lpc: &mut [f32], ac: &[f32]) {
+    lpc.fill(0.0);
+    if ac[0] == 0.0 {
+        return;
+    }
+
+    let mut error = ac[0];
+    for i in 0..lpc.len() {
+        let rr = (0..i).fold(0.0, |rr, j| rr + lpc[j] * ac[i - j]) + ac[i + 1];
+        let r = -rr / error;
+        lpc[i] = r;
+        for j in 0..(i + 1) >> 1 {
+            let tmp1 = lpc[j];
+            let tmp2 = lpc[i - 1 - j];
+            lpc[j] = tmp1 + r * tmp2;
+            lpc[i - 1 - j] = tmp2 + r * tmp1;
+        }
+
+        error -= r * r * error;
+        if error < 0.001 * ac[0] {
+            break;
+        }
+    }
+}
+
+/// Runs `x` through the prediction filter `num`, where `x` starts with `num.len()` samples of
+/// history before the `y.len()` samples to filter.
+pub(crate) fn fir(x: &[f32], num: &[f32], y: &mut [f32]) {
+    let ord = num.len();
+    for (i, y) in y.iter_mut().enumerate() {
+        *y = (0..ord).fold(x[i + ord], |sum, j| sum + num[ord - j - 1] * x[i + j]);
+    }
+}
+
+/// Runs `x` in place through the synthesis filter `den`, with `mem` holding the most recent output
+/// first. Samples are filtered in blocks of four, accumulating in the same order as the reference
+/// decoder so concealed frames match it exactly.
+pub(crate) fn iir(x: &mut [f32], den: &[f32], mem: &mut [f32]) {
+    let ord = den.len();
+    let n = x.len();
+    let mut y = vec![0.0f32; n + ord];
+    for i in 0..ord {
+        y[i] = -mem[ord - i - 1];
+    }
+
+    let mut i = 0;
+    while i + 3 < n {
+        let mut sum = [x[i], x[i + 1], x[i + 2], x[i + 3]];
+        for j in 0..ord {
+            let d = den[ord - j - 1];
+            for (k, sum) in sum.iter_mut().enumerate() {
+                *sum += d * y[i + j + k];
+            }
+        }
+
+        for k in 0..4 {
+            for l in 0..k {
+                sum[k] += y[i + ord + k - l - 1] * den[l];
+            }
+
+            y[i + ord + k] = -sum[k];
+            x[i + k] = sum[k];
+        }
+
+        i += 4;
+    }
+
+    for i in i..n {
+        let sum = (0..ord).fold(x[i], |sum, j| sum + den[ord - j - 1] * y[i + j]);
+        y[i + ord] = -sum;
+        x[i] = sum;
+    }
+
+    for (i, mem) in mem.iter_mut().enumerate() {
+        *mem = x[n - i - 1];
+    }
+}
+
+fn fir5(x: &mut [f32], num: &[f32; 5]) {
+    let mut mem = [0.0f32; 5];
+    for x in x.iter_mut() {
+        let sum = (0..5).fold(*x, |sum, j| sum + num[j] * mem[j]);
+        mem.copy_within(0..4, 1);
+        mem[0] = *x;
+        *x = sum;
+    }
+}
+
+/// Low passes and decimates the channels of `x` by two into `x_lp`, whitening the result.
+pub(crate) fn pitch_downsample(x: &[&[f32]], x_lp: &mut [f32], len: usize) {
+    let half = len >> 1;
+    for (c, x) in x.iter().enumerate() {
+        let first = 0.5 * (0.5 * x[1] + x[0]);
+        let lp = (1..half).map(|i| 0.5 * (0.5 * (x[2 * i - 1] + x[2 * i + 1]) + x[2 * i]));
+        for (y, v) in x_lp[..half]
+            .iter_mut()
+            .zip(std::iter::once(first).chain(lp))
+        {
+            *y = if c == 0 { v } else { *y + v };
+        }
+    }
+
+    let mut ac = [0.0f32; 5];
+    autocorr(x_lp, &mut ac, None, half);
+    ac[0] *= 1.0001;
+    for (i, ac) in ac.iter_mut().enumerate().skip(1) {
+        *ac -= *ac * (0.008 * i as f32) * (0.008 * i as f32);
+    }
+
+    let mut lpc4 = [0.0f32; 4];
+    lpc(&mut lpc4, &ac);
+    let mut tmp = 1.0f32;
+    for lpc in lpc4.iter_mut() {
+        tmp *= 0.9;
+        *lpc *= tmp;
+    }
+
+    let c1 = 0.8;
+    let lpc2 = [
+        lpc4[0] + 0.8,
+        lpc4[1] + c1 * lpc4[0],
+        lpc4[2] + c1 * lpc4[1],
+        lpc4[3] + c1 * lpc4[2],
+        c1 * lpc4[3],
+    ];
+
+    fir5(&mut x_lp[..half], &lpc2);
+}
+
+fn find_best_pitch(xcorr: &[f32], y: &[f32], len: usize, best_pitch: &mut [usize; 2]) {
+    let mut syy = y[..len].iter().fold(1.0, |syy, y| syy + y * y);
+    let mut best_num = [-1.0f32; 2];
+    let mut best_den = [0.0f32; 2];
+    *best_pitch = [0, 1];
+
+    for (i, &xcorr) in xcorr.iter().enumerate() {
+        if xcorr > 0.0 {
+            let xcorr16 = xcorr * 1e-12;
+            let num = xcorr16 * xcorr16;
+            if num * best_den[1] > best_num[1] * syy {
+                if num * best_den[0] > best_num[0] * syy {
+                    best_num[1] = best_num[0];
+                    best_den[1] = best_den[0];
+                    best_pitch[1] = best_pitch[0];
+                    best_num[0] = num;
+                    best_den[0] = syy;
+                    best_pitch[0] = i;
+                } else {
+                    best_num[1] = num;
+                    best_den[1] = syy;
+                    best_pitch[1] = i;
+                }
+            }
+        }
+
+        syy += y[i + len] * y[i + len] - y[i] * y[i];
+        syy = syy.max(1.0);
+    }
+}
+
+/// Finds the lag in `0..max_pitch` at which `y` best matches `x_lp`, searching at a quarter of
+/// the resolution first and refining around the best candidates.
+pub(crate) fn pitch_search(x_lp: &[f32], y: &[f32], len: usize, max_pitch: usize) -> usize {
+    let lag = len + max_pitch;
+    let x_lp4: Vec<f32> = (0..len >> 2).map(|j| x_lp[2 * j]).collect();
+    let y_lp4: Vec<f32> = (0..lag >> 2).map(|j| y[2 * j]).collect();
+
+    let mut xcorr: Vec<f32> = (0..max_pitch >> 2)
+        .map(|i| inner_prod(&x_lp4, &y_lp4[i..]))
+        .collect();
+
+    let mut best_pitch = [0, 0];
+    find_best_pitch(&xcorr, &y_lp4, len >> 2, &mut best_pitch);
+
+    xcorr.resize(max_pitch >> 1, 0.0);
+    for (i, xcorr) in xcorr.iter_mut().enumerate() {
+        *xcorr = 0.0;
+        if (i as i32 - 2 * best_pitch[0] as i32).abs() > 2
+            && (i as i32 - 2 * best_pitch[1] as i32).abs() > 2
+        {
+            continue;
+        }
+
+        *xcorr = inner_prod(&x_lp[..len >> 1], &y[i..]).max(-1.0);
+    }
+
+    find_best_pitch(&xcorr, y, len >> 1, &mut best_pitch);
+
+    let best = best_pitch[0];
+    let mut offset = 0;
+    if best > 0 && best < (max_pitch >> 1) - 1 {
+        let (a, b, c) = (xcorr[best - 1], xcorr[best], xcorr[best + 1]);
+        if c - a > 0.7 * (b - a) {
+            offset = 1;
+        } else if a - c > 0.7 * (b - c) {
+            offset = -1;
+        }
+    }
+
+    (2 * best as i32 - offset) as usize
+}
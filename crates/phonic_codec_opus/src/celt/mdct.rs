@@ -0,0 +1,300 @@
+use std::f64::consts::PI;
+
+#[derive(Clone, Copy, Default)]
+struct Complex {
+    r: f32,
+    i: f32,
+}
+
+impl Complex {
+    fn add(self, o: Self) -> Self {
+        Self {
+            r: self.r + o.r,
+            i: self.i + o.i,
+        }
+    }
+
+    fn sub(self, o: Self) -> Self {
+        Self {
+            r: self.r - o.r,
+            i: self.i - o.i,
+        }
+    }
+
+    fn mul(self, o: Self) -> Self {
+        Self {
+            r: self.r * o.r - self.i * o.i,
+            i: self.r * o.i + self.i * o.r,
+        }
+    }
+
+    fn scale(self, s: f32) -> Self {
+        Self {
+            r: self.r * s,
+            i: self.i * s,
+        }
+    }
+}
+
+/// A mixed radix forward FFT for the sizes used by the celt MDCT.
+struct Fft {
+    n: usize,
+    factors: Vec<(usize, usize)>,
+    twiddles: Vec<Complex>,
+}
+
+impl Fft {
+    fn new(n: usize) -> Self {
+        let mut factors = Vec::new();
+        let mut rem = n;
+        for p in [4, 2, 3, 5] {
+            while rem.is_multiple_of(p) {
+                rem /= p;
+                factors.push((p, rem));
+            }
+        }
+
+        let twiddles = (0..n)
+            .map(|i| {
+                let phase = (-2.0 * PI / n as f64) * i as f64;
+                Complex {
+                    r: phase.cos() as f32,
+                    i: phase.sin() as f32,
+                }
+            })
+            .collect();
+
+        Self {
+            n,
+            factors,
+            twiddles,
+        }
+    }
+
+    fn process(&self, input: &[Complex], output: &mut [Complex]) {
+        self.work(input, output, 1, 0);
+    }
+
+    fn work(&self, input: &[Complex], out: &mut [Complex], fstride: usize, stage: usize) {
+        let (p, m) = self.factors[stage];
+        if m == 1 {
+            for q in 0..p {
+                out[q] = input[q * fstride];
+            }
+        } else {
+            for q in 0..p {
+                self.work(
+                    &input[q * fstride..],
+                    &mut out[q * m..],
+                    fstride * p,
+                    stage + 1,
+                );
+            }
+        }
+
+        match p {
+            2 => self.bfly2(out, fstride, m),
+            3 => self.bfly3(out, fstride, m),
+            4 => self.bfly4(out, fstride, m),
+            _ => self.bfly5(out, fstride, m),
+        }
+    }
+
+    fn bfly2(&self, out: &mut [Complex], fstride: usize, m: usize) {
+        for j in 0..m {
+            let t = out[j + m].mul(self.twiddles[j * fstride]);
+            out[j + m] = out[j].sub(t);
+            out[j] = out[j].add(t);
+        }
+    }
+
+    fn bfly3(&self, out: &mut [Complex], fstride: usize, m: usize) {
+        let epi3 = self.twiddles[fstride * m];
+        for k in 0..m {
+            let s1 = out[k + m].mul(self.twiddles[k * fstride]);
+            let s2 = out[k + 2 * m].mul(self.twiddles[2 * k * fstride]);
+            let s3 = s1.add(s2);
+            let s0 = s1.sub(s2).scale(epi3.i);
+
+            let fm = Complex {
+                r: out[k].r - 0.5 * s3.r,
+                i: out[k].i - 0.5 * s3.i,
+            };
+
+            out[k] = out[k].add(s3);
+            out[k + 2 * m] = Complex {
+                r: fm.r + s0.i,
+                i: fm.i - s0.r,
+            };
+
+            out[k + m] = Complex {
+                r: fm.r - s0.i,
+                i: fm.i + s0.r,
+            };
+        }
+    }
+
+    fn bfly4(&self, out: &mut [Complex], fstride: usize, m: usize) {
+        for j in 0..m {
+            let s0 = out[j + m].mul(self.twiddles[j * fstride]);
+            let s1 = out[j + 2 * m].mul(self.twiddles[2 * j * fstride]);
+            let s2 = out[j + 3 * m].mul(self.twiddles[3 * j * fstride]);
+            let s5 = out[j].sub(s1);
+            let f0 = out[j].add(s1);
+            let s3 = s0.add(s2);
+            let s4 = s0.sub(s2);
+
+            out[j + 2 * m] = f0.sub(s3);
+            out[j] = f0.add(s3);
+            out[j + m] = Complex {
+                r: s5.r + s4.i,
+                i: s5.i - s4.r,
+            };
+
+            out[j + 3 * m] = Complex {
+                r: s5.r - s4.i,
+                i: s5.i + s4.r,
+            };
+        }
+    }
+
+    fn bfly5(&self, out: &mut [Complex], fstride: usize, m: usize) {
+        let ya = self.twiddles[fstride * m];
+        let yb = self.twiddles[fstride * 2 * m];
+        let tw = &self.twiddles;
+        for u in 0..m {
+            let s0 = out[u];
+            let s1 = out[u + m].mul(tw[u * fstride]);
+            let s2 = out[u + 2 * m].mul(tw[2 * u * fstride]);
+            let s3 = out[u + 3 * m].mul(tw[3 * u * fstride]);
+            let s4 = out[u + 4 * m].mul(tw[4 * u * fstride]);
+
+            let s7 = s1.add(s4);
+            let s10 = s1.sub(s4);
+            let s8 = s2.add(s3);
+            let s9 = s2.sub(s3);
+
+            out[u] = Complex {
+                r: s0.r + (s7.r + s8.r),
+                i: s0.i + (s7.i + s8.i),
+            };
+
+            let s5 = Complex {
+                r: s0.r + (s7.r * ya.r + s8.r * yb.r),
+                i: s0.i + (s7.i * ya.r + s8.i * yb.r),
+            };
+
+            let s6 = Complex {
+                r: s10.i * ya.i + s9.i * yb.i,
+                i: -(s10.r * ya.i + s9.r * yb.i),
+            };
+
+            out[u + m] = s5.sub(s6);
+            out[u + 4 * m] = s5.add(s6);
+
+            let s11 = Complex {
+                r: s0.r + (s7.r * yb.r + s8.r * ya.r),
+                i: s0.i + (s7.i * yb.r + s8.i * ya.r),
+            };
+
+            let s12 = Complex {
+                r: s9.i * ya.i - s10.i * yb.i,
+                i: s10.r * yb.i - s9.r * ya.i,
+            };
+
+            out[u + 2 * m] = s11.add(s12);
+            out[u + 3 * m] = s11.sub(s12);
+        }
+    }
+}
+
+/// The inverse MDCT for every frame size of the 48 kHz mode, sharing one twiddle table per size.
+pub(crate) struct Mdct {
+    ffts: Vec<Fft>,
+    trig: Vec<Vec<f32>>,
+}
+
+impl Mdct {
+    pub fn new() -> Self {
+        let ffts = (0..4).map(|shift| Fft::new(480 >> shift)).collect();
+        let trig = (0..4)
+            .map(|shift| {
+                let n = 1920 >> shift;
+                let two_pi = (2.0 * std::f32::consts::PI) as f64;
+                (0..n / 2)
+                    .map(|i| (two_pi * (i as f64 + 0.125) / n as f64).cos() as f32)
+                    .collect()
+            })
+            .collect();
+
+        Self { ffts, trig }
+    }
+
+    /// Transforms `n / 2` strided coefficients into `out`, overlap-adding the windowed start of
+    /// the output with what was already there.
+    pub fn backward(
+        &self,
+        input: &[f32],
+        out: &mut [f32],
+        window: &[f32],
+        overlap: usize,
+        shift: usize,
+        stride: usize,
+    ) {
+        let fft = &self.ffts[shift];
+        let t = &self.trig[shift];
+        let n4 = fft.n;
+        let n2 = n4 * 2;
+
+        let mut z = [Complex::default(); 480];
+        for (i, z) in z.iter_mut().enumerate().take(n4) {
+            let x1 = input[2 * i * stride];
+            let x2 = input[stride * (n2 - 1) - 2 * i * stride];
+            let yr = x2 * t[i] + x1 * t[n4 + i];
+            let yi = x1 * t[i] - x2 * t[n4 + i];
+            *z = Complex { r: yi, i: yr };
+        }
+
+        let mut f = [Complex::default(); 480];
+        fft.process(&z[..n4], &mut f[..n4]);
+
+        let y = &mut out[overlap >> 1..];
+        for (k, f) in f.iter().enumerate().take(n4) {
+            y[2 * k] = f.r;
+            y[2 * k + 1] = f.i;
+        }
+
+        for i in 0..(n4 + 1) >> 1 {
+            let p0 = 2 * i;
+            let p1 = n2 - 2 - 2 * i;
+
+            let re = y[p0 + 1];
+            let im = y[p0];
+            let t0 = t[i];
+            let t1 = t[n4 + i];
+            let yr = re * t0 + im * t1;
+            let yi = re * t1 - im * t0;
+
+            let re = y[p1 + 1];
+            let im = y[p1];
+            y[p0] = yr;
+            y[p1 + 1] = yi;
+
+            let t0 = t[n4 - i - 1];
+            let t1 = t[n2 - i - 1];
+            let yr = re * t0 + im * t1;
+            let yi = re * t1 - im * t0;
+            y[p1] = yr;
+            y[p0 + 1] = yi;
+        }
+
+        for i in 0..overlap / 2 {
+            let x1 = out[overlap - 1 - i];
+            let x2 = out[i];
+            let wp1 = window[i];
+            let wp2 = window[overlap - 1 - i];
+            out[i] = wp2 * x2 - wp1 * x1;
+            out[overlap - 1 - i] = wp1 * x2 + wp2 * x1;
+        }
+    }
+}
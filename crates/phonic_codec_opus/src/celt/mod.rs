@@ -0,0 +1,28 @@
+mod bands;
+mod cwrs;
+mod decoder;
+mod energy;
+mod lpc;
+mod mdct;
+mod rate;
+mod tables;
+mod vq;
+
+pub(crate) use decoder::CeltDecoder;
+pub(crate) use tables::WINDOW;
+
+/// The number of energy bands in the 48 kHz mode.
+pub(crate) const NB_EBANDS: usize = 21;
+
+pub(crate) const SPREAD_NONE: i32 = 0;
+pub(crate) const SPREAD_NORMAL: i32 = 2;
+pub(crate) const SPREAD_AGGRESSIVE: i32 = 3;
+
+/// `cos(x * pi / 2)`, computed the way the reference float build does it.
+fn cos_norm(x: f32) -> f32 {
+    (((0.5 * std::f32::consts::PI) * x) as f64).cos() as f32
+}
+
+fn exp2(x: f32) -> f32 {
+    (std::f64::consts::LN_2 * x as f64).exp() as f32
+}
@@ -0,0 +1,415 @@
+use crate::{
+    celt::{
+        tables::{BAND_ALLOCATION, CACHE_BITS, CACHE_INDEX, EBANDS, LOG_N},
+        NB_EBANDS,
+    },
+    range::{RangeDecoder, BITRES},
+};
+
+pub(crate) const MAX_FINE_BITS: i32 = 8;
+pub(crate) const FINE_OFFSET: i32 = 21;
+pub(crate) const QTHETA_OFFSET: i32 = 4;
+pub(crate) const QTHETA_OFFSET_TWOPHASE: i32 = 16;
+
+const LOG_MAX_PSEUDO: usize = 6;
+const ALLOC_STEPS: usize = 6;
+const NB_ALLOC_VECTORS: usize = 11;
+
+const LOG2_FRAC_TABLE: [u8; 24] = [
+    0, 8, 13, 16, 19, 21, 23, 24, 26, 27, 28, 29, 30, 31, 32, 32, 33, 34, 34, 35, 36, 36, 37, 37,
+];
+
+/// The result of splitting a frame's bit budget between its bands.
+pub(crate) struct Allocation {
+    pub coded_bands: usize,
+    pub intensity: usize,
+    pub dual_stereo: bool,
+    pub balance: i32,
+    pub pulses: [i32; NB_EBANDS],
+    pub fine_quant: [i32; NB_EBANDS],
+    pub fine_priority: [i32; NB_EBANDS],
+}
+
+pub(crate) fn get_pulses(i: i32) -> i32 {
+    if i < 8 {
+        i
+    } else {
+        (8 + (i & 7)) << ((i >> 3) - 1)
+    }
+}
+
+fn pulse_cache(band: usize, lm: i32) -> &'static [u8] {
+    let index = CACHE_INDEX[(lm + 1) as usize * NB_EBANDS + band] as usize;
+    &CACHE_BITS[index..]
+}
+
+pub(crate) fn bits2pulses(band: usize, lm: i32, bits: i32) -> i32 {
+    let cache = pulse_cache(band, lm);
+    let mut lo = 0;
+    let mut hi = cache[0] as i32;
+    let bits = bits - 1;
+    for _ in 0..LOG_MAX_PSEUDO {
+        let mid = (lo + hi + 1) >> 1;
+        if cache[mid as usize] as i32 >= bits {
+            hi = mid;
+        } else {
+            lo = mid;
+        }
+    }
+
+    let lo_bits = if lo == 0 {
+        -1
+    } else {
+        cache[lo as usize] as i32
+    };
+    if bits - lo_bits <= cache[hi as usize] as i32 - bits {
+        lo
+    } else {
+        hi
+    }
+}
+
+pub(crate) fn pulses2bits(band: usize, lm: i32, pulses: i32) -> i32 {
+    match pulses {
+        0 => 0,
+        _ => pulse_cache(band, lm)[pulses as usize] as i32 + 1,
+    }
+}
+
+fn band_width(j: usize) -> i32 {
+    (EBANDS[j + 1] - EBANDS[j]) as i32
+}
+
+fn udiv(n: i32, d: i32) -> i32 {
+    ((n as u32) / (d as u32)) as i32
+}
+
+#[allow(clippy::too_many_arguments)]
+fn interp_bits2pulses(
+    start: usize,
+    end: usize,
+    skip_start: usize,
+    bits1: &[i32; NB_EBANDS],
+    bits2: &[i32; NB_EBANDS],
+    thresh: &[i32; NB_EBANDS],
+    cap: &[i32; NB_EBANDS],
+    mut total: i32,
+    skip_rsv: i32,
+    mut intensity_rsv: i32,
+    mut dual_stereo_rsv: i32,
+    channels: usize,
+    lm: i32,
+    dec: &mut RangeDecoder,
+) -> Allocation {
+    let c = channels as i32;
+    let alloc_floor = c << BITRES;
+    let stereo = (channels > 1) as i32;
+    let log_m = lm << BITRES;
+
+    let mut lo = 0;
+    let mut hi = 1 << ALLOC_STEPS;
+    for _ in 0..ALLOC_STEPS {
+        let mid = (lo + hi) >> 1;
+        let mut psum = 0;
+        let mut done = false;
+        for j in (start..end).rev() {
+            let tmp = bits1[j] + ((mid * bits2[j]) >> ALLOC_STEPS);
+            if tmp >= thresh[j] || done {
+                done = true;
+                psum += tmp.min(cap[j]);
+            } else if tmp >= alloc_floor {
+                psum += alloc_floor;
+            }
+        }
+
+        if psum > total {
+            hi = mid;
+        } else {
+            lo = mid;
+        }
+    }
+
+    let mut alloc = Allocation {
+        coded_bands: end,
+        intensity: 0,
+        dual_stereo: false,
+        balance: 0,
+        pulses: [0; NB_EBANDS],
+        fine_quant: [0; NB_EBANDS],
+        fine_priority: [0; NB_EBANDS],
+    };
+    let bits = &mut alloc.pulses;
+
+    let mut psum = 0;
+    let mut done = false;
+    for j in (start..end).rev() {
+        let mut tmp = bits1[j] + ((lo * bits2[j]) >> ALLOC_STEPS);
+        if tmp < thresh[j] && !done {
+            tmp = if tmp >= alloc_floor { alloc_floor } else { 0 };
+        } else {
+            done = true;
+        }
+
+        tmp = tmp.min(cap[j]);
+        bits[j] = tmp;
+        psum += tmp;
+    }
+
+    let mut coded_bands = end;
+    loop {
+        let j = coded_bands - 1;
+        if j <= skip_start {
+            total += skip_rsv;
+            break;
+        }
+
+        let span = (EBANDS[coded_bands] - EBANDS[start]) as i32;
+        let mut left = total - psum;
+        let percoeff = udiv(left, span);
+        left = left.wrapping_sub(span.wrapping_mul(percoeff));
+        let rem = (left - (EBANDS[j] - EBANDS[start]) as i32).max(0);
+        let width = (EBANDS[coded_bands] - EBANDS[j]) as i32;
+        let mut band_bits = bits[j]
+            .wrapping_add(percoeff.wrapping_mul(width))
+            .wrapping_add(rem);
+        if band_bits >= thresh[j].max(alloc_floor + (1 << BITRES)) {
+            if dec.bit_logp(1) {
+                break;
+            }
+
+            psum += 1 << BITRES;
+            band_bits -= 1 << BITRES;
+        }
+
+        psum -= bits[j] + intensity_rsv;
+        if intensity_rsv > 0 {
+            intensity_rsv = LOG2_FRAC_TABLE[j - start] as i32;
+        }
+
+        psum += intensity_rsv;
+        if band_bits >= alloc_floor {
+            psum += alloc_floor;
+            bits[j] = alloc_floor;
+        } else {
+            bits[j] = 0;
+        }
+
+        coded_bands -= 1;
+    }
+
+    alloc.coded_bands = coded_bands;
+    if intensity_rsv > 0 {
+        alloc.intensity = start + dec.uint((coded_bands + 1 - start) as u32) as usize;
+    }
+
+    if alloc.intensity <= start {
+        total += dual_stereo_rsv;
+        dual_stereo_rsv = 0;
+    }
+
+    if dual_stereo_rsv > 0 {
+        alloc.dual_stereo = dec.bit_logp(1);
+    }
+
+    let span = (EBANDS[coded_bands] - EBANDS[start]) as i32;
+    let mut left = total - psum;
+    let percoeff = udiv(left, span);
+    left = left.wrapping_sub(span.wrapping_mul(percoeff));
+    for (j, bits) in bits.iter_mut().enumerate().take(coded_bands).skip(start) {
+        *bits = bits.wrapping_add(percoeff.wrapping_mul(band_width(j)));
+    }
+
+    for (j, bits) in bits.iter_mut().enumerate().take(coded_bands).skip(start) {
+        let tmp = left.min(band_width(j));
+        *bits += tmp;
+        left -= tmp;
+    }
+
+    let ebits = &mut alloc.fine_quant;
+    let fine_priority = &mut alloc.fine_priority;
+    let mut balance = 0;
+    for j in start..coded_bands {
+        let n = band_width(j) << lm;
+        let bit = bits[j] + balance;
+        let mut excess;
+        if n > 1 {
+            excess = (bit - cap[j]).max(0);
+            bits[j] = bit - excess;
+
+            let den = c * n
+                + (channels == 2 && n > 2 && !alloc.dual_stereo && j < alloc.intensity) as i32;
+            let nc_log_n = den * (LOG_N[j] as i32 + log_m);
+            let mut offset = (nc_log_n >> 1) - den * FINE_OFFSET;
+            if n == 2 {
+                offset += den << BITRES >> 2;
+            }
+
+            if bits[j] + offset < (den * 2) << BITRES {
+                offset += nc_log_n >> 2;
+            } else if bits[j] + offset < (den * 3) << BITRES {
+                offset += nc_log_n >> 3;
+            }
+
+            ebits[j] = (bits[j] + offset + (den << (BITRES - 1))).max(0);
+            ebits[j] = udiv(ebits[j], den) >> BITRES;
+            if c * ebits[j] > (bits[j] >> BITRES) {
+                ebits[j] = bits[j] >> stereo >> BITRES;
+            }
+
+            ebits[j] = ebits[j].min(MAX_FINE_BITS);
+            fine_priority[j] = (ebits[j] * (den << BITRES) >= bits[j] + offset) as i32;
+            bits[j] -= (c * ebits[j]) << BITRES;
+        } else {
+            excess = (bit - (c << BITRES)).max(0);
+            bits[j] = bit - excess;
+            ebits[j] = 0;
+            fine_priority[j] = 1;
+        }
+
+        if excess > 0 {
+            let extra_fine = (excess >> (stereo + BITRES as i32)).min(MAX_FINE_BITS - ebits[j]);
+            ebits[j] += extra_fine;
+            let extra_bits = (extra_fine * c) << BITRES;
+            fine_priority[j] = (extra_bits >= excess - balance) as i32;
+            excess -= extra_bits;
+        }
+
+        balance = excess;
+    }
+
+    alloc.balance = balance;
+    for j in coded_bands..end {
+        ebits[j] = bits[j] >> stereo >> BITRES;
+        bits[j] = 0;
+        fine_priority[j] = (ebits[j] < 1) as i32;
+    }
+
+    alloc
+}
+
+/// Decodes how the bits of a frame are split between pulses and fine energy in each band.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn compute_allocation(
+    start: usize,
+    end: usize,
+    offsets: &[i32; NB_EBANDS],
+    cap: &[i32; NB_EBANDS],
+    alloc_trim: i32,
+    total: i32,
+    channels: usize,
+    lm: i32,
+    dec: &mut RangeDecoder,
+) -> Allocation {
+    let c = channels as i32;
+    let mut total = total.max(0);
+    let mut skip_start = start;
+    let skip_rsv = if total >= 1 << BITRES { 1 << BITRES } else { 0 };
+    total -= skip_rsv;
+
+    let mut intensity_rsv = 0;
+    let mut dual_stereo_rsv = 0;
+    if channels == 2 {
+        intensity_rsv = LOG2_FRAC_TABLE[end - start] as i32;
+        if intensity_rsv > total {
+            intensity_rsv = 0;
+        } else {
+            total -= intensity_rsv;
+            dual_stereo_rsv = if total >= 1 << BITRES { 1 << BITRES } else { 0 };
+            total -= dual_stereo_rsv;
+        }
+    }
+
+    let mut bits1 = [0; NB_EBANDS];
+    let mut bits2 = [0; NB_EBANDS];
+    let mut thresh = [0; NB_EBANDS];
+    let mut trim_offset = [0; NB_EBANDS];
+    for j in start..end {
+        let n = band_width(j);
+        thresh[j] = (c << BITRES).max(((3 * n) << lm << BITRES) >> 4);
+        trim_offset[j] =
+            (c * n * (alloc_trim - 5 - lm) * (end - j - 1) as i32 * (1 << (lm + BITRES as i32)))
+                >> 6;
+        if n << lm == 1 {
+            trim_offset[j] -= c << BITRES;
+        }
+    }
+
+    let alloc_bits = |row: usize, j: usize| {
+        (c * band_width(j) * (BAND_ALLOCATION[row * NB_EBANDS + j] as i32)) << lm >> 2
+    };
+
+    let mut lo = 1;
+    let mut hi = NB_ALLOC_VECTORS as i32 - 1;
+    while lo <= hi {
+        let mut done = false;
+        let mut psum = 0;
+        let mid = (lo + hi) >> 1;
+        for j in (start..end).rev() {
+            let mut bitsj = alloc_bits(mid as usize, j);
+            if bitsj > 0 {
+                bitsj = (bitsj + trim_offset[j]).max(0);
+            }
+
+            bitsj += offsets[j];
+            if bitsj >= thresh[j] || done {
+                done = true;
+                psum += bitsj.min(cap[j]);
+            } else if bitsj >= c << BITRES {
+                psum += c << BITRES;
+            }
+        }
+
+        if psum > total {
+            hi = mid - 1;
+        } else {
+            lo = mid + 1;
+        }
+    }
+
+    let hi = lo as usize;
+    let lo = hi - 1;
+    for j in start..end {
+        let mut bits1j = alloc_bits(lo, j);
+        let mut bits2j = match hi >= NB_ALLOC_VECTORS {
+            true => cap[j],
+            false => alloc_bits(hi, j),
+        };
+
+        if bits1j > 0 {
+            bits1j = (bits1j + trim_offset[j]).max(0);
+        }
+
+        if bits2j > 0 {
+            bits2j = (bits2j + trim_offset[j]).max(0);
+        }
+
+        if lo > 0 {
+            bits1j += offsets[j];
+        }
+
+        bits2j += offsets[j];
+        if offsets[j] > 0 {
+            skip_start = j;
+        }
+
+        bits1[j] = bits1j;
+        bits2[j] = (bits2j - bits1j).max(0);
+    }
+
+    interp_bits2pulses(
+        start,
+        end,
+        skip_start,
+        &bits1,
+        &bits2,
+        &thresh,
+        cap,
+        total,
+        skip_rsv,
+        intensity_rsv,
+        dual_stereo_rsv,
+        channels,
+        lm,
+        dec,
+    )
+}
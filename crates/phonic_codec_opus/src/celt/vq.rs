@@ -0,0 +1,115 @@
+use crate::{
+    celt::{cos_norm, cwrs::decode_pulses, SPREAD_NONE},
+    range::RangeDecoder,
+};
+
+const SPREAD_FACTOR: [i32; 3] = [15, 10, 5];
+
+fn exp_rotation1(x: &mut [f32], len: usize, stride: usize, c: f32, s: f32) {
+    let ms = -s;
+    for i in 0..len.saturating_sub(stride) {
+        let x1 = x[i];
+        let x2 = x[i + stride];
+        x[i + stride] = c * x2 + s * x1;
+        x[i] = c * x1 + ms * x2;
+    }
+
+    if len < 2 * stride + 1 {
+        return;
+    }
+
+    for i in (0..len - 2 * stride).rev() {
+        let x1 = x[i];
+        let x2 = x[i + stride];
+        x[i + stride] = c * x2 + s * x1;
+        x[i] = c * x1 + ms * x2;
+    }
+}
+
+/// Spreads (or, with a positive `dir`, collapses) the energy of a band across its coefficients
+/// to avoid tonal artifacts when only a few pulses were coded.
+pub(crate) fn exp_rotation(
+    x: &mut [f32],
+    len: usize,
+    dir: i32,
+    stride: usize,
+    k: i32,
+    spread: i32,
+) {
+    if 2 * k >= len as i32 || spread == SPREAD_NONE {
+        return;
+    }
+
+    let factor = SPREAD_FACTOR[spread as usize - 1];
+    let gain = len as f32 / (len as i32 + factor * k) as f32;
+    let theta = 0.5 * (gain * gain);
+    let c = cos_norm(theta);
+    let s = cos_norm(1.0 - theta);
+
+    let mut stride2 = 0;
+    if len >= 8 * stride {
+        stride2 = 1;
+        while (stride2 * stride2 + stride2) * stride + (stride >> 2) < len {
+            stride2 += 1;
+        }
+    }
+
+    let len = len / stride;
+    for i in 0..stride {
+        let x = &mut x[i * len..];
+        if dir < 0 {
+            if stride2 != 0 {
+                exp_rotation1(x, len, stride2, s, c);
+            }
+
+            exp_rotation1(x, len, 1, c, s);
+        } else {
+            exp_rotation1(x, len, 1, c, -s);
+            if stride2 != 0 {
+                exp_rotation1(x, len, stride2, s, -c);
+            }
+        }
+    }
+}
+
+fn extract_collapse_mask(iy: &[i32], n: usize, b: usize) -> u32 {
+    if b <= 1 {
+        return 1;
+    }
+
+    let n0 = n / b;
+    (0..b).fold(0, |mask, i| {
+        let any = iy[i * n0..(i + 1) * n0].iter().any(|&y| y != 0);
+        mask | ((any as u32) << i)
+    })
+}
+
+/// Decodes the normalised shape of a band with `k` pulses, returning a mask of which of the `b`
+/// short blocks received any energy.
+pub(crate) fn alg_unquant(
+    x: &mut [f32],
+    n: usize,
+    k: i32,
+    spread: i32,
+    b: usize,
+    dec: &mut RangeDecoder,
+    gain: f32,
+) -> u32 {
+    let mut iy = [0i32; 176];
+    let ryy = decode_pulses(&mut iy, n, k as usize, dec);
+
+    let g = (1.0 / ryy.sqrt()) * gain;
+    for (x, &y) in x.iter_mut().zip(&iy[..n]) {
+        *x = g * y as f32;
+    }
+
+    exp_rotation(x, n, -1, b, k, spread);
+    extract_collapse_mask(&iy, n, b)
+}
+
+/// Scales a vector to have the given gain.
+pub(crate) fn renormalise_vector(x: &mut [f32], gain: f32) {
+    let e = 1e-15 + x.iter().map(|x| x * x).sum::<f32>();
+    let g = (1.0 / e.sqrt()) * gain;
+    x.iter_mut().for_each(|x| *x *= g);
+}
@@ -0,0 +1,400 @@
+use crate::{
+    celt::{CeltDecoder, WINDOW},
+    packet::{
+        parse_packet, parse_self_delimited, OpusBandwidth, OpusMode, OpusToc, MAX_PACKET_SAMPLES,
+    },
+    range::RangeDecoder,
+    silk::{SilkControl, SilkDecoder},
+};
+use phonic_core::PhonicError;
+
+const F2_5: usize = 120;
+const F5: usize = 240;
+const F10: usize = 480;
+const F20: usize = 960;
+
+/// Cross-fades from `in1` to `in2` over `overlap` interleaved samples with the square of the celt
+/// window.
+fn smooth_fade(in1: &[f32], in2: &[f32], out: &mut [f32], overlap: usize, channels: usize) {
+    for (i, w) in WINDOW[..overlap].iter().enumerate() {
+        let w = w * w;
+        for c in 0..channels {
+            let j = i * channels + c;
+            out[j] = w * in2[j] + (1.0 - w) * in1[j];
+        }
+    }
+}
+
+/// Decodes opus packets into interleaved 48 kHz samples, switching between the silk and celt
+/// layers and concealing lost packets the same way the reference decoder does.
+pub struct OpusDecoder {
+    channels: usize,
+    celt: CeltDecoder,
+    silk: SilkDecoder,
+    silk_control: SilkControl,
+    stream_channels: usize,
+    bandwidth: Option<OpusBandwidth>,
+    mode: Option<OpusMode>,
+    prev_mode: Option<OpusMode>,
+    frame_size: usize,
+    prev_redundancy: bool,
+    final_range: u32,
+}
+
+impl OpusDecoder {
+    pub fn new(channels: usize) -> Result<Self, PhonicError> {
+        if !(1..=2).contains(&channels) {
+            return Err(PhonicError::Unsupported);
+        }
+
+        Ok(Self {
+            channels,
+            celt: CeltDecoder::new(channels),
+            silk: SilkDecoder::new(),
+            silk_control: SilkControl::new(channels),
+            stream_channels: channels,
+            bandwidth: None,
+            mode: None,
+            prev_mode: None,
+            frame_size: F2_5,
+            prev_redundancy: false,
+            final_range: 0,
+        })
+    }
+
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// The final state of the range decoder, which an encoder can report to check that both
+    /// sides agree on every bit of a packet.
+    pub fn final_range(&self) -> u32 {
+        self.final_range
+    }
+
+    pub fn reset(&mut self) {
+        self.celt.reset();
+        self.silk.reset();
+        self.stream_channels = self.channels;
+        self.bandwidth = None;
+        self.mode = None;
+        self.prev_mode = None;
+        self.frame_size = F2_5;
+        self.prev_redundancy = false;
+        self.final_range = 0;
+    }
+
+    /// Decodes a packet into `pcm`, returning the number of samples per channel written. With no
+    /// packet, the whole of `pcm` is filled by concealing a loss instead, and its length must be a
+    /// multiple of 2.5 ms.
+    pub fn decode(&mut self, packet: Option<&[u8]>, pcm: &mut [f32]) -> Result<usize, PhonicError> {
+        let channels = self.channels;
+        let frame_size = pcm.len() / channels;
+
+        let packet = match packet {
+            Some(packet) if !packet.is_empty() => packet,
+            _ => {
+                if !frame_size.is_multiple_of(F2_5) {
+                    return Err(PhonicError::InvalidData);
+                }
+
+                let mut count = 0;
+                while count < frame_size {
+                    count +=
+                        self.decode_frame(None, &mut pcm[count * channels..], frame_size - count)?;
+                }
+
+                return Ok(frame_size);
+            }
+        };
+
+        let (toc, frames) = parse_packet(packet)?;
+        self.decode_frames(toc, &frames, pcm)
+    }
+
+    /// Decodes a self-delimited packet from the start of `data` into `pcm`, returning the number
+    /// of samples per channel written along with the length of the packet.
+    pub fn decode_self_delimited(
+        &mut self,
+        data: &[u8],
+        pcm: &mut [f32],
+    ) -> Result<(usize, usize), PhonicError> {
+        let (toc, frames, len) = parse_self_delimited(data)?;
+        let n = self.decode_frames(toc, &frames, pcm)?;
+        Ok((n, len))
+    }
+
+    fn decode_frames(
+        &mut self,
+        toc: OpusToc,
+        frames: &[&[u8]],
+        pcm: &mut [f32],
+    ) -> Result<usize, PhonicError> {
+        let channels = self.channels;
+        let frame_size = pcm.len() / channels;
+        if frames.len() * toc.frame_size > frame_size {
+            return Err(PhonicError::InvalidData);
+        }
+
+        self.mode = Some(toc.mode);
+        self.bandwidth = Some(toc.bandwidth);
+        self.frame_size = toc.frame_size;
+        self.stream_channels = if toc.stereo { 2 } else { 1 };
+
+        let mut n = 0;
+        for &frame in frames {
+            n += self.decode_frame(Some(frame), &mut pcm[n * channels..], frame_size - n)?;
+        }
+
+        Ok(n)
+    }
+
+    fn decode_frame(
+        &mut self,
+        data: Option<&[u8]>,
+        pcm: &mut [f32],
+        frame_size: usize,
+    ) -> Result<usize, PhonicError> {
+        let channels = self.channels;
+        let mut frame_size = frame_size.min(MAX_PACKET_SAMPLES / 2);
+        let data = data.filter(|data| data.len() > 1);
+        if data.is_none() {
+            frame_size = frame_size.min(self.frame_size);
+        }
+
+        let (audio_size, mode, bandwidth) = match data {
+            Some(_) => (
+                self.frame_size,
+                self.mode.ok_or(PhonicError::Unreachable)?,
+                self.bandwidth,
+            ),
+            None => {
+                let Some(mode) = self.prev_mode else {
+                    pcm[..frame_size * channels].fill(0.0);
+                    return Ok(frame_size);
+                };
+
+                let mut audio_size = frame_size;
+                if audio_size > F20 {
+                    let mut n = 0;
+                    while n < audio_size {
+                        n += self.decode_frame(
+                            None,
+                            &mut pcm[n * channels..],
+                            (audio_size - n).min(F20),
+                        )?;
+                    }
+
+                    return Ok(frame_size);
+                } else if audio_size < F20 {
+                    if audio_size > F10 {
+                        audio_size = F10;
+                    } else if mode != OpusMode::SilkOnly && audio_size > F5 && audio_size < F10 {
+                        audio_size = F5;
+                    }
+                }
+
+                (audio_size, mode, None)
+            }
+        };
+
+        let mut dec = data.map(RangeDecoder::new);
+        let mut len = data.map_or(0, |data| data.len() as isize);
+
+        let mut transition = data.is_some()
+            && self.prev_mode.is_some_and(|prev| match mode {
+                OpusMode::CeltOnly => prev != OpusMode::CeltOnly && !self.prev_redundancy,
+                _ => prev == OpusMode::CeltOnly,
+            });
+
+        let mut pcm_transition = Vec::new();
+        if transition && mode == OpusMode::CeltOnly {
+            pcm_transition = vec![0.0; F5 * channels];
+            self.decode_frame(None, &mut pcm_transition, F5.min(audio_size))?;
+        }
+
+        if audio_size > frame_size {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let frame_size = audio_size;
+        let mut pcm_silk = Vec::new();
+        if mode != OpusMode::CeltOnly {
+            pcm_silk = vec![0i16; F10.max(frame_size) * channels];
+            if self.prev_mode == Some(OpusMode::CeltOnly) {
+                self.silk.reset();
+            }
+
+            self.silk_control.payload_size_ms = (audio_size / 48).max(10);
+            if data.is_some() {
+                self.silk_control.channels_internal = self.stream_channels;
+                self.silk_control.internal_sample_rate = match (mode, bandwidth) {
+                    (OpusMode::SilkOnly, Some(OpusBandwidth::Narrow)) => 8000,
+                    (OpusMode::SilkOnly, Some(OpusBandwidth::Medium)) => 12000,
+                    _ => 16000,
+                };
+            }
+
+            let lost = data.is_none();
+            let mut decoded = 0;
+            while decoded < frame_size {
+                let first_frame = decoded == 0;
+                let out = &mut pcm_silk[decoded * channels..];
+                match self
+                    .silk
+                    .decode(&self.silk_control, lost, first_frame, dec.as_mut(), out)
+                {
+                    Ok(n) => decoded += n,
+                    // a failure to conceal a loss isn't fatal, the frame is just silent
+                    Err(_) if lost => {
+                        out.fill(0);
+                        break;
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+        }
+
+        let mut redundancy = false;
+        let mut redundancy_bytes = 0;
+        let mut celt_to_silk = false;
+        if let Some(dec) = dec.as_mut().filter(|_| mode != OpusMode::CeltOnly) {
+            let hybrid = mode == OpusMode::Hybrid;
+            if dec.tell() as isize + 17 + 20 * hybrid as isize <= 8 * len {
+                redundancy = !hybrid || dec.bit_logp(12);
+                if redundancy {
+                    celt_to_silk = dec.bit_logp(1);
+                    redundancy_bytes = match hybrid {
+                        true => dec.uint(256) as isize + 2,
+                        false => len - ((dec.tell() as isize + 7) >> 3),
+                    };
+
+                    len -= redundancy_bytes;
+                    if len * 8 < dec.tell() as isize {
+                        len = 0;
+                        redundancy_bytes = 0;
+                        redundancy = false;
+                    }
+
+                    dec.storage -= redundancy_bytes as usize;
+                }
+            }
+        }
+
+        let start_band = if mode != OpusMode::CeltOnly { 17 } else { 0 };
+        if redundancy {
+            transition = false;
+        }
+
+        if transition && mode != OpusMode::CeltOnly {
+            pcm_transition = vec![0.0; F5 * channels];
+            self.decode_frame(None, &mut pcm_transition, F5.min(audio_size))?;
+        }
+
+        if let Some(bandwidth) = bandwidth {
+            self.celt.set_end_band(match bandwidth {
+                OpusBandwidth::Narrow => 13,
+                OpusBandwidth::Medium | OpusBandwidth::Wide => 17,
+                OpusBandwidth::SuperWide => 19,
+                OpusBandwidth::Full => 21,
+            });
+        }
+
+        self.celt.set_stream_channels(self.stream_channels);
+
+        let redundant_data = data.map(|data| {
+            let start = len.max(0) as usize;
+            &data[start..start + redundancy_bytes as usize]
+        });
+
+        let mut redundant_audio = vec![0.0; F5 * channels];
+        let mut redundant_rng = 0;
+        if let Some(redundant_data) = redundant_data.filter(|_| redundancy && celt_to_silk) {
+            self.celt.set_start_band(0);
+            self.celt.decode(redundant_data, &mut redundant_audio, F5);
+            redundant_rng = self.celt.rng();
+        }
+
+        self.celt.set_start_band(start_band);
+        if mode != OpusMode::SilkOnly {
+            let celt_frame_size = F20.min(frame_size);
+            if self.prev_mode.is_some_and(|prev| prev != mode) && !self.prev_redundancy {
+                self.celt.reset();
+            }
+
+            self.celt
+                .decode_with_ec(dec.as_mut(), len.max(0) as usize, pcm, celt_frame_size);
+        } else {
+            pcm[..frame_size * channels].fill(0.0);
+            if self.prev_mode == Some(OpusMode::Hybrid)
+                && !(redundancy && celt_to_silk && self.prev_redundancy)
+            {
+                self.celt.set_start_band(0);
+                self.celt.decode(&[0xff, 0xff], pcm, F2_5);
+            }
+        }
+
+        if mode != OpusMode::CeltOnly {
+            for (pcm, &silk) in pcm[..frame_size * channels].iter_mut().zip(&pcm_silk) {
+                *pcm += (1.0 / 32768.0) * silk as f32;
+            }
+        }
+
+        if let Some(redundant_data) = redundant_data.filter(|_| redundancy && !celt_to_silk) {
+            self.celt.reset();
+            self.celt.set_start_band(0);
+            self.celt.decode(redundant_data, &mut redundant_audio, F5);
+            redundant_rng = self.celt.rng();
+
+            let tail = &mut pcm[channels * (frame_size - F2_5)..];
+            let in1 = tail[..channels * F2_5].to_vec();
+            smooth_fade(
+                &in1,
+                &redundant_audio[channels * F2_5..],
+                tail,
+                F2_5,
+                channels,
+            );
+        }
+
+        if redundancy && celt_to_silk {
+            pcm[..channels * F2_5].copy_from_slice(&redundant_audio[..channels * F2_5]);
+            let tail = &mut pcm[channels * F2_5..];
+            let in2 = tail[..channels * F2_5].to_vec();
+            smooth_fade(
+                &redundant_audio[channels * F2_5..],
+                &in2,
+                tail,
+                F2_5,
+                channels,
+            );
+        }
+
+        if transition {
+            if audio_size >= F5 {
+                pcm[..channels * F2_5].copy_from_slice(&pcm_transition[..channels * F2_5]);
+                let tail = &mut pcm[channels * F2_5..];
+                let in2 = tail[..channels * F2_5].to_vec();
+                smooth_fade(
+                    &pcm_transition[channels * F2_5..],
+                    &in2,
+                    tail,
+                    F2_5,
+                    channels,
+                );
+            } else {
+                let in2 = pcm[..channels * F2_5].to_vec();
+                smooth_fade(&pcm_transition, &in2, pcm, F2_5, channels);
+            }
+        }
+
+        self.final_range = match dec {
+            Some(dec) if len > 1 => dec.rng() ^ redundant_rng,
+            _ => 0,
+        };
+
+        self.prev_mode = Some(mode);
+        self.prev_redundancy = redundancy && !celt_to_silk;
+        Ok(audio_size)
+    }
+}
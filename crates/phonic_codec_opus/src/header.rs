@@ -0,0 +1,165 @@
+use phonic_core::PhonicError;
+
+pub const OPUS_HEAD_MARKER: [u8; 8] = *b"OpusHead";
+pub const OPUS_TAGS_MARKER: [u8; 8] = *b"OpusTags";
+
+const OPUS_HEAD_LEN: usize = 19;
+
+/// How the coded streams of a packet map to output channels. Family 0 holds a single mono or
+/// stereo stream, while families 1 and 2 hold several streams, the first of which are coupled
+/// stereo pairs, and a table that picks a stream channel for each output channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelMapping {
+    pub family: u8,
+    pub n_streams: u8,
+    pub n_coupled: u8,
+    pub table: Vec<u8>,
+}
+
+impl ChannelMapping {
+    /// The mapping of family 0, for one or two channels.
+    pub fn new(n_channels: u8) -> Self {
+        Self {
+            family: 0,
+            n_streams: 1,
+            n_coupled: (n_channels == 2) as u8,
+            table: (0..n_channels).collect(),
+        }
+    }
+
+    /// The number of coded channels across every stream.
+    pub fn n_coded_channels(&self) -> usize {
+        self.n_streams as usize + self.n_coupled as usize
+    }
+}
+
+/// The identification header, which is the first packet of an ogg opus stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpusHead {
+    pub version: u8,
+    pub n_channels: u8,
+    /// The number of 48 kHz frames to drop from the start of the decoded output.
+    pub pre_skip: u16,
+    /// The sample rate of the original input, which does not affect decoding.
+    pub input_sample_rate: u32,
+    /// The gain to apply to the decoded output, in 1/256 dB.
+    pub output_gain: i16,
+    pub mapping: ChannelMapping,
+}
+
+impl OpusHead {
+    pub fn read(packet: &[u8]) -> Result<Self, PhonicError> {
+        if packet.len() < OPUS_HEAD_LEN || packet[..8] != OPUS_HEAD_MARKER {
+            return Err(PhonicError::InvalidData);
+        }
+
+        // versions share a major version in the upper nibble and stay compatible within it
+        let version = packet[8];
+        if version >> 4 != 0 {
+            return Err(PhonicError::Unsupported);
+        }
+
+        let n_channels = packet[9];
+        if n_channels == 0 {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let mapping = match packet[18] {
+            0 if n_channels > 2 => return Err(PhonicError::InvalidData),
+            0 => ChannelMapping::new(n_channels),
+            family => {
+                let table_end = OPUS_HEAD_LEN + 2 + n_channels as usize;
+                let table = packet
+                    .get(OPUS_HEAD_LEN + 2..table_end)
+                    .ok_or(PhonicError::InvalidData)?;
+
+                let mapping = ChannelMapping {
+                    family,
+                    n_streams: packet[OPUS_HEAD_LEN],
+                    n_coupled: packet[OPUS_HEAD_LEN + 1],
+                    table: table.to_vec(),
+                };
+
+                let n_coded_channels = mapping.n_coded_channels();
+                if mapping.n_streams == 0
+                    || mapping.n_coupled > mapping.n_streams
+                    || n_coded_channels > 255
+                    || mapping
+                        .table
+                        .iter()
+                        .any(|&i| i != 255 && i as usize >= n_coded_channels)
+                {
+                    return Err(PhonicError::InvalidData);
+                }
+
+                mapping
+            }
+        };
+
+        Ok(Self {
+            version,
+            n_channels,
+            pre_skip: u16::from_le_bytes([packet[10], packet[11]]),
+            input_sample_rate: u32::from_le_bytes([packet[12], packet[13], packet[14], packet[15]]),
+            output_gain: i16::from_le_bytes([packet[16], packet[17]]),
+            mapping,
+        })
+    }
+
+    /// The factor the decoded samples are scaled by to apply the output gain.
+    pub fn gain_factor(&self) -> f32 {
+        10f32.powf(self.output_gain as f32 / (20.0 * 256.0))
+    }
+}
+
+/// The comment header, which is the second packet of an ogg opus stream. Comments are stored as
+/// `NAME=value` pairs, where names are compared without regard to case.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpusTags {
+    pub vendor: String,
+    pub comments: Vec<(String, String)>,
+}
+
+impl OpusTags {
+    pub fn read(packet: &[u8]) -> Result<Self, PhonicError> {
+        let mut rest = packet
+            .strip_prefix(&OPUS_TAGS_MARKER)
+            .ok_or(PhonicError::InvalidData)?;
+
+        let vendor = read_string(&mut rest)?;
+        let n_comments = read_u32(&mut rest)?;
+        let mut comments = Vec::new();
+        for _ in 0..n_comments {
+            let comment = read_string(&mut rest)?;
+            let (name, value) = comment.split_once('=').unwrap_or((&comment, ""));
+            comments.push((name.to_owned(), value.to_owned()));
+        }
+
+        Ok(Self { vendor, comments })
+    }
+
+    /// Returns the values of every comment with the given name.
+    pub fn get<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.comments
+            .iter()
+            .filter(move |(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+fn read_u32(rest: &mut &[u8]) -> Result<u32, PhonicError> {
+    let (bytes, tail) = rest
+        .split_first_chunk::<4>()
+        .ok_or(PhonicError::InvalidData)?;
+
+    *rest = tail;
+    Ok(u32::from_le_bytes(*bytes))
+}
+
+fn read_string(rest: &mut &[u8]) -> Result<String, PhonicError> {
+    let len = read_u32(rest)? as usize;
+    let (bytes, tail) = rest.split_at_checked(len).ok_or(PhonicError::InvalidData)?;
+
+    *rest = tail;
+    Ok(String::from_utf8_lossy(bytes).into_owned())
+}
@@ -0,0 +1,369 @@
+use crate::packet::MAX_PACKET_SAMPLES;
+use std::{any::TypeId, marker::PhantomData};
+use phonic_core::PhonicError;
+use phonic_io_core::{
+    utils::TaggedSignal, CodecTag, DynCodecConstructor, DynStream, Stream, StreamObserver,
+    StreamReader, StreamSeeker, StreamSpec,
+};
+use phonic_signal::{Signal, SignalObserver, SignalReader, SignalSeeker, SignalSpec, SignalWriter};
+
+mod celt;
+mod decoder;
+mod header;
+mod multistream;
+mod packet;
+mod range;
+mod silk;
+mod tag;
+
+pub use decoder::OpusDecoder;
+pub use header::*;
+pub use multistream::MultistreamDecoder;
+pub use packet::*;
+pub use tag::*;
+
+/// The fewest bytes read from the inner stream at a time while gathering a packet.
+const READ_LEN: usize = 64 * 1024;
+
+/// The number of frames decoded ahead of a seek target, so the decoder has settled by the time it
+/// reaches it. This is the 80 ms that the ogg opus specification recommends.
+const SEEK_PREROLL: u64 = 3840;
+
+/// Decodes an ogg opus stream, where each read of the inner stream gives one packet, as the ogg
+/// format passes them on. The first two packets hold the identification and comment headers, and
+/// the inner stream is positioned by granule, which counts frames at 48 kHz from the start of the
+/// stream, including the frames the pre-skip drops. Samples are always decoded at 48 kHz, with
+/// the output gain of the header applied.
+pub struct OpusCodec<T, C: CodecTag = OpusCodecTag> {
+    inner: T,
+    signal_spec: SignalSpec,
+    head: Option<OpusHead>,
+    tags: Option<OpusTags>,
+    decoder: Option<MultistreamDecoder>,
+    packet: Vec<u8>,
+    samples: Vec<f32>,
+    range: (usize, usize),
+    gain: f32,
+    granule: u64,
+    buffered_i: u64,
+    position: u64,
+    _tag: PhantomData<C>,
+}
+
+pub fn fill_opus_spec<C>(spec: &mut StreamSpec<C>) -> Result<(), PhonicError>
+where
+    C: CodecTag + TryInto<OpusCodecTag>,
+{
+    let codec = spec.codec.ok_or(PhonicError::MissingData)?;
+    let _: OpusCodecTag = codec.try_into().map_err(|_| PhonicError::InvalidData)?;
+
+    if *spec.sample_type.get_or_insert(TypeId::of::<f32>()) != TypeId::of::<f32>() {
+        return Err(PhonicError::Unsupported);
+    }
+
+    let frame_rate = spec
+        .decoded_spec
+        .frame_rate
+        .get_or_insert(OpusCodecTag::FRAME_RATE);
+    if *frame_rate != OpusCodecTag::FRAME_RATE {
+        return Err(PhonicError::Unsupported);
+    }
+
+    if spec
+        .decoded_spec
+        .channels
+        .is_some_and(|channels| !(1..=255).contains(&channels.count()))
+    {
+        return Err(PhonicError::Unsupported);
+    }
+
+    Ok(())
+}
+
+pub fn opus_codec_from_stream<S>(stream: S) -> Result<TaggedSignal, PhonicError>
+where
+    S: DynStream + 'static,
+    S::Tag: TryInto<OpusCodecTag>,
+{
+    Ok(TaggedSignal::F32(Box::new(OpusCodec::from_stream(stream)?)))
+}
+
+/// Opus streams can only be decoded.
+pub fn opus_codec_from_signal<C>(
+    _signal: TaggedSignal,
+    _tag: OpusCodecTag,
+) -> Result<Box<dyn DynStream<Tag = C>>, PhonicError>
+where
+    C: CodecTag + TryInto<OpusCodecTag> + 'static,
+{
+    Err(PhonicError::Unsupported)
+}
+
+impl CodecTag for OpusCodecTag {
+    fn fill_spec(spec: &mut StreamSpec<Self>) -> Result<(), PhonicError> {
+        fill_opus_spec(spec)
+    }
+}
+
+impl DynCodecConstructor for OpusCodecTag {
+    fn from_signal(
+        &self,
+        signal: TaggedSignal,
+    ) -> Result<Box<dyn DynStream<Tag = Self>>, PhonicError> {
+        opus_codec_from_signal(signal, *self)
+    }
+
+    fn from_stream<S: DynStream<Tag = Self> + 'static>(
+        stream: S,
+    ) -> Result<TaggedSignal, PhonicError> {
+        opus_codec_from_stream(stream)
+    }
+}
+
+impl<T, C: CodecTag> OpusCodec<T, C> {
+    pub fn from_stream(inner: T) -> Result<Self, PhonicError>
+    where
+        T: Stream<Tag = C>,
+        C: TryInto<OpusCodecTag>,
+    {
+        let mut stream_spec = *inner.spec();
+        fill_opus_spec(&mut stream_spec)?;
+        let signal_spec = stream_spec.decoded_spec.build()?;
+
+        Ok(Self {
+            inner,
+            signal_spec,
+            head: None,
+            tags: None,
+            decoder: None,
+            packet: Vec::new(),
+            samples: Vec::new(),
+            range: (0, 0),
+            gain: 1.0,
+            granule: 0,
+            buffered_i: 0,
+            position: 0,
+            _tag: PhantomData,
+        })
+    }
+
+    /// Returns the identification header, once it has been read from the stream.
+    pub fn head(&self) -> Option<&OpusHead> {
+        self.head.as_ref()
+    }
+
+    /// Returns the comment header, once it has been read from the stream.
+    pub fn tags(&self) -> Option<&OpusTags> {
+        self.tags.as_ref()
+    }
+
+    pub fn as_inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn n_channels(&self) -> u64 {
+        self.signal_spec.channels.count() as u64
+    }
+
+    /// The number of samples the pre-skip drops from the start of the stream.
+    fn pre_skip_samples(&self) -> u64 {
+        self.head.as_ref().map_or(0, |head| head.pre_skip as u64) * self.n_channels()
+    }
+
+    fn set_head(&mut self, head: OpusHead) -> Result<(), PhonicError> {
+        if head.n_channels as u64 != self.n_channels() {
+            return Err(PhonicError::SignalMismatch);
+        }
+
+        self.decoder = Some(MultistreamDecoder::new(head.mapping.clone())?);
+        self.samples = vec![0.0; MAX_PACKET_SAMPLES * head.n_channels as usize];
+        self.gain = head.gain_factor();
+        self.head = Some(head);
+
+        Ok(())
+    }
+
+    /// Decodes a packet into the sample buffer, leaving out the frames past the end of the
+    /// signal.
+    fn decode_packet(&mut self, inner_granule: u64) -> Result<(), PhonicError> {
+        let Some(decoder) = &mut self.decoder else {
+            return Err(PhonicError::NotReady);
+        };
+
+        let n_channels = self.signal_spec.channels.count() as usize;
+        let n_frames = decoder.decode(&self.packet, &mut self.samples)?;
+        let mut start = self.granule;
+
+        // a packet that was cut off by a seek is skipped, which the granule of the page shows
+        if inner_granule > start + n_frames as u64 {
+            start = inner_granule - n_frames as u64;
+        }
+
+        self.granule = start + n_frames as u64;
+
+        let mut keep = n_frames;
+        if let Some((total, head)) = self.signal_spec.n_frames.zip(self.head.as_ref()) {
+            let end = head.pre_skip as u64 + total;
+            keep = keep.min(end.saturating_sub(start) as usize);
+        }
+
+        let n_samples = keep * n_channels;
+        if self.gain != 1.0 {
+            for sample in self.samples[..n_samples].iter_mut() {
+                *sample *= self.gain;
+            }
+        }
+
+        self.range = (0, n_samples);
+        self.buffered_i = start * n_channels as u64;
+        Ok(())
+    }
+}
+
+impl<T, C: CodecTag> Signal for OpusCodec<T, C> {
+    type Sample = f32;
+
+    fn spec(&self) -> &SignalSpec {
+        &self.signal_spec
+    }
+}
+
+impl<T, C: CodecTag> SignalObserver for OpusCodec<T, C> {
+    fn position(&self) -> Result<u64, PhonicError> {
+        Ok(self.position)
+    }
+}
+
+impl<T: StreamReader + StreamObserver, C: CodecTag> OpusCodec<T, C> {
+    /// Reads the next packet of the inner stream, returning false at the end of it. A read that
+    /// fills the whole buffer may have cut the packet short, so the rest of it is read as well.
+    fn read_packet(&mut self) -> Result<bool, PhonicError> {
+        self.packet.clear();
+        loop {
+            let filled = self.packet.len();
+            let len = filled.max(READ_LEN);
+            self.packet.resize(filled + len, 0);
+
+            let result = self.inner.read(&mut self.packet[filled..]);
+            let n = *result.as_ref().unwrap_or(&0);
+            self.packet.truncate(filled + n);
+            result?;
+
+            if n < len {
+                return Ok(!self.packet.is_empty());
+            }
+        }
+    }
+
+    fn read_headers(&mut self) -> Result<(), PhonicError> {
+        if self.head.is_none() {
+            if !self.read_packet()? {
+                return Err(PhonicError::MissingData);
+            }
+
+            self.set_head(OpusHead::read(&self.packet)?)?;
+        }
+
+        if self.tags.is_none() {
+            if !self.read_packet()? {
+                return Err(PhonicError::MissingData);
+            }
+
+            self.tags = Some(OpusTags::read(&self.packet)?);
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: StreamReader + StreamObserver, C: CodecTag> SignalReader for OpusCodec<T, C> {
+    fn read(&mut self, buf: &mut [Self::Sample]) -> Result<usize, PhonicError> {
+        self.read_headers()?;
+
+        loop {
+            // drop what comes before the position, which is the pre-skip at the start of the
+            // stream and the preroll after a seek
+            let target_i = self.position + self.pre_skip_samples();
+            let pending = (self.range.1 - self.range.0) as u64;
+            let skip = target_i.saturating_sub(self.buffered_i).min(pending);
+            self.range.0 += skip as usize;
+            self.buffered_i += skip;
+
+            if self.range.0 < self.range.1 {
+                break;
+            }
+
+            if !self.read_packet()? {
+                return Ok(0);
+            }
+
+            let inner_granule = self.inner.position()?;
+            self.decode_packet(inner_granule)?;
+        }
+
+        let pending = &self.samples[self.range.0..self.range.1];
+        let n = buf.len().min(pending.len());
+        buf[..n].copy_from_slice(&pending[..n]);
+        self.range.0 += n;
+        self.buffered_i += n as u64;
+        self.position += n as u64;
+
+        Ok(n)
+    }
+}
+
+impl<T, C: CodecTag> SignalWriter for OpusCodec<T, C> {
+    fn write(&mut self, _buf: &[Self::Sample]) -> Result<usize, PhonicError> {
+        Err(PhonicError::Unsupported)
+    }
+
+    fn flush(&mut self) -> Result<(), PhonicError> {
+        Ok(())
+    }
+}
+
+impl<T, C> SignalSeeker for OpusCodec<T, C>
+where
+    T: StreamReader + StreamObserver + StreamSeeker,
+    C: CodecTag,
+{
+    fn seek(&mut self, offset: i64) -> Result<(), PhonicError> {
+        let position = self
+            .position
+            .checked_add_signed(offset)
+            .ok_or(PhonicError::InvalidData)?;
+
+        self.read_headers()?;
+        let n_channels = self.n_channels();
+        let target = (position + self.pre_skip_samples()) / n_channels;
+        let start = target.saturating_sub(SEEK_PREROLL);
+
+        // decoding carries on from where it is if that is no further from the target
+        if position >= self.position && start <= self.granule {
+            self.position = position;
+            return Ok(());
+        }
+
+        let inner_position = self.inner.position()?;
+        self.inner.seek(start as i64 - inner_position as i64)?;
+        let granule = self.inner.position()?;
+        if granule > target {
+            return Err(PhonicError::InvalidData);
+        }
+
+        if let Some(decoder) = &mut self.decoder {
+            decoder.reset();
+        }
+
+        self.range = (0, 0);
+        self.granule = granule;
+        self.buffered_i = granule * n_channels;
+        self.position = position;
+
+        Ok(())
+    }
+}
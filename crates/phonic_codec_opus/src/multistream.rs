@@ -0,0 +1,101 @@
+use crate::{decoder::OpusDecoder, header::ChannelMapping, packet::MAX_PACKET_SAMPLES};
+use phonic_core::PhonicError;
+
+/// Decodes packets that hold one or more streams, spreading the channels of the streams over the
+/// output channels the way a channel mapping describes. Every stream but the last is
+/// self-delimited, and the coupled stereo streams come first.
+pub struct MultistreamDecoder {
+    mapping: ChannelMapping,
+    decoders: Vec<OpusDecoder>,
+    buffers: Vec<Vec<f32>>,
+}
+
+impl MultistreamDecoder {
+    pub fn new(mapping: ChannelMapping) -> Result<Self, PhonicError> {
+        let n_streams = mapping.n_streams as usize;
+        let n_coupled = mapping.n_coupled as usize;
+        if n_streams == 0 || n_coupled > n_streams {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let decoders = (0..n_streams)
+            .map(|i| OpusDecoder::new(if i < n_coupled { 2 } else { 1 }))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let buffers = decoders
+            .iter()
+            .map(|decoder| vec![0.0; MAX_PACKET_SAMPLES * decoder.channels()])
+            .collect();
+
+        Ok(Self {
+            mapping,
+            decoders,
+            buffers,
+        })
+    }
+
+    pub fn channels(&self) -> usize {
+        self.mapping.table.len()
+    }
+
+    pub fn reset(&mut self) {
+        for decoder in self.decoders.iter_mut() {
+            decoder.reset();
+        }
+    }
+
+    /// Decodes a packet into `pcm`, returning the number of samples per channel written. `pcm`
+    /// must have room for the longest packet, which is 120 ms.
+    pub fn decode(&mut self, packet: &[u8], pcm: &mut [f32]) -> Result<usize, PhonicError> {
+        let n_channels = self.channels();
+        if pcm.len() < MAX_PACKET_SAMPLES * n_channels {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let mut data = packet;
+        let mut n_frames = None;
+        let last = self.decoders.len() - 1;
+        for (i, (decoder, buf)) in self.decoders.iter_mut().zip(&mut self.buffers).enumerate() {
+            let n = match i == last {
+                true => decoder.decode(Some(data), buf)?,
+                false => {
+                    let (n, len) = decoder.decode_self_delimited(data, buf)?;
+                    data = &data[len..];
+                    n
+                }
+            };
+
+            // every stream has to cover the same stretch of time
+            if n_frames.is_some_and(|n_frames| n_frames != n) {
+                return Err(PhonicError::InvalidData);
+            }
+
+            n_frames = Some(n);
+        }
+
+        let n_frames = n_frames.ok_or(PhonicError::Unreachable)?;
+        let n_coupled = self.mapping.n_coupled as usize;
+        for (c, &coded_i) in self.mapping.table.iter().enumerate() {
+            let samples = pcm[c..n_frames * n_channels].iter_mut().step_by(n_channels);
+            if coded_i == 255 {
+                samples.for_each(|sample| *sample = 0.0);
+                continue;
+            }
+
+            let coded_i = coded_i as usize;
+            let (stream_i, stream_c, stream_channels) = match coded_i < 2 * n_coupled {
+                true => (coded_i / 2, coded_i % 2, 2),
+                false => (coded_i - n_coupled, 0, 1),
+            };
+
+            let buf = self.buffers[stream_i][stream_c..]
+                .iter()
+                .step_by(stream_channels);
+            for (sample, &decoded) in samples.zip(buf) {
+                *sample = decoded;
+            }
+        }
+
+        Ok(n_frames)
+    }
+}
@@ -0,0 +1,245 @@
+use phonic_core::PhonicError;
+
+/// The layers used to code the frames of a packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpusMode {
+    SilkOnly,
+    Hybrid,
+    CeltOnly,
+}
+
+/// The audio bandwidth of the frames of a packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OpusBandwidth {
+    Narrow,
+    Medium,
+    Wide,
+    SuperWide,
+    Full,
+}
+
+/// The configuration shared by every frame of a packet, read from its first byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpusToc {
+    pub mode: OpusMode,
+    pub bandwidth: OpusBandwidth,
+    /// The number of 48 kHz samples in each frame.
+    pub frame_size: usize,
+    pub stereo: bool,
+}
+
+impl OpusToc {
+    pub fn from_byte(toc: u8) -> Self {
+        let config = (toc >> 3) & 0x3;
+        let (mode, bandwidth, frame_size) = if toc & 0x80 != 0 {
+            let bandwidth = match (toc >> 5) & 0x3 {
+                0 => OpusBandwidth::Narrow,
+                1 => OpusBandwidth::Wide,
+                2 => OpusBandwidth::SuperWide,
+                _ => OpusBandwidth::Full,
+            };
+
+            (OpusMode::CeltOnly, bandwidth, 120 << config)
+        } else if toc & 0x60 == 0x60 {
+            let bandwidth = match toc & 0x10 {
+                0 => OpusBandwidth::SuperWide,
+                _ => OpusBandwidth::Full,
+            };
+
+            (OpusMode::Hybrid, bandwidth, 480 << (config & 1))
+        } else {
+            let bandwidth = match (toc >> 5) & 0x3 {
+                0 => OpusBandwidth::Narrow,
+                1 => OpusBandwidth::Medium,
+                _ => OpusBandwidth::Wide,
+            };
+
+            let frame_size = match config {
+                3 => 2880,
+                _ => 480 << config,
+            };
+
+            (OpusMode::SilkOnly, bandwidth, frame_size)
+        };
+
+        Self {
+            mode,
+            bandwidth,
+            frame_size,
+            stereo: toc & 0x4 != 0,
+        }
+    }
+}
+
+/// The most frames a packet can hold.
+const MAX_FRAMES: usize = 48;
+
+/// The longest a single frame can be.
+const MAX_FRAME_LEN: usize = 1275;
+
+/// The most 48 kHz samples a packet can hold.
+pub(crate) const MAX_PACKET_SAMPLES: usize = 5760;
+
+fn parse_size(data: &[u8]) -> Result<(usize, usize), PhonicError> {
+    match data {
+        [b0, ..] if *b0 < 252 => Ok((*b0 as usize, 1)),
+        [b0, b1, ..] => Ok((4 * *b1 as usize + *b0 as usize, 2)),
+        _ => Err(PhonicError::InvalidData),
+    }
+}
+
+/// Splits a packet into its table of contents and the frames it holds.
+pub fn parse_packet(packet: &[u8]) -> Result<(OpusToc, Vec<&[u8]>), PhonicError> {
+    parse_frames(packet, false).map(|(toc, frames, _)| (toc, frames))
+}
+
+/// Splits a self-delimited packet, which codes the length of its last frame so that another
+/// packet can follow it, returning the length of the packet along with its frames. Every stream
+/// but the last of a multistream packet is self-delimited.
+pub fn parse_self_delimited(packet: &[u8]) -> Result<(OpusToc, Vec<&[u8]>, usize), PhonicError> {
+    parse_frames(packet, true)
+}
+
+fn parse_frames(
+    packet: &[u8],
+    self_delimited: bool,
+) -> Result<(OpusToc, Vec<&[u8]>, usize), PhonicError> {
+    let (&toc_byte, mut data) = packet.split_first().ok_or(PhonicError::InvalidData)?;
+    let toc = OpusToc::from_byte(toc_byte);
+
+    // the length of the data left, leaving out any padding at the end
+    let mut len = data.len();
+    let mut last_size = len as isize;
+    let mut padding = 0;
+    let mut cbr = false;
+
+    let mut sizes = Vec::with_capacity(2);
+    let count = match toc_byte & 0x3 {
+        0 => 1,
+        1 => {
+            cbr = true;
+            if !self_delimited {
+                if len % 2 != 0 {
+                    return Err(PhonicError::InvalidData);
+                }
+
+                last_size = (len / 2) as isize;
+                sizes.push(len / 2);
+            }
+
+            2
+        }
+        2 => {
+            let (size, bytes) = parse_size(data)?;
+            len -= bytes;
+            data = &data[bytes..];
+            if size > len {
+                return Err(PhonicError::InvalidData);
+            }
+
+            last_size = (len - size) as isize;
+            sizes.push(size);
+            2
+        }
+        _ => {
+            let (&ch, rest) = data.split_first().ok_or(PhonicError::InvalidData)?;
+            data = rest;
+            len -= 1;
+
+            let count = (ch & 0x3f) as usize;
+            if count == 0 || count > MAX_FRAMES || toc.frame_size * count > MAX_PACKET_SAMPLES {
+                return Err(PhonicError::InvalidData);
+            }
+
+            if ch & 0x40 != 0 {
+                loop {
+                    let (&p, rest) = data
+                        .split_first()
+                        .filter(|_| len > 0)
+                        .ok_or(PhonicError::InvalidData)?;
+                    data = rest;
+                    len -= 1;
+
+                    let n = p.min(254) as usize;
+                    len = len.checked_sub(n).ok_or(PhonicError::InvalidData)?;
+                    padding += n;
+                    if p != 255 {
+                        break;
+                    }
+                }
+            }
+
+            cbr = ch & 0x80 == 0;
+            if !cbr {
+                last_size = len as isize;
+                for _ in 0..count - 1 {
+                    let (size, bytes) = parse_size(&data[..len])?;
+                    len -= bytes;
+                    data = &data[bytes..];
+                    if size > len {
+                        return Err(PhonicError::InvalidData);
+                    }
+
+                    last_size -= (bytes + size) as isize;
+                    sizes.push(size);
+                }
+
+                if last_size < 0 {
+                    return Err(PhonicError::InvalidData);
+                }
+            } else if !self_delimited {
+                if len % count != 0 {
+                    return Err(PhonicError::InvalidData);
+                }
+
+                last_size = (len / count) as isize;
+                sizes.extend(std::iter::repeat_n(len / count, count - 1));
+            }
+
+            count
+        }
+    };
+
+    if self_delimited {
+        let (size, bytes) = parse_size(&data[..len])?;
+        len -= bytes;
+        data = &data[bytes..];
+        if size > len {
+            return Err(PhonicError::InvalidData);
+        }
+
+        if cbr {
+            if size * count > len {
+                return Err(PhonicError::InvalidData);
+            }
+
+            sizes = vec![size; count - 1];
+        } else if (bytes + size) as isize > last_size {
+            return Err(PhonicError::InvalidData);
+        }
+
+        sizes.push(size);
+    } else {
+        if last_size > MAX_FRAME_LEN as isize {
+            return Err(PhonicError::InvalidData);
+        }
+
+        sizes.push(last_size as usize);
+    }
+
+    let mut frames = Vec::with_capacity(sizes.len());
+    for size in sizes {
+        let (frame, rest) = data
+            .split_at_checked(size)
+            .ok_or(PhonicError::InvalidData)?;
+        frames.push(frame);
+        data = rest;
+    }
+
+    let packet_len = packet.len() - data.len() + padding;
+    if packet_len > packet.len() {
+        return Err(PhonicError::InvalidData);
+    }
+
+    Ok((toc, frames, packet_len))
+}
@@ -0,0 +1,255 @@
+const SYM_BITS: u32 = 8;
+const CODE_BITS: i32 = 32;
+const CODE_TOP: u32 = 1 << (CODE_BITS - 1);
+const CODE_BOT: u32 = CODE_TOP >> SYM_BITS;
+const CODE_EXTRA: u32 = (CODE_BITS as u32 - 2) % SYM_BITS + 1;
+const UINT_BITS: u32 = 8;
+const WINDOW_SIZE: u32 = 32;
+
+/// The number of fractional bits `tell_frac` counts in.
+pub(crate) const BITRES: u32 = 3;
+
+/// The range decoder shared by both layers of an opus frame. Symbols are read from the front of
+/// the buffer while raw bits are read from the back, so the decoder tracks both ends.
+pub(crate) struct RangeDecoder<'a> {
+    buf: &'a [u8],
+    pub storage: usize,
+    end_offs: usize,
+    end_window: u32,
+    n_end_bits: u32,
+    n_bits_total: i32,
+    offs: usize,
+    rng: u32,
+    val: u32,
+    ext: u32,
+    rem: u32,
+}
+
+/// The number of bits needed to hold `x`, which is zero for zero.
+pub(crate) fn ilog(x: u32) -> i32 {
+    (32 - x.leading_zeros()) as i32
+}
+
+impl<'a> RangeDecoder<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        let mut dec = Self {
+            buf,
+            storage: buf.len(),
+            end_offs: 0,
+            end_window: 0,
+            n_end_bits: 0,
+            n_bits_total: CODE_BITS + 1
+                - ((CODE_BITS - CODE_EXTRA as i32) / SYM_BITS as i32) * SYM_BITS as i32,
+            offs: 0,
+            rng: 1 << CODE_EXTRA,
+            val: 0,
+            ext: 0,
+            rem: 0,
+        };
+
+        dec.rem = dec.read_byte();
+        dec.val = dec.rng - 1 - (dec.rem >> (SYM_BITS - CODE_EXTRA));
+        dec.normalize();
+        dec
+    }
+
+    pub fn rng(&self) -> u32 {
+        self.rng
+    }
+
+    fn read_byte(&mut self) -> u32 {
+        if self.offs < self.storage {
+            self.offs += 1;
+            self.buf[self.offs - 1] as u32
+        } else {
+            0
+        }
+    }
+
+    fn read_byte_from_end(&mut self) -> u32 {
+        if self.end_offs < self.storage {
+            self.end_offs += 1;
+            self.buf[self.storage - self.end_offs] as u32
+        } else {
+            0
+        }
+    }
+
+    fn normalize(&mut self) {
+        while self.rng <= CODE_BOT {
+            self.n_bits_total += SYM_BITS as i32;
+            self.rng <<= SYM_BITS;
+
+            let sym = self.rem;
+            self.rem = self.read_byte();
+            let sym = ((sym << SYM_BITS) | self.rem) >> (SYM_BITS - CODE_EXTRA);
+            self.val = ((self.val << SYM_BITS) + (0xff & !sym)) & (CODE_TOP - 1);
+        }
+    }
+
+    pub fn decode(&mut self, ft: u32) -> u32 {
+        self.ext = self.rng / ft;
+        let s = self.val / self.ext;
+        ft - (s + 1).min(ft)
+    }
+
+    pub fn decode_bin(&mut self, bits: u32) -> u32 {
+        self.ext = self.rng >> bits;
+        let s = self.val / self.ext;
+        (1 << bits) - (s + 1).min(1 << bits)
+    }
+
+    pub fn update(&mut self, fl: u32, fh: u32, ft: u32) {
+        let s = self.ext.wrapping_mul(ft - fh);
+        self.val = self.val.wrapping_sub(s);
+        self.rng = match fl {
+            0 => self.rng - s,
+            _ => self.ext.wrapping_mul(fh - fl),
+        };
+
+        self.normalize();
+    }
+
+    /// Decodes a bit that is set with a probability of `1 / (1 << logp)`.
+    pub fn bit_logp(&mut self, logp: u32) -> bool {
+        let r = self.rng;
+        let d = self.val;
+        let s = r >> logp;
+        let bit = d < s;
+        if !bit {
+            self.val = d - s;
+        }
+
+        self.rng = if bit { s } else { r - s };
+        self.normalize();
+        bit
+    }
+
+    /// Decodes a symbol from an inverse cumulative distribution that totals `1 << ftb`.
+    pub fn icdf(&mut self, icdf: &[u8], ftb: u32) -> usize {
+        let mut s = self.rng;
+        let d = self.val;
+        let r = s >> ftb;
+        let mut i = 0;
+        let mut t;
+        loop {
+            t = s;
+            s = r.wrapping_mul(icdf[i] as u32);
+            if d >= s {
+                break;
+            }
+
+            i += 1;
+        }
+
+        self.val = d - s;
+        self.rng = t - s;
+        self.normalize();
+        i
+    }
+
+    /// Decodes a value that is uniformly distributed in `0..ft`.
+    pub fn uint(&mut self, ft: u32) -> u32 {
+        let ft = ft - 1;
+        let mut ftb = ilog(ft) as u32;
+        if ftb > UINT_BITS {
+            ftb -= UINT_BITS;
+            let top = (ft >> ftb) + 1;
+            let s = self.decode(top);
+            self.update(s, s + 1, top);
+
+            let t = (s << ftb) | self.bits(ftb);
+            if t <= ft {
+                return t;
+            }
+
+            ft
+        } else {
+            let s = self.decode(ft + 1);
+            self.update(s, s + 1, ft + 1);
+            s
+        }
+    }
+
+    /// Reads raw bits from the end of the buffer.
+    pub fn bits(&mut self, bits: u32) -> u32 {
+        let mut window = self.end_window;
+        let mut available = self.n_end_bits;
+        if available < bits {
+            loop {
+                window |= self.read_byte_from_end() << available;
+                available += SYM_BITS;
+                if available > WINDOW_SIZE - SYM_BITS {
+                    break;
+                }
+            }
+        }
+
+        let value = window & ((1u64 << bits) - 1) as u32;
+        self.end_window = window.checked_shr(bits).unwrap_or(0);
+        self.n_end_bits = available - bits;
+        self.n_bits_total += bits as i32;
+        value
+    }
+
+    /// Decodes a value from a laplace distribution, as used by the coarse band energies.
+    pub fn laplace(&mut self, fs: u32, decay: u32) -> i32 {
+        const MINP: u32 = 1;
+        const NMIN: u32 = 16;
+
+        let mut val = 0i32;
+        let fm = self.decode_bin(15);
+        let mut fl = 0;
+        let mut fs = fs;
+        if fm >= fs {
+            val += 1;
+            fl = fs;
+            fs = (((32768 - MINP * (2 * NMIN) - fs) * (16384 - decay)) >> 15) + MINP;
+            while fs > MINP && fm >= fl + 2 * fs {
+                fs *= 2;
+                fl += fs;
+                fs = ((fs - 2 * MINP) * decay) >> 15;
+                fs += MINP;
+                val += 1;
+            }
+
+            if fs <= MINP {
+                let di = (fm - fl) >> 1;
+                val += di as i32;
+                fl += 2 * di * MINP;
+            }
+
+            if fm < fl + fs {
+                val = -val;
+            } else {
+                fl += fs;
+            }
+        }
+
+        self.update(fl, (fl + fs).min(32768), 32768);
+        val
+    }
+
+    /// The number of whole bits decoded so far, rounded up.
+    pub fn tell(&self) -> i32 {
+        self.n_bits_total - ilog(self.rng)
+    }
+
+    /// The number of bits decoded so far, in eighths of a bit.
+    pub fn tell_frac(&self) -> u32 {
+        const CORRECTION: [u32; 8] = [35733, 38967, 42495, 46340, 50535, 55109, 60097, 65535];
+
+        let n_bits = (self.n_bits_total as u32) << BITRES;
+        let l = ilog(self.rng);
+        let r = self.rng >> (l - 16);
+        let mut b = (r >> 12) - 8;
+        b += (r > CORRECTION[b as usize]) as u32;
+        n_bits - ((l as u32) << 3) - b
+    }
+
+    /// Makes the decoder believe it has read the whole buffer, as a silent celt frame does.
+    pub fn skip_to_end(&mut self, len: usize) {
+        let tell = len as i32 * 8;
+        self.n_bits_total += tell - self.tell();
+    }
+}
@@ -0,0 +1,596 @@
+use crate::{
+    range::RangeDecoder,
+    silk::{
+        cng::Cng,
+        math::{
+            add_sat32, div32_varq, inverse32_varq, log2lin, lshift_sat32, rand, rshift_round,
+            sat16, smlawb, smulbb, smulwb, smulww,
+        },
+        nlsf::{
+            bwexpander, nlsf2a, NlsfCodebook, NLSF_CB_NB_MB, NLSF_CB_WB, NLSF_QUANT_MAX_AMPLITUDE,
+        },
+        plc::Plc,
+        pulses::{decode_pulses, SHELL_CODEC_FRAME_LENGTH},
+        resampler::Resampler,
+        tables::*,
+    },
+};
+
+pub(crate) const TYPE_NO_VOICE_ACTIVITY: usize = 0;
+pub(crate) const TYPE_VOICED: usize = 2;
+
+pub(crate) const MAX_NB_SUBFR: usize = 4;
+pub(crate) const MAX_LPC_ORDER: usize = 16;
+pub(crate) const LTP_ORDER: usize = 5;
+pub(crate) const MAX_FRAME_LENGTH: usize = 320;
+const MAX_SUB_FRAME_LENGTH: usize = 80;
+const SUB_FRAME_LENGTH_MS: usize = 5;
+const LTP_MEM_LENGTH_MS: usize = 20;
+
+const PE_MIN_LAG_MS: i32 = 2;
+const PE_MAX_LAG_MS: i32 = 18;
+
+const QUANT_LEVEL_ADJUST_Q10: i32 = 80;
+const BWE_AFTER_LOSS_Q16: i32 = 63570;
+
+const MIN_DELTA_GAIN_QUANT: i32 = -4;
+const MAX_DELTA_GAIN_QUANT: i32 = 36;
+const N_LEVELS_QGAIN: i32 = 64;
+const GAIN_OFFSET: i32 = 2090;
+const GAIN_INV_SCALE_Q16: i32 = 1907825;
+
+/// How a frame's parameters are coded relative to the previous frame.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum CondCoding {
+    Independently,
+    IndependentlyNoLtpScaling,
+    Conditionally,
+}
+
+/// The quantization indices of a frame, as read from the bitstream.
+#[derive(Default, Clone, Copy)]
+pub(crate) struct SideInfoIndices {
+    pub gains_indices: [i32; MAX_NB_SUBFR],
+    pub ltp_index: [usize; MAX_NB_SUBFR],
+    pub nlsf_indices: [i8; MAX_LPC_ORDER + 1],
+    pub lag_index: i32,
+    pub contour_index: usize,
+    pub signal_type: usize,
+    pub quant_offset_type: usize,
+    pub nlsf_interp_coef_q2: i32,
+    pub per_index: usize,
+    pub ltp_scale_index: usize,
+    pub seed: i32,
+}
+
+/// The dequantized parameters of a frame.
+#[derive(Default)]
+pub(crate) struct DecoderControl {
+    pub pitch_l: [i32; MAX_NB_SUBFR],
+    pub gains_q16: [i32; MAX_NB_SUBFR],
+    pub pred_coef_q12: [[i16; MAX_LPC_ORDER]; 2],
+    pub ltp_coef_q14: [i16; LTP_ORDER * MAX_NB_SUBFR],
+    pub ltp_scale_q14: i32,
+}
+
+/// The decoder state of a single silk channel.
+pub(crate) struct ChannelState {
+    pub prev_gain_q16: i32,
+    pub exc_q14: [i32; MAX_FRAME_LENGTH],
+    pub s_lpc_q14_buf: [i32; MAX_LPC_ORDER],
+    pub out_buf: [i16; MAX_FRAME_LENGTH + 2 * MAX_SUB_FRAME_LENGTH],
+    pub lag_prev: i32,
+    pub last_gain_index: i32,
+    pub fs_khz: usize,
+    pub nb_subfr: usize,
+    pub frame_length: usize,
+    pub subfr_length: usize,
+    pub ltp_mem_length: usize,
+    pub lpc_order: usize,
+    pub prev_nlsf_q15: [i16; MAX_LPC_ORDER],
+    pub first_frame_after_reset: bool,
+    pitch_lag_low_bits_icdf: &'static [u8],
+    pitch_contour_icdf: &'static [u8],
+    pub frames_decoded: usize,
+    pub frames_per_packet: usize,
+    ec_prev_signal_type: usize,
+    ec_prev_lag_index: i32,
+    pub vad_flags: [bool; 3],
+    pub lbrr_flag: bool,
+    pub lbrr_flags: [bool; 3],
+    pub resampler: Resampler,
+    nlsf_cb: &'static NlsfCodebook,
+    pub indices: SideInfoIndices,
+    pub cng: Cng,
+    pub loss_cnt: i32,
+    pub prev_signal_type: usize,
+    pub plc: Plc,
+}
+
+/// Whitens `input` with the lpc filter `b`, zeroing the first `b.len()` samples of `out`.
+pub(crate) fn lpc_analysis_filter(out: &mut [i16], input: &[i16], b: &[i16], len: usize) {
+    let d = b.len();
+    for ix in d..len {
+        let mut out32_q12 = 0i32;
+        for (j, &b) in b.iter().enumerate() {
+            out32_q12 = out32_q12.wrapping_add(smulbb(input[ix - 1 - j] as i32, b as i32));
+        }
+
+        let out32_q12 = ((input[ix] as i32) << 12).wrapping_sub(out32_q12);
+        out[ix] = sat16(rshift_round(out32_q12, 12));
+    }
+
+    out[..d].fill(0);
+}
+
+/// Runs the lpc synthesis filter over the `len` samples following `MAX_LPC_ORDER` samples of
+/// history in `s_q14`, which initially hold the excitation.
+pub(crate) fn lpc_synthesis(
+    s_q14: &mut [i32],
+    a_q12: &[i16],
+    len: usize,
+    mut out: impl FnMut(usize, i32),
+) {
+    for i in 0..len {
+        let mut pred_q10 = a_q12.len() as i32 >> 1;
+        for (j, &a) in a_q12.iter().enumerate() {
+            pred_q10 = smlawb(pred_q10, s_q14[MAX_LPC_ORDER + i - j - 1], a as i32);
+        }
+
+        let s = add_sat32(s_q14[MAX_LPC_ORDER + i], lshift_sat32(pred_q10, 4));
+        s_q14[MAX_LPC_ORDER + i] = s;
+        out(i, s);
+    }
+}
+
+impl ChannelState {
+    pub fn new() -> Self {
+        let mut state = Self {
+            prev_gain_q16: 65536,
+            exc_q14: [0; MAX_FRAME_LENGTH],
+            s_lpc_q14_buf: [0; MAX_LPC_ORDER],
+            out_buf: [0; MAX_FRAME_LENGTH + 2 * MAX_SUB_FRAME_LENGTH],
+            lag_prev: 0,
+            last_gain_index: 0,
+            fs_khz: 0,
+            nb_subfr: 0,
+            frame_length: 0,
+            subfr_length: 0,
+            ltp_mem_length: 0,
+            lpc_order: 0,
+            prev_nlsf_q15: [0; MAX_LPC_ORDER],
+            first_frame_after_reset: true,
+            pitch_lag_low_bits_icdf: &UNIFORM8_ICDF,
+            pitch_contour_icdf: &PITCH_CONTOUR_ICDF,
+            frames_decoded: 0,
+            frames_per_packet: 0,
+            ec_prev_signal_type: 0,
+            ec_prev_lag_index: 0,
+            vad_flags: [false; 3],
+            lbrr_flag: false,
+            lbrr_flags: [false; 3],
+            resampler: Resampler::new(16000),
+            nlsf_cb: &NLSF_CB_WB,
+            indices: SideInfoIndices::default(),
+            cng: Cng::default(),
+            loss_cnt: 0,
+            prev_signal_type: 0,
+            plc: Plc::default(),
+        };
+
+        state.cng_reset();
+        state.plc_reset();
+        state
+    }
+
+    /// Configures the internal sample rate, resetting the state that depends on it.
+    pub fn set_fs(&mut self, fs_khz: usize) {
+        self.subfr_length = SUB_FRAME_LENGTH_MS * fs_khz;
+        let frame_length = self.nb_subfr * self.subfr_length;
+        if self.fs_khz != fs_khz {
+            self.resampler = Resampler::new(fs_khz as i32 * 1000);
+        }
+
+        if self.fs_khz != fs_khz || frame_length != self.frame_length {
+            self.pitch_contour_icdf = match (fs_khz, self.nb_subfr) {
+                (8, MAX_NB_SUBFR) => &PITCH_CONTOUR_NB_ICDF,
+                (8, _) => &PITCH_CONTOUR_10_MS_NB_ICDF,
+                (_, MAX_NB_SUBFR) => &PITCH_CONTOUR_ICDF,
+                _ => &PITCH_CONTOUR_10_MS_ICDF,
+            };
+
+            if self.fs_khz != fs_khz {
+                self.ltp_mem_length = LTP_MEM_LENGTH_MS * fs_khz;
+                (self.lpc_order, self.nlsf_cb) = match fs_khz {
+                    16 => (16, &NLSF_CB_WB),
+                    _ => (10, &NLSF_CB_NB_MB),
+                };
+
+                self.pitch_lag_low_bits_icdf = match fs_khz {
+                    16 => &UNIFORM8_ICDF,
+                    12 => &UNIFORM6_ICDF,
+                    _ => &UNIFORM4_ICDF,
+                };
+
+                self.first_frame_after_reset = true;
+                self.lag_prev = 100;
+                self.last_gain_index = 10;
+                self.prev_signal_type = TYPE_NO_VOICE_ACTIVITY;
+                self.out_buf.fill(0);
+                self.s_lpc_q14_buf.fill(0);
+            }
+
+            self.fs_khz = fs_khz;
+            self.frame_length = frame_length;
+        }
+    }
+
+    /// Decodes the quantization indices of a frame.
+    pub fn decode_indices(
+        &mut self,
+        dec: &mut RangeDecoder,
+        frame_index: usize,
+        decode_lbrr: bool,
+        cond: CondCoding,
+    ) {
+        let ix = match decode_lbrr || self.vad_flags[frame_index] {
+            true => dec.icdf(&TYPE_OFFSET_VAD_ICDF, 8) + 2,
+            false => dec.icdf(&TYPE_OFFSET_NO_VAD_ICDF, 8),
+        };
+
+        let indices = &mut self.indices;
+        indices.signal_type = ix >> 1;
+        indices.quant_offset_type = ix & 1;
+
+        indices.gains_indices[0] = match cond {
+            CondCoding::Conditionally => dec.icdf(&DELTA_GAIN_ICDF, 8) as i32,
+            _ => {
+                let msb = dec.icdf(&GAIN_ICDF[indices.signal_type], 8) as i32;
+                (msb << 3) + dec.icdf(&UNIFORM8_ICDF, 8) as i32
+            }
+        };
+
+        for i in 1..self.nb_subfr {
+            indices.gains_indices[i] = dec.icdf(&DELTA_GAIN_ICDF, 8) as i32;
+        }
+
+        let cb = self.nlsf_cb;
+        let cb1_index = dec.icdf(&cb.cb1_icdf[(indices.signal_type >> 1) * 32..], 8);
+        indices.nlsf_indices[0] = cb1_index as i8;
+        let (ec_ix, _) = cb.unpack(cb1_index);
+        for (i, &ec_i) in ec_ix[..cb.order].iter().enumerate() {
+            let mut ix = dec.icdf(&cb.ec_icdf[ec_i..], 8) as i32;
+            if ix == 0 {
+                ix -= dec.icdf(&NLSF_EXT_ICDF, 8) as i32;
+            } else if ix == 2 * NLSF_QUANT_MAX_AMPLITUDE {
+                ix += dec.icdf(&NLSF_EXT_ICDF, 8) as i32;
+            }
+
+            indices.nlsf_indices[i + 1] = (ix - NLSF_QUANT_MAX_AMPLITUDE) as i8;
+        }
+
+        indices.nlsf_interp_coef_q2 = match self.nb_subfr {
+            MAX_NB_SUBFR => dec.icdf(&NLSF_INTERPOLATION_FACTOR_ICDF, 8) as i32,
+            _ => 4,
+        };
+
+        if indices.signal_type == TYPE_VOICED {
+            let mut decode_absolute_lag_index = true;
+            if cond == CondCoding::Conditionally && self.ec_prev_signal_type == TYPE_VOICED {
+                let delta_lag_index = dec.icdf(&PITCH_DELTA_ICDF, 8) as i32;
+                if delta_lag_index > 0 {
+                    indices.lag_index =
+                        (self.ec_prev_lag_index + delta_lag_index - 9) as i16 as i32;
+                    decode_absolute_lag_index = false;
+                }
+            }
+
+            if decode_absolute_lag_index {
+                indices.lag_index = dec.icdf(&PITCH_LAG_ICDF, 8) as i32 * (self.fs_khz as i32 >> 1);
+                indices.lag_index += dec.icdf(self.pitch_lag_low_bits_icdf, 8) as i32;
+            }
+
+            self.ec_prev_lag_index = indices.lag_index;
+            indices.contour_index = dec.icdf(self.pitch_contour_icdf, 8);
+            indices.per_index = dec.icdf(&LTP_PER_INDEX_ICDF, 8);
+            let ltp_gain_icdf: &[u8] = match indices.per_index {
+                0 => &LTP_GAIN_ICDF_0,
+                1 => &LTP_GAIN_ICDF_1,
+                _ => &LTP_GAIN_ICDF_2,
+            };
+
+            for k in 0..self.nb_subfr {
+                indices.ltp_index[k] = dec.icdf(ltp_gain_icdf, 8);
+            }
+
+            indices.ltp_scale_index = match cond {
+                CondCoding::Independently => dec.icdf(&LTP_SCALE_ICDF, 8),
+                _ => 0,
+            };
+        }
+
+        self.ec_prev_signal_type = indices.signal_type;
+        indices.seed = dec.icdf(&UNIFORM4_ICDF, 8) as i32;
+    }
+
+    /// Returns the number of pulses to decode, rounded up to a whole number of shell blocks.
+    pub fn pulses_length(&self) -> usize {
+        (self.frame_length + SHELL_CODEC_FRAME_LENGTH - 1) & !(SHELL_CODEC_FRAME_LENGTH - 1)
+    }
+
+    fn gains_dequant(&mut self, gains_q16: &mut [i32], conditional: bool) {
+        for (k, gain_q16) in gains_q16[..self.nb_subfr].iter_mut().enumerate() {
+            let ind = self.indices.gains_indices[k];
+            if k == 0 && !conditional {
+                self.last_gain_index = ind.max(self.last_gain_index - 16);
+            } else {
+                let ind_tmp = ind + MIN_DELTA_GAIN_QUANT;
+                let double_step_size_threshold =
+                    2 * MAX_DELTA_GAIN_QUANT - N_LEVELS_QGAIN + self.last_gain_index;
+                if ind_tmp > double_step_size_threshold {
+                    self.last_gain_index += (ind_tmp << 1) - double_step_size_threshold;
+                } else {
+                    self.last_gain_index += ind_tmp;
+                }
+            }
+
+            self.last_gain_index = self.last_gain_index.clamp(0, N_LEVELS_QGAIN - 1);
+            *gain_q16 =
+                log2lin((smulwb(GAIN_INV_SCALE_Q16, self.last_gain_index) + GAIN_OFFSET).min(3967));
+        }
+    }
+
+    /// Dequantizes the gains, lpc coefficients and ltp parameters of a frame.
+    fn decode_parameters(&mut self, ctrl: &mut DecoderControl, cond: CondCoding) {
+        self.gains_dequant(&mut ctrl.gains_q16, cond == CondCoding::Conditionally);
+
+        let order = self.lpc_order;
+        let mut nlsf_q15 = [0i16; MAX_LPC_ORDER];
+        self.nlsf_cb
+            .decode(&self.indices.nlsf_indices, &mut nlsf_q15);
+        nlsf2a(&mut ctrl.pred_coef_q12[1], &nlsf_q15[..order]);
+
+        if self.first_frame_after_reset {
+            self.indices.nlsf_interp_coef_q2 = 4;
+        }
+
+        if self.indices.nlsf_interp_coef_q2 < 4 {
+            let mut nlsf0_q15 = [0i16; MAX_LPC_ORDER];
+            for i in 0..order {
+                let prev = self.prev_nlsf_q15[i] as i32;
+                nlsf0_q15[i] = (prev
+                    + ((self.indices.nlsf_interp_coef_q2 * (nlsf_q15[i] as i32 - prev)) >> 2))
+                    as i16;
+            }
+
+            nlsf2a(&mut ctrl.pred_coef_q12[0], &nlsf0_q15[..order]);
+        } else {
+            ctrl.pred_coef_q12[0] = ctrl.pred_coef_q12[1];
+        }
+
+        self.prev_nlsf_q15[..order].copy_from_slice(&nlsf_q15[..order]);
+
+        if self.loss_cnt != 0 {
+            bwexpander(&mut ctrl.pred_coef_q12[0][..order], BWE_AFTER_LOSS_Q16);
+            bwexpander(&mut ctrl.pred_coef_q12[1][..order], BWE_AFTER_LOSS_Q16);
+        }
+
+        if self.indices.signal_type == TYPE_VOICED {
+            self.decode_pitch(&mut ctrl.pitch_l);
+
+            let cbk_q7: &[[i8; LTP_ORDER]] = match self.indices.per_index {
+                0 => &LTP_GAIN_VQ_0,
+                1 => &LTP_GAIN_VQ_1,
+                _ => &LTP_GAIN_VQ_2,
+            };
+
+            for k in 0..self.nb_subfr {
+                let taps = &cbk_q7[self.indices.ltp_index[k]];
+                for (coef, &tap) in ctrl.ltp_coef_q14[k * LTP_ORDER..].iter_mut().zip(taps) {
+                    *coef = (tap as i16) << 7;
+                }
+            }
+
+            ctrl.ltp_scale_q14 = LTP_SCALES_Q14[self.indices.ltp_scale_index] as i32;
+        } else {
+            ctrl.pitch_l[..self.nb_subfr].fill(0);
+            ctrl.ltp_coef_q14[..LTP_ORDER * self.nb_subfr].fill(0);
+            self.indices.per_index = 0;
+            ctrl.ltp_scale_q14 = 0;
+        }
+    }
+
+    fn decode_pitch(&self, pitch_lags: &mut [i32]) {
+        let fs_khz = self.fs_khz as i32;
+        let contour = self.indices.contour_index;
+        let lag_cb = |k: usize| match (self.fs_khz, self.nb_subfr) {
+            (8, MAX_NB_SUBFR) => CB_LAGS_STAGE2[k][contour],
+            (8, _) => CB_LAGS_STAGE2_10_MS[k][contour],
+            (_, MAX_NB_SUBFR) => CB_LAGS_STAGE3[k][contour],
+            _ => CB_LAGS_STAGE3_10_MS[k][contour],
+        };
+
+        let min_lag = PE_MIN_LAG_MS * fs_khz;
+        let max_lag = PE_MAX_LAG_MS * fs_khz;
+        let lag = min_lag + self.indices.lag_index;
+        for (k, pitch_lag) in pitch_lags[..self.nb_subfr].iter_mut().enumerate() {
+            *pitch_lag = (lag + lag_cb(k) as i32).clamp(min_lag, max_lag);
+        }
+    }
+
+    /// Reconstructs the frame from the excitation pulses and the dequantized parameters.
+    fn decode_core(&mut self, ctrl: &mut DecoderControl, xq: &mut [i16], pulses: &[i16]) {
+        let ltp_mem_length = self.ltp_mem_length;
+        let subfr_length = self.subfr_length;
+        let order = self.lpc_order;
+
+        let mut s_ltp = vec![0i16; ltp_mem_length];
+        let mut s_ltp_q15 = vec![0i32; ltp_mem_length + self.frame_length];
+        let mut res_q14 = vec![0i32; subfr_length];
+        let mut s_lpc_q14 = vec![0i32; subfr_length + MAX_LPC_ORDER];
+
+        let offset_q10 = QUANTIZATION_OFFSETS_Q10[self.indices.signal_type >> 1]
+            [self.indices.quant_offset_type] as i32;
+        let nlsf_interpolation = self.indices.nlsf_interp_coef_q2 < 4;
+
+        let mut rand_seed = self.indices.seed;
+        for (i, &pulse) in pulses[..self.frame_length].iter().enumerate() {
+            rand_seed = rand(rand_seed);
+            let mut exc = (pulse as i32) << 14;
+            if exc > 0 {
+                exc -= QUANT_LEVEL_ADJUST_Q10 << 4;
+            } else if exc < 0 {
+                exc += QUANT_LEVEL_ADJUST_Q10 << 4;
+            }
+
+            exc += offset_q10 << 4;
+            if rand_seed < 0 {
+                exc = -exc;
+            }
+
+            self.exc_q14[i] = exc;
+            rand_seed = rand_seed.wrapping_add(pulse as i32);
+        }
+
+        s_lpc_q14[..MAX_LPC_ORDER].copy_from_slice(&self.s_lpc_q14_buf);
+        let mut s_ltp_buf_idx = ltp_mem_length;
+        for k in 0..self.nb_subfr {
+            let a_q12 = ctrl.pred_coef_q12[k >> 1];
+            let mut signal_type = self.indices.signal_type;
+
+            let gain_q10 = ctrl.gains_q16[k] >> 6;
+            let mut inv_gain_q31 = inverse32_varq(ctrl.gains_q16[k], 47);
+
+            let gain_adj_q16 = match ctrl.gains_q16[k] != self.prev_gain_q16 {
+                true => {
+                    let gain_adj_q16 = div32_varq(self.prev_gain_q16, ctrl.gains_q16[k], 16);
+                    for s in &mut s_lpc_q14[..MAX_LPC_ORDER] {
+                        *s = smulww(gain_adj_q16, *s);
+                    }
+
+                    gain_adj_q16
+                }
+                false => 1 << 16,
+            };
+
+            self.prev_gain_q16 = ctrl.gains_q16[k];
+
+            // soften the transition from a lost voiced frame to an unvoiced one
+            if self.loss_cnt != 0
+                && self.prev_signal_type == TYPE_VOICED
+                && self.indices.signal_type != TYPE_VOICED
+                && k < MAX_NB_SUBFR / 2
+            {
+                let b_q14 = &mut ctrl.ltp_coef_q14[k * LTP_ORDER..(k + 1) * LTP_ORDER];
+                b_q14.fill(0);
+                b_q14[LTP_ORDER / 2] = 4096;
+                signal_type = TYPE_VOICED;
+                ctrl.pitch_l[k] = self.lag_prev;
+            }
+
+            let b_q14 = &ctrl.ltp_coef_q14[k * LTP_ORDER..(k + 1) * LTP_ORDER];
+            let res_q14: &[i32] = if signal_type == TYPE_VOICED {
+                let lag = ctrl.pitch_l[k] as usize;
+                if k == 0 || (k == 2 && nlsf_interpolation) {
+                    // rewhiten the history with the current lpc coefficients
+                    let start_idx = ltp_mem_length - lag - order - LTP_ORDER / 2;
+                    if k == 2 {
+                        self.out_buf[ltp_mem_length..ltp_mem_length + 2 * subfr_length]
+                            .copy_from_slice(&xq[..2 * subfr_length]);
+                    }
+
+                    lpc_analysis_filter(
+                        &mut s_ltp[start_idx..],
+                        &self.out_buf[start_idx + k * subfr_length..],
+                        &a_q12[..order],
+                        ltp_mem_length - start_idx,
+                    );
+
+                    if k == 0 {
+                        inv_gain_q31 = smulwb(inv_gain_q31, ctrl.ltp_scale_q14) << 2;
+                    }
+
+                    for i in 0..lag + LTP_ORDER / 2 {
+                        s_ltp_q15[s_ltp_buf_idx - i - 1] =
+                            smulwb(inv_gain_q31, s_ltp[ltp_mem_length - i - 1] as i32);
+                    }
+                } else if gain_adj_q16 != 1 << 16 {
+                    for i in 0..lag + LTP_ORDER / 2 {
+                        let s = &mut s_ltp_q15[s_ltp_buf_idx - i - 1];
+                        *s = smulww(gain_adj_q16, *s);
+                    }
+                }
+
+                let exc_q14 = &self.exc_q14[k * subfr_length..];
+                for i in 0..subfr_length {
+                    let pred_lag = s_ltp_buf_idx - lag + LTP_ORDER / 2;
+                    let mut ltp_pred_q13 = 2;
+                    for (j, &b) in b_q14.iter().enumerate() {
+                        ltp_pred_q13 = smlawb(ltp_pred_q13, s_ltp_q15[pred_lag - j], b as i32);
+                    }
+
+                    res_q14[i] = exc_q14[i] + (ltp_pred_q13 << 1);
+                    s_ltp_q15[s_ltp_buf_idx] = res_q14[i] << 1;
+                    s_ltp_buf_idx += 1;
+                }
+
+                &res_q14
+            } else {
+                &self.exc_q14[k * subfr_length..(k + 1) * subfr_length]
+            };
+
+            s_lpc_q14[MAX_LPC_ORDER..].copy_from_slice(res_q14);
+            let xq = &mut xq[k * subfr_length..];
+            lpc_synthesis(&mut s_lpc_q14, &a_q12[..order], subfr_length, |i, s| {
+                xq[i] = sat16(rshift_round(smulww(s, gain_q10), 8));
+            });
+
+            s_lpc_q14.copy_within(subfr_length..subfr_length + MAX_LPC_ORDER, 0);
+        }
+
+        self.s_lpc_q14_buf
+            .copy_from_slice(&s_lpc_q14[..MAX_LPC_ORDER]);
+    }
+
+    /// Decodes a single frame into `out`, or conceals it if it was lost.
+    pub fn decode_frame(
+        &mut self,
+        dec: Option<&mut RangeDecoder>,
+        out: &mut [i16],
+        cond: CondCoding,
+    ) -> usize {
+        let len = self.frame_length;
+        let mut ctrl = DecoderControl::default();
+
+        match dec {
+            Some(dec) => {
+                let mut pulses = vec![0i16; self.pulses_length()];
+                self.decode_indices(dec, self.frames_decoded, false, cond);
+                decode_pulses(
+                    dec,
+                    &mut pulses,
+                    self.indices.signal_type,
+                    self.indices.quant_offset_type,
+                );
+                self.decode_parameters(&mut ctrl, cond);
+                self.decode_core(&mut ctrl, out, &pulses);
+                self.plc(&mut ctrl, out, false);
+
+                self.loss_cnt = 0;
+                self.prev_signal_type = self.indices.signal_type;
+                self.first_frame_after_reset = false;
+            }
+            None => self.plc(&mut ctrl, out, true),
+        }
+
+        let mv_len = self.ltp_mem_length - len;
+        self.out_buf.copy_within(len..len + mv_len, 0);
+        self.out_buf[mv_len..mv_len + len].copy_from_slice(&out[..len]);
+
+        self.cng(&ctrl, &mut out[..len]);
+        self.plc_glue_frames(&mut out[..len]);
+
+        self.lag_prev = ctrl.pitch_l[self.nb_subfr - 1];
+        len
+    }
+}
@@ -0,0 +1,135 @@
+use crate::silk::{
+    channel::{
+        lpc_synthesis, ChannelState, DecoderControl, MAX_FRAME_LENGTH, MAX_LPC_ORDER,
+        TYPE_NO_VOICE_ACTIVITY,
+    },
+    math::{rand, rshift_round, sat16, smulwb, smultt, smulww, sqrt_approx},
+    nlsf::nlsf2a,
+};
+
+const CNG_BUF_MASK_MAX: usize = 255;
+const CNG_GAIN_SMTH_Q16: i32 = 4634;
+const CNG_GAIN_SMTH_THRESHOLD_Q16: i32 = 46396;
+const CNG_NLSF_SMTH_Q16: i32 = 16348;
+
+/// The state used to generate comfort noise during lost frames.
+pub(crate) struct Cng {
+    exc_buf_q14: [i32; MAX_FRAME_LENGTH],
+    smth_nlsf_q15: [i16; MAX_LPC_ORDER],
+    synth_state: [i32; MAX_LPC_ORDER],
+    smth_gain_q16: i32,
+    rand_seed: i32,
+    fs_khz: usize,
+}
+
+impl Default for Cng {
+    fn default() -> Self {
+        Self {
+            exc_buf_q14: [0; MAX_FRAME_LENGTH],
+            smth_nlsf_q15: [0; MAX_LPC_ORDER],
+            synth_state: [0; MAX_LPC_ORDER],
+            smth_gain_q16: 0,
+            rand_seed: 0,
+            fs_khz: 0,
+        }
+    }
+}
+
+impl ChannelState {
+    pub fn cng_reset(&mut self) {
+        let nlsf_step_q15 = i16::MAX as i32 / (self.lpc_order as i32 + 1);
+        let mut nlsf_acc_q15 = 0;
+        for nlsf in &mut self.cng.smth_nlsf_q15[..self.lpc_order] {
+            nlsf_acc_q15 += nlsf_step_q15;
+            *nlsf = nlsf_acc_q15 as i16;
+        }
+
+        self.cng.smth_gain_q16 = 0;
+        self.cng.rand_seed = 3176576;
+    }
+
+    /// Tracks the background noise of inactive frames, and adds comfort noise to lost ones.
+    pub fn cng(&mut self, ctrl: &DecoderControl, frame: &mut [i16]) {
+        if self.fs_khz != self.cng.fs_khz {
+            self.cng_reset();
+            self.cng.fs_khz = self.fs_khz;
+        }
+
+        let order = self.lpc_order;
+        let cng = &mut self.cng;
+        if self.loss_cnt == 0 && self.prev_signal_type == TYPE_NO_VOICE_ACTIVITY {
+            for (smth, &prev) in cng.smth_nlsf_q15[..order]
+                .iter_mut()
+                .zip(&self.prev_nlsf_q15)
+            {
+                *smth += smulwb(prev as i32 - *smth as i32, CNG_NLSF_SMTH_Q16) as i16;
+            }
+
+            let mut max_gain_q16 = 0;
+            let mut subfr = 0;
+            for (i, &gain) in ctrl.gains_q16[..self.nb_subfr].iter().enumerate() {
+                if gain > max_gain_q16 {
+                    max_gain_q16 = gain;
+                    subfr = i;
+                }
+            }
+
+            let subfr_length = self.subfr_length;
+            cng.exc_buf_q14
+                .copy_within(..(self.nb_subfr - 1) * subfr_length, subfr_length);
+            cng.exc_buf_q14[..subfr_length]
+                .copy_from_slice(&self.exc_q14[subfr * subfr_length..(subfr + 1) * subfr_length]);
+
+            for &gain in &ctrl.gains_q16[..self.nb_subfr] {
+                cng.smth_gain_q16 += smulwb(gain - cng.smth_gain_q16, CNG_GAIN_SMTH_Q16);
+                if smulww(cng.smth_gain_q16, CNG_GAIN_SMTH_THRESHOLD_Q16) > gain {
+                    cng.smth_gain_q16 = gain;
+                }
+            }
+        }
+
+        if self.loss_cnt == 0 {
+            cng.synth_state[..order].fill(0);
+            return;
+        }
+
+        // match the noise to the energy the concealment lost
+        let gain_q16 = smulww(self.plc.rand_scale_q14 as i32, self.plc.prev_gain_q16[1]);
+        let gain_q16 = if gain_q16 >= 1 << 21 || cng.smth_gain_q16 > 1 << 23 {
+            let gain_q16 = smultt(gain_q16, gain_q16);
+            let gain_q16 = smultt(cng.smth_gain_q16, cng.smth_gain_q16).wrapping_sub(gain_q16 << 5);
+            sqrt_approx(gain_q16) << 16
+        } else {
+            let gain_q16 = smulww(gain_q16, gain_q16);
+            let gain_q16 = smulww(cng.smth_gain_q16, cng.smth_gain_q16).wrapping_sub(gain_q16 << 5);
+            sqrt_approx(gain_q16) << 8
+        };
+
+        let gain_q10 = gain_q16 >> 6;
+        let len = frame.len();
+        let mut sig_q14 = vec![0i32; len + MAX_LPC_ORDER];
+
+        let mut exc_mask = CNG_BUF_MASK_MAX;
+        while exc_mask > len {
+            exc_mask >>= 1;
+        }
+
+        let mut seed = cng.rand_seed;
+        for sig in &mut sig_q14[MAX_LPC_ORDER..] {
+            seed = rand(seed);
+            *sig = cng.exc_buf_q14[(seed >> 24) as usize & exc_mask];
+        }
+
+        cng.rand_seed = seed;
+
+        let mut a_q12 = [0i16; MAX_LPC_ORDER];
+        nlsf2a(&mut a_q12, &cng.smth_nlsf_q15[..order]);
+        sig_q14[..MAX_LPC_ORDER].copy_from_slice(&cng.synth_state);
+        lpc_synthesis(&mut sig_q14, &a_q12[..order], len, |i, s| {
+            frame[i] = sat16(frame[i] as i32 + sat16(rshift_round(smulww(s, gain_q10), 8)) as i32);
+        });
+
+        cng.synth_state
+            .copy_from_slice(&sig_q14[len..len + MAX_LPC_ORDER]);
+    }
+}
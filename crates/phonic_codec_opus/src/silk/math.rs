@@ -0,0 +1,170 @@
+/// `(a * (b as i16)) >> 16`.
+pub(crate) fn smulwb(a: i32, b: i32) -> i32 {
+    ((a as i64 * b as i16 as i64) >> 16) as i32
+}
+
+/// `a + (b * (c as i16)) >> 16`.
+pub(crate) fn smlawb(a: i32, b: i32, c: i32) -> i32 {
+    (a as i64 + ((b as i64 * c as i16 as i64) >> 16)) as i32
+}
+
+/// `(a * b) >> 16`.
+pub(crate) fn smulww(a: i32, b: i32) -> i32 {
+    ((a as i64 * b as i64) >> 16) as i32
+}
+
+/// `a + (b * c) >> 16`.
+pub(crate) fn smlaww(a: i32, b: i32, c: i32) -> i32 {
+    (a as i64 + ((b as i64 * c as i64) >> 16)) as i32
+}
+
+/// The product of the low 16 bits of `a` and `b`.
+pub(crate) fn smulbb(a: i32, b: i32) -> i32 {
+    (a as i16 as i32) * (b as i16 as i32)
+}
+
+/// `a` plus the product of the low 16 bits of `b` and `c`.
+pub(crate) fn smlabb(a: i32, b: i32, c: i32) -> i32 {
+    a.wrapping_add(smulbb(b, c))
+}
+
+/// The product of the high 16 bits of `a` and `b`.
+pub(crate) fn smultt(a: i32, b: i32) -> i32 {
+    (a >> 16) * (b >> 16)
+}
+
+/// The high 32 bits of the 64 bit product of `a` and `b`.
+pub(crate) fn smmul(a: i32, b: i32) -> i32 {
+    ((a as i64 * b as i64) >> 32) as i32
+}
+
+/// Shifts `a` right by `shift` bits, rounding to nearest.
+pub(crate) fn rshift_round(a: i32, shift: u32) -> i32 {
+    match shift {
+        1 => (a >> 1) + (a & 1),
+        _ => ((a >> (shift - 1)) + 1) >> 1,
+    }
+}
+
+/// Shifts `a` right by `shift` bits, rounding to nearest in 64 bit precision.
+pub(crate) fn rshift_round64(a: i64, shift: u32) -> i64 {
+    match shift {
+        1 => (a >> 1) + (a & 1),
+        _ => ((a >> (shift - 1)) + 1) >> 1,
+    }
+}
+
+/// Clamps `a` between `l1` and `l2`, in whichever order the limits are given.
+pub(crate) fn limit(a: i32, l1: i32, l2: i32) -> i32 {
+    match l1 > l2 {
+        true => a.clamp(l2, l1),
+        false => a.clamp(l1, l2),
+    }
+}
+
+pub(crate) fn sat16(a: i32) -> i16 {
+    a.clamp(i16::MIN as i32, i16::MAX as i32) as i16
+}
+
+pub(crate) fn add_sat32(a: i32, b: i32) -> i32 {
+    a.saturating_add(b)
+}
+
+pub(crate) fn sub_sat32(a: i32, b: i32) -> i32 {
+    a.saturating_sub(b)
+}
+
+/// Shifts `a` left by `shift` bits, saturating to the 32 bit range.
+pub(crate) fn lshift_sat32(a: i32, shift: u32) -> i32 {
+    limit(a, i32::MIN >> shift, i32::MAX >> shift) << shift
+}
+
+/// The number of leading zeros of `x`.
+pub(crate) fn clz32(x: i32) -> i32 {
+    x.leading_zeros() as i32
+}
+
+/// Splits `x` into its number of leading zeros and the 7 bits that follow the leading one.
+pub(crate) fn clz_frac(x: i32) -> (i32, i32) {
+    let lz = clz32(x);
+    (
+        lz,
+        (x as u32).rotate_right((24 - lz).rem_euclid(32) as u32) as i32 & 0x7f,
+    )
+}
+
+/// Approximates the square root of `x`, with an error of up to 1.3%.
+pub(crate) fn sqrt_approx(x: i32) -> i32 {
+    if x <= 0 {
+        return 0;
+    }
+
+    let (lz, frac) = clz_frac(x);
+    let mut y = match lz & 1 {
+        0 => 46214,
+        _ => 32768,
+    };
+
+    y >>= lz >> 1;
+    smlawb(y, y, smulbb(213, frac))
+}
+
+/// Divides `a` by `b`, returning the result in Q`q`.
+pub(crate) fn div32_varq(a: i32, b: i32, q: i32) -> i32 {
+    let a_headroom = clz32(a.wrapping_abs()) - 1;
+    let a_nrm = a.wrapping_shl(a_headroom as u32);
+    let b_headroom = clz32(b.wrapping_abs()) - 1;
+    let b_nrm = b.wrapping_shl(b_headroom as u32);
+
+    let b_inv = (i32::MAX >> 2) / (b_nrm >> 16);
+    let result = smulwb(a_nrm, b_inv);
+    let a_nrm = a_nrm.wrapping_sub(smmul(b_nrm, result).wrapping_shl(3));
+    let result = smlawb(result, a_nrm, b_inv);
+
+    let shift = 29 + a_headroom - b_headroom - q;
+    match shift {
+        ..=-1 => lshift_sat32(result, -shift as u32),
+        0..=31 => result >> shift,
+        _ => 0,
+    }
+}
+
+/// Inverts `b`, returning the result in Q`q`.
+pub(crate) fn inverse32_varq(b: i32, q: i32) -> i32 {
+    let b_headroom = clz32(b.wrapping_abs()) - 1;
+    let b_nrm = b.wrapping_shl(b_headroom as u32);
+
+    let b_inv = (i32::MAX >> 2) / (b_nrm >> 16);
+    let result = b_inv << 16;
+    let err = ((1 << 29) - smulwb(b_nrm, b_inv)).wrapping_shl(3);
+    let result = smlaww(result, err, b_inv);
+
+    let shift = 61 - b_headroom - q;
+    match shift {
+        ..=0 => lshift_sat32(result, -shift as u32),
+        1..=31 => result >> shift,
+        _ => 0,
+    }
+}
+
+/// The pseudo random number generator used for noise and comfort noise.
+pub(crate) fn rand(seed: i32) -> i32 {
+    907633515i32.wrapping_add(seed.wrapping_mul(196314165))
+}
+
+/// Approximates `2^(x / 128)`.
+pub(crate) fn log2lin(x: i32) -> i32 {
+    if x < 0 {
+        return 0;
+    } else if x >= 3967 {
+        return i32::MAX;
+    }
+
+    let out = 1 << (x >> 7);
+    let frac = x & 0x7f;
+    let poly = smlawb(frac, smulbb(frac, 128 - frac), -174);
+    match x < 2048 {
+        true => out + ((out * poly) >> 7),
+        false => out + (out >> 7) * poly,
+    }
+}
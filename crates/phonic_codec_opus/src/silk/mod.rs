@@ -0,0 +1,305 @@
+mod channel;
+mod cng;
+mod math;
+mod nlsf;
+mod plc;
+mod pulses;
+mod resampler;
+mod stereo;
+mod tables;
+
+use crate::{
+    range::RangeDecoder,
+    silk::{
+        channel::{ChannelState, CondCoding},
+        pulses::decode_pulses,
+        stereo::{decode_mid_only, decode_pred, StereoState},
+        tables::{LBRR_FLAGS_2_ICDF, LBRR_FLAGS_3_ICDF},
+    },
+};
+use phonic_core::PhonicError;
+
+/// The parameters the opus layer passes to the silk decoder for each frame.
+pub(crate) struct SilkControl {
+    pub channels_api: usize,
+    pub channels_internal: usize,
+    pub internal_sample_rate: u32,
+    pub payload_size_ms: usize,
+}
+
+impl SilkControl {
+    pub fn new(channels: usize) -> Self {
+        Self {
+            channels_api: channels,
+            channels_internal: channels,
+            internal_sample_rate: 16000,
+            payload_size_ms: 20,
+        }
+    }
+}
+
+/// Decodes the silk layer of a packet, one 10 or 20 ms frame at a time, and upsamples it to
+/// 48 kHz.
+pub(crate) struct SilkDecoder {
+    channels: [ChannelState; 2],
+    stereo: StereoState,
+    channels_api: usize,
+    channels_internal: usize,
+    prev_decode_only_middle: bool,
+}
+
+impl SilkDecoder {
+    pub fn new() -> Self {
+        Self {
+            channels: [ChannelState::new(), ChannelState::new()],
+            stereo: StereoState::default(),
+            channels_api: 0,
+            channels_internal: 0,
+            prev_decode_only_middle: false,
+        }
+    }
+
+    /// Resets the decoder state. The channel counts of the previous frame are kept, the same way
+    /// the reference decoder keeps them.
+    pub fn reset(&mut self) {
+        self.channels = [ChannelState::new(), ChannelState::new()];
+        self.stereo = StereoState::default();
+        self.prev_decode_only_middle = false;
+    }
+
+    /// Decodes the next frame of the current packet into interleaved samples at 48 kHz, returning
+    /// the number of samples per channel written. Without a range decoder the frame is concealed.
+    pub fn decode(
+        &mut self,
+        control: &SilkControl,
+        lost: bool,
+        new_packet: bool,
+        dec: Option<&mut RangeDecoder>,
+        out: &mut [i16],
+    ) -> Result<usize, PhonicError> {
+        let api = control.channels_api;
+        let internal = control.channels_internal;
+        let mut dec = dec.filter(|_| !lost);
+        if !(1..=2).contains(&api) || !(1..=2).contains(&internal) {
+            return Err(PhonicError::Unsupported);
+        }
+
+        if new_packet {
+            for channel in &mut self.channels[..internal] {
+                channel.frames_decoded = 0;
+            }
+        }
+
+        if internal > self.channels_internal {
+            self.channels[1] = ChannelState::new();
+        }
+
+        let stereo_to_mono = internal == 1
+            && self.channels_internal == 2
+            && control.internal_sample_rate == 1000 * self.channels[0].fs_khz as u32;
+
+        if self.channels[0].frames_decoded == 0 {
+            let (frames_per_packet, nb_subfr) = match control.payload_size_ms {
+                0 | 10 => (1, 2),
+                20 => (1, 4),
+                40 => (2, 4),
+                60 => (3, 4),
+                _ => return Err(PhonicError::InvalidData),
+            };
+
+            let fs_khz = match control.internal_sample_rate {
+                8000 | 12000 | 16000 => (control.internal_sample_rate as usize >> 10) + 1,
+                _ => return Err(PhonicError::Unsupported),
+            };
+
+            for channel in &mut self.channels[..internal] {
+                channel.frames_per_packet = frames_per_packet;
+                channel.nb_subfr = nb_subfr;
+                channel.set_fs(fs_khz);
+            }
+        }
+
+        if api == 2 && internal == 2 && (self.channels_api == 1 || self.channels_internal == 1) {
+            self.stereo.pred_prev_q13 = [0; 2];
+            self.stereo.s_side = [0; 2];
+            self.channels[1].resampler = self.channels[0].resampler.clone();
+        }
+
+        self.channels_api = api;
+        self.channels_internal = internal;
+
+        if let Some(dec) = dec
+            .as_deref_mut()
+            .filter(|_| self.channels[0].frames_decoded == 0)
+        {
+            self.decode_flags(dec, internal);
+        }
+
+        let mut ms_pred_q13 = [0; 2];
+        let mut decode_only_middle = false;
+        if internal == 2 {
+            match dec.as_deref_mut() {
+                Some(dec) => {
+                    ms_pred_q13 = decode_pred(dec);
+                    if !self.channels[1].vad_flags[self.channels[0].frames_decoded] {
+                        decode_only_middle = decode_mid_only(dec);
+                    }
+                }
+                None => ms_pred_q13 = self.stereo.pred_prev_q13.map(i32::from),
+            }
+        }
+
+        // the side channel restarts from silence when it comes back after mid only frames
+        if internal == 2 && !decode_only_middle && self.prev_decode_only_middle {
+            let side = &mut self.channels[1];
+            side.out_buf.fill(0);
+            side.s_lpc_q14_buf.fill(0);
+            side.lag_prev = 100;
+            side.last_gain_index = 10;
+            side.prev_signal_type = 0;
+            side.first_frame_after_reset = true;
+        }
+
+        let frame_length = self.channels[0].frame_length;
+        let mut tmp = [vec![0i16; frame_length + 2], vec![0i16; frame_length + 2]];
+        let has_side = match dec {
+            Some(_) => !decode_only_middle,
+            None => !self.prev_decode_only_middle,
+        };
+
+        let mut n_samples_out_dec = frame_length;
+        for (n, tmp) in tmp[..internal].iter_mut().enumerate() {
+            if n == 0 || has_side {
+                let frame_index = self.channels[0].frames_decoded as isize - n as isize;
+                let cond = if frame_index <= 0 {
+                    CondCoding::Independently
+                } else if n > 0 && self.prev_decode_only_middle {
+                    CondCoding::IndependentlyNoLtpScaling
+                } else {
+                    CondCoding::Conditionally
+                };
+
+                n_samples_out_dec =
+                    self.channels[n].decode_frame(dec.as_deref_mut(), &mut tmp[2..], cond);
+            } else {
+                tmp[2..2 + n_samples_out_dec].fill(0);
+            }
+
+            self.channels[n].frames_decoded += 1;
+        }
+
+        let fs_khz = self.channels[0].fs_khz;
+        if api == 2 && internal == 2 {
+            let [mid, side] = &mut tmp;
+            self.stereo
+                .ms_to_lr(mid, side, ms_pred_q13, fs_khz, n_samples_out_dec);
+        } else {
+            tmp[0][..2].copy_from_slice(&self.stereo.s_mid);
+            self.stereo
+                .s_mid
+                .copy_from_slice(&tmp[0][n_samples_out_dec..n_samples_out_dec + 2]);
+        }
+
+        let n_samples_out = n_samples_out_dec * 48 / fs_khz;
+        let mut resampled = vec![0i16; n_samples_out];
+        for n in 0..api.min(internal) {
+            self.channels[n]
+                .resampler
+                .resample(&mut resampled, &tmp[n][1..1 + n_samples_out_dec]);
+            for (out, &sample) in out[n..].iter_mut().step_by(api).zip(&resampled) {
+                *out = sample;
+            }
+        }
+
+        if api == 2 && internal == 1 {
+            if stereo_to_mono {
+                self.channels[1]
+                    .resampler
+                    .resample(&mut resampled, &tmp[0][1..1 + n_samples_out_dec]);
+                for (out, &sample) in out[1..].iter_mut().step_by(2).zip(&resampled) {
+                    *out = sample;
+                }
+            } else {
+                for frame in out[..2 * n_samples_out].chunks_exact_mut(2) {
+                    frame[1] = frame[0];
+                }
+            }
+        }
+
+        match dec {
+            Some(_) => self.prev_decode_only_middle = decode_only_middle,
+            None => {
+                // remove the gain clamping so the energy doesn't bounce back after a loss
+                for channel in &mut self.channels[..internal] {
+                    channel.last_gain_index = 10;
+                }
+            }
+        }
+
+        Ok(n_samples_out)
+    }
+
+    /// Decodes the voice activity and low bitrate redundancy flags at the start of a packet, and
+    /// skips over the redundant frames.
+    fn decode_flags(&mut self, dec: &mut RangeDecoder, internal: usize) {
+        for channel in &mut self.channels[..internal] {
+            for i in 0..channel.frames_per_packet {
+                channel.vad_flags[i] = dec.bit_logp(1);
+            }
+
+            channel.lbrr_flag = dec.bit_logp(1);
+        }
+
+        for channel in &mut self.channels[..internal] {
+            channel.lbrr_flags = [false; 3];
+            if channel.lbrr_flag {
+                if channel.frames_per_packet == 1 {
+                    channel.lbrr_flags[0] = true;
+                } else {
+                    let icdf: &[u8] = match channel.frames_per_packet {
+                        2 => &LBRR_FLAGS_2_ICDF,
+                        _ => &LBRR_FLAGS_3_ICDF,
+                    };
+
+                    let symbol = dec.icdf(icdf, 8) + 1;
+                    for (i, flag) in channel.lbrr_flags[..channel.frames_per_packet]
+                        .iter_mut()
+                        .enumerate()
+                    {
+                        *flag = (symbol >> i) & 1 != 0;
+                    }
+                }
+            }
+        }
+
+        for i in 0..self.channels[0].frames_per_packet {
+            for n in 0..internal {
+                if !self.channels[n].lbrr_flags[i] {
+                    continue;
+                }
+
+                if internal == 2 && n == 0 {
+                    decode_pred(dec);
+                    if !self.channels[1].lbrr_flags[i] {
+                        decode_mid_only(dec);
+                    }
+                }
+
+                let cond = match i > 0 && self.channels[n].lbrr_flags[i - 1] {
+                    true => CondCoding::Conditionally,
+                    false => CondCoding::Independently,
+                };
+
+                let channel = &mut self.channels[n];
+                channel.decode_indices(dec, i, true, cond);
+                let mut pulses = vec![0i16; channel.pulses_length()];
+                decode_pulses(
+                    dec,
+                    &mut pulses,
+                    channel.indices.signal_type,
+                    channel.indices.quant_offset_type,
+                );
+            }
+        }
+    }
+}
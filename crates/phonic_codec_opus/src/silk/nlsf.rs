@@ -0,0 +1,365 @@
+use crate::silk::{
+    math::{
+        inverse32_varq, limit, rshift_round, rshift_round64, sat16, smmul, smulbb, smlawb, smulww,
+        sub_sat32,
+    },
+    tables::*,
+};
+
+/// The largest magnitude of a second stage nlsf residual before the extension is coded.
+pub(crate) const NLSF_QUANT_MAX_AMPLITUDE: i32 = 4;
+
+const NLSF_QUANT_LEVEL_ADJ_Q10: i32 = 102;
+const MAX_STABILIZE_LOOPS: usize = 20;
+const MAX_LPC_STABILIZE_ITERATIONS: usize = 16;
+
+/// The Q of the intermediate polynomials when converting nlsfs to lpc coefficients.
+const NLSF2A_QA: u32 = 16;
+
+/// The Q of the coefficients when checking the stability of a filter.
+const INV_PRED_QA: u32 = 24;
+const INV_PRED_A_LIMIT: i32 = 16773022;
+const INV_PRED_MIN_GAIN_Q30: i32 = 107374;
+
+const ORDERING_16: [usize; 16] = [0, 15, 8, 7, 4, 11, 12, 3, 2, 13, 10, 5, 6, 9, 14, 1];
+const ORDERING_10: [usize; 10] = [0, 9, 6, 3, 4, 5, 8, 1, 2, 7];
+
+/// A two stage codebook of normalized line spectral frequencies.
+pub(crate) struct NlsfCodebook {
+    pub order: usize,
+    pub quant_step_size_q16: i32,
+    pub cb1_nlsf_q8: &'static [u8],
+    pub cb1_wght_q9: &'static [i16],
+    pub cb1_icdf: &'static [u8],
+    pub pred_q8: &'static [u8],
+    pub ec_sel: &'static [u8],
+    pub ec_icdf: &'static [u8],
+    pub delta_min_q15: &'static [i16],
+}
+
+/// The codebook used at 8 and 12 kHz.
+pub(crate) const NLSF_CB_NB_MB: NlsfCodebook = NlsfCodebook {
+    order: 10,
+    quant_step_size_q16: 11796,
+    cb1_nlsf_q8: &NLSF_CB1_NB_MB_Q8,
+    cb1_wght_q9: &NLSF_CB1_NB_MB_WGHT_Q9,
+    cb1_icdf: &NLSF_CB1_NB_MB_ICDF,
+    pred_q8: &NLSF_PRED_NB_MB_Q8,
+    ec_sel: &NLSF_CB2_NB_MB_SELECT,
+    ec_icdf: &NLSF_CB2_NB_MB_ICDF,
+    delta_min_q15: &NLSF_DELTA_MIN_NB_MB_Q15,
+};
+
+/// The codebook used at 16 kHz.
+pub(crate) const NLSF_CB_WB: NlsfCodebook = NlsfCodebook {
+    order: 16,
+    quant_step_size_q16: 9830,
+    cb1_nlsf_q8: &NLSF_CB1_WB_Q8,
+    cb1_wght_q9: &NLSF_CB1_WB_WGHT_Q9,
+    cb1_icdf: &NLSF_CB1_WB_ICDF,
+    pred_q8: &NLSF_PRED_WB_Q8,
+    ec_sel: &NLSF_CB2_WB_SELECT,
+    ec_icdf: &NLSF_CB2_WB_ICDF,
+    delta_min_q15: &NLSF_DELTA_MIN_WB_Q15,
+};
+
+impl NlsfCodebook {
+    /// Returns the offset of each coefficient's residual icdf and the predictor of each
+    /// coefficient for the given first stage vector.
+    pub fn unpack(&self, cb1_index: usize) -> ([usize; 16], [u8; 16]) {
+        let mut ec_ix = [0; 16];
+        let mut pred_q8 = [0; 16];
+        let ec_sel = &self.ec_sel[cb1_index * self.order / 2..];
+        for i in (0..self.order).step_by(2) {
+            let entry = ec_sel[i / 2] as usize;
+            ec_ix[i] = ((entry >> 1) & 7) * (2 * NLSF_QUANT_MAX_AMPLITUDE as usize + 1);
+            pred_q8[i] = self.pred_q8[i + (entry & 1) * (self.order - 1)];
+            ec_ix[i + 1] = ((entry >> 5) & 7) * (2 * NLSF_QUANT_MAX_AMPLITUDE as usize + 1);
+            pred_q8[i + 1] = self.pred_q8[i + ((entry >> 4) & 1) * (self.order - 1) + 1];
+        }
+
+        (ec_ix, pred_q8)
+    }
+
+    /// Reconstructs the stabilized nlsfs from the codebook indices.
+    pub fn decode(&self, indices: &[i8], nlsf_q15: &mut [i16]) {
+        let (_, pred_q8) = self.unpack(indices[0] as usize);
+
+        let mut res_q10 = [0i32; 16];
+        let mut out_q10 = 0;
+        for i in (0..self.order).rev() {
+            let pred_q10 = smulbb(out_q10, pred_q8[i] as i32) >> 8;
+            out_q10 = (indices[i + 1] as i32) << 10;
+            if out_q10 > 0 {
+                out_q10 -= NLSF_QUANT_LEVEL_ADJ_Q10;
+            } else if out_q10 < 0 {
+                out_q10 += NLSF_QUANT_LEVEL_ADJ_Q10;
+            }
+
+            out_q10 = smlawb(pred_q10, out_q10, self.quant_step_size_q16);
+            res_q10[i] = out_q10;
+        }
+
+        let base = indices[0] as usize * self.order;
+        for i in 0..self.order {
+            let weight = self.cb1_wght_q9[base + i] as i32;
+            let nlsf = ((res_q10[i] as i16 as i32) << 14) / weight
+                + ((self.cb1_nlsf_q8[base + i] as i32) << 7);
+            nlsf_q15[i] = nlsf.clamp(0, 32767) as i16;
+        }
+
+        stabilize(&mut nlsf_q15[..self.order], self.delta_min_q15);
+    }
+}
+
+/// Moves the nlsfs apart until each pair is at least the given minimum distance apart.
+pub(crate) fn stabilize(nlsf_q15: &mut [i16], delta_min_q15: &[i16]) {
+    let l = nlsf_q15.len();
+    let delta = |i: usize| delta_min_q15[i] as i32;
+
+    for _ in 0..MAX_STABILIZE_LOOPS {
+        let mut min_diff = nlsf_q15[0] as i32 - delta(0);
+        let mut min_i = 0;
+        for i in 1..l {
+            let diff = nlsf_q15[i] as i32 - (nlsf_q15[i - 1] as i32 + delta(i));
+            if diff < min_diff {
+                min_diff = diff;
+                min_i = i;
+            }
+        }
+
+        let diff = (1 << 15) - (nlsf_q15[l - 1] as i32 + delta(l));
+        if diff < min_diff {
+            min_diff = diff;
+            min_i = l;
+        }
+
+        if min_diff >= 0 {
+            return;
+        }
+
+        if min_i == 0 {
+            nlsf_q15[0] = delta(0) as i16;
+        } else if min_i == l {
+            nlsf_q15[l - 1] = ((1 << 15) - delta(l)) as i16;
+        } else {
+            let min_center = (0..min_i).map(delta).sum::<i32>() + (delta(min_i) >> 1);
+            let max_center =
+                (1 << 15) - (min_i + 1..=l).map(delta).sum::<i32>() - (delta(min_i) >> 1);
+            let center = limit(
+                rshift_round(nlsf_q15[min_i - 1] as i32 + nlsf_q15[min_i] as i32, 1),
+                min_center,
+                max_center,
+            );
+
+            nlsf_q15[min_i - 1] = (center - (delta(min_i) >> 1)) as i16;
+            nlsf_q15[min_i] = (nlsf_q15[min_i - 1] as i32 + delta(min_i)) as i16;
+        }
+    }
+
+    // fall back to sorting and clamping if the nlsfs failed to converge
+    nlsf_q15.sort_unstable();
+    nlsf_q15[0] = nlsf_q15[0].max(delta(0) as i16);
+    for i in 1..l {
+        nlsf_q15[i] = nlsf_q15[i].max(sat16(nlsf_q15[i - 1] as i32 + delta(i)));
+    }
+
+    nlsf_q15[l - 1] = nlsf_q15[l - 1].min(((1 << 15) - delta(l)) as i16);
+    for i in (0..l - 1).rev() {
+        nlsf_q15[i] = nlsf_q15[i].min((nlsf_q15[i + 1] as i32 - delta(i + 1)) as i16);
+    }
+}
+
+fn find_poly(out: &mut [i32], c_lsf: &[i32], dd: usize) {
+    out[0] = 1 << NLSF2A_QA;
+    out[1] = -c_lsf[0];
+    for k in 1..dd {
+        let ftmp = c_lsf[2 * k] as i64;
+        out[k + 1] = (out[k - 1] << 1) - rshift_round64(ftmp * out[k] as i64, NLSF2A_QA) as i32;
+        for n in (2..=k).rev() {
+            out[n] += out[n - 2] - rshift_round64(ftmp * out[n - 1] as i64, NLSF2A_QA) as i32;
+        }
+
+        out[1] -= ftmp as i32;
+    }
+}
+
+/// Converts nlsfs to stable lpc coefficients in Q12.
+pub(crate) fn nlsf2a(a_q12: &mut [i16], nlsf: &[i16]) {
+    let d = nlsf.len();
+    let ordering: &[usize] = match d {
+        16 => &ORDERING_16,
+        _ => &ORDERING_10,
+    };
+
+    let mut cos_lsf_qa = [0i32; 16];
+    for k in 0..d {
+        let f_int = (nlsf[k] >> 8) as usize;
+        let f_frac = nlsf[k] as i32 - ((f_int as i32) << 8);
+        let cos_val = LSF_COS_TAB_Q12[f_int] as i32;
+        let delta = LSF_COS_TAB_Q12[f_int + 1] as i32 - cos_val;
+        cos_lsf_qa[ordering[k]] = rshift_round((cos_val << 8) + delta * f_frac, 20 - NLSF2A_QA);
+    }
+
+    let dd = d / 2;
+    let mut p = [0i32; 9];
+    let mut q = [0i32; 9];
+    find_poly(&mut p, &cos_lsf_qa, dd);
+    find_poly(&mut q, &cos_lsf_qa[1..], dd);
+
+    let mut a32_qa1 = [0i32; 16];
+    for k in 0..dd {
+        let p_tmp = p[k + 1] + p[k];
+        let q_tmp = q[k + 1] - q[k];
+        a32_qa1[k] = -q_tmp - p_tmp;
+        a32_qa1[d - k - 1] = q_tmp - p_tmp;
+    }
+
+    let a32_qa1 = &mut a32_qa1[..d];
+    lpc_fit(a_q12, a32_qa1, 12, NLSF2A_QA + 1);
+
+    let mut i = 0;
+    while inverse_pred_gain(&a_q12[..d]) == 0 && i < MAX_LPC_STABILIZE_ITERATIONS {
+        bwexpander_32(a32_qa1, 65536 - (2 << i));
+        for k in 0..d {
+            a_q12[k] = rshift_round(a32_qa1[k], NLSF2A_QA + 1 - 12) as i16;
+        }
+
+        i += 1;
+    }
+}
+
+/// Converts lpc coefficients from Q`q_in` to Q`q_out`, applying bandwidth expansion until they
+/// fit in 16 bits.
+pub(crate) fn lpc_fit(a_out: &mut [i16], a_in: &mut [i32], q_out: u32, q_in: u32) {
+    let d = a_in.len();
+    let mut fitted = false;
+    for _ in 0..10 {
+        let (idx, maxabs) =
+            a_in.iter()
+                .map(|a| a.wrapping_abs())
+                .enumerate()
+                .fold(
+                    (0, 0),
+                    |(idx, max), (i, a)| if a > max { (i, a) } else { (idx, max) },
+                );
+
+        let maxabs = rshift_round(maxabs, q_in - q_out);
+        if maxabs > i16::MAX as i32 {
+            let maxabs = maxabs.min(163838);
+            let chirp_q16 =
+                65470 - ((maxabs - i16::MAX as i32) << 14) / ((maxabs * (idx as i32 + 1)) >> 2);
+            bwexpander_32(a_in, chirp_q16);
+        } else {
+            fitted = true;
+            break;
+        }
+    }
+
+    if fitted {
+        for k in 0..d {
+            a_out[k] = rshift_round(a_in[k], q_in - q_out) as i16;
+        }
+    } else {
+        for k in 0..d {
+            a_out[k] = sat16(rshift_round(a_in[k], q_in - q_out));
+            a_in[k] = (a_out[k] as i32) << (q_in - q_out);
+        }
+    }
+}
+
+/// Applies bandwidth expansion to 16 bit lpc coefficients.
+pub(crate) fn bwexpander(ar: &mut [i16], mut chirp_q16: i32) {
+    let chirp_minus_one_q16 = chirp_q16 - 65536;
+    let d = ar.len();
+    for a in &mut ar[..d - 1] {
+        *a = rshift_round(chirp_q16 * *a as i32, 16) as i16;
+        chirp_q16 += rshift_round(chirp_q16 * chirp_minus_one_q16, 16);
+    }
+
+    ar[d - 1] = rshift_round(chirp_q16 * ar[d - 1] as i32, 16) as i16;
+}
+
+/// Applies bandwidth expansion to 32 bit lpc coefficients.
+pub(crate) fn bwexpander_32(ar: &mut [i32], mut chirp_q16: i32) {
+    let chirp_minus_one_q16 = chirp_q16 - 65536;
+    let d = ar.len();
+    for a in &mut ar[..d - 1] {
+        *a = smulww(chirp_q16, *a);
+        chirp_q16 += rshift_round(chirp_q16 * chirp_minus_one_q16, 16);
+    }
+
+    ar[d - 1] = smulww(chirp_q16, ar[d - 1]);
+}
+
+fn mul32_frac_q31(a: i32, b: i32) -> i32 {
+    rshift_round64(a as i64 * b as i64, 31) as i32
+}
+
+/// Returns the inverse prediction gain of the filter in Q30, or 0 if the filter is unstable.
+pub(crate) fn inverse_pred_gain(a_q12: &[i16]) -> i32 {
+    let order = a_q12.len();
+    let mut a_qa = [0i32; 16];
+    let mut dc_resp = 0;
+    for k in 0..order {
+        dc_resp += a_q12[k] as i32;
+        a_qa[k] = (a_q12[k] as i32) << (INV_PRED_QA - 12);
+    }
+
+    if dc_resp >= 4096 {
+        return 0;
+    }
+
+    let mut inv_gain_q30 = 1 << 30;
+    for k in (1..order).rev() {
+        if a_qa[k] > INV_PRED_A_LIMIT || a_qa[k] < -INV_PRED_A_LIMIT {
+            return 0;
+        }
+
+        let rc_q31 = -(a_qa[k] << (31 - INV_PRED_QA));
+        let rc_mult1_q30 = (1 << 30) - smmul(rc_q31, rc_q31);
+        inv_gain_q30 = smmul(inv_gain_q30, rc_mult1_q30) << 2;
+        if inv_gain_q30 < INV_PRED_MIN_GAIN_Q30 {
+            return 0;
+        }
+
+        let mult2q = 32 - rc_mult1_q30.leading_zeros();
+        let rc_mult2 = inverse32_varq(rc_mult1_q30, mult2q as i32 + 30);
+        for n in 0..(k + 1) >> 1 {
+            let tmp1 = a_qa[n];
+            let tmp2 = a_qa[k - n - 1];
+
+            let tmp = rshift_round64(
+                sub_sat32(tmp1, mul32_frac_q31(tmp2, rc_q31)) as i64 * rc_mult2 as i64,
+                mult2q,
+            );
+            if tmp > i32::MAX as i64 || tmp < i32::MIN as i64 {
+                return 0;
+            }
+
+            a_qa[n] = tmp as i32;
+            let tmp = rshift_round64(
+                sub_sat32(tmp2, mul32_frac_q31(tmp1, rc_q31)) as i64 * rc_mult2 as i64,
+                mult2q,
+            );
+            if tmp > i32::MAX as i64 || tmp < i32::MIN as i64 {
+                return 0;
+            }
+
+            a_qa[k - n - 1] = tmp as i32;
+        }
+    }
+
+    if a_qa[0] > INV_PRED_A_LIMIT || a_qa[0] < -INV_PRED_A_LIMIT {
+        return 0;
+    }
+
+    let rc_q31 = -(a_qa[0] << (31 - INV_PRED_QA));
+    let rc_mult1_q30 = (1 << 30) - smmul(rc_q31, rc_q31);
+    inv_gain_q30 = smmul(inv_gain_q30, rc_mult1_q30) << 2;
+    if inv_gain_q30 < INV_PRED_MIN_GAIN_Q30 {
+        return 0;
+    }
+
+    inv_gain_q30
+}
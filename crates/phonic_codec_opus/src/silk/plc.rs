@@ -0,0 +1,305 @@
+use crate::silk::{
+    channel::{
+        lpc_analysis_filter, lpc_synthesis, ChannelState, DecoderControl, LTP_ORDER, MAX_LPC_ORDER,
+        MAX_NB_SUBFR, TYPE_VOICED,
+    },
+    math::{
+        inverse32_varq, rand, rshift_round, sat16, smlawb, smulbb, smulwb, smulww, sqrt_approx,
+    },
+    nlsf::{bwexpander, inverse_pred_gain},
+};
+
+const BWE_COEF_Q16: i32 = 64881;
+const V_PITCH_GAIN_START_MIN_Q14: i32 = 11469;
+const V_PITCH_GAIN_START_MAX_Q14: i32 = 15565;
+const MAX_PITCH_LAG_MS: i32 = 18;
+const RAND_BUF_SIZE: usize = 128;
+const RAND_BUF_MASK: i32 = RAND_BUF_SIZE as i32 - 1;
+const LOG2_INV_LPC_GAIN_HIGH_THRES: u32 = 3;
+const LOG2_INV_LPC_GAIN_LOW_THRES: u32 = 8;
+const PITCH_DRIFT_FAC_Q16: i32 = 655;
+
+const HARM_ATT_Q15: [i32; 2] = [32440, 31130];
+const PLC_RAND_ATTENUATE_V_Q15: [i32; 2] = [31130, 26214];
+const PLC_RAND_ATTENUATE_UV_Q15: [i32; 2] = [32440, 29491];
+
+/// The state used to conceal lost frames.
+#[derive(Default)]
+pub(crate) struct Plc {
+    pub pitch_l_q8: i32,
+    ltp_coef_q14: [i16; LTP_ORDER],
+    prev_lpc_q12: [i16; MAX_LPC_ORDER],
+    last_frame_lost: bool,
+    rand_seed: i32,
+    pub rand_scale_q14: i16,
+    conc_energy: i32,
+    conc_energy_shift: u32,
+    prev_ltp_scale_q14: i32,
+    pub prev_gain_q16: [i32; 2],
+    fs_khz: usize,
+    nb_subfr: usize,
+    subfr_length: usize,
+}
+
+/// Returns the energy of `x`, right shifted by the returned amount so it fits in 31 bits.
+fn sum_sqr_shift(x: &[i16]) -> (i32, u32) {
+    let energy = |shift: u32, init: u32| {
+        x.chunks(2).fold(init, |nrg, pair| {
+            let tmp = pair.iter().fold(0u32, |tmp, &x| {
+                tmp.wrapping_add(smulbb(x as i32, x as i32) as u32)
+            });
+            nrg.wrapping_add(tmp >> shift)
+        }) as i32
+    };
+
+    let shift = 31 - (x.len() as u32).leading_zeros();
+    let nrg = energy(shift, x.len() as u32);
+    let shift = (shift as i32 + 3 - nrg.leading_zeros() as i32).max(0) as u32;
+    (energy(shift, 0), shift)
+}
+
+impl ChannelState {
+    pub fn plc_reset(&mut self) {
+        self.plc.pitch_l_q8 = (self.frame_length as i32) << 7;
+        self.plc.prev_gain_q16 = [1 << 16; 2];
+        self.plc.subfr_length = 20;
+        self.plc.nb_subfr = 2;
+    }
+
+    /// Updates the concealment state from a decoded frame, or conceals a lost one.
+    pub fn plc(&mut self, ctrl: &mut DecoderControl, frame: &mut [i16], lost: bool) {
+        if self.fs_khz != self.plc.fs_khz {
+            self.plc_reset();
+            self.plc.fs_khz = self.fs_khz;
+        }
+
+        if lost {
+            self.plc_conceal(ctrl, frame);
+            self.loss_cnt += 1;
+        } else {
+            self.plc_update(ctrl);
+        }
+    }
+
+    fn plc_update(&mut self, ctrl: &DecoderControl) {
+        let plc = &mut self.plc;
+        self.prev_signal_type = self.indices.signal_type;
+
+        let mut ltp_gain_q14 = 0;
+        if self.indices.signal_type == TYPE_VOICED {
+            // find the subframe with the strongest ltp filter within one pitch period of the end
+            let mut j = 0;
+            while j * self.subfr_length < ctrl.pitch_l[self.nb_subfr - 1] as usize
+                && j < self.nb_subfr
+            {
+                let k = self.nb_subfr - 1 - j;
+                let taps = &ctrl.ltp_coef_q14[k * LTP_ORDER..(k + 1) * LTP_ORDER];
+                let tmp_ltp_gain_q14 = taps.iter().map(|&b| b as i32).sum::<i32>();
+                if tmp_ltp_gain_q14 > ltp_gain_q14 {
+                    ltp_gain_q14 = tmp_ltp_gain_q14;
+                    plc.ltp_coef_q14.copy_from_slice(taps);
+                    plc.pitch_l_q8 = ctrl.pitch_l[k] << 8;
+                }
+
+                j += 1;
+            }
+
+            plc.ltp_coef_q14 = [0; LTP_ORDER];
+            plc.ltp_coef_q14[LTP_ORDER / 2] = ltp_gain_q14 as i16;
+
+            if ltp_gain_q14 < V_PITCH_GAIN_START_MIN_Q14 {
+                let scale_q10 = (V_PITCH_GAIN_START_MIN_Q14 << 10) / ltp_gain_q14.max(1);
+                for b in &mut plc.ltp_coef_q14 {
+                    *b = (smulbb(*b as i32, scale_q10) >> 10) as i16;
+                }
+            } else if ltp_gain_q14 > V_PITCH_GAIN_START_MAX_Q14 {
+                let scale_q14 = (V_PITCH_GAIN_START_MAX_Q14 << 14) / ltp_gain_q14.max(1);
+                for b in &mut plc.ltp_coef_q14 {
+                    *b = (smulbb(*b as i32, scale_q14) >> 14) as i16;
+                }
+            }
+        } else {
+            plc.pitch_l_q8 = smulbb(self.fs_khz as i32, 18) << 8;
+            plc.ltp_coef_q14 = [0; LTP_ORDER];
+        }
+
+        plc.prev_lpc_q12[..self.lpc_order]
+            .copy_from_slice(&ctrl.pred_coef_q12[1][..self.lpc_order]);
+        plc.prev_ltp_scale_q14 = ctrl.ltp_scale_q14;
+        plc.prev_gain_q16
+            .copy_from_slice(&ctrl.gains_q16[self.nb_subfr - 2..self.nb_subfr]);
+        plc.subfr_length = self.subfr_length;
+        plc.nb_subfr = self.nb_subfr;
+    }
+
+    fn plc_energy(&self, prev_gain_q10: [i32; 2]) -> ((i32, u32), (i32, u32)) {
+        let subfr_length = self.subfr_length;
+        let mut exc_buf = vec![0i16; 2 * subfr_length];
+        for k in 0..2 {
+            let exc_q14 = &self.exc_q14[(k + self.nb_subfr - 2) * subfr_length..];
+            for i in 0..subfr_length {
+                exc_buf[k * subfr_length + i] = sat16(smulww(exc_q14[i], prev_gain_q10[k]) >> 8);
+            }
+        }
+
+        (
+            sum_sqr_shift(&exc_buf[..subfr_length]),
+            sum_sqr_shift(&exc_buf[subfr_length..]),
+        )
+    }
+
+    fn plc_conceal(&mut self, ctrl: &mut DecoderControl, frame: &mut [i16]) {
+        let ltp_mem_length = self.ltp_mem_length;
+        let order = self.lpc_order;
+        let mut s_ltp = vec![0i16; ltp_mem_length];
+        let mut s_ltp_q14 = vec![0i32; ltp_mem_length + self.frame_length];
+
+        let prev_gain_q10 = [
+            self.plc.prev_gain_q16[0] >> 6,
+            self.plc.prev_gain_q16[1] >> 6,
+        ];
+        if self.first_frame_after_reset {
+            self.plc.prev_lpc_q12 = [0; MAX_LPC_ORDER];
+        }
+
+        // take the noise from the subframe with the lower energy
+        let ((energy1, shift1), (energy2, shift2)) = self.plc_energy(prev_gain_q10);
+        let plc = &mut self.plc;
+        let rand_offset = match (energy1 >> shift2) < (energy2 >> shift1) {
+            true => ((plc.nb_subfr - 1) * plc.subfr_length).saturating_sub(RAND_BUF_SIZE),
+            false => (plc.nb_subfr * plc.subfr_length).saturating_sub(RAND_BUF_SIZE),
+        };
+
+        let rand_buf = &self.exc_q14[rand_offset..];
+        let mut b_q14 = plc.ltp_coef_q14;
+        let mut rand_scale_q14 = plc.rand_scale_q14;
+
+        let att_index = self.loss_cnt.min(1) as usize;
+        let harm_gain_q15 = HARM_ATT_Q15[att_index];
+        let mut rand_gain_q15 = match self.prev_signal_type {
+            TYPE_VOICED => PLC_RAND_ATTENUATE_V_Q15[att_index],
+            _ => PLC_RAND_ATTENUATE_UV_Q15[att_index],
+        };
+
+        bwexpander(&mut plc.prev_lpc_q12[..order], BWE_COEF_Q16);
+        let a_q12 = plc.prev_lpc_q12;
+
+        if self.loss_cnt == 0 {
+            rand_scale_q14 = 1 << 14;
+            if self.prev_signal_type == TYPE_VOICED {
+                for &b in &b_q14 {
+                    rand_scale_q14 = rand_scale_q14.wrapping_sub(b);
+                }
+
+                rand_scale_q14 = rand_scale_q14.max(3277);
+                rand_scale_q14 =
+                    (smulbb(rand_scale_q14 as i32, plc.prev_ltp_scale_q14) >> 14) as i16;
+            } else {
+                let inv_gain_q30 = inverse_pred_gain(&plc.prev_lpc_q12[..order]);
+                let down_scale_q30 = inv_gain_q30.clamp(
+                    (1 << 30) >> LOG2_INV_LPC_GAIN_LOW_THRES,
+                    (1 << 30) >> LOG2_INV_LPC_GAIN_HIGH_THRES,
+                );
+
+                let down_scale_q30 = down_scale_q30 << LOG2_INV_LPC_GAIN_HIGH_THRES;
+                rand_gain_q15 = smulwb(down_scale_q30, rand_gain_q15) >> 14;
+            }
+        }
+
+        let mut rand_seed = plc.rand_seed;
+        let mut lag = rshift_round(plc.pitch_l_q8, 8) as usize;
+        let mut s_ltp_buf_idx = ltp_mem_length;
+
+        // rewhiten the history and scale it to the excitation domain
+        let idx = ltp_mem_length - lag - order - LTP_ORDER / 2;
+        lpc_analysis_filter(
+            &mut s_ltp[idx..],
+            &self.out_buf[idx..],
+            &a_q12[..order],
+            ltp_mem_length - idx,
+        );
+
+        let inv_gain_q30 = inverse32_varq(plc.prev_gain_q16[1], 46).min(i32::MAX >> 1);
+        for i in idx + order..ltp_mem_length {
+            s_ltp_q14[i] = smulwb(inv_gain_q30, s_ltp[i] as i32);
+        }
+
+        for _ in 0..self.nb_subfr {
+            for _ in 0..self.subfr_length {
+                let pred_lag = s_ltp_buf_idx - lag + LTP_ORDER / 2;
+                let mut ltp_pred_q12 = 2;
+                for (j, &b) in b_q14.iter().enumerate() {
+                    ltp_pred_q12 = smlawb(ltp_pred_q12, s_ltp_q14[pred_lag - j], b as i32);
+                }
+
+                rand_seed = rand(rand_seed);
+                let idx = ((rand_seed >> 25) & RAND_BUF_MASK) as usize;
+                s_ltp_q14[s_ltp_buf_idx] =
+                    smlawb(ltp_pred_q12, rand_buf[idx], rand_scale_q14 as i32) << 2;
+                s_ltp_buf_idx += 1;
+            }
+
+            for b in &mut b_q14 {
+                *b = (smulbb(harm_gain_q15, *b as i32) >> 15) as i16;
+            }
+
+            rand_scale_q14 = (smulbb(rand_scale_q14 as i32, rand_gain_q15) >> 15) as i16;
+            plc.pitch_l_q8 = smlawb(plc.pitch_l_q8, plc.pitch_l_q8, PITCH_DRIFT_FAC_Q16);
+            plc.pitch_l_q8 = plc
+                .pitch_l_q8
+                .min(smulbb(MAX_PITCH_LAG_MS, self.fs_khz as i32) << 8);
+            lag = rshift_round(plc.pitch_l_q8, 8) as usize;
+        }
+
+        let s_lpc_q14 = &mut s_ltp_q14[ltp_mem_length - MAX_LPC_ORDER..];
+        s_lpc_q14[..MAX_LPC_ORDER].copy_from_slice(&self.s_lpc_q14_buf);
+        lpc_synthesis(s_lpc_q14, &a_q12[..order], self.frame_length, |i, s| {
+            frame[i] = sat16(rshift_round(smulww(s, prev_gain_q10[1]), 8));
+        });
+
+        self.s_lpc_q14_buf
+            .copy_from_slice(&s_lpc_q14[self.frame_length..self.frame_length + MAX_LPC_ORDER]);
+        plc.ltp_coef_q14 = b_q14;
+        plc.rand_seed = rand_seed;
+        plc.rand_scale_q14 = rand_scale_q14;
+        ctrl.pitch_l = [lag as i32; MAX_NB_SUBFR];
+    }
+
+    /// Smooths the energy transition from a concealed frame to the next decoded one.
+    pub fn plc_glue_frames(&mut self, frame: &mut [i16]) {
+        let plc = &mut self.plc;
+        if self.loss_cnt != 0 {
+            (plc.conc_energy, plc.conc_energy_shift) = sum_sqr_shift(frame);
+            plc.last_frame_lost = true;
+            return;
+        }
+
+        if plc.last_frame_lost {
+            let (mut energy, energy_shift) = sum_sqr_shift(frame);
+            if energy_shift > plc.conc_energy_shift {
+                plc.conc_energy >>= energy_shift - plc.conc_energy_shift;
+            } else if energy_shift < plc.conc_energy_shift {
+                energy >>= plc.conc_energy_shift - energy_shift;
+            }
+
+            if energy > plc.conc_energy {
+                let lz = plc.conc_energy.leading_zeros() as i32 - 1;
+                plc.conc_energy <<= lz;
+                energy >>= (24 - lz).max(0);
+
+                let frac_q24 = plc.conc_energy / energy.max(1);
+                let mut gain_q16 = sqrt_approx(frac_q24) << 4;
+                let slope_q16 = (((1 << 16) - gain_q16) / frame.len() as i32) << 2;
+                for x in frame.iter_mut() {
+                    *x = smulwb(gain_q16, *x as i32) as i16;
+                    gain_q16 += slope_q16;
+                    if gain_q16 > 1 << 16 {
+                        break;
+                    }
+                }
+            }
+        }
+
+        plc.last_frame_lost = false;
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{range::RangeDecoder, silk::tables::*};
+
+/// The number of samples in each shell coded block.
+pub(crate) const SHELL_CODEC_FRAME_LENGTH: usize = 16;
+
+const MAX_PULSES: usize = 16;
+const N_RATE_LEVELS: usize = 10;
+
+fn decode_split(dec: &mut RangeDecoder, p: i16, shell_table: &[u8]) -> (i16, i16) {
+    match p {
+        0 => (0, 0),
+        _ => {
+            let offset = SHELL_CODE_TABLE_OFFSETS[p as usize] as usize;
+            let child = dec.icdf(&shell_table[offset..], 8) as i16;
+            (child, p - child)
+        }
+    }
+}
+
+/// Recursively splits the pulses of a block in half until each sample has its pulse count.
+fn shell_decoder(pulses0: &mut [i16], dec: &mut RangeDecoder, pulses4: i16) {
+    let mut pulses3 = [0; 2];
+    let mut pulses2 = [0; 4];
+    let mut pulses1 = [0; 8];
+
+    (pulses3[0], pulses3[1]) = decode_split(dec, pulses4, &SHELL_CODE_TABLE3);
+    for i in 0..2 {
+        (pulses2[2 * i], pulses2[2 * i + 1]) = decode_split(dec, pulses3[i], &SHELL_CODE_TABLE2);
+        for j in 2 * i..2 * i + 2 {
+            (pulses1[2 * j], pulses1[2 * j + 1]) =
+                decode_split(dec, pulses2[j], &SHELL_CODE_TABLE1);
+            for k in 2 * j..2 * j + 2 {
+                (pulses0[2 * k], pulses0[2 * k + 1]) =
+                    decode_split(dec, pulses1[k], &SHELL_CODE_TABLE0);
+            }
+        }
+    }
+}
+
+/// Decodes the signs of the non-zero pulses.
+fn decode_signs(
+    dec: &mut RangeDecoder,
+    pulses: &mut [i16],
+    signal_type: usize,
+    quant_offset_type: usize,
+    sum_pulses: &[i32],
+) {
+    let sign_icdf = &SIGN_ICDF[7 * (quant_offset_type + (signal_type << 1))..];
+    for (block, &p) in pulses
+        .chunks_exact_mut(SHELL_CODEC_FRAME_LENGTH)
+        .zip(sum_pulses)
+    {
+        if p > 0 {
+            let icdf = [sign_icdf[(p & 0x1f).min(6) as usize], 0];
+            for q in block.iter_mut().filter(|q| **q > 0) {
+                *q *= ((dec.icdf(&icdf, 8) as i16) << 1) - 1;
+            }
+        }
+    }
+}
+
+/// Decodes the excitation pulses of a frame. `pulses` is rounded up to a whole number of shell
+/// blocks.
+pub(crate) fn decode_pulses(
+    dec: &mut RangeDecoder,
+    pulses: &mut [i16],
+    signal_type: usize,
+    quant_offset_type: usize,
+) {
+    let rate_level_index = dec.icdf(&RATE_LEVELS_ICDF[signal_type >> 1], 8);
+    let iter = pulses.len() / SHELL_CODEC_FRAME_LENGTH;
+
+    let mut sum_pulses = [0i32; 20];
+    let mut n_lshifts = [0u32; 20];
+    for i in 0..iter {
+        sum_pulses[i] = dec.icdf(&PULSES_PER_BLOCK_ICDF[rate_level_index], 8) as i32;
+        while sum_pulses[i] == MAX_PULSES as i32 + 1 {
+            n_lshifts[i] += 1;
+            let icdf = &PULSES_PER_BLOCK_ICDF[N_RATE_LEVELS - 1][(n_lshifts[i] == 10) as usize..];
+            sum_pulses[i] = dec.icdf(icdf, 8) as i32;
+        }
+    }
+
+    for (i, block) in pulses
+        .chunks_exact_mut(SHELL_CODEC_FRAME_LENGTH)
+        .enumerate()
+    {
+        match sum_pulses[i] {
+            0 => block.fill(0),
+            p => shell_decoder(block, dec, p as i16),
+        }
+    }
+
+    for (i, block) in pulses
+        .chunks_exact_mut(SHELL_CODEC_FRAME_LENGTH)
+        .enumerate()
+    {
+        let n_ls = n_lshifts[i];
+        if n_ls > 0 {
+            for q in block.iter_mut() {
+                let mut abs_q = *q as i32;
+                for _ in 0..n_ls {
+                    abs_q = (abs_q << 1) + dec.icdf(&LSB_ICDF, 8) as i32;
+                }
+
+                *q = abs_q as i16;
+            }
+
+            sum_pulses[i] |= (n_ls << 5) as i32;
+        }
+    }
+
+    decode_signs(
+        dec,
+        pulses,
+        signal_type,
+        quant_offset_type,
+        &sum_pulses[..iter],
+    );
+}
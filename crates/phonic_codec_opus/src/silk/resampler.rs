@@ -0,0 +1,132 @@
+use crate::silk::{
+    math::{rshift_round, sat16, smlawb, smulbb, smulwb, smulww},
+    tables::RESAMPLER_FRAC_FIR_12,
+};
+
+const ORDER_FIR_12: usize = 8;
+const MAX_BATCH_SIZE_MS: usize = 10;
+const OUTPUT_RATE: i32 = 48000;
+
+const UP2_HQ_0: [i32; 3] = [1746, 14986, 39083 - 65536];
+const UP2_HQ_1: [i32; 3] = [6854, 25769, 55542 - 65536];
+
+/// Upsamples the internal silk rate to 48 kHz, by doubling the rate with an allpass filter and
+/// interpolating the rest of the way with a fractional fir filter.
+#[derive(Clone)]
+pub(crate) struct Resampler {
+    s_iir: [i32; 6],
+    s_fir: [i16; ORDER_FIR_12],
+    delay_buf: [i16; 16],
+    input_delay: usize,
+    fs_in_khz: usize,
+    batch_size: usize,
+    inv_ratio_q16: i32,
+}
+
+impl Resampler {
+    pub fn new(fs_in_hz: i32) -> Self {
+        let input_delay = match fs_in_hz {
+            8000 => 0,
+            12000 => 4,
+            _ => 7,
+        };
+
+        let mut inv_ratio_q16 = ((fs_in_hz << 15) / OUTPUT_RATE) << 2;
+        while smulww(inv_ratio_q16, OUTPUT_RATE) < fs_in_hz << 1 {
+            inv_ratio_q16 += 1;
+        }
+
+        let fs_in_khz = fs_in_hz as usize / 1000;
+        Self {
+            s_iir: [0; 6],
+            s_fir: [0; ORDER_FIR_12],
+            delay_buf: [0; 16],
+            input_delay,
+            fs_in_khz,
+            batch_size: fs_in_khz * MAX_BATCH_SIZE_MS,
+            inv_ratio_q16,
+        }
+    }
+
+    /// Resamples `input`, writing six times as many samples to `out` for every 8 kHz of input.
+    pub fn resample(&mut self, out: &mut [i16], input: &[i16]) {
+        let n = self.fs_in_khz - self.input_delay;
+        self.delay_buf[self.input_delay..self.fs_in_khz].copy_from_slice(&input[..n]);
+
+        let delay_buf = self.delay_buf;
+        let out = self.iir_fir(out, &delay_buf[..self.fs_in_khz]);
+        self.iir_fir(out, &input[n..input.len() - self.input_delay]);
+
+        let len = input.len();
+        self.delay_buf[..self.input_delay].copy_from_slice(&input[len - self.input_delay..]);
+    }
+
+    fn iir_fir<'a>(&mut self, mut out: &'a mut [i16], mut input: &[i16]) -> &'a mut [i16] {
+        let mut buf = vec![0i16; 2 * self.batch_size + ORDER_FIR_12];
+        buf[..ORDER_FIR_12].copy_from_slice(&self.s_fir);
+
+        loop {
+            let n = input.len().min(self.batch_size);
+            up2_hq(&mut self.s_iir, &mut buf[ORDER_FIR_12..], &input[..n]);
+
+            let max_index_q16 = (n as i32) << 17;
+            let mut index_q16 = 0;
+            let mut written = 0;
+            while index_q16 < max_index_q16 {
+                let table_index = smulwb(index_q16 & 0xffff, 12) as usize;
+                let x = &buf[(index_q16 >> 16) as usize..];
+                let fir = &RESAMPLER_FRAC_FIR_12[table_index];
+                let fir_rev = &RESAMPLER_FRAC_FIR_12[11 - table_index];
+
+                let mut res_q15 = smulbb(x[0] as i32, fir[0] as i32);
+                for i in 1..4 {
+                    res_q15 += smulbb(x[i] as i32, fir[i] as i32);
+                }
+
+                for i in 0..4 {
+                    res_q15 += smulbb(x[4 + i] as i32, fir_rev[3 - i] as i32);
+                }
+
+                out[written] = sat16(rshift_round(res_q15, 15));
+                written += 1;
+                index_q16 += self.inv_ratio_q16;
+            }
+
+            out = &mut out[written..];
+            input = &input[n..];
+            buf.copy_within(n << 1..(n << 1) + ORDER_FIR_12, 0);
+            if input.is_empty() {
+                break;
+            }
+        }
+
+        self.s_fir.copy_from_slice(&buf[..ORDER_FIR_12]);
+        out
+    }
+}
+
+fn up2_hq(s: &mut [i32; 6], out: &mut [i16], input: &[i16]) {
+    for (k, &x) in input.iter().enumerate() {
+        let in32 = (x as i32) << 10;
+        for (phase, coefs) in [UP2_HQ_0, UP2_HQ_1].iter().enumerate() {
+            let s = &mut s[phase * 3..phase * 3 + 3];
+
+            let y = in32 - s[0];
+            let x = smulwb(y, coefs[0]);
+            let out1 = s[0] + x;
+            s[0] = in32 + x;
+
+            let y = out1 - s[1];
+            let x = smulwb(y, coefs[1]);
+            let out2 = s[1] + x;
+            s[1] = out1 + x;
+
+            let y = out2 - s[2];
+            let x = smlawb(y, y, coefs[2]);
+            let out1 = s[2] + x;
+            s[2] = out2 + x;
+
+            out[2 * k + phase] = sat16(rshift_round(out1, 10));
+        }
+    }
+}
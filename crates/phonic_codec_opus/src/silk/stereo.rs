@@ -0,0 +1,98 @@
+use crate::{
+    range::RangeDecoder,
+    silk::{
+        math::{rshift_round, sat16, smlabb, smlawb, smulbb, smulwb},
+        tables::{
+            STEREO_ONLY_CODE_MID_ICDF, STEREO_PRED_JOINT_ICDF, STEREO_PRED_QUANT_Q13,
+            UNIFORM3_ICDF, UNIFORM5_ICDF,
+        },
+    },
+};
+
+const STEREO_INTERP_LEN_MS: usize = 8;
+
+/// The state carried between frames when converting mid/side to left/right.
+#[derive(Default)]
+pub(crate) struct StereoState {
+    pub pred_prev_q13: [i16; 2],
+    pub s_mid: [i16; 2],
+    pub s_side: [i16; 2],
+}
+
+/// Decodes the two mid/side prediction weights, in Q13.
+pub(crate) fn decode_pred(dec: &mut RangeDecoder) -> [i32; 2] {
+    let n = dec.icdf(&STEREO_PRED_JOINT_ICDF, 8);
+    let mut ix = [[0usize; 3]; 2];
+    ix[0][2] = n / 5;
+    ix[1][2] = n - 5 * ix[0][2];
+    for ix in &mut ix {
+        ix[0] = dec.icdf(&UNIFORM3_ICDF, 8);
+        ix[1] = dec.icdf(&UNIFORM5_ICDF, 8);
+    }
+
+    let mut pred_q13 = [0; 2];
+    for (pred_q13, ix) in pred_q13.iter_mut().zip(&mut ix) {
+        ix[0] += 3 * ix[2];
+        let low_q13 = STEREO_PRED_QUANT_Q13[ix[0]] as i32;
+        let step_q13 = smulwb(STEREO_PRED_QUANT_Q13[ix[0] + 1] as i32 - low_q13, 6554);
+        *pred_q13 = smlabb(low_q13, step_q13, 2 * ix[1] as i32 + 1);
+    }
+
+    pred_q13[0] -= pred_q13[1];
+    pred_q13
+}
+
+/// Decodes whether the frame omits the side channel.
+pub(crate) fn decode_mid_only(dec: &mut RangeDecoder) -> bool {
+    dec.icdf(&STEREO_ONLY_CODE_MID_ICDF, 8) != 0
+}
+
+impl StereoState {
+    /// Converts the mid and side channels to left and right in place. Both buffers hold two
+    /// samples of history followed by the frame.
+    pub fn ms_to_lr(
+        &mut self,
+        x1: &mut [i16],
+        x2: &mut [i16],
+        pred_q13: [i32; 2],
+        fs_khz: usize,
+        frame_length: usize,
+    ) {
+        x1[..2].copy_from_slice(&self.s_mid);
+        x2[..2].copy_from_slice(&self.s_side);
+        self.s_mid
+            .copy_from_slice(&x1[frame_length..frame_length + 2]);
+        self.s_side
+            .copy_from_slice(&x2[frame_length..frame_length + 2]);
+
+        let interp_len = STEREO_INTERP_LEN_MS * fs_khz;
+        let mut pred0_q13 = self.pred_prev_q13[0] as i32;
+        let mut pred1_q13 = self.pred_prev_q13[1] as i32;
+        let denom_q16 = (1 << 16) / interp_len as i32;
+        let delta0_q13 = rshift_round(smulbb(pred_q13[0] - pred0_q13, denom_q16), 16);
+        let delta1_q13 = rshift_round(smulbb(pred_q13[1] - pred1_q13, denom_q16), 16);
+
+        for n in 0..frame_length {
+            if n < interp_len {
+                pred0_q13 += delta0_q13;
+                pred1_q13 += delta1_q13;
+            } else if n == interp_len {
+                pred0_q13 = pred_q13[0];
+                pred1_q13 = pred_q13[1];
+            }
+
+            let sum = ((x1[n] as i32 + x1[n + 2] as i32) + ((x1[n + 1] as i32) << 1)) << 9;
+            let sum = smlawb((x2[n + 1] as i32) << 8, sum, pred0_q13);
+            let sum = smlawb(sum, (x1[n + 1] as i32) << 11, pred1_q13);
+            x2[n + 1] = sat16(rshift_round(sum, 8));
+        }
+
+        self.pred_prev_q13 = [pred_q13[0] as i16, pred_q13[1] as i16];
+        for n in 0..frame_length {
+            let sum = x1[n + 1] as i32 + x2[n + 1] as i32;
+            let diff = x1[n + 1] as i32 - x2[n + 1] as i32;
+            x1[n + 1] = sat16(sum);
+            x2[n + 1] = sat16(diff);
+        }
+    }
+}
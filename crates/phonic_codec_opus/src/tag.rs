@@ -0,0 +1,13 @@
+/// Identifies an opus stream. Opus streams carry their own settings in the identification header,
+/// so there is nothing to choose between, and every stream decodes to `f32` samples at 48 kHz.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+pub struct OpusCodecTag;
+
+impl OpusCodecTag {
+    /// The rate every opus stream is decoded at, whatever the rate of the original input.
+    pub const FRAME_RATE: u32 = 48_000;
+
+    pub fn new() -> Self {
+        Self
+    }
+}
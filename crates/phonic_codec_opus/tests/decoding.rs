@@ -0,0 +1,485 @@
+use phonic_codec_opus::{
+    fill_opus_spec, parse_self_delimited, ChannelMapping, MultistreamDecoder, OpusCodec,
+    OpusCodecTag, OpusDecoder,
+};
+use phonic_core::PhonicError;
+use phonic_io_core::{Stream, StreamObserver, StreamReader, StreamSeeker, StreamSpec};
+use phonic_signal::{Signal, SignalObserver, SignalReader, SignalSeeker, SignalSpecBuilder};
+
+/// Packets from the reference encoder, along with the final state of its range coder.
+const SILK_PACKETS: [(&[u8], u32); 6] = [
+    (
+        &[
+            0x48, 0x84, 0x7b, 0x31, 0x29, 0x63, 0x59, 0x91, 0x77, 0x6f, 0xb9, 0x7c, 0xbf, 0x6d,
+            0xe3, 0x69, 0x0e, 0x44, 0x67, 0x45, 0x14, 0x63, 0xb0, 0x5a, 0xc0,
+        ],
+        0x61b50400,
+    ),
+    (
+        &[
+            0x48, 0xb9, 0xa5, 0xa0, 0x1e, 0x34, 0x84, 0xb5, 0xaa, 0x6c, 0xb7, 0xc8, 0xd6, 0x4f,
+            0x7b, 0x10, 0x90, 0xd0, 0x55, 0x57, 0x3b, 0x97, 0xa6, 0xc0,
+        ],
+        0x4defde00,
+    ),
+    (
+        &[
+            0x48, 0xb9, 0x99, 0x0a, 0xe0, 0x4c, 0xd4, 0xad, 0x1b, 0x61, 0xbc, 0xae, 0x45, 0x0a,
+            0xc0, 0xc1, 0x19, 0x63, 0x6f, 0xd7, 0xcc, 0xf3, 0xfe, 0xe1, 0x6a, 0x6a, 0x88, 0x74,
+            0x22, 0xaf, 0x14, 0xb9, 0x74, 0x4c, 0x82,
+        ],
+        0x00e9325e,
+    ),
+    (
+        &[
+            0x48, 0xb9, 0x77, 0x31, 0x5e, 0x28, 0x06, 0x90, 0x7f, 0xa2, 0x22, 0x7d, 0x53, 0x0d,
+            0x3a, 0x2f, 0x82, 0x7a, 0xcb, 0x10, 0x5d, 0xcc, 0x64, 0x49, 0xd9, 0xf3, 0x45, 0xf6,
+            0xc5, 0xd8,
+        ],
+        0x0acc2000,
+    ),
+    (
+        &[
+            0x48, 0xba, 0x86, 0xea, 0x40, 0x4b, 0x0b, 0x2d, 0x71, 0xe5, 0x0e, 0x31, 0x53, 0x1a,
+            0x67, 0xfe, 0x49, 0xd4, 0x36, 0xdb, 0x18, 0x4a, 0x01, 0x8d, 0x48, 0x83, 0xa8, 0x86,
+            0xd2, 0x78, 0x40,
+        ],
+        0x3e9db6d0,
+    ),
+    (
+        &[
+            0x48, 0xba, 0x8e, 0xfd, 0xce, 0xcc, 0xcb, 0xc8, 0xff, 0x92, 0xee, 0xf1, 0x1a, 0xaf,
+            0xd4, 0x94, 0x3e, 0xbe, 0x25, 0x84, 0xbb, 0x1c, 0x87, 0x84, 0xb7, 0x1c, 0xcc, 0x66,
+            0xed, 0x4a, 0xcb, 0x8e, 0x74, 0x0c,
+        ],
+        0x040b004c,
+    ),
+];
+
+const HYBRID_PACKETS: [(&[u8], u32); 6] = [
+    (
+        &[
+            0x78, 0x84, 0x26, 0xbe, 0x23, 0xf4, 0xdd, 0x6c, 0x3a, 0x14, 0xb2, 0xf6, 0x7a, 0x70,
+            0x7e, 0x4f, 0x6a, 0x12, 0x9e, 0x9c, 0x99, 0x2d, 0x8a, 0x7f, 0x50, 0xf7, 0xf4, 0x08,
+            0xdd, 0xdc, 0xe0, 0xf5, 0x32, 0xc8, 0xfe, 0xe8, 0x3d, 0xf9, 0x6f, 0xbe, 0x1c, 0x07,
+            0xe5, 0xdb, 0xad, 0x68, 0xe9,
+        ],
+        0x06405400,
+    ),
+    (
+        &[
+            0x78, 0xb2, 0x1d, 0xf7, 0xb9, 0xd0, 0xb1, 0xaf, 0xb7, 0xe6, 0xb2, 0x23, 0x5a, 0x43,
+            0x80, 0x7a, 0x4a, 0x08, 0xd4, 0x85, 0x80, 0x6a, 0x6b, 0x63, 0xd8, 0x84, 0xdd, 0x00,
+            0x54, 0x8a, 0xa6, 0xd9, 0xf3, 0x13, 0x54, 0xf1, 0x4d, 0x90, 0x21, 0xc6, 0xc2, 0xa0,
+            0xe1, 0x15, 0x96, 0xbb, 0xf5, 0xed, 0xda, 0xd2, 0x50, 0xe0, 0x2e,
+        ],
+        0x564d0400,
+    ),
+    (
+        &[
+            0x78, 0xb2, 0x08, 0xde, 0xcf, 0x22, 0xb1, 0xaf, 0x04, 0xae, 0x0e, 0x05, 0x0b, 0x1a,
+            0x22, 0x98, 0xd4, 0xa4, 0xaa, 0x46, 0x59, 0xe2, 0x60, 0xb0, 0x9b, 0x29, 0x2b, 0x60,
+            0xea, 0x20, 0xc8, 0x01, 0xe1, 0x6d, 0x04, 0x90, 0xc8, 0x1d, 0x15, 0xf9, 0xb0, 0x2a,
+            0xf8, 0x31, 0x27, 0xa2, 0xf6, 0x69,
+        ],
+        0x158c7c00,
+    ),
+    (
+        &[
+            0x78, 0xb1, 0xfb, 0x43, 0x22, 0xba, 0xf8, 0x25, 0x9c, 0x83, 0x90, 0x35, 0x34, 0xd5,
+            0x79, 0x5d, 0x67, 0x17, 0xea, 0x30, 0x14, 0xa7, 0xf6, 0xe0, 0x4d, 0x52, 0xe2, 0x44,
+            0x13, 0x10, 0x8f, 0xd0, 0x1f, 0x24, 0xf6, 0x51, 0xe5, 0x91, 0x70, 0x0e, 0x95, 0x87,
+            0x34, 0xb0, 0xd1, 0x13, 0xf3, 0x3a, 0xd3, 0xb0, 0x2f, 0x98, 0x85,
+        ],
+        0x0081ced0,
+    ),
+    (
+        &[
+            0x78, 0xb2, 0x50, 0x55, 0x08, 0x21, 0xf8, 0x09, 0x50, 0xcd, 0xe8, 0xbe, 0x9e, 0x01,
+            0x0f, 0x1b, 0xf8, 0x06, 0xe3, 0xc3, 0xba, 0x7b, 0xec, 0x61, 0x5d, 0xcc, 0xdf, 0x3c,
+            0x2a, 0xd0, 0xb4, 0xbf, 0x6f, 0x8d, 0x59, 0x5c, 0x56, 0x88, 0x16, 0xc5, 0xd4, 0x69,
+            0xf8, 0x96, 0x41, 0xe5, 0x7a, 0x08, 0x35, 0xe4, 0x43, 0xeb, 0x15,
+        ],
+        0x00958263,
+    ),
+    (
+        &[
+            0x78, 0xb3, 0xae, 0x03, 0x16, 0xbb, 0x30, 0x4b, 0xce, 0xde, 0x0a, 0xe1, 0x30, 0x0a,
+            0x60, 0xf0, 0x96, 0x59, 0xfb, 0x8a, 0x71, 0x8d, 0xfb, 0x2e, 0x7d, 0x72, 0x49, 0x5d,
+            0x15, 0x24, 0x0e, 0x9e, 0xf3, 0x13, 0x5d, 0x0c, 0x4e, 0x14, 0x52, 0xff, 0x03, 0x3c,
+            0x55, 0x94, 0x15,
+        ],
+        0x00e94300,
+    ),
+];
+
+const CELT_PACKETS: [(&[u8], u32); 6] = [
+    (
+        &[
+            0xf4, 0x7f, 0xac, 0x8a, 0x48, 0x59, 0xf4, 0x8e, 0xa7, 0x6d, 0xe8, 0xf6, 0x1c, 0xbd,
+            0x40, 0x94, 0x36, 0x10, 0xd6, 0x31, 0xf1, 0x85, 0x3d, 0x25, 0xb0, 0x7e, 0x56, 0xc9,
+            0xf3, 0x43, 0x41, 0xd2, 0x9d, 0x6d, 0x64, 0x00, 0xc4, 0xe4, 0xfd, 0xc4, 0x02, 0x54,
+            0x4e, 0xb7, 0xa2, 0x6d, 0x02, 0x77, 0xb2, 0x3f, 0x8c, 0x20, 0x60, 0x7c,
+        ],
+        0x01982555,
+    ),
+    (
+        &[
+            0xf4, 0xd6, 0x2e, 0x58, 0xe4, 0xd8, 0xfe, 0x26, 0x84, 0x4c, 0x7b, 0x8b, 0xb4, 0x97,
+            0x4a, 0x3e, 0x5b, 0x92, 0xe9, 0x46, 0x05, 0xe3, 0x7a, 0xea, 0x19, 0xc9, 0x2f, 0x9f,
+            0x38, 0xb2, 0x8a, 0x53, 0xb8, 0x09, 0x28, 0x86, 0x2c, 0xc0, 0x2d, 0xe3, 0xa9,
+        ],
+        0x010dd840,
+    ),
+    (
+        &[
+            0xf4, 0xd9, 0xaa, 0xf2, 0x26, 0x16, 0x91, 0x3a, 0xc0, 0x15, 0x82, 0x00, 0xb6, 0x30,
+            0x5a, 0x5b, 0xf3, 0x91, 0x15, 0xda, 0x9d, 0x82, 0xfe, 0x5c, 0x05, 0x01, 0x09, 0x4a,
+            0xc4, 0x82, 0x08, 0xb2, 0x51, 0xbf, 0x87, 0x79, 0x65, 0x43, 0xa9,
+        ],
+        0x01d13b40,
+    ),
+    (
+        &[
+            0xf4, 0xee, 0xbb, 0x31, 0x1c, 0x68, 0xf7, 0xe6, 0xd1, 0x02, 0x0c, 0x0f, 0x27, 0x28,
+            0x8c, 0xc8, 0x22, 0x22, 0xe7, 0xf4, 0x42, 0x01, 0xa3, 0x41, 0xd5, 0x7d, 0x20, 0x66,
+            0x04, 0xd0, 0x33, 0x2c, 0x9c, 0xdf, 0x62, 0x5e, 0xb6, 0xb0, 0xce, 0xe7, 0x73,
+        ],
+        0x008251d3,
+    ),
+    (
+        &[
+            0xf4, 0xeb, 0x07, 0x47, 0x1e, 0xfb, 0x23, 0x30, 0xc4, 0x6b, 0x98, 0xee, 0xb5, 0x67,
+            0xe4, 0xd3, 0x9f, 0x7d, 0x35, 0x05, 0xd9, 0x59, 0x22, 0xed, 0x79, 0xc3, 0x5c, 0x99,
+            0x9d, 0xd8, 0x38, 0x90, 0xf1, 0x3a, 0xd7, 0xab, 0xff, 0x40, 0x37, 0xc9, 0x74,
+        ],
+        0x0115eb80,
+    ),
+    (
+        &[
+            0xf4, 0xf1, 0x6e, 0xcf, 0x11, 0x4a, 0x3a, 0xda, 0xf2, 0x52, 0x9c, 0xc7, 0xc7, 0x3a,
+            0x0d, 0x92, 0xf0, 0xfe, 0x4b, 0x34, 0xf9, 0x5f, 0x88, 0x99, 0x9f, 0xe0, 0x9e, 0xb3,
+            0x2b, 0x51, 0x55, 0xa1, 0x7e, 0x4b, 0x35, 0xb2, 0xca, 0xb4, 0x71, 0x4c, 0x49, 0x73,
+        ],
+        0x04eb2380,
+    ),
+];
+
+const PRE_SKIP: u16 = 312;
+
+/// A stream that gives one packet per read and is positioned by granule, the way the ogg format
+/// passes opus packets on.
+struct PacketStream {
+    spec: StreamSpec<OpusCodecTag>,
+    packets: Vec<Vec<u8>>,
+    granules: Vec<u64>,
+    i: usize,
+}
+
+impl PacketStream {
+    fn new(n_channels: u16, n_frames: u64, head: Vec<u8>, packets: &[&[u8]]) -> Self {
+        let decoded_spec = SignalSpecBuilder::new()
+            .with_channels(n_channels)
+            .with_n_frames(n_frames);
+
+        let spec = StreamSpec::new()
+            .with_codec(OpusCodecTag::new())
+            .with_decoded_spec(decoded_spec);
+
+        let mut all = vec![
+            head,
+            b"OpusTags\x06\0\0\0phonic\x01\0\0\0\x0b\0\0\0TITLE=hello".to_vec(),
+        ];
+        let mut granules = vec![0, 0];
+        for packet in packets {
+            let previous = granules.last().copied().unwrap_or(0);
+            granules.push(previous + 960);
+            all.push(packet.to_vec());
+        }
+
+        Self {
+            spec,
+            packets: all,
+            granules,
+            i: 0,
+        }
+    }
+}
+
+impl Stream for PacketStream {
+    type Tag = OpusCodecTag;
+
+    fn spec(&self) -> &StreamSpec<Self::Tag> {
+        &self.spec
+    }
+}
+
+impl StreamObserver for PacketStream {
+    fn position(&self) -> Result<u64, PhonicError> {
+        Ok(self.i.checked_sub(1).map_or(0, |i| self.granules[i]))
+    }
+}
+
+impl StreamReader for PacketStream {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, PhonicError> {
+        let Some(packet) = self.packets.get(self.i) else {
+            return Ok(0);
+        };
+
+        buf[..packet.len()].copy_from_slice(packet);
+        self.i += 1;
+        Ok(packet.len())
+    }
+}
+
+impl StreamSeeker for PacketStream {
+    fn seek(&mut self, offset: i64) -> Result<(), PhonicError> {
+        let granule = self
+            .position()?
+            .checked_add_signed(offset)
+            .ok_or(PhonicError::InvalidData)?;
+
+        // resume after the last packet that ends at or before the granule
+        self.i = 2 + self.granules[2..].iter().filter(|&&g| g <= granule).count();
+        Ok(())
+    }
+}
+
+fn opus_head(n_channels: u8, output_gain: i16) -> Vec<u8> {
+    let mut packet = b"OpusHead".to_vec();
+    packet.extend_from_slice(&[1, n_channels]);
+    packet.extend_from_slice(&PRE_SKIP.to_le_bytes());
+    packet.extend_from_slice(&48_000u32.to_le_bytes());
+    packet.extend_from_slice(&output_gain.to_le_bytes());
+    packet.push(0);
+    packet
+}
+
+/// Frames a packet so that another can follow it, as every stream but the last of a
+/// multistream packet is.
+fn self_delimited(packet: &[u8]) -> Vec<u8> {
+    assert_eq!(packet[0] & 0x03, 0);
+    assert!(packet.len() - 1 < 252);
+
+    let mut framed = vec![packet[0], packet.len() as u8 - 1];
+    framed.extend_from_slice(&packet[1..]);
+    framed
+}
+
+fn decode_all(channels: usize, packets: &[(&[u8], u32)]) -> Vec<f32> {
+    let mut decoder = OpusDecoder::new(channels).unwrap();
+    let mut pcm = vec![0.0; 5760 * channels];
+    let mut all = Vec::new();
+    for (packet, _) in packets {
+        let n = decoder.decode(Some(packet), &mut pcm).unwrap();
+        all.extend_from_slice(&pcm[..n * channels]);
+    }
+
+    all
+}
+
+fn read_all(codec: &mut impl SignalReader<Sample = f32>) -> Vec<f32> {
+    let mut all = Vec::new();
+    let mut buf = [0.0; 700];
+    loop {
+        match codec.read(&mut buf).unwrap() {
+            0 => return all,
+            n => all.extend_from_slice(&buf[..n]),
+        }
+    }
+}
+
+#[test]
+fn decoder_matches_reference_range() {
+    for (channels, packets) in [(1, &SILK_PACKETS), (1, &HYBRID_PACKETS), (2, &CELT_PACKETS)] {
+        let mut decoder = OpusDecoder::new(channels).unwrap();
+        let mut pcm = vec![0.0; 5760 * channels];
+        for (packet, range) in packets {
+            let n = decoder.decode(Some(packet), &mut pcm).unwrap();
+            assert_eq!(decoder.final_range(), *range);
+            assert!(n == 480 || n == 960);
+        }
+    }
+}
+
+#[test]
+fn decoder_conceals_lost_packets() {
+    let mut decoder = OpusDecoder::new(1).unwrap();
+    let mut pcm = vec![0.0; 960];
+    decoder.decode(Some(SILK_PACKETS[0].0), &mut pcm).unwrap();
+
+    assert_eq!(decoder.decode(None, &mut pcm).unwrap(), 960);
+    assert_eq!(
+        decoder.decode(None, &mut pcm[..100]),
+        Err(PhonicError::InvalidData)
+    );
+    assert!(pcm.iter().any(|&sample| sample != 0.0));
+}
+
+#[test]
+fn decodes_self_delimited_packets() {
+    let (packet, _) = CELT_PACKETS[0];
+    let mut data = self_delimited(packet);
+    data.extend_from_slice(&[0xaa; 7]);
+
+    let (toc, frames, len) = parse_self_delimited(&data).unwrap();
+    assert_eq!(toc.frame_size, 480);
+    assert_eq!(frames, [&packet[1..]]);
+    assert_eq!(len, packet.len() + 1);
+    assert_eq!(
+        parse_self_delimited(&data[..packet.len()]),
+        Err(PhonicError::InvalidData)
+    );
+
+    let mut decoder = OpusDecoder::new(2).unwrap();
+    let mut pcm = vec![0.0; 5760 * 2];
+    assert_eq!(
+        decoder.decode_self_delimited(&data, &mut pcm).unwrap(),
+        (480, len)
+    );
+    assert_eq!(pcm[..960], decode_all(2, &CELT_PACKETS[..1]));
+}
+
+#[test]
+fn multistream_decoder_maps_channels() {
+    let mapping = ChannelMapping {
+        family: 1,
+        n_streams: 2,
+        n_coupled: 1,
+        table: vec![0, 2, 1, 255],
+    };
+
+    let mut decoder = MultistreamDecoder::new(mapping).unwrap();
+    let mut pcm = vec![0.0; 5760 * 4];
+    let mut decoded = Vec::new();
+    for ((coupled, _), (mono, _)) in HYBRID_PACKETS.iter().zip(&SILK_PACKETS) {
+        let mut packet = self_delimited(coupled);
+        packet.extend_from_slice(mono);
+
+        let n = decoder.decode(&packet, &mut pcm).unwrap();
+        decoded.extend_from_slice(&pcm[..n * 4]);
+    }
+
+    let coupled = decode_all(2, &HYBRID_PACKETS);
+    let mono = decode_all(1, &SILK_PACKETS);
+    assert_eq!(decoded.len(), mono.len() * 4);
+    for (i, frame) in decoded.chunks_exact(4).enumerate() {
+        assert_eq!(frame, [coupled[2 * i], mono[i], coupled[2 * i + 1], 0.0]);
+    }
+}
+
+#[test]
+fn multistream_decoder_rejects_mismatched_streams() {
+    let mut decoder = MultistreamDecoder::new(ChannelMapping {
+        family: 1,
+        n_streams: 2,
+        n_coupled: 1,
+        table: vec![0, 1, 2],
+    })
+    .unwrap();
+
+    // a 10 ms stream followed by a 20 ms one
+    let mut packet = self_delimited(CELT_PACKETS[0].0);
+    packet.extend_from_slice(SILK_PACKETS[0].0);
+
+    let mut pcm = vec![0.0; 5760 * 3];
+    assert_eq!(
+        decoder.decode(&packet, &mut pcm),
+        Err(PhonicError::InvalidData)
+    );
+}
+
+#[test]
+fn fills_spec() {
+    let mut spec = StreamSpec::new().with_codec(OpusCodecTag::new());
+    fill_opus_spec(&mut spec).unwrap();
+    assert_eq!(spec.sample_type, Some(std::any::TypeId::of::<f32>()));
+    assert_eq!(spec.decoded_spec.frame_rate, Some(48_000));
+
+    let mut spec = StreamSpec::new()
+        .with_codec(OpusCodecTag::new())
+        .with_sample_type::<i16>();
+
+    assert_eq!(fill_opus_spec(&mut spec), Err(PhonicError::Unsupported));
+
+    let mut spec = StreamSpec::new()
+        .with_codec(OpusCodecTag::new())
+        .with_decoded_spec(SignalSpecBuilder::new().with_frame_rate(44_100));
+
+    assert_eq!(fill_opus_spec(&mut spec), Err(PhonicError::Unsupported));
+}
+
+#[test]
+fn codec_drops_pre_skip_and_trims_end() {
+    let packets = SILK_PACKETS.map(|(packet, _)| packet);
+    let n_frames = 6 * 960 - PRE_SKIP as u64 - 100;
+    let stream = PacketStream::new(1, n_frames, opus_head(1, 0), &packets);
+    let mut codec = OpusCodec::from_stream(stream).unwrap();
+
+    let decoded = read_all(&mut codec);
+    let expected = decode_all(1, &SILK_PACKETS);
+    assert_eq!(decoded, expected[PRE_SKIP as usize..][..n_frames as usize]);
+    assert_eq!(codec.position().unwrap(), n_frames);
+
+    let head = codec.head().unwrap();
+    assert_eq!(head.pre_skip, PRE_SKIP);
+    assert_eq!(codec.tags().unwrap().get("title").next(), Some("hello"));
+}
+
+#[test]
+fn codec_applies_output_gain() {
+    let packets = HYBRID_PACKETS.map(|(packet, _)| packet);
+    let n_frames = 6 * 960 - PRE_SKIP as u64;
+    let stream = PacketStream::new(1, n_frames, opus_head(1, 6 * 256), &packets);
+    let mut codec = OpusCodec::from_stream(stream).unwrap();
+
+    let decoded = read_all(&mut codec);
+    let expected = decode_all(1, &HYBRID_PACKETS);
+    let gain = 10f32.powf(6.0 / 20.0);
+    assert_eq!(decoded.len(), n_frames as usize);
+    for (sample, expected) in decoded.iter().zip(&expected[PRE_SKIP as usize..]) {
+        assert!((sample - expected * gain).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn codec_seeks() {
+    let packets = SILK_PACKETS.map(|(packet, _)| packet);
+    let n_frames = 6 * 960 - PRE_SKIP as u64;
+    let stream = PacketStream::new(1, n_frames, opus_head(1, 0), &packets);
+    let mut codec = OpusCodec::from_stream(stream).unwrap();
+    let expected = read_all(&mut codec);
+
+    // targets within the preroll of the start decode from the start, so they match exactly
+    let mut buf = [0.0; 500];
+    for target in [0, 2000, 2500, 100] {
+        codec
+            .seek(target as i64 - codec.position().unwrap() as i64)
+            .unwrap();
+        codec.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, expected[target..target + 500]);
+        assert_eq!(codec.position().unwrap(), target as u64 + 500);
+    }
+
+    // further in, decoding resumes from the preroll ahead of the target
+    codec.seek(5000 - codec.position().unwrap() as i64).unwrap();
+    let rest = read_all(&mut codec);
+    assert_eq!(rest.len(), expected.len() - 5000);
+    assert_eq!(codec.position().unwrap(), n_frames);
+    assert_eq!(codec.as_inner().position().unwrap(), 6 * 960);
+
+    assert!(codec.seek(-(n_frames as i64) - 1).is_err());
+}
+
+#[test]
+fn codec_rejects_mismatched_channels() {
+    let packets = SILK_PACKETS.map(|(packet, _)| packet);
+    let stream = PacketStream::new(2, 1000, opus_head(1, 0), &packets);
+    let mut codec = OpusCodec::from_stream(stream).unwrap();
+
+    assert_eq!(
+        codec.read(&mut [0.0; 100]),
+        Err(PhonicError::SignalMismatch)
+    );
+    assert_eq!(codec.spec().frame_rate, 48_000);
+}
@@ -0,0 +1,119 @@
+use phonic_codec_opus::{ChannelMapping, OpusHead, OpusTags};
+use phonic_core::PhonicError;
+
+fn opus_head(n_channels: u8, family: u8, mapping: &[u8]) -> Vec<u8> {
+    let mut packet = b"OpusHead".to_vec();
+    packet.extend_from_slice(&[1, n_channels]);
+    packet.extend_from_slice(&312u16.to_le_bytes());
+    packet.extend_from_slice(&44_100u32.to_le_bytes());
+    packet.extend_from_slice(&(-512i16).to_le_bytes());
+    packet.push(family);
+    packet.extend_from_slice(mapping);
+    packet
+}
+
+fn opus_tags(vendor: &str, comments: &[&str]) -> Vec<u8> {
+    let mut packet = b"OpusTags".to_vec();
+    packet.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    packet.extend_from_slice(vendor.as_bytes());
+    packet.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+    for comment in comments {
+        packet.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+        packet.extend_from_slice(comment.as_bytes());
+    }
+
+    packet
+}
+
+#[test]
+fn reads_family_0_head() {
+    let head = OpusHead::read(&opus_head(2, 0, &[])).unwrap();
+
+    assert_eq!(head.version, 1);
+    assert_eq!(head.n_channels, 2);
+    assert_eq!(head.pre_skip, 312);
+    assert_eq!(head.input_sample_rate, 44_100);
+    assert_eq!(head.output_gain, -512);
+    assert_eq!(head.mapping, ChannelMapping::new(2));
+    assert_eq!(head.mapping.n_coupled, 1);
+    assert!((head.gain_factor() - 10f32.powf(-2.0 / 20.0)).abs() < 1e-6);
+}
+
+#[test]
+fn reads_family_1_head() {
+    let head = OpusHead::read(&opus_head(4, 1, &[2, 1, 0, 2, 1, 255])).unwrap();
+
+    assert_eq!(
+        head.mapping,
+        ChannelMapping {
+            family: 1,
+            n_streams: 2,
+            n_coupled: 1,
+            table: vec![0, 2, 1, 255],
+        }
+    );
+
+    assert_eq!(head.mapping.n_coded_channels(), 3);
+}
+
+#[test]
+fn rejects_invalid_heads() {
+    assert_eq!(OpusHead::read(b"OpusHead"), Err(PhonicError::InvalidData));
+    assert_eq!(
+        OpusHead::read(&opus_head(0, 0, &[])),
+        Err(PhonicError::InvalidData)
+    );
+
+    // family 0 only holds mono and stereo
+    assert_eq!(
+        OpusHead::read(&opus_head(3, 0, &[])),
+        Err(PhonicError::InvalidData)
+    );
+
+    // the table refers to a coded channel that doesn't exist
+    assert_eq!(
+        OpusHead::read(&opus_head(2, 1, &[1, 0, 0, 1])),
+        Err(PhonicError::InvalidData)
+    );
+
+    // the table is cut short
+    assert_eq!(
+        OpusHead::read(&opus_head(3, 1, &[2, 1, 0, 1])),
+        Err(PhonicError::InvalidData)
+    );
+
+    let mut packet = opus_head(1, 0, &[]);
+    packet[8] = 0x10;
+    assert_eq!(OpusHead::read(&packet), Err(PhonicError::Unsupported));
+}
+
+#[test]
+fn reads_tags() {
+    let packet = opus_tags(
+        "phonic",
+        &["TITLE=Episode 1", "artist=Someone", "Artist=Else", "EMPTY"],
+    );
+    let tags = OpusTags::read(&packet).unwrap();
+
+    assert_eq!(tags.vendor, "phonic");
+    assert_eq!(tags.comments.len(), 4);
+    assert_eq!(tags.get("title").collect::<Vec<_>>(), ["Episode 1"]);
+    assert_eq!(tags.get("ARTIST").collect::<Vec<_>>(), ["Someone", "Else"]);
+    assert_eq!(tags.get("empty").collect::<Vec<_>>(), [""]);
+    assert_eq!(tags.get("album").count(), 0);
+}
+
+#[test]
+fn rejects_truncated_tags() {
+    let packet = opus_tags("phonic", &["TITLE=Episode 1"]);
+
+    assert_eq!(
+        OpusTags::read(&packet[..packet.len() - 1]),
+        Err(PhonicError::InvalidData)
+    );
+
+    assert_eq!(
+        OpusTags::read(b"OpusHead\0\0\0\0\0\0\0\0"),
+        Err(PhonicError::InvalidData)
+    );
+}
@@ -1,51 +1,103 @@
-use byte_slice_cast::{
-    AsByteSlice, AsMutByteSlice, AsMutSliceOf, FromByteSlice, ToByteSlice, ToMutByteSlice,
-};
 use std::{
     marker::PhantomData,
     mem::{align_of, size_of},
 };
 use phonic_core::PhonicError;
 use phonic_io_core::{
-    match_tagged_signal, utils::TaggedSignal, CodecTag, DynCodecConstructor, DynStream, Stream,
-    StreamObserver, StreamReader, StreamSeeker, StreamSpec, StreamWriter,
+    match_tagged_signal, utils::TaggedSignal, CodecTag, DynStream, Stream, StreamObserver,
+    StreamReader, StreamSeeker, StreamSpec, StreamWriter,
 };
 use phonic_signal::{
     KnownSampleType, Sample, Signal, SignalObserver, SignalReader, SignalSeeker, SignalSpec,
     SignalWriter,
 };
 
-#[derive(PartialEq, Eq, Clone, Copy)]
-pub struct PcmCodecTag;
+mod sample;
+mod tag;
+
+pub use sample::*;
+pub use tag::*;
+
+const BUF_SAMPLES: usize = 1024;
 
 pub struct PcmCodec<T, S: Sample, C: CodecTag = PcmCodecTag> {
     inner: T,
     stream_spec: StreamSpec<C>,
     signal_spec: SignalSpec,
+    tag: PcmCodecTag,
+    read_partial: PartialSample,
+    write_partial: PartialSample,
     _sample: PhantomData<S>,
 }
 
+/// The bytes of a sample that has only been partly transferred between the signal and the byte
+/// stream. When reading, these have been taken from the inner signal or stream but not returned
+/// to the caller. When writing, they have been accepted from the caller but not passed on.
+#[derive(Default)]
+struct PartialSample {
+    bytes: [u8; 8],
+    range: (usize, usize),
+}
+
+impl PartialSample {
+    fn pending(&self) -> &[u8] {
+        &self.bytes[self.range.0..self.range.1]
+    }
+
+    fn len(&self) -> usize {
+        self.range.1 - self.range.0
+    }
+
+    fn is_empty(&self) -> bool {
+        self.range.0 == self.range.1
+    }
+
+    fn set(&mut self, bytes: &[u8]) {
+        self.bytes[..bytes.len()].copy_from_slice(bytes);
+        self.range = (0, bytes.len());
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        self.bytes[self.range.1..self.range.1 + bytes.len()].copy_from_slice(bytes);
+        self.range.1 += bytes.len();
+    }
+
+    fn consume(&mut self, n: usize) {
+        self.range.0 += n;
+    }
+
+    fn clear(&mut self) {
+        self.range = (0, 0);
+    }
+}
+
 pub fn fill_pcm_spec<C>(spec: &mut StreamSpec<C>) -> Result<(), PhonicError>
 where
-    C: CodecTag,
+    C: CodecTag + TryInto<PcmCodecTag>,
     PcmCodecTag: TryInto<C>,
 {
-    let expected_codec = PcmCodecTag.try_into().ok();
-    if spec.codec.is_some() && spec.codec != expected_codec {
-        return Err(PhonicError::InvalidData);
-    } else {
-        spec.codec = expected_codec;
-    }
+    let tag = match spec.codec {
+        Some(codec) => codec.try_into().map_err(|_| PhonicError::InvalidData)?,
+        None => {
+            let tag = PcmCodecTag::default();
+            spec.codec = tag.try_into().ok();
+            tag
+        }
+    };
 
     let sample_type = spec
         .sample_type
         .and_then(|s| KnownSampleType::try_from(s).ok());
 
-    if sample_type.is_none() {
+    let Some(sample_type) = sample_type else {
         return Ok(());
+    };
+
+    if !tag.supports_sample_type(sample_type) {
+        return Err(PhonicError::Unsupported);
     }
 
-    let sample_byte_size = sample_type.unwrap().byte_size();
+    let sample_byte_size = sample_type.byte_size();
     let calculated_bitrate = spec
         .decoded_spec
         .sample_rate()
@@ -72,6 +124,7 @@ where
 pub fn pcm_codec_from_stream<S>(stream: S) -> Result<TaggedSignal, PhonicError>
 where
     S: DynStream + 'static,
+    S::Tag: TryInto<PcmCodecTag>,
     PcmCodecTag: TryInto<S::Tag>,
 {
     let signal = match stream
@@ -82,11 +135,15 @@ where
     {
         KnownSampleType::I8 => TaggedSignal::I8(Box::new(PcmCodec::from_stream(stream)?)),
         KnownSampleType::I16 => TaggedSignal::I16(Box::new(PcmCodec::from_stream(stream)?)),
+        KnownSampleType::I24 => TaggedSignal::I24(Box::new(PcmCodec::from_stream(stream)?)),
         KnownSampleType::I32 => TaggedSignal::I32(Box::new(PcmCodec::from_stream(stream)?)),
+        KnownSampleType::I48 => TaggedSignal::I48(Box::new(PcmCodec::from_stream(stream)?)),
         KnownSampleType::I64 => TaggedSignal::I64(Box::new(PcmCodec::from_stream(stream)?)),
         KnownSampleType::U8 => TaggedSignal::U8(Box::new(PcmCodec::from_stream(stream)?)),
         KnownSampleType::U16 => TaggedSignal::U16(Box::new(PcmCodec::from_stream(stream)?)),
+        KnownSampleType::U24 => TaggedSignal::U24(Box::new(PcmCodec::from_stream(stream)?)),
         KnownSampleType::U32 => TaggedSignal::U32(Box::new(PcmCodec::from_stream(stream)?)),
+        KnownSampleType::U48 => TaggedSignal::U48(Box::new(PcmCodec::from_stream(stream)?)),
         KnownSampleType::U64 => TaggedSignal::U64(Box::new(PcmCodec::from_stream(stream)?)),
         KnownSampleType::F32 => TaggedSignal::F32(Box::new(PcmCodec::from_stream(stream)?)),
         KnownSampleType::F64 => TaggedSignal::F64(Box::new(PcmCodec::from_stream(stream)?)),
@@ -97,12 +154,16 @@ where
 
 pub fn pcm_codec_from_signal<C>(
     signal: TaggedSignal,
+    tag: PcmCodecTag,
 ) -> Result<Box<dyn DynStream<Tag = C>>, PhonicError>
 where
-    C: CodecTag + 'static,
+    C: CodecTag + TryInto<PcmCodecTag> + 'static,
     PcmCodecTag: TryInto<C>,
 {
-    match_tagged_signal!(signal, inner => Ok(Box::new(PcmCodec::from_signal(inner)?)))
+    match_tagged_signal!(
+        signal,
+        inner => Ok(Box::new(PcmCodec::from_signal_with_tag(inner, tag)?))
+    )
 }
 
 impl CodecTag for PcmCodecTag {
@@ -115,38 +176,78 @@ impl<T, S: Sample, C: CodecTag> PcmCodec<T, S, C> {
     pub fn from_stream(inner: T) -> Result<Self, PhonicError>
     where
         T: Stream<Tag = C>,
+        S: PcmSample,
+        C: TryInto<PcmCodecTag>,
         PcmCodecTag: TryInto<C>,
     {
         let mut stream_spec = *inner.spec();
         fill_pcm_spec(&mut stream_spec)?;
         let signal_spec = stream_spec.decoded_spec.build()?;
+        let tag = stream_spec
+            .codec
+            .and_then(|codec| codec.try_into().ok())
+            .unwrap_or_default();
 
-        Ok(Self {
-            inner,
-            stream_spec,
-            signal_spec,
-            _sample: PhantomData,
-        })
+        Self::new(inner, stream_spec, signal_spec, tag)
     }
 
     pub fn from_signal(inner: T) -> Result<Self, PhonicError>
     where
         T: Signal,
         T::Sample: 'static,
+        S: PcmSample,
+        C: TryInto<PcmCodecTag>,
+        PcmCodecTag: TryInto<C>,
+    {
+        Self::from_signal_with_tag(inner, PcmCodecTag::default())
+    }
+
+    /// Creates an encoder that stores the samples of `inner` as described by `tag`.
+    pub fn from_signal_with_tag(inner: T, tag: PcmCodecTag) -> Result<Self, PhonicError>
+    where
+        T: Signal,
+        T::Sample: 'static,
+        S: PcmSample,
+        C: TryInto<PcmCodecTag>,
         PcmCodecTag: TryInto<C>,
     {
         let signal_spec = *inner.spec();
         let mut stream_spec = StreamSpec::<C>::from(&inner);
+        stream_spec.codec = Some(tag.try_into().map_err(|_| PhonicError::Unsupported)?);
         fill_pcm_spec(&mut stream_spec)?;
 
+        Self::new(inner, stream_spec, signal_spec, tag)
+    }
+
+    fn new(
+        inner: T,
+        stream_spec: StreamSpec<C>,
+        signal_spec: SignalSpec,
+        tag: PcmCodecTag,
+    ) -> Result<Self, PhonicError>
+    where
+        S: PcmSample,
+    {
+        let is_float = S::ENCODING == PcmEncoding::Float;
+        if tag.encoding.is_some_and(|e| (e == PcmEncoding::Float) != is_float) {
+            return Err(PhonicError::Unsupported);
+        }
+
         Ok(Self {
             inner,
             stream_spec,
             signal_spec,
+            tag,
+            read_partial: PartialSample::default(),
+            write_partial: PartialSample::default(),
             _sample: PhantomData,
         })
     }
 
+    pub fn tag(&self) -> &PcmCodecTag {
+        &self.tag
+    }
+
     pub fn as_inner(&self) -> &T {
         &self.inner
     }
@@ -166,55 +267,113 @@ impl<T, S: Sample, C: CodecTag> Signal for PcmCodec<T, S, C> {
 
 impl<T: StreamObserver, S: Sample, C: CodecTag> SignalObserver for PcmCodec<T, S, C> {
     fn position(&self) -> Result<u64, PhonicError> {
-        Ok(self.inner.position()? / size_of::<S>() as u64)
+        let position = self.inner.position()? - self.read_partial.len() as u64
+            + self.write_partial.len() as u64;
+
+        Ok(position / size_of::<S>() as u64)
     }
 }
 
 impl<T, S, C> SignalReader for PcmCodec<T, S, C>
 where
     T: StreamReader,
-    S: Sample + ToMutByteSlice,
+    S: PcmSample,
     C: CodecTag,
 {
     fn read(&mut self, buf: &mut [Self::Sample]) -> Result<usize, PhonicError> {
-        let byte_buf = buf.as_mut_byte_slice();
-        let n = self.inner.read(byte_buf)?;
+        if buf.is_empty() {
+            return Ok(0);
+        }
 
-        let bytes_per_sample = byte_buf.len() / buf.len();
-        if n % bytes_per_sample != 0 {
-            todo!()
+        // bytes left over from the previous read make up the start of the first sample
+        let byte_buf = S::as_mut_bytes(buf);
+        let mut n_bytes = self.read_partial.len();
+        byte_buf[..n_bytes].copy_from_slice(self.read_partial.pending());
+        self.read_partial.clear();
+
+        while n_bytes < size_of::<S>() {
+            match self.inner.read(&mut byte_buf[n_bytes..]) {
+                Ok(0) => {
+                    self.read_partial.set(&byte_buf[..n_bytes]);
+                    return Ok(0);
+                }
+                Ok(n) => n_bytes += n,
+                Err(e) => {
+                    self.read_partial.set(&byte_buf[..n_bytes]);
+                    return Err(e);
+                }
+            }
         }
 
-        Ok(n / bytes_per_sample)
+        let n_samples = n_bytes / size_of::<S>();
+        self.read_partial
+            .set(&byte_buf[n_samples * size_of::<S>()..n_bytes]);
+
+        self.tag.decode(&mut buf[..n_samples]);
+        Ok(n_samples)
     }
 }
 
 impl<T, S, C> SignalWriter for PcmCodec<T, S, C>
 where
     T: StreamWriter,
-    S: Sample + ToByteSlice,
+    S: PcmSample,
     C: CodecTag,
 {
     fn write(&mut self, buf: &[Self::Sample]) -> Result<usize, PhonicError> {
-        let byte_buf = buf.as_byte_slice();
+        while !self.write_partial.is_empty() {
+            match self.inner.write(self.write_partial.pending())? {
+                0 => return Ok(0),
+                n => self.write_partial.consume(n),
+            }
+        }
+
+        let mut sample_buf = [S::ORIGIN; BUF_SAMPLES];
+        let buf = if self.tag.is_native::<S>() {
+            buf
+        } else {
+            let n = buf.len().min(BUF_SAMPLES);
+            sample_buf[..n].copy_from_slice(&buf[..n]);
+            self.tag.encode(&mut sample_buf[..n]);
+            &sample_buf[..n]
+        };
+
+        let byte_buf = S::as_bytes(buf);
         let n = self.inner.write(byte_buf)?;
 
-        let bytes_per_sample = byte_buf.len() / buf.len();
-        if n % bytes_per_sample != 0 {
-            todo!()
-        }
+        // a sample the inner stream only took part of counts as written, and the rest of its
+        // bytes are written before anything else
+        let n_samples = n.div_ceil(size_of::<S>());
+        self.write_partial
+            .set(&byte_buf[n..n_samples * size_of::<S>()]);
 
-        Ok(n / bytes_per_sample)
+        Ok(n_samples)
     }
 
     fn flush(&mut self) -> Result<(), PhonicError> {
-        self.inner.flush().map_err(Into::into)
+        while !self.write_partial.is_empty() {
+            match self.inner.write(self.write_partial.pending())? {
+                0 => return Err(PhonicError::EndOfStream),
+                n => self.write_partial.consume(n),
+            }
+        }
+
+        self.inner.flush()
     }
 }
 
 impl<T: StreamSeeker, S: Sample, C: CodecTag> SignalSeeker for PcmCodec<T, S, C> {
     fn seek(&mut self, offset: i64) -> Result<(), PhonicError> {
-        self.inner.seek(offset * size_of::<S>() as i64)
+        if !self.write_partial.is_empty() {
+            return Err(PhonicError::NotReady);
+        }
+
+        // the inner stream is ahead of the signal by any partially read sample
+        let n_partial = self.read_partial.len() as i64;
+        self.inner.seek(offset * size_of::<S>() as i64 - n_partial)?;
+        self.read_partial.clear();
+
+        Ok(())
     }
 }
 
@@ -228,27 +387,64 @@ impl<T, S: Sample, C: CodecTag> Stream for PcmCodec<T, S, C> {
 
 impl<T: SignalObserver, S: Sample, C: CodecTag> StreamObserver for PcmCodec<T, S, C> {
     fn position(&self) -> Result<u64, PhonicError> {
-        Ok(self.inner.position()? / size_of::<S>() as u64)
+        let position = self.inner.position()? * size_of::<S>() as u64;
+        Ok(position - self.read_partial.len() as u64 + self.write_partial.len() as u64)
+    }
+}
+
+impl<T, S, C> PcmCodec<T, S, C>
+where
+    T: SignalReader<Sample = S>,
+    S: PcmSample,
+    C: CodecTag,
+{
+    /// Reads a single sample from the inner signal into the partial buffer.
+    fn read_partial_sample(&mut self) -> Result<bool, PhonicError> {
+        let mut sample = [S::ORIGIN];
+        match self.inner.read(&mut sample)? {
+            0 => Ok(false),
+            _ => {
+                self.tag.encode(&mut sample);
+                self.read_partial.set(S::as_bytes(&sample));
+                Ok(true)
+            }
+        }
     }
 }
 
 impl<T, S, C> StreamReader for PcmCodec<T, S, C>
 where
     T: SignalReader<Sample = S>,
-    S: Sample + FromByteSlice,
+    S: PcmSample,
     C: CodecTag,
 {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize, PhonicError> {
-        let start_i = size_of::<S>() - (buf.as_ptr() as usize % align_of::<S>());
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let start_i = buf.as_ptr().align_offset(align_of::<S>()).min(buf.len());
         let aligned_len = buf.len() - start_i;
         let usable_len = aligned_len - (aligned_len % size_of::<S>());
 
-        let sample_buf = match buf[start_i..start_i + usable_len].as_mut_slice_of::<S>() {
-            Ok(buf) => buf,
-            _ => return Err(PhonicError::InvalidData),
-        };
+        // buffers too small to hold an aligned sample are filled a sample at a time
+        if self.read_partial.is_empty() && usable_len == 0 && !self.read_partial_sample()? {
+            return Ok(0);
+        }
+
+        if !self.read_partial.is_empty() {
+            let n = self.read_partial.len().min(buf.len());
+            buf[..n].copy_from_slice(&self.read_partial.pending()[..n]);
+            self.read_partial.consume(n);
+
+            return Ok(n);
+        }
+
+        let sample_buf = S::from_mut_bytes(&mut buf[start_i..start_i + usable_len])
+            .ok_or(PhonicError::InvalidData)?;
 
         let n = self.inner.read(sample_buf)?;
+        self.tag.encode(&mut sample_buf[..n]);
         if start_i > 0 {
             buf.rotate_left(start_i);
         }
@@ -260,31 +456,97 @@ where
 impl<T, S, C> StreamWriter for PcmCodec<T, S, C>
 where
     T: SignalWriter<Sample = S>,
-    S: Sample + FromByteSlice,
+    S: PcmSample,
     C: CodecTag,
 {
     fn write(&mut self, buf: &[u8]) -> Result<usize, PhonicError> {
-        //         let start_i = size_of::<S>() - (buf.as_ptr() as usize % align_of::<S>());
-        //         let aligned_len = buf.len() - start_i;
-        //         let usable_len = aligned_len - (aligned_len % size_of::<S>());
+        // a sample that has been received in full but not yet accepted by the inner signal
+        if self.write_partial.len() == size_of::<S>() {
+            self.write_partial_sample()?;
+            if !self.write_partial.is_empty() {
+                return Ok(0);
+            }
+        }
 
-        //         let sample_buf = match buf[start_i..start_i + usable_len].as_slice_of::<S>() {
-        //             Ok(buf) => buf,
-        //             _ => return Err(io::ErrorKind::InvalidData.into()),
-        //         };
+        if !self.write_partial.is_empty() || buf.len() < size_of::<S>() {
+            let n = (size_of::<S>() - self.write_partial.len()).min(buf.len());
+            self.write_partial.push(&buf[..n]);
+
+            if self.write_partial.len() == size_of::<S>() {
+                match self.write_partial_sample() {
+                    Ok(()) | Err(PhonicError::NotReady) | Err(PhonicError::Interrupted) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+
+            return Ok(n);
+        }
 
-        //         let n = self.inner.write(sample_buf)?;
-        //         Ok(n * size_of::<S>())
-        todo!()
+        // the buffer may not be aligned to the sample type, so samples are copied out of it
+        let mut sample_buf = [S::ORIGIN; BUF_SAMPLES];
+        let n_samples = (buf.len() / size_of::<S>()).min(BUF_SAMPLES);
+        let sample_buf = &mut sample_buf[..n_samples];
+        S::as_mut_bytes(sample_buf).copy_from_slice(&buf[..n_samples * size_of::<S>()]);
+        self.tag.decode(sample_buf);
+
+        let n = self.inner.write(sample_buf)?;
+        Ok(n * size_of::<S>())
     }
 
     fn flush(&mut self) -> Result<(), PhonicError> {
+        if !self.write_partial.is_empty() {
+            if self.write_partial.len() < size_of::<S>() {
+                return Err(PhonicError::NotReady);
+            }
+
+            self.write_partial_sample()?;
+            if !self.write_partial.is_empty() {
+                return Err(PhonicError::EndOfStream);
+            }
+        }
+
         self.inner.flush()
     }
 }
 
+impl<T, S, C> PcmCodec<T, S, C>
+where
+    T: SignalWriter<Sample = S>,
+    S: PcmSample,
+    C: CodecTag,
+{
+    /// Writes the sample in the partial buffer to the inner signal, leaving it in place if the
+    /// inner signal does not accept it.
+    fn write_partial_sample(&mut self) -> Result<(), PhonicError> {
+        let mut sample = [S::ORIGIN];
+        S::as_mut_bytes(&mut sample).copy_from_slice(self.write_partial.pending());
+        self.tag.decode(&mut sample);
+
+        if self.inner.write(&sample)? > 0 {
+            self.write_partial.clear();
+        }
+
+        Ok(())
+    }
+}
+
 impl<T: SignalSeeker, S: Sample, C: CodecTag> StreamSeeker for PcmCodec<T, S, C> {
     fn seek(&mut self, offset: i64) -> Result<(), PhonicError> {
-        self.inner.seek(offset / size_of::<S>() as i64)
+        if !self.write_partial.is_empty() {
+            return Err(PhonicError::NotReady);
+        }
+
+        // the inner signal can only be moved by whole samples and is ahead of the stream by any
+        // partially read sample
+        let sample_size = size_of::<S>() as i64;
+        let offset = offset - self.read_partial.len() as i64;
+        if offset % sample_size != 0 {
+            return Err(PhonicError::InvalidData);
+        }
+
+        self.inner.seek(offset / sample_size)?;
+        self.read_partial.clear();
+
+        Ok(())
     }
 }
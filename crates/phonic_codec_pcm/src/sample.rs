@@ -0,0 +1,118 @@
+use crate::{ByteOrder, PcmEncoding};
+use byte_slice_cast::{AsByteSlice, AsMutByteSlice, AsMutSliceOf};
+use phonic_signal::{Sample, I24, I48, U24, U48};
+use std::{mem::size_of, slice};
+
+/// A sample type that can be reinterpreted as raw pcm bytes and back without any conversion.
+pub trait PcmSample: Sample {
+    /// The order of the bytes of the sample in memory.
+    const BYTE_ORDER: ByteOrder;
+    const ENCODING: PcmEncoding;
+
+    fn as_bytes(buf: &[Self]) -> &[u8];
+    fn as_mut_bytes(buf: &mut [Self]) -> &mut [u8];
+
+    /// Reinterprets `buf` as a slice of samples, returning `None` if it is not aligned to or a
+    /// multiple of the sample size.
+    fn from_mut_bytes(buf: &mut [u8]) -> Option<&mut [Self]>;
+
+    fn swap_bytes(self) -> Self;
+
+    /// Toggles the most significant bit, which converts an integer sample between its signed
+    /// and offset binary encodings. Float samples are returned unchanged.
+    fn flip_sign(self) -> Self;
+}
+
+macro_rules! impl_pcm_sample {
+    ($($sample:ty: $encoding:ident),*) => {
+        $(
+            impl PcmSample for $sample {
+                const BYTE_ORDER: ByteOrder = ByteOrder::NATIVE;
+                const ENCODING: PcmEncoding = PcmEncoding::$encoding;
+
+                fn as_bytes(buf: &[Self]) -> &[u8] {
+                    buf.as_byte_slice()
+                }
+
+                fn as_mut_bytes(buf: &mut [Self]) -> &mut [u8] {
+                    buf.as_mut_byte_slice()
+                }
+
+                fn from_mut_bytes(buf: &mut [u8]) -> Option<&mut [Self]> {
+                    buf.as_mut_slice_of().ok()
+                }
+
+                fn swap_bytes(self) -> Self {
+                    Self::from_ne_bytes(self.to_be_bytes())
+                }
+
+                fn flip_sign(self) -> Self {
+                    impl_pcm_sample!(@flip_sign $encoding, self)
+                }
+            }
+        )*
+    };
+    (@flip_sign Float, $sample:expr) => {
+        $sample
+    };
+    (@flip_sign $encoding:ident, $sample:expr) => {
+        $sample ^ (1 << (Self::BITS - 1))
+    };
+}
+
+macro_rules! impl_packed_pcm_sample {
+    ($($sample:ty: $encoding:ident),*) => {
+        $(
+            // SAFETY: packed samples are transparent wrappers around their little endian bytes, so
+            // they have an alignment of 1 and every bit pattern is valid.
+            impl PcmSample for $sample {
+                const BYTE_ORDER: ByteOrder = ByteOrder::LittleEndian;
+                const ENCODING: PcmEncoding = PcmEncoding::$encoding;
+
+                fn as_bytes(buf: &[Self]) -> &[u8] {
+                    let len = buf.len() * size_of::<Self>();
+                    unsafe { slice::from_raw_parts(buf.as_ptr() as *const u8, len) }
+                }
+
+                fn as_mut_bytes(buf: &mut [Self]) -> &mut [u8] {
+                    let len = buf.len() * size_of::<Self>();
+                    unsafe { slice::from_raw_parts_mut(buf.as_mut_ptr() as *mut u8, len) }
+                }
+
+                fn from_mut_bytes(buf: &mut [u8]) -> Option<&mut [Self]> {
+                    if buf.len() % size_of::<Self>() != 0 {
+                        return None;
+                    }
+
+                    let len = buf.len() / size_of::<Self>();
+                    Some(unsafe { slice::from_raw_parts_mut(buf.as_mut_ptr() as *mut Self, len) })
+                }
+
+                fn swap_bytes(self) -> Self {
+                    Self::from_be_bytes(self.to_le_bytes())
+                }
+
+                fn flip_sign(self) -> Self {
+                    let mut bytes = self.to_le_bytes();
+                    bytes[size_of::<Self>() - 1] ^= 0x80;
+                    Self::from_le_bytes(bytes)
+                }
+            }
+        )*
+    };
+}
+
+impl_pcm_sample!(
+    i8: SignedInt,
+    i16: SignedInt,
+    i32: SignedInt,
+    i64: SignedInt,
+    u8: UnsignedInt,
+    u16: UnsignedInt,
+    u32: UnsignedInt,
+    u64: UnsignedInt,
+    f32: Float,
+    f64: Float
+);
+
+impl_packed_pcm_sample!(I24: SignedInt, I48: SignedInt, U24: UnsignedInt, U48: UnsignedInt);
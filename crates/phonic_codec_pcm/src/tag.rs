@@ -0,0 +1,146 @@
+use crate::PcmSample;
+use phonic_signal::KnownSampleType;
+
+/// The order of the bytes within each sample of a pcm stream.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum ByteOrder {
+    #[default]
+    LittleEndian,
+    BigEndian,
+}
+
+/// How the value of each sample in a pcm stream is encoded.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum PcmEncoding {
+    /// Two's complement integers.
+    SignedInt,
+
+    /// Offset binary integers, where the middle of the range is silence.
+    UnsignedInt,
+
+    /// IEEE 754 floats.
+    Float,
+}
+
+/// Describes how the samples of a pcm stream are stored. When no encoding is given, samples
+/// are stored with the encoding of the stream's sample type.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct PcmCodecTag {
+    pub byte_order: ByteOrder,
+    pub encoding: Option<PcmEncoding>,
+}
+
+impl ByteOrder {
+    #[cfg(target_endian = "little")]
+    pub const NATIVE: Self = Self::LittleEndian;
+
+    #[cfg(target_endian = "big")]
+    pub const NATIVE: Self = Self::BigEndian;
+}
+
+impl PcmEncoding {
+    /// Returns the sample type with this encoding that is `bits` wide, if there is one.
+    pub fn sample_type(self, bits: u32) -> Option<KnownSampleType> {
+        type T = KnownSampleType;
+
+        let sample_type = match (self, bits) {
+            (Self::SignedInt, 8) => T::I8,
+            (Self::SignedInt, 16) => T::I16,
+            (Self::SignedInt, 24) => T::I24,
+            (Self::SignedInt, 32) => T::I32,
+            (Self::SignedInt, 48) => T::I48,
+            (Self::SignedInt, 64) => T::I64,
+            (Self::UnsignedInt, 8) => T::U8,
+            (Self::UnsignedInt, 16) => T::U16,
+            (Self::UnsignedInt, 24) => T::U24,
+            (Self::UnsignedInt, 32) => T::U32,
+            (Self::UnsignedInt, 48) => T::U48,
+            (Self::UnsignedInt, 64) => T::U64,
+            (Self::Float, 32) => T::F32,
+            (Self::Float, 64) => T::F64,
+            _ => return None,
+        };
+
+        Some(sample_type)
+    }
+}
+
+impl From<KnownSampleType> for PcmEncoding {
+    fn from(sample_type: KnownSampleType) -> Self {
+        type T = KnownSampleType;
+
+        match sample_type {
+            T::I8 | T::I16 | T::I24 | T::I32 | T::I48 | T::I64 => Self::SignedInt,
+            T::U8 | T::U16 | T::U24 | T::U32 | T::U48 | T::U64 => Self::UnsignedInt,
+            T::F32 | T::F64 => Self::Float,
+        }
+    }
+}
+
+impl PcmCodecTag {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_byte_order(mut self, byte_order: ByteOrder) -> Self {
+        self.byte_order = byte_order;
+        self
+    }
+
+    pub fn with_encoding(mut self, encoding: PcmEncoding) -> Self {
+        self.encoding = Some(encoding);
+        self
+    }
+
+    /// Returns whether samples of type `sample_type` can be stored with this tag's encoding.
+    /// Integers can be stored as either signed or unsigned integers of the same width.
+    pub fn supports_sample_type(&self, sample_type: KnownSampleType) -> bool {
+        let is_float = self
+            .encoding
+            .map_or(sample_type.is_float(), |e| e == PcmEncoding::Float);
+        is_float == sample_type.is_float()
+    }
+
+    fn conversions<S: PcmSample>(&self) -> (bool, bool) {
+        let swap = self.byte_order != S::BYTE_ORDER;
+        let flip = self.encoding.is_some_and(|e| e != S::ENCODING);
+        (swap, flip)
+    }
+
+    /// Returns whether samples of type `S` are stored exactly as they are laid out in memory.
+    pub(crate) fn is_native<S: PcmSample>(&self) -> bool {
+        self.conversions::<S>() == (false, false)
+    }
+
+    /// Converts samples read from a stream with this tag into samples of type `S`.
+    pub(crate) fn decode<S: PcmSample>(&self, buf: &mut [S]) {
+        match self.conversions::<S>() {
+            (false, false) => {}
+            (swap, flip) => buf.iter_mut().for_each(|s| {
+                if swap {
+                    *s = s.swap_bytes();
+                }
+
+                if flip {
+                    *s = s.flip_sign();
+                }
+            }),
+        }
+    }
+
+    /// Converts samples of type `S` into samples to be written to a stream with this tag.
+    pub(crate) fn encode<S: PcmSample>(&self, buf: &mut [S]) {
+        match self.conversions::<S>() {
+            (false, false) => {}
+            (swap, flip) => buf.iter_mut().for_each(|s| {
+                if flip {
+                    *s = s.flip_sign();
+                }
+
+                if swap {
+                    *s = s.swap_bytes();
+                }
+            }),
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use phonic_codec_pcm::{fill_pcm_spec, ByteOrder, PcmCodec, PcmCodecTag, PcmEncoding, PcmSample};
+use phonic_io_core::{test_utils::BufferStream, StreamReader, StreamSpec, StreamWriter};
+use phonic_signal::{
+    test_utils::BufferSignal, Sample, SignalReader, SignalSpecBuilder, SignalWriter, I24,
+};
+use std::fmt::Debug;
+
+fn stream_spec<S: Sample + 'static>(tag: PcmCodecTag) -> StreamSpec<PcmCodecTag> {
+    let signal_spec = SignalSpecBuilder::new()
+        .with_frame_rate(8_000)
+        .with_channels(1);
+
+    StreamSpec::new()
+        .with_codec(tag)
+        .with_sample_type::<S>()
+        .with_decoded_spec(signal_spec)
+}
+
+/// Checks that `bytes` decode to `samples` and that `samples` encode to `bytes`, through both
+/// the decoding and the encoding side of the codec.
+fn check<S>(tag: PcmCodecTag, bytes: &[u8], samples: &[S])
+where
+    S: PcmSample + Debug + PartialEq + 'static,
+{
+    let stream = BufferStream::new(stream_spec::<S>(tag), bytes.to_vec());
+    let mut decoder = PcmCodec::<_, S>::from_stream(stream).unwrap();
+    let mut decoded = vec![S::ORIGIN; samples.len()];
+    decoder.read_exact(&mut decoded).unwrap();
+    assert_eq!(decoded, samples, "{tag:?} decode");
+
+    let stream = BufferStream::new(stream_spec::<S>(tag), Vec::new());
+    let mut decoder = PcmCodec::<_, S>::from_stream(stream).unwrap();
+    decoder.write_exact(samples).unwrap();
+    assert_eq!(
+        decoder.into_inner().into_bytes(),
+        bytes,
+        "{tag:?} decoder write"
+    );
+
+    let signal = BufferSignal::new(8_000, 1, samples.to_vec());
+    let mut encoder = PcmCodec::<_, S>::from_signal_with_tag(signal, tag).unwrap();
+    let mut encoded = vec![0; bytes.len()];
+    encoder.read_exact(&mut encoded).unwrap();
+    assert_eq!(encoded, bytes, "{tag:?} encode");
+
+    let signal = BufferSignal::new(8_000, 1, Vec::new());
+    let mut encoder = PcmCodec::<_, S>::from_signal_with_tag(signal, tag).unwrap();
+    encoder.write_exact(bytes).unwrap();
+    assert_eq!(
+        encoder.into_inner().into_samples(),
+        samples,
+        "{tag:?} encoder write"
+    );
+}
+
+const BE: PcmCodecTag = PcmCodecTag {
+    byte_order: ByteOrder::BigEndian,
+    encoding: None,
+};
+
+#[test]
+fn big_endian_samples() {
+    check(BE, &[0x12, 0x34, 0xff, 0xfe], &[0x1234i16, -2]);
+    check(BE, &[0x80, 0x00, 0x01], &[I24::new(-0x7f_ffff)]);
+    check(BE, &[0x3f, 0x80, 0x00, 0x00], &[1.0f32]);
+    check(BE, &[0xbf, 0xf0, 0, 0, 0, 0, 0, 0], &[-1.0f64]);
+    check(BE, &[0x7f, 0x80], &[0x7fu8, 0x80]);
+}
+
+#[test]
+fn little_endian_samples() {
+    let le = PcmCodecTag::default();
+    check(le, &[0x34, 0x12, 0xfe, 0xff], &[0x1234i16, -2]);
+    check(le, &[0x01, 0x00, 0x80], &[I24::new(-0x7f_ffff)]);
+    check(le, &[0, 0, 0x80, 0x3f], &[1.0f32]);
+}
+
+#[test]
+fn signedness_is_converted() {
+    // offset binary silence is the middle of the range
+    let unsigned = PcmCodecTag::new().with_encoding(PcmEncoding::UnsignedInt);
+    check(unsigned, &[0x00, 0x80, 0xff, 0xff], &[0i16, 0x7fff]);
+    check(unsigned, &[0x80, 0x00], &[0i8, -128]);
+
+    let signed = PcmCodecTag::new().with_encoding(PcmEncoding::SignedInt);
+    check(signed, &[0x00, 0x80, 0x7f], &[0x80u8, 0x00, 0xff]);
+
+    let unsigned_be = unsigned.with_byte_order(ByteOrder::BigEndian);
+    check(unsigned_be, &[0x80, 0x00, 0x01], &[I24::new(1)]);
+}
+
+#[test]
+fn float_and_integer_encodings_do_not_mix() {
+    let float = PcmCodecTag::new().with_encoding(PcmEncoding::Float);
+    let stream = BufferStream::new(stream_spec::<i32>(float), Vec::new());
+    assert!(PcmCodec::<_, i32>::from_stream(stream).is_err());
+
+    let mut spec = StreamSpec::new()
+        .with_codec(float)
+        .with_sample_type::<i16>();
+
+    assert!(fill_pcm_spec(&mut spec).is_err());
+
+    let signal = BufferSignal::new(8_000, 1, vec![0.0f32]);
+    let signed = PcmCodecTag::new().with_encoding(PcmEncoding::SignedInt);
+    assert!(PcmCodec::<_, f32>::from_signal_with_tag(signal, signed).is_err());
+}
@@ -0,0 +1,363 @@
+use phonic_codec_pcm::{PcmCodec, PcmCodecTag, PcmSample};
+use phonic_core::PhonicError;
+use phonic_io_core::{
+    Stream, StreamObserver, StreamReader, StreamSeeker, StreamSpec, StreamWriter,
+};
+use phonic_signal::{
+    Sample, Signal, SignalObserver, SignalReader, SignalSeeker, SignalSpec, SignalWriter, I24,
+};
+use std::{fmt::Debug, mem::size_of};
+
+/// A xorshift generator, so that failures can be reproduced.
+struct Rng(u64);
+
+impl Rng {
+    fn range(&mut self, min: usize, max: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        min + (self.0 % (max - min + 1) as u64) as usize
+    }
+}
+
+/// A byte stream that transfers a random number of bytes per call and is sometimes interrupted.
+struct FragmentedStream {
+    spec: StreamSpec<PcmCodecTag>,
+    bytes: Vec<u8>,
+    i: usize,
+    rng: Rng,
+}
+
+impl FragmentedStream {
+    fn new<S: Sample + 'static>(bytes: Vec<u8>, seed: u64) -> Self {
+        let signal_spec = SignalSpec::builder()
+            .with_frame_rate(48_000)
+            .with_channels(1);
+
+        Self {
+            spec: StreamSpec::new()
+                .with_sample_type::<S>()
+                .with_decoded_spec(signal_spec),
+            bytes,
+            i: 0,
+            rng: Rng(seed),
+        }
+    }
+
+    fn fragment_len(&mut self, len: usize) -> Result<usize, PhonicError> {
+        match self.rng.range(0, 11) {
+            0 => Err(PhonicError::Interrupted),
+            n => Ok(n.min(len)),
+        }
+    }
+}
+
+impl Stream for FragmentedStream {
+    type Tag = PcmCodecTag;
+
+    fn spec(&self) -> &StreamSpec<Self::Tag> {
+        &self.spec
+    }
+}
+
+impl StreamObserver for FragmentedStream {
+    fn position(&self) -> Result<u64, PhonicError> {
+        Ok(self.i as u64)
+    }
+}
+
+impl StreamReader for FragmentedStream {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, PhonicError> {
+        let n = self.fragment_len(buf.len().min(self.bytes.len() - self.i))?;
+        buf[..n].copy_from_slice(&self.bytes[self.i..self.i + n]);
+        self.i += n;
+        Ok(n)
+    }
+}
+
+impl StreamWriter for FragmentedStream {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, PhonicError> {
+        let n = self.fragment_len(buf.len())?;
+        self.bytes.extend_from_slice(&buf[..n]);
+        self.i += n;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<(), PhonicError> {
+        Ok(())
+    }
+}
+
+impl StreamSeeker for FragmentedStream {
+    fn seek(&mut self, offset: i64) -> Result<(), PhonicError> {
+        self.i = self
+            .i
+            .checked_add_signed(offset as isize)
+            .filter(|i| *i <= self.bytes.len())
+            .ok_or(PhonicError::InvalidData)?;
+
+        Ok(())
+    }
+}
+
+/// A signal that transfers a random number of samples per call.
+struct FragmentedSignal<S> {
+    spec: SignalSpec,
+    samples: Vec<S>,
+    i: usize,
+    rng: Rng,
+}
+
+impl<S: Sample> FragmentedSignal<S> {
+    fn new(samples: Vec<S>, seed: u64) -> Self {
+        Self {
+            spec: SignalSpec {
+                frame_rate: 48_000,
+                channels: 1.into(),
+                n_frames: None,
+            },
+            samples,
+            i: 0,
+            rng: Rng(seed),
+        }
+    }
+}
+
+impl<S: Sample> Signal for FragmentedSignal<S> {
+    type Sample = S;
+
+    fn spec(&self) -> &SignalSpec {
+        &self.spec
+    }
+}
+
+impl<S: Sample> SignalObserver for FragmentedSignal<S> {
+    fn position(&self) -> Result<u64, PhonicError> {
+        Ok(self.i as u64)
+    }
+}
+
+impl<S: Sample> SignalReader for FragmentedSignal<S> {
+    fn read(&mut self, buf: &mut [S]) -> Result<usize, PhonicError> {
+        let n = self
+            .rng
+            .range(1, 5)
+            .min(buf.len())
+            .min(self.samples.len() - self.i);
+
+        buf[..n].copy_from_slice(&self.samples[self.i..self.i + n]);
+        self.i += n;
+        Ok(n)
+    }
+}
+
+impl<S: Sample> SignalWriter for FragmentedSignal<S> {
+    fn write(&mut self, buf: &[S]) -> Result<usize, PhonicError> {
+        let n = self.rng.range(0, 5).min(buf.len());
+        self.samples.extend_from_slice(&buf[..n]);
+        self.i += n;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<(), PhonicError> {
+        Ok(())
+    }
+}
+
+impl<S: Sample> SignalSeeker for FragmentedSignal<S> {
+    fn seek(&mut self, offset: i64) -> Result<(), PhonicError> {
+        self.i = self
+            .i
+            .checked_add_signed(offset as isize)
+            .filter(|i| *i <= self.samples.len())
+            .ok_or(PhonicError::InvalidData)?;
+
+        Ok(())
+    }
+}
+
+trait TestSample: PcmSample + Debug + PartialEq + 'static {
+    fn nth(i: usize) -> Self;
+}
+
+impl TestSample for i16 {
+    fn nth(i: usize) -> Self {
+        (i as i16).wrapping_mul(2_017)
+    }
+}
+
+impl TestSample for I24 {
+    fn nth(i: usize) -> Self {
+        I24::new((i as i32).wrapping_mul(104_729))
+    }
+}
+
+impl TestSample for f64 {
+    fn nth(i: usize) -> Self {
+        (i as f64 * 0.37).sin()
+    }
+}
+
+fn samples<S: TestSample>() -> Vec<S> {
+    (0..2_000).map(S::nth).collect()
+}
+
+fn bytes<S: TestSample>(samples: &[S]) -> Vec<u8> {
+    S::as_bytes(samples).to_vec()
+}
+
+fn decode_reads<S: TestSample>(seed: u64) {
+    let expected = samples::<S>();
+    let stream = FragmentedStream::new::<S>(bytes(&expected), seed);
+    let mut codec = PcmCodec::<_, S>::from_stream(stream).unwrap();
+    let mut rng = Rng(seed);
+
+    let mut output = Vec::new();
+    let mut buf = [S::ORIGIN; 7];
+    loop {
+        let len = rng.range(1, buf.len());
+        match codec.read(&mut buf[..len]) {
+            Ok(0) => break,
+            Ok(n) => output.extend_from_slice(&buf[..n]),
+            Err(PhonicError::Interrupted) => continue,
+            Err(e) => panic!("{e:?}"),
+        }
+
+        assert_eq!(codec.position().unwrap(), output.len() as u64);
+    }
+
+    assert_eq!(output, expected);
+}
+
+fn decode_writes<S: TestSample>(seed: u64) {
+    let input = samples::<S>();
+    let stream = FragmentedStream::new::<S>(Vec::new(), seed);
+    let mut codec = PcmCodec::<_, S>::from_stream(stream).unwrap();
+    let mut rng = Rng(seed);
+
+    let mut i = 0;
+    while i < input.len() {
+        let len = rng.range(1, 7).min(input.len() - i);
+        match codec.write(&input[i..i + len]) {
+            Ok(n) => i += n,
+            Err(PhonicError::Interrupted) => continue,
+            Err(e) => panic!("{e:?}"),
+        }
+
+        assert_eq!(codec.position().unwrap(), i as u64);
+    }
+
+    while let Err(e) = codec.flush() {
+        assert!(matches!(e, PhonicError::Interrupted), "{e:?}");
+    }
+
+    assert_eq!(codec.into_inner().bytes, bytes(&input));
+}
+
+fn encode_reads<S: TestSample>(seed: u64) {
+    let input = samples::<S>();
+    let signal = FragmentedSignal::new(input.clone(), seed);
+    let mut codec = PcmCodec::<_, S>::from_signal(signal).unwrap();
+    let mut rng = Rng(seed);
+
+    // reading at an odd offset into the buffer misaligns it for every sample type but bytes
+    let mut output = Vec::new();
+    let mut buf = [0u8; 64];
+    loop {
+        let start = rng.range(0, 3);
+        let end = rng.range(start, buf.len());
+        match codec.read(&mut buf[start..end]).unwrap() {
+            0 if start == end => continue,
+            0 => break,
+            n => output.extend_from_slice(&buf[start..start + n]),
+        }
+
+        assert_eq!(
+            StreamObserver::position(&codec).unwrap(),
+            output.len() as u64
+        );
+    }
+
+    assert_eq!(output, bytes(&input));
+}
+
+fn encode_writes<S: TestSample>(seed: u64) {
+    let input = bytes(&samples::<S>());
+    let signal = FragmentedSignal::new(Vec::new(), seed);
+    let mut codec = PcmCodec::<_, S>::from_signal(signal).unwrap();
+    let mut rng = Rng(seed);
+
+    let mut i = 0;
+    while i < input.len() {
+        let len = rng.range(1, 40).min(input.len() - i);
+        i += codec.write(&input[i..i + len]).unwrap();
+        assert_eq!(StreamObserver::position(&codec).unwrap(), i as u64);
+    }
+
+    loop {
+        match StreamWriter::flush(&mut codec) {
+            Ok(()) => break,
+            Err(PhonicError::EndOfStream) => continue,
+            Err(e) => panic!("{e:?}"),
+        }
+    }
+
+    assert_eq!(bytes(&codec.into_inner().samples), input);
+}
+
+macro_rules! test_fragmented {
+    ($($name:ident: $sample:ty),*) => {
+        $(
+            #[test]
+            fn $name() {
+                for seed in 1..20 {
+                    decode_reads::<$sample>(seed);
+                    decode_writes::<$sample>(seed);
+                    encode_reads::<$sample>(seed);
+                    encode_writes::<$sample>(seed);
+                }
+            }
+        )*
+    };
+}
+
+test_fragmented!(fragmented_i16: i16, fragmented_i24: I24, fragmented_f64: f64);
+
+#[test]
+fn seek_after_partial_read() {
+    let expected = samples::<f64>();
+    let bytes = bytes(&expected);
+
+    let mut stream = FragmentedStream::new::<f64>(bytes.clone(), 3);
+    stream.rng = Rng(0x5);
+    let mut codec = PcmCodec::<_, f64>::from_stream(stream).unwrap();
+
+    // leave part of the third sample behind in the codec
+    let mut buf = [0.0; 2];
+    codec.read(&mut buf).ok();
+    while codec.as_inner().position().unwrap() % size_of::<f64>() as u64 == 0 {
+        codec.read(&mut buf[..1]).ok();
+    }
+
+    let position = codec.position().unwrap();
+    codec.seek(10).unwrap();
+    assert_eq!(codec.position().unwrap(), position + 10);
+
+    let mut buf = [0.0; 1];
+    while !matches!(codec.read(&mut buf), Ok(1)) {}
+    assert_eq!(buf[0], expected[position as usize + 10]);
+
+    // the encoder can only be seeked to sample boundaries
+    let signal = FragmentedSignal::new(expected.clone(), 3);
+    let mut codec = PcmCodec::<_, f64>::from_signal(signal).unwrap();
+    let mut buf = [0u8; 3];
+    codec.read(&mut buf).unwrap();
+
+    assert!(StreamSeeker::seek(&mut codec, 4).is_err());
+    StreamSeeker::seek(&mut codec, 13).unwrap();
+    assert_eq!(StreamObserver::position(&codec).unwrap(), 16);
+
+    let mut buf = [0u8; 8];
+    codec.read(&mut buf).unwrap();
+    assert_eq!(&buf, &bytes[16..24]);
+}
@@ -0,0 +1,72 @@
+use phonic_core::PhonicError;
+
+/// Reads little endian bit fields from a byte slice, starting from the least significant bit of
+/// each byte. Reading past the end of the slice is the end of packet condition, which audio
+/// packets use to leave out trailing data, so reads return `None` there instead of failing.
+pub(crate) struct BitReader<'a> {
+    buf: &'a [u8],
+    bit_i: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf, bit_i: 0 }
+    }
+
+    /// The number of bits left before the end of the slice.
+    pub fn remaining(&self) -> usize {
+        (self.buf.len() * 8).saturating_sub(self.bit_i)
+    }
+
+    /// Returns the next `n_bits` bits without moving past them, with bits past the end of the
+    /// slice reading as zeros. At most 32 bits can be peeked.
+    pub fn peek(&self, n_bits: u32) -> u32 {
+        if n_bits == 0 {
+            return 0;
+        }
+
+        let byte_i = self.bit_i / 8;
+        let mut window = 0u64;
+        for i in 0..5 {
+            let byte = self.buf.get(byte_i + i).copied().unwrap_or(0);
+            window |= (byte as u64) << (8 * i);
+        }
+
+        let value = window >> (self.bit_i % 8);
+        (value & (u64::MAX >> (64 - n_bits))) as u32
+    }
+
+    pub fn skip(&mut self, n_bits: u32) {
+        self.bit_i += n_bits as usize;
+    }
+
+    /// Reads `n_bits` bits, or returns `None` if they run past the end of the slice.
+    pub fn read(&mut self, n_bits: u32) -> Option<u32> {
+        if n_bits as usize > self.remaining() {
+            self.bit_i = self.buf.len() * 8;
+            return None;
+        }
+
+        let value = self.peek(n_bits);
+        self.skip(n_bits);
+        Some(value)
+    }
+
+    pub fn read_bool(&mut self) -> Option<bool> {
+        self.read(1).map(|bit| bit == 1)
+    }
+
+    /// Reads a field of a header, where running out of bits means the header is damaged.
+    pub fn field(&mut self, n_bits: u32) -> Result<u32, PhonicError> {
+        self.read(n_bits).ok_or(PhonicError::InvalidData)
+    }
+
+    pub fn flag(&mut self) -> Result<bool, PhonicError> {
+        self.read_bool().ok_or(PhonicError::InvalidData)
+    }
+}
+
+/// The number of bits needed to hold `value`, which is 0 for 0.
+pub(crate) fn ilog(value: u32) -> u32 {
+    u32::BITS - value.leading_zeros()
+}
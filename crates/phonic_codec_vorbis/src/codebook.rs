@@ -0,0 +1,276 @@
+use crate::bits::{ilog, BitReader};
+use phonic_core::PhonicError;
+
+const CODEBOOK_SYNC: u32 = 0x564342;
+
+/// The longest codewords that are looked up in a single step. Longer ones are matched one by one.
+const TABLE_BITS: u32 = 10;
+
+/// A codebook of the setup header, which maps huffman codewords to entry numbers and, for books
+/// with a lookup table, entry numbers to vectors of `dimensions` values.
+pub(crate) struct Codebook {
+    pub dimensions: usize,
+    table_bits: u32,
+    /// The entry and codeword length of each value of the next `table_bits` bits, where a length
+    /// of 0 marks a codeword too long for the table.
+    table: Vec<(u32, u8)>,
+    /// The codewords too long for the table, bit reversed, with their lengths and entries.
+    long_codes: Vec<(u32, u8, u32)>,
+    /// The only entry of a book with a single used entry, whose codeword is a single bit.
+    single_entry: Option<u32>,
+    lookup: Option<Lookup>,
+}
+
+/// The vector lookup table of a codebook, which is either a lattice of `lookup_values` values in
+/// every dimension, for type 1, or a list of values for each entry, for type 2.
+struct Lookup {
+    lattice: bool,
+    min: f32,
+    delta: f32,
+    sequence: bool,
+    multiplicands: Vec<u32>,
+}
+
+impl Codebook {
+    pub fn read(bits: &mut BitReader) -> Result<Self, PhonicError> {
+        if bits.field(24)? != CODEBOOK_SYNC {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let dimensions = bits.field(16)? as usize;
+        let n_entries = bits.field(24)? as usize;
+        let mut lengths = vec![0u8; n_entries];
+
+        if bits.flag()? {
+            let mut entry_i = 0;
+            let mut length = bits.field(5)? + 1;
+            while entry_i < n_entries {
+                let n = bits.field(ilog((n_entries - entry_i) as u32))? as usize;
+                if length > 32 || entry_i + n > n_entries {
+                    return Err(PhonicError::InvalidData);
+                }
+
+                lengths[entry_i..entry_i + n].fill(length as u8);
+                entry_i += n;
+                length += 1;
+            }
+        } else {
+            let sparse = bits.flag()?;
+            for length in lengths.iter_mut() {
+                if !sparse || bits.flag()? {
+                    *length = bits.field(5)? as u8 + 1;
+                }
+            }
+        }
+
+        let lookup = match bits.field(4)? {
+            0 => None,
+            lookup_type @ (1 | 2) => {
+                let min = float32_unpack(bits.field(32)?);
+                let delta = float32_unpack(bits.field(32)?);
+                let value_bits = bits.field(4)? + 1;
+                let sequence = bits.flag()?;
+
+                let lattice = lookup_type == 1;
+                let n_values = match lattice {
+                    true => lookup1_values(n_entries, dimensions),
+                    false => n_entries.checked_mul(dimensions),
+                };
+
+                // every value takes at least one bit, so a count beyond the bits left is damaged
+                let n_values = n_values
+                    .filter(|&n| n <= bits.remaining())
+                    .ok_or(PhonicError::InvalidData)?;
+
+                let multiplicands = (0..n_values)
+                    .map(|_| bits.field(value_bits))
+                    .collect::<Result<_, _>>()?;
+
+                Some(Lookup {
+                    lattice,
+                    min,
+                    delta,
+                    sequence,
+                    multiplicands,
+                })
+            }
+            _ => return Err(PhonicError::InvalidData),
+        };
+
+        let mut codebook = Self {
+            dimensions,
+            table_bits: 0,
+            table: Vec::new(),
+            long_codes: Vec::new(),
+            single_entry: None,
+            lookup,
+        };
+
+        codebook.build_codes(&lengths)?;
+        Ok(codebook)
+    }
+
+    pub fn has_lookup(&self) -> bool {
+        self.lookup.is_some()
+    }
+
+    /// Assigns codewords to the entries in order, each taking the lowest free codeword of its
+    /// length, and fills the tables they are decoded with. The lengths have to fill the code
+    /// space exactly, except in a book with a single used entry.
+    fn build_codes(&mut self, lengths: &[u8]) -> Result<(), PhonicError> {
+        let mut used = lengths.iter().enumerate().filter(|(_, &len)| len > 0);
+        match (used.next(), used.next()) {
+            (None, _) => return Ok(()),
+            (Some((entry, _)), None) => {
+                self.single_entry = Some(entry as u32);
+                return Ok(());
+            }
+            _ => (),
+        }
+
+        let max_len = *lengths.iter().max().unwrap_or(&0) as u32;
+        self.table_bits = max_len.min(TABLE_BITS);
+        self.table = vec![(0, 0); 1 << self.table_bits];
+
+        // the next free codeword of each length, as in the reference implementation
+        let mut marker = [0u32; 33];
+        for (entry, &len) in lengths.iter().enumerate() {
+            if len == 0 {
+                continue;
+            }
+
+            let len = len as u32;
+            let code = marker[len as usize];
+            if len < 32 && code >> len != 0 {
+                return Err(PhonicError::InvalidData);
+            }
+
+            for j in (1..=len as usize).rev() {
+                if marker[j] & 1 != 0 {
+                    marker[j] = match j {
+                        1 => marker[1] + 1,
+                        _ => marker[j - 1] << 1,
+                    };
+                    break;
+                }
+
+                marker[j] = marker[j].wrapping_add(1);
+            }
+
+            let mut branch = code;
+            for j in len as usize + 1..33 {
+                if marker[j] >> 1 != branch {
+                    break;
+                }
+
+                branch = marker[j];
+                marker[j] = marker[j - 1] << 1;
+            }
+
+            let reversed = code.reverse_bits() >> (32 - len);
+            if len <= self.table_bits {
+                let mut i = reversed as usize;
+                while i < self.table.len() {
+                    self.table[i] = (entry as u32, len as u8);
+                    i += 1 << len;
+                }
+            } else {
+                self.long_codes.push((reversed, len as u8, entry as u32));
+            }
+        }
+
+        if (1..33).any(|len| marker[len] & (u32::MAX >> (32 - len)) != 0) {
+            return Err(PhonicError::InvalidData);
+        }
+
+        self.long_codes.sort_by_key(|&(_, len, _)| len);
+        Ok(())
+    }
+
+    /// Reads a codeword, returning its entry number, or `None` at the end of the packet.
+    pub fn read_entry(&self, bits: &mut BitReader) -> Option<u32> {
+        if let Some(entry) = self.single_entry {
+            return bits.read(1).map(|_| entry);
+        }
+
+        let (entry, len) = self.table.get(bits.peek(self.table_bits) as usize)?;
+        if *len > 0 {
+            return bits.read(*len as u32).map(|_| *entry);
+        }
+
+        let peeked = bits.peek(32);
+        let &(_, len, entry) = self.long_codes.iter().find(|&&(code, len, _)| {
+            let mask = u32::MAX >> (32 - len);
+            peeked & mask == code
+        })?;
+
+        bits.read(len as u32).map(|_| entry)
+    }
+
+    /// Reads a codeword and writes the vector of its entry to `out`, which is as long as the
+    /// dimensions of the book. Returns `None` at the end of the packet.
+    pub fn read_vector(&self, bits: &mut BitReader, out: &mut [f32]) -> Option<()> {
+        let entry = self.read_entry(bits)? as usize;
+        let lookup = self.lookup.as_ref()?;
+
+        let mut last = 0.0;
+        let n_values = lookup.multiplicands.len();
+        let mut divisor = 1;
+        for (i, value) in out.iter_mut().enumerate() {
+            let value_i = match lookup.lattice {
+                true => (entry / divisor) % n_values,
+                false => entry * self.dimensions + i,
+            };
+
+            *value = lookup.multiplicands[value_i] as f32 * lookup.delta + lookup.min + last;
+            if lookup.sequence {
+                last = *value;
+            }
+
+            divisor = divisor.saturating_mul(n_values);
+        }
+
+        Some(())
+    }
+}
+
+/// Unpacks the 32 bit float format of codebooks, which has a 21 bit mantissa and a 10 bit
+/// exponent.
+fn float32_unpack(value: u32) -> f32 {
+    let mantissa = (value & 0x1fffff) as f64;
+    let exponent = ((value & 0x7fe00000) >> 21) as i32;
+    let mantissa = if value & 0x80000000 != 0 {
+        -mantissa
+    } else {
+        mantissa
+    };
+
+    (mantissa * 2f64.powi(exponent - 788)) as f32
+}
+
+/// The number of values in each dimension of a lattice lookup table, which is the largest number
+/// whose power of `dimensions` is no more than the number of entries.
+fn lookup1_values(n_entries: usize, dimensions: usize) -> Option<usize> {
+    if dimensions == 0 {
+        return None;
+    }
+
+    let fits = |n: usize| {
+        (0..dimensions)
+            .try_fold(1usize, |product, _| {
+                product.checked_mul(n).filter(|&p| p <= n_entries)
+            })
+            .is_some()
+    };
+
+    let mut n = (n_entries as f64).powf(1.0 / dimensions as f64).floor() as usize;
+    while n > 0 && !fits(n) {
+        n -= 1;
+    }
+
+    while fits(n + 1) {
+        n += 1;
+    }
+
+    Some(n)
+}
@@ -0,0 +1,275 @@
+use crate::{
+    bits::{ilog, BitReader},
+    floor::FloorCurve,
+    header::IdentHeader,
+    mdct::Imdct,
+    setup::SetupHeader,
+};
+use std::f32::consts::FRAC_PI_2;
+use phonic_core::PhonicError;
+
+/// Decodes vorbis audio packets into interleaved samples. Each packet is a block that overlaps
+/// the one before it by half, so the samples of a packet run from the centre of the block before
+/// it to the centre of its own, and the first packet after a reset decodes to nothing.
+pub struct VorbisDecoder {
+    ident: IdentHeader,
+    setup: SetupHeader,
+    imdct: [Imdct; 2],
+    /// The rising half of the windows of short and long blocks.
+    slopes: [Vec<f32>; 2],
+    curves: Vec<FloorCurve>,
+    spectra: Vec<Vec<f32>>,
+    block: Vec<f32>,
+    /// The windowed second half of the last block of each channel.
+    overlap: Vec<Vec<f32>>,
+    /// The size of the last block and where its right window starts, from its centre.
+    previous: Option<(usize, usize)>,
+    lead_frames: usize,
+    scratch: Vec<f32>,
+}
+
+fn window_slope(len: usize) -> Vec<f32> {
+    (0..len)
+        .map(|i| {
+            let x = (i as f32 + 0.5) / len as f32 * FRAC_PI_2;
+            (FRAC_PI_2 * x.sin() * x.sin()).sin()
+        })
+        .collect()
+}
+
+impl VorbisDecoder {
+    pub fn new(ident: IdentHeader, setup: SetupHeader) -> Self {
+        let n_channels = ident.n_channels as usize;
+        let block_sizes = [ident.block_size_0 as usize, ident.block_size_1 as usize];
+        let half_long = block_sizes[1] / 2;
+
+        Self {
+            ident,
+            setup,
+            imdct: block_sizes.map(Imdct::new),
+            slopes: block_sizes.map(|block_size| window_slope(block_size / 2)),
+            curves: (0..n_channels).map(|_| FloorCurve::default()).collect(),
+            spectra: vec![vec![0.0; half_long]; n_channels],
+            block: vec![0.0; block_sizes[1]],
+            overlap: vec![vec![0.0; half_long]; n_channels],
+            previous: None,
+            lead_frames: 0,
+            scratch: Vec::new(),
+        }
+    }
+
+    pub fn ident(&self) -> &IdentHeader {
+        &self.ident
+    }
+
+    pub fn setup(&self) -> &SetupHeader {
+        &self.setup
+    }
+
+    /// The most frames a packet decodes to, which is half a long block.
+    pub fn max_packet_frames(&self) -> usize {
+        self.ident.block_size_1 as usize / 2
+    }
+
+    /// The frames that the first packet after a reset covers ahead of its centre, which it
+    /// doesn't decode to, since the block before it is missing. The size of the block before a
+    /// short block isn't coded, so it is taken to be short as well.
+    pub fn lead_frames(&self) -> usize {
+        self.lead_frames
+    }
+
+    /// Forgets the packets decoded so far, as if the next packet started the stream.
+    pub fn reset(&mut self) {
+        self.previous = None;
+        self.lead_frames = 0;
+    }
+
+    /// Decodes an audio packet into `pcm`, and returns the number of frames written. Packets that
+    /// aren't audio packets, including empty ones, are ignored and decode to nothing.
+    pub fn decode(&mut self, packet: &[u8], pcm: &mut [f32]) -> Result<usize, PhonicError> {
+        let mut bits = BitReader::new(packet);
+        if bits.read_bool() != Some(false) {
+            return Ok(0);
+        }
+
+        let n_modes = self.setup.modes.len() as u32;
+        let Some(mode_i) = bits.read(ilog(n_modes - 1)) else {
+            return Ok(0);
+        };
+
+        let mode = *self
+            .setup
+            .modes
+            .get(mode_i as usize)
+            .ok_or(PhonicError::InvalidData)?;
+
+        let short_n = self.ident.block_size_0 as usize;
+        let long = mode.long_block;
+        let n = match long {
+            true => self.ident.block_size_1 as usize,
+            false => short_n,
+        };
+
+        // long blocks shorten their slopes to overlap a short block on either side
+        let (previous_long, next_long) = match long {
+            true => match (bits.read_bool(), bits.read_bool()) {
+                (Some(previous_long), Some(next_long)) => (previous_long, next_long),
+                _ => return Ok(0),
+            },
+            false => (false, false),
+        };
+
+        let (left_start, left_len) = match long && !previous_long {
+            true => (n / 4 - short_n / 4, short_n / 2),
+            false => (0, n / 2),
+        };
+
+        let (right_start, right_len) = match long && !next_long {
+            true => (n * 3 / 4 - short_n / 4, short_n / 2),
+            false => (n / 2, n / 2),
+        };
+
+        let n_channels = self.ident.n_channels as usize;
+        let n_frames = self
+            .previous
+            .map_or(0, |(previous_n, _)| previous_n / 4 + n / 4);
+        if pcm.len() < n_frames * n_channels {
+            return Err(PhonicError::InvalidData);
+        }
+
+        self.decode_spectra(&mut bits, mode.mapping, long, n / 2);
+
+        for ch in 0..n_channels {
+            let block = &mut self.block[..n];
+            self.imdct[long as usize].transform(&self.spectra[ch][..n / 2], block);
+
+            let left_slope = &self.slopes[(left_len != short_n / 2) as usize];
+            let right_slope = &self.slopes[(right_len != short_n / 2) as usize];
+            block[..left_start].fill(0.0);
+            for (sample, gain) in block[left_start..].iter_mut().zip(left_slope) {
+                *sample *= gain;
+            }
+
+            for (sample, gain) in block[right_start..]
+                .iter_mut()
+                .zip(right_slope.iter().rev())
+            {
+                *sample *= gain;
+            }
+
+            block[right_start + right_len..].fill(0.0);
+
+            // the previous block runs on past its centre, where this block takes over from the
+            // start of its left window
+            if let Some((previous_n, previous_right)) = self.previous {
+                let overlap = &self.overlap[ch][..previous_n / 2];
+                let offset = left_start as isize - previous_right as isize;
+                for (frame_i, sample) in pcm[ch..n_frames * n_channels]
+                    .iter_mut()
+                    .step_by(n_channels)
+                    .enumerate()
+                {
+                    let block_i = frame_i as isize + offset;
+                    *sample = overlap.get(frame_i).copied().unwrap_or(0.0)
+                        + match block_i >= 0 && block_i < n as isize {
+                            true => block[block_i as usize],
+                            false => 0.0,
+                        };
+                }
+            }
+
+            self.overlap[ch][..n / 2].copy_from_slice(&block[n / 2..]);
+        }
+
+        if self.previous.is_none() {
+            let previous_n = match previous_long {
+                true => self.ident.block_size_1 as usize,
+                false => short_n,
+            };
+
+            self.lead_frames = previous_n / 4 + n / 4;
+        }
+
+        self.previous = Some((n, right_start - n / 2));
+        Ok(n_frames)
+    }
+
+    /// Decodes the floors and residues of a packet into the spectrum of each channel.
+    fn decode_spectra(&mut self, bits: &mut BitReader, mapping_i: usize, long: bool, n: usize) {
+        let setup = &self.setup;
+        let mapping = &setup.mappings[mapping_i];
+        let n_channels = self.ident.n_channels as usize;
+
+        let mut unused = vec![false; n_channels];
+        for (ch, curve) in self.curves.iter_mut().enumerate() {
+            let (floor_i, _) = mapping.submaps[mapping.mux[ch]];
+            unused[ch] = !setup.floors[floor_i].decode(bits, &setup.codebooks, curve);
+        }
+
+        // coupled channels both carry residue if either of them does
+        let mut no_residue = unused.clone();
+        for &(magnitude, angle) in &mapping.coupling {
+            if !no_residue[magnitude] || !no_residue[angle] {
+                no_residue[magnitude] = false;
+                no_residue[angle] = false;
+            }
+        }
+
+        for spectrum in self.spectra.iter_mut() {
+            spectrum[..n].fill(0.0);
+        }
+
+        for (submap_i, &(_, residue_i)) in mapping.submaps.iter().enumerate() {
+            let (mut spectra, flags): (Vec<_>, Vec<_>) = self
+                .spectra
+                .iter_mut()
+                .zip(&no_residue)
+                .zip(&mapping.mux)
+                .filter(|(_, &submap)| submap == submap_i)
+                .map(|((spectrum, &no_residue), _)| (&mut spectrum[..n], no_residue))
+                .unzip();
+
+            setup.residues[residue_i].decode(
+                bits,
+                &setup.codebooks,
+                &mut spectra,
+                &flags,
+                n,
+                &mut self.scratch,
+            );
+        }
+
+        for &(magnitude, angle) in mapping.coupling.iter().rev() {
+            let (magnitudes, angles) = match magnitude < angle {
+                true => {
+                    let (low, high) = self.spectra.split_at_mut(angle);
+                    (&mut low[magnitude], &mut high[0])
+                }
+                false => {
+                    let (low, high) = self.spectra.split_at_mut(magnitude);
+                    (&mut high[0], &mut low[angle])
+                }
+            };
+
+            for (m, a) in magnitudes[..n].iter_mut().zip(angles[..n].iter_mut()) {
+                (*m, *a) = match (*m > 0.0, *a > 0.0) {
+                    (true, true) => (*m, *m - *a),
+                    (true, false) => (*m + *a, *m),
+                    (false, true) => (*m, *m + *a),
+                    (false, false) => (*m - *a, *m),
+                };
+            }
+        }
+
+        for (ch, (spectrum, unused)) in self.spectra.iter_mut().zip(unused).enumerate() {
+            let spectrum = &mut spectrum[..n];
+            if unused {
+                spectrum.fill(0.0);
+                continue;
+            }
+
+            let (floor_i, _) = mapping.submaps[mapping.mux[ch]];
+            setup.floors[floor_i].apply(&self.curves[ch], long, spectrum);
+        }
+    }
+}
@@ -0,0 +1,504 @@
+use crate::{
+    bits::{ilog, BitReader},
+    codebook::Codebook,
+};
+use std::f32::consts::PI;
+use phonic_core::PhonicError;
+
+/// The range of the amplitude values of floor 1, by multiplier.
+const FLOOR1_RANGES: [i32; 4] = [256, 128, 86, 64];
+
+/// The most points a floor 1 curve can have, counting the two at its ends.
+const FLOOR1_MAX_POINTS: usize = 65;
+
+/// The gain of each amplitude value of a floor 1 curve, from -140 dB up to 0 dB.
+#[rustfmt::skip]
+#[allow(clippy::excessive_precision)]
+static FLOOR1_INVERSE_DB: [f32; 256] = [
+    1.0649863e-07, 1.1341951e-07, 1.2079015e-07, 1.2863978e-07,
+    1.3699951e-07, 1.4590251e-07, 1.5538408e-07, 1.6548181e-07,
+    1.7623575e-07, 1.8768855e-07, 1.9988561e-07, 2.1287530e-07,
+    2.2670913e-07, 2.4144197e-07, 2.5713223e-07, 2.7384213e-07,
+    2.9163793e-07, 3.1059021e-07, 3.3077411e-07, 3.5226968e-07,
+    3.7516214e-07, 3.9954229e-07, 4.2550680e-07, 4.5315863e-07,
+    4.8260743e-07, 5.1396998e-07, 5.4737065e-07, 5.8294187e-07,
+    6.2082472e-07, 6.6116941e-07, 7.0413592e-07, 7.4989464e-07,
+    7.9862701e-07, 8.5052630e-07, 9.0579828e-07, 9.6466216e-07,
+    1.0273513e-06, 1.0941144e-06, 1.1652161e-06, 1.2409384e-06,
+    1.3215816e-06, 1.4074654e-06, 1.4989305e-06, 1.5963394e-06,
+    1.7000785e-06, 1.8105592e-06, 1.9282195e-06, 2.0535261e-06,
+    2.1869758e-06, 2.3290978e-06, 2.4804557e-06, 2.6416497e-06,
+    2.8133190e-06, 2.9961443e-06, 3.1908506e-06, 3.3982101e-06,
+    3.6190449e-06, 3.8542308e-06, 4.1047004e-06, 4.3714470e-06,
+    4.6555282e-06, 4.9580707e-06, 5.2802740e-06, 5.6234160e-06,
+    5.9888572e-06, 6.3780469e-06, 6.7925283e-06, 7.2339451e-06,
+    7.7040476e-06, 8.2047000e-06, 8.7378876e-06, 9.3057248e-06,
+    9.9104632e-06, 1.0554501e-05, 1.1240392e-05, 1.1970856e-05,
+    1.2748789e-05, 1.3577278e-05, 1.4459606e-05, 1.5399272e-05,
+    1.6400004e-05, 1.7465768e-05, 1.8600792e-05, 1.9809576e-05,
+    2.1096914e-05, 2.2467911e-05, 2.3928002e-05, 2.5482978e-05,
+    2.7139006e-05, 2.8902651e-05, 3.0780908e-05, 3.2781225e-05,
+    3.4911534e-05, 3.7180282e-05, 3.9596466e-05, 4.2169667e-05,
+    4.4910090e-05, 4.7828601e-05, 5.0936773e-05, 5.4246931e-05,
+    5.7772202e-05, 6.1526565e-05, 6.5524908e-05, 6.9783085e-05,
+    7.4317983e-05, 7.9147585e-05, 8.4291040e-05, 8.9768747e-05,
+    9.5602426e-05, 0.00010181521, 0.00010843174, 0.00011547824,
+    0.00012298267, 0.00013097477, 0.00013948625, 0.00014855085,
+    0.00015820453, 0.00016848555, 0.00017943469, 0.00019109536,
+    0.00020351382, 0.00021673929, 0.00023082423, 0.00024582449,
+    0.00026179955, 0.00027881276, 0.00029693158, 0.00031622787,
+    0.00033677814, 0.00035866388, 0.00038197188, 0.00040679456,
+    0.00043323036, 0.00046138411, 0.00049136745, 0.00052329927,
+    0.00055730621, 0.00059352311, 0.00063209358, 0.00067317058,
+    0.00071691700, 0.00076350630, 0.00081312324, 0.00086596457,
+    0.00092223983, 0.00098217216, 0.0010459992, 0.0011139742,
+    0.0011863665, 0.0012634633, 0.0013455702, 0.0014330129,
+    0.0015261382, 0.0016253153, 0.0017309374, 0.0018434235,
+    0.0019632195, 0.0020908006, 0.0022266726, 0.0023713743,
+    0.0025254795, 0.0026895994, 0.0028643847, 0.0030505286,
+    0.0032487691, 0.0034598925, 0.0036847358, 0.0039241906,
+    0.0041792066, 0.0044507950, 0.0047400328, 0.0050480668,
+    0.0053761186, 0.0057254891, 0.0060975636, 0.0064938176,
+    0.0069158225, 0.0073652516, 0.0078438871, 0.0083536271,
+    0.0088964928, 0.009474637, 0.010090352, 0.010746080,
+    0.011444421, 0.012188144, 0.012980198, 0.013823725,
+    0.014722068, 0.015678791, 0.016697687, 0.017782797,
+    0.018938423, 0.020169149, 0.021479854, 0.022875735,
+    0.024362330, 0.025945531, 0.027631618, 0.029427276,
+    0.031339626, 0.033376252, 0.035545228, 0.037855157,
+    0.040315199, 0.042935108, 0.045725273, 0.048696758,
+    0.051861348, 0.055231591, 0.058820850, 0.062643361,
+    0.066714279, 0.071049749, 0.075666962, 0.080584227,
+    0.085821044, 0.091398179, 0.097337747, 0.10366330,
+    0.11039993, 0.11757434, 0.12521498, 0.13335215,
+    0.14201813, 0.15124727, 0.16107617, 0.17154380,
+    0.18269168, 0.19456402, 0.20720788, 0.22067342,
+    0.23501402, 0.25028656, 0.26655159, 0.28387361,
+    0.30232132, 0.32196786, 0.34289114, 0.36517414,
+    0.38890521, 0.41417847, 0.44109412, 0.46975890,
+    0.50028648, 0.53279791, 0.56742212, 0.60429640,
+    0.64356699, 0.68538959, 0.72993007, 0.77736504,
+    0.82788260, 0.88168307, 0.9389798, 1.0,
+];
+
+/// A floor of the setup header, which codes the rough spectral envelope of a channel that the
+/// residue is multiplied by.
+pub(crate) enum Floor {
+    Zero(Floor0),
+    One(Floor1),
+}
+
+/// A floor given by the coefficients of a line spectral pair filter.
+pub(crate) struct Floor0 {
+    order: usize,
+    amplitude_bits: u32,
+    amplitude_offset: u32,
+    books: Vec<usize>,
+    /// The cosine of the bark scaled frequency of each line of short and long blocks.
+    cos_omega: [Vec<f32>; 2],
+}
+
+/// A floor given by points joined with straight lines on a log amplitude scale.
+pub(crate) struct Floor1 {
+    partition_classes: Vec<usize>,
+    classes: Vec<Floor1Class>,
+    multiplier: i32,
+    x_list: Vec<u32>,
+    /// The points in order of their positions.
+    sorted: Vec<usize>,
+    /// The closest points before each point, below and above its position.
+    neighbors: Vec<(usize, usize)>,
+}
+
+struct Floor1Class {
+    dimensions: usize,
+    subclass_bits: u32,
+    masterbook: usize,
+    subclass_books: Vec<Option<usize>>,
+}
+
+/// What the floor of a channel decodes to in a packet, which is the amplitude and coefficients of
+/// floor 0 or the amplitude values of the points of floor 1.
+#[derive(Default)]
+pub(crate) struct FloorCurve {
+    amplitude: u64,
+    coefficients: Vec<f32>,
+    y: Vec<i32>,
+}
+
+fn read_book(bits: &mut BitReader, codebooks: &[Codebook]) -> Result<usize, PhonicError> {
+    let book = bits.field(8)? as usize;
+    match book < codebooks.len() {
+        true => Ok(book),
+        false => Err(PhonicError::InvalidData),
+    }
+}
+
+impl Floor {
+    pub fn read(
+        bits: &mut BitReader,
+        codebooks: &[Codebook],
+        block_sizes: [usize; 2],
+    ) -> Result<Self, PhonicError> {
+        match bits.field(16)? {
+            0 => Floor0::read(bits, codebooks, block_sizes).map(Self::Zero),
+            1 => Floor1::read(bits, codebooks).map(Self::One),
+            _ => Err(PhonicError::InvalidData),
+        }
+    }
+
+    /// Decodes the curve of a channel, returning false if the channel is unused in the packet,
+    /// which an end of packet in the middle of the floor also means.
+    pub fn decode(
+        &self,
+        bits: &mut BitReader,
+        codebooks: &[Codebook],
+        curve: &mut FloorCurve,
+    ) -> bool {
+        let decoded = match self {
+            Self::Zero(floor) => floor.decode(bits, codebooks, curve),
+            Self::One(floor) => floor.decode(bits, codebooks, curve),
+        };
+
+        decoded.is_some()
+    }
+
+    /// Multiplies the spectrum of a channel by its curve.
+    pub fn apply(&self, curve: &FloorCurve, long: bool, spectrum: &mut [f32]) {
+        match self {
+            Self::Zero(floor) => floor.apply(curve, long, spectrum),
+            Self::One(floor) => floor.apply(curve, spectrum),
+        }
+    }
+}
+
+fn bark(x: f32) -> f32 {
+    13.1 * (0.00074 * x).atan() + 2.24 * (0.0000000185 * x * x).atan() + 0.0001 * x
+}
+
+impl Floor0 {
+    fn read(
+        bits: &mut BitReader,
+        codebooks: &[Codebook],
+        block_sizes: [usize; 2],
+    ) -> Result<Self, PhonicError> {
+        let order = bits.field(8)? as usize;
+        let rate = bits.field(16)?;
+        let bark_map_size = bits.field(16)?;
+        let amplitude_bits = bits.field(6)?;
+        let amplitude_offset = bits.field(8)?;
+        let n_books = bits.field(4)? + 1;
+        let books = (0..n_books)
+            .map(|_| read_book(bits, codebooks))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if order == 0 || rate == 0 || bark_map_size == 0 {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let nyquist = rate as f32 / 2.0;
+        let bark_scale = bark_map_size as f32 / bark(nyquist);
+        let omega_scale = PI / bark_map_size as f32;
+        let cos_omega = block_sizes.map(|block_size| {
+            let n = block_size / 2;
+            let line_width = nyquist / n as f32;
+            (0..n)
+                .map(|i| {
+                    let map = (bark(i as f32 * line_width) * bark_scale).floor();
+                    (map.min(bark_map_size as f32 - 1.0) * omega_scale).cos()
+                })
+                .collect()
+        });
+
+        Ok(Self {
+            order,
+            amplitude_bits,
+            amplitude_offset,
+            books,
+            cos_omega,
+        })
+    }
+
+    fn decode(
+        &self,
+        bits: &mut BitReader,
+        codebooks: &[Codebook],
+        curve: &mut FloorCurve,
+    ) -> Option<()> {
+        let low_bits = self.amplitude_bits.min(32);
+        let mut amplitude = bits.read(low_bits)? as u64;
+        if self.amplitude_bits > 32 {
+            amplitude |= (bits.read(self.amplitude_bits - 32)? as u64) << 32;
+        }
+
+        if amplitude == 0 {
+            return None;
+        }
+
+        // a book number past the list makes the packet undecodable, which leaves the channel
+        // silent here
+        let book_i = bits.read(ilog(self.books.len() as u32))? as usize;
+        let codebook = &codebooks[*self.books.get(book_i)?];
+
+        curve.amplitude = amplitude;
+        curve.coefficients.clear();
+        let mut vector = vec![0.0; codebook.dimensions];
+        let mut last = 0.0;
+        while curve.coefficients.len() < self.order {
+            codebook.read_vector(bits, &mut vector)?;
+            curve
+                .coefficients
+                .extend(vector.iter().map(|value| value + last));
+            last += vector.last()?;
+        }
+
+        // the curve only needs the cosine of each coefficient
+        curve.coefficients.truncate(self.order);
+        for coefficient in curve.coefficients.iter_mut() {
+            *coefficient = coefficient.cos();
+        }
+
+        Some(())
+    }
+
+    fn apply(&self, curve: &FloorCurve, long: bool, spectrum: &mut [f32]) {
+        let cos_omega = &self.cos_omega[long as usize];
+        let amplitude_offset = self.amplitude_offset as f32;
+        let amplitude_scale =
+            curve.amplitude as f32 * amplitude_offset / ((1u64 << self.amplitude_bits) - 1) as f32;
+
+        let coefficients = &curve.coefficients;
+        let mut i = 0;
+        while i < spectrum.len() {
+            let cos = cos_omega[i];
+            let (mut p, mut q) = match self.order % 2 {
+                1 => (1.0 - cos * cos, 0.25),
+                _ => ((1.0 - cos) / 2.0, (1.0 + cos) / 2.0),
+            };
+
+            for odd in coefficients.iter().skip(1).step_by(2) {
+                p *= 4.0 * (odd - cos) * (odd - cos);
+            }
+
+            for even in coefficients.iter().step_by(2) {
+                q *= 4.0 * (even - cos) * (even - cos);
+            }
+
+            let value = (0.11512925 * (amplitude_scale / (p + q).sqrt() - amplitude_offset)).exp();
+
+            // lines that map to the same bark value share the value
+            while i < spectrum.len() && cos_omega[i] == cos {
+                spectrum[i] *= value;
+                i += 1;
+            }
+        }
+    }
+}
+
+impl Floor1 {
+    fn read(bits: &mut BitReader, codebooks: &[Codebook]) -> Result<Self, PhonicError> {
+        let n_partitions = bits.field(5)?;
+        let partition_classes = (0..n_partitions)
+            .map(|_| bits.field(4).map(|class| class as usize))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let n_classes = partition_classes.iter().max().map_or(0, |max| max + 1);
+        let mut classes = Vec::with_capacity(n_classes);
+        for _ in 0..n_classes {
+            let dimensions = bits.field(3)? as usize + 1;
+            let subclass_bits = bits.field(2)?;
+            let masterbook = match subclass_bits {
+                0 => 0,
+                _ => read_book(bits, codebooks)?,
+            };
+
+            let subclass_books = (0..1 << subclass_bits)
+                .map(|_| match bits.field(8)? {
+                    0 => Ok(None),
+                    book if book as usize <= codebooks.len() => Ok(Some(book as usize - 1)),
+                    _ => Err(PhonicError::InvalidData),
+                })
+                .collect::<Result<_, _>>()?;
+
+            classes.push(Floor1Class {
+                dimensions,
+                subclass_bits,
+                masterbook,
+                subclass_books,
+            });
+        }
+
+        let multiplier = bits.field(2)? as i32 + 1;
+        let range_bits = bits.field(4)?;
+        let mut x_list = vec![0, 1 << range_bits];
+        for &class in &partition_classes {
+            for _ in 0..classes[class].dimensions {
+                x_list.push(bits.field(range_bits)?);
+            }
+        }
+
+        let mut sorted: Vec<usize> = (0..x_list.len()).collect();
+        sorted.sort_by_key(|&i| x_list[i]);
+        let duplicate = sorted.windows(2).any(|w| x_list[w[0]] == x_list[w[1]]);
+        if x_list.len() > FLOOR1_MAX_POINTS || duplicate {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let neighbors = (0..x_list.len())
+            .map(|i| {
+                let before = || (0..i).map(|j| (j, x_list[j]));
+                let low = before()
+                    .filter(|&(_, x)| x < x_list[i])
+                    .max_by_key(|&(_, x)| x);
+                let high = before()
+                    .filter(|&(_, x)| x > x_list[i])
+                    .min_by_key(|&(_, x)| x);
+                (low.map_or(0, |(j, _)| j), high.map_or(0, |(j, _)| j))
+            })
+            .collect();
+
+        Ok(Self {
+            partition_classes,
+            classes,
+            multiplier,
+            x_list,
+            sorted,
+            neighbors,
+        })
+    }
+
+    fn decode(
+        &self,
+        bits: &mut BitReader,
+        codebooks: &[Codebook],
+        curve: &mut FloorCurve,
+    ) -> Option<()> {
+        if !bits.read_bool()? {
+            return None;
+        }
+
+        let range = FLOOR1_RANGES[self.multiplier as usize - 1];
+        let range_bits = ilog(range as u32 - 1);
+        curve.y.clear();
+        curve.y.push(bits.read(range_bits)? as i32);
+        curve.y.push(bits.read(range_bits)? as i32);
+
+        for &class in &self.partition_classes {
+            let class = &self.classes[class];
+            let mut cval = match class.subclass_bits {
+                0 => 0,
+                _ => codebooks[class.masterbook].read_entry(bits)?,
+            };
+
+            for _ in 0..class.dimensions {
+                let subclass = cval & ((1 << class.subclass_bits) - 1);
+                cval >>= class.subclass_bits;
+                let y = match class.subclass_books[subclass as usize] {
+                    Some(book) => codebooks[book].read_entry(bits)?,
+                    None => 0,
+                };
+
+                curve.y.push(y as i32);
+            }
+        }
+
+        Some(())
+    }
+
+    fn apply(&self, curve: &FloorCurve, spectrum: &mut [f32]) {
+        let range = FLOOR1_RANGES[self.multiplier as usize - 1];
+
+        // each point is coded as an offset from the line between its neighbours, and points that
+        // are on that line are left out of the curve
+        let n_points = self.x_list.len();
+        let mut final_y = [0i32; FLOOR1_MAX_POINTS];
+        let mut used = [false; FLOOR1_MAX_POINTS];
+        final_y[..2].copy_from_slice(&curve.y[..2]);
+        used[..2].fill(true);
+
+        for i in 2..n_points {
+            let (low, high) = self.neighbors[i];
+            let predicted = render_point(
+                self.x_list[low] as i32,
+                final_y[low],
+                self.x_list[high] as i32,
+                final_y[high],
+                self.x_list[i] as i32,
+            );
+
+            let value = curve.y[i];
+            let high_room = range - predicted;
+            let low_room = predicted;
+            let room = high_room.min(low_room) * 2;
+
+            if value == 0 {
+                final_y[i] = predicted;
+                continue;
+            }
+
+            used[low] = true;
+            used[high] = true;
+            used[i] = true;
+            final_y[i] = match value >= room {
+                true if high_room > low_room => value - low_room + predicted,
+                true => predicted - value + high_room - 1,
+                false if value % 2 == 1 => predicted - (value + 1) / 2,
+                false => predicted + value / 2,
+            };
+        }
+
+        for y in final_y[..n_points].iter_mut() {
+            *y = (*y).clamp(0, range - 1);
+        }
+
+        let n = spectrum.len();
+        let (mut lx, mut ly) = (0, final_y[self.sorted[0]] * self.multiplier);
+        for &i in &self.sorted[1..] {
+            if used[i] {
+                let (hx, hy) = (self.x_list[i] as usize, final_y[i] * self.multiplier);
+                render_line(lx, ly, hx, hy, spectrum);
+                (lx, ly) = (hx, hy);
+            }
+        }
+
+        if lx < n {
+            render_line(lx, ly, n, ly, spectrum);
+        }
+    }
+}
+
+fn render_point(x0: i32, y0: i32, x1: i32, y1: i32, x: i32) -> i32 {
+    let dy = y1 - y0;
+    let adx = x1 - x0;
+    let offset = dy.abs() * (x - x0) / adx;
+    match dy < 0 {
+        true => y0 - offset,
+        false => y0 + offset,
+    }
+}
+
+/// Multiplies the spectrum from `x0` up to `x1` by the gains along the line between the points,
+/// stepping the amplitude value with integer arithmetic as the specification does.
+fn render_line(x0: usize, y0: i32, x1: usize, y1: i32, spectrum: &mut [f32]) {
+    let dy = y1 - y0;
+    let adx = (x1 - x0) as i32;
+    let base = dy / adx;
+    let step = if dy < 0 { base - 1 } else { base + 1 };
+    let ady = dy.abs() - base.abs() * adx;
+
+    let mut y = y0;
+    let mut err = 0;
+    for x in x0..x1.min(spectrum.len()) {
+        if x > x0 {
+            err += ady;
+            if err >= adx {
+                err -= adx;
+                y += step;
+            } else {
+                y += base;
+            }
+        }
+
+        spectrum[x] *= FLOOR1_INVERSE_DB[y as usize];
+    }
+}
@@ -0,0 +1,120 @@
+use phonic_core::PhonicError;
+
+/// The marker that follows the packet type at the start of every header packet.
+pub const VORBIS_MARKER: [u8; 6] = *b"vorbis";
+
+const IDENT_PACKET_TYPE: u8 = 1;
+const COMMENT_PACKET_TYPE: u8 = 3;
+
+const IDENT_HEADER_LEN: usize = 30;
+
+/// The identification header, which is the first packet of a vorbis stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdentHeader {
+    pub version: u32,
+    pub n_channels: u8,
+    pub sample_rate: u32,
+    /// The bitrates the encoder aimed for, in bits per second, where 0 means unset.
+    pub bitrate_max: i32,
+    pub bitrate_nominal: i32,
+    pub bitrate_min: i32,
+    /// The lengths of short and long blocks, which are powers of two from 64 to 8192.
+    pub block_size_0: u16,
+    pub block_size_1: u16,
+}
+
+impl IdentHeader {
+    pub fn read(packet: &[u8]) -> Result<Self, PhonicError> {
+        if packet.len() < IDENT_HEADER_LEN
+            || packet[0] != IDENT_PACKET_TYPE
+            || packet[1..7] != VORBIS_MARKER
+        {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let read_u32 =
+            |i: usize| u32::from_le_bytes([packet[i], packet[i + 1], packet[i + 2], packet[i + 3]]);
+        let version = read_u32(7);
+        if version != 0 {
+            return Err(PhonicError::Unsupported);
+        }
+
+        let n_channels = packet[11];
+        let sample_rate = read_u32(12);
+        let block_size_0 = 1 << (packet[28] & 0xf);
+        let block_size_1 = 1 << (packet[28] >> 4);
+        if n_channels == 0
+            || sample_rate == 0
+            || block_size_0 < 64
+            || block_size_0 > block_size_1
+            || block_size_1 > 8192
+            || packet[29] & 1 == 0
+        {
+            return Err(PhonicError::InvalidData);
+        }
+
+        Ok(Self {
+            version,
+            n_channels,
+            sample_rate,
+            bitrate_max: read_u32(16) as i32,
+            bitrate_nominal: read_u32(20) as i32,
+            bitrate_min: read_u32(24) as i32,
+            block_size_0,
+            block_size_1,
+        })
+    }
+}
+
+/// The comment header, which is the second packet of a vorbis stream. Comments are stored as
+/// `NAME=value` pairs, where names are compared without regard to case.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommentHeader {
+    pub vendor: String,
+    pub comments: Vec<(String, String)>,
+}
+
+impl CommentHeader {
+    pub fn read(packet: &[u8]) -> Result<Self, PhonicError> {
+        let mut rest = packet
+            .strip_prefix(&[COMMENT_PACKET_TYPE])
+            .and_then(|rest| rest.strip_prefix(&VORBIS_MARKER))
+            .ok_or(PhonicError::InvalidData)?;
+
+        let vendor = read_string(&mut rest)?;
+        let n_comments = read_u32(&mut rest)?;
+        let mut comments = Vec::new();
+        for _ in 0..n_comments {
+            let comment = read_string(&mut rest)?;
+            let (name, value) = comment.split_once('=').unwrap_or((&comment, ""));
+            comments.push((name.to_owned(), value.to_owned()));
+        }
+
+        Ok(Self { vendor, comments })
+    }
+
+    /// Returns the values of every comment with the given name.
+    pub fn get<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.comments
+            .iter()
+            .filter(move |(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+fn read_u32(rest: &mut &[u8]) -> Result<u32, PhonicError> {
+    let (bytes, tail) = rest
+        .split_first_chunk::<4>()
+        .ok_or(PhonicError::InvalidData)?;
+
+    *rest = tail;
+    Ok(u32::from_le_bytes(*bytes))
+}
+
+fn read_string(rest: &mut &[u8]) -> Result<String, PhonicError> {
+    let len = read_u32(rest)? as usize;
+    let (bytes, tail) = rest.split_at_checked(len).ok_or(PhonicError::InvalidData)?;
+
+    *rest = tail;
+    Ok(String::from_utf8_lossy(bytes).into_owned())
+}
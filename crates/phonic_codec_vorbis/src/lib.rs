@@ -0,0 +1,421 @@
+use std::{any::TypeId, marker::PhantomData};
+use phonic_core::PhonicError;
+use phonic_io_core::{
+    utils::TaggedSignal, CodecTag, DynCodecConstructor, DynStream, Stream, StreamObserver,
+    StreamReader, StreamSeeker, StreamSpec,
+};
+use phonic_signal::{Signal, SignalObserver, SignalReader, SignalSeeker, SignalSpec, SignalWriter};
+
+mod bits;
+mod codebook;
+mod decoder;
+mod floor;
+mod header;
+mod mdct;
+mod residue;
+mod setup;
+mod tag;
+
+pub use decoder::VorbisDecoder;
+pub use header::*;
+pub use setup::{Mode, SetupHeader};
+pub use tag::*;
+
+/// The fewest bytes read from the inner stream at a time while gathering a packet.
+const READ_LEN: usize = 64 * 1024;
+
+/// Decodes an ogg vorbis stream, where each read of the inner stream gives one packet, as the ogg
+/// format passes them on. The first three packets hold the identification, comment and setup
+/// headers, and the inner stream is positioned by granule, which counts the frames decoded up to
+/// the end of the last page read.
+///
+/// Packets don't record how many frames they decode to, as that depends on the packet before, so
+/// decoded frames are only placed in the stream once a page ends and gives their granule. This
+/// drops the frames that the granule of the first page leaves out from the start of the stream,
+/// and the end is cut to the number of frames in the stream spec.
+pub struct VorbisCodec<T, C: CodecTag = VorbisCodecTag> {
+    inner: T,
+    signal_spec: SignalSpec,
+    ident: Option<IdentHeader>,
+    comments: Option<CommentHeader>,
+    decoder: Option<VorbisDecoder>,
+    packet: Vec<u8>,
+    samples: Vec<f32>,
+    range: (usize, usize),
+    /// The frame that follows the decoded samples, once a page has placed them.
+    granule: Option<u64>,
+    /// The granule of the inner stream where decoding started, at the start or after a seek.
+    run_granule: u64,
+    buffered_i: u64,
+    position: u64,
+    _tag: PhantomData<C>,
+}
+
+pub fn fill_vorbis_spec<C>(spec: &mut StreamSpec<C>) -> Result<(), PhonicError>
+where
+    C: CodecTag + TryInto<VorbisCodecTag>,
+{
+    let codec = spec.codec.ok_or(PhonicError::MissingData)?;
+    let _: VorbisCodecTag = codec.try_into().map_err(|_| PhonicError::InvalidData)?;
+
+    if *spec.sample_type.get_or_insert(TypeId::of::<f32>()) != TypeId::of::<f32>() {
+        return Err(PhonicError::Unsupported);
+    }
+
+    if spec
+        .decoded_spec
+        .channels
+        .is_some_and(|channels| !(1..=255).contains(&channels.count()))
+    {
+        return Err(PhonicError::Unsupported);
+    }
+
+    Ok(())
+}
+
+pub fn vorbis_codec_from_stream<S>(stream: S) -> Result<TaggedSignal, PhonicError>
+where
+    S: DynStream + 'static,
+    S::Tag: TryInto<VorbisCodecTag>,
+{
+    Ok(TaggedSignal::F32(Box::new(VorbisCodec::from_stream(
+        stream,
+    )?)))
+}
+
+/// Vorbis streams can only be decoded.
+pub fn vorbis_codec_from_signal<C>(
+    _signal: TaggedSignal,
+    _tag: VorbisCodecTag,
+) -> Result<Box<dyn DynStream<Tag = C>>, PhonicError>
+where
+    C: CodecTag + TryInto<VorbisCodecTag> + 'static,
+{
+    Err(PhonicError::Unsupported)
+}
+
+impl CodecTag for VorbisCodecTag {
+    fn fill_spec(spec: &mut StreamSpec<Self>) -> Result<(), PhonicError> {
+        fill_vorbis_spec(spec)
+    }
+}
+
+impl DynCodecConstructor for VorbisCodecTag {
+    fn from_signal(
+        &self,
+        signal: TaggedSignal,
+    ) -> Result<Box<dyn DynStream<Tag = Self>>, PhonicError> {
+        vorbis_codec_from_signal(signal, *self)
+    }
+
+    fn from_stream<S: DynStream<Tag = Self> + 'static>(
+        stream: S,
+    ) -> Result<TaggedSignal, PhonicError> {
+        vorbis_codec_from_stream(stream)
+    }
+}
+
+impl<T, C: CodecTag> VorbisCodec<T, C> {
+    pub fn from_stream(inner: T) -> Result<Self, PhonicError>
+    where
+        T: Stream<Tag = C>,
+        C: TryInto<VorbisCodecTag>,
+    {
+        let mut stream_spec = *inner.spec();
+        fill_vorbis_spec(&mut stream_spec)?;
+        let signal_spec = stream_spec.decoded_spec.build()?;
+
+        Ok(Self {
+            inner,
+            signal_spec,
+            ident: None,
+            comments: None,
+            decoder: None,
+            packet: Vec::new(),
+            samples: Vec::new(),
+            range: (0, 0),
+            granule: None,
+            run_granule: 0,
+            buffered_i: 0,
+            position: 0,
+            _tag: PhantomData,
+        })
+    }
+
+    /// Returns the identification header, once it has been read from the stream.
+    pub fn ident(&self) -> Option<&IdentHeader> {
+        self.ident.as_ref()
+    }
+
+    /// Returns the comment header, once it has been read from the stream.
+    pub fn comments(&self) -> Option<&CommentHeader> {
+        self.comments.as_ref()
+    }
+
+    pub fn as_inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn n_channels(&self) -> u64 {
+        self.signal_spec.channels.count() as u64
+    }
+
+    fn set_ident(&mut self, ident: IdentHeader) -> Result<(), PhonicError> {
+        if ident.n_channels as u64 != self.n_channels()
+            || ident.sample_rate != self.signal_spec.frame_rate
+        {
+            return Err(PhonicError::SignalMismatch);
+        }
+
+        self.ident = Some(ident);
+        Ok(())
+    }
+
+    /// The frames decoded ahead of a seek target, which is as many as the first packet after the
+    /// seek can cover without decoding to them.
+    fn seek_preroll(&self) -> u64 {
+        self.ident.map_or(0, |ident| ident.block_size_1 as u64 / 2)
+    }
+
+    /// Places the decoded samples in the stream, starting at the frame `start`, which is before
+    /// the start of the stream if the first page leaves out some of them.
+    fn place_samples(&mut self, start: i64) {
+        let n_channels = self.n_channels() as usize;
+        let n_frames = (self.samples.len() / n_channels) as i64;
+
+        let mut end = n_frames;
+        if let Some(total) = self.signal_spec.n_frames {
+            end = end.min(total as i64 - start);
+        }
+
+        let first = (-start).clamp(0, n_frames);
+        let end = end.clamp(first, n_frames);
+        self.range = (first as usize * n_channels, end as usize * n_channels);
+        self.buffered_i = (start + first) as u64 * n_channels as u64;
+        self.granule = Some((start + n_frames).max(0) as u64);
+    }
+
+    /// Where the decoded samples start when the page that ends them can't tell, because it is
+    /// the last page, whose granule cuts the end of the stream instead.
+    fn estimated_start(&self) -> i64 {
+        match self.run_granule {
+            0 => 0,
+            granule => {
+                let lead = self
+                    .decoder
+                    .as_ref()
+                    .map_or(0, |decoder| decoder.lead_frames());
+                (granule + lead as u64) as i64
+            }
+        }
+    }
+}
+
+impl<T, C: CodecTag> Signal for VorbisCodec<T, C> {
+    type Sample = f32;
+
+    fn spec(&self) -> &SignalSpec {
+        &self.signal_spec
+    }
+}
+
+impl<T, C: CodecTag> SignalObserver for VorbisCodec<T, C> {
+    fn position(&self) -> Result<u64, PhonicError> {
+        Ok(self.position)
+    }
+}
+
+impl<T: StreamReader + StreamObserver, C: CodecTag> VorbisCodec<T, C> {
+    /// Reads the next packet of the inner stream, returning false at the end of it. A read that
+    /// fills the whole buffer may have cut the packet short, so the rest of it is read as well.
+    fn read_packet(&mut self) -> Result<bool, PhonicError> {
+        self.packet.clear();
+        loop {
+            let filled = self.packet.len();
+            let len = filled.max(READ_LEN);
+            self.packet.resize(filled + len, 0);
+
+            let result = self.inner.read(&mut self.packet[filled..]);
+            let n = *result.as_ref().unwrap_or(&0);
+            self.packet.truncate(filled + n);
+            result?;
+
+            if n < len {
+                return Ok(!self.packet.is_empty());
+            }
+        }
+    }
+
+    fn read_headers(&mut self) -> Result<(), PhonicError> {
+        if self.ident.is_none() {
+            if !self.read_packet()? {
+                return Err(PhonicError::MissingData);
+            }
+
+            self.set_ident(IdentHeader::read(&self.packet)?)?;
+        }
+
+        if self.comments.is_none() {
+            if !self.read_packet()? {
+                return Err(PhonicError::MissingData);
+            }
+
+            self.comments = Some(CommentHeader::read(&self.packet)?);
+        }
+
+        if let (None, Some(ident)) = (&self.decoder, self.ident) {
+            if !self.read_packet()? {
+                return Err(PhonicError::MissingData);
+            }
+
+            let setup = SetupHeader::read(&self.packet, &ident)?;
+            self.decoder = Some(VorbisDecoder::new(ident, setup));
+        }
+
+        Ok(())
+    }
+
+    /// Decodes a packet, adding its frames to the samples that are waiting to be placed, or
+    /// replacing the samples once they have been placed.
+    fn decode_packet(&mut self) -> Result<(), PhonicError> {
+        let Some(decoder) = &mut self.decoder else {
+            return Err(PhonicError::NotReady);
+        };
+
+        let n_channels = self.signal_spec.channels.count() as usize;
+        if self.granule.is_some() {
+            self.samples.clear();
+        }
+
+        let filled = self.samples.len();
+        self.samples
+            .resize(filled + decoder.max_packet_frames() * n_channels, 0.0);
+        let n_frames = decoder.decode(&self.packet, &mut self.samples[filled..])?;
+        self.samples.truncate(filled + n_frames * n_channels);
+
+        let inner_granule = self.inner.position()?;
+        match self.granule {
+            Some(granule) => self.place_samples(granule as i64),
+            None if inner_granule != self.run_granule => {
+                let start = match self.signal_spec.n_frames == Some(inner_granule) {
+                    true => self.estimated_start(),
+                    false => {
+                        let n_frames = self.samples.len() / n_channels;
+                        inner_granule as i64 - n_frames as i64
+                    }
+                };
+
+                self.place_samples(start);
+            }
+            None => (),
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: StreamReader + StreamObserver, C: CodecTag> SignalReader for VorbisCodec<T, C> {
+    fn read(&mut self, buf: &mut [Self::Sample]) -> Result<usize, PhonicError> {
+        self.read_headers()?;
+
+        loop {
+            // drop what comes before the position, which is the preroll after a seek
+            if self.granule.is_some() {
+                let pending = (self.range.1 - self.range.0) as u64;
+                let skip = self.position.saturating_sub(self.buffered_i).min(pending);
+                self.range.0 += skip as usize;
+                self.buffered_i += skip;
+
+                if self.range.0 < self.range.1 {
+                    break;
+                }
+            }
+
+            if !self.read_packet()? {
+                // a damaged stream can end before a page places the last samples
+                if self.granule.is_some() || self.samples.is_empty() {
+                    return Ok(0);
+                }
+
+                let start = self.estimated_start();
+                self.place_samples(start);
+                continue;
+            }
+
+            self.decode_packet()?;
+        }
+
+        let pending = &self.samples[self.range.0..self.range.1];
+        let n = buf.len().min(pending.len());
+        buf[..n].copy_from_slice(&pending[..n]);
+        self.range.0 += n;
+        self.buffered_i += n as u64;
+        self.position += n as u64;
+
+        Ok(n)
+    }
+}
+
+impl<T, C: CodecTag> SignalWriter for VorbisCodec<T, C> {
+    fn write(&mut self, _buf: &[Self::Sample]) -> Result<usize, PhonicError> {
+        Err(PhonicError::Unsupported)
+    }
+
+    fn flush(&mut self) -> Result<(), PhonicError> {
+        Ok(())
+    }
+}
+
+impl<T, C> SignalSeeker for VorbisCodec<T, C>
+where
+    T: StreamReader + StreamObserver + StreamSeeker,
+    C: CodecTag,
+{
+    fn seek(&mut self, offset: i64) -> Result<(), PhonicError> {
+        let position = self
+            .position
+            .checked_add_signed(offset)
+            .ok_or(PhonicError::InvalidData)?;
+
+        self.read_headers()?;
+        let n_channels = self.n_channels();
+        let target = position / n_channels;
+        let start = target.saturating_sub(self.seek_preroll());
+
+        // decoding carries on from where it is if that is no further from the target
+        if position >= self.position && self.granule.is_some_and(|granule| start <= granule) {
+            self.position = position;
+            return Ok(());
+        }
+
+        let inner_position = self.inner.position()?;
+        self.inner.seek(start as i64 - inner_position as i64)?;
+        let mut granule = self.inner.position()?;
+        if granule > target {
+            return Err(PhonicError::InvalidData);
+        }
+
+        // decoding starts a page earlier, so that the page landed on is the first to end and its
+        // granule places the frames, since the last page of the stream can't if it comes first
+        if granule > 0 {
+            self.inner.seek(-1)?;
+            granule = self.inner.position()?;
+        }
+
+        if let Some(decoder) = &mut self.decoder {
+            decoder.reset();
+        }
+
+        self.samples.clear();
+        self.range = (0, 0);
+        self.granule = None;
+        self.run_granule = granule;
+        self.position = position;
+
+        Ok(())
+    }
+}
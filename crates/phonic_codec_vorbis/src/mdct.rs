@@ -0,0 +1,125 @@
+use std::f64::consts::PI;
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Complex {
+    re: f32,
+    im: f32,
+}
+
+impl Complex {
+    fn from_angle(angle: f64) -> Self {
+        Self {
+            re: angle.cos() as f32,
+            im: angle.sin() as f32,
+        }
+    }
+
+    fn mul(self, other: Self) -> Self {
+        Self {
+            re: self.re * other.re - self.im * other.im,
+            im: self.re * other.im + self.im * other.re,
+        }
+    }
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            re: self.re + other.re,
+            im: self.im + other.im,
+        }
+    }
+
+    fn sub(self, other: Self) -> Self {
+        Self {
+            re: self.re - other.re,
+            im: self.im - other.im,
+        }
+    }
+}
+
+/// The inverse MDCT of one block size, which turns the `n / 2` lines of a spectrum into `n`
+/// samples. It is computed as a DCT-IV of `n / 2` points, folded out to the whole block, with
+/// the DCT-IV done by a complex FFT of `n / 8` points.
+pub(crate) struct Imdct {
+    n: usize,
+    pre_twiddles: Vec<Complex>,
+    post_twiddles: Vec<Complex>,
+    fft_twiddles: Vec<Complex>,
+    bit_reversed: Vec<usize>,
+    buf: Vec<Complex>,
+    dct: Vec<f32>,
+}
+
+impl Imdct {
+    pub fn new(n: usize) -> Self {
+        let m = n / 2;
+        let fft_len = m / 2;
+        let index_bits = fft_len.trailing_zeros();
+
+        Self {
+            n,
+            pre_twiddles: (0..fft_len)
+                .map(|k| Complex::from_angle(-PI * (k as f64 + 0.25) / m as f64))
+                .collect(),
+            post_twiddles: (0..fft_len)
+                .map(|k| Complex::from_angle(-PI * k as f64 / m as f64))
+                .collect(),
+            fft_twiddles: (0..fft_len / 2)
+                .map(|k| Complex::from_angle(-2.0 * PI * k as f64 / fft_len as f64))
+                .collect(),
+            bit_reversed: (0..fft_len)
+                .map(|i| i.reverse_bits() >> (usize::BITS - index_bits))
+                .collect(),
+            buf: vec![Complex::default(); fft_len],
+            dct: vec![0.0; m],
+        }
+    }
+
+    /// Transforms the `n / 2` lines of `spectrum` into the `n` samples of `output`.
+    pub fn transform(&mut self, spectrum: &[f32], output: &mut [f32]) {
+        let n = self.n;
+        let m = n / 2;
+        let fft_len = m / 2;
+
+        // pair up the even lines with the odd ones from the top, in bit reversed order for the fft
+        for k in 0..fft_len {
+            let value = Complex {
+                re: spectrum[2 * k],
+                im: spectrum[m - 1 - 2 * k],
+            };
+
+            self.buf[self.bit_reversed[k]] = value.mul(self.pre_twiddles[k]);
+        }
+
+        let mut len = 2;
+        while len <= fft_len {
+            let stride = fft_len / len;
+            for start in (0..fft_len).step_by(len) {
+                for i in 0..len / 2 {
+                    let even = self.buf[start + i];
+                    let odd = self.buf[start + i + len / 2].mul(self.fft_twiddles[i * stride]);
+                    self.buf[start + i] = even.add(odd);
+                    self.buf[start + i + len / 2] = even.sub(odd);
+                }
+            }
+
+            len *= 2;
+        }
+
+        for k in 0..fft_len {
+            let value = self.buf[k].mul(self.post_twiddles[k]);
+            self.dct[2 * k] = value.re;
+            self.dct[m - 1 - 2 * k] = -value.im;
+        }
+
+        // the block is the DCT-IV output and its mirror images, with the signs of the folding
+        let n4 = n / 4;
+        let n3_4 = n - n4;
+        for (i, sample) in output[..n].iter_mut().enumerate() {
+            *sample = match i {
+                i if i < n4 => self.dct[i + n4],
+                i if i < n3_4 => -self.dct[n3_4 - i - 1],
+                i => -self.dct[i - n3_4],
+            };
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use crate::{bits::BitReader, codebook::Codebook};
+use phonic_core::PhonicError;
+
+/// A residue of the setup header, which codes the fine structure of the spectra of the channels
+/// of a submap in partitions, each classified to pick the books it is coded with in up to eight
+/// passes.
+pub(crate) struct Residue {
+    /// Type 0 interleaves the values of each partition, type 1 codes them in order, and type 2
+    /// codes the channels interleaved into one vector as type 1 does.
+    residue_type: u16,
+    begin: usize,
+    end: usize,
+    partition_size: usize,
+    n_classifications: u32,
+    classbook: usize,
+    books: Vec<[Option<usize>; 8]>,
+}
+
+impl Residue {
+    pub fn read(bits: &mut BitReader, codebooks: &[Codebook]) -> Result<Self, PhonicError> {
+        let residue_type = bits.field(16)? as u16;
+        if residue_type > 2 {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let begin = bits.field(24)? as usize;
+        let end = bits.field(24)? as usize;
+        let partition_size = bits.field(24)? as usize + 1;
+        let n_classifications = bits.field(6)? + 1;
+        let classbook = bits.field(8)? as usize;
+
+        let mut cascades = Vec::with_capacity(n_classifications as usize);
+        for _ in 0..n_classifications {
+            let low_bits = bits.field(3)?;
+            let high_bits = match bits.flag()? {
+                true => bits.field(5)?,
+                false => 0,
+            };
+
+            cascades.push((high_bits << 3) | low_bits);
+        }
+
+        let mut books = Vec::with_capacity(cascades.len());
+        for cascade in cascades {
+            let mut passes = [None; 8];
+            for (pass, book) in passes.iter_mut().enumerate() {
+                if cascade & (1 << pass) != 0 {
+                    let book_i = bits.field(8)? as usize;
+                    match codebooks.get(book_i) {
+                        Some(codebook) if codebook.has_lookup() && codebook.dimensions > 0 => {
+                            *book = Some(book_i)
+                        }
+                        _ => return Err(PhonicError::InvalidData),
+                    }
+                }
+            }
+
+            books.push(passes);
+        }
+
+        if codebooks
+            .get(classbook)
+            .is_none_or(|book| book.dimensions == 0)
+        {
+            return Err(PhonicError::InvalidData);
+        }
+
+        Ok(Self {
+            residue_type,
+            begin,
+            end,
+            partition_size,
+            n_classifications,
+            classbook,
+            books,
+        })
+    }
+
+    /// Decodes the spectra of the channels of a submap, each `n` values long, into `spectra`,
+    /// which start out zeroed. Spectra marked in `no_residue` are left as they are, except with
+    /// type 2, which decodes every channel unless none of them have residue. An end of packet
+    /// stops decoding, leaving the values decoded so far.
+    pub fn decode(
+        &self,
+        bits: &mut BitReader,
+        codebooks: &[Codebook],
+        spectra: &mut [&mut [f32]],
+        no_residue: &[bool],
+        n: usize,
+        scratch: &mut Vec<f32>,
+    ) {
+        if self.residue_type != 2 {
+            let mut vectors: Vec<&mut [f32]> = spectra
+                .iter_mut()
+                .zip(no_residue)
+                .filter(|(_, &no_residue)| !no_residue)
+                .map(|(spectrum, _)| &mut spectrum[..n])
+                .collect();
+
+            self.decode_vectors(bits, codebooks, &mut vectors, n);
+            return;
+        }
+
+        if no_residue.iter().all(|&no_residue| no_residue) {
+            return;
+        }
+
+        let n_channels = spectra.len();
+        scratch.clear();
+        scratch.resize(n * n_channels, 0.0);
+        self.decode_vectors(bits, codebooks, &mut [&mut scratch[..]], n * n_channels);
+
+        for (ch, spectrum) in spectra.iter_mut().enumerate() {
+            for (value, interleaved) in spectrum[..n]
+                .iter_mut()
+                .zip(scratch[ch..].iter().step_by(n_channels))
+            {
+                *value = *interleaved;
+            }
+        }
+    }
+
+    fn decode_vectors(
+        &self,
+        bits: &mut BitReader,
+        codebooks: &[Codebook],
+        vectors: &mut [&mut [f32]],
+        n: usize,
+    ) {
+        let begin = self.begin.min(n);
+        let end = self.end.min(n);
+        let n_partitions = end.saturating_sub(begin) / self.partition_size;
+        if n_partitions == 0 || vectors.is_empty() {
+            return;
+        }
+
+        // each codeword of the classbook classifies several partitions, and the last one may run
+        // past the end
+        let classbook = &codebooks[self.classbook];
+        let per_codeword = classbook.dimensions;
+        let stride = n_partitions + per_codeword;
+        let mut classes = vec![0; vectors.len() * stride];
+        let mut entry = Vec::new();
+
+        for pass in 0..8 {
+            let mut partition_i = 0;
+            while partition_i < n_partitions {
+                if pass == 0 {
+                    for ch in 0..vectors.len() {
+                        let Some(mut value) = classbook.read_entry(bits) else {
+                            return;
+                        };
+
+                        for i in (0..per_codeword).rev() {
+                            classes[ch * stride + partition_i + i] = value % self.n_classifications;
+                            value /= self.n_classifications;
+                        }
+                    }
+                }
+
+                for _ in 0..per_codeword {
+                    if partition_i >= n_partitions {
+                        break;
+                    }
+
+                    let offset = begin + partition_i * self.partition_size;
+                    for (ch, vector) in vectors.iter_mut().enumerate() {
+                        let class = classes[ch * stride + partition_i] as usize;
+                        let Some(book) = self.books[class][pass] else {
+                            continue;
+                        };
+
+                        let codebook = &codebooks[book];
+                        entry.resize(codebook.dimensions, 0.0);
+                        let read =
+                            self.read_partition(bits, codebook, &mut entry, &mut vector[offset..]);
+                        if read.is_none() {
+                            return;
+                        }
+                    }
+
+                    partition_i += 1;
+                }
+            }
+        }
+    }
+
+    /// Adds the values of one partition to the start of `vector`, which runs on to the end of
+    /// the whole vector.
+    fn read_partition(
+        &self,
+        bits: &mut BitReader,
+        codebook: &Codebook,
+        entry: &mut [f32],
+        vector: &mut [f32],
+    ) -> Option<()> {
+        let dimensions = codebook.dimensions;
+        if self.residue_type == 0 {
+            let step = self.partition_size / dimensions;
+            for i in 0..step {
+                codebook.read_vector(bits, entry)?;
+                for (j, value) in entry.iter().enumerate() {
+                    vector[i + j * step] += value;
+                }
+            }
+
+            return Some(());
+        }
+
+        let mut i = 0;
+        while i < self.partition_size {
+            codebook.read_vector(bits, entry)?;
+            let Some(values) = vector.get_mut(i..i + dimensions) else {
+                break;
+            };
+
+            for (value, add) in values.iter_mut().zip(entry.iter()) {
+                *value += add;
+            }
+
+            i += dimensions;
+        }
+
+        Some(())
+    }
+}
@@ -0,0 +1,182 @@
+use crate::{
+    bits::{ilog, BitReader},
+    codebook::Codebook,
+    floor::Floor,
+    header::{IdentHeader, VORBIS_MARKER},
+    residue::Residue,
+};
+use phonic_core::PhonicError;
+
+const SETUP_PACKET_TYPE: u8 = 5;
+
+/// The setup header, which is the third packet of a vorbis stream. It holds the codebooks and the
+/// configurations of floors, residues, channel mappings and modes that audio packets are decoded
+/// with.
+pub struct SetupHeader {
+    pub(crate) codebooks: Vec<Codebook>,
+    pub(crate) floors: Vec<Floor>,
+    pub(crate) residues: Vec<Residue>,
+    pub(crate) mappings: Vec<Mapping>,
+    pub(crate) modes: Vec<Mode>,
+}
+
+/// A mode, which each audio packet picks, giving its block size and channel mapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mode {
+    pub long_block: bool,
+    pub mapping: usize,
+}
+
+/// How the channels of a packet are coded. Channels are split between submaps, which each pick a
+/// floor and residue, and pairs of channels can be coupled as a magnitude and an angle.
+pub(crate) struct Mapping {
+    pub coupling: Vec<(usize, usize)>,
+    /// The submap of each channel.
+    pub mux: Vec<usize>,
+    /// The floor and residue of each submap.
+    pub submaps: Vec<(usize, usize)>,
+}
+
+impl SetupHeader {
+    /// Reads the setup header, whose layout depends on the number of channels and the block
+    /// sizes of the identification header.
+    pub fn read(packet: &[u8], ident: &IdentHeader) -> Result<Self, PhonicError> {
+        let body = packet
+            .strip_prefix(&[SETUP_PACKET_TYPE])
+            .and_then(|rest| rest.strip_prefix(&VORBIS_MARKER))
+            .ok_or(PhonicError::InvalidData)?;
+
+        let mut bits = BitReader::new(body);
+        let n_codebooks = bits.field(8)? + 1;
+        let codebooks = (0..n_codebooks)
+            .map(|_| Codebook::read(&mut bits))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // time domain transforms are placeholders that have to be 0
+        let n_times = bits.field(6)? + 1;
+        for _ in 0..n_times {
+            if bits.field(16)? != 0 {
+                return Err(PhonicError::InvalidData);
+            }
+        }
+
+        let block_sizes = [ident.block_size_0 as usize, ident.block_size_1 as usize];
+        let n_floors = bits.field(6)? + 1;
+        let floors = (0..n_floors)
+            .map(|_| Floor::read(&mut bits, &codebooks, block_sizes))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let n_residues = bits.field(6)? + 1;
+        let residues = (0..n_residues)
+            .map(|_| Residue::read(&mut bits, &codebooks))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let n_mappings = bits.field(6)? + 1;
+        let mappings = (0..n_mappings)
+            .map(|_| Mapping::read(&mut bits, ident.n_channels as usize, &floors, &residues))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let n_modes = bits.field(6)? + 1;
+        let mut modes = Vec::with_capacity(n_modes as usize);
+        for _ in 0..n_modes {
+            let long_block = bits.flag()?;
+            let window_type = bits.field(16)?;
+            let transform_type = bits.field(16)?;
+            let mapping = bits.field(8)? as usize;
+            if window_type != 0 || transform_type != 0 || mapping >= mappings.len() {
+                return Err(PhonicError::InvalidData);
+            }
+
+            modes.push(Mode {
+                long_block,
+                mapping,
+            });
+        }
+
+        if !bits.flag()? {
+            return Err(PhonicError::InvalidData);
+        }
+
+        Ok(Self {
+            codebooks,
+            floors,
+            residues,
+            mappings,
+            modes,
+        })
+    }
+
+    pub fn modes(&self) -> &[Mode] {
+        &self.modes
+    }
+
+    pub fn n_codebooks(&self) -> usize {
+        self.codebooks.len()
+    }
+}
+
+impl Mapping {
+    fn read(
+        bits: &mut BitReader,
+        n_channels: usize,
+        floors: &[Floor],
+        residues: &[Residue],
+    ) -> Result<Self, PhonicError> {
+        if bits.field(16)? != 0 {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let n_submaps = match bits.flag()? {
+            true => bits.field(4)? as usize + 1,
+            false => 1,
+        };
+
+        let mut coupling = Vec::new();
+        if bits.flag()? {
+            let channel_bits = ilog(n_channels as u32 - 1);
+            let n_steps = bits.field(8)? + 1;
+            for _ in 0..n_steps {
+                let magnitude = bits.field(channel_bits)? as usize;
+                let angle = bits.field(channel_bits)? as usize;
+                if magnitude == angle || magnitude >= n_channels || angle >= n_channels {
+                    return Err(PhonicError::InvalidData);
+                }
+
+                coupling.push((magnitude, angle));
+            }
+        }
+
+        if bits.field(2)? != 0 {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let mut mux = vec![0; n_channels];
+        if n_submaps > 1 {
+            for submap in mux.iter_mut() {
+                *submap = bits.field(4)? as usize;
+                if *submap >= n_submaps {
+                    return Err(PhonicError::InvalidData);
+                }
+            }
+        }
+
+        let mut submaps = Vec::with_capacity(n_submaps);
+        for _ in 0..n_submaps {
+            // an unused time configuration
+            bits.field(8)?;
+            let floor = bits.field(8)? as usize;
+            let residue = bits.field(8)? as usize;
+            if floor >= floors.len() || residue >= residues.len() {
+                return Err(PhonicError::InvalidData);
+            }
+
+            submaps.push((floor, residue));
+        }
+
+        Ok(Self {
+            coupling,
+            mux,
+            submaps,
+        })
+    }
+}
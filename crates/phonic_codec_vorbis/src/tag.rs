@@ -0,0 +1,11 @@
+/// Identifies a vorbis stream. Vorbis streams carry their own settings in the identification and
+/// setup headers, so there is nothing to choose between, and every stream decodes to `f32`
+/// samples at the frame rate of its identification header.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+pub struct VorbisCodecTag;
+
+impl VorbisCodecTag {
+    pub fn new() -> Self {
+        Self
+    }
+}
@@ -0,0 +1,423 @@
+use phonic_codec_vorbis::{
+    fill_vorbis_spec, IdentHeader, SetupHeader, VorbisCodec, VorbisCodecTag, VorbisDecoder,
+};
+use phonic_core::PhonicError;
+use phonic_io_core::{
+    test_utils::{BitOrder, BitWriter},
+    Stream, StreamObserver, StreamReader, StreamSeeker, StreamSpec,
+};
+use phonic_signal::{Signal, SignalObserver, SignalReader, SignalSeeker, SignalSpecBuilder};
+
+/// Which of the audio packets of the test streams are long blocks.
+const LONG_BLOCKS: [bool; 14] = [
+    false, false, true, true, false, true, false, false, true, true, true, false, true, false,
+];
+
+/// The frames that the test streams decode to.
+const N_FRAMES: u64 = 7936;
+
+/// Samples of the test streams as decoded by lewton, for mono and stereo.
+const MONO_REFERENCE: [(usize, f32); 4] = [
+    (53, -1.181696),
+    (3344, -0.105716),
+    (4021, -2.323718),
+    (5435, 0.051270),
+];
+
+const STEREO_REFERENCE: [(usize, f32); 5] = [
+    (287, -0.065715),
+    (2703, -0.323526),
+    (8482, -0.050779),
+    (10634, 0.656281),
+    (13279, -2.054627),
+];
+
+fn ident_packet(n_channels: u8, block_sizes: u8) -> Vec<u8> {
+    let mut packet = b"\x01vorbis".to_vec();
+    packet.extend_from_slice(&0u32.to_le_bytes());
+    packet.push(n_channels);
+    packet.extend_from_slice(&44_100u32.to_le_bytes());
+    packet.extend_from_slice(&0i32.to_le_bytes());
+    packet.extend_from_slice(&128_000i32.to_le_bytes());
+    packet.extend_from_slice(&(-1i32).to_le_bytes());
+    packet.extend_from_slice(&[block_sizes, 1]);
+    packet
+}
+
+fn comment_packet(vendor: &str, comments: &[&str]) -> Vec<u8> {
+    let mut packet = b"\x03vorbis".to_vec();
+    packet.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    packet.extend_from_slice(vendor.as_bytes());
+    packet.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+    for comment in comments {
+        packet.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+        packet.extend_from_slice(comment.as_bytes());
+    }
+
+    packet.push(1);
+    packet
+}
+
+/// A setup with a residue book of the values -1 and 1, a floor book, one floor and residue, and
+/// a short and a long mode. Stereo streams couple their channels.
+fn setup_packet(n_channels: u8) -> Vec<u8> {
+    let mut bits = BitWriter::new(BitOrder::LsbFirst);
+    bits.write(5, 8);
+    for byte in b"vorbis" {
+        bits.write(*byte as u32, 8);
+    }
+
+    bits.write(1, 8);
+    bits.write(0x564342, 24);
+    bits.write(1, 16);
+    bits.write(2, 24);
+    bits.write(0, 2);
+    bits.write(0, 5);
+    bits.write(0, 5);
+    bits.write(1, 4);
+    bits.write(0x80000000 | 768 << 21 | 1 << 20, 32);
+    bits.write(769 << 21 | 1 << 20, 32);
+    bits.write(0, 5);
+    bits.write(0b10, 2);
+
+    bits.write(0x564342, 24);
+    bits.write(1, 16);
+    bits.write(4, 24);
+    bits.write(0, 2);
+    for _ in 0..4 {
+        bits.write(1, 5);
+    }
+    bits.write(0, 4);
+
+    bits.write(0, 6);
+    bits.write(0, 16);
+
+    bits.write(0, 6);
+    bits.write(1, 16);
+    bits.write(1, 5);
+    bits.write(0, 4);
+    bits.write(1, 3);
+    bits.write(0, 2);
+    bits.write(2, 8);
+    bits.write(1, 2);
+    bits.write(10, 4);
+    bits.write(200, 10);
+    bits.write(500, 10);
+
+    bits.write(0, 6);
+    bits.write(1, 16);
+    bits.write(0, 24);
+    bits.write(1024, 24);
+    bits.write(31, 24);
+    bits.write(1, 6);
+    bits.write(0, 8);
+    bits.write(0, 4);
+    bits.write(1, 4);
+    bits.write(0, 8);
+
+    bits.write(0, 6);
+    bits.write(0, 16);
+    bits.write(0, 1);
+    match n_channels {
+        2 => {
+            bits.write(1, 1);
+            bits.write(0, 8);
+            bits.write(0b10, 2);
+        }
+        _ => bits.write(0, 1),
+    }
+    bits.write(0, 2);
+    bits.write(0, 24);
+
+    bits.write(1, 6);
+    for long_block in [0, 1] {
+        bits.write(long_block, 1);
+        bits.write(0, 32);
+        bits.write(0, 8);
+    }
+
+    bits.write(1, 1);
+    bits.into_bytes()
+}
+
+/// A generator of the payload bits of audio packets.
+struct XorShift(u32);
+
+impl XorShift {
+    fn next(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0
+    }
+}
+
+/// Audio packets of short and long blocks, as `LONG_BLOCKS` has them, which have random bits
+/// after their mode and window flags.
+fn audio_packets(n_channels: u8) -> Vec<Vec<u8>> {
+    let mut rng = XorShift(match n_channels {
+        1 => 7,
+        _ => 8,
+    });
+
+    let mut packets = Vec::new();
+    for (i, &long_block) in LONG_BLOCKS.iter().enumerate() {
+        let mut bits = BitWriter::new(BitOrder::LsbFirst);
+        bits.write(0, 1);
+        bits.write(long_block as u32, 1);
+        if long_block {
+            bits.write(LONG_BLOCKS[i - 1] as u32, 1);
+            bits.write(LONG_BLOCKS.get(i + 1).copied().unwrap_or(false) as u32, 1);
+        }
+
+        for _ in 0..40 + rng.next() % 200 {
+            bits.write(rng.next(), 8);
+        }
+
+        packets.push(bits.into_bytes());
+    }
+
+    packets
+}
+
+fn decoder(n_channels: u8) -> VorbisDecoder {
+    let ident = IdentHeader::read(&ident_packet(n_channels, 0xb8)).unwrap();
+    let setup = SetupHeader::read(&setup_packet(n_channels), &ident).unwrap();
+    VorbisDecoder::new(ident, setup)
+}
+
+fn decode_all(n_channels: u8) -> Vec<f32> {
+    let mut decoder = decoder(n_channels);
+    let mut pcm = vec![0.0; 1024 * n_channels as usize];
+    let mut all = Vec::new();
+    for packet in audio_packets(n_channels) {
+        let n = decoder.decode(&packet, &mut pcm).unwrap();
+        all.extend_from_slice(&pcm[..n * n_channels as usize]);
+    }
+
+    all
+}
+
+fn read_all(codec: &mut impl SignalReader<Sample = f32>) -> Vec<f32> {
+    let mut all = Vec::new();
+    let mut buf = [0.0; 700];
+    loop {
+        match codec.read(&mut buf).unwrap() {
+            0 => return all,
+            n => all.extend_from_slice(&buf[..n]),
+        }
+    }
+}
+
+/// A stream that gives one packet per read and is positioned by granule, the way the ogg format
+/// passes vorbis packets on, with a page for every packet.
+struct PacketStream {
+    spec: StreamSpec<VorbisCodecTag>,
+    packets: Vec<Vec<u8>>,
+    granules: Vec<u64>,
+    i: usize,
+}
+
+impl PacketStream {
+    /// Holds a test stream whose granules leave out the first `trim_start` frames, and whose
+    /// last granule cuts the stream to `n_frames`.
+    fn new(n_channels: u16, coded_channels: u8, trim_start: u64, n_frames: u64) -> Self {
+        let decoded_spec = SignalSpecBuilder::new()
+            .with_channels(n_channels)
+            .with_frame_rate(44_100)
+            .with_n_frames(n_frames);
+
+        let spec = StreamSpec::new()
+            .with_codec(VorbisCodecTag::new())
+            .with_decoded_spec(decoded_spec);
+
+        let mut packets = vec![
+            ident_packet(coded_channels, 0xb8),
+            comment_packet("phonic", &["TITLE=Level 1"]),
+            setup_packet(coded_channels),
+        ];
+        let mut granules = vec![0, 0, 0];
+
+        let mut decoded = 0u64;
+        let block_size = |long_block| match long_block {
+            true => 2048,
+            false => 256,
+        };
+        for (i, packet) in audio_packets(coded_channels).into_iter().enumerate() {
+            if i > 0 {
+                decoded += block_size(LONG_BLOCKS[i - 1]) / 4 + block_size(LONG_BLOCKS[i]) / 4;
+            }
+
+            packets.push(packet);
+            granules.push(decoded.saturating_sub(trim_start));
+        }
+
+        *granules.last_mut().unwrap() = n_frames;
+        Self {
+            spec,
+            packets,
+            granules,
+            i: 0,
+        }
+    }
+}
+
+impl Stream for PacketStream {
+    type Tag = VorbisCodecTag;
+
+    fn spec(&self) -> &StreamSpec<Self::Tag> {
+        &self.spec
+    }
+}
+
+impl StreamObserver for PacketStream {
+    fn position(&self) -> Result<u64, PhonicError> {
+        Ok(self.i.checked_sub(1).map_or(0, |i| self.granules[i]))
+    }
+}
+
+impl StreamReader for PacketStream {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, PhonicError> {
+        let Some(packet) = self.packets.get(self.i) else {
+            return Ok(0);
+        };
+
+        buf[..packet.len()].copy_from_slice(packet);
+        self.i += 1;
+        Ok(packet.len())
+    }
+}
+
+impl StreamSeeker for PacketStream {
+    fn seek(&mut self, offset: i64) -> Result<(), PhonicError> {
+        let granule = self
+            .position()?
+            .checked_add_signed(offset)
+            .ok_or(PhonicError::InvalidData)?;
+
+        // resume after the last packet that ends at or before the granule, or from the start
+        self.i = match granule {
+            0 => 3,
+            _ => 3 + self.granules[3..].iter().filter(|&&g| g <= granule).count(),
+        };
+        Ok(())
+    }
+}
+
+#[test]
+fn decoder_matches_reference() {
+    for (n_channels, reference) in [(1, &MONO_REFERENCE[..]), (2, &STEREO_REFERENCE[..])] {
+        let decoded = decode_all(n_channels);
+        assert_eq!(decoded.len(), N_FRAMES as usize * n_channels as usize);
+        for &(i, expected) in reference {
+            assert!((decoded[i] - expected).abs() < 1e-5);
+        }
+    }
+}
+
+#[test]
+fn decoder_starts_over_after_reset() {
+    let packets = audio_packets(1);
+    let mut decoder = decoder(1);
+    let mut pcm = vec![0.0; 1024];
+
+    // header packets and empty packets are skipped
+    assert_eq!(decoder.decode(&ident_packet(1, 0xb8), &mut pcm), Ok(0));
+    assert_eq!(decoder.decode(&[], &mut pcm), Ok(0));
+
+    assert_eq!(decoder.decode(&packets[0], &mut pcm), Ok(0));
+    assert_eq!(decoder.lead_frames(), 128);
+    assert_eq!(
+        decoder.decode(&packets[1], &mut pcm[..100]),
+        Err(PhonicError::InvalidData)
+    );
+
+    decoder.reset();
+    assert_eq!(decoder.decode(&packets[2], &mut pcm), Ok(0));
+    assert_eq!(decoder.lead_frames(), 64 + 512);
+    assert_eq!(decoder.decode(&packets[3], &mut pcm), Ok(1024));
+    assert_eq!(pcm, decode_all(1)[704..1728]);
+}
+
+#[test]
+fn fills_spec() {
+    let mut spec = StreamSpec::new().with_codec(VorbisCodecTag::new());
+    fill_vorbis_spec(&mut spec).unwrap();
+    assert_eq!(spec.sample_type, Some(std::any::TypeId::of::<f32>()));
+
+    let mut spec = StreamSpec::new()
+        .with_codec(VorbisCodecTag::new())
+        .with_sample_type::<i16>();
+
+    assert_eq!(fill_vorbis_spec(&mut spec), Err(PhonicError::Unsupported));
+}
+
+#[test]
+fn codec_reads_headers() {
+    let stream = PacketStream::new(2, 2, 0, N_FRAMES);
+    let mut codec = VorbisCodec::from_stream(stream).unwrap();
+    assert!(codec.ident().is_none());
+
+    let decoded = read_all(&mut codec);
+    assert_eq!(decoded, decode_all(2));
+    assert_eq!(codec.position().unwrap(), N_FRAMES * 2);
+
+    let ident = codec.ident().unwrap();
+    assert_eq!((ident.block_size_0, ident.block_size_1), (256, 2048));
+    assert_eq!(
+        codec.comments().unwrap().get("title").next(),
+        Some("Level 1")
+    );
+}
+
+#[test]
+fn codec_trims_start_and_end() {
+    let n_frames = N_FRAMES - 300 - 500;
+    let stream = PacketStream::new(2, 2, 300, n_frames);
+    let mut codec = VorbisCodec::from_stream(stream).unwrap();
+
+    let decoded = read_all(&mut codec);
+    let expected = decode_all(2);
+    assert_eq!(decoded, expected[600..][..n_frames as usize * 2]);
+    assert_eq!(codec.position().unwrap(), n_frames * 2);
+}
+
+#[test]
+fn codec_seeks() {
+    let stream = PacketStream::new(2, 2, 0, N_FRAMES);
+    let mut codec = VorbisCodec::from_stream(stream).unwrap();
+    let expected = read_all(&mut codec);
+
+    // targets within the preroll of the start decode from the start, and the rest from the
+    // preroll ahead of them, which both match the whole stream exactly
+    let mut buf = [0.0; 500];
+    for target in [0, 6000, 7000, 200, 12000, 3000] {
+        codec
+            .seek(target as i64 - codec.position().unwrap() as i64)
+            .unwrap();
+        codec.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, expected[target..target + 500]);
+        assert_eq!(codec.position().unwrap(), target as u64 + 500);
+    }
+
+    codec.seek(9000 - codec.position().unwrap() as i64).unwrap();
+    let rest = read_all(&mut codec);
+    assert_eq!(rest, expected[9000..]);
+    assert_eq!(codec.position().unwrap(), N_FRAMES * 2);
+    assert_eq!(codec.as_inner().position().unwrap(), N_FRAMES);
+
+    assert!(codec.seek(-(N_FRAMES as i64 * 2) - 1).is_err());
+}
+
+#[test]
+fn codec_rejects_mismatched_channels() {
+    let stream = PacketStream::new(1, 2, 0, N_FRAMES);
+    let mut codec = VorbisCodec::from_stream(stream).unwrap();
+
+    assert_eq!(
+        codec.read(&mut [0.0; 100]),
+        Err(PhonicError::SignalMismatch)
+    );
+    assert!(codec.ident().is_none());
+    assert_eq!(codec.spec().frame_rate, 44_100);
+}
@@ -0,0 +1,255 @@
+use phonic_codec_vorbis::{CommentHeader, IdentHeader, Mode, SetupHeader};
+use phonic_core::PhonicError;
+use phonic_io_core::test_utils::{BitOrder, BitWriter};
+
+fn ident_packet(n_channels: u8, block_sizes: u8) -> Vec<u8> {
+    let mut packet = b"\x01vorbis".to_vec();
+    packet.extend_from_slice(&0u32.to_le_bytes());
+    packet.push(n_channels);
+    packet.extend_from_slice(&44_100u32.to_le_bytes());
+    packet.extend_from_slice(&0i32.to_le_bytes());
+    packet.extend_from_slice(&128_000i32.to_le_bytes());
+    packet.extend_from_slice(&(-1i32).to_le_bytes());
+    packet.extend_from_slice(&[block_sizes, 1]);
+    packet
+}
+
+fn comment_packet(vendor: &str, comments: &[&str]) -> Vec<u8> {
+    let mut packet = b"\x03vorbis".to_vec();
+    packet.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    packet.extend_from_slice(vendor.as_bytes());
+    packet.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+    for comment in comments {
+        packet.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+        packet.extend_from_slice(comment.as_bytes());
+    }
+
+    packet.push(1);
+    packet
+}
+
+/// A setup with a residue book of the values -1 and 1, a floor book, one floor and residue, and
+/// a short and a long mode. Stereo streams couple their channels.
+fn setup_packet(n_channels: u8) -> Vec<u8> {
+    let mut bits = BitWriter::new(BitOrder::LsbFirst);
+    bits.write(5, 8);
+    for byte in b"vorbis" {
+        bits.write(*byte as u32, 8);
+    }
+
+    bits.write(1, 8);
+    bits.write(0x564342, 24);
+    bits.write(1, 16);
+    bits.write(2, 24);
+    bits.write(0, 2);
+    bits.write(0, 5);
+    bits.write(0, 5);
+    bits.write(1, 4);
+    bits.write(0x80000000 | 768 << 21 | 1 << 20, 32);
+    bits.write(769 << 21 | 1 << 20, 32);
+    bits.write(0, 5);
+    bits.write(0b10, 2);
+
+    bits.write(0x564342, 24);
+    bits.write(1, 16);
+    bits.write(4, 24);
+    bits.write(0, 2);
+    for _ in 0..4 {
+        bits.write(1, 5);
+    }
+    bits.write(0, 4);
+
+    bits.write(0, 6);
+    bits.write(0, 16);
+
+    bits.write(0, 6);
+    bits.write(1, 16);
+    bits.write(1, 5);
+    bits.write(0, 4);
+    bits.write(1, 3);
+    bits.write(0, 2);
+    bits.write(2, 8);
+    bits.write(1, 2);
+    bits.write(10, 4);
+    bits.write(200, 10);
+    bits.write(500, 10);
+
+    bits.write(0, 6);
+    bits.write(1, 16);
+    bits.write(0, 24);
+    bits.write(1024, 24);
+    bits.write(31, 24);
+    bits.write(1, 6);
+    bits.write(0, 8);
+    bits.write(0, 4);
+    bits.write(1, 4);
+    bits.write(0, 8);
+
+    bits.write(0, 6);
+    bits.write(0, 16);
+    bits.write(0, 1);
+    match n_channels {
+        2 => {
+            bits.write(1, 1);
+            bits.write(0, 8);
+            bits.write(0b10, 2);
+        }
+        _ => bits.write(0, 1),
+    }
+    bits.write(0, 2);
+    bits.write(0, 24);
+
+    bits.write(1, 6);
+    for long_block in [0, 1] {
+        bits.write(long_block, 1);
+        bits.write(0, 32);
+        bits.write(0, 8);
+    }
+
+    bits.write(1, 1);
+    bits.into_bytes()
+}
+
+#[test]
+fn reads_ident_header() {
+    let ident = IdentHeader::read(&ident_packet(2, 0xb8)).unwrap();
+
+    assert_eq!(
+        ident,
+        IdentHeader {
+            version: 0,
+            n_channels: 2,
+            sample_rate: 44_100,
+            bitrate_max: 0,
+            bitrate_nominal: 128_000,
+            bitrate_min: -1,
+            block_size_0: 256,
+            block_size_1: 2048,
+        }
+    );
+}
+
+#[test]
+fn rejects_invalid_ident_headers() {
+    let packet = ident_packet(2, 0xb8);
+    assert_eq!(
+        IdentHeader::read(&packet[..29]),
+        Err(PhonicError::InvalidData)
+    );
+
+    assert_eq!(
+        IdentHeader::read(&ident_packet(0, 0xb8)),
+        Err(PhonicError::InvalidData)
+    );
+
+    // short blocks can't be longer than long ones, and neither can be below 64
+    for block_sizes in [0x8b, 0xb5, 0xee] {
+        assert_eq!(
+            IdentHeader::read(&ident_packet(2, block_sizes)),
+            Err(PhonicError::InvalidData)
+        );
+    }
+
+    let mut missing_framing = packet.clone();
+    missing_framing[29] = 0;
+    assert_eq!(
+        IdentHeader::read(&missing_framing),
+        Err(PhonicError::InvalidData)
+    );
+
+    let mut wrong_type = packet.clone();
+    wrong_type[0] = 3;
+    assert_eq!(
+        IdentHeader::read(&wrong_type),
+        Err(PhonicError::InvalidData)
+    );
+
+    let mut version = packet;
+    version[7] = 1;
+    assert_eq!(IdentHeader::read(&version), Err(PhonicError::Unsupported));
+}
+
+#[test]
+fn reads_comment_header() {
+    let packet = comment_packet(
+        "phonic",
+        &["TITLE=Level 1", "artist=Someone", "Artist=Else", "EMPTY"],
+    );
+    let comments = CommentHeader::read(&packet).unwrap();
+
+    assert_eq!(comments.vendor, "phonic");
+    assert_eq!(comments.comments.len(), 4);
+    assert_eq!(comments.get("title").collect::<Vec<_>>(), ["Level 1"]);
+    assert_eq!(
+        comments.get("ARTIST").collect::<Vec<_>>(),
+        ["Someone", "Else"]
+    );
+    assert_eq!(comments.get("empty").collect::<Vec<_>>(), [""]);
+    assert_eq!(comments.get("album").count(), 0);
+}
+
+#[test]
+fn rejects_truncated_comment_headers() {
+    let packet = comment_packet("phonic", &["TITLE=Level 1"]);
+
+    assert_eq!(
+        CommentHeader::read(&packet[..packet.len() - 2]),
+        Err(PhonicError::InvalidData)
+    );
+
+    assert_eq!(
+        CommentHeader::read(&ident_packet(2, 0xb8)),
+        Err(PhonicError::InvalidData)
+    );
+}
+
+#[test]
+fn reads_setup_header() {
+    for n_channels in [1, 2] {
+        let ident = IdentHeader::read(&ident_packet(n_channels, 0xb8)).unwrap();
+        let setup = SetupHeader::read(&setup_packet(n_channels), &ident).unwrap();
+
+        assert_eq!(setup.n_codebooks(), 2);
+        assert_eq!(
+            setup.modes(),
+            [
+                Mode {
+                    long_block: false,
+                    mapping: 0,
+                },
+                Mode {
+                    long_block: true,
+                    mapping: 0,
+                },
+            ]
+        );
+    }
+}
+
+#[test]
+fn rejects_invalid_setup_headers() {
+    let ident = IdentHeader::read(&ident_packet(2, 0xb8)).unwrap();
+    let packet = setup_packet(2);
+
+    // every cut short packet is rejected, down to the missing framing bit
+    for len in 0..packet.len() {
+        assert_eq!(
+            SetupHeader::read(&packet[..len], &ident).err(),
+            Some(PhonicError::InvalidData)
+        );
+    }
+
+    let mut bad_sync = packet.clone();
+    bad_sync[8] ^= 0xff;
+    assert_eq!(
+        SetupHeader::read(&bad_sync, &ident).err(),
+        Some(PhonicError::InvalidData)
+    );
+
+    // the coupling steps refer to a second channel
+    let mono = IdentHeader::read(&ident_packet(1, 0xb8)).unwrap();
+    assert_eq!(
+        SetupHeader::read(&packet, &mono).err(),
+        Some(PhonicError::InvalidData)
+    );
+}
@@ -34,7 +34,7 @@ impl SignalSpecExt for SignalSpec {
 
 pub trait KnownSampleTpeExt: Sized {
     fn try_from_cpal_sample_format(format: SampleFormat) -> Result<Self, PhonicError>;
-    fn into_cpal_sample_format(self) -> SampleFormat;
+    fn try_into_cpal_sample_format(self) -> Result<SampleFormat, PhonicError>;
 }
 
 impl KnownSampleTpeExt for KnownSampleType {
@@ -54,8 +54,8 @@ impl KnownSampleTpeExt for KnownSampleType {
         })
     }
 
-    fn into_cpal_sample_format(self) -> SampleFormat {
-        match self {
+    fn try_into_cpal_sample_format(self) -> Result<SampleFormat, PhonicError> {
+        Ok(match self {
             Self::I8 => SampleFormat::I8,
             Self::I16 => SampleFormat::I16,
             Self::I32 => SampleFormat::I32,
@@ -66,7 +66,8 @@ impl KnownSampleTpeExt for KnownSampleType {
             Self::U64 => SampleFormat::U64,
             Self::F32 => SampleFormat::F32,
             Self::F64 => SampleFormat::F64,
-        }
+            Self::I24 | Self::I48 | Self::U24 | Self::U48 => return Err(PhonicError::Unsupported),
+        })
     }
 }
 
@@ -85,7 +86,9 @@ impl SupportedStreamConfigRangeExt for SupportedStreamConfigRange {
     {
         let spec = signal.spec();
 
-        self.sample_format() == S::Sample::TYPE.into_cpal_sample_format()
+        S::Sample::TYPE
+            .try_into_cpal_sample_format()
+            .is_ok_and(|format| format == self.sample_format())
             && self.channels() == spec.channels.count()
             && self.max_sample_rate().0 >= spec.frame_rate
             && self.min_sample_rate().0 <= spec.frame_rate
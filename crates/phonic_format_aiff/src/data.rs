@@ -0,0 +1,127 @@
+use phonic_codec_g711::{fill_g711_spec, G711CodecTag};
+use phonic_codec_pcm::{fill_pcm_spec, ByteOrder, PcmCodecTag};
+use phonic_core::PhonicError;
+use phonic_io_core::{
+    utils::{FormatIdentifiers, FormatMarker},
+    CodecTag, FormatData, FormatTag, StreamSpec,
+};
+
+pub static AIFF_IDENTIFIERS: FormatIdentifiers = FormatIdentifiers {
+    file_extensions: &["aif", "aiff", "aifc"],
+    mime_types: &["audio/aiff", "audio/x-aiff", "audio/x-aifc"],
+    markers: &[
+        FormatMarker {
+            offset: 0,
+            bytes: b"FORM",
+        },
+        FormatMarker {
+            offset: 8,
+            bytes: b"AIFF",
+        },
+        FormatMarker {
+            offset: 8,
+            bytes: b"AIFC",
+        },
+    ],
+};
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub struct AiffFormatTag;
+
+/// The codecs an aiff file can be stored with. Plain aiff files only hold big endian integers,
+/// the rest need the compression types of aiff-c.
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum AiffSupportedCodec {
+    Pcm(ByteOrder),
+    ALaw,
+    MuLaw,
+}
+
+pub fn fill_aiff_data<F>(data: &mut FormatData<F>) -> Result<(), PhonicError>
+where
+    F: FormatTag,
+    AiffFormatTag: TryInto<F>,
+{
+    let expected_format = AiffFormatTag.try_into().ok();
+    if data.format.is_some() && data.format != expected_format {
+        return Err(PhonicError::InvalidData);
+    } else {
+        data.format = expected_format;
+    }
+
+    match data.streams.len() {
+        0 => data.streams.push(StreamSpec::new()),
+        1 => data.streams.first_mut().unwrap().fill()?,
+        _ => return Err(PhonicError::Unsupported),
+    }
+
+    Ok(())
+}
+
+impl FormatTag for AiffFormatTag {
+    type Codec = AiffSupportedCodec;
+
+    fn fill_data(data: &mut FormatData<Self>) -> Result<(), PhonicError> {
+        fill_aiff_data(data)
+    }
+}
+
+impl CodecTag for AiffSupportedCodec {
+    fn fill_spec(spec: &mut StreamSpec<Self>) -> Result<(), PhonicError> {
+        match spec.codec {
+            Some(Self::ALaw | Self::MuLaw) => fill_g711_spec(spec),
+            Some(Self::Pcm(_)) => fill_pcm_spec(spec),
+            None => {
+                spec.codec = Some(Self::Pcm(ByteOrder::BigEndian));
+                fill_pcm_spec(spec)
+            }
+        }
+    }
+}
+
+impl TryFrom<PcmCodecTag> for AiffSupportedCodec {
+    type Error = PhonicError;
+
+    // aiff files store integers as two's complement and floats as ieee 754, which matches the
+    // encoding of every sample type they can hold
+    fn try_from(tag: PcmCodecTag) -> Result<Self, Self::Error> {
+        match tag.encoding {
+            None => Ok(Self::Pcm(tag.byte_order)),
+            Some(_) => Err(PhonicError::Unsupported),
+        }
+    }
+}
+
+impl TryFrom<AiffSupportedCodec> for PcmCodecTag {
+    type Error = PhonicError;
+
+    fn try_from(codec: AiffSupportedCodec) -> Result<Self, Self::Error> {
+        match codec {
+            AiffSupportedCodec::Pcm(byte_order) => {
+                Ok(PcmCodecTag::new().with_byte_order(byte_order))
+            }
+            _ => Err(PhonicError::Unsupported),
+        }
+    }
+}
+
+impl From<G711CodecTag> for AiffSupportedCodec {
+    fn from(tag: G711CodecTag) -> Self {
+        match tag {
+            G711CodecTag::ALaw => Self::ALaw,
+            G711CodecTag::MuLaw => Self::MuLaw,
+        }
+    }
+}
+
+impl TryFrom<AiffSupportedCodec> for G711CodecTag {
+    type Error = PhonicError;
+
+    fn try_from(codec: AiffSupportedCodec) -> Result<Self, Self::Error> {
+        match codec {
+            AiffSupportedCodec::ALaw => Ok(Self::ALaw),
+            AiffSupportedCodec::MuLaw => Ok(Self::MuLaw),
+            _ => Err(PhonicError::Unsupported),
+        }
+    }
+}
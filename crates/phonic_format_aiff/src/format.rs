@@ -0,0 +1,207 @@
+use crate::{AiffFormatTag, AiffHeader, AiffSupportedCodec};
+use std::io::{Read, Seek, SeekFrom, Write};
+use phonic_core::PhonicError;
+use phonic_io_core::{
+    Format, FormatChunk, FormatData, FormatObserver, FormatOffset, FormatPosition, FormatReader,
+    FormatSeeker, FormatTag, FormatWriter,
+};
+
+type ReadHeader<T> = fn(&mut T) -> Result<(AiffHeader, usize), PhonicError>;
+
+pub struct AiffFormat<T, F: FormatTag = AiffFormatTag> {
+    inner: T,
+    i: usize,
+    data_start: usize,
+    data_len: usize,
+    data: FormatData<F>,
+
+    /// Reads the header along with its length, seeking past the sound data to a comm chunk that
+    /// comes after it, for readers made with `with_seekable_reads`.
+    read_header: Option<ReadHeader<T>>,
+}
+
+impl<T, F: FormatTag> AiffFormat<T, F> {
+    pub fn new(inner: T) -> Result<Self, PhonicError>
+    where
+        AiffFormatTag: TryInto<F>,
+    {
+        let mut data = FormatData::new();
+        data.format = AiffFormatTag.try_into().ok();
+        Ok(Self {
+            inner,
+            i: 0,
+            data_start: 0,
+            data_len: 0,
+            data,
+            read_header: None,
+        })
+    }
+
+    pub fn as_inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn block_align(&self) -> usize {
+        self.data
+            .streams
+            .first()
+            .and_then(|spec| spec.block_align)
+            .map_or(1, |align| align.max(1) as usize)
+    }
+
+    fn trim_buf_len(&self, len: usize) -> usize {
+        let remaining = (self.data_start + self.data_len).saturating_sub(self.i);
+        let len = len.min(remaining);
+
+        len - len % self.block_align()
+    }
+}
+
+impl<T: Read + Seek, F: FormatTag> AiffFormat<T, F> {
+    /// Lets the reader seek past the sound data when reading the header, to read files whose comm
+    /// chunk comes after it. Readers that can't seek fail on such files with `Unsupported`.
+    pub fn with_seekable_reads(mut self) -> Self {
+        self.read_header = Some(Self::read_header);
+        self
+    }
+
+    fn read_header(inner: &mut T) -> Result<(AiffHeader, usize), PhonicError> {
+        let start = inner.stream_position()?;
+        let header = AiffHeader::read_seekable(inner)?;
+        Ok((header, (inner.stream_position()? - start) as usize))
+    }
+}
+
+impl<T, F: FormatTag> Format for AiffFormat<T, F> {
+    type Tag = F;
+
+    fn data(&self) -> &FormatData<Self::Tag> {
+        &self.data
+    }
+}
+
+impl<T, F: FormatTag> FormatObserver for AiffFormat<T, F> {
+    fn position(&self) -> Result<FormatPosition, PhonicError> {
+        Ok(FormatPosition {
+            stream_i: 0,
+            byte_i: self.i.saturating_sub(self.data_start).min(self.data_len) as u64,
+        })
+    }
+}
+
+impl<T: Read, F: FormatTag> FormatReader for AiffFormat<T, F>
+where
+    AiffFormatTag: TryInto<F>,
+    AiffSupportedCodec: TryInto<F::Codec>,
+{
+    fn read_data(&mut self) -> Result<(), PhonicError> {
+        if self.i > 0 {
+            return Ok(());
+        }
+
+        let (header, header_len) = match self.read_header {
+            Some(read_header) => read_header(&mut self.inner)?,
+            None => {
+                let mut reader = (&mut self.inner).take(u64::MAX);
+                let header = AiffHeader::read(&mut reader)?;
+                (header, (u64::MAX - reader.limit()) as usize)
+            }
+        };
+
+        self.data.merge(&header.into())?;
+        self.data_start = header_len;
+        self.data_len = header.ssnd.byte_len as usize;
+        self.i = header_len;
+
+        Ok(())
+    }
+
+    fn read<'a>(&'a mut self, buf: &'a mut [u8]) -> Result<FormatChunk<'a>, PhonicError> {
+        if self.i == 0 {
+            self.read_data()?;
+        }
+
+        let len = self.trim_buf_len(buf.len());
+        let n = self.inner.read(&mut buf[..len])?;
+        self.i += n;
+
+        if n % self.block_align() != 0 {
+            return Err(PhonicError::SignalMismatch);
+        }
+
+        Ok(FormatChunk::Stream {
+            stream_i: 0,
+            buf: &buf[..n],
+        })
+    }
+}
+
+impl<T: Write, F: FormatTag> FormatWriter for AiffFormat<T, F>
+where
+    F::Codec: TryInto<AiffSupportedCodec>,
+{
+    fn write_data(&mut self, data: &FormatData<F>) -> Result<(), PhonicError> {
+        self.data.merge(data)?;
+        let header = AiffHeader::try_from(&self.data)?;
+        header.write(&mut self.inner)?;
+
+        self.data_len = header.ssnd.byte_len as usize;
+        self.data_start = header.data_offset() as usize;
+        self.i = self.data_start;
+
+        Ok(())
+    }
+
+    fn write(&mut self, chunk: FormatChunk) -> Result<(), PhonicError> {
+        match chunk {
+            FormatChunk::Stream { stream_i, buf } if self.i > 0 && stream_i == 0 => {
+                if buf.len() % self.block_align() != 0 {
+                    return Err(PhonicError::SignalMismatch);
+                }
+
+                self.inner.write_all(buf)?;
+                self.i += buf.len();
+            }
+            _ => return Err(PhonicError::InvalidData),
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), PhonicError> {
+        // the sound data chunk is padded to an even length once all of it has been written
+        let data_end = self.data_start + self.data_len;
+        if self.data_len % 2 == 1 && self.i == data_end {
+            self.inner.write_all(&[0])?;
+            self.i += 1;
+        }
+
+        self.inner.flush().map_err(Into::into)
+    }
+}
+
+impl<T: Seek, F: FormatTag> FormatSeeker for AiffFormat<T, F> {
+    fn seek(&mut self, offset: FormatOffset) -> Result<(), PhonicError> {
+        if offset.stream_offset != 0 {
+            return Err(PhonicError::NotFound);
+        }
+
+        if self.i == 0 {
+            return Err(PhonicError::NotReady);
+        }
+
+        let byte_i = ((self.i - self.data_start) as i64).saturating_add(offset.byte_offset);
+        let byte_i = byte_i.clamp(0, self.data_len as i64) as usize;
+        let byte_i = byte_i - byte_i % self.block_align();
+
+        self.inner
+            .seek(SeekFrom::Start((self.data_start + byte_i) as u64))?;
+        self.i = self.data_start + byte_i;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,416 @@
+use crate::{AiffFormatTag, AiffSupportedCodec};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use phonic_codec_pcm::{ByteOrder, PcmEncoding};
+use phonic_core::PhonicError;
+use phonic_io_core::{FormatData, FormatMetadata, FormatTag, StreamSpec};
+use phonic_signal::{Channels, KnownSampleType, SignalSpecBuilder};
+
+const FORM_CHUNK_ID: &[u8; 4] = b"FORM";
+const AIFF_FORM_TYPE: &[u8; 4] = b"AIFF";
+const AIFC_FORM_TYPE: &[u8; 4] = b"AIFC";
+
+/// The largest chunk read into memory, which fits an aiff-c comm chunk with the longest name.
+const MAX_CHUNK_LEN: usize = 22 + 256;
+
+type SeekBy<R> = fn(&mut R, i64) -> Result<(), PhonicError>;
+
+#[derive(Clone, Copy)]
+pub struct AiffHeader {
+    pub comm: CommChunk,
+    pub ssnd: SsndChunk,
+}
+
+const COMM_CHUNK_ID: &[u8; 4] = b"COMM";
+
+#[derive(Clone, Copy)]
+pub struct CommChunk {
+    pub n_channels: u16,
+    pub n_frames: u32,
+    pub bits_per_sample: u16,
+    pub sample_rate: f64,
+
+    /// The aiff-c compression type, or `None` for a plain aiff file.
+    pub compression_type: Option<[u8; 4]>,
+}
+
+const FVER_CHUNK_ID: &[u8; 4] = b"FVER";
+const AIFC_VERSION_1: u32 = 0xa280_5140;
+
+const SSND_CHUNK_ID: &[u8; 4] = b"SSND";
+
+const BIG_ENDIAN: AiffSupportedCodec = AiffSupportedCodec::Pcm(ByteOrder::BigEndian);
+const LITTLE_ENDIAN: AiffSupportedCodec = AiffSupportedCodec::Pcm(ByteOrder::LittleEndian);
+
+#[derive(Clone, Copy)]
+pub struct SsndChunk {
+    pub offset: u32,
+    pub block_size: u32,
+    pub byte_len: u32,
+}
+
+impl AiffHeader {
+    pub fn is_aifc(&self) -> bool {
+        self.comm.compression_type.is_some()
+    }
+
+    /// The number of bytes from the start of the file to the first byte of sound data.
+    pub fn data_offset(&self) -> u32 {
+        let fver_len = if self.is_aifc() { 8 + 4 } else { 0 };
+        12 + fver_len + 8 + self.comm.byte_len() + 8 + 8 + self.ssnd.offset
+    }
+
+    pub fn byte_len(&self) -> u32 {
+        // chunks are padded to an even length
+        self.data_offset() + self.ssnd.byte_len + self.ssnd.byte_len % 2
+    }
+
+    /// Reads the header up to the start of the sound data. A comm chunk after the sound data can
+    /// only be reached by seeking past it, so such files are `Unsupported`, see `read_seekable`.
+    pub fn read(reader: &mut impl Read) -> Result<Self, PhonicError> {
+        Self::read_chunks(reader, None)
+    }
+
+    /// Reads the header up to the start of the sound data, seeking past the sound data to a comm
+    /// chunk that comes after it and back again.
+    pub fn read_seekable(reader: &mut (impl Read + Seek)) -> Result<Self, PhonicError> {
+        Self::read_chunks(reader, Some(seek_by))
+    }
+
+    fn read_chunks<R: Read>(
+        reader: &mut R,
+        seek_by: Option<SeekBy<R>>,
+    ) -> Result<Self, PhonicError> {
+        let mut buf = [0u8; MAX_CHUNK_LEN];
+
+        reader.read_exact(&mut buf[0..12])?;
+        if &buf[0..4] != FORM_CHUNK_ID {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let is_aifc = match buf[8..12].try_into().unwrap() {
+            AIFF_FORM_TYPE => false,
+            AIFC_FORM_TYPE => true,
+            _ => return Err(PhonicError::InvalidData),
+        };
+
+        let mut comm = None;
+        let mut ssnd = None;
+
+        // the number of bytes read or seeked past since the start of the sound data
+        let mut data_distance = 0;
+
+        while comm.is_none() || ssnd.is_none() {
+            reader.read_exact(&mut buf[..8])?;
+            let chunk_id: [u8; 4] = buf[0..4].try_into().unwrap();
+            let byte_len = u32::from_be_bytes(buf[4..8].try_into().unwrap());
+
+            let padded_len = byte_len as u64 + byte_len as u64 % 2;
+            if ssnd.is_some() {
+                data_distance += 8 + padded_len;
+            }
+
+            match &chunk_id {
+                SSND_CHUNK_ID if ssnd.is_none() => {
+                    reader.read_exact(&mut buf[..8])?;
+                    let offset = u32::from_be_bytes(buf[0..4].try_into().unwrap());
+                    let block_size = u32::from_be_bytes(buf[4..8].try_into().unwrap());
+                    skip(reader, offset as u64)?;
+
+                    ssnd = Some(SsndChunk {
+                        offset,
+                        block_size,
+                        byte_len: byte_len
+                            .checked_sub(8)
+                            .and_then(|len| len.checked_sub(offset))
+                            .ok_or(PhonicError::InvalidData)?,
+                    });
+
+                    if comm.is_none() {
+                        let seek = seek_by.ok_or(PhonicError::Unsupported)?;
+                        data_distance = padded_len - 8 - offset as u64;
+                        seek(reader, data_distance as i64)?;
+                    }
+                }
+
+                COMM_CHUNK_ID => {
+                    let byte_len = byte_len as usize;
+                    if byte_len > buf.len() {
+                        return Err(PhonicError::InvalidData);
+                    }
+
+                    reader.read_exact(&mut buf[..byte_len])?;
+                    skip(reader, padded_len - byte_len as u64)?;
+                    comm = Some(CommChunk::read(&buf[..byte_len], is_aifc)?);
+                }
+
+                // markers, instrument data and comments are not needed to decode the sound data
+                _ => skip(reader, padded_len)?,
+            }
+        }
+
+        if let Some(seek) = seek_by.filter(|_| data_distance > 0) {
+            seek(reader, -(data_distance as i64))?;
+        }
+
+        Ok(Self {
+            comm: comm.ok_or(PhonicError::Unreachable)?,
+            ssnd: ssnd.ok_or(PhonicError::Unreachable)?,
+        })
+    }
+
+    pub fn write(&self, writer: &mut impl Write) -> Result<(), PhonicError> {
+        let mut buf = [0u8; MAX_CHUNK_LEN];
+
+        buf[0..4].copy_from_slice(FORM_CHUNK_ID);
+        buf[4..8].copy_from_slice(&(self.byte_len() - 8).to_be_bytes());
+        if self.is_aifc() {
+            buf[8..12].copy_from_slice(AIFC_FORM_TYPE);
+        } else {
+            buf[8..12].copy_from_slice(AIFF_FORM_TYPE);
+        }
+
+        writer.write_all(&buf[0..12])?;
+
+        if self.is_aifc() {
+            buf[0..4].copy_from_slice(FVER_CHUNK_ID);
+            buf[4..8].copy_from_slice(&4u32.to_be_bytes());
+            buf[8..12].copy_from_slice(&AIFC_VERSION_1.to_be_bytes());
+            writer.write_all(&buf[0..12])?;
+        }
+
+        buf[0..4].copy_from_slice(COMM_CHUNK_ID);
+        buf[4..8].copy_from_slice(&self.comm.byte_len().to_be_bytes());
+        let n = self.comm.write(&mut buf[8..])?;
+        writer.write_all(&buf[..n + 8])?;
+
+        buf[0..4].copy_from_slice(SSND_CHUNK_ID);
+        buf[4..8].copy_from_slice(&(8 + self.ssnd.offset + self.ssnd.byte_len).to_be_bytes());
+        buf[8..12].copy_from_slice(&self.ssnd.offset.to_be_bytes());
+        buf[12..16].copy_from_slice(&self.ssnd.block_size.to_be_bytes());
+        writer.write_all(&buf[0..16])?;
+        io::copy(&mut io::repeat(0).take(self.ssnd.offset as u64), writer)?;
+
+        Ok(())
+    }
+}
+
+fn seek_by<R: Seek>(reader: &mut R, n: i64) -> Result<(), PhonicError> {
+    reader.seek(SeekFrom::Current(n))?;
+    Ok(())
+}
+
+fn skip(reader: &mut impl Read, n: u64) -> Result<(), PhonicError> {
+    if io::copy(&mut reader.take(n), &mut io::sink())? < n {
+        return Err(PhonicError::EndOfStream);
+    }
+
+    Ok(())
+}
+
+/// Reads an 80 bit ieee 754 extended precision float, which aiff uses for sample rates.
+fn read_extended(bytes: &[u8]) -> f64 {
+    let exponent = u16::from_be_bytes([bytes[0] & 0x7f, bytes[1]]) as i32;
+    let mantissa = u64::from_be_bytes(bytes[2..10].try_into().unwrap());
+    let value = mantissa as f64 * 2f64.powi(exponent - 16383 - 63);
+
+    if bytes[0] & 0x80 != 0 {
+        -value
+    } else {
+        value
+    }
+}
+
+fn write_extended(value: f64) -> [u8; 10] {
+    let mut bytes = [0u8; 10];
+    if value == 0.0 || !value.is_normal() {
+        return bytes;
+    }
+
+    // the extended format stores the integer bit of the mantissa that doubles leave implicit
+    let bits = value.to_bits();
+    let sign = (bits >> 63) as u16;
+    let exponent = ((bits >> 52) & 0x7ff) as u16 + 16383 - 1023;
+    let mantissa = ((bits & ((1 << 52) - 1)) | (1 << 52)) << 11;
+
+    bytes[0..2].copy_from_slice(&((sign << 15) | exponent).to_be_bytes());
+    bytes[2..10].copy_from_slice(&mantissa.to_be_bytes());
+    bytes
+}
+
+impl<F> From<AiffHeader> for FormatData<F>
+where
+    F: FormatTag,
+    AiffFormatTag: TryInto<F>,
+    AiffSupportedCodec: TryInto<F::Codec>,
+{
+    fn from(header: AiffHeader) -> Self {
+        let byte_size = header.comm.bits_per_sample.div_ceil(8);
+        let int_type = PcmEncoding::SignedInt.sample_type(byte_size as u32 * 8);
+
+        // sample sizes that are not a whole number of bytes are stored in the next largest size
+        let (codec, sample_type) = match header.comm.compression_type.as_ref() {
+            None | Some(b"NONE" | b"twos") => (Some(BIG_ENDIAN), int_type),
+            Some(b"sowt") => (Some(LITTLE_ENDIAN), int_type),
+            Some(b"fl32" | b"FL32") => (Some(BIG_ENDIAN), Some(KnownSampleType::F32)),
+            Some(b"fl64" | b"FL64") => (Some(BIG_ENDIAN), Some(KnownSampleType::F64)),
+            Some(b"alaw" | b"ALAW") => (Some(AiffSupportedCodec::ALaw), Some(KnownSampleType::I16)),
+            Some(b"ulaw" | b"ULAW") => {
+                (Some(AiffSupportedCodec::MuLaw), Some(KnownSampleType::I16))
+            }
+            _ => (None, None),
+        };
+
+        let block_align = match (codec, sample_type) {
+            (Some(AiffSupportedCodec::ALaw | AiffSupportedCodec::MuLaw), _) => {
+                Some(header.comm.n_channels)
+            }
+            (_, Some(sample_type)) => Some(sample_type.byte_size() as u16 * header.comm.n_channels),
+            _ => None,
+        };
+
+        let frame_rate = header.comm.sample_rate.round() as u32;
+
+        Self {
+            format: AiffFormatTag.try_into().ok(),
+            streams: vec![StreamSpec {
+                codec: codec.and_then(|codec| codec.try_into().ok()),
+                avg_bitrate: block_align.map(|align| frame_rate as f64 * align as f64 * 8.0),
+                block_align,
+                sample_type: sample_type.map(Into::into),
+                decoded_spec: SignalSpecBuilder::new()
+                    .with_channels(Channels::Count(header.comm.n_channels))
+                    .with_frame_rate(frame_rate)
+                    .with_n_frames(Some(header.comm.n_frames as u64)),
+            }],
+            metadata: FormatMetadata::new(),
+        }
+    }
+}
+
+impl<F> TryFrom<&FormatData<F>> for AiffHeader
+where
+    F: FormatTag,
+    F::Codec: TryInto<AiffSupportedCodec>,
+{
+    type Error = PhonicError;
+
+    fn try_from(data: &FormatData<F>) -> Result<Self, Self::Error> {
+        if data.streams.len() != 1 {
+            return Err(PhonicError::Unsupported);
+        }
+
+        let spec = data.streams[0];
+        let sample_type: KnownSampleType = spec
+            .sample_type
+            .ok_or(PhonicError::MissingData)?
+            .try_into()?;
+
+        let codec = match spec.codec {
+            Some(codec) => codec.try_into().map_err(|_| PhonicError::Unsupported)?,
+            None => BIG_ENDIAN,
+        };
+
+        type T = KnownSampleType;
+
+        let bits = sample_type.byte_size() as u16 * 8;
+        let (compression_type, bits_per_sample, byte_size) = match (codec, sample_type) {
+            (BIG_ENDIAN, T::I8 | T::I16 | T::I24 | T::I32) => (None, bits, bits / 8),
+            (LITTLE_ENDIAN, T::I16 | T::I24 | T::I32) => (Some(*b"sowt"), bits, bits / 8),
+            (BIG_ENDIAN, T::F32) => (Some(*b"fl32"), 32, 4),
+            (BIG_ENDIAN, T::F64) => (Some(*b"fl64"), 64, 8),
+            (AiffSupportedCodec::ALaw, T::I16) => (Some(*b"alaw"), 16, 1),
+            (AiffSupportedCodec::MuLaw, T::I16) => (Some(*b"ulaw"), 16, 1),
+            _ => return Err(PhonicError::Unsupported),
+        };
+
+        let n_channels = spec
+            .decoded_spec
+            .channels
+            .ok_or(PhonicError::InvalidData)?
+            .count();
+
+        let sample_rate = spec
+            .decoded_spec
+            .frame_rate
+            .ok_or(PhonicError::InvalidData)?;
+
+        let n_frames = spec.decoded_spec.n_frames.ok_or(PhonicError::Unsupported)?;
+
+        Ok(Self {
+            comm: CommChunk {
+                n_channels,
+                n_frames: n_frames as u32,
+                bits_per_sample,
+                sample_rate: sample_rate as f64,
+                compression_type,
+            },
+            ssnd: SsndChunk {
+                offset: 0,
+                block_size: 0,
+                byte_len: n_frames as u32 * byte_size as u32 * n_channels as u32,
+            },
+        })
+    }
+}
+
+impl CommChunk {
+    fn compression_name(&self) -> &'static [u8] {
+        match self.compression_type.as_ref() {
+            Some(b"NONE") => b"not compressed",
+            Some(b"sowt") => b"little endian",
+            Some(b"fl32") => b"32-bit floating point",
+            Some(b"fl64") => b"64-bit floating point",
+            Some(b"alaw") => b"aLaw 2:1",
+            Some(b"ulaw") => b"uLaw 2:1",
+            _ => b"",
+        }
+    }
+
+    fn byte_len(&self) -> u32 {
+        if self.compression_type.is_some() {
+            // the name is a pascal string padded to an even length
+            let name_len = 1 + self.compression_name().len() as u32;
+            18 + 4 + name_len + name_len % 2
+        } else {
+            18
+        }
+    }
+
+    fn read(buf: &[u8], is_aifc: bool) -> Result<Self, PhonicError> {
+        let buf_len = buf.len();
+        if buf_len < 18 || is_aifc && buf_len < 22 {
+            return Err(PhonicError::InvalidData);
+        }
+
+        // the compression name is only meant to be shown to people, so it is not read
+        Ok(Self {
+            n_channels: u16::from_be_bytes(buf[0..2].try_into().unwrap()),
+            n_frames: u32::from_be_bytes(buf[2..6].try_into().unwrap()),
+            bits_per_sample: u16::from_be_bytes(buf[6..8].try_into().unwrap()),
+            sample_rate: read_extended(&buf[8..18]),
+            compression_type: is_aifc.then(|| buf[18..22].try_into().unwrap()),
+        })
+    }
+
+    fn write(&self, buf: &mut [u8]) -> Result<usize, PhonicError> {
+        let byte_len = self.byte_len() as usize;
+        if buf.len() < byte_len {
+            return Err(PhonicError::InvalidData);
+        }
+
+        buf[0..2].copy_from_slice(&self.n_channels.to_be_bytes());
+        buf[2..6].copy_from_slice(&self.n_frames.to_be_bytes());
+        buf[6..8].copy_from_slice(&self.bits_per_sample.to_be_bytes());
+        buf[8..18].copy_from_slice(&write_extended(self.sample_rate));
+
+        if let Some(compression_type) = &self.compression_type {
+            let name = self.compression_name();
+            buf[18..22].copy_from_slice(compression_type);
+            buf[22] = name.len() as u8;
+            buf[23..23 + name.len()].copy_from_slice(name);
+            buf[23 + name.len()..byte_len].fill(0);
+        }
+
+        Ok(byte_len)
+    }
+}
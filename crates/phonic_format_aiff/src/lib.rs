@@ -0,0 +1,7 @@
+mod data;
+mod format;
+mod header;
+
+pub use data::*;
+pub use format::*;
+pub use header::*;
@@ -0,0 +1,237 @@
+use phonic_codec_pcm::ByteOrder;
+use phonic_core::PhonicError;
+use phonic_format_aiff::{
+    AiffFormat, AiffFormatTag, AiffHeader, AiffSupportedCodec, CommChunk, SsndChunk,
+};
+use phonic_io_core::{
+    Format, FormatChunk, FormatData, FormatObserver, FormatOffset, FormatReader, FormatSeeker,
+    FormatWriter, StreamSpec,
+};
+use phonic_signal::{KnownSampleType, SignalSpecBuilder};
+use std::{any::TypeId, io::Cursor};
+
+const BIG_ENDIAN: AiffSupportedCodec = AiffSupportedCodec::Pcm(ByteOrder::BigEndian);
+const LITTLE_ENDIAN: AiffSupportedCodec = AiffSupportedCodec::Pcm(ByteOrder::LittleEndian);
+
+fn chunk(id: &[u8; 4], body: &[u8]) -> Vec<u8> {
+    let mut chunk = id.to_vec();
+    chunk.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    chunk.extend_from_slice(body);
+    if body.len() % 2 == 1 {
+        chunk.push(0);
+    }
+
+    chunk
+}
+
+fn header(compression_type: Option<[u8; 4]>, bits_per_sample: u16) -> AiffHeader {
+    AiffHeader {
+        comm: CommChunk {
+            n_channels: 2,
+            n_frames: 100,
+            bits_per_sample,
+            sample_rate: 44_100.0,
+            compression_type,
+        },
+        ssnd: SsndChunk {
+            offset: 0,
+            block_size: 0,
+            byte_len: 400,
+        },
+    }
+}
+
+fn stream_spec(
+    codec: AiffSupportedCodec,
+    sample_type: KnownSampleType,
+) -> StreamSpec<AiffSupportedCodec> {
+    let decoded_spec = SignalSpecBuilder::new()
+        .with_frame_rate(22_050)
+        .with_channels(1)
+        .with_n_frames(Some(3));
+
+    let mut spec = StreamSpec::new()
+        .with_codec(codec)
+        .with_decoded_spec(decoded_spec);
+
+    spec.sample_type = Some(sample_type.into());
+    spec.filled().unwrap()
+}
+
+#[test]
+fn sample_rate_is_an_extended_float() {
+    let mut file = Vec::new();
+    header(None, 16).write(&mut file).unwrap();
+
+    assert_eq!(file[0..4], *b"FORM");
+    assert_eq!(file[8..12], *b"AIFF");
+    assert_eq!(file[12..16], *b"COMM");
+    assert_eq!(file[28..38], [0x40, 0x0e, 0xac, 0x44, 0, 0, 0, 0, 0, 0]);
+
+    let header = AiffHeader::read(&mut file.as_slice()).unwrap();
+    assert_eq!(header.comm.sample_rate, 44_100.0);
+    assert_eq!(header.data_offset() as usize, file.len());
+}
+
+#[test]
+fn compression_types_are_read() {
+    for (compression_type, bits, codec, sample_type, block_align) in [
+        (None, 12, BIG_ENDIAN, KnownSampleType::I16, 4),
+        (Some(*b"NONE"), 24, BIG_ENDIAN, KnownSampleType::I24, 6),
+        (Some(*b"sowt"), 16, LITTLE_ENDIAN, KnownSampleType::I16, 4),
+        (Some(*b"fl32"), 32, BIG_ENDIAN, KnownSampleType::F32, 8),
+        (
+            Some(*b"ulaw"),
+            16,
+            AiffSupportedCodec::MuLaw,
+            KnownSampleType::I16,
+            2,
+        ),
+        (
+            Some(*b"alaw"),
+            16,
+            AiffSupportedCodec::ALaw,
+            KnownSampleType::I16,
+            2,
+        ),
+    ] {
+        let mut file = Vec::new();
+        header(compression_type, bits).write(&mut file).unwrap();
+        let form_type = compression_type.map_or(b"AIFF", |_| b"AIFC");
+        assert_eq!(file[8..12], *form_type);
+
+        let header = AiffHeader::read(&mut file.as_slice()).unwrap();
+        assert!(header.comm.compression_type == compression_type);
+
+        let data = FormatData::<AiffFormatTag>::from(header);
+        let spec = data.streams[0];
+        assert!(spec.codec == Some(codec));
+        assert_eq!(spec.sample_type, Some(sample_type.into()));
+        assert_eq!(spec.block_align, Some(block_align));
+        assert_eq!(spec.decoded_spec.n_frames, Some(100));
+    }
+}
+
+#[test]
+fn other_chunks_are_skipped() {
+    let mut comm = Vec::new();
+    comm.extend_from_slice(&1u16.to_be_bytes());
+    comm.extend_from_slice(&4u32.to_be_bytes());
+    comm.extend_from_slice(&8u16.to_be_bytes());
+    comm.extend_from_slice(&[0x40, 0x0b, 0xfa, 0, 0, 0, 0, 0, 0, 0]);
+
+    let mut ssnd = Vec::new();
+    ssnd.extend_from_slice(&3u32.to_be_bytes());
+    ssnd.extend_from_slice(&0u32.to_be_bytes());
+    ssnd.extend_from_slice(&[0xff, 0xff, 0xff, 1, 2, 3, 4]);
+
+    let mut body = b"AIFF".to_vec();
+    body.extend(chunk(b"NAME", b"odd"));
+    body.extend(chunk(b"COMM", &comm));
+    body.extend(chunk(b"MARK", &[0, 0]));
+    body.extend(chunk(b"SSND", &ssnd));
+    let file = chunk(b"FORM", &body);
+
+    let mut format = AiffFormat::<_, AiffFormatTag>::new(Cursor::new(file)).unwrap();
+    format.read_data().unwrap();
+
+    let spec = format.data().streams[0];
+    assert_eq!(spec.decoded_spec.frame_rate, Some(8_000));
+    assert_eq!(spec.sample_type, Some(TypeId::of::<i8>()));
+
+    let mut buf = [0; 16];
+    let FormatChunk::Stream { buf, .. } = format.read(&mut buf).unwrap();
+    assert_eq!(buf, [1, 2, 3, 4]);
+}
+
+#[test]
+fn comm_chunks_after_the_sound_data_need_seeking() {
+    let mut comm = Vec::new();
+    comm.extend_from_slice(&1u16.to_be_bytes());
+    comm.extend_from_slice(&5u32.to_be_bytes());
+    comm.extend_from_slice(&8u16.to_be_bytes());
+    comm.extend_from_slice(&[0x40, 0x0b, 0xfa, 0, 0, 0, 0, 0, 0, 0]);
+
+    let mut ssnd = Vec::new();
+    ssnd.extend_from_slice(&2u32.to_be_bytes());
+    ssnd.extend_from_slice(&0u32.to_be_bytes());
+    ssnd.extend_from_slice(&[0xff, 0xff, 1, 2, 3, 4, 5]);
+
+    let mut body = b"AIFF".to_vec();
+    body.extend(chunk(b"SSND", &ssnd));
+    body.extend(chunk(b"MARK", &[0, 0]));
+    body.extend(chunk(b"COMM", &comm));
+    let file = chunk(b"FORM", &body);
+
+    let header = AiffHeader::read_seekable(&mut Cursor::new(&file)).unwrap();
+    assert_eq!(header.comm.n_frames, 5);
+    assert_eq!(header.ssnd.byte_len, 5);
+
+    let mut format = AiffFormat::<_, AiffFormatTag>::new(Cursor::new(file.clone()))
+        .unwrap()
+        .with_seekable_reads();
+    format.read_data().unwrap();
+    assert_eq!(format.data().streams[0].decoded_spec.n_frames, Some(5));
+
+    let mut buf = [0; 16];
+    let FormatChunk::Stream { buf, .. } = format.read(&mut buf).unwrap();
+    assert_eq!(buf, [1, 2, 3, 4, 5]);
+
+    // a slice reader can't seek past the sound data
+    let mut format = AiffFormat::<_, AiffFormatTag>::new(file.as_slice()).unwrap();
+    assert_eq!(format.read_data().err(), Some(PhonicError::Unsupported));
+}
+
+#[test]
+fn written_files_are_read_back() {
+    for (codec, sample_type, data) in [
+        (BIG_ENDIAN, KnownSampleType::I8, vec![1, 2, 3]),
+        (
+            AiffSupportedCodec::MuLaw,
+            KnownSampleType::I16,
+            vec![1, 2, 3],
+        ),
+        (BIG_ENDIAN, KnownSampleType::F32, (0..12).collect()),
+    ] {
+        let spec = stream_spec(codec, sample_type);
+        let mut format = AiffFormat::<_, AiffFormatTag>::new(Cursor::new(Vec::new())).unwrap();
+        format
+            .write_data(&FormatData::new().with_stream(spec))
+            .unwrap();
+        format
+            .write(FormatChunk::Stream {
+                stream_i: 0,
+                buf: &data,
+            })
+            .unwrap();
+
+        format.flush().unwrap();
+        let file = format.into_inner().into_inner();
+        assert_eq!(file.len() % 2, 0);
+        assert_eq!(
+            u32::from_be_bytes(file[4..8].try_into().unwrap()) as usize,
+            file.len() - 8
+        );
+
+        let mut format = AiffFormat::<_, AiffFormatTag>::new(Cursor::new(file)).unwrap();
+        format.read_data().unwrap();
+        let read_spec = format.data().streams[0];
+        assert!(read_spec.codec == spec.codec);
+        assert_eq!(read_spec.sample_type, spec.sample_type);
+        assert_eq!(read_spec.block_align, spec.block_align);
+        assert_eq!(read_spec.decoded_spec.n_frames, Some(3));
+
+        let mut buf = [0; 16];
+        let FormatChunk::Stream { buf, .. } = format.read(&mut buf).unwrap();
+        assert_eq!(buf, data);
+
+        format
+            .seek(FormatOffset {
+                stream_offset: 0,
+                byte_offset: -(data.len() as i64),
+            })
+            .unwrap();
+
+        assert_eq!(format.position().unwrap().byte_i, 0);
+    }
+}
@@ -0,0 +1,116 @@
+use phonic_codec_g711::{fill_g711_spec, G711CodecTag};
+use phonic_codec_pcm::{fill_pcm_spec, ByteOrder, PcmCodecTag, PcmEncoding};
+use phonic_core::PhonicError;
+use phonic_io_core::{
+    utils::{FormatIdentifiers, FormatMarker},
+    CodecTag, FormatData, FormatTag, StreamSpec,
+};
+
+pub static AU_IDENTIFIERS: FormatIdentifiers = FormatIdentifiers {
+    file_extensions: &["au", "snd"],
+    mime_types: &["audio/basic"],
+    markers: &[FormatMarker {
+        offset: 0,
+        bytes: b".snd",
+    }],
+};
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub struct AuFormatTag;
+
+/// The codecs an au file can be stored with. Pcm samples are always big endian, and are either
+/// signed integers or floats.
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum AuSupportedCodec {
+    Pcm,
+    ALaw,
+    MuLaw,
+}
+
+pub fn fill_au_data<F>(data: &mut FormatData<F>) -> Result<(), PhonicError>
+where
+    F: FormatTag,
+    AuFormatTag: TryInto<F>,
+{
+    let expected_format = AuFormatTag.try_into().ok();
+    if data.format.is_some() && data.format != expected_format {
+        return Err(PhonicError::InvalidData);
+    } else {
+        data.format = expected_format;
+    }
+
+    match data.streams.len() {
+        0 => data.streams.push(StreamSpec::new()),
+        1 => data.streams.first_mut().unwrap().fill()?,
+        _ => return Err(PhonicError::Unsupported),
+    }
+
+    Ok(())
+}
+
+impl FormatTag for AuFormatTag {
+    type Codec = AuSupportedCodec;
+
+    fn fill_data(data: &mut FormatData<Self>) -> Result<(), PhonicError> {
+        fill_au_data(data)
+    }
+}
+
+impl CodecTag for AuSupportedCodec {
+    fn fill_spec(spec: &mut StreamSpec<Self>) -> Result<(), PhonicError> {
+        match spec.codec {
+            Some(Self::ALaw | Self::MuLaw) => fill_g711_spec(spec),
+            Some(Self::Pcm) => fill_pcm_spec(spec),
+            None => {
+                spec.codec = Some(Self::Pcm);
+                fill_pcm_spec(spec)
+            }
+        }
+    }
+}
+
+impl TryFrom<PcmCodecTag> for AuSupportedCodec {
+    type Error = PhonicError;
+
+    // the 8 bit linear encoding is signed like the rest, so unsigned samples have no encoding
+    fn try_from(tag: PcmCodecTag) -> Result<Self, Self::Error> {
+        match (tag.byte_order, tag.encoding) {
+            (ByteOrder::BigEndian, None | Some(PcmEncoding::SignedInt | PcmEncoding::Float)) => {
+                Ok(Self::Pcm)
+            }
+            _ => Err(PhonicError::Unsupported),
+        }
+    }
+}
+
+impl TryFrom<AuSupportedCodec> for PcmCodecTag {
+    type Error = PhonicError;
+
+    fn try_from(codec: AuSupportedCodec) -> Result<Self, Self::Error> {
+        match codec {
+            AuSupportedCodec::Pcm => Ok(PcmCodecTag::new().with_byte_order(ByteOrder::BigEndian)),
+            _ => Err(PhonicError::Unsupported),
+        }
+    }
+}
+
+impl From<G711CodecTag> for AuSupportedCodec {
+    fn from(tag: G711CodecTag) -> Self {
+        match tag {
+            G711CodecTag::ALaw => Self::ALaw,
+            G711CodecTag::MuLaw => Self::MuLaw,
+        }
+    }
+}
+
+impl TryFrom<AuSupportedCodec> for G711CodecTag {
+    type Error = PhonicError;
+
+    fn try_from(codec: AuSupportedCodec) -> Result<Self, Self::Error> {
+        match codec {
+            AuSupportedCodec::ALaw => Ok(Self::ALaw),
+            AuSupportedCodec::MuLaw => Ok(Self::MuLaw),
+            _ => Err(PhonicError::Unsupported),
+        }
+    }
+}
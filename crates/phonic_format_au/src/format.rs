@@ -0,0 +1,183 @@
+use crate::{AuFormatTag, AuHeader, AuSupportedCodec};
+use std::io::{Read, Seek, SeekFrom, Write};
+use phonic_core::PhonicError;
+use phonic_io_core::{
+    Format, FormatChunk, FormatData, FormatObserver, FormatOffset, FormatPosition, FormatReader,
+    FormatSeeker, FormatTag, FormatWriter,
+};
+
+pub struct AuFormat<T, F: FormatTag = AuFormatTag> {
+    inner: T,
+    i: usize,
+    data_start: usize,
+    /// The length of the sound data, or `None` when it runs on to the end of the file.
+    data_len: Option<usize>,
+    data: FormatData<F>,
+}
+
+impl<T, F: FormatTag> AuFormat<T, F> {
+    pub fn new(inner: T) -> Result<Self, PhonicError>
+    where
+        AuFormatTag: TryInto<F>,
+    {
+        let mut data = FormatData::new();
+        data.format = AuFormatTag.try_into().ok();
+        Ok(Self {
+            inner,
+            i: 0,
+            data_start: 0,
+            data_len: None,
+            data,
+        })
+    }
+
+    pub fn as_inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn block_align(&self) -> usize {
+        self.data
+            .streams
+            .first()
+            .and_then(|spec| spec.block_align)
+            .map_or(1, |align| align.max(1) as usize)
+    }
+
+    fn trim_buf_len(&self, len: usize) -> usize {
+        let len = match self.data_len {
+            Some(data_len) => len.min((self.data_start + data_len).saturating_sub(self.i)),
+            None => len,
+        };
+
+        len - len % self.block_align()
+    }
+}
+
+impl<T, F: FormatTag> Format for AuFormat<T, F> {
+    type Tag = F;
+
+    fn data(&self) -> &FormatData<Self::Tag> {
+        &self.data
+    }
+}
+
+impl<T, F: FormatTag> FormatObserver for AuFormat<T, F> {
+    fn position(&self) -> Result<FormatPosition, PhonicError> {
+        let byte_i = self.i.saturating_sub(self.data_start);
+        Ok(FormatPosition {
+            stream_i: 0,
+            byte_i: self.data_len.map_or(byte_i, |len| byte_i.min(len)) as u64,
+        })
+    }
+}
+
+impl<T: Read, F: FormatTag> FormatReader for AuFormat<T, F>
+where
+    AuFormatTag: TryInto<F>,
+    AuSupportedCodec: TryInto<F::Codec>,
+{
+    fn read_data(&mut self) -> Result<(), PhonicError> {
+        if self.i > 0 {
+            return Ok(());
+        }
+
+        let header = AuHeader::read(&mut self.inner)?;
+
+        self.data.merge(&header.into())?;
+        self.data_start = header.data_offset as usize;
+        self.data_len = header.byte_len.map(|len| len as usize);
+        self.i = self.data_start;
+
+        Ok(())
+    }
+
+    fn read<'a>(&'a mut self, buf: &'a mut [u8]) -> Result<FormatChunk<'a>, PhonicError> {
+        if self.i == 0 {
+            self.read_data()?;
+        }
+
+        let len = self.trim_buf_len(buf.len());
+        let n = self.inner.read(&mut buf[..len])?;
+        self.i += n;
+
+        if n % self.block_align() != 0 {
+            return Err(PhonicError::SignalMismatch);
+        }
+
+        Ok(FormatChunk::Stream {
+            stream_i: 0,
+            buf: &buf[..n],
+        })
+    }
+}
+
+impl<T: Write, F: FormatTag> FormatWriter for AuFormat<T, F>
+where
+    F::Codec: TryInto<AuSupportedCodec>,
+{
+    fn write_data(&mut self, data: &FormatData<F>) -> Result<(), PhonicError> {
+        self.data.merge(data)?;
+        let header = AuHeader::try_from(&self.data)?;
+        header.write(&mut self.inner)?;
+
+        self.data_start = header.data_offset as usize;
+        self.data_len = header.byte_len.map(|len| len as usize);
+        self.i = self.data_start;
+
+        Ok(())
+    }
+
+    fn write(&mut self, chunk: FormatChunk) -> Result<(), PhonicError> {
+        match chunk {
+            FormatChunk::Stream { stream_i, buf } if self.i > 0 && stream_i == 0 => {
+                if buf.len() % self.block_align() != 0 {
+                    return Err(PhonicError::SignalMismatch);
+                }
+
+                self.inner.write_all(buf)?;
+                self.i += buf.len();
+            }
+            _ => return Err(PhonicError::InvalidData),
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), PhonicError> {
+        self.inner.flush().map_err(Into::into)
+    }
+}
+
+impl<T: Seek, F: FormatTag> FormatSeeker for AuFormat<T, F> {
+    fn seek(&mut self, offset: FormatOffset) -> Result<(), PhonicError> {
+        if offset.stream_offset != 0 {
+            return Err(PhonicError::NotFound);
+        }
+
+        if self.i == 0 {
+            return Err(PhonicError::NotReady);
+        }
+
+        let data_len = match self.data_len {
+            Some(len) => len,
+            None => {
+                let end = self.inner.seek(SeekFrom::End(0))? as usize;
+                end.saturating_sub(self.data_start)
+            }
+        };
+
+        let byte_i = ((self.i - self.data_start) as i64).saturating_add(offset.byte_offset);
+        let byte_i = byte_i.clamp(0, data_len as i64) as usize;
+        let byte_i = byte_i - byte_i % self.block_align();
+
+        self.inner
+            .seek(SeekFrom::Start((self.data_start + byte_i) as u64))?;
+        self.i = self.data_start + byte_i;
+
+        Ok(())
+    }
+}
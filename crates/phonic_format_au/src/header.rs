@@ -0,0 +1,208 @@
+use crate::{AuFormatTag, AuSupportedCodec};
+use std::io::{self, Read, Write};
+use phonic_core::PhonicError;
+use phonic_io_core::{FormatData, FormatMetadata, FormatTag, StreamSpec};
+use phonic_signal::{Channels, KnownSampleType, SignalSpecBuilder};
+
+const MAGIC: &[u8; 4] = b".snd";
+
+/// The length of the fixed part of the header, which the annotation follows.
+const HEADER_LEN: u32 = 24;
+
+/// The data size of a file whose length wasn't known when its header was written.
+const UNKNOWN_LEN: u32 = u32::MAX;
+
+const MU_LAW_8: u32 = 1;
+const LINEAR_8: u32 = 2;
+const LINEAR_16: u32 = 3;
+const LINEAR_24: u32 = 4;
+const LINEAR_32: u32 = 5;
+const FLOAT: u32 = 6;
+const DOUBLE: u32 = 7;
+const A_LAW_8: u32 = 27;
+
+#[derive(Clone, Copy)]
+pub struct AuHeader {
+    /// The number of bytes from the start of the file to the first byte of sound data, which
+    /// includes the annotation after the fixed part of the header.
+    pub data_offset: u32,
+
+    /// The number of bytes of sound data, or `None` when the data runs on to the end of the file.
+    pub byte_len: Option<u32>,
+
+    pub encoding: u32,
+    pub sample_rate: u32,
+    pub n_channels: u16,
+}
+
+impl AuHeader {
+    pub fn read(reader: &mut impl Read) -> Result<Self, PhonicError> {
+        let mut buf = [0u8; HEADER_LEN as usize];
+        reader.read_exact(&mut buf)?;
+        if &buf[0..4] != MAGIC {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let field = |i: usize| u32::from_be_bytes(buf[i..i + 4].try_into().unwrap());
+        let data_offset = field(4);
+        if data_offset < HEADER_LEN {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let n_channels = u16::try_from(field(20))
+            .ok()
+            .filter(|&n| n > 0)
+            .ok_or(PhonicError::InvalidData)?;
+
+        // the annotation is free text, usually empty, and not needed to decode the sound data
+        let annotation_len = (data_offset - HEADER_LEN) as u64;
+        if io::copy(&mut reader.take(annotation_len), &mut io::sink())? < annotation_len {
+            return Err(PhonicError::EndOfStream);
+        }
+
+        Ok(Self {
+            data_offset,
+            byte_len: Some(field(8)).filter(|&len| len != UNKNOWN_LEN),
+            encoding: field(12),
+            sample_rate: field(16),
+            n_channels,
+        })
+    }
+
+    pub fn write(&self, writer: &mut impl Write) -> Result<(), PhonicError> {
+        if self.data_offset < HEADER_LEN {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let mut buf = [0u8; HEADER_LEN as usize];
+        buf[0..4].copy_from_slice(MAGIC);
+        buf[4..8].copy_from_slice(&self.data_offset.to_be_bytes());
+        buf[8..12].copy_from_slice(&self.byte_len.unwrap_or(UNKNOWN_LEN).to_be_bytes());
+        buf[12..16].copy_from_slice(&self.encoding.to_be_bytes());
+        buf[16..20].copy_from_slice(&self.sample_rate.to_be_bytes());
+        buf[20..24].copy_from_slice(&(self.n_channels as u32).to_be_bytes());
+        writer.write_all(&buf)?;
+
+        let annotation_len = (self.data_offset - HEADER_LEN) as u64;
+        io::copy(&mut io::repeat(0).take(annotation_len), writer)?;
+
+        Ok(())
+    }
+}
+
+impl<F> From<AuHeader> for FormatData<F>
+where
+    F: FormatTag,
+    AuFormatTag: TryInto<F>,
+    AuSupportedCodec: TryInto<F::Codec>,
+{
+    fn from(header: AuHeader) -> Self {
+        type T = KnownSampleType;
+
+        let (codec, sample_type) = match header.encoding {
+            MU_LAW_8 => (Some(AuSupportedCodec::MuLaw), Some(T::I16)),
+            A_LAW_8 => (Some(AuSupportedCodec::ALaw), Some(T::I16)),
+            LINEAR_8 => (Some(AuSupportedCodec::Pcm), Some(T::I8)),
+            LINEAR_16 => (Some(AuSupportedCodec::Pcm), Some(T::I16)),
+            LINEAR_24 => (Some(AuSupportedCodec::Pcm), Some(T::I24)),
+            LINEAR_32 => (Some(AuSupportedCodec::Pcm), Some(T::I32)),
+            FLOAT => (Some(AuSupportedCodec::Pcm), Some(T::F32)),
+            DOUBLE => (Some(AuSupportedCodec::Pcm), Some(T::F64)),
+            _ => (None, None),
+        };
+
+        let block_align = match (codec, sample_type) {
+            (Some(AuSupportedCodec::ALaw | AuSupportedCodec::MuLaw), _) => Some(header.n_channels),
+            (_, Some(sample_type)) => Some(sample_type.byte_size() as u16 * header.n_channels),
+            _ => None,
+        };
+
+        let n_frames = header
+            .byte_len
+            .zip(block_align)
+            .map(|(byte_len, align)| byte_len as u64 / align as u64);
+
+        Self {
+            format: AuFormatTag.try_into().ok(),
+            streams: vec![StreamSpec {
+                codec: codec.and_then(|codec| codec.try_into().ok()),
+                avg_bitrate: block_align
+                    .map(|align| header.sample_rate as f64 * align as f64 * 8.0),
+                block_align,
+                sample_type: sample_type.map(Into::into),
+                decoded_spec: SignalSpecBuilder::new()
+                    .with_channels(Channels::Count(header.n_channels))
+                    .with_frame_rate(header.sample_rate)
+                    .with_n_frames(n_frames),
+            }],
+            metadata: FormatMetadata::new(),
+        }
+    }
+}
+
+impl<F> TryFrom<&FormatData<F>> for AuHeader
+where
+    F: FormatTag,
+    F::Codec: TryInto<AuSupportedCodec>,
+{
+    type Error = PhonicError;
+
+    fn try_from(data: &FormatData<F>) -> Result<Self, Self::Error> {
+        if data.streams.len() != 1 {
+            return Err(PhonicError::Unsupported);
+        }
+
+        let spec = data.streams[0];
+        let sample_type: KnownSampleType = spec
+            .sample_type
+            .ok_or(PhonicError::MissingData)?
+            .try_into()?;
+
+        let codec = match spec.codec {
+            Some(codec) => codec.try_into().map_err(|_| PhonicError::Unsupported)?,
+            None => AuSupportedCodec::Pcm,
+        };
+
+        type T = KnownSampleType;
+
+        let (encoding, byte_size) = match (codec, sample_type) {
+            (AuSupportedCodec::Pcm, T::I8) => (LINEAR_8, 1),
+            (AuSupportedCodec::Pcm, T::I16) => (LINEAR_16, 2),
+            (AuSupportedCodec::Pcm, T::I24) => (LINEAR_24, 3),
+            (AuSupportedCodec::Pcm, T::I32) => (LINEAR_32, 4),
+            (AuSupportedCodec::Pcm, T::F32) => (FLOAT, 4),
+            (AuSupportedCodec::Pcm, T::F64) => (DOUBLE, 8),
+            (AuSupportedCodec::MuLaw, T::I16) => (MU_LAW_8, 1),
+            (AuSupportedCodec::ALaw, T::I16) => (A_LAW_8, 1),
+            _ => return Err(PhonicError::Unsupported),
+        };
+
+        let n_channels = spec
+            .decoded_spec
+            .channels
+            .ok_or(PhonicError::InvalidData)?
+            .count();
+
+        let sample_rate = spec
+            .decoded_spec
+            .frame_rate
+            .ok_or(PhonicError::InvalidData)?;
+
+        // a length that is unknown or doesn't fit the header is left for readers to find at the
+        // end of the file, which makes streaming writes possible
+        let byte_len = spec
+            .decoded_spec
+            .n_frames
+            .and_then(|n_frames| n_frames.checked_mul(byte_size * n_channels as u64))
+            .and_then(|byte_len| u32::try_from(byte_len).ok())
+            .filter(|&byte_len| byte_len != UNKNOWN_LEN);
+
+        Ok(Self {
+            data_offset: HEADER_LEN,
+            byte_len,
+            encoding,
+            sample_rate,
+            n_channels,
+        })
+    }
+}
@@ -0,0 +1,249 @@
+use phonic_core::PhonicError;
+use phonic_format_au::{AuFormat, AuFormatTag, AuHeader, AuSupportedCodec};
+use phonic_io_core::{
+    Format, FormatChunk, FormatData, FormatObserver, FormatOffset, FormatReader, FormatSeeker,
+    FormatWriter, StreamSpec,
+};
+use phonic_signal::{KnownSampleType, SignalSpecBuilder};
+use std::{any::TypeId, io::Cursor};
+
+fn header(encoding: u32, byte_len: Option<u32>) -> AuHeader {
+    AuHeader {
+        data_offset: 24,
+        byte_len,
+        encoding,
+        sample_rate: 8_000,
+        n_channels: 2,
+    }
+}
+
+fn file(data_offset: u32, byte_len: u32, annotation: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut file = b".snd".to_vec();
+    for field in [data_offset, byte_len, 3, 44_100, 1] {
+        file.extend_from_slice(&field.to_be_bytes());
+    }
+
+    file.extend_from_slice(annotation);
+    file.extend_from_slice(data);
+    file
+}
+
+fn stream_spec(
+    codec: AuSupportedCodec,
+    sample_type: KnownSampleType,
+    n_frames: Option<u64>,
+) -> StreamSpec<AuSupportedCodec> {
+    let decoded_spec = SignalSpecBuilder::new()
+        .with_frame_rate(22_050)
+        .with_channels(1)
+        .with_n_frames(n_frames);
+
+    let mut spec = StreamSpec::new()
+        .with_codec(codec)
+        .with_decoded_spec(decoded_spec);
+
+    spec.sample_type = Some(sample_type.into());
+    spec.filled().unwrap()
+}
+
+#[test]
+fn header_fields_are_big_endian() {
+    let mut file = Vec::new();
+    header(3, Some(400)).write(&mut file).unwrap();
+
+    assert_eq!(
+        file,
+        [
+            b'.', b's', b'n', b'd', 0, 0, 0, 24, 0, 0, 1, 0x90, 0, 0, 0, 3, 0, 0, 0x1f, 0x40, 0, 0,
+            0, 2
+        ]
+    );
+
+    let header = AuHeader::read(&mut file.as_slice()).unwrap();
+    assert_eq!(header.data_offset, 24);
+    assert_eq!(header.byte_len, Some(400));
+    assert_eq!(header.sample_rate, 8_000);
+    assert_eq!(header.n_channels, 2);
+}
+
+#[test]
+fn encodings_are_read() {
+    type T = KnownSampleType;
+
+    for (encoding, codec, sample_type, block_align, n_frames) in [
+        (1, AuSupportedCodec::MuLaw, T::I16, 2, 200),
+        (27, AuSupportedCodec::ALaw, T::I16, 2, 200),
+        (2, AuSupportedCodec::Pcm, T::I8, 2, 200),
+        (3, AuSupportedCodec::Pcm, T::I16, 4, 100),
+        (4, AuSupportedCodec::Pcm, T::I24, 6, 66),
+        (5, AuSupportedCodec::Pcm, T::I32, 8, 50),
+        (6, AuSupportedCodec::Pcm, T::F32, 8, 50),
+        (7, AuSupportedCodec::Pcm, T::F64, 16, 25),
+    ] {
+        let data = FormatData::<AuFormatTag>::from(header(encoding, Some(400)));
+        let spec = data.streams[0];
+        assert!(spec.codec == Some(codec));
+        assert_eq!(spec.sample_type, Some(sample_type.into()));
+        assert_eq!(spec.block_align, Some(block_align));
+        assert_eq!(spec.decoded_spec.n_frames, Some(n_frames));
+    }
+
+    // adpcm and the other encodings of the format have no codec
+    let data = FormatData::<AuFormatTag>::from(header(23, Some(400)));
+    assert!(data.streams[0].codec.is_none());
+    assert_eq!(data.streams[0].decoded_spec.n_frames, None);
+}
+
+#[test]
+fn annotation_is_skipped() {
+    let file = file(32, 4, b"level 1\0", &[1, 2, 3, 4]);
+
+    let mut format = AuFormat::<_, AuFormatTag>::new(Cursor::new(file)).unwrap();
+    format.read_data().unwrap();
+
+    let spec = format.data().streams[0];
+    assert_eq!(spec.decoded_spec.frame_rate, Some(44_100));
+    assert_eq!(spec.decoded_spec.n_frames, Some(2));
+    assert_eq!(spec.sample_type, Some(TypeId::of::<i16>()));
+
+    let mut buf = [0; 16];
+    let FormatChunk::Stream { buf, .. } = format.read(&mut buf).unwrap();
+    assert_eq!(buf, [1, 2, 3, 4]);
+}
+
+#[test]
+fn data_of_unknown_length_runs_to_the_end() {
+    let data = (0..10).collect::<Vec<u8>>();
+    let file = file(24, u32::MAX, &[], &data);
+
+    let mut format = AuFormat::<_, AuFormatTag>::new(Cursor::new(file)).unwrap();
+    format.read_data().unwrap();
+    assert_eq!(format.data().streams[0].decoded_spec.n_frames, None);
+
+    let mut buf = [0; 16];
+    let FormatChunk::Stream { buf, .. } = format.read(&mut buf).unwrap();
+    assert_eq!(buf, data);
+
+    format
+        .seek(FormatOffset {
+            stream_offset: 0,
+            byte_offset: -4,
+        })
+        .unwrap();
+    assert_eq!(format.position().unwrap().byte_i, 6);
+
+    // seeking past the end stops at the last frame
+    format
+        .seek(FormatOffset {
+            stream_offset: 0,
+            byte_offset: 100,
+        })
+        .unwrap();
+    assert_eq!(format.position().unwrap().byte_i, 10);
+}
+
+#[test]
+fn invalid_headers_are_rejected() {
+    let valid = file(24, 0, &[], &[]);
+    assert!(AuHeader::read(&mut valid.as_slice()).is_ok());
+
+    let mut magic = valid.clone();
+    magic[1] = b'S';
+    assert_eq!(
+        AuHeader::read(&mut magic.as_slice()).err(),
+        Some(PhonicError::InvalidData)
+    );
+
+    // the data can't start inside the header
+    let offset = file(16, 0, &[], &[]);
+    assert_eq!(
+        AuHeader::read(&mut offset.as_slice()).err(),
+        Some(PhonicError::InvalidData)
+    );
+
+    let mut no_channels = valid.clone();
+    no_channels[23] = 0;
+    assert_eq!(
+        AuHeader::read(&mut no_channels.as_slice()).err(),
+        Some(PhonicError::InvalidData)
+    );
+
+    let truncated = file(32, 0, b"abc", &[]);
+    assert_eq!(
+        AuHeader::read(&mut truncated.as_slice()).err(),
+        Some(PhonicError::EndOfStream)
+    );
+}
+
+#[test]
+fn written_files_are_read_back() {
+    for (codec, sample_type, data) in [
+        (AuSupportedCodec::Pcm, KnownSampleType::I8, vec![1, 2, 3]),
+        (AuSupportedCodec::ALaw, KnownSampleType::I16, vec![1, 2, 3]),
+        (
+            AuSupportedCodec::Pcm,
+            KnownSampleType::F64,
+            (0..24).collect(),
+        ),
+    ] {
+        for n_frames in [Some(3), None] {
+            let spec = stream_spec(codec, sample_type, n_frames);
+            let mut format = AuFormat::<_, AuFormatTag>::new(Cursor::new(Vec::new())).unwrap();
+            format
+                .write_data(&FormatData::new().with_stream(spec))
+                .unwrap();
+            format
+                .write(FormatChunk::Stream {
+                    stream_i: 0,
+                    buf: &data,
+                })
+                .unwrap();
+
+            format.flush().unwrap();
+            let file = format.into_inner().into_inner();
+            assert_eq!(file.len(), 24 + data.len());
+
+            // files written without a length say so in the header
+            let byte_len = u32::from_be_bytes(file[8..12].try_into().unwrap());
+            assert_eq!(byte_len, n_frames.map_or(u32::MAX, |_| data.len() as u32));
+
+            let mut format = AuFormat::<_, AuFormatTag>::new(Cursor::new(file)).unwrap();
+            format.read_data().unwrap();
+            let read_spec = format.data().streams[0];
+            assert!(read_spec.codec == spec.codec);
+            assert_eq!(read_spec.sample_type, spec.sample_type);
+            assert_eq!(read_spec.block_align, spec.block_align);
+            assert_eq!(read_spec.decoded_spec.n_frames, n_frames);
+
+            let mut buf = [0; 32];
+            let FormatChunk::Stream { buf, .. } = format.read(&mut buf).unwrap();
+            assert_eq!(buf, data);
+
+            format
+                .seek(FormatOffset {
+                    stream_offset: 0,
+                    byte_offset: -(data.len() as i64),
+                })
+                .unwrap();
+
+            assert_eq!(format.position().unwrap().byte_i, 0);
+        }
+    }
+}
+
+#[test]
+fn unsigned_samples_are_not_written() {
+    let mut spec = StreamSpec::<AuSupportedCodec>::new()
+        .with_codec(AuSupportedCodec::Pcm)
+        .with_decoded_spec(
+            SignalSpecBuilder::new()
+                .with_frame_rate(8_000)
+                .with_channels(1),
+        );
+
+    spec.sample_type = Some(KnownSampleType::U8.into());
+    let data = FormatData::new().with_stream(spec);
+
+    let mut format = AuFormat::<_, AuFormatTag>::new(Cursor::new(Vec::new())).unwrap();
+    assert_eq!(format.write_data(&data), Err(PhonicError::Unsupported));
+}
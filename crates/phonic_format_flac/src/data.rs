@@ -0,0 +1,54 @@
+use phonic_codec_flac::FlacCodecTag;
+use phonic_core::PhonicError;
+use phonic_io_core::{
+    utils::{FormatIdentifiers, FormatMarker},
+    FormatData, FormatTag, StreamSpec,
+};
+
+pub static FLAC_IDENTIFIERS: FormatIdentifiers = FormatIdentifiers {
+    file_extensions: &["flac"],
+    mime_types: &["audio/flac", "audio/x-flac"],
+    markers: &[FormatMarker {
+        offset: 0,
+        bytes: b"fLaC",
+    }],
+};
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub struct FlacFormatTag;
+
+pub fn fill_flac_data<F>(data: &mut FormatData<F>) -> Result<(), PhonicError>
+where
+    F: FormatTag,
+    FlacFormatTag: TryInto<F>,
+    FlacCodecTag: TryInto<F::Codec>,
+{
+    let expected_format = FlacFormatTag.try_into().ok();
+    if data.format.is_some() && data.format != expected_format {
+        return Err(PhonicError::InvalidData);
+    } else {
+        data.format = expected_format;
+    }
+
+    if data.streams.is_empty() {
+        data.streams.push(StreamSpec::new());
+    }
+
+    let [spec] = data.streams.as_mut_slice() else {
+        return Err(PhonicError::Unsupported);
+    };
+
+    if spec.codec.is_none() {
+        spec.codec = FlacCodecTag::new().try_into().ok();
+    }
+
+    spec.fill()
+}
+
+impl FormatTag for FlacFormatTag {
+    type Codec = FlacCodecTag;
+
+    fn fill_data(data: &mut FormatData<Self>) -> Result<(), PhonicError> {
+        fill_flac_data(data)
+    }
+}
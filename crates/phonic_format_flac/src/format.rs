@@ -0,0 +1,204 @@
+use crate::FlacFormatTag;
+use std::io::{Read, Seek, SeekFrom, Write};
+use phonic_codec_flac::{FlacCodecTag, FlacMetadata};
+use phonic_core::PhonicError;
+use phonic_io_core::{
+    Format, FormatChunk, FormatData, FormatObserver, FormatOffset, FormatPosition, FormatReader,
+    FormatSeeker, FormatTag, FormatWriter,
+};
+
+/// A native flac file. The codec needs the metadata blocks as well as the coded blocks, so the
+/// stream covers the whole file. When reading, the metadata is parsed for the format data and
+/// then passed on to the stream from memory.
+pub struct FlacFormat<T, F: FormatTag = FlacFormatTag> {
+    inner: T,
+    i: usize,
+    header: Vec<u8>,
+    metadata: Option<FlacMetadata>,
+    data: FormatData<F>,
+}
+
+/// Keeps a copy of everything read through it.
+struct RecordingReader<'a, R> {
+    inner: &'a mut R,
+    bytes: Vec<u8>,
+}
+
+impl<R: Read> Read for RecordingReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.bytes.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+}
+
+impl<T, F: FormatTag> FlacFormat<T, F> {
+    pub fn new(inner: T) -> Result<Self, PhonicError>
+    where
+        FlacFormatTag: TryInto<F>,
+    {
+        let mut data = FormatData::new();
+        data.format = FlacFormatTag.try_into().ok();
+        Ok(Self {
+            inner,
+            i: 0,
+            header: Vec::new(),
+            metadata: None,
+            data,
+        })
+    }
+
+    /// Returns the metadata that was read from the start of the file.
+    pub fn metadata(&self) -> Option<&FlacMetadata> {
+        self.metadata.as_ref()
+    }
+
+    pub fn as_inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Moves a byte index that lands among the coded blocks back to the closest seek point, so
+    /// that decoding can resume from the start of a block. Files without a seek table are left
+    /// to the codec, which finds the next block itself.
+    fn snap_to_seek_point(&self, byte_i: u64) -> u64 {
+        let Some(metadata) = &self.metadata else {
+            return byte_i;
+        };
+
+        let offsets = metadata
+            .seek_table
+            .iter()
+            .filter(|point| !point.is_placeholder())
+            .map(|point| metadata.frames_offset + point.byte_offset);
+
+        let mut offsets = offsets.peekable();
+        if offsets.peek().is_none() || byte_i < metadata.frames_offset {
+            return byte_i;
+        }
+
+        offsets
+            .filter(|offset| *offset <= byte_i)
+            .fold(metadata.frames_offset, u64::max)
+    }
+}
+
+impl<T, F: FormatTag> Format for FlacFormat<T, F> {
+    type Tag = F;
+
+    fn data(&self) -> &FormatData<Self::Tag> {
+        &self.data
+    }
+}
+
+impl<T, F: FormatTag> FormatObserver for FlacFormat<T, F> {
+    fn position(&self) -> Result<FormatPosition, PhonicError> {
+        Ok(FormatPosition {
+            stream_i: 0,
+            byte_i: self.i as u64,
+        })
+    }
+}
+
+impl<T: Read, F: FormatTag> FormatReader for FlacFormat<T, F>
+where
+    FlacFormatTag: TryInto<F>,
+    FlacCodecTag: TryInto<F::Codec>,
+{
+    fn read_data(&mut self) -> Result<(), PhonicError> {
+        if self.metadata.is_some() {
+            return Ok(());
+        }
+
+        let mut reader = RecordingReader {
+            inner: &mut self.inner,
+            bytes: Vec::new(),
+        };
+
+        let metadata = FlacMetadata::read(&mut reader)?;
+        let data = FormatData::new().with_stream(metadata.stream_info.stream_spec());
+
+        self.data.merge(&data)?;
+        self.header = reader.bytes;
+        self.metadata = Some(metadata);
+
+        Ok(())
+    }
+
+    fn read<'a>(&'a mut self, buf: &'a mut [u8]) -> Result<FormatChunk<'a>, PhonicError> {
+        if self.metadata.is_none() {
+            self.read_data()?;
+        }
+
+        let n = match self.header.get(self.i..) {
+            Some(header) if !header.is_empty() => {
+                let n = buf.len().min(header.len());
+                buf[..n].copy_from_slice(&header[..n]);
+                n
+            }
+            _ => self.inner.read(buf)?,
+        };
+
+        self.i += n;
+        Ok(FormatChunk::Stream {
+            stream_i: 0,
+            buf: &buf[..n],
+        })
+    }
+}
+
+impl<T: Write, F: FormatTag> FormatWriter for FlacFormat<T, F>
+where
+    F::Codec: TryInto<FlacCodecTag>,
+{
+    fn write_data(&mut self, data: &FormatData<F>) -> Result<(), PhonicError> {
+        self.data.merge(data)?;
+
+        // the codec writes the metadata blocks at the start of the stream
+        match self.data.streams.as_slice() {
+            [spec] if spec.codec.is_some_and(|codec| codec.try_into().is_ok()) => Ok(()),
+            _ => Err(PhonicError::Unsupported),
+        }
+    }
+
+    fn write(&mut self, chunk: FormatChunk) -> Result<(), PhonicError> {
+        match chunk {
+            FormatChunk::Stream { stream_i, buf } if !self.data.is_empty() && stream_i == 0 => {
+                self.inner.write_all(buf)?;
+                self.i += buf.len();
+            }
+            _ => return Err(PhonicError::InvalidData),
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), PhonicError> {
+        self.inner.flush().map_err(Into::into)
+    }
+}
+
+impl<T: Seek, F: FormatTag> FormatSeeker for FlacFormat<T, F> {
+    fn seek(&mut self, offset: FormatOffset) -> Result<(), PhonicError> {
+        if offset.stream_offset != 0 {
+            return Err(PhonicError::NotFound);
+        }
+
+        if self.data.is_empty() {
+            return Err(PhonicError::NotReady);
+        }
+
+        let byte_i = (self.i as i64).saturating_add(offset.byte_offset).max(0) as u64;
+        let byte_i = self.snap_to_seek_point(byte_i);
+
+        // the metadata is passed on from memory, so the file stays past it
+        let inner_i = byte_i.max(self.header.len() as u64);
+        self.inner.seek(SeekFrom::Start(inner_i))?;
+        self.i = byte_i as usize;
+
+        Ok(())
+    }
+}
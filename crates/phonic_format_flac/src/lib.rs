@@ -0,0 +1,5 @@
+mod data;
+mod format;
+
+pub use data::*;
+pub use format::*;
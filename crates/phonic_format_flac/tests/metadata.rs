@@ -0,0 +1,178 @@
+use phonic_codec_flac::{FlacCodecTag, FlacMetadata, SeekPoint, StreamInfo};
+use phonic_core::PhonicError;
+use phonic_format_flac::{FlacFormat, FlacFormatTag};
+use phonic_io_core::{
+    Format, FormatChunk, FormatData, FormatObserver, FormatOffset, FormatReader, FormatSeeker,
+    FormatWriter, StreamSpec,
+};
+use phonic_signal::SignalSpecBuilder;
+use std::{any::TypeId, io::Cursor};
+
+const BLOCKS_LEN: usize = 3_000;
+
+fn stream_info(bits_per_sample: u8) -> StreamInfo {
+    StreamInfo {
+        min_block_size: 4_096,
+        max_block_size: 4_096,
+        min_block_len: 0,
+        max_block_len: 0,
+        sample_rate: 44_100,
+        n_channels: 2,
+        bits_per_sample,
+        n_frames: Some(10_000),
+        md5: [0; 16],
+    }
+}
+
+fn file(seek_table: Vec<SeekPoint>) -> Vec<u8> {
+    let metadata = FlacMetadata::new(stream_info(16)).with_seek_table(seek_table);
+
+    let mut file = Vec::new();
+    metadata.write(&mut file).unwrap();
+    file.extend((0..BLOCKS_LEN).map(|i| i as u8));
+    file
+}
+
+fn read_to_end<T: std::io::Read>(format: &mut FlacFormat<T>) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut buf = [0; 700];
+    loop {
+        match format.read(&mut buf).unwrap() {
+            FormatChunk::Stream {
+                stream_i: 0,
+                buf: [],
+            } => return out,
+            FormatChunk::Stream { stream_i: 0, buf } => out.extend_from_slice(buf),
+            _ => panic!("unexpected chunk"),
+        }
+    }
+}
+
+#[test]
+fn stream_info_is_read() {
+    let file = file(Vec::new());
+    let mut format = FlacFormat::<_>::new(Cursor::new(file.clone())).unwrap();
+    format.read_data().unwrap();
+
+    let data = format.data();
+    assert!(data.format == Some(FlacFormatTag));
+
+    let [spec] = data.streams.as_slice() else {
+        panic!("expected one stream");
+    };
+
+    assert_eq!(spec.codec, Some(FlacCodecTag::new()));
+    assert_eq!(spec.sample_type, Some(TypeId::of::<i16>()));
+    assert_eq!(spec.decoded_spec.frame_rate, Some(44_100));
+    assert_eq!(spec.decoded_spec.n_frames, Some(10_000));
+
+    let metadata = format.metadata().unwrap();
+    assert_eq!(metadata.stream_info, stream_info(16));
+    assert_eq!(metadata.frames_offset as usize, file.len() - BLOCKS_LEN);
+
+    let mut file = Vec::new();
+    FlacMetadata::new(stream_info(20)).write(&mut file).unwrap();
+
+    let mut format = FlacFormat::<_>::new(Cursor::new(file)).unwrap();
+    format.read_data().unwrap();
+    assert_eq!(
+        format.data().streams[0].sample_type,
+        Some(TypeId::of::<phonic_signal::I24>())
+    );
+}
+
+#[test]
+fn metadata_is_passed_on_to_the_stream() {
+    let file = file(Vec::new());
+    let mut format = FlacFormat::<_>::new(Cursor::new(file.clone())).unwrap();
+    assert_eq!(read_to_end(&mut format), file);
+
+    let mut format = FlacFormat::<_>::new(Cursor::new(b"RIFF....WAVE".to_vec())).unwrap();
+    assert!(matches!(format.read_data(), Err(PhonicError::InvalidData)));
+}
+
+#[test]
+fn seeks_snap_to_seek_points() {
+    let points = vec![
+        SeekPoint {
+            frame_i: 0,
+            byte_offset: 0,
+            n_frames: 4_096,
+        },
+        SeekPoint {
+            frame_i: 4_096,
+            byte_offset: 1_000,
+            n_frames: 4_096,
+        },
+        SeekPoint::placeholder(),
+    ];
+
+    let file = file(points);
+    let frames_offset = (file.len() - BLOCKS_LEN) as i64;
+    let mut format = FlacFormat::<_>::new(Cursor::new(file.clone())).unwrap();
+    format.read_data().unwrap();
+
+    for (target, expected) in [
+        (frames_offset + 1_500, frames_offset + 1_000),
+        (frames_offset + 999, frames_offset),
+        (frames_offset + 2_999, frames_offset + 1_000),
+        (10, 10),
+    ] {
+        let byte_i = format.position().unwrap().byte_i as i64;
+        format
+            .seek(FormatOffset {
+                stream_offset: 0,
+                byte_offset: target - byte_i,
+            })
+            .unwrap();
+
+        assert_eq!(format.position().unwrap().byte_i as i64, expected);
+        assert_eq!(read_to_end(&mut format), file[expected as usize..]);
+    }
+
+    let file = self::file(Vec::new());
+    let mut format = FlacFormat::<_>::new(Cursor::new(file.clone())).unwrap();
+    format.read_data().unwrap();
+    format
+        .seek(FormatOffset {
+            stream_offset: 0,
+            byte_offset: frames_offset + 1_500,
+        })
+        .unwrap();
+
+    assert_eq!(
+        read_to_end(&mut format),
+        file[frames_offset as usize + 1_500..]
+    );
+}
+
+#[test]
+fn streams_are_written_as_they_are() {
+    let decoded_spec = SignalSpecBuilder::new()
+        .with_frame_rate(48_000)
+        .with_channels(1);
+
+    let data = FormatData::new()
+        .with_format(FlacFormatTag)
+        .with_stream(StreamSpec::new().with_decoded_spec(decoded_spec))
+        .filled()
+        .unwrap();
+
+    assert_eq!(data.streams[0].codec, Some(FlacCodecTag::new()));
+    assert_eq!(data.streams[0].sample_type, Some(TypeId::of::<i16>()));
+
+    let file = file(Vec::new());
+    let mut format = FlacFormat::<_>::new(Cursor::new(Vec::new())).unwrap();
+    format.write_data(&data).unwrap();
+    for chunk in file.chunks(1_000) {
+        format
+            .write(FormatChunk::Stream {
+                stream_i: 0,
+                buf: chunk,
+            })
+            .unwrap();
+    }
+
+    FormatWriter::flush(&mut format).unwrap();
+    assert_eq!(format.into_inner().into_inner(), file);
+}
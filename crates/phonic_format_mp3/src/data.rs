@@ -0,0 +1,82 @@
+use phonic_codec_mp3::Mp3CodecTag;
+use phonic_core::PhonicError;
+use phonic_io_core::{
+    utils::{FormatIdentifiers, FormatMarker},
+    FormatData, FormatTag, StreamSpec,
+};
+
+/// Files either start with an ID3v2 tag or directly with the sync word of a layer III frame,
+/// with or without a checksum.
+pub static MP3_IDENTIFIERS: FormatIdentifiers = FormatIdentifiers {
+    file_extensions: &["mp3"],
+    mime_types: &["audio/mpeg", "audio/mp3"],
+    markers: &[
+        FormatMarker {
+            offset: 0,
+            bytes: b"ID3",
+        },
+        FormatMarker {
+            offset: 0,
+            bytes: b"\xff\xfb",
+        },
+        FormatMarker {
+            offset: 0,
+            bytes: b"\xff\xfa",
+        },
+        FormatMarker {
+            offset: 0,
+            bytes: b"\xff\xf3",
+        },
+        FormatMarker {
+            offset: 0,
+            bytes: b"\xff\xf2",
+        },
+        FormatMarker {
+            offset: 0,
+            bytes: b"\xff\xe3",
+        },
+        FormatMarker {
+            offset: 0,
+            bytes: b"\xff\xe2",
+        },
+    ],
+};
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub struct Mp3FormatTag;
+
+pub fn fill_mp3_data<F>(data: &mut FormatData<F>) -> Result<(), PhonicError>
+where
+    F: FormatTag,
+    Mp3FormatTag: TryInto<F>,
+    Mp3CodecTag: TryInto<F::Codec>,
+{
+    let expected_format = Mp3FormatTag.try_into().ok();
+    if data.format.is_some() && data.format != expected_format {
+        return Err(PhonicError::InvalidData);
+    } else {
+        data.format = expected_format;
+    }
+
+    if data.streams.is_empty() {
+        data.streams.push(StreamSpec::new());
+    }
+
+    let [spec] = data.streams.as_mut_slice() else {
+        return Err(PhonicError::Unsupported);
+    };
+
+    if spec.codec.is_none() {
+        spec.codec = Mp3CodecTag::new().try_into().ok();
+    }
+
+    spec.fill()
+}
+
+impl FormatTag for Mp3FormatTag {
+    type Codec = Mp3CodecTag;
+
+    fn fill_data(data: &mut FormatData<Self>) -> Result<(), PhonicError> {
+        fill_mp3_data(data)
+    }
+}
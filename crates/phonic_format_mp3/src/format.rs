@@ -0,0 +1,444 @@
+use crate::{Mp3FormatTag, VbriHeader, XingHeader};
+use std::io::{Read, Seek, Write};
+use phonic_codec_mp3::{FrameHeader, Mp3CodecTag};
+use phonic_core::PhonicError;
+use phonic_io_core::{
+    utils::LookaheadReader, Format, FormatChunk, FormatData, FormatObserver, FormatOffset,
+    FormatPosition, FormatReader, FormatSeeker, FormatTag, FormatWriter, StreamSpec,
+};
+use phonic_signal::SignalSpecBuilder;
+
+const ID3_HEADER_LEN: usize = 10;
+const SCAN_LEN: usize = 4096;
+
+/// An mp3 file, which is a run of frames that each start with a header, after any number of
+/// ID3v2 tags. Each chunk holds one frame, or as much of it as fits.
+///
+/// The stream is positioned by frame, counting the frames of samples that the frames read so far
+/// decode to. The first frame may hold a Xing or VBRI header in place of audio, which gives the
+/// length of the stream and, with a LAME tag, the delay and padding the encoder added. That frame
+/// is left out of the stream. Reading only reads the inner reader in order. Seeking reads the
+/// headers of the frames up to the target to find the frame that holds it, and keeps their
+/// offsets so that they are only read once.
+///
+/// Bytes that aren't part of a frame are skipped over, looking for a header that the next frame
+/// follows. Writing passes the frames on as they are.
+pub struct Mp3Format<T, F: FormatTag = Mp3FormatTag> {
+    inner: LookaheadReader<T>,
+    header: Option<FrameHeader>,
+    xing: Option<XingHeader>,
+    vbri: Option<VbriHeader>,
+    frame: Vec<u8>,
+    frame_start: usize,
+    frame_i: u64,
+    next_i: u64,
+    frame_offsets: Vec<u64>,
+    index_end: u64,
+    indexed: bool,
+    n_written: u64,
+    data: FormatData<F>,
+}
+
+/// Returns the length of an ID3v2 tag from its header, counting the header and footer.
+fn id3_len(header: &[u8; ID3_HEADER_LEN]) -> u64 {
+    let size = header[6..]
+        .iter()
+        .fold(0, |size, &byte| (size << 7) | (byte & 0x7f) as u64);
+
+    let footer_len = match header[5] & 0x10 {
+        0 => 0,
+        _ => ID3_HEADER_LEN as u64,
+    };
+
+    ID3_HEADER_LEN as u64 + size + footer_len
+}
+
+impl<T, F: FormatTag> Mp3Format<T, F> {
+    pub fn new(inner: T) -> Result<Self, PhonicError>
+    where
+        Mp3FormatTag: TryInto<F>,
+    {
+        let mut data = FormatData::new();
+        data.format = Mp3FormatTag.try_into().ok();
+        Ok(Self {
+            inner: LookaheadReader::new(inner),
+            header: None,
+            xing: None,
+            vbri: None,
+            frame: Vec::new(),
+            frame_start: 0,
+            frame_i: 0,
+            next_i: 0,
+            frame_offsets: Vec::new(),
+            index_end: 0,
+            indexed: false,
+            n_written: 0,
+            data,
+        })
+    }
+
+    /// Returns the Xing header of the first frame, if it has one.
+    pub fn xing_header(&self) -> Option<&XingHeader> {
+        self.xing.as_ref()
+    }
+
+    /// Returns the VBRI header of the first frame, if it has one.
+    pub fn vbri_header(&self) -> Option<&VbriHeader> {
+        self.vbri.as_ref()
+    }
+
+    pub fn as_inner(&self) -> &T {
+        self.inner.as_inner()
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner()
+    }
+
+    /// Whether a frame with this header can be part of the stream.
+    fn accepts(&self, header: &FrameHeader) -> bool {
+        header.frame_len().is_some() && self.header.is_none_or(|h| h.is_compatible(header))
+    }
+
+    /// The number of frames of samples that each frame of the stream decodes to.
+    fn n_packet_frames(&self) -> u64 {
+        self.header.map_or(0, |header| header.n_frames() as u64)
+    }
+}
+
+impl<T: Read, F: FormatTag> Mp3Format<T, F> {
+    /// Reads the bytes a frame header at `offset` would take, returning `None` at the end of the
+    /// file.
+    fn header_at(&mut self, offset: u64) -> Result<Option<[u8; FrameHeader::LEN]>, PhonicError> {
+        let mut bytes = [0; FrameHeader::LEN];
+        match self.inner.read_at(offset, &mut bytes)? {
+            FrameHeader::LEN => Ok(Some(bytes)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Whether a frame is followed by another frame of the stream or by the end of the file.
+    fn is_followed(&mut self, offset: u64, header: &FrameHeader) -> Result<bool, PhonicError> {
+        let next_i = offset + header.frame_len().unwrap_or(0) as u64;
+        Ok(match self.header_at(next_i)? {
+            Some(bytes) => FrameHeader::read(&bytes).is_ok_and(|next| header.is_compatible(&next)),
+            None => true,
+        })
+    }
+
+    /// Returns the offset of the first header at or after `offset` that a frame of the stream
+    /// could start with.
+    fn find_sync(&mut self, offset: u64) -> Result<Option<u64>, PhonicError> {
+        let mut buf = [0; SCAN_LEN];
+        let mut offset = offset;
+        loop {
+            let n = self.inner.read_at(offset, &mut buf)?;
+            let found = buf[..n]
+                .windows(FrameHeader::LEN)
+                .position(|bytes| FrameHeader::read(bytes).is_ok_and(|h| self.accepts(&h)));
+
+            if let Some(i) = found {
+                return Ok(Some(offset + i as u64));
+            }
+
+            if n < buf.len() {
+                return Ok(None);
+            }
+
+            // a header may straddle the end of the buffer
+            offset += (n - (FrameHeader::LEN - 1)) as u64;
+        }
+    }
+
+    /// Returns the first frame at or after `offset`. A frame right at `offset` is taken as it is,
+    /// unless `checked` is set, while one found past anything else has to be followed by another
+    /// frame so that stray sync words aren't mistaken for frames.
+    fn find_frame(
+        &mut self,
+        offset: u64,
+        checked: bool,
+    ) -> Result<Option<(u64, FrameHeader)>, PhonicError> {
+        let mut offset = offset;
+        let mut checked = checked;
+        loop {
+            let Some(bytes) = self.header_at(offset)? else {
+                return Ok(None);
+            };
+
+            if let Some(header) = FrameHeader::read(&bytes).ok().filter(|h| self.accepts(h)) {
+                if !checked || self.is_followed(offset, &header)? {
+                    return Ok(Some((offset, header)));
+                }
+            }
+
+            checked = true;
+            match self.find_sync(offset + 1)? {
+                Some(sync_i) => offset = sync_i,
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Reads the whole frame that starts at `offset` into the frame buffer, returning false if
+    /// the file ends within it.
+    fn load_frame(&mut self, offset: u64, header: &FrameHeader) -> Result<bool, PhonicError> {
+        let len = header.frame_len().unwrap_or(0);
+        let mut frame = std::mem::take(&mut self.frame);
+        frame.resize(len, 0);
+
+        let n = self.inner.read_at(offset, &mut frame)?;
+        self.frame = frame;
+        self.frame_start = 0;
+
+        Ok(n == len)
+    }
+
+    /// Adds the offset of a frame to the index if it is the next one that the index is missing.
+    fn index_frame(&mut self, frame_i: u64, offset: u64, header: &FrameHeader) {
+        if frame_i == self.frame_offsets.len() as u64 && !self.indexed {
+            self.frame_offsets.push(offset);
+            self.index_end = offset + header.frame_len().unwrap_or(0) as u64;
+        }
+    }
+
+    /// Reads the next frame into the frame buffer, returning false at the end of the stream.
+    fn read_frame(&mut self) -> Result<bool, PhonicError> {
+        let found = match self.find_frame(self.next_i, false)? {
+            Some((offset, header)) if self.load_frame(offset, &header)? => Some((offset, header)),
+            _ => None,
+        };
+
+        let Some((offset, header)) = found else {
+            self.frame.clear();
+            self.frame_start = 0;
+            return Ok(false);
+        };
+
+        self.index_frame(self.frame_i, offset, &header);
+        self.next_i = offset + self.frame.len() as u64;
+        self.inner.release(self.next_i);
+        Ok(true)
+    }
+}
+
+impl<T: Read + Seek, F: FormatTag> Mp3Format<T, F> {
+    /// Reads the headers of the frames the index is missing up to `frame_i`, returning the index
+    /// of the frame to resume from, which is `frame_i` or the end of the stream if that comes
+    /// first.
+    fn index_to(&mut self, frame_i: u64) -> Result<u64, PhonicError> {
+        while self.frame_offsets.len() as u64 <= frame_i && !self.indexed {
+            match self.find_frame(self.index_end, false)? {
+                Some((offset, header)) => {
+                    self.index_frame(self.frame_offsets.len() as u64, offset, &header)
+                }
+                None => self.indexed = true,
+            }
+        }
+
+        Ok(frame_i.min(self.frame_offsets.len() as u64))
+    }
+}
+
+impl<T, F: FormatTag> Format for Mp3Format<T, F> {
+    type Tag = F;
+
+    fn data(&self) -> &FormatData<Self::Tag> {
+        &self.data
+    }
+}
+
+impl<T, F: FormatTag> FormatObserver for Mp3Format<T, F> {
+    fn position(&self) -> Result<FormatPosition, PhonicError> {
+        let byte_i = match self.header {
+            Some(_) => self.frame_i * self.n_packet_frames(),
+            None => self.n_written,
+        };
+
+        Ok(FormatPosition {
+            stream_i: 0,
+            byte_i,
+        })
+    }
+}
+
+impl<T: Read, F: FormatTag> FormatReader for Mp3Format<T, F>
+where
+    Mp3FormatTag: TryInto<F>,
+    Mp3CodecTag: TryInto<F::Codec>,
+{
+    fn read_data(&mut self) -> Result<(), PhonicError> {
+        if self.header.is_some() {
+            return Ok(());
+        }
+
+        let mut offset = 0;
+        let mut id3_header = [0; ID3_HEADER_LEN];
+        while self.inner.read_at(offset, &mut id3_header)? == ID3_HEADER_LEN
+            && id3_header.starts_with(b"ID3")
+        {
+            // the tags are skipped rather than kept
+            offset += id3_len(&id3_header);
+            self.inner.release(offset);
+        }
+
+        let (offset, header) = self
+            .find_frame(offset, true)?
+            .ok_or(PhonicError::InvalidData)?;
+
+        if !self.load_frame(offset, &header)? {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let xing = XingHeader::read(&self.frame, &header);
+        let vbri = VbriHeader::read(&self.frame);
+        let start_i = match xing.is_some() || vbri.is_some() {
+            true => offset + self.frame.len() as u64,
+            false => offset,
+        };
+
+        // the counts of the headers leave out the frame they are in, and a LAME tag counts the
+        // frames the encoder added, which the codec drops again along with its own delay
+        let n_packet_frames = header.n_frames() as u64;
+        let lame = xing.as_ref().and_then(|xing| xing.lame);
+        let n_tag_frames = match (&xing, &vbri) {
+            (Some(xing), _) => xing.n_frames,
+            (None, Some(vbri)) => Some(vbri.n_frames),
+            (None, None) => None,
+        };
+
+        let n_frames = n_tag_frames.map(|n| {
+            let n_frames = n as u64 * n_packet_frames;
+            lame.map_or(n_frames, |lame| {
+                n_frames.saturating_sub(lame.delay as u64 + lame.padding as u64)
+            })
+        });
+
+        let n_tag_bytes = match (&xing, &vbri) {
+            (Some(xing), _) => xing.n_bytes,
+            (None, Some(vbri)) => Some(vbri.n_bytes),
+            (None, None) => None,
+        };
+
+        let avg_bitrate = match (n_tag_bytes, n_tag_frames) {
+            (Some(n_bytes), Some(n)) if n > 0 => {
+                n_bytes as f64 * 8.0 * header.frame_rate as f64
+                    / (n as u64 * n_packet_frames) as f64
+            }
+            _ => header.bitrate() as f64 * 1000.0,
+        };
+
+        let delay = lame.map_or(0, |lame| lame.delay as u32 + Mp3CodecTag::DECODER_DELAY);
+        let decoded_spec = SignalSpecBuilder::new()
+            .with_frame_rate(header.frame_rate)
+            .with_channels(header.n_channels() as u16)
+            .with_n_frames(n_frames);
+
+        let spec = StreamSpec::new()
+            .with_codec(Mp3CodecTag::new().with_delay(delay))
+            .with_sample_type::<f32>()
+            .with_avg_bitrate(avg_bitrate)
+            .with_decoded_spec(decoded_spec);
+
+        let data = FormatData::<Mp3FormatTag>::new().with_stream(spec);
+        self.data.merge(&data.with_tag_type())?;
+
+        self.header = Some(header);
+        self.xing = xing;
+        self.vbri = vbri;
+        self.frame.clear();
+        self.next_i = start_i;
+        self.index_end = start_i;
+        self.inner.release(start_i);
+
+        Ok(())
+    }
+
+    fn read<'a>(&'a mut self, buf: &'a mut [u8]) -> Result<FormatChunk<'a>, PhonicError> {
+        if self.header.is_none() {
+            self.read_data()?;
+        }
+
+        if self.frame_start == self.frame.len() && !self.read_frame()? {
+            return Ok(FormatChunk::Stream {
+                stream_i: 0,
+                buf: &buf[..0],
+            });
+        }
+
+        let n = buf.len().min(self.frame.len() - self.frame_start);
+        buf[..n].copy_from_slice(&self.frame[self.frame_start..self.frame_start + n]);
+        self.frame_start += n;
+
+        if self.frame_start == self.frame.len() {
+            self.frame_i += 1;
+        }
+
+        Ok(FormatChunk::Stream {
+            stream_i: 0,
+            buf: &buf[..n],
+        })
+    }
+}
+
+impl<T: Write, F: FormatTag> FormatWriter for Mp3Format<T, F>
+where
+    F::Codec: TryInto<Mp3CodecTag>,
+{
+    fn write_data(&mut self, data: &FormatData<F>) -> Result<(), PhonicError> {
+        self.data.merge(data)?;
+
+        // the frames are written as they come from the codec, with nothing around them
+        match self.data.streams.as_slice() {
+            [spec] if spec.codec.is_some_and(|codec| codec.try_into().is_ok()) => Ok(()),
+            _ => Err(PhonicError::Unsupported),
+        }
+    }
+
+    fn write(&mut self, chunk: FormatChunk) -> Result<(), PhonicError> {
+        match chunk {
+            FormatChunk::Stream { stream_i, buf } if !self.data.is_empty() && stream_i == 0 => {
+                self.inner.as_inner_mut().write_all(buf)?;
+                self.n_written += buf.len() as u64;
+            }
+            _ => return Err(PhonicError::InvalidData),
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), PhonicError> {
+        self.inner.as_inner_mut().flush().map_err(Into::into)
+    }
+}
+
+impl<T: Read + Seek, F: FormatTag> FormatSeeker for Mp3Format<T, F> {
+    fn seek(&mut self, offset: FormatOffset) -> Result<(), PhonicError> {
+        if offset.stream_offset != 0 {
+            return Err(PhonicError::NotFound);
+        }
+
+        if self.header.is_none() {
+            return Err(PhonicError::NotReady);
+        }
+
+        self.inner.enable_seeking()?;
+
+        // every frame holds the same number of frames of samples, so the target is in the frame
+        // that its position divides into
+        let n_packet_frames = self.n_packet_frames();
+        let position = self.frame_i * n_packet_frames;
+        let target = position.saturating_add_signed(offset.byte_offset);
+        let frame_i = self.index_to(target / n_packet_frames)?;
+
+        self.frame_i = frame_i;
+        self.next_i = match self.frame_offsets.get(frame_i as usize) {
+            Some(&offset) => offset,
+            None => self.index_end,
+        };
+
+        self.frame.clear();
+        self.frame_start = 0;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,167 @@
+use phonic_codec_mp3::FrameHeader;
+
+/// The offset of a VBRI header in its frame, which is the same for every kind of frame.
+const VBRI_OFFSET: usize = FrameHeader::LEN + 32;
+
+/// The encoders whose LAME tag records the delay and padding of a stream.
+const LAME_ENCODERS: [&[u8]; 3] = [b"LAME", b"Lavf", b"Lavc"];
+
+fn read_u16(bytes: &[u8], i: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(bytes.get(i..i + 2)?.try_into().ok()?))
+}
+
+fn read_u32(bytes: &[u8], i: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(i..i + 4)?.try_into().ok()?))
+}
+
+/// The header that encoders write in place of the audio of the first frame, as "Xing" for
+/// variable bitrate streams and "Info" for constant bitrate ones. The frame decodes to silence
+/// and isn't part of the stream, so the counts leave it out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XingHeader {
+    pub n_frames: Option<u32>,
+    pub n_bytes: Option<u32>,
+    /// The byte position of each percent of the stream, in 256ths of the length of the stream.
+    pub toc: Option<[u8; 100]>,
+    pub quality: Option<u32>,
+    pub lame: Option<LameTag>,
+}
+
+/// The extension that LAME and encoders based on it append to the Xing header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LameTag {
+    pub encoder: [u8; 9],
+    /// The frames of silence the encoder added at the start, not counting the decoder delay.
+    pub delay: u16,
+    /// The frames of silence the encoder added at the end.
+    pub padding: u16,
+}
+
+/// The header that the Fraunhofer encoder writes in place of the audio of the first frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VbriHeader {
+    pub version: u16,
+    pub delay: u16,
+    pub quality: u16,
+    pub n_bytes: u32,
+    pub n_frames: u32,
+    /// The number of frames covered by each entry of the table of contents.
+    pub frames_per_entry: u16,
+    /// The length in bytes of each run of frames, already multiplied by the scale.
+    pub toc: Vec<u32>,
+}
+
+impl XingHeader {
+    /// Reads the header from a whole frame, where it follows the side info.
+    pub fn read(frame: &[u8], header: &FrameHeader) -> Option<Self> {
+        let checksum_len = if header.protected { 2 } else { 0 };
+        let tag = frame.get(FrameHeader::LEN + checksum_len + header.side_info_len()..)?;
+        if !tag.starts_with(b"Xing") && !tag.starts_with(b"Info") {
+            return None;
+        }
+
+        let flags = read_u32(tag, 4)?;
+        let mut i = 8;
+        let mut xing = Self {
+            n_frames: None,
+            n_bytes: None,
+            toc: None,
+            quality: None,
+            lame: None,
+        };
+
+        if flags & 1 != 0 {
+            xing.n_frames = Some(read_u32(tag, i)?);
+            i += 4;
+        }
+
+        if flags & 2 != 0 {
+            xing.n_bytes = Some(read_u32(tag, i)?);
+            i += 4;
+        }
+
+        if flags & 4 != 0 {
+            xing.toc = Some(tag.get(i..i + 100)?.try_into().ok()?);
+            i += 100;
+        }
+
+        if flags & 8 != 0 {
+            xing.quality = Some(read_u32(tag, i)?);
+            i += 4;
+        }
+
+        xing.lame = tag.get(i..).and_then(LameTag::read);
+        Some(xing)
+    }
+
+    /// Returns the byte offset into the stream of a point given as a fraction of its duration,
+    /// from the table of contents.
+    pub fn toc_offset(&self, fraction: f64) -> Option<u64> {
+        let toc = self.toc.as_ref()?;
+        let n_bytes = self.n_bytes? as f64;
+
+        let percent = (fraction * 100.0).clamp(0.0, 100.0);
+        let i = (percent as usize).min(99);
+        let low = toc[i] as f64;
+        let high = toc.get(i + 1).map_or(256.0, |&high| high as f64);
+        let position = low + (high - low) * (percent - i as f64);
+
+        Some((position / 256.0 * n_bytes) as u64)
+    }
+}
+
+impl LameTag {
+    /// The length of the tag up to the end of the delay and padding.
+    const LEN: usize = 24;
+
+    fn read(tag: &[u8]) -> Option<Self> {
+        let tag = tag.get(..Self::LEN)?;
+        if !LAME_ENCODERS.iter().any(|encoder| tag.starts_with(encoder)) {
+            return None;
+        }
+
+        Some(Self {
+            encoder: tag[..9].try_into().ok()?,
+            delay: ((tag[21] as u16) << 4) | (tag[22] as u16 >> 4),
+            padding: ((tag[22] as u16 & 0xf) << 8) | tag[23] as u16,
+        })
+    }
+}
+
+impl VbriHeader {
+    /// Reads the header from a whole frame.
+    pub fn read(frame: &[u8]) -> Option<Self> {
+        let tag = frame.get(VBRI_OFFSET..)?;
+        if !tag.starts_with(b"VBRI") {
+            return None;
+        }
+
+        let n_entries = read_u16(tag, 18)? as usize;
+        let scale = read_u16(tag, 20)? as u32;
+        let entry_len = read_u16(tag, 22)? as usize;
+        if !(1..=4).contains(&entry_len) {
+            return None;
+        }
+
+        let entries = tag.get(26..26 + n_entries * entry_len)?;
+        let toc = entries
+            .chunks_exact(entry_len)
+            .map(|entry| {
+                entry
+                    .iter()
+                    .fold(0, |n, &byte| (n << 8) | byte as u32)
+                    .saturating_mul(scale)
+            })
+            .collect();
+
+        Some(Self {
+            version: read_u16(tag, 4)?,
+            delay: read_u16(tag, 6)?,
+            quality: read_u16(tag, 8)?,
+            n_bytes: read_u32(tag, 10)?,
+            n_frames: read_u32(tag, 14)?,
+            frames_per_entry: read_u16(tag, 24)?,
+            toc,
+        })
+    }
+}
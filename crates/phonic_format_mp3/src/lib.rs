@@ -0,0 +1,7 @@
+mod data;
+mod format;
+mod info;
+
+pub use data::*;
+pub use format::*;
+pub use info::*;
@@ -0,0 +1,350 @@
+use phonic_codec_mp3::{FrameHeader, Mp3CodecTag};
+use phonic_core::PhonicError;
+use phonic_format_mp3::{Mp3Format, Mp3FormatTag, XingHeader};
+use phonic_io_core::{
+    Format, FormatChunk, FormatData, FormatObserver, FormatOffset, FormatReader, FormatSeeker,
+    FormatWriter, StreamSpec,
+};
+use std::io::Cursor;
+
+const N_FRAMES: usize = 20;
+const PACKET_FRAMES: u64 = 1152;
+
+/// A joint stereo frame of 128 kbit/s at 44.1 kHz, with every third one padded.
+fn frame(frame_i: usize) -> Vec<u8> {
+    let padded = frame_i % 3 == 2;
+    let mut frame = vec![0xff, 0xfb, 0x90 | (padded as u8) << 1, 0x64];
+    frame.resize(36, 0);
+    frame.resize(417 + padded as usize, frame_i as u8 + 1);
+    frame
+}
+
+fn frames() -> Vec<Vec<u8>> {
+    (0..N_FRAMES).map(frame).collect()
+}
+
+fn xing_frame(n_bytes: u32, delay: u16, padding: u16) -> Vec<u8> {
+    let mut frame = vec![0xff, 0xfb, 0x90, 0x64];
+    frame.resize(36, 0);
+    frame.extend_from_slice(b"Xing");
+    frame.extend_from_slice(&0xfu32.to_be_bytes());
+    frame.extend_from_slice(&(N_FRAMES as u32).to_be_bytes());
+    frame.extend_from_slice(&n_bytes.to_be_bytes());
+    frame.extend((0..100).map(|i| (i * 256 / 100) as u8));
+    frame.extend_from_slice(&60u32.to_be_bytes());
+
+    let mut lame = b"LAME3.100".to_vec();
+    lame.resize(21, 0);
+    lame.push((delay >> 4) as u8);
+    lame.push(((delay & 0xf) << 4) as u8 | (padding >> 8) as u8);
+    lame.push(padding as u8);
+    frame.extend_from_slice(&lame);
+
+    frame.resize(417, 0);
+    frame
+}
+
+fn vbri_frame() -> Vec<u8> {
+    let mut frame = vec![0xff, 0xfb, 0x90, 0x64];
+    frame.resize(36, 0);
+    frame.extend_from_slice(b"VBRI");
+    frame.extend_from_slice(&1u16.to_be_bytes());
+    frame.extend_from_slice(&1105u16.to_be_bytes());
+    frame.extend_from_slice(&75u16.to_be_bytes());
+    frame.extend_from_slice(&8400u32.to_be_bytes());
+    frame.extend_from_slice(&(N_FRAMES as u32).to_be_bytes());
+    frame.extend_from_slice(&4u16.to_be_bytes());
+    frame.extend_from_slice(&2u16.to_be_bytes());
+    frame.extend_from_slice(&2u16.to_be_bytes());
+    frame.extend_from_slice(&5u16.to_be_bytes());
+    for entry in [2085u16, 2086, 2085, 2086] {
+        frame.extend_from_slice(&(entry / 2).to_be_bytes());
+    }
+
+    frame.resize(417, 0);
+    frame
+}
+
+fn id3_tag(size: usize) -> Vec<u8> {
+    let mut tag = b"ID3\x04\x00\x00".to_vec();
+    tag.extend([21, 14, 7, 0].map(|shift| ((size >> shift) & 0x7f) as u8));
+    tag.resize(10 + size, 0xff);
+    tag
+}
+
+/// Reads every remaining chunk along with the position after it.
+fn read_chunks<T>(format: &mut Mp3Format<T>, buf_len: usize) -> Vec<(Vec<u8>, u64)>
+where
+    T: std::io::Read,
+{
+    let mut chunks = Vec::new();
+    let mut buf = vec![0; buf_len];
+    loop {
+        let FormatChunk::Stream { stream_i, buf } = format.read(&mut buf).unwrap();
+        assert_eq!(stream_i, 0);
+        if buf.is_empty() {
+            return chunks;
+        }
+
+        let chunk = buf.to_vec();
+        chunks.push((chunk, format.position().unwrap().byte_i));
+    }
+}
+
+#[test]
+fn reads_one_frame_per_chunk() {
+    let mut file = id3_tag(300);
+    file.extend(frames().concat());
+    let mut format = Mp3Format::<_>::new(Cursor::new(file)).unwrap();
+    format.read_data().unwrap();
+
+    let [spec] = format.data().streams.as_slice() else {
+        panic!("expected one stream");
+    };
+
+    assert!(format.data().format == Some(Mp3FormatTag));
+    assert_eq!(spec.codec, Some(Mp3CodecTag::new()));
+    assert_eq!(spec.avg_bitrate, Some(128_000.0));
+    assert_eq!(spec.decoded_spec.frame_rate, Some(44_100));
+    assert_eq!(spec.decoded_spec.channels.map(|c| c.count()), Some(2));
+    assert_eq!(spec.decoded_spec.n_frames, None);
+    assert!(format.xing_header().is_none());
+
+    let chunks = read_chunks(&mut format, 1024);
+    assert_eq!(chunks.len(), N_FRAMES);
+    for (i, (chunk, position)) in chunks.into_iter().enumerate() {
+        assert_eq!(chunk, frame(i));
+        assert_eq!(position, (i as u64 + 1) * PACKET_FRAMES);
+    }
+}
+
+#[test]
+fn small_buffers_split_frames() {
+    let mut format = Mp3Format::<_>::new(Cursor::new(frames().concat())).unwrap();
+    let chunks = read_chunks(&mut format, 100);
+
+    let first: Vec<_> = chunks[..5].iter().map(|(_, position)| *position).collect();
+    assert_eq!(first, [0, 0, 0, 0, PACKET_FRAMES]);
+
+    let bytes: Vec<_> = chunks.into_iter().flat_map(|(chunk, _)| chunk).collect();
+    assert_eq!(bytes, frames().concat());
+}
+
+#[test]
+fn reads_xing_and_lame_headers() {
+    let n_bytes = frames().concat().len() as u32;
+    let mut file = id3_tag(20);
+    file.extend(xing_frame(n_bytes, 576, 300));
+    file.extend(frames().concat());
+    let mut format = Mp3Format::<_>::new(Cursor::new(file)).unwrap();
+    format.read_data().unwrap();
+
+    let spec = format.data().streams[0];
+    let n_frames = N_FRAMES as u64 * PACKET_FRAMES;
+    let delay = 576 + Mp3CodecTag::DECODER_DELAY;
+    assert_eq!(spec.codec, Some(Mp3CodecTag::new().with_delay(delay)));
+    assert_eq!(spec.decoded_spec.n_frames, Some(n_frames - 576 - 300));
+
+    let avg_bitrate = n_bytes as f64 * 8.0 * 44_100.0 / n_frames as f64;
+    assert_eq!(spec.avg_bitrate, Some(avg_bitrate));
+
+    let xing = format.xing_header().unwrap();
+    assert_eq!(xing.n_frames, Some(N_FRAMES as u32));
+    assert_eq!(xing.n_bytes, Some(n_bytes));
+    assert_eq!(xing.quality, Some(60));
+
+    let lame = xing.lame.unwrap();
+    assert_eq!(&lame.encoder, b"LAME3.100");
+    assert_eq!((lame.delay, lame.padding), (576, 300));
+
+    // the frame of the header isn't part of the stream
+    let chunks = read_chunks(&mut format, 1024);
+    assert_eq!(chunks.len(), N_FRAMES);
+    assert_eq!(chunks[0].0, frame(0));
+}
+
+#[test]
+fn reads_vbri_header() {
+    let mut file = vbri_frame();
+    file.extend(frames().concat());
+    let mut format = Mp3Format::<_>::new(Cursor::new(file)).unwrap();
+    format.read_data().unwrap();
+
+    let spec = format.data().streams[0];
+    assert_eq!(spec.codec, Some(Mp3CodecTag::new()));
+    assert_eq!(
+        spec.decoded_spec.n_frames,
+        Some(N_FRAMES as u64 * PACKET_FRAMES)
+    );
+
+    let vbri = format.vbri_header().unwrap();
+    assert_eq!(vbri.version, 1);
+    assert_eq!(vbri.delay, 1105);
+    assert_eq!(vbri.n_bytes, 8400);
+    assert_eq!(vbri.frames_per_entry, 5);
+    assert_eq!(vbri.toc, [2084, 2086, 2084, 2086]);
+
+    assert_eq!(read_chunks(&mut format, 1024).len(), N_FRAMES);
+}
+
+#[test]
+fn xing_toc_gives_offsets() {
+    let frame = xing_frame(10_000, 0, 0);
+    let header = FrameHeader::read(&frame).unwrap();
+    let xing = XingHeader::read(&frame, &header).unwrap();
+
+    assert_eq!(xing.toc_offset(0.0), Some(0));
+    assert_eq!(xing.toc_offset(0.5), Some(5000));
+    assert_eq!(xing.toc_offset(0.255), Some(2539));
+    assert_eq!(xing.toc_offset(2.0), Some(10_000));
+
+    let mut frame = frame;
+    frame[43] = 0x3;
+    let xing = XingHeader::read(&frame, &header).unwrap();
+    assert_eq!(xing.toc, None);
+    assert_eq!(xing.toc_offset(0.5), None);
+}
+
+#[test]
+fn garbage_between_frames_is_skipped() {
+    // a stray sync word that no frame follows is passed over along with the rest
+    let garbage = [&b"junk"[..], &[0xff, 0xfb, 0x90, 0x64], &[0x11; 50]].concat();
+    let mut file = garbage.clone();
+    for (i, frame) in frames().into_iter().enumerate() {
+        file.extend(frame);
+        if i % 7 == 3 {
+            file.extend_from_slice(&garbage);
+        }
+    }
+
+    // the last frame is cut short
+    file.extend_from_slice(&frame(N_FRAMES)[..200]);
+
+    let mut format = Mp3Format::<_>::new(Cursor::new(file)).unwrap();
+    let chunks: Vec<_> = read_chunks(&mut format, 1024)
+        .into_iter()
+        .map(|(chunk, _)| chunk)
+        .collect();
+
+    assert_eq!(chunks, frames());
+}
+
+#[test]
+fn files_are_read_without_seeking() {
+    let n_bytes = frames().concat().len() as u32;
+    let mut file = id3_tag(100_000);
+    file.extend(xing_frame(n_bytes, 576, 300));
+    for (i, frame) in frames().into_iter().enumerate() {
+        file.extend(frame);
+        if i % 5 == 2 {
+            file.extend_from_slice(&[0x11; 30]);
+        }
+    }
+
+    // a slice reader can't seek
+    let mut format = Mp3Format::<_>::new(file.as_slice()).unwrap();
+    format.read_data().unwrap();
+    assert_eq!(
+        format.data().streams[0].decoded_spec.n_frames,
+        Some(N_FRAMES as u64 * PACKET_FRAMES - 576 - 300)
+    );
+
+    let chunks: Vec<_> = read_chunks(&mut format, 1024)
+        .into_iter()
+        .map(|(chunk, _)| chunk)
+        .collect();
+
+    assert_eq!(chunks, frames());
+}
+
+#[test]
+fn rejects_files_without_frames() {
+    let mut file = id3_tag(100);
+    file.extend_from_slice(&[0x11; 5000]);
+    let mut format = Mp3Format::<_>::new(Cursor::new(file)).unwrap();
+    assert_eq!(format.read_data(), Err(PhonicError::InvalidData));
+}
+
+#[test]
+fn seeks_to_the_frame_holding_a_target() {
+    let mut file = xing_frame(0, 0, 0);
+    file.extend(frames().concat());
+    let mut format = Mp3Format::<_>::new(Cursor::new(file)).unwrap();
+
+    let offset = FormatOffset {
+        stream_offset: 0,
+        byte_offset: 0,
+    };
+
+    assert_eq!(format.seek(offset), Err(PhonicError::NotReady));
+    format.read_data().unwrap();
+
+    let mut buf = [0; 1024];
+    for target in [5000, 20_000, 1151, 1152, 14_000, 0] {
+        let position = format.position().unwrap().byte_i;
+        let offset = FormatOffset {
+            stream_offset: 0,
+            byte_offset: target - position as i64,
+        };
+
+        format.seek(offset).unwrap();
+        let frame_i = target as usize / PACKET_FRAMES as usize;
+        assert_eq!(
+            format.position().unwrap().byte_i,
+            frame_i as u64 * PACKET_FRAMES
+        );
+
+        let FormatChunk::Stream { buf, .. } = format.read(&mut buf).unwrap();
+        assert_eq!(buf, frame(frame_i));
+    }
+
+    // past the end, the stream resumes at its end
+    format
+        .seek(FormatOffset {
+            stream_offset: 0,
+            byte_offset: 100_000,
+        })
+        .unwrap();
+
+    let n_frames = N_FRAMES as u64 * PACKET_FRAMES;
+    assert_eq!(format.position().unwrap().byte_i, n_frames);
+    assert!(read_chunks(&mut format, 1024).is_empty());
+
+    let offset = FormatOffset {
+        stream_offset: 1,
+        byte_offset: 0,
+    };
+
+    assert_eq!(format.seek(offset), Err(PhonicError::NotFound));
+}
+
+#[test]
+fn writes_frames_as_they_are() {
+    let mut format = Mp3Format::<_>::new(Cursor::new(Vec::new())).unwrap();
+    let chunk = FormatChunk::Stream {
+        stream_i: 0,
+        buf: &frame(0),
+    };
+
+    assert_eq!(format.write(chunk), Err(PhonicError::InvalidData));
+
+    let data = FormatData::new()
+        .with_format(Mp3FormatTag)
+        .with_stream(StreamSpec::new().with_codec(Mp3CodecTag::new()));
+
+    format.write_data(&data).unwrap();
+    for frame in frames() {
+        let chunk = FormatChunk::Stream {
+            stream_i: 0,
+            buf: &frame,
+        };
+
+        format.write(chunk).unwrap();
+    }
+
+    let n_bytes = frames().concat().len() as u64;
+    assert_eq!(format.position().unwrap().byte_i, n_bytes);
+
+    FormatWriter::flush(&mut format).unwrap();
+    assert_eq!(format.into_inner().into_inner(), frames().concat());
+}
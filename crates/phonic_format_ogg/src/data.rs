@@ -0,0 +1,102 @@
+use std::any::TypeId;
+use phonic_codec_flac::{fill_flac_spec, FlacCodecTag};
+use phonic_core::PhonicError;
+use phonic_io_core::{
+    utils::{FormatIdentifiers, FormatMarker},
+    CodecTag, FormatData, FormatTag, StreamSpec,
+};
+
+pub static OGG_IDENTIFIERS: FormatIdentifiers = FormatIdentifiers {
+    file_extensions: &["ogg", "oga", "opus"],
+    mime_types: &["audio/ogg", "audio/opus", "application/ogg"],
+    markers: &[FormatMarker {
+        offset: 0,
+        bytes: b"OggS",
+    }],
+};
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub struct OggFormatTag;
+
+/// The codecs an ogg file can carry. Each logical bitstream is identified by the first packet
+/// it holds, which starts with a marker for the codec.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum OggSupportedCodec {
+    Flac(FlacCodecTag),
+    Opus,
+    Vorbis,
+}
+
+pub fn fill_ogg_data<F>(data: &mut FormatData<F>) -> Result<(), PhonicError>
+where
+    F: FormatTag,
+    OggFormatTag: TryInto<F>,
+    OggSupportedCodec: TryInto<F::Codec>,
+{
+    let expected_format = OggFormatTag.try_into().ok();
+    if data.format.is_some() && data.format != expected_format {
+        return Err(PhonicError::InvalidData);
+    } else {
+        data.format = expected_format;
+    }
+
+    if data.streams.is_empty() {
+        data.streams.push(StreamSpec::new());
+    }
+
+    for spec in data.streams.iter_mut() {
+        if spec.codec.is_none() {
+            spec.codec = OggSupportedCodec::Flac(FlacCodecTag::new()).try_into().ok();
+        }
+
+        spec.fill()?;
+    }
+
+    Ok(())
+}
+
+impl FormatTag for OggFormatTag {
+    type Codec = OggSupportedCodec;
+
+    fn fill_data(data: &mut FormatData<Self>) -> Result<(), PhonicError> {
+        fill_ogg_data(data)
+    }
+}
+
+impl CodecTag for OggSupportedCodec {
+    fn fill_spec(spec: &mut StreamSpec<Self>) -> Result<(), PhonicError> {
+        match spec.codec {
+            Some(Self::Flac(_)) => fill_flac_spec(spec),
+            Some(Self::Opus | Self::Vorbis) => {
+                // both codecs decode to floats
+                let sample_type = *spec.sample_type.get_or_insert(TypeId::of::<f32>());
+                if sample_type != TypeId::of::<f32>() {
+                    return Err(PhonicError::Unsupported);
+                }
+
+                Ok(())
+            }
+            None => {
+                spec.codec = Some(Self::Flac(FlacCodecTag::new()));
+                fill_flac_spec(spec)
+            }
+        }
+    }
+}
+
+impl From<FlacCodecTag> for OggSupportedCodec {
+    fn from(tag: FlacCodecTag) -> Self {
+        Self::Flac(tag)
+    }
+}
+
+impl TryFrom<OggSupportedCodec> for FlacCodecTag {
+    type Error = PhonicError;
+
+    fn try_from(codec: OggSupportedCodec) -> Result<Self, Self::Error> {
+        match codec {
+            OggSupportedCodec::Flac(tag) => Ok(tag),
+            _ => Err(PhonicError::Unsupported),
+        }
+    }
+}
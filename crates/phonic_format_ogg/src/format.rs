@@ -0,0 +1,801 @@
+use crate::{mapping::OggMapping, OggFormatTag, OggPage, OggSupportedCodec, OGG_MARKER};
+use std::{
+    collections::VecDeque,
+    io::{Read, Seek, Write},
+    mem,
+};
+use phonic_core::PhonicError;
+use phonic_io_core::{
+    utils::LookaheadReader, Format, FormatChunk, FormatData, FormatObserver, FormatOffset,
+    FormatPosition, FormatReader, FormatSeeker, FormatTag, FormatWriter, StreamSpec,
+};
+
+/// The body length past which a page is written out once a packet ends.
+const PAGE_LEN: usize = 4096;
+
+/// The range below which a granule search scans pages in order rather than bisecting.
+const BISECT_LEN: u64 = 64 * 1024;
+
+/// The length of the end of the file first scanned for the last granule of each stream.
+const TAIL_LEN: u64 = 64 * 1024;
+
+const SCAN_LEN: usize = 4096;
+const SERIAL: u32 = 0x7068_6f6e;
+
+type ScanGranules<T, F> = fn(&mut OggFormat<T, F>) -> Result<Vec<Option<u64>>, PhonicError>;
+
+/// An ogg file, which interleaves the pages of one or more logical bitstreams. Each logical
+/// bitstream is a stream of the format, in the order of their first pages, and each chunk holds
+/// one packet or as much of it as fits.
+///
+/// Flac streams are positioned by byte in the native stream passed on to the codec. Opus and
+/// vorbis streams are positioned by granule, and seeking to a granule position finds the page
+/// that ends closest before it by bisection, so the codec can resume from the next packet.
+///
+/// Reading the file only reads the inner reader in order. The length of each stream comes from
+/// its last granule position near the end of the file, which is only looked for by readers made
+/// with `with_seekable_reads`.
+///
+/// Pages are written as they fill up. Flushing the format ends every stream, so it should only
+/// be done once all of them have been written.
+pub struct OggFormat<T, F: FormatTag = OggFormatTag> {
+    inner: LookaheadReader<T>,
+    page_i: u64,
+    stream_i: usize,
+    n_ended: usize,
+    streams: Vec<LogicalStream>,
+    packets: VecDeque<Packet>,
+    writers: Vec<PageWriter>,
+    held_pages: Vec<u8>,
+    data: FormatData<F>,
+
+    /// Finds the last granule position of each stream, for readers made with
+    /// `with_seekable_reads`.
+    scan_granules: Option<ScanGranules<T, F>>,
+}
+
+struct LogicalStream {
+    serial: u32,
+    mapping: Option<OggMapping>,
+    n_packets: u64,
+    partial: Vec<u8>,
+    position: u64,
+    data_offset: Option<u64>,
+}
+
+struct Packet {
+    stream_i: usize,
+    bytes: Vec<u8>,
+    start: usize,
+    granule_position: Option<u64>,
+}
+
+struct PageWriter {
+    mapping: OggMapping,
+    serial: u32,
+    sequence: u32,
+    n_packets: u64,
+    n_bytes: u64,
+    granule_position: u64,
+    continued: bool,
+    ended: bool,
+    lacing: Vec<u8>,
+    body: Vec<u8>,
+    page_granule: Option<u64>,
+    pages: Vec<(bool, Vec<u8>)>,
+}
+
+fn page_len(page: &OggPage) -> u64 {
+    (page.header_len() + page.body_len()) as u64
+}
+
+impl<T, F: FormatTag> OggFormat<T, F> {
+    pub fn new(inner: T) -> Result<Self, PhonicError>
+    where
+        OggFormatTag: TryInto<F>,
+    {
+        let mut data = FormatData::new();
+        data.format = OggFormatTag.try_into().ok();
+        Ok(Self {
+            inner: LookaheadReader::new(inner),
+            page_i: 0,
+            stream_i: 0,
+            n_ended: 0,
+            streams: Vec::new(),
+            packets: VecDeque::new(),
+            writers: Vec::new(),
+            held_pages: Vec::new(),
+            data,
+            scan_granules: None,
+        })
+    }
+
+    pub fn as_inner(&self) -> &T {
+        self.inner.as_inner()
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner()
+    }
+
+    /// Queues the packets that end on a page, keeping any packet that continues on the next page
+    /// of its stream.
+    fn read_packets(&mut self, page: &OggPage, body: &[u8]) {
+        let Some(stream_i) = self.streams.iter().position(|s| s.serial == page.serial) else {
+            return;
+        };
+
+        let stream = &mut self.streams[stream_i];
+        let segments = page.segments();
+        let last_complete = segments.iter().rposition(|segment| segment.complete);
+
+        // a packet left unfinished by the previous page was cut short by a lost page
+        if !page.continued {
+            stream.partial.clear();
+        }
+
+        for (i, segment) in segments.iter().enumerate() {
+            // the rest of a packet whose start was skipped
+            if i == 0 && page.continued && stream.partial.is_empty() {
+                continue;
+            }
+
+            stream
+                .partial
+                .extend_from_slice(&body[segment.start..segment.end]);
+            if !segment.complete {
+                continue;
+            }
+
+            let bytes = mem::take(&mut stream.partial);
+            let start = stream
+                .mapping
+                .map_or(0, |mapping| mapping.packet_offset(stream.n_packets))
+                .min(bytes.len());
+
+            self.packets.push_back(Packet {
+                stream_i,
+                bytes,
+                start,
+                granule_position: page.granule_position.filter(|_| Some(i) == last_complete),
+            });
+
+            stream.n_packets += 1;
+        }
+    }
+
+    /// Drops the packet at the front of the queue, moving its stream past it.
+    fn skip_packet(&mut self) {
+        let Some(packet) = self.packets.pop_front() else {
+            return;
+        };
+
+        let stream = &mut self.streams[packet.stream_i];
+        match stream.mapping {
+            Some(mapping) if mapping.by_granule() => {
+                if let Some(granule_position) = packet.granule_position {
+                    stream.position = granule_position;
+                }
+            }
+            _ => stream.position += (packet.bytes.len() - packet.start) as u64,
+        }
+    }
+
+    fn clear_packets(&mut self) {
+        self.packets.clear();
+        self.n_ended = 0;
+        for stream in self.streams.iter_mut() {
+            stream.partial.clear();
+        }
+    }
+}
+
+impl<T: Read, F: FormatTag> OggFormat<T, F> {
+    /// Returns the offset of the first page marker at or after `offset`.
+    fn find_marker(&mut self, offset: u64) -> Result<Option<u64>, PhonicError> {
+        let mut buf = [0; SCAN_LEN];
+        let mut offset = offset;
+        loop {
+            let n = self.inner.read_at(offset, &mut buf)?;
+            if let Some(i) = buf[..n].windows(4).position(|w| w == OGG_MARKER) {
+                return Ok(Some(offset + i as u64));
+            }
+
+            if n < buf.len() {
+                return Ok(None);
+            }
+
+            // a marker may straddle the end of the buffer
+            offset += (n - (OGG_MARKER.len() - 1)) as u64;
+        }
+    }
+
+    /// Reads the first valid page at or after `offset`, skipping over anything that is not one.
+    fn next_page(&mut self, offset: u64) -> Result<Option<(u64, OggPage, Vec<u8>)>, PhonicError> {
+        let mut offset = offset;
+        loop {
+            match OggPage::read(&mut self.inner.reader_at(offset)) {
+                Ok((page, body)) => return Ok(Some((offset, page, body))),
+                Err(PhonicError::InvalidData | PhonicError::EndOfStream) => {}
+                Err(e) => return Err(e),
+            }
+
+            match self.find_marker(offset + 1)? {
+                Some(marker_i) => offset = marker_i,
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Reads the next page into the packet queue, returning false at the end of the file.
+    fn read_page(&mut self) -> Result<bool, PhonicError> {
+        let Some((offset, page, body)) = self.next_page(self.page_i)? else {
+            return Ok(false);
+        };
+
+        self.page_i = offset + page_len(&page);
+        self.inner.release(self.page_i);
+        self.read_packets(&page, &body);
+        Ok(true)
+    }
+
+    /// Returns the next page of a stream at or after `offset` and before `limit` that has a
+    /// granule position, along with its length.
+    fn next_granule_page(
+        &mut self,
+        offset: u64,
+        serial: u32,
+        limit: u64,
+    ) -> Result<Option<(u64, u64, u64)>, PhonicError> {
+        let mut offset = offset;
+        while let Some((page_offset, page, _)) = self.next_page(offset)? {
+            if page_offset >= limit {
+                break;
+            }
+
+            let len = page_len(&page);
+            match page.granule_position {
+                Some(granule_position) if page.serial == serial => {
+                    return Ok(Some((page_offset, len, granule_position)))
+                }
+                _ => offset = page_offset + len,
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl<T: Read + Seek, F: FormatTag> OggFormat<T, F> {
+    /// Lets the reader seek to the end of the file when reading the format data, to find the
+    /// length of each stream from its last granule position. Readers that can't seek leave the
+    /// lengths unknown.
+    pub fn with_seekable_reads(mut self) -> Self {
+        self.scan_granules = Some(Self::last_granules);
+        self
+    }
+
+    /// Returns the last granule position of each stream, scanning back from the end of the file
+    /// until every stream with a known codec has one.
+    fn last_granules(&mut self) -> Result<Vec<Option<u64>>, PhonicError> {
+        let file_len = self.inner.byte_len()?;
+        let mut granules = vec![None; self.streams.len()];
+        let mut window = TAIL_LEN;
+        loop {
+            let start = file_len.saturating_sub(window).max(self.page_i);
+            let mut offset = start;
+            while let Some((page_offset, page, _)) = self.next_page(offset)? {
+                let stream_i = self.streams.iter().position(|s| s.serial == page.serial);
+                if let (Some(i), Some(granule)) = (stream_i, page.granule_position) {
+                    granules[i] = Some(granule);
+                }
+
+                offset = page_offset + page_len(&page);
+            }
+
+            let found = granules
+                .iter()
+                .zip(self.streams.iter())
+                .all(|(granule, stream)| granule.is_some() || stream.mapping.is_none());
+
+            if found || start == self.page_i {
+                return Ok(granules);
+            }
+
+            window *= 4;
+        }
+    }
+
+    /// Returns the offset of the first page of a stream that starts with an audio packet.
+    fn data_offset(&mut self, stream_i: usize) -> Result<u64, PhonicError> {
+        let stream = &self.streams[stream_i];
+        if let Some(offset) = stream.data_offset {
+            return Ok(offset);
+        }
+
+        let serial = stream.serial;
+        let n_header_packets = stream
+            .mapping
+            .and_then(|mapping| mapping.n_header_packets())
+            .unwrap_or(0);
+
+        let mut n_packets = 0;
+        let mut offset = 0;
+        while let Some((page_offset, page, _)) = self.next_page(offset)? {
+            if page.serial == serial {
+                if n_packets >= n_header_packets && !page.continued {
+                    break;
+                }
+
+                n_packets += page.segments().iter().filter(|s| s.complete).count() as u64;
+            }
+
+            offset = page_offset + page_len(&page);
+        }
+
+        self.streams[stream_i].data_offset = Some(offset);
+        Ok(offset)
+    }
+
+    /// Moves a stream to the last page that ends at or before a granule position. The packets
+    /// that end on that page are dropped, so the next one read starts at its granule position.
+    /// Granule 0 is the start of the stream, since a page that ends there can still hold packets
+    /// that later ones need, as the first packet of a vorbis stream decodes to nothing.
+    fn seek_granule(&mut self, stream_i: usize, granule_position: u64) -> Result<(), PhonicError> {
+        let data_offset = self.data_offset(stream_i)?;
+        let best = match granule_position {
+            0 => None,
+            _ => self.find_granule_page(stream_i, data_offset, granule_position)?,
+        };
+
+        self.clear_packets();
+        self.page_i = best.unwrap_or(data_offset);
+        self.streams[stream_i].position = 0;
+
+        if best.is_some() {
+            let Some((offset, page, body)) = self.next_page(self.page_i)? else {
+                return Err(PhonicError::InvalidData);
+            };
+
+            self.page_i = offset + page_len(&page);
+            self.read_packets(&page, &body);
+            self.packets.clear();
+            self.streams[stream_i].position = page.granule_position.unwrap_or(0);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the offset of the last page of a stream that ends at or before a granule
+    /// position, by bisecting the file and then scanning forward.
+    fn find_granule_page(
+        &mut self,
+        stream_i: usize,
+        data_offset: u64,
+        granule_position: u64,
+    ) -> Result<Option<u64>, PhonicError> {
+        let serial = self.streams[stream_i].serial;
+        let file_len = self.inner.byte_len()?;
+        let mut best = None;
+        let mut low = data_offset;
+        let mut high = file_len;
+        while high.saturating_sub(low) > BISECT_LEN {
+            let mid = low + (high - low) / 2;
+            match self.next_granule_page(mid, serial, high)? {
+                Some((offset, len, granule)) if granule <= granule_position => {
+                    best = Some(offset);
+                    low = offset + len;
+                }
+                _ => high = mid,
+            }
+        }
+
+        let mut offset = low;
+        while let Some((page_offset, len, granule)) =
+            self.next_granule_page(offset, serial, u64::MAX)?
+        {
+            if granule > granule_position {
+                break;
+            }
+
+            best = Some(page_offset);
+            offset = page_offset + len;
+        }
+
+        Ok(best)
+    }
+
+    /// Moves a stream to a byte index, reading again from the start of the file if it is behind.
+    fn seek_bytes(&mut self, stream_i: usize, byte_i: u64) -> Result<(), PhonicError> {
+        if byte_i < self.streams[stream_i].position {
+            self.clear_packets();
+            self.page_i = 0;
+            for stream in self.streams.iter_mut() {
+                stream.n_packets = 0;
+                stream.position = 0;
+            }
+        }
+
+        while self.streams[stream_i].position < byte_i {
+            let Some(packet) = self.packets.front_mut() else {
+                match self.read_page()? {
+                    true => continue,
+                    false => break,
+                }
+            };
+
+            if packet.stream_i != stream_i {
+                self.skip_packet();
+                continue;
+            }
+
+            let stream = &mut self.streams[stream_i];
+            let remaining = (packet.bytes.len() - packet.start) as u64;
+            let n = remaining.min(byte_i - stream.position);
+
+            packet.start += n as usize;
+            stream.position += n;
+            if n == remaining {
+                self.packets.pop_front();
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<T, F: FormatTag> Format for OggFormat<T, F> {
+    type Tag = F;
+
+    fn data(&self) -> &FormatData<Self::Tag> {
+        &self.data
+    }
+}
+
+impl<T, F: FormatTag> FormatObserver for OggFormat<T, F> {
+    fn position(&self) -> Result<FormatPosition, PhonicError> {
+        let byte_i = match self.writers.get(self.stream_i) {
+            Some(writer) => writer.n_bytes,
+            None => self
+                .streams
+                .get(self.stream_i)
+                .map_or(0, |stream| stream.position),
+        };
+
+        Ok(FormatPosition {
+            stream_i: self.stream_i,
+            byte_i,
+        })
+    }
+}
+
+impl<T: Read, F: FormatTag> FormatReader for OggFormat<T, F>
+where
+    OggFormatTag: TryInto<F>,
+    OggSupportedCodec: TryInto<F::Codec>,
+{
+    fn read_data(&mut self) -> Result<(), PhonicError> {
+        if !self.streams.is_empty() {
+            return Ok(());
+        }
+
+        // every stream starts with a page holding only its identification header
+        let mut data = FormatData::<OggFormatTag>::new();
+        while let Some((offset, page, body)) = self.next_page(self.page_i)? {
+            if !page.bos {
+                break;
+            }
+
+            let (mapping, spec) = page
+                .segments()
+                .first()
+                .filter(|segment| segment.complete)
+                .and_then(|segment| OggMapping::identify(&body[segment.start..segment.end]))
+                .unzip();
+
+            self.streams.push(LogicalStream {
+                serial: page.serial,
+                mapping,
+                n_packets: 0,
+                partial: Vec::new(),
+                position: 0,
+                data_offset: None,
+            });
+
+            data.streams.push(spec.unwrap_or_else(StreamSpec::new));
+            self.page_i = offset + page_len(&page);
+            self.inner.release(self.page_i);
+            self.read_packets(&page, &body);
+        }
+
+        if self.streams.is_empty() {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let granules = match self.scan_granules {
+            Some(scan_granules) => scan_granules(self)?,
+            None => vec![None; self.streams.len()],
+        };
+
+        for ((spec, stream), granule) in data.streams.iter_mut().zip(&self.streams).zip(granules) {
+            let n_frames = stream.mapping.zip(granule).map(|(m, g)| m.n_frames(g));
+            if spec.decoded_spec.n_frames.is_none() {
+                spec.decoded_spec.n_frames = n_frames;
+            }
+        }
+
+        self.data.merge(&data.with_tag_type())
+    }
+
+    fn read<'a>(&'a mut self, buf: &'a mut [u8]) -> Result<FormatChunk<'a>, PhonicError> {
+        if self.streams.is_empty() {
+            self.read_data()?;
+        }
+
+        loop {
+            let Some(packet) = self.packets.front_mut() else {
+                if self.read_page()? {
+                    continue;
+                }
+
+                // once the file has ended every stream reads as empty in turn, so that a reader
+                // of any one stream sees the end
+                let stream_i = self.n_ended % self.streams.len();
+                self.n_ended += 1;
+
+                return Ok(FormatChunk::Stream {
+                    stream_i,
+                    buf: &buf[..0],
+                });
+            };
+
+            let stream_i = packet.stream_i;
+            let n = buf.len().min(packet.bytes.len() - packet.start);
+            buf[..n].copy_from_slice(&packet.bytes[packet.start..packet.start + n]);
+            packet.start += n;
+
+            let stream = &mut self.streams[stream_i];
+            let by_granule = stream.mapping.is_some_and(|mapping| mapping.by_granule());
+            if !by_granule {
+                stream.position += n as u64;
+            }
+
+            if packet.start == packet.bytes.len() {
+                if let Some(granule_position) = packet.granule_position.filter(|_| by_granule) {
+                    stream.position = granule_position;
+                }
+
+                self.packets.pop_front();
+            }
+
+            // empty packets carry nothing for the codec
+            if n == 0 && !buf.is_empty() {
+                continue;
+            }
+
+            self.stream_i = stream_i;
+            self.n_ended = 0;
+            return Ok(FormatChunk::Stream {
+                stream_i,
+                buf: &buf[..n],
+            });
+        }
+    }
+}
+
+impl PageWriter {
+    fn new(mapping: OggMapping, serial: u32) -> Self {
+        Self {
+            mapping,
+            serial,
+            sequence: 0,
+            n_packets: 0,
+            n_bytes: 0,
+            granule_position: 0,
+            continued: false,
+            ended: false,
+            lacing: Vec::new(),
+            body: Vec::new(),
+            page_granule: None,
+            pages: Vec::new(),
+        }
+    }
+
+    /// Adds a write of the codec to the stream. The first write holds the headers, which the
+    /// mapping splits into packets that each get a page of their own.
+    fn write(&mut self, buf: &[u8]) -> Result<(), PhonicError> {
+        if self.ended {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let n_header_packets = self.mapping.n_header_packets();
+        if self.n_packets == 0 {
+            let (headers, rest) = self.mapping.header_packets(buf)?;
+            for header in headers {
+                self.push(&header, 0)?;
+                self.write_page(false)?;
+            }
+
+            if !rest.is_empty() {
+                let granule_position = self.mapping.granule_after(rest, self.granule_position)?;
+                self.push(rest, granule_position)?;
+            }
+        } else if n_header_packets.is_some_and(|n| self.n_packets < n) {
+            self.push(buf, 0)?;
+            self.write_page(false)?;
+        } else {
+            let granule_position = self.mapping.granule_after(buf, self.granule_position)?;
+            self.push(buf, granule_position)?;
+        }
+
+        self.n_bytes += buf.len() as u64;
+        Ok(())
+    }
+
+    fn push(&mut self, packet: &[u8], granule_position: u64) -> Result<(), PhonicError> {
+        let mut rest = packet;
+        loop {
+            if self.lacing.len() == 255 {
+                self.write_page(false)?;
+            }
+
+            let n = rest.len().min(255);
+            self.lacing.push(n as u8);
+            self.body.extend_from_slice(&rest[..n]);
+            rest = &rest[n..];
+
+            if n < 255 {
+                break;
+            }
+        }
+
+        self.n_packets += 1;
+        self.granule_position = granule_position;
+        self.page_granule = Some(granule_position);
+
+        if self.body.len() >= PAGE_LEN || self.lacing.len() == 255 {
+            self.write_page(false)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_page(&mut self, eos: bool) -> Result<(), PhonicError> {
+        let page = OggPage {
+            continued: self.continued,
+            bos: self.sequence == 0,
+            eos,
+            granule_position: self.page_granule,
+            serial: self.serial,
+            sequence: self.sequence,
+            lacing: mem::take(&mut self.lacing),
+        };
+
+        let mut bytes = Vec::with_capacity(page.header_len() + self.body.len());
+        page.write(&self.body, &mut bytes)?;
+        self.pages.push((page.bos, bytes));
+
+        self.continued = page.lacing.last() == Some(&255);
+        self.sequence += 1;
+        self.page_granule = None;
+        self.body.clear();
+
+        Ok(())
+    }
+
+    fn end(&mut self) -> Result<(), PhonicError> {
+        if self.ended || self.sequence == 0 && self.lacing.is_empty() {
+            return Ok(());
+        }
+
+        if self.lacing.is_empty() {
+            self.page_granule = Some(self.granule_position);
+        }
+
+        self.write_page(true)?;
+        self.ended = true;
+        Ok(())
+    }
+}
+
+impl<T: Write, F: FormatTag> OggFormat<T, F> {
+    /// Writes out the pages of every stream. Every first page has to come before any other
+    /// page, so the rest are held back until each stream has started.
+    fn write_pages(&mut self, all: bool) -> Result<(), PhonicError> {
+        for writer in self.writers.iter_mut() {
+            for (bos, page) in writer.pages.drain(..) {
+                match bos {
+                    true => self.inner.as_inner_mut().write_all(&page)?,
+                    false => self.held_pages.extend_from_slice(&page),
+                }
+            }
+        }
+
+        if all || self.writers.iter().all(|writer| writer.sequence > 0) {
+            self.inner.as_inner_mut().write_all(&self.held_pages)?;
+            self.held_pages.clear();
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: Write, F: FormatTag> FormatWriter for OggFormat<T, F>
+where
+    F::Codec: TryInto<OggSupportedCodec>,
+{
+    fn write_data(&mut self, data: &FormatData<F>) -> Result<(), PhonicError> {
+        self.data.merge(data)?;
+
+        let writers = self
+            .data
+            .streams
+            .iter()
+            .enumerate()
+            .map(|(i, spec)| match spec.codec.map(TryInto::try_into) {
+                Some(Ok(OggSupportedCodec::Vorbis)) | Some(Err(_)) | None => {
+                    Err(PhonicError::Unsupported)
+                }
+                Some(Ok(codec)) => Ok(PageWriter::new(OggMapping::new(codec), SERIAL + i as u32)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if writers.is_empty() {
+            return Err(PhonicError::MissingData);
+        }
+
+        self.writers = writers;
+        Ok(())
+    }
+
+    fn write(&mut self, chunk: FormatChunk) -> Result<(), PhonicError> {
+        let FormatChunk::Stream { stream_i, buf } = chunk;
+        let writer = self
+            .writers
+            .get_mut(stream_i)
+            .ok_or(PhonicError::InvalidData)?;
+
+        writer.write(buf)?;
+        self.stream_i = stream_i;
+        self.write_pages(false)
+    }
+
+    fn flush(&mut self) -> Result<(), PhonicError> {
+        for writer in self.writers.iter_mut() {
+            writer.end()?;
+        }
+
+        self.write_pages(true)?;
+        self.inner.as_inner_mut().flush().map_err(Into::into)
+    }
+}
+
+impl<T: Read + Seek, F: FormatTag> FormatSeeker for OggFormat<T, F> {
+    fn seek(&mut self, offset: FormatOffset) -> Result<(), PhonicError> {
+        if !self.writers.is_empty() {
+            return Err(PhonicError::Unsupported);
+        }
+
+        if self.streams.is_empty() {
+            return Err(PhonicError::NotReady);
+        }
+
+        self.inner.enable_seeking()?;
+        let stream_i = (self.stream_i as isize)
+            .checked_add(offset.stream_offset)
+            .and_then(|i| usize::try_from(i).ok())
+            .filter(|i| *i < self.streams.len())
+            .ok_or(PhonicError::NotFound)?;
+
+        let position = self.streams[self.stream_i].position as i64;
+        let target = position.saturating_add(offset.byte_offset).max(0) as u64;
+
+        match self.streams[stream_i].mapping {
+            Some(mapping) if mapping.by_granule() => self.seek_granule(stream_i, target)?,
+            _ => self.seek_bytes(stream_i, target)?,
+        }
+
+        self.stream_i = stream_i;
+        Ok(())
+    }
+}
@@ -0,0 +1,8 @@
+mod data;
+mod format;
+mod mapping;
+mod page;
+
+pub use data::*;
+pub use format::*;
+pub use page::*;
@@ -0,0 +1,257 @@
+use crate::OggSupportedCodec;
+use phonic_codec_flac::{BlockHeader, FlacCodecTag, StreamInfo, FLAC_MARKER};
+use phonic_core::PhonicError;
+use phonic_io_core::StreamSpec;
+use phonic_signal::SignalSpecBuilder;
+
+const FLAC_PACKET_MARKER: &[u8] = b"\x7fFLAC";
+const OPUS_HEAD_MARKER: &[u8] = b"OpusHead";
+const VORBIS_ID_MARKER: &[u8] = b"\x01vorbis";
+
+/// The bytes ahead of the native flac marker in the first packet of an ogg flac stream.
+const FLAC_PREFIX_LEN: usize = 9;
+
+const FLAC_BLOCK_HEADER_LEN: usize = 4;
+const FLAC_STREAM_INFO_LEN: usize = 34;
+const FLAC_VORBIS_COMMENT_TYPE: u8 = 4;
+const FLAC_SEEK_TABLE_TYPE: u8 = 3;
+
+const VENDOR: &[u8] = b"phonic";
+
+/// How the packets of a logical bitstream relate to the stream a codec reads. Flac packets are
+/// passed on as a native flac stream, which numbers its own frames, so flac streams are
+/// positioned by byte. Opus and vorbis packets are passed on one at a time and positioned by
+/// granule, which counts frames the way the codec defines them.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct OggMapping {
+    pub codec: OggSupportedCodec,
+    pub stream_info: Option<StreamInfo>,
+    pub pre_skip: u64,
+}
+
+impl OggMapping {
+    pub fn new(codec: OggSupportedCodec) -> Self {
+        Self {
+            codec,
+            stream_info: None,
+            pre_skip: 0,
+        }
+    }
+
+    /// Identifies the codec of a logical bitstream from its first packet, returning the mapping
+    /// along with the spec of the stream.
+    pub fn identify(packet: &[u8]) -> Option<(Self, StreamSpec<OggSupportedCodec>)> {
+        if packet.starts_with(FLAC_PACKET_MARKER) {
+            let info_start = FLAC_PREFIX_LEN + FLAC_MARKER.len() + FLAC_BLOCK_HEADER_LEN;
+            let info = packet.get(info_start..info_start + FLAC_STREAM_INFO_LEN)?;
+            if packet[5] != 1 || packet[FLAC_PREFIX_LEN..FLAC_PREFIX_LEN + 4] != FLAC_MARKER {
+                return None;
+            }
+
+            let stream_info = StreamInfo::read(info.try_into().ok()?).ok()?;
+            let mapping = Self {
+                stream_info: Some(stream_info),
+                ..Self::new(OggSupportedCodec::Flac(FlacCodecTag::new()))
+            };
+
+            return Some((mapping, stream_info.stream_spec()));
+        }
+
+        if packet.starts_with(OPUS_HEAD_MARKER) && packet.len() >= 19 {
+            let n_channels = packet[9] as u16;
+            let mapping = Self {
+                pre_skip: u16::from_le_bytes([packet[10], packet[11]]) as u64,
+                ..Self::new(OggSupportedCodec::Opus)
+            };
+
+            // opus is always decoded at 48 khz, whatever the rate of the original input
+            let decoded_spec = SignalSpecBuilder::new()
+                .with_frame_rate(48_000)
+                .with_channels(n_channels);
+
+            let spec = StreamSpec::new()
+                .with_codec(OggSupportedCodec::Opus)
+                .with_sample_type::<f32>()
+                .with_decoded_spec(decoded_spec);
+
+            return Some((mapping, spec));
+        }
+
+        if packet.starts_with(VORBIS_ID_MARKER) && packet.len() >= 30 {
+            let n_channels = packet[11] as u16;
+            let sample_rate = u32::from_le_bytes(packet[12..16].try_into().ok()?);
+            let decoded_spec = SignalSpecBuilder::new()
+                .with_frame_rate(sample_rate)
+                .with_channels(n_channels);
+
+            let spec = StreamSpec::new()
+                .with_codec(OggSupportedCodec::Vorbis)
+                .with_sample_type::<f32>()
+                .with_decoded_spec(decoded_spec);
+
+            return Some((Self::new(OggSupportedCodec::Vorbis), spec));
+        }
+
+        None
+    }
+
+    pub fn by_granule(&self) -> bool {
+        !matches!(self.codec, OggSupportedCodec::Flac(_))
+    }
+
+    /// The number of packets at the start of the stream that hold headers rather than audio, if
+    /// it is fixed by the codec.
+    pub fn n_header_packets(&self) -> Option<u64> {
+        match self.codec {
+            OggSupportedCodec::Flac(_) => None,
+            OggSupportedCodec::Opus => Some(2),
+            OggSupportedCodec::Vorbis => Some(3),
+        }
+    }
+
+    /// The number of bytes left out from the start of the packet at `packet_i`.
+    pub fn packet_offset(&self, packet_i: u64) -> usize {
+        match (self.codec, packet_i) {
+            (OggSupportedCodec::Flac(_), 0) => FLAC_PREFIX_LEN,
+            _ => 0,
+        }
+    }
+
+    /// The number of decoded frames in a stream that ends at `granule_position`.
+    pub fn n_frames(&self, granule_position: u64) -> u64 {
+        granule_position.saturating_sub(self.pre_skip)
+    }
+
+    /// Splits the first write of a stream into the header packets of the mapping, returning
+    /// the packets along with any bytes past the headers.
+    pub fn header_packets<'a>(
+        &mut self,
+        buf: &'a [u8],
+    ) -> Result<(Vec<Vec<u8>>, &'a [u8]), PhonicError> {
+        match self.codec {
+            OggSupportedCodec::Flac(_) => self.flac_header_packets(buf),
+            _ => Ok((vec![buf.to_vec()], &[])),
+        }
+    }
+
+    /// Splits the metadata blocks of a native flac stream into packets. The stream info goes in
+    /// the first packet and a vorbis comment block in the second, as the mapping requires. Seek
+    /// tables are left out, since they point into the native stream.
+    fn flac_header_packets<'a>(
+        &mut self,
+        buf: &'a [u8],
+    ) -> Result<(Vec<Vec<u8>>, &'a [u8]), PhonicError> {
+        if !buf.starts_with(&FLAC_MARKER) {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let mut blocks = Vec::new();
+        let mut rest = &buf[FLAC_MARKER.len()..];
+        loop {
+            let header: [u8; FLAC_BLOCK_HEADER_LEN] = rest
+                .get(..FLAC_BLOCK_HEADER_LEN)
+                .and_then(|header| header.try_into().ok())
+                .ok_or(PhonicError::InvalidData)?;
+
+            let len = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+            let block = rest
+                .get(..FLAC_BLOCK_HEADER_LEN + len)
+                .ok_or(PhonicError::InvalidData)?;
+
+            blocks.push(block);
+            rest = &rest[block.len()..];
+            if header[0] & 0x80 != 0 {
+                break;
+            }
+        }
+
+        let [stream_info, others @ ..] = blocks.as_slice() else {
+            return Err(PhonicError::InvalidData);
+        };
+
+        let info = stream_info
+            .get(FLAC_BLOCK_HEADER_LEN..)
+            .and_then(|info| info.try_into().ok())
+            .ok_or(PhonicError::InvalidData)?;
+        self.stream_info = Some(StreamInfo::read(info)?);
+
+        let block_type = |block: &[u8]| block[0] & 0x7f;
+        let mut others: Vec<Vec<u8>> = others
+            .iter()
+            .filter(|block| block_type(block) != FLAC_SEEK_TABLE_TYPE)
+            .map(|block| block.to_vec())
+            .collect();
+
+        match others
+            .iter()
+            .position(|block| block_type(block) == FLAC_VORBIS_COMMENT_TYPE)
+        {
+            Some(i) => {
+                let comment = others.remove(i);
+                others.insert(0, comment);
+            }
+            None => others.insert(0, vorbis_comment_block()),
+        }
+
+        let n_others = others.len();
+        for (i, block) in others.iter_mut().enumerate() {
+            block[0] = block_type(block) | ((i + 1 == n_others) as u8) << 7;
+        }
+
+        let mut first = FLAC_PACKET_MARKER.to_vec();
+        first.extend_from_slice(&[1, 0]);
+        first.extend_from_slice(&(n_others as u16).to_be_bytes());
+        first.extend_from_slice(&FLAC_MARKER);
+        first.extend_from_slice(stream_info);
+        first[FLAC_PREFIX_LEN + FLAC_MARKER.len()] &= 0x7f;
+
+        let mut packets = vec![first];
+        packets.extend(others);
+        Ok((packets, rest))
+    }
+
+    /// Returns the granule position at the end of an audio packet that follows one ending at
+    /// `granule_position`.
+    pub fn granule_after(&self, packet: &[u8], granule_position: u64) -> Result<u64, PhonicError> {
+        match self.codec {
+            OggSupportedCodec::Flac(_) => {
+                let info = self.stream_info.as_ref().ok_or(PhonicError::NotReady)?;
+                let header = BlockHeader::read(packet)?;
+                let frame_i = header.frame_i(info).unwrap_or(granule_position);
+                Ok(frame_i + header.block_size as u64)
+            }
+            OggSupportedCodec::Opus => Ok(granule_position + opus_packet_len(packet)?),
+            OggSupportedCodec::Vorbis => Err(PhonicError::Unsupported),
+        }
+    }
+}
+
+/// A vorbis comment block with only a vendor string.
+fn vorbis_comment_block() -> Vec<u8> {
+    let len = 4 + VENDOR.len() + 4;
+    let mut block = vec![FLAC_VORBIS_COMMENT_TYPE];
+    block.extend_from_slice(&(len as u32).to_be_bytes()[1..]);
+    block.extend_from_slice(&(VENDOR.len() as u32).to_le_bytes());
+    block.extend_from_slice(VENDOR);
+    block.extend_from_slice(&0u32.to_le_bytes());
+    block
+}
+
+/// The number of 48 khz frames an opus packet decodes to, from its table of contents byte.
+fn opus_packet_len(packet: &[u8]) -> Result<u64, PhonicError> {
+    let toc = *packet.first().ok_or(PhonicError::InvalidData)?;
+    let config = toc >> 3;
+    let frame_len = match config {
+        0..=11 => [480, 960, 1920, 2880][config as usize % 4],
+        12..=15 => [480, 960][config as usize % 2],
+        _ => [120, 240, 480, 960][config as usize % 4],
+    };
+
+    let n_frames = match toc & 0x03 {
+        0 => 1,
+        1 | 2 => 2,
+        _ => (*packet.get(1).ok_or(PhonicError::InvalidData)? & 0x3f) as u64,
+    };
+
+    Ok(frame_len * n_frames)
+}
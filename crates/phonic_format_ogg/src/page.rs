@@ -0,0 +1,181 @@
+use std::io::{Read, Write};
+use phonic_core::PhonicError;
+
+pub const OGG_MARKER: [u8; 4] = *b"OggS";
+
+/// The most bytes a page body can hold, which is 255 lacing values of 255 bytes.
+pub const MAX_PAGE_BODY_LEN: usize = 255 * 255;
+
+const HEADER_LEN: usize = 27;
+
+const CONTINUED_FLAG: u8 = 0x01;
+const BOS_FLAG: u8 = 0x02;
+const EOS_FLAG: u8 = 0x04;
+
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = (i as u32) << 24;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = match crc & 0x8000_0000 {
+                0 => crc << 1,
+                _ => (crc << 1) ^ 0x04c1_1db7,
+            };
+
+            bit += 1;
+        }
+
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+};
+
+/// The header of a page, which carries a run of packet data for one logical bitstream. Packets
+/// are split into segments of 255 bytes, and a segment shorter than that ends a packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OggPage {
+    /// Whether the first segment continues a packet from the previous page of the stream.
+    pub continued: bool,
+
+    /// Whether this is the first page of the stream.
+    pub bos: bool,
+
+    /// Whether this is the last page of the stream.
+    pub eos: bool,
+
+    /// The position of the last packet that ends on this page, in the units of the codec. Pages
+    /// on which no packet ends have no granule position.
+    pub granule_position: Option<u64>,
+    pub serial: u32,
+    pub sequence: u32,
+    pub lacing: Vec<u8>,
+}
+
+/// A packet, or part of one, within the body of a page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageSegment {
+    pub start: usize,
+    pub end: usize,
+
+    /// Whether the packet ends on this page.
+    pub complete: bool,
+}
+
+pub(crate) fn crc32(crc: u32, bytes: &[u8]) -> u32 {
+    bytes.iter().fold(crc, |crc, byte| {
+        (crc << 8) ^ CRC_TABLE[((crc >> 24) as u8 ^ byte) as usize]
+    })
+}
+
+impl OggPage {
+    pub fn body_len(&self) -> usize {
+        self.lacing.iter().map(|len| *len as usize).sum()
+    }
+
+    pub fn header_len(&self) -> usize {
+        HEADER_LEN + self.lacing.len()
+    }
+
+    /// Returns the packets in the body, in order. The first one continues a packet from the
+    /// previous page if the page is marked as continued.
+    pub fn segments(&self) -> Vec<PageSegment> {
+        let mut segments = Vec::new();
+        let mut start = 0;
+        let mut end = 0;
+
+        for len in self.lacing.iter() {
+            end += *len as usize;
+            if *len < 255 {
+                segments.push(PageSegment {
+                    start,
+                    end,
+                    complete: true,
+                });
+
+                start = end;
+            }
+        }
+
+        if self.lacing.last() == Some(&255) {
+            segments.push(PageSegment {
+                start,
+                end,
+                complete: false,
+            });
+        }
+
+        segments
+    }
+
+    /// Reads a page and its body from the start of `reader`, checking the crc.
+    pub fn read<R: Read>(reader: &mut R) -> Result<(Self, Vec<u8>), PhonicError> {
+        let mut header = [0; HEADER_LEN];
+        reader.read_exact(&mut header)?;
+        if header[..4] != OGG_MARKER || header[4] != 0 {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let mut lacing = vec![0; header[26] as usize];
+        reader.read_exact(&mut lacing)?;
+
+        let granule_position = i64::from_le_bytes(header[6..14].try_into().unwrap());
+        let page = Self {
+            continued: header[5] & CONTINUED_FLAG != 0,
+            bos: header[5] & BOS_FLAG != 0,
+            eos: header[5] & EOS_FLAG != 0,
+            granule_position: u64::try_from(granule_position).ok(),
+            serial: u32::from_le_bytes(header[14..18].try_into().unwrap()),
+            sequence: u32::from_le_bytes(header[18..22].try_into().unwrap()),
+            lacing,
+        };
+
+        let mut body = vec![0; page.body_len()];
+        reader.read_exact(&mut body)?;
+
+        let expected = u32::from_le_bytes(header[22..26].try_into().unwrap());
+        header[22..26].fill(0);
+
+        let crc = crc32(0, &header);
+        let crc = crc32(crc, &page.lacing);
+        if crc32(crc, &body) != expected {
+            return Err(PhonicError::InvalidData);
+        }
+
+        Ok((page, body))
+    }
+
+    pub fn write<W: Write>(&self, body: &[u8], writer: &mut W) -> Result<(), PhonicError> {
+        if self.lacing.len() > 255 || self.body_len() != body.len() {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let flags = (self.continued as u8 * CONTINUED_FLAG)
+            | (self.bos as u8 * BOS_FLAG)
+            | (self.eos as u8 * EOS_FLAG);
+
+        let granule_position = self.granule_position.map_or(-1, |granule| granule as i64);
+
+        let mut header = [0; HEADER_LEN];
+        header[..4].copy_from_slice(&OGG_MARKER);
+        header[5] = flags;
+        header[6..14].copy_from_slice(&granule_position.to_le_bytes());
+        header[14..18].copy_from_slice(&self.serial.to_le_bytes());
+        header[18..22].copy_from_slice(&self.sequence.to_le_bytes());
+        header[26] = self.lacing.len() as u8;
+
+        let crc = crc32(0, &header);
+        let crc = crc32(crc, &self.lacing);
+        let crc = crc32(crc, body);
+        header[22..26].copy_from_slice(&crc.to_le_bytes());
+
+        writer.write_all(&header)?;
+        writer.write_all(&self.lacing)?;
+        writer.write_all(body)?;
+
+        Ok(())
+    }
+}
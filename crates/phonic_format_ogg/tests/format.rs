@@ -0,0 +1,279 @@
+use phonic_codec_flac::{FlacCodec, FlacCodecTag};
+use phonic_core::PhonicError;
+use phonic_format_ogg::{OggFormat, OggFormatTag, OggPage, OggSupportedCodec};
+use phonic_io_core::{
+    Format, FormatChunk, FormatData, FormatObserver, FormatPosition, FormatReader, FormatSeeker,
+    FormatWriter, StreamSpec,
+};
+use phonic_signal::{SignalReader, SignalSeeker, SignalSpecBuilder, SignalWriter};
+use std::{any::TypeId, io::Cursor};
+
+const PRE_SKIP: u64 = 312;
+const N_PACKETS: usize = 400;
+
+/// A celt only opus packet of one 20 ms frame.
+const PACKET_LEN: u64 = 960;
+
+fn opus_head(n_channels: u8) -> Vec<u8> {
+    let mut head = b"OpusHead".to_vec();
+    head.extend_from_slice(&[1, n_channels]);
+    head.extend_from_slice(&(PRE_SKIP as u16).to_le_bytes());
+    head.extend_from_slice(&44_100u32.to_le_bytes());
+    head.extend_from_slice(&[0, 0, 0]);
+    head
+}
+
+fn opus_packet(stream_i: usize, packet_i: usize) -> Vec<u8> {
+    let len = 200 + (packet_i * 37) % 400;
+    let mut packet = vec![0xf8, stream_i as u8];
+    packet.extend_from_slice(&(packet_i as u32).to_le_bytes());
+    packet.resize(len, packet_i as u8);
+    packet
+}
+
+fn opus_data(n_streams: usize) -> FormatData<OggFormatTag> {
+    (0..n_streams).fold(FormatData::new().with_format(OggFormatTag), |data, _| {
+        data.with_stream(StreamSpec::new().with_codec(OggSupportedCodec::Opus))
+    })
+}
+
+/// Writes streams of opus packets, interleaving them one packet at a time.
+fn opus_file(n_streams: usize) -> Vec<u8> {
+    let mut format = OggFormat::<_>::new(Cursor::new(Vec::new())).unwrap();
+    format.write_data(&opus_data(n_streams)).unwrap();
+
+    for stream_i in 0..n_streams {
+        for header in [opus_head(stream_i as u8 + 1), b"OpusTags".to_vec()] {
+            let chunk = FormatChunk::Stream {
+                stream_i,
+                buf: &header,
+            };
+
+            format.write(chunk).unwrap();
+        }
+    }
+
+    for packet_i in 0..N_PACKETS {
+        for stream_i in 0..n_streams {
+            let packet = opus_packet(stream_i, packet_i);
+            let chunk = FormatChunk::Stream {
+                stream_i,
+                buf: &packet,
+            };
+
+            format.write(chunk).unwrap();
+        }
+    }
+
+    FormatWriter::flush(&mut format).unwrap();
+    format.into_inner().into_inner()
+}
+
+/// Reads every remaining chunk, grouped by stream.
+fn read_packets<T>(format: &mut OggFormat<T>, n_streams: usize) -> Vec<Vec<Vec<u8>>>
+where
+    T: std::io::Read,
+{
+    let mut packets = vec![Vec::new(); n_streams];
+    let mut n_empty = 0;
+    let mut buf = [0; 1024];
+    while n_empty < n_streams {
+        let FormatChunk::Stream { stream_i, buf } = format.read(&mut buf).unwrap();
+        match buf {
+            [] => n_empty += 1,
+            buf => packets[stream_i].push(buf.to_vec()),
+        }
+    }
+
+    packets
+}
+
+fn pages(file: &[u8]) -> Vec<OggPage> {
+    let mut pages = Vec::new();
+    let mut reader = file;
+    while !reader.is_empty() {
+        pages.push(OggPage::read(&mut reader).unwrap().0);
+    }
+
+    pages
+}
+
+#[test]
+fn streams_are_multiplexed() {
+    let file = opus_file(2);
+    let pages = pages(&file);
+
+    // every stream starts before any other page
+    assert!(pages[0].bos && pages[1].bos);
+    assert!(pages[2..].iter().all(|page| !page.bos));
+    assert_eq!(pages.iter().filter(|page| page.eos).count(), 2);
+
+    let mut format = OggFormat::<_>::new(Cursor::new(file))
+        .unwrap()
+        .with_seekable_reads();
+    format.read_data().unwrap();
+
+    let data = format.data();
+    assert_eq!(data.streams.len(), 2);
+    for (i, spec) in data.streams.iter().enumerate() {
+        assert_eq!(spec.codec, Some(OggSupportedCodec::Opus));
+        assert_eq!(spec.sample_type, Some(TypeId::of::<f32>()));
+        assert_eq!(spec.decoded_spec.frame_rate, Some(48_000));
+        assert_eq!(spec.decoded_spec.channels, Some((i as u16 + 1).into()));
+        assert_eq!(
+            spec.decoded_spec.n_frames,
+            Some(N_PACKETS as u64 * PACKET_LEN - PRE_SKIP)
+        );
+    }
+
+    let packets = read_packets(&mut format, 2);
+    for (stream_i, packets) in packets.iter().enumerate() {
+        assert_eq!(packets.len(), N_PACKETS + 2);
+        assert_eq!(packets[0], opus_head(stream_i as u8 + 1));
+        assert_eq!(packets[1], b"OpusTags");
+        for (packet_i, packet) in packets[2..].iter().enumerate() {
+            assert_eq!(*packet, opus_packet(stream_i, packet_i));
+        }
+    }
+}
+
+#[test]
+fn files_are_read_without_seeking() {
+    let file = opus_file(2);
+
+    // a slice reader can't seek, so the lengths are left unknown
+    let mut format = OggFormat::<_>::new(file.as_slice()).unwrap();
+    format.read_data().unwrap();
+    assert_eq!(format.data().streams.len(), 2);
+    assert!(format
+        .data()
+        .streams
+        .iter()
+        .all(|spec| spec.decoded_spec.n_frames.is_none()));
+
+    let packets = read_packets(&mut format, 2);
+    for (stream_i, packets) in packets.iter().enumerate() {
+        assert_eq!(packets.len(), N_PACKETS + 2);
+        assert_eq!(packets[0], opus_head(stream_i as u8 + 1));
+        for (packet_i, packet) in packets[2..].iter().enumerate() {
+            assert_eq!(*packet, opus_packet(stream_i, packet_i));
+        }
+    }
+}
+
+#[test]
+fn seeks_find_the_page_before_a_granule() {
+    let file = opus_file(2);
+    let mut format = OggFormat::<_>::new(Cursor::new(file)).unwrap();
+    format.read_data().unwrap();
+
+    for (stream_i, target) in [
+        (1, 100_000),
+        (0, 0),
+        (0, 383_000),
+        (1, 5_000),
+        (0, 10_000_000),
+        (1, 959),
+        (0, 250_000),
+    ] {
+        format
+            .set_position(FormatPosition {
+                stream_i,
+                byte_i: target,
+            })
+            .unwrap();
+
+        let FormatPosition { byte_i, .. } = format.position().unwrap();
+        assert!(byte_i <= target);
+        assert!(target - byte_i < 20 * PACKET_LEN || byte_i == N_PACKETS as u64 * PACKET_LEN);
+
+        let packets = read_packets(&mut format, 2);
+        let next_i = (byte_i / PACKET_LEN) as usize;
+        assert_eq!(packets[stream_i].len(), N_PACKETS - next_i);
+        if let Some(packet) = packets[stream_i].first() {
+            assert_eq!(*packet, opus_packet(stream_i, next_i));
+        }
+    }
+}
+
+#[test]
+fn damaged_pages_are_skipped() {
+    let mut file = opus_file(1);
+    let page_len = |page: &OggPage| page.header_len() + page.body_len();
+    let pages = pages(&file);
+    let offset: usize = pages[..5].iter().map(page_len).sum();
+    file[offset + 40] ^= 0xff;
+
+    let mut format = OggFormat::<_>::new(Cursor::new(file)).unwrap();
+    let packets = read_packets(&mut format, 1).remove(0);
+    assert!(packets.len() < N_PACKETS + 2);
+    assert_eq!(packets.last(), Some(&opus_packet(0, N_PACKETS - 1)));
+
+    let mut format = OggFormat::<_>::new(Cursor::new(b"RIFF....WAVE".to_vec())).unwrap();
+    assert!(matches!(format.read_data(), Err(PhonicError::InvalidData)));
+}
+
+#[test]
+fn flac_streams_round_trip() {
+    let samples: Vec<i16> = (0..30_000)
+        .map(|i| ((i * 37) % 2_000 - 1_000) as i16)
+        .collect();
+    let decoded_spec = SignalSpecBuilder::new()
+        .with_frame_rate(8_000)
+        .with_channels(2)
+        .with_n_frames(15_000);
+
+    let data = FormatData::new()
+        .with_format(OggFormatTag)
+        .with_stream(StreamSpec::new().with_decoded_spec(decoded_spec))
+        .filled()
+        .unwrap();
+
+    assert_eq!(
+        data.streams[0].codec,
+        Some(OggSupportedCodec::Flac(FlacCodecTag::new()))
+    );
+
+    let mut format = OggFormat::<_>::new(Cursor::new(Vec::new())).unwrap();
+    format.write_data(&data).unwrap();
+
+    let mut encoder = FlacCodec::<_, i16, _>::from_stream(format.as_stream(0).unwrap()).unwrap();
+    encoder.write_exact(&samples).unwrap();
+    SignalWriter::flush(&mut encoder).unwrap();
+    FormatWriter::flush(&mut format).unwrap();
+
+    let file = format.into_inner().into_inner();
+    let pages = pages(&file);
+    assert_eq!(&pages[0].lacing, &[51]);
+    assert_eq!(pages[1].granule_position, Some(0));
+    assert_eq!(pages.last().unwrap().granule_position, Some(15_000));
+
+    let mut format = OggFormat::<_>::new(Cursor::new(file))
+        .unwrap()
+        .with_seekable_reads();
+    format.read_data().unwrap();
+    assert_eq!(format.data().streams[0].decoded_spec.n_frames, Some(15_000));
+
+    let mut decoder = FlacCodec::<_, i16, _>::from_stream(format.as_stream(0).unwrap()).unwrap();
+    let mut decoded = vec![0; samples.len()];
+    decoder.read_exact(&mut decoded).unwrap();
+    assert_eq!(decoded, samples);
+
+    decoder.seek(-10_000).unwrap();
+    let mut decoded = vec![0; 4_000];
+    decoder.read_exact(&mut decoded).unwrap();
+    assert_eq!(decoded, samples[20_000..24_000]);
+}
+
+#[test]
+fn vorbis_is_not_written() {
+    let data = FormatData::new()
+        .with_format(OggFormatTag)
+        .with_stream(StreamSpec::new().with_codec(OggSupportedCodec::Vorbis));
+
+    let mut format = OggFormat::<_>::new(Cursor::new(Vec::new())).unwrap();
+    assert!(matches!(
+        format.write_data(&data),
+        Err(PhonicError::Unsupported)
+    ));
+}
@@ -0,0 +1,98 @@
+use phonic_core::PhonicError;
+use phonic_format_ogg::{OggPage, PageSegment, OGG_MARKER};
+
+fn page(lacing: Vec<u8>) -> OggPage {
+    OggPage {
+        continued: true,
+        bos: false,
+        eos: true,
+        granule_position: Some(123_456),
+        serial: 0x1234_5678,
+        sequence: 7,
+        lacing,
+    }
+}
+
+#[test]
+fn pages_round_trip() {
+    let page = page(vec![255, 10, 0, 255]);
+    let body: Vec<u8> = (0..page.body_len()).map(|i| i as u8).collect();
+
+    let mut bytes = Vec::new();
+    page.write(&body, &mut bytes).unwrap();
+    assert_eq!(bytes[..4], OGG_MARKER);
+    assert_eq!(bytes.len(), page.header_len() + body.len());
+
+    let (read, read_body) = OggPage::read(&mut bytes.as_slice()).unwrap();
+    assert_eq!(read, page);
+    assert_eq!(read_body, body);
+
+    let unknown = OggPage {
+        granule_position: None,
+        ..page.clone()
+    };
+
+    let mut bytes = Vec::new();
+    unknown.write(&body, &mut bytes).unwrap();
+    assert_eq!(bytes[6..14], [0xff; 8]);
+    assert_eq!(OggPage::read(&mut bytes.as_slice()).unwrap().0, unknown);
+}
+
+#[test]
+fn corrupt_pages_fail_the_crc() {
+    let page = page(vec![20]);
+    let mut bytes = Vec::new();
+    page.write(&[3; 20], &mut bytes).unwrap();
+
+    for i in [5, 14, 30] {
+        let mut corrupt = bytes.clone();
+        corrupt[i] ^= 0x10;
+        assert!(matches!(
+            OggPage::read(&mut corrupt.as_slice()),
+            Err(PhonicError::InvalidData)
+        ));
+    }
+
+    assert!(matches!(
+        OggPage::read(&mut &bytes[..bytes.len() - 1]),
+        Err(PhonicError::EndOfStream)
+    ));
+
+    assert!(matches!(
+        page.write(&[3; 19], &mut Vec::new()),
+        Err(PhonicError::InvalidData)
+    ));
+}
+
+#[test]
+fn lacing_splits_packets() {
+    let page = page(vec![255, 255, 0, 100, 255]);
+    assert_eq!(page.body_len(), 865);
+    assert_eq!(
+        page.segments(),
+        [
+            PageSegment {
+                start: 0,
+                end: 510,
+                complete: true,
+            },
+            PageSegment {
+                start: 510,
+                end: 610,
+                complete: true,
+            },
+            PageSegment {
+                start: 610,
+                end: 865,
+                complete: false,
+            },
+        ]
+    );
+
+    assert!(OggPage {
+        lacing: Vec::new(),
+        ..page
+    }
+    .segments()
+    .is_empty());
+}
@@ -1,16 +1,33 @@
-use phonic_codec_pcm::{fill_pcm_spec, PcmCodecTag};
+use phonic_codec_adpcm::{fill_adpcm_spec, AdpcmCodecTag};
+use phonic_codec_g711::{fill_g711_spec, G711CodecTag};
+use phonic_codec_pcm::{fill_pcm_spec, ByteOrder, PcmCodecTag};
 use phonic_core::PhonicError;
 use phonic_io_core::{
-    utils::FormatIdentifiers, CodecTag, DynFormat, DynFormatConstructor, FormatData, FormatTag,
-    StdIoSource, StreamSpec,
+    utils::{FormatIdentifiers, FormatMarker},
+    CodecTag, FormatData, FormatTag, StreamSpec,
 };
 
-use crate::WaveFormat;
-
 pub static WAVE_IDENTIFIERS: FormatIdentifiers = FormatIdentifiers {
     file_extensions: &["wav", "wave"],
     mime_types: &["audio/vnd.wave", "audio/x-wav", "audio/wav", "audio/wave"],
-    markers: &[b"RIFF", b"WAVE"],
+    markers: &[
+        FormatMarker {
+            offset: 0,
+            bytes: b"RIFF",
+        },
+        FormatMarker {
+            offset: 0,
+            bytes: b"RF64",
+        },
+        FormatMarker {
+            offset: 0,
+            bytes: b"BW64",
+        },
+        FormatMarker {
+            offset: 8,
+            bytes: b"WAVE",
+        },
+    ],
 };
 
 #[derive(PartialEq, Eq, Clone, Copy)]
@@ -19,6 +36,10 @@ pub struct WaveFormatTag;
 #[derive(PartialEq, Eq, Clone, Copy)]
 pub enum WaveSupportedCodec {
     Pcm,
+    ALaw,
+    MuLaw,
+    ImaAdpcm,
+    MsAdpcm,
 }
 
 pub fn fill_wave_data<F>(data: &mut FormatData<F>) -> Result<(), PhonicError>
@@ -52,13 +73,26 @@ impl FormatTag for WaveFormatTag {
 
 impl CodecTag for WaveSupportedCodec {
     fn fill_spec(spec: &mut StreamSpec<Self>) -> Result<(), PhonicError> {
-        fill_pcm_spec(spec)
+        match spec.codec {
+            Some(Self::ALaw | Self::MuLaw) => fill_g711_spec(spec),
+            Some(Self::ImaAdpcm | Self::MsAdpcm) => fill_adpcm_spec(spec),
+            _ => fill_pcm_spec(spec),
+        }
     }
 }
 
-impl From<PcmCodecTag> for WaveSupportedCodec {
-    fn from(_: PcmCodecTag) -> Self {
-        Self::Pcm
+impl TryFrom<PcmCodecTag> for WaveSupportedCodec {
+    type Error = PhonicError;
+
+    // wave files store every sample in little endian with the encoding of its sample type
+    fn try_from(tag: PcmCodecTag) -> Result<Self, Self::Error> {
+        match tag {
+            PcmCodecTag {
+                byte_order: ByteOrder::LittleEndian,
+                encoding: None,
+            } => Ok(Self::Pcm),
+            _ => Err(PhonicError::Unsupported),
+        }
     }
 }
 
@@ -67,7 +101,49 @@ impl TryFrom<WaveSupportedCodec> for PcmCodecTag {
 
     fn try_from(codec: WaveSupportedCodec) -> Result<Self, Self::Error> {
         match codec {
-            WaveSupportedCodec::Pcm => Ok(PcmCodecTag),
+            WaveSupportedCodec::Pcm => Ok(PcmCodecTag::default()),
+            _ => Err(PhonicError::Unsupported),
+        }
+    }
+}
+
+impl From<G711CodecTag> for WaveSupportedCodec {
+    fn from(tag: G711CodecTag) -> Self {
+        match tag {
+            G711CodecTag::ALaw => Self::ALaw,
+            G711CodecTag::MuLaw => Self::MuLaw,
+        }
+    }
+}
+
+impl TryFrom<WaveSupportedCodec> for G711CodecTag {
+    type Error = PhonicError;
+
+    fn try_from(codec: WaveSupportedCodec) -> Result<Self, Self::Error> {
+        match codec {
+            WaveSupportedCodec::ALaw => Ok(Self::ALaw),
+            WaveSupportedCodec::MuLaw => Ok(Self::MuLaw),
+            _ => Err(PhonicError::Unsupported),
+        }
+    }
+}
+
+impl From<AdpcmCodecTag> for WaveSupportedCodec {
+    fn from(tag: AdpcmCodecTag) -> Self {
+        match tag {
+            AdpcmCodecTag::Ima => Self::ImaAdpcm,
+            AdpcmCodecTag::Microsoft => Self::MsAdpcm,
+        }
+    }
+}
+
+impl TryFrom<WaveSupportedCodec> for AdpcmCodecTag {
+    type Error = PhonicError;
+
+    fn try_from(codec: WaveSupportedCodec) -> Result<Self, Self::Error> {
+        match codec {
+            WaveSupportedCodec::ImaAdpcm => Ok(Self::Ima),
+            WaveSupportedCodec::MsAdpcm => Ok(Self::Microsoft),
             _ => Err(PhonicError::Unsupported),
         }
     }
@@ -1,15 +1,29 @@
-use crate::{WaveFormatTag, WaveHeader, WaveSupportedCodec};
-use std::io::{Read, Seek, Write};
+use crate::{WaveChunk, WaveFormatTag, WaveHeader, WaveSupportedCodec, UNKNOWN_LEN};
+use std::io::{Read, Seek, SeekFrom, Write};
 use phonic_core::PhonicError;
 use phonic_io_core::{
-    Format, FormatChunk, FormatData, FormatObserver, FormatReader, FormatSeeker, FormatTag,
-    FormatWriter,
+    Format, FormatChunk, FormatData, FormatMetadata, FormatObserver, FormatOffset, FormatPosition,
+    FormatReader, FormatSeeker, FormatTag, FormatWriter,
 };
 
+type PatchHeader<T, F> = fn(&mut WaveFormat<T, F>) -> Result<(), PhonicError>;
+
 pub struct WaveFormat<T, F: FormatTag = WaveFormatTag> {
-    inner: T,
+    /// Only taken by `into_inner`, since the format can't be moved out of once it's dropped.
+    inner: Option<T>,
     i: usize,
+    data_start: usize,
+    data_len: usize,
     data: FormatData<F>,
+    chunks: Vec<WaveChunk>,
+
+    /// The header of data written without a known length, which was written with placeholder
+    /// sizes.
+    unsized_header: Option<WaveHeader>,
+
+    /// Rewrites the unsized header with the length of the data written so far, for writers made
+    /// with `with_seekable_writes`.
+    patch_header: Option<PatchHeader<T, F>>,
 }
 
 impl<T, F: FormatTag> WaveFormat<T, F> {
@@ -19,20 +33,55 @@ impl<T, F: FormatTag> WaveFormat<T, F> {
     {
         let mut data = FormatData::new();
         data.format = WaveFormatTag.try_into().ok();
-        Ok(Self { inner, i: 0, data })
+        Ok(Self {
+            inner: Some(inner),
+            i: 0,
+            data_start: 0,
+            data_len: 0,
+            data,
+            chunks: Vec::new(),
+            unsized_header: None,
+            patch_header: None,
+        })
     }
 
-    fn trim_buf_len(&self, mut len: usize) -> usize {
-        let stream_spec = self.data.streams.get(0);
-        // if let Some(n_bytes) = stream_spec.and_then(StreamSpec::n_bytes) {
-        //     len = len.min(n_bytes as usize);
-        // }
+    pub fn as_inner(&self) -> &T {
+        self.inner.as_ref().unwrap()
+    }
 
-        if let Some(block_align) = stream_spec.and_then(|spec| spec.block_align) {
-            len = len - len % block_align as usize;
+    /// Returns the inner writer, after patching the header as flushing does.
+    pub fn into_inner(mut self) -> T {
+        if let Some(patch_header) = self.patch_header.take() {
+            // there is no way to report the error, and the placeholder sizes are still valid
+            let _ = patch_header(&mut self);
         }
 
-        len
+        self.inner.take().unwrap()
+    }
+
+    fn inner_mut(&mut self) -> &mut T {
+        self.inner.as_mut().unwrap()
+    }
+
+    /// The chunks of the file that have been read, up to the data chunk unless the chunks after
+    /// it have been read with `read_trailing_chunks`.
+    pub fn chunks(&self) -> &[WaveChunk] {
+        &self.chunks
+    }
+
+    fn block_align(&self) -> usize {
+        self.data
+            .streams
+            .first()
+            .and_then(|spec| spec.block_align)
+            .map_or(1, |align| align.max(1) as usize)
+    }
+
+    fn trim_buf_len(&self, len: usize) -> usize {
+        let remaining = (self.data_start + self.data_len).saturating_sub(self.i);
+        let len = len.min(remaining);
+
+        len - len % self.block_align()
     }
 }
 
@@ -45,8 +94,11 @@ impl<T, F: FormatTag> Format for WaveFormat<T, F> {
 }
 
 impl<T, F: FormatTag> FormatObserver for WaveFormat<T, F> {
-    fn position(&self) -> Result<phonic_io_core::FormatPosition, PhonicError> {
-        todo!()
+    fn position(&self) -> Result<FormatPosition, PhonicError> {
+        Ok(FormatPosition {
+            stream_i: 0,
+            byte_i: self.i.saturating_sub(self.data_start) as u64,
+        })
     }
 }
 
@@ -60,9 +112,19 @@ where
             return Ok(());
         }
 
-        self.data
-            .merge(&WaveHeader::read(&mut self.inner)?.into())?;
-        return Ok(());
+        let mut reader = self.inner_mut().take(u64::MAX);
+        let mut header = WaveHeader::read(&mut reader)?;
+        let header_len = (u64::MAX - reader.limit()) as usize;
+        let chunks = std::mem::take(&mut header.chunks);
+        let data_len = header.data.byte_len as usize;
+
+        self.data.merge(&header.into())?;
+        self.chunks = chunks;
+        self.data_start = header_len;
+        self.data_len = data_len;
+        self.i = header_len;
+
+        Ok(())
     }
 
     fn read<'a>(&'a mut self, buf: &'a mut [u8]) -> Result<FormatChunk<'a>, PhonicError> {
@@ -71,16 +133,10 @@ where
         }
 
         let len = self.trim_buf_len(buf.len());
-        let n = self.inner.read(&mut buf[..len])?;
+        let n = self.inner_mut().read(&mut buf[..len])?;
         self.i += n;
 
-        if self
-            .data
-            .streams
-            .get(0)
-            .and_then(|spec| spec.block_align)
-            .is_some_and(|align| n % align as usize != 0)
-        {
+        if n % self.block_align() != 0 {
             return Err(PhonicError::SignalMismatch);
         }
 
@@ -91,12 +147,19 @@ where
     }
 }
 
-impl<T: Write, F: FormatTag> FormatWriter for WaveFormat<T, F> {
+impl<T: Write, F: FormatTag> FormatWriter for WaveFormat<T, F>
+where
+    F::Codec: TryInto<WaveSupportedCodec>,
+{
     fn write_data(&mut self, data: &FormatData<F>) -> Result<(), PhonicError> {
         self.data.merge(data)?;
         let header = WaveHeader::try_from(&self.data)?;
-        header.write(&mut self.inner)?;
-        self.i += header.byte_len() as usize;
+        header.write(self.inner_mut())?;
+
+        self.data_len = header.data.byte_len as usize;
+        self.data_start = header.data_offset() as usize;
+        self.i = self.data_start;
+        self.unsized_header = Some(header).filter(WaveHeader::has_unknown_len);
 
         Ok(())
     }
@@ -104,11 +167,11 @@ impl<T: Write, F: FormatTag> FormatWriter for WaveFormat<T, F> {
     fn write(&mut self, chunk: FormatChunk) -> Result<(), PhonicError> {
         match chunk {
             FormatChunk::Stream { stream_i, buf } if self.i > 0 && stream_i == 0 => {
-                if buf.len() != self.trim_buf_len(buf.len()) {
+                if buf.len() % self.block_align() != 0 {
                     return Err(PhonicError::SignalMismatch);
                 }
 
-                self.inner.write_all(buf)?;
+                self.inner_mut().write_all(buf)?;
                 self.i += buf.len();
             }
             _ => return Err(PhonicError::InvalidData),
@@ -118,12 +181,138 @@ impl<T: Write, F: FormatTag> FormatWriter for WaveFormat<T, F> {
     }
 
     fn flush(&mut self) -> Result<(), PhonicError> {
-        self.inner.flush().map_err(Into::into)
+        // the data chunk is padded to an even length once all of it has been written
+        let data_end = self.data_start + self.data_len;
+        if self.unsized_header.is_none() && self.data_len % 2 == 1 && self.i == data_end {
+            self.inner_mut().write_all(&[0])?;
+            self.i += 1;
+        }
+
+        if let Some(patch_header) = self.patch_header {
+            patch_header(self)?;
+        }
+
+        self.inner_mut().flush().map_err(Into::into)
+    }
+}
+
+impl<T: Write + Seek, F: FormatTag> WaveFormat<T, F> {
+    /// Lets the writer seek back to the header of data written without a known length, to fill
+    /// in its sizes when flushed or dropped. Writers that can't seek leave the `UNKNOWN_LEN`
+    /// placeholders, which readers take to mean the data runs to the end of the file.
+    pub fn with_seekable_writes(mut self) -> Self {
+        self.patch_header = Some(Self::patch_header);
+        self
+    }
+
+    fn patch_header(&mut self) -> Result<(), PhonicError> {
+        let Some(mut header) = self.unsized_header.clone() else {
+            return Ok(());
+        };
+
+        // the header is the same length whatever the sizes, as riff files reserve the room a
+        // ds64 chunk needs
+        let byte_len = (self.i - self.data_start) as u64;
+        header.data.byte_len = byte_len;
+        if let Some(fact) = &mut header.fact {
+            fact.n_frames = byte_len / header.fmt.block_align.max(1) as u64;
+        }
+
+        let i = self.i as u64;
+        let inner = self.inner_mut();
+
+        // a writer that turns out not to seek keeps the placeholders
+        if inner.seek(SeekFrom::Start(0)).is_err() {
+            self.patch_header = None;
+            return Ok(());
+        }
+
+        header.write(inner)?;
+        inner.seek(SeekFrom::Start(i))?;
+
+        // the pad byte is written over by any data written after it
+        if byte_len % 2 == 1 {
+            inner.write_all(&[0])?;
+            inner.seek(SeekFrom::Start(i))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<T, F: FormatTag> Drop for WaveFormat<T, F> {
+    fn drop(&mut self) {
+        if let (Some(patch_header), Some(_)) = (self.patch_header, &self.inner) {
+            let _ = patch_header(self);
+        }
+    }
+}
+
+impl<T: Read + Seek, F: FormatTag> WaveFormat<T, F> {
+    /// Reads the ids and lengths of the chunks that follow the data chunk, which reading the
+    /// data never reaches, and adds them to `chunks`. The metadata of any `bext` or `iXML` chunks
+    /// among them is added to the format data. The position in the data is kept.
+    pub fn read_trailing_chunks(&mut self) -> Result<&[WaveChunk], PhonicError> {
+        if self.i == 0 {
+            return Err(PhonicError::NotReady);
+        }
+
+        if self.chunks.last().is_some_and(|chunk| &chunk.id == b"data") {
+            let data_end = (self.data_start + self.data_len + self.data_len % 2) as u64;
+            let i = self.i as u64;
+            let trailing = WaveHeader::read_chunks(self.inner_mut(), data_end)?;
+
+            let metadata =
+                trailing
+                    .iter()
+                    .try_fold(FormatMetadata::new(), |mut metadata, chunk| {
+                        if let Some(chunk) =
+                            WaveHeader::read_chunk_metadata(self.inner_mut(), chunk)?
+                        {
+                            metadata.merge(&chunk)?;
+                        }
+
+                        Ok::<_, PhonicError>(metadata)
+                    });
+
+            // the position is kept even when the metadata can't be read
+            self.inner_mut().seek(SeekFrom::Start(i))?;
+            self.data.metadata.merge(&metadata?)?;
+            self.chunks.extend(trailing);
+        }
+
+        Ok(&self.chunks)
     }
 }
 
 impl<T: Seek, F: FormatTag> FormatSeeker for WaveFormat<T, F> {
-    fn seek(&mut self, offset: phonic_io_core::FormatOffset) -> Result<(), PhonicError> {
-        todo!()
+    fn seek(&mut self, offset: FormatOffset) -> Result<(), PhonicError> {
+        if offset.stream_offset != 0 {
+            return Err(PhonicError::NotFound);
+        }
+
+        if self.i == 0 {
+            return Err(PhonicError::NotReady);
+        }
+
+        // data of unknown length runs to the end of the file
+        let data_len = match self.data_len {
+            len if len == UNKNOWN_LEN as usize => {
+                let end = self.inner_mut().seek(SeekFrom::End(0))? as usize;
+                end.saturating_sub(self.data_start)
+            }
+            len => len,
+        };
+
+        let byte_i = ((self.i - self.data_start) as i64).saturating_add(offset.byte_offset);
+        let byte_i = byte_i.clamp(0, data_len as i64) as usize;
+        let byte_i = byte_i - byte_i % self.block_align();
+
+        let data_start = self.data_start as u64;
+        self.inner_mut()
+            .seek(SeekFrom::Start(data_start + byte_i as u64))?;
+        self.i = self.data_start + byte_i;
+
+        Ok(())
     }
 }
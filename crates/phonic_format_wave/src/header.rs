@@ -1,17 +1,66 @@
-use crate::{WaveFormatTag, WaveSupportedCodec};
-use std::io::{Read, Write};
+use crate::{BextChunk, IxmlChunk, WaveFormatTag, WaveSupportedCodec};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use phonic_codec_adpcm::{AdpcmCodecTag, MS_ADPCM_COEFFICIENTS};
 use phonic_core::PhonicError;
-use phonic_io_core::{FormatData, FormatTag, StreamSpec};
+use phonic_io_core::{FormatData, FormatMetadata, FormatTag, StreamSpec};
 use phonic_signal::{ChannelLayout, Channels, KnownSampleType, SignalSpecBuilder};
 
 const RIFF_CHUNK_ID: &[u8; 4] = b"RIFF";
+const RF64_CHUNK_ID: &[u8; 4] = b"RF64";
+const BW64_CHUNK_ID: &[u8; 4] = b"BW64";
 const WAVE_CHUNK_ID: &[u8; 4] = b"WAVE";
+const JUNK_CHUNK_ID: &[u8; 4] = b"JUNK";
 
-#[derive(Clone, Copy)]
+/// The riff and data sizes of a file whose length wasn't known when its header was written, and
+/// whose data runs to the end of the file.
+pub const UNKNOWN_LEN: u32 = u32::MAX;
+
+#[derive(Clone)]
 pub struct WaveHeader {
+    /// The id the file starts with. A riff header is written as rf64 when its sizes don't fit in
+    /// 32 bits.
+    pub form: WaveForm,
     pub fmt: FmtChunk,
     pub fact: Option<FactChunk>,
     pub data: DataChunk,
+    pub bext: Option<BextChunk>,
+    pub ixml: Option<IxmlChunk>,
+
+    /// Every chunk found while reading the header, in the order of the file, up to and including
+    /// the data chunk. Writing the header leaves out all but its own chunks.
+    pub chunks: Vec<WaveChunk>,
+}
+
+/// The id and location of a chunk of a file. The chunks the header doesn't need are skipped, and
+/// can be read by seeking to their offset.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct WaveChunk {
+    pub id: [u8; 4],
+
+    /// The number of bytes from the start of the file to the body of the chunk.
+    pub offset: u64,
+    pub byte_len: u64,
+}
+
+/// The forms a wave file comes in. Rf64 and bw64 files are riff files that keep the sizes too
+/// large for 32 bits in a `ds64` chunk, and set the 32-bit fields to `u32::MAX`.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum WaveForm {
+    #[default]
+    Riff,
+    Rf64,
+    Bw64,
+}
+
+const DS64_CHUNK_ID: &[u8; 4] = b"ds64";
+
+/// The 64-bit sizes of an rf64 file. Written headers always reserve room for one right after the
+/// riff header, as a `JUNK` chunk in riff files, so a file can be promoted in place.
+struct Ds64Chunk {
+    riff_len: u64,
+    data_len: u64,
+    n_frames: u64,
+    table: Vec<([u8; 4], u64)>,
 }
 
 const FMT_CHUNK_ID: &[u8; 4] = b"fmt ";
@@ -27,6 +76,15 @@ pub struct FmtChunk {
     pub ext: Option<FmtChunkExt>,
 }
 
+/// The format tag of a fmt chunk whose format is given by the sub format of its extension.
+pub const WAVE_FORMAT_EXTENSIBLE: u16 = 0xfffe;
+
+/// The bytes that follow the format tag in the sub format guids derived from a format tag, as in
+/// `00000001-0000-0010-8000-00aa00389b71` for pcm.
+const SUB_FORMAT_GUID_TAIL: [u8; 14] = [
+    0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b, 0x71,
+];
+
 #[derive(Clone, Copy)]
 pub struct FmtChunkExt {
     pub valid_bits_per_sample: u16,
@@ -35,88 +93,252 @@ pub struct FmtChunkExt {
 }
 
 const FACT_CHUNK_ID: &[u8; 4] = b"fact";
+const BEXT_CHUNK_ID: &[u8; 4] = b"bext";
+const IXML_CHUNK_ID: &[u8; 4] = b"iXML";
 
 #[derive(Clone, Copy)]
 pub struct FactChunk {
-    pub n_frames: u32,
+    pub n_frames: u64,
 }
 
 const DATA_CHUNK_ID: &[u8; 4] = b"data";
 
 #[derive(Clone, Copy)]
 pub struct DataChunk {
-    pub byte_len: u32,
+    pub byte_len: u64,
 }
 
 impl WaveHeader {
-    pub fn byte_len(&self) -> u32 {
-        8 + WAVE_CHUNK_ID.len() as u32
+    /// The number of bytes from the start of the file to the first byte of sample data.
+    pub fn data_offset(&self) -> u64 {
+        8 + WAVE_CHUNK_ID.len() as u64
             + 8
-            + self.fmt.byte_len()
-            + self.fact.as_ref().map_or(0, |f| 8 + f.byte_len())
+            + Ds64Chunk::BYTE_LEN as u64
             + 8
-            + self.data.byte_len
+            + self.fmt.byte_len() as u64
+            + self.fact.as_ref().map_or(0, |f| 8 + f.byte_len() as u64)
+            + self.bext.as_ref().map_or(0, |b| 8 + padded(b.byte_len()))
+            + self.ixml.as_ref().map_or(0, |i| 8 + padded(i.xml.len()))
+            + 8
+    }
+
+    pub fn byte_len(&self) -> u64 {
+        // chunks are padded to an even length
+        self.data_offset() + self.data.byte_len + self.data.byte_len % 2
+    }
+
+    /// The form the header is written in, which is rf64 for a riff header whose sizes don't fit
+    /// in 32 bits.
+    pub fn written_form(&self) -> WaveForm {
+        let n_frames = self.fact.map_or(0, |fact| fact.n_frames);
+        match self.form {
+            WaveForm::Riff if self.has_unknown_len() => WaveForm::Riff,
+            WaveForm::Riff if self.byte_len() - 8 >= u32::MAX as u64 => WaveForm::Rf64,
+            WaveForm::Riff if n_frames >= u32::MAX as u64 => WaveForm::Rf64,
+            form => form,
+        }
+    }
+
+    /// Whether the data runs to the end of the file, as the sizes of a riff file written without
+    /// knowing its length are `UNKNOWN_LEN`.
+    pub fn has_unknown_len(&self) -> bool {
+        self.form == WaveForm::Riff && self.data.byte_len == UNKNOWN_LEN as u64
     }
 
     pub fn read(reader: &mut impl Read) -> Result<Self, PhonicError> {
-        let mut buf = [0u8; 40];
+        let mut buf = [0u8; 12];
 
         reader.read_exact(&mut buf[0..12])?;
-        if &buf[0..4] != RIFF_CHUNK_ID || &buf[8..12] != WAVE_CHUNK_ID {
+        let form = match buf[0..4].try_into().unwrap() {
+            RIFF_CHUNK_ID => WaveForm::Riff,
+            RF64_CHUNK_ID => WaveForm::Rf64,
+            BW64_CHUNK_ID => WaveForm::Bw64,
+            _ => return Err(PhonicError::InvalidData),
+        };
+
+        if &buf[8..12] != WAVE_CHUNK_ID {
             return Err(PhonicError::InvalidData);
         }
 
+        let mut ds64 = None;
         let mut fmt = None;
         let mut fact = None;
+        let mut bext = None;
+        let mut ixml = None;
+        let mut chunks = Vec::new();
+        let mut offset = 12;
         let data;
 
         loop {
             reader.read_exact(&mut buf[..8])?;
             let chunk_id: [u8; 4] = buf[0..4].try_into().unwrap();
-            let byte_len = u32::from_le_bytes(buf[4..8].try_into().unwrap());
+            let byte_len = match u32::from_le_bytes(buf[4..8].try_into().unwrap()) {
+                u32::MAX => ds64.as_ref().map_or(u32::MAX as u64, |ds64: &Ds64Chunk| {
+                    ds64.chunk_len(&chunk_id)
+                }),
+                byte_len => byte_len as u64,
+            };
+
+            offset += 8;
+            chunks.push(WaveChunk {
+                id: chunk_id,
+                offset,
+                byte_len,
+            });
 
             if &chunk_id == DATA_CHUNK_ID {
                 data = DataChunk { byte_len };
                 break;
             }
 
-            let byte_len = byte_len as usize;
-            if byte_len > buf.len() {
-                return Err(PhonicError::InvalidData);
-            }
-
-            reader.read_exact(&mut buf[..byte_len])?;
+            // chunks are padded to an even length
+            let padded_len = byte_len + byte_len % 2;
             match &chunk_id {
-                FMT_CHUNK_ID => {
-                    fmt = Some(FmtChunk::read(&buf[..byte_len])?);
+                DS64_CHUNK_ID if form != WaveForm::Riff && ds64.is_none() => {
+                    ds64 = Some(Ds64Chunk::read(reader, byte_len)?);
+                    skip(reader, padded_len - byte_len)?;
                 }
-                FACT_CHUNK_ID => {
-                    fact = Some(FactChunk::read(&buf[..byte_len])?);
+                FMT_CHUNK_ID | FACT_CHUNK_ID | BEXT_CHUNK_ID | IXML_CHUNK_ID => {
+                    let body = read_body(reader, byte_len)?;
+                    skip(reader, padded_len - byte_len)?;
+                    match &chunk_id {
+                        FMT_CHUNK_ID => fmt = Some(FmtChunk::read(&body)?),
+                        FACT_CHUNK_ID => fact = Some(FactChunk::read(&body, ds64.as_ref())?),
+                        // a bext chunk too short to hold its fields is only listed
+                        BEXT_CHUNK_ID => bext = BextChunk::read(&body).ok(),
+                        _ => ixml = Some(IxmlChunk::read(&body)),
+                    }
                 }
-                _ => return Err(PhonicError::InvalidData),
+
+                // lists, cue points, padding and the like are not needed to decode the data
+                _ => skip(reader, padded_len)?,
             }
+
+            offset += padded_len;
         }
 
         Ok(Self {
+            form,
             fmt: fmt.ok_or(PhonicError::InvalidData)?,
             fact,
             data,
+            bext,
+            ixml,
+            chunks,
+        })
+    }
+
+    /// Reads the ids and lengths of the chunks from `offset` to the end of the file, which is
+    /// where the chunks that follow the data chunk are found. A chunk cut short by the end of
+    /// the file is still listed.
+    pub fn read_chunks(
+        reader: &mut (impl Read + Seek),
+        mut offset: u64,
+    ) -> Result<Vec<WaveChunk>, PhonicError> {
+        let mut chunks = Vec::new();
+        reader.seek(SeekFrom::Start(offset))?;
+
+        loop {
+            let mut buf = Vec::with_capacity(8);
+            reader.take(8).read_to_end(&mut buf)?;
+            if buf.len() < 8 {
+                return Ok(chunks);
+            }
+
+            let byte_len = u32::from_le_bytes(buf[4..8].try_into().unwrap()) as u64;
+            offset += 8;
+            chunks.push(WaveChunk {
+                id: buf[0..4].try_into().unwrap(),
+                offset,
+                byte_len,
+            });
+
+            offset += byte_len + byte_len % 2;
+            reader.seek(SeekFrom::Start(offset))?;
+        }
+    }
+
+    /// Reads the metadata of a `bext` or `iXML` chunk listed by `read_chunks`, or returns `None`
+    /// for other chunks and for chunks cut short by the end of the file or too short to hold
+    /// their fields.
+    pub fn read_chunk_metadata(
+        reader: &mut (impl Read + Seek),
+        chunk: &WaveChunk,
+    ) -> Result<Option<FormatMetadata>, PhonicError> {
+        if &chunk.id != BEXT_CHUNK_ID && &chunk.id != IXML_CHUNK_ID {
+            return Ok(None);
+        }
+
+        reader.seek(SeekFrom::Start(chunk.offset))?;
+        let body = match read_body(reader, chunk.byte_len) {
+            Ok(body) => body,
+            Err(PhonicError::EndOfStream) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        Ok(match &chunk.id {
+            BEXT_CHUNK_ID => BextChunk::read(&body).ok().map(|bext| bext.metadata()),
+            _ => Some(IxmlChunk::read(&body).metadata()),
         })
     }
 
     pub fn write(&self, writer: &mut impl Write) -> Result<(), PhonicError> {
-        let mut buf = [0u8; 40];
+        let mut buf = [0u8; 64];
+        let form = self.written_form();
+
+        // the sizes of an rf64 file are found in its ds64 chunk, and in a riff file the chunk is
+        // reserved as junk
+        let (form_id, riff_len, data_len) = match form {
+            WaveForm::Riff if self.has_unknown_len() => {
+                (RIFF_CHUNK_ID, UNKNOWN_LEN, self.data.byte_len)
+            }
+            WaveForm::Riff => (
+                RIFF_CHUNK_ID,
+                self.byte_len() as u32 - 8,
+                self.data.byte_len,
+            ),
+            WaveForm::Rf64 => (RF64_CHUNK_ID, u32::MAX, u32::MAX as u64),
+            WaveForm::Bw64 => (BW64_CHUNK_ID, u32::MAX, u32::MAX as u64),
+        };
 
-        buf[0..4].copy_from_slice(RIFF_CHUNK_ID);
-        buf[4..8].copy_from_slice(&(self.byte_len() - 8).to_le_bytes());
+        buf[0..4].copy_from_slice(form_id);
+        buf[4..8].copy_from_slice(&riff_len.to_le_bytes());
         buf[8..12].copy_from_slice(WAVE_CHUNK_ID);
         writer.write_all(&buf[0..12])?;
 
+        buf[..8 + Ds64Chunk::BYTE_LEN].fill(0);
+        buf[4..8].copy_from_slice(&(Ds64Chunk::BYTE_LEN as u32).to_le_bytes());
+        if form == WaveForm::Riff {
+            buf[0..4].copy_from_slice(JUNK_CHUNK_ID);
+        } else {
+            let ds64 = Ds64Chunk {
+                riff_len: self.byte_len() - 8,
+                data_len: self.data.byte_len,
+                n_frames: self.fact.map_or(0, |fact| fact.n_frames),
+                table: Vec::new(),
+            };
+
+            buf[0..4].copy_from_slice(DS64_CHUNK_ID);
+            ds64.write(&mut buf[8..])?;
+        }
+
+        writer.write_all(&buf[..8 + Ds64Chunk::BYTE_LEN])?;
+
         buf[0..4].copy_from_slice(FMT_CHUNK_ID);
         buf[4..8].copy_from_slice(&self.fmt.byte_len().to_le_bytes());
         let n = self.fmt.write(&mut buf[8..])?;
         writer.write_all(&buf[..n + 8])?;
 
+        let mut body = Vec::new();
+        if let Some(bext) = &self.bext {
+            bext.write(&mut body);
+            write_chunk(writer, BEXT_CHUNK_ID, &body)?;
+        }
+
+        if let Some(ixml) = &self.ixml {
+            write_chunk(writer, IXML_CHUNK_ID, ixml.xml.as_bytes())?;
+        }
+
         if let Some(fact) = &self.fact {
             buf[0..4].copy_from_slice(FACT_CHUNK_ID);
             buf[4..8].copy_from_slice(&fact.byte_len().to_le_bytes());
@@ -125,13 +347,47 @@ impl WaveHeader {
         }
 
         buf[0..4].copy_from_slice(DATA_CHUNK_ID);
-        buf[4..8].copy_from_slice(&self.data.byte_len.to_le_bytes());
+        buf[4..8].copy_from_slice(&(data_len as u32).to_le_bytes());
         writer.write_all(&buf[0..8])?;
 
         Ok(())
     }
 }
 
+/// Reads the body of a chunk that is only read whole, such as metadata.
+fn read_body(reader: &mut impl Read, byte_len: u64) -> Result<Vec<u8>, PhonicError> {
+    let mut body = Vec::new();
+    reader.take(byte_len).read_to_end(&mut body)?;
+    if (body.len() as u64) < byte_len {
+        return Err(PhonicError::EndOfStream);
+    }
+
+    Ok(body)
+}
+
+fn write_chunk(writer: &mut impl Write, id: &[u8; 4], body: &[u8]) -> Result<(), PhonicError> {
+    writer.write_all(id)?;
+    writer.write_all(&(body.len() as u32).to_le_bytes())?;
+    writer.write_all(body)?;
+    if body.len() % 2 == 1 {
+        writer.write_all(&[0])?;
+    }
+
+    Ok(())
+}
+
+fn padded(byte_len: usize) -> u64 {
+    (byte_len + byte_len % 2) as u64
+}
+
+fn skip(reader: &mut impl Read, n: u64) -> Result<(), PhonicError> {
+    if io::copy(&mut reader.take(n), &mut io::sink())? < n {
+        return Err(PhonicError::EndOfStream);
+    }
+
+    Ok(())
+}
+
 impl<F> From<WaveHeader> for FormatData<F>
 where
     F: FormatTag,
@@ -139,25 +395,65 @@ where
     WaveSupportedCodec: TryInto<F::Codec>,
 {
     fn from(header: WaveHeader) -> Self {
-        let codec = match header.fmt.format_tag {
+        // an unknown sub format is read as the unknown format tag, which has no codec
+        let format_tag = header.fmt.data_format_tag().unwrap_or(0);
+        let codec = match format_tag {
             1 | 3 => WaveSupportedCodec::Pcm.try_into().ok(),
+            6 => WaveSupportedCodec::ALaw.try_into().ok(),
+            7 => WaveSupportedCodec::MuLaw.try_into().ok(),
+            0x11 => WaveSupportedCodec::ImaAdpcm.try_into().ok(),
+            2 => WaveSupportedCodec::MsAdpcm.try_into().ok(),
             _ => None,
         };
 
-        let sample_type = match (header.fmt.format_tag, header.fmt.bits_per_sample) {
+        let sample_type = match (format_tag, header.fmt.bits_per_sample) {
             (1, 8) => Some(KnownSampleType::U8),
             (1, 16) => Some(KnownSampleType::I16),
+            (1, 24) => Some(KnownSampleType::I24),
             (1, 32) => Some(KnownSampleType::I32),
+            (1, 48) => Some(KnownSampleType::I48),
             (3, 32) => Some(KnownSampleType::F32),
             (3, 64) => Some(KnownSampleType::F64),
+            (6 | 7, 8) => Some(KnownSampleType::I16),
+            (0x11 | 2, 4) => Some(KnownSampleType::I16),
             _ => None,
         };
 
-        let channels = header
-            .fmt
-            .ext
-            .and_then(|ext| Some(ChannelLayout::from_bits(ext.channel_mask).into()))
-            .unwrap_or_else(|| Channels::Count(header.fmt.n_channels));
+        // pcm and g.711 data have a fixed block size, so the data chunk determines the length
+        // when there is no fact chunk. adpcm blocks hold a fixed number of frames, but the last
+        // one may be padded, so the length is only an upper bound without a fact chunk
+        // a file written without knowing its length may have placeholders for both
+        let n_frames = header.fact.map(|fact| fact.n_frames);
+        let n_frames = n_frames.filter(|&n| n != UNKNOWN_LEN as u64).or_else(|| {
+            if header.has_unknown_len() {
+                return None;
+            }
+
+            let n_blocks = header
+                .data
+                .byte_len
+                .checked_div(header.fmt.block_align as u64)?;
+
+            match format_tag {
+                1 | 3 | 6 | 7 => Some(n_blocks),
+                _ => Some(n_blocks * header.fmt.samples_per_block()? as u64),
+            }
+        });
+
+        // a channel mask that doesn't assign every channel to a speaker is ignored
+        let channels = match header.fmt.ext {
+            Some(ext) if ext.channel_mask.count_ones() == header.fmt.n_channels as u32 => {
+                ChannelLayout::from_bits(ext.channel_mask).into()
+            }
+            _ => Channels::Count(header.fmt.n_channels),
+        };
+
+        // the bext time reference places the recording on a timeline
+        let metadata = FormatMetadata {
+            timeline_start: header.bext.as_ref().map(|bext| bext.time_reference),
+            broadcast: header.bext.as_ref().map(|bext| bext.broadcast.clone()),
+            ixml: header.ixml.as_ref().map(|ixml| ixml.xml.clone()),
+        };
 
         Self {
             format: WaveFormatTag.try_into().ok(),
@@ -169,8 +465,9 @@ where
                 decoded_spec: SignalSpecBuilder::new()
                     .with_channels(channels)
                     .with_frame_rate(header.fmt.sample_rate)
-                    .with_n_frames(header.fact.map(|fact| fact.n_frames as u64)),
+                    .with_n_frames(n_frames),
             }],
+            metadata,
         }
     }
 }
@@ -178,6 +475,7 @@ where
 impl<F> TryFrom<&FormatData<F>> for WaveHeader
 where
     F: FormatTag,
+    F::Codec: TryInto<WaveSupportedCodec>,
     // PhonicCodec: TryInto<F::Codec>,
 {
     type Error = PhonicError;
@@ -187,7 +485,7 @@ where
             return Err(PhonicError::Unsupported);
         }
 
-        let mut spec = data.streams[0];
+        let spec = data.streams[0];
 
         // let expected_codec = PhonicCodec::Pcm.try_into().ok();
         // if spec.codec.is_some() && spec.codec != expected_codec {
@@ -202,56 +500,139 @@ where
             .ok_or(PhonicError::MissingData)?
             .try_into()?;
 
-        let format_tag = match sample_type {
-            KnownSampleType::U8 | KnownSampleType::I16 | KnownSampleType::I32 => 1,
-            KnownSampleType::F32 | KnownSampleType::F64 => 3,
+        let codec = match spec.codec {
+            Some(codec) => codec.try_into().map_err(|_| PhonicError::Unsupported)?,
+            None => WaveSupportedCodec::Pcm,
+        };
+
+        let (format_tag, bits_per_sample) = match (codec, sample_type) {
+            (
+                WaveSupportedCodec::Pcm,
+                KnownSampleType::U8
+                | KnownSampleType::I16
+                | KnownSampleType::I24
+                | KnownSampleType::I32
+                | KnownSampleType::I48,
+            ) => (1, sample_type.byte_size() as u16 * 8),
+            (WaveSupportedCodec::Pcm, KnownSampleType::F32 | KnownSampleType::F64) => {
+                (3, sample_type.byte_size() as u16 * 8)
+            }
+            (WaveSupportedCodec::ALaw, KnownSampleType::I16) => (6, 8),
+            (WaveSupportedCodec::MuLaw, KnownSampleType::I16) => (7, 8),
+            (WaveSupportedCodec::ImaAdpcm, KnownSampleType::I16) => (0x11, 4),
+            (WaveSupportedCodec::MsAdpcm, KnownSampleType::I16) => (2, 4),
             _ => return Err(PhonicError::Unsupported),
         };
 
-        let n_channels = spec
-            .decoded_spec
-            .channels
-            .ok_or(PhonicError::InvalidData)?
-            .count() as u16;
+        let channels = spec.decoded_spec.channels.ok_or(PhonicError::InvalidData)?;
+
+        let n_channels = channels.count();
 
         let sample_rate = spec
             .decoded_spec
             .frame_rate
             .ok_or(PhonicError::InvalidData)?;
 
+        let mut fmt = FmtChunk {
+            format_tag,
+            n_channels,
+            sample_rate,
+            avg_byte_rate: sample_rate * bits_per_sample as u32 / 8 * n_channels as u32,
+            block_align: bits_per_sample / 8 * n_channels,
+            bits_per_sample,
+            ext: None,
+        };
+
+        // pcm data with more than two channels, more than 16 bits or a speaker layout other than
+        // mono or stereo is written in the extensible format, which names the format in its sub
+        // format. a layout that isn't given leaves the speakers unassigned
+        let has_layout = channels
+            .layout()
+            .is_some_and(|layout| ![ChannelLayout::MONO, ChannelLayout::STEREO].contains(layout));
+
+        if matches!(format_tag, 1 | 3)
+            && (n_channels > 2 || has_layout || format_tag == 1 && bits_per_sample > 16)
+        {
+            fmt.format_tag = WAVE_FORMAT_EXTENSIBLE;
+            fmt.ext = Some(FmtChunkExt {
+                valid_bits_per_sample: bits_per_sample,
+                channel_mask: channels.layout().map_or(0, |layout| layout.bits()),
+                sub_format: FmtChunkExt::sub_format_guid(format_tag),
+            });
+        }
+
+        // formats other than pcm are expected to have a fact chunk, which holds a placeholder
+        // until the length is known
+        let fact = match spec.decoded_spec.n_frames {
+            Some(n_frames) => Some(FactChunk { n_frames }),
+            None if !matches!(format_tag, 1 | 3) => Some(FactChunk {
+                n_frames: UNKNOWN_LEN as u64,
+            }),
+            None => None,
+        };
+
+        // adpcm blocks are sized by the codec, and the last one is padded to a whole block
+        let byte_len = if matches!(format_tag, 0x11 | 2) {
+            fmt.block_align = spec.block_align.ok_or(PhonicError::MissingData)?;
+            let samples_per_block = fmt.samples_per_block().ok_or(PhonicError::InvalidData)?;
+            let n_frames = spec.decoded_spec.n_frames.ok_or(PhonicError::MissingData)?;
+
+            fmt.avg_byte_rate =
+                (sample_rate as u64 * fmt.block_align as u64 / samples_per_block as u64) as u32;
+            n_frames.div_ceil(samples_per_block as u64) * fmt.block_align as u64
+        } else if spec.decoded_spec.n_frames.is_none() {
+            // the sizes are left as placeholders, for the writer to fill in once they're known
+            UNKNOWN_LEN as u64
+        } else {
+            spec.n_bytes().ok_or(PhonicError::Unsupported)?
+        };
+
         Ok(Self {
-            fmt: FmtChunk {
-                format_tag,
-                n_channels,
-                sample_rate,
-                avg_byte_rate: sample_rate * sample_type.byte_size() as u32,
-                block_align: sample_type.byte_size() as u16 * n_channels,
-                bits_per_sample: sample_type.byte_size() as u16 * 8,
-                ext: None,
-            },
-            fact: spec
-                .decoded_spec
-                .n_frames
-                .map(|n| FactChunk { n_frames: n as u32 }),
-            data: DataChunk {
-                byte_len: spec.n_bytes().ok_or(PhonicError::Unsupported)? as u32,
-            },
+            form: WaveForm::Riff,
+            fmt,
+            fact,
+            data: DataChunk { byte_len },
+            bext: BextChunk::from_metadata(&data.metadata),
+            ixml: IxmlChunk::from_metadata(&data.metadata),
+            chunks: Vec::new(),
         })
     }
 }
 
 impl FmtChunk {
+    /// The number of frames in each block of adpcm data, or `None` for other formats.
+    pub fn samples_per_block(&self) -> Option<usize> {
+        let tag = match self.format_tag {
+            0x11 => AdpcmCodecTag::Ima,
+            2 => AdpcmCodecTag::Microsoft,
+            _ => return None,
+        };
+
+        tag.frames_per_block(self.block_align, self.n_channels)
+    }
+
+    /// The format tag of the data, which for the extensible format is taken from the sub format.
+    /// Returns `None` for a sub format that isn't derived from a format tag.
+    pub fn data_format_tag(&self) -> Option<u16> {
+        match (self.format_tag, &self.ext) {
+            (WAVE_FORMAT_EXTENSIBLE, Some(ext)) => ext.sub_format_tag(),
+            (WAVE_FORMAT_EXTENSIBLE, None) => None,
+            (format_tag, _) => Some(format_tag),
+        }
+    }
+
     fn byte_len(&self) -> u32 {
-        if self.ext.is_some() {
-            40
-        } else {
-            16
+        match self.format_tag {
+            _ if self.ext.is_some() => 40,
+            0x11 => 20,
+            2 => 50,
+            _ => 16,
         }
     }
 
     fn read(buf: &[u8]) -> Result<Self, PhonicError> {
         let buf_len = buf.len();
-        if buf_len != 16 && buf_len != 18 && buf_len != 40 {
+        if buf_len < 16 {
             return Err(PhonicError::InvalidData);
         }
 
@@ -265,26 +646,63 @@ impl FmtChunk {
             ext: None,
         };
 
-        if buf_len < 18 {
+        if buf_len == 16 {
             return Ok(chunk);
         }
 
-        let ext_len = u16::from_le_bytes(buf[16..18].try_into().unwrap());
-        if ext_len == 0 && buf_len == 18 {
-            return Ok(chunk);
-        } else if ext_len != 22 && buf_len != 40 {
-            return Err(PhonicError::InvalidData);
-        }
+        let ext_len = buf
+            .get(16..18)
+            .map(|len| u16::from_le_bytes(len.try_into().unwrap()) as usize);
 
-        chunk.ext = Some(FmtChunkExt {
-            valid_bits_per_sample: u16::from_le_bytes(buf[18..20].try_into().unwrap()),
-            channel_mask: u32::from_le_bytes(buf[20..24].try_into().unwrap()),
-            sub_format: buf[24..40].try_into().unwrap(),
-        });
+        // some writers pad the chunk past the end of the extension
+        let ext_len = match ext_len {
+            Some(ext_len) if ext_len <= buf_len - 18 => ext_len,
+            _ => return Err(PhonicError::InvalidData),
+        };
+
+        match (chunk.format_tag, ext_len) {
+            (_, 0) => {}
+            (0x11, 2) | (2, 32) => chunk.check_adpcm_ext(&buf[18..18 + ext_len])?,
+            (_, 22) => {
+                chunk.ext = Some(FmtChunkExt {
+                    valid_bits_per_sample: u16::from_le_bytes(buf[18..20].try_into().unwrap()),
+                    channel_mask: u32::from_le_bytes(buf[20..24].try_into().unwrap()),
+                    sub_format: buf[24..40].try_into().unwrap(),
+                });
+            }
+            _ => return Err(PhonicError::InvalidData),
+        }
 
         Ok(chunk)
     }
 
+    /// Checks that the extension of an adpcm format agrees with the block layout the codec
+    /// expects. Custom microsoft coefficients are not supported.
+    fn check_adpcm_ext(&self, ext: &[u8]) -> Result<(), PhonicError> {
+        let read_u16 = |i: usize| u16::from_le_bytes(ext[i..i + 2].try_into().unwrap());
+        let read_i16 = |i: usize| i16::from_le_bytes(ext[i..i + 2].try_into().unwrap());
+
+        let samples_per_block = read_u16(0) as usize;
+        if self.samples_per_block() != Some(samples_per_block) {
+            return Err(PhonicError::Unsupported);
+        }
+
+        if self.format_tag == 2 {
+            let n_coefficients = read_u16(2) as usize;
+            if 4 + n_coefficients * 4 != ext.len() {
+                return Err(PhonicError::Unsupported);
+            }
+
+            let coefficients =
+                (0..n_coefficients).map(|i| (read_i16(4 + i * 4), read_i16(6 + i * 4)));
+            if !coefficients.eq(MS_ADPCM_COEFFICIENTS) {
+                return Err(PhonicError::Unsupported);
+            }
+        }
+
+        Ok(())
+    }
+
     fn write(&self, buf: &mut [u8]) -> Result<usize, PhonicError> {
         let byte_len = self.byte_len() as usize;
         if buf.len() < byte_len {
@@ -303,26 +721,57 @@ impl FmtChunk {
             buf[18..20].copy_from_slice(&ext.valid_bits_per_sample.to_le_bytes());
             buf[20..24].copy_from_slice(&ext.channel_mask.to_le_bytes());
             buf[24..40].copy_from_slice(&ext.sub_format);
+        } else if byte_len > 16 {
+            let samples_per_block = self.samples_per_block().ok_or(PhonicError::InvalidData)?;
+            buf[16..18].copy_from_slice(&(byte_len as u16 - 18).to_le_bytes());
+            buf[18..20].copy_from_slice(&(samples_per_block as u16).to_le_bytes());
+
+            if self.format_tag == 2 {
+                buf[20..22].copy_from_slice(&(MS_ADPCM_COEFFICIENTS.len() as u16).to_le_bytes());
+                for (i, (coef1, coef2)) in MS_ADPCM_COEFFICIENTS.iter().enumerate() {
+                    buf[22 + i * 4..24 + i * 4].copy_from_slice(&coef1.to_le_bytes());
+                    buf[24 + i * 4..26 + i * 4].copy_from_slice(&coef2.to_le_bytes());
+                }
+            }
         }
 
         Ok(byte_len)
     }
 }
 
+impl FmtChunkExt {
+    /// The sub format guid of a format tag.
+    pub fn sub_format_guid(format_tag: u16) -> [u8; 16] {
+        let mut guid = [0; 16];
+        guid[0..2].copy_from_slice(&format_tag.to_le_bytes());
+        guid[2..].copy_from_slice(&SUB_FORMAT_GUID_TAIL);
+        guid
+    }
+
+    /// The format tag the sub format guid is derived from, or `None` for other guids.
+    pub fn sub_format_tag(&self) -> Option<u16> {
+        (self.sub_format[2..] == SUB_FORMAT_GUID_TAIL)
+            .then(|| u16::from_le_bytes(self.sub_format[0..2].try_into().unwrap()))
+    }
+}
+
 impl FactChunk {
     fn byte_len(&self) -> u32 {
         4
     }
 
-    fn read(buf: &[u8]) -> Result<Self, PhonicError> {
-        let buf_len = buf.len();
-        if buf_len != 4 {
+    fn read(buf: &[u8], ds64: Option<&Ds64Chunk>) -> Result<Self, PhonicError> {
+        // the frame count may be followed by fields this reader has no use for
+        if buf.len() < 4 {
             return Err(PhonicError::InvalidData);
         }
 
-        Ok(Self {
-            n_frames: u32::from_le_bytes(buf[0..4].try_into().unwrap()),
-        })
+        let n_frames = match u32::from_le_bytes(buf[0..4].try_into().unwrap()) {
+            u32::MAX => ds64.map_or(u32::MAX as u64, |ds64| ds64.n_frames),
+            n_frames => n_frames as u64,
+        };
+
+        Ok(Self { n_frames })
     }
 
     fn write(&self, buf: &mut [u8]) -> Result<usize, PhonicError> {
@@ -330,7 +779,68 @@ impl FactChunk {
             return Err(PhonicError::InvalidData);
         }
 
-        buf[0..4].copy_from_slice(&self.n_frames.to_le_bytes());
+        // a count too large for 32 bits is found in the ds64 chunk
+        let n_frames = self.n_frames.min(u32::MAX as u64) as u32;
+        buf[0..4].copy_from_slice(&n_frames.to_le_bytes());
         Ok(4)
     }
 }
+
+impl Ds64Chunk {
+    const BYTE_LEN: usize = 28;
+
+    /// The length of a chunk whose 32-bit size is `u32::MAX`.
+    fn chunk_len(&self, chunk_id: &[u8; 4]) -> u64 {
+        if chunk_id == DATA_CHUNK_ID {
+            return self.data_len;
+        }
+
+        self.table
+            .iter()
+            .find(|(id, _)| id == chunk_id)
+            .map_or(u32::MAX as u64, |(_, byte_len)| *byte_len)
+    }
+
+    fn read(reader: &mut impl Read, byte_len: u64) -> Result<Self, PhonicError> {
+        let mut buf = [0u8; Self::BYTE_LEN];
+        if byte_len < Self::BYTE_LEN as u64 {
+            return Err(PhonicError::InvalidData);
+        }
+
+        reader.read_exact(&mut buf)?;
+        let read_u64 = |buf: &[u8], i: usize| u64::from_le_bytes(buf[i..i + 8].try_into().unwrap());
+        let table_len = u32::from_le_bytes(buf[24..28].try_into().unwrap()) as u64;
+        if Self::BYTE_LEN as u64 + table_len * 12 > byte_len {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let mut chunk = Self {
+            riff_len: read_u64(&buf, 0),
+            data_len: read_u64(&buf, 8),
+            n_frames: read_u64(&buf, 16),
+            table: Vec::new(),
+        };
+
+        for _ in 0..table_len {
+            reader.read_exact(&mut buf[..12])?;
+            chunk
+                .table
+                .push((buf[0..4].try_into().unwrap(), read_u64(&buf, 4)));
+        }
+
+        skip(reader, byte_len - Self::BYTE_LEN as u64 - table_len * 12)?;
+        Ok(chunk)
+    }
+
+    fn write(&self, buf: &mut [u8]) -> Result<usize, PhonicError> {
+        if buf.len() < Self::BYTE_LEN || !self.table.is_empty() {
+            return Err(PhonicError::InvalidData);
+        }
+
+        buf[0..8].copy_from_slice(&self.riff_len.to_le_bytes());
+        buf[8..16].copy_from_slice(&self.data_len.to_le_bytes());
+        buf[16..24].copy_from_slice(&self.n_frames.to_le_bytes());
+        buf[24..28].copy_from_slice(&0u32.to_le_bytes());
+        Ok(Self::BYTE_LEN)
+    }
+}
@@ -1,7 +1,9 @@
 mod data;
 mod format;
 mod header;
+mod metadata;
 
 pub use data::*;
 pub use format::*;
 pub use header::*;
+pub use metadata::*;
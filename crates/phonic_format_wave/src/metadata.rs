@@ -0,0 +1,220 @@
+use phonic_core::PhonicError;
+use phonic_io_core::{BroadcastMetadata, FormatMetadata};
+
+/// The value of a loudness field that isn't given.
+const UNSET_LOUDNESS: i16 = 0x7fff;
+
+/// The broadcast extension of a broadcast wave file.
+///
+/// In the format data, the time reference is the timeline start and the other fields are the
+/// broadcast metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BextChunk {
+    /// The number of frames from midnight to the first frame of the recording.
+    pub time_reference: u64,
+    pub version: u16,
+    pub broadcast: BroadcastMetadata,
+}
+
+/// The iXML document of a production wave file.
+///
+/// In the format data, the document is the iXML metadata.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct IxmlChunk {
+    pub xml: String,
+}
+
+impl Default for BextChunk {
+    fn default() -> Self {
+        Self {
+            time_reference: 0,
+            version: 2,
+            broadcast: BroadcastMetadata::default(),
+        }
+    }
+}
+
+/// The offset and length of each text field.
+const TEXT_FIELDS: [(usize, usize); 5] = [(0, 256), (256, 32), (288, 32), (320, 10), (330, 8)];
+
+impl BextChunk {
+    /// The length of the chunk before the coding history.
+    const FIXED_LEN: usize = 602;
+
+    pub(crate) fn byte_len(&self) -> usize {
+        match self.broadcast.coding_history.len() {
+            0 => Self::FIXED_LEN,
+
+            // the coding history ends with a nul byte, and is padded to an even length
+            len => Self::FIXED_LEN + (len + 2) / 2 * 2,
+        }
+    }
+
+    fn text_fields(&self) -> [&String; 5] {
+        let broadcast = &self.broadcast;
+        [
+            &broadcast.description,
+            &broadcast.originator,
+            &broadcast.originator_reference,
+            &broadcast.origination_date,
+            &broadcast.origination_time,
+        ]
+    }
+
+    fn text_fields_mut(&mut self) -> [&mut String; 5] {
+        let broadcast = &mut self.broadcast;
+        [
+            &mut broadcast.description,
+            &mut broadcast.originator,
+            &mut broadcast.originator_reference,
+            &mut broadcast.origination_date,
+            &mut broadcast.origination_time,
+        ]
+    }
+
+    fn loudness_fields(&self) -> [Option<i16>; 5] {
+        let loudness = &self.broadcast.loudness;
+        [
+            loudness.value,
+            loudness.range,
+            loudness.max_true_peak_level,
+            loudness.max_momentary_loudness,
+            loudness.max_short_term_loudness,
+        ]
+    }
+
+    fn loudness_fields_mut(&mut self) -> [&mut Option<i16>; 5] {
+        let loudness = &mut self.broadcast.loudness;
+        [
+            &mut loudness.value,
+            &mut loudness.range,
+            &mut loudness.max_true_peak_level,
+            &mut loudness.max_momentary_loudness,
+            &mut loudness.max_short_term_loudness,
+        ]
+    }
+
+    pub(crate) fn read(buf: &[u8]) -> Result<Self, PhonicError> {
+        if buf.len() < Self::FIXED_LEN {
+            return Err(PhonicError::InvalidData);
+        }
+
+        let read_u32 = |i: usize| u32::from_le_bytes(buf[i..i + 4].try_into().unwrap());
+        let read_i16 = |i: usize| i16::from_le_bytes(buf[i..i + 2].try_into().unwrap());
+
+        let mut chunk = Self {
+            time_reference: read_u32(338) as u64 | (read_u32(342) as u64) << 32,
+            version: read_i16(346) as u16,
+            broadcast: BroadcastMetadata {
+                umid: buf[348..412].try_into().unwrap(),
+                coding_history: read_text(&buf[Self::FIXED_LEN..]),
+                ..BroadcastMetadata::default()
+            },
+        };
+
+        for (field, (offset, len)) in chunk.text_fields_mut().into_iter().zip(TEXT_FIELDS) {
+            *field = read_text(&buf[offset..offset + len]);
+        }
+
+        // the loudness fields were reserved before version 2
+        if chunk.version >= 2 {
+            for (i, field) in chunk.loudness_fields_mut().into_iter().enumerate() {
+                *field = Some(read_i16(412 + i * 2)).filter(|&n| n != UNSET_LOUDNESS);
+            }
+        }
+
+        Ok(chunk)
+    }
+
+    pub(crate) fn write(&self, buf: &mut Vec<u8>) {
+        let start = buf.len();
+        buf.resize(start + self.byte_len(), 0);
+        let chunk = &mut buf[start..];
+
+        // text that doesn't fit is cut short, at the end of the last character that fits
+        for (field, (offset, len)) in self.text_fields().into_iter().zip(TEXT_FIELDS) {
+            let field = truncate(field, len);
+            chunk[offset..offset + field.len()].copy_from_slice(field.as_bytes());
+        }
+
+        chunk[338..342].copy_from_slice(&(self.time_reference as u32).to_le_bytes());
+        chunk[342..346].copy_from_slice(&((self.time_reference >> 32) as u32).to_le_bytes());
+        chunk[346..348].copy_from_slice(&self.version.to_le_bytes());
+        chunk[348..412].copy_from_slice(&self.broadcast.umid);
+
+        // a value that can't be told apart from an unset one is written one step lower
+        for (i, field) in self.loudness_fields().into_iter().enumerate() {
+            let loudness = field.map_or(UNSET_LOUDNESS, |n| n.min(UNSET_LOUDNESS - 1));
+            chunk[412 + i * 2..414 + i * 2].copy_from_slice(&loudness.to_le_bytes());
+        }
+
+        let coding_history = self.broadcast.coding_history.as_bytes();
+        chunk[Self::FIXED_LEN..Self::FIXED_LEN + coding_history.len()]
+            .copy_from_slice(coding_history);
+    }
+
+    /// Returns the chunk as the metadata of the format data.
+    pub fn metadata(&self) -> FormatMetadata {
+        FormatMetadata::new()
+            .with_timeline_start(self.time_reference)
+            .with_broadcast(self.broadcast.clone())
+    }
+
+    /// Returns the chunk the metadata describes, or `None` when it has neither a timeline start
+    /// nor broadcast metadata.
+    pub fn from_metadata(metadata: &FormatMetadata) -> Option<Self> {
+        if metadata.timeline_start.is_none() && metadata.broadcast.is_none() {
+            return None;
+        }
+
+        Some(Self {
+            time_reference: metadata.timeline_start.unwrap_or(0),
+            broadcast: metadata.broadcast.clone().unwrap_or_default(),
+            ..Self::default()
+        })
+    }
+}
+
+impl IxmlChunk {
+    pub(crate) fn read(buf: &[u8]) -> Self {
+        Self {
+            xml: read_text(buf),
+        }
+    }
+
+    /// Returns the text of the first element with a name, such as `PROJECT` or `TAKE`, as it is
+    /// written in the document.
+    pub fn field(&self, name: &str) -> Option<&str> {
+        let start = self.xml.find(&format!("<{name}>"))? + name.len() + 2;
+        let len = self.xml[start..].find(&format!("</{name}>"))?;
+        Some(&self.xml[start..start + len])
+    }
+
+    /// Returns the document as the metadata of the format data.
+    pub fn metadata(&self) -> FormatMetadata {
+        FormatMetadata::new().with_ixml(self.xml.as_str())
+    }
+
+    /// Returns the document of the metadata, or `None` when it has none.
+    pub fn from_metadata(metadata: &FormatMetadata) -> Option<Self> {
+        metadata.ixml.clone().map(|xml| Self { xml })
+    }
+}
+
+/// Reads text padded with nul bytes, as text of the chunks is.
+fn read_text(buf: &[u8]) -> String {
+    let len = buf.iter().position(|&byte| byte == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..len]).into_owned()
+}
+
+/// Returns the longest start of `text` that fits in `len` bytes without splitting a character.
+fn truncate(text: &str, len: usize) -> &str {
+    let end = text
+        .char_indices()
+        .map(|(i, c)| i + c.len_utf8())
+        .take_while(|&end| end <= len)
+        .last()
+        .unwrap_or(0);
+
+    &text[..end]
+}
@@ -0,0 +1,69 @@
+use phonic_core::PhonicError;
+use phonic_format_wave::{WaveFormatTag, WaveHeader, WaveSupportedCodec};
+use phonic_io_core::{FormatData, StreamSpec};
+use phonic_signal::SignalSpecBuilder;
+use std::any::TypeId;
+
+fn written_header(codec: WaveSupportedCodec, n_channels: u16) -> Vec<u8> {
+    let decoded_spec = SignalSpecBuilder::new()
+        .with_frame_rate(22_050)
+        .with_channels(n_channels)
+        .with_n_frames(Some(2_000));
+
+    let spec = StreamSpec::new()
+        .with_codec(codec)
+        .with_decoded_spec(decoded_spec)
+        .filled()
+        .unwrap();
+
+    let data = FormatData::<WaveFormatTag>::new().with_stream(spec);
+    let header = WaveHeader::try_from(&data).unwrap();
+
+    let mut bytes = Vec::new();
+    header.write(&mut bytes).unwrap();
+    bytes
+}
+
+#[test]
+fn adpcm_headers_round_trip() {
+    for (codec, format_tag, fmt_len, frames_per_block) in [
+        (WaveSupportedCodec::ImaAdpcm, 0x11, 20, 1_017),
+        (WaveSupportedCodec::MsAdpcm, 2, 50, 1_012),
+    ] {
+        let bytes = written_header(codec, 2);
+        let header = WaveHeader::read(&mut bytes.as_slice()).unwrap();
+
+        assert_eq!(header.fmt.format_tag, format_tag);
+        assert_eq!(header.fmt.bits_per_sample, 4);
+        assert_eq!(header.fmt.block_align, 1_024);
+        assert_eq!(header.fmt.samples_per_block(), Some(frames_per_block));
+        assert_eq!(bytes[52..56], (fmt_len as u32).to_le_bytes());
+        assert_eq!(header.fact.map(|fact| fact.n_frames), Some(2_000));
+        assert_eq!(header.data.byte_len, 2 * 1_024);
+
+        let data = FormatData::<WaveFormatTag>::from(header);
+        let spec = data.streams[0];
+        assert!(spec.codec == Some(codec));
+        assert_eq!(spec.sample_type, Some(TypeId::of::<i16>()));
+        assert_eq!(spec.decoded_spec.n_frames, Some(2_000));
+    }
+}
+
+#[test]
+fn adpcm_extension_is_checked() {
+    // the samples per block field must agree with the block size
+    let mut bytes = written_header(WaveSupportedCodec::ImaAdpcm, 1);
+    bytes[74..76].copy_from_slice(&100u16.to_le_bytes());
+    assert!(matches!(
+        WaveHeader::read(&mut bytes.as_slice()),
+        Err(PhonicError::Unsupported)
+    ));
+
+    // custom microsoft coefficients are not supported
+    let mut bytes = written_header(WaveSupportedCodec::MsAdpcm, 1);
+    bytes[78..80].copy_from_slice(&300i16.to_le_bytes());
+    assert!(matches!(
+        WaveHeader::read(&mut bytes.as_slice()),
+        Err(PhonicError::Unsupported)
+    ));
+}
@@ -0,0 +1,191 @@
+use phonic_core::PhonicError;
+use phonic_format_wave::{WaveChunk, WaveFormat, WaveFormatTag, WaveHeader};
+use phonic_io_core::{
+    Format, FormatChunk, FormatData, FormatObserver, FormatReader, FormatWriter, StreamSpec,
+};
+use phonic_signal::{KnownSampleType, SignalSpecBuilder};
+use std::io::Cursor;
+
+const FMT: [u8; 16] = [1, 0, 1, 0, 0x40, 0x1f, 0, 0, 0x40, 0x1f, 0, 0, 1, 0, 8, 0];
+
+fn chunk(id: &[u8; 4], body: &[u8]) -> Vec<u8> {
+    let mut chunk = id.to_vec();
+    chunk.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    chunk.extend_from_slice(body);
+    if body.len() % 2 == 1 {
+        chunk.push(0);
+    }
+
+    chunk
+}
+
+fn riff(chunks: &[Vec<u8>]) -> Vec<u8> {
+    let body = chunks.concat();
+    let mut file = b"RIFF".to_vec();
+    file.extend_from_slice(&(body.len() as u32 + 4).to_le_bytes());
+    file.extend_from_slice(b"WAVE");
+    file.extend(body);
+    file
+}
+
+fn found(id: &[u8; 4], offset: u64, byte_len: u64) -> WaveChunk {
+    WaveChunk {
+        id: *id,
+        offset,
+        byte_len,
+    }
+}
+
+#[test]
+fn unknown_chunks_are_skipped() {
+    let file = riff(&[
+        chunk(b"JUNK", &[0; 28]),
+        chunk(b"fmt ", &FMT),
+        chunk(b"LIST", b"INFOINAM\x05\x00\x00\x00tone\x00"),
+        chunk(b"PAD ", &[0; 1000]),
+        chunk(b"data", &[1, 2, 3]),
+    ]);
+
+    let mut format = WaveFormat::<_, WaveFormatTag>::new(Cursor::new(file)).unwrap();
+    format.read_data().unwrap();
+
+    assert_eq!(
+        format.chunks(),
+        [
+            found(b"JUNK", 20, 28),
+            found(b"fmt ", 56, 16),
+            found(b"LIST", 80, 17),
+            found(b"PAD ", 106, 1000),
+            found(b"data", 1114, 3),
+        ]
+    );
+
+    let spec = format.data().streams[0];
+    assert_eq!(spec.decoded_spec.frame_rate, Some(8_000));
+    assert_eq!(spec.decoded_spec.n_frames, Some(3));
+
+    let mut buf = [0; 8];
+    let FormatChunk::Stream { buf, .. } = format.read(&mut buf).unwrap();
+    assert_eq!(buf, [1, 2, 3]);
+}
+
+#[test]
+fn long_fmt_and_fact_chunks_are_read() {
+    // an extensible fmt chunk padded out past its extension, and a fact chunk with extra fields
+    let mut fmt = FMT.to_vec();
+    fmt[0..2].copy_from_slice(&0xfffeu16.to_le_bytes());
+    fmt.extend_from_slice(&22u16.to_le_bytes());
+    fmt.extend_from_slice(&[8, 0, 4, 0, 0, 0]);
+    fmt.extend_from_slice(b"\x01\x00\x00\x00\x00\x00\x10\x00\x80\x00\x00\xaa\x00\x38\x9b\x71");
+    fmt.extend_from_slice(&[0; 60]);
+
+    let mut fact = 3u32.to_le_bytes().to_vec();
+    fact.extend_from_slice(&[0; 80]);
+
+    let file = riff(&[
+        chunk(b"fmt ", &fmt),
+        chunk(b"fact", &fact),
+        chunk(b"data", &[1, 2, 3]),
+    ]);
+
+    let header = WaveHeader::read(&mut file.as_slice()).unwrap();
+    assert_eq!(header.fmt.sample_rate, 8_000);
+    assert_eq!(header.fmt.ext.map(|ext| ext.channel_mask), Some(4));
+    assert_eq!(header.fact.map(|fact| fact.n_frames), Some(3));
+
+    // an extension longer than the chunk is still rejected
+    fmt[16..18].copy_from_slice(&200u16.to_le_bytes());
+    let file = riff(&[chunk(b"fmt ", &fmt), chunk(b"data", &[1, 2, 3])]);
+    assert_eq!(
+        WaveHeader::read(&mut file.as_slice()).err(),
+        Some(PhonicError::InvalidData)
+    );
+}
+
+#[test]
+fn trailing_chunks_are_listed() {
+    let file = riff(&[
+        chunk(b"fmt ", &FMT),
+        chunk(b"data", &[1, 2, 3]),
+        chunk(b"cue ", &[0; 4]),
+        chunk(b"LIST", &[0; 300]),
+    ]);
+
+    let mut format = WaveFormat::<_, WaveFormatTag>::new(Cursor::new(file)).unwrap();
+    format.read_data().unwrap();
+
+    let mut buf = [0; 1];
+    format.read(&mut buf).unwrap();
+
+    // the data chunk is padded, so the next chunk starts at an even offset
+    let chunks = format.read_trailing_chunks().unwrap().to_vec();
+    assert_eq!(
+        chunks[1..],
+        [
+            found(b"data", 44, 3),
+            found(b"cue ", 56, 4),
+            found(b"LIST", 68, 300),
+        ]
+    );
+
+    // reading again lists nothing twice, and the data carries on where it was
+    assert_eq!(format.read_trailing_chunks().unwrap().len(), 4);
+    assert_eq!(format.position().unwrap().byte_i, 1);
+
+    let mut buf = [0; 8];
+    let FormatChunk::Stream { buf, .. } = format.read(&mut buf).unwrap();
+    assert_eq!(buf, [2, 3]);
+}
+
+#[test]
+fn truncated_chunks_are_rejected() {
+    let mut file = riff(&[chunk(b"fmt ", &FMT), chunk(b"LIST", &[0; 100])]);
+    file.truncate(file.len() - 10);
+
+    assert_eq!(
+        WaveHeader::read(&mut file.as_slice()).err(),
+        Some(PhonicError::EndOfStream)
+    );
+
+    // a file cut short after its data is still listed up to the end
+    let mut file = riff(&[chunk(b"fmt ", &FMT), chunk(b"data", &[0; 4])]);
+    file.extend_from_slice(b"LIST\x10\x00\x00\x00abc");
+    let chunks = WaveHeader::read_chunks(&mut Cursor::new(file), 48).unwrap();
+    assert_eq!(chunks, [found(b"LIST", 56, 16)]);
+}
+
+#[test]
+fn odd_data_chunks_are_padded() {
+    let decoded_spec = SignalSpecBuilder::new()
+        .with_frame_rate(8_000)
+        .with_channels(1)
+        .with_n_frames(Some(3));
+
+    let mut spec = StreamSpec::new().with_decoded_spec(decoded_spec);
+    spec.sample_type = Some(KnownSampleType::U8.into());
+    let data = FormatData::<WaveFormatTag>::new()
+        .with_stream(spec.filled().unwrap())
+        .filled()
+        .unwrap();
+
+    let mut format = WaveFormat::<_, WaveFormatTag>::new(Cursor::new(Vec::new())).unwrap();
+    format.write_data(&data).unwrap();
+    format
+        .write(FormatChunk::Stream {
+            stream_i: 0,
+            buf: &[1, 2, 3],
+        })
+        .unwrap();
+
+    format.flush().unwrap();
+    let file = format.into_inner().into_inner();
+    assert_eq!(file.len(), 96);
+    assert_eq!(
+        u32::from_le_bytes(file[4..8].try_into().unwrap()) as usize,
+        file.len() - 8
+    );
+
+    let header = WaveHeader::read(&mut file.as_slice()).unwrap();
+    assert_eq!(header.data.byte_len, 3);
+    assert_eq!(file[header.data_offset() as usize..], [1, 2, 3, 0]);
+}
@@ -0,0 +1,112 @@
+use phonic_format_wave::{FmtChunkExt, WaveFormatTag, WaveHeader, WaveSupportedCodec};
+use phonic_io_core::{FormatData, StreamSpec};
+use phonic_signal::{ChannelLayout, Channels, KnownSampleType, SignalSpecBuilder};
+
+const PCM_GUID: [u8; 16] = [
+    0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b, 0x71,
+];
+
+const FLOAT_GUID: [u8; 16] = [
+    0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b, 0x71,
+];
+
+fn written_header(sample_type: KnownSampleType, channels: impl Into<Channels>) -> WaveHeader {
+    let decoded_spec = SignalSpecBuilder::new()
+        .with_frame_rate(48_000)
+        .with_channels(channels)
+        .with_n_frames(Some(100));
+
+    let mut spec = StreamSpec::new()
+        .with_codec(WaveSupportedCodec::Pcm)
+        .with_decoded_spec(decoded_spec);
+
+    spec.sample_type = Some(sample_type.into());
+    let data = FormatData::<WaveFormatTag>::new().with_stream(spec.filled().unwrap());
+    WaveHeader::try_from(&data).unwrap()
+}
+
+fn read_back(header: &WaveHeader) -> (WaveHeader, Vec<u8>) {
+    let mut bytes = Vec::new();
+    header.write(&mut bytes).unwrap();
+    (WaveHeader::read(&mut bytes.as_slice()).unwrap(), bytes)
+}
+
+#[test]
+fn plain_formats_are_kept() {
+    for (sample_type, format_tag) in [
+        (KnownSampleType::U8, 1),
+        (KnownSampleType::I16, 1),
+        (KnownSampleType::F32, 3),
+        (KnownSampleType::F64, 3),
+    ] {
+        let (header, _) = read_back(&written_header(sample_type, ChannelLayout::STEREO));
+        assert_eq!(header.fmt.format_tag, format_tag);
+        assert!(header.fmt.ext.is_none());
+    }
+}
+
+#[test]
+fn surround_layouts_are_written() {
+    let (header, bytes) = read_back(&written_header(
+        KnownSampleType::I16,
+        ChannelLayout::SURROUND_5_1,
+    ));
+
+    // the fmt chunk follows the reserved ds64 chunk
+    assert_eq!(&bytes[48..56], b"fmt \x28\x00\x00\x00");
+    assert_eq!(bytes[56..58], [0xfe, 0xff]);
+    assert_eq!(bytes[72..74], 22u16.to_le_bytes());
+
+    let ext = header.fmt.ext.unwrap();
+    assert_eq!(header.fmt.n_channels, 6);
+    assert_eq!(ext.channel_mask, 0x3f);
+    assert_eq!(ext.valid_bits_per_sample, 16);
+    assert_eq!(ext.sub_format, PCM_GUID);
+    assert_eq!(header.fmt.data_format_tag(), Some(1));
+
+    let spec = FormatData::<WaveFormatTag>::from(header).streams[0];
+    assert!(spec.codec == Some(WaveSupportedCodec::Pcm));
+    assert_eq!(spec.sample_type, Some(KnownSampleType::I16.into()));
+    assert_eq!(
+        spec.decoded_spec.channels,
+        Some(ChannelLayout::SURROUND_5_1.into())
+    );
+}
+
+#[test]
+fn channel_counts_and_bit_depths_are_extended() {
+    // without a layout the speakers are left unassigned
+    let (header, _) = read_back(&written_header(KnownSampleType::I24, 2));
+    let ext = header.fmt.ext.unwrap();
+    assert_eq!(ext.channel_mask, 0);
+    assert_eq!(ext.valid_bits_per_sample, 24);
+    assert_eq!(ext.sub_format, PCM_GUID);
+
+    let spec = FormatData::<WaveFormatTag>::from(header).streams[0];
+    assert_eq!(spec.sample_type, Some(KnownSampleType::I24.into()));
+    assert_eq!(spec.decoded_spec.channels, Some(Channels::Count(2)));
+
+    let (header, _) = read_back(&written_header(KnownSampleType::F32, 3));
+    let ext = header.fmt.ext.unwrap();
+    assert_eq!(ext.valid_bits_per_sample, 32);
+    assert_eq!(ext.sub_format, FLOAT_GUID);
+
+    let spec = FormatData::<WaveFormatTag>::from(header).streams[0];
+    assert!(spec.codec == Some(WaveSupportedCodec::Pcm));
+    assert_eq!(spec.sample_type, Some(KnownSampleType::F32.into()));
+    assert_eq!(spec.decoded_spec.channels, Some(Channels::Count(3)));
+}
+
+#[test]
+fn unknown_sub_formats_have_no_codec() {
+    let mut header = written_header(KnownSampleType::I16, ChannelLayout::SURROUND_5_1);
+    let ext = header.fmt.ext.as_mut().unwrap();
+    assert_eq!(ext.sub_format, FmtChunkExt::sub_format_guid(1));
+
+    ext.sub_format[15] ^= 1;
+    assert_eq!(header.fmt.data_format_tag(), None);
+
+    let spec = FormatData::<WaveFormatTag>::from(header).streams[0];
+    assert!(spec.codec.is_none());
+    assert!(spec.sample_type.is_none());
+}
@@ -0,0 +1,67 @@
+use phonic_format_wave::{DataChunk, FmtChunk, WaveForm, WaveFormatTag, WaveHeader, WaveSupportedCodec};
+use phonic_io_core::{FormatData, StreamSpec};
+use phonic_signal::SignalSpecBuilder;
+use std::any::TypeId;
+
+fn header(format_tag: u16) -> WaveHeader {
+    WaveHeader {
+        form: WaveForm::Riff,
+        fmt: FmtChunk {
+            format_tag,
+            n_channels: 1,
+            sample_rate: 8_000,
+            avg_byte_rate: 8_000,
+            block_align: 1,
+            bits_per_sample: 8,
+            ext: None,
+        },
+        fact: None,
+        data: DataChunk { byte_len: 4_000 },
+        bext: None,
+        ixml: None,
+        chunks: Vec::new(),
+    }
+}
+
+#[test]
+fn companded_headers_are_read() {
+    for (format_tag, codec) in [
+        (6, WaveSupportedCodec::ALaw),
+        (7, WaveSupportedCodec::MuLaw),
+    ] {
+        let data = FormatData::<WaveFormatTag>::from(header(format_tag));
+        let spec = data.streams[0];
+
+        assert!(spec.codec == Some(codec));
+        assert_eq!(spec.sample_type, Some(TypeId::of::<i16>()));
+        assert_eq!(spec.decoded_spec.n_frames, Some(4_000));
+    }
+}
+
+#[test]
+fn companded_headers_are_written() {
+    for (format_tag, codec) in [
+        (6, WaveSupportedCodec::ALaw),
+        (7, WaveSupportedCodec::MuLaw),
+    ] {
+        let decoded_spec = SignalSpecBuilder::new()
+            .with_frame_rate(8_000)
+            .with_channels(2)
+            .with_n_frames(Some(100));
+
+        let spec = StreamSpec::new()
+            .with_codec(codec)
+            .with_decoded_spec(decoded_spec)
+            .filled()
+            .unwrap();
+
+        let data = FormatData::<WaveFormatTag>::new().with_stream(spec);
+        let header = WaveHeader::try_from(&data).unwrap();
+
+        assert_eq!(header.fmt.format_tag, format_tag);
+        assert_eq!(header.fmt.bits_per_sample, 8);
+        assert_eq!(header.fmt.block_align, 2);
+        assert_eq!(header.fmt.avg_byte_rate, 16_000);
+        assert_eq!(header.data.byte_len, 200);
+    }
+}
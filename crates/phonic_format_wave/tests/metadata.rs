@@ -0,0 +1,235 @@
+use phonic_core::PhonicError;
+use phonic_format_wave::{BextChunk, IxmlChunk, WaveFormat, WaveFormatTag, WaveHeader};
+use phonic_io_core::{
+    BroadcastMetadata, Format, FormatChunk, FormatData, FormatMetadata, FormatReader, FormatWriter,
+    Loudness, StreamSpec,
+};
+use phonic_signal::{KnownSampleType, SignalSpecBuilder};
+use std::io::Cursor;
+
+const FMT: [u8; 16] = [1, 0, 1, 0, 0x40, 0x1f, 0, 0, 0x40, 0x1f, 0, 0, 1, 0, 8, 0];
+const IXML: &str = "<?xml version=\"1.0\"?><BWFXML><PROJECT>dawn</PROJECT><TAKE>3</TAKE></BWFXML>";
+
+fn chunk(id: &[u8; 4], body: &[u8]) -> Vec<u8> {
+    let mut chunk = id.to_vec();
+    chunk.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    chunk.extend_from_slice(body);
+    if body.len() % 2 == 1 {
+        chunk.push(0);
+    }
+
+    chunk
+}
+
+fn riff(chunks: &[Vec<u8>]) -> Vec<u8> {
+    let body = chunks.concat();
+    let mut file = b"RIFF".to_vec();
+    file.extend_from_slice(&(body.len() as u32 + 4).to_le_bytes());
+    file.extend_from_slice(b"WAVE");
+    file.extend(body);
+    file
+}
+
+fn write_file(metadata: FormatMetadata) -> Result<Vec<u8>, PhonicError> {
+    let decoded_spec = SignalSpecBuilder::new()
+        .with_frame_rate(48_000)
+        .with_channels(1)
+        .with_n_frames(Some(3));
+
+    let mut spec = StreamSpec::new().with_decoded_spec(decoded_spec);
+    spec.sample_type = Some(KnownSampleType::U8.into());
+    let data = FormatData::<WaveFormatTag>::new()
+        .with_stream(spec.filled().unwrap())
+        .with_metadata(metadata);
+
+    let mut format = WaveFormat::<_, WaveFormatTag>::new(Cursor::new(Vec::new()))?;
+    format.write_data(&data)?;
+    format.write(FormatChunk::Stream {
+        stream_i: 0,
+        buf: &[1, 2, 3],
+    })?;
+
+    format.flush()?;
+    Ok(format.into_inner().into_inner())
+}
+
+#[test]
+fn bext_fields_are_read() {
+    let mut bext = vec![0; 602];
+    bext[0..6].copy_from_slice(b"take 1");
+    bext[256..260].copy_from_slice(b"desk");
+    bext[320..330].copy_from_slice(b"2024-05-01");
+    bext[330..338].copy_from_slice(b"12:30:00");
+    bext[338..346].copy_from_slice(&0x1_0000_0002u64.to_le_bytes());
+    bext[346] = 1;
+    bext[412..414].copy_from_slice(&(-2_300i16).to_le_bytes());
+    bext.extend_from_slice(b"A=PCM\r\n");
+
+    let file = riff(&[
+        chunk(b"bext", &bext),
+        chunk(b"fmt ", &FMT),
+        chunk(b"data", &[1, 2]),
+    ]);
+
+    let mut format = WaveFormat::<_, WaveFormatTag>::new(Cursor::new(file)).unwrap();
+    format.read_data().unwrap();
+
+    // the loudness fields are reserved in version 1
+    let metadata = &format.data().metadata;
+    assert_eq!(metadata.timeline_start, Some(0x1_0000_0002));
+    assert_eq!(
+        metadata.broadcast,
+        Some(BroadcastMetadata {
+            description: "take 1".into(),
+            originator: "desk".into(),
+            origination_date: "2024-05-01".into(),
+            origination_time: "12:30:00".into(),
+            coding_history: "A=PCM\r\n".into(),
+            ..Default::default()
+        })
+    );
+
+    let mut buf = [0; 8];
+    let FormatChunk::Stream { buf, .. } = format.read(&mut buf).unwrap();
+    assert_eq!(buf, [1, 2]);
+}
+
+#[test]
+fn metadata_is_written() {
+    let mut umid = [0; 64];
+    umid[..32].fill(10);
+
+    let broadcast = BroadcastMetadata {
+        originator: "phonic".into(),
+        umid,
+        loudness: Loudness {
+            value: Some(-2_350),
+            max_true_peak_level: Some(-100),
+            ..Default::default()
+        },
+        coding_history: "A=PCM,F=48000".into(),
+        ..Default::default()
+    };
+
+    let metadata = FormatMetadata::new()
+        .with_timeline_start(172_800_000)
+        .with_broadcast(broadcast)
+        .with_ixml(IXML);
+
+    let file = write_file(metadata.clone()).unwrap();
+    let mut format = WaveFormat::<_, WaveFormatTag>::new(Cursor::new(file)).unwrap();
+    format.read_data().unwrap();
+
+    let read = &format.data().metadata;
+    assert_eq!(*read, metadata);
+
+    let bext = BextChunk::from_metadata(read).unwrap();
+    assert_eq!(bext.version, 2);
+    assert_eq!(bext.time_reference, 172_800_000);
+
+    let mut buf = [0; 8];
+    let FormatChunk::Stream { buf, .. } = format.read(&mut buf).unwrap();
+    assert_eq!(buf, [1, 2, 3]);
+
+    // nothing is written without metadata
+    let file = write_file(FormatMetadata::new()).unwrap();
+    assert!(!file.windows(4).any(|id| id == b"bext" || id == b"iXML"));
+}
+
+#[test]
+fn bext_fields_are_written_within_their_limits() {
+    let broadcast = BroadcastMetadata {
+        // a two byte character that would straddle the end of the field
+        originator: format!("{}é", "a".repeat(31)),
+        loudness: Loudness {
+            value: Some(i16::MAX),
+            range: Some(i16::MIN),
+            ..Default::default()
+        },
+        coding_history: "A=PCM".into(),
+        ..Default::default()
+    };
+
+    let file = write_file(FormatMetadata::new().with_broadcast(broadcast)).unwrap();
+    let header = WaveHeader::read(&mut file.as_slice()).unwrap();
+    let bext = header.bext.unwrap().broadcast;
+    assert_eq!(bext.originator, "a".repeat(31));
+
+    // the largest loudness is moved off the value that means it isn't given
+    assert_eq!(bext.loudness.value, Some(i16::MAX - 1));
+    assert_eq!(bext.loudness.range, Some(i16::MIN));
+
+    // the coding history ends with a nul byte and is padded to an even length
+    let chunk = header.chunks.iter().find(|c| &c.id == b"bext").unwrap();
+    assert_eq!(chunk.byte_len, 608);
+
+    let history = &file[chunk.offset as usize + 602..][..6];
+    assert_eq!(history, b"A=PCM\0");
+    assert_eq!(bext.coding_history, "A=PCM");
+}
+
+#[test]
+fn trailing_ixml_is_read() {
+    let file = riff(&[
+        chunk(b"fmt ", &FMT),
+        chunk(b"data", &[1, 2, 3]),
+        chunk(b"iXML", IXML.as_bytes()),
+    ]);
+
+    let mut format = WaveFormat::<_, WaveFormatTag>::new(Cursor::new(file)).unwrap();
+    format.read_data().unwrap();
+    assert!(format.data().metadata.is_empty());
+
+    format.read_trailing_chunks().unwrap();
+    let ixml = IxmlChunk::from_metadata(&format.data().metadata).unwrap();
+    assert_eq!(ixml.field("PROJECT"), Some("dawn"));
+    assert_eq!(ixml.field("TAKE"), Some("3"));
+    assert_eq!(ixml.field("SCENE"), None);
+
+    let mut buf = [0; 8];
+    let FormatChunk::Stream { buf, .. } = format.read(&mut buf).unwrap();
+    assert_eq!(buf, [1, 2, 3]);
+}
+
+#[test]
+fn short_bext_chunks_are_skipped() {
+    let file = riff(&[
+        chunk(b"bext", &[0; 100]),
+        chunk(b"fmt ", &FMT),
+        chunk(b"data", &[1, 2, 3]),
+        chunk(b"bext", &[0; 10]),
+        chunk(b"iXML", IXML.as_bytes()),
+    ]);
+
+    let mut format = WaveFormat::<_, WaveFormatTag>::new(Cursor::new(file)).unwrap();
+    format.read_data().unwrap();
+    assert!(format.data().metadata.is_empty());
+
+    // the chunks are still listed, and the metadata that can be read is
+    let chunks = format.read_trailing_chunks().unwrap();
+    assert_eq!(chunks.iter().filter(|c| &c.id == b"bext").count(), 2);
+    assert_eq!(
+        format.data().metadata,
+        FormatMetadata::new().with_ixml(IXML)
+    );
+}
+
+#[test]
+fn conflicting_metadata_is_rejected() {
+    let mut metadata = FormatMetadata::new().with_ixml(IXML);
+    metadata
+        .merge(&FormatMetadata::new().with_ixml(IXML))
+        .unwrap();
+    metadata
+        .merge(&FormatMetadata::new().with_timeline_start(48_000))
+        .unwrap();
+
+    assert_eq!(
+        metadata.merge(&FormatMetadata::new().with_ixml("<BWFXML/>")),
+        Err(PhonicError::SignalMismatch)
+    );
+    assert_eq!(
+        metadata.merge(&FormatMetadata::new().with_timeline_start(0)),
+        Err(PhonicError::SignalMismatch)
+    );
+}
@@ -0,0 +1,120 @@
+use phonic_core::PhonicError;
+use phonic_format_wave::{
+    DataChunk, FactChunk, FmtChunk, WaveChunk, WaveForm, WaveFormat, WaveFormatTag, WaveHeader,
+};
+use phonic_io_core::{Format, FormatChunk, FormatReader};
+use std::io::Cursor;
+
+const FMT: [u8; 16] = [1, 0, 1, 0, 0x40, 0x1f, 0, 0, 0x40, 0x1f, 0, 0, 1, 0, 8, 0];
+
+fn header(form: WaveForm, byte_len: u64) -> WaveHeader {
+    WaveHeader {
+        form,
+        fmt: FmtChunk {
+            format_tag: 1,
+            n_channels: 1,
+            sample_rate: 8_000,
+            avg_byte_rate: 8_000,
+            block_align: 1,
+            bits_per_sample: 8,
+            ext: None,
+        },
+        fact: Some(FactChunk { n_frames: byte_len }),
+        data: DataChunk { byte_len },
+        bext: None,
+        ixml: None,
+        chunks: Vec::new(),
+    }
+}
+
+fn chunk(id: &[u8; 4], byte_len: u32, body: &[u8]) -> Vec<u8> {
+    let mut chunk = id.to_vec();
+    chunk.extend_from_slice(&byte_len.to_le_bytes());
+    chunk.extend_from_slice(body);
+    chunk
+}
+
+#[test]
+fn large_headers_are_promoted_to_rf64() {
+    let byte_len = 5 << 30;
+    let mut riff = Vec::new();
+    header(WaveForm::Riff, 1_000).write(&mut riff).unwrap();
+    let mut rf64 = Vec::new();
+    header(WaveForm::Riff, byte_len).write(&mut rf64).unwrap();
+
+    // the junk chunk of a riff header is where the ds64 chunk of a promoted one goes
+    assert_eq!(&riff[0..4], b"RIFF");
+    assert_eq!(&riff[12..20], b"JUNK\x1c\x00\x00\x00");
+    assert_eq!(&rf64[0..8], b"RF64\xff\xff\xff\xff");
+    assert_eq!(&rf64[12..20], b"ds64\x1c\x00\x00\x00");
+    assert_eq!(riff.len(), rf64.len());
+    assert_eq!(riff[20..48], [0; 28]);
+
+    let data_offset = header(WaveForm::Riff, byte_len).data_offset() as usize;
+    assert_eq!(rf64.len(), data_offset);
+    assert_eq!(rf64[data_offset - 4..], [0xff; 4]);
+
+    let read = WaveHeader::read(&mut rf64.as_slice()).unwrap();
+    assert_eq!(read.form, WaveForm::Rf64);
+    assert_eq!(read.data.byte_len, byte_len);
+    assert_eq!(read.fact.map(|fact| fact.n_frames), Some(byte_len));
+    assert_eq!(read.chunks.last().unwrap().byte_len, byte_len);
+    assert_eq!(read.byte_len(), data_offset as u64 + byte_len);
+
+    let read = WaveHeader::read(&mut riff.as_slice()).unwrap();
+    assert_eq!(read.form, WaveForm::Riff);
+    assert_eq!(read.data.byte_len, 1_000);
+}
+
+#[test]
+fn bw64_files_are_read() {
+    let mut file = Vec::new();
+    header(WaveForm::Bw64, 3).write(&mut file).unwrap();
+    file.extend_from_slice(&[1, 2, 3, 0]);
+    assert_eq!(&file[0..4], b"BW64");
+
+    let mut format = WaveFormat::<_, WaveFormatTag>::new(Cursor::new(file)).unwrap();
+    format.read_data().unwrap();
+    assert_eq!(format.data().streams[0].decoded_spec.n_frames, Some(3));
+
+    let mut buf = [0; 8];
+    let FormatChunk::Stream { buf, .. } = format.read(&mut buf).unwrap();
+    assert_eq!(buf, [1, 2, 3]);
+}
+
+#[test]
+fn ds64_table_sizes_are_read() {
+    let mut ds64 = [0u64, 4, 4].map(u64::to_le_bytes).concat();
+    ds64.extend_from_slice(&1u32.to_le_bytes());
+    ds64.extend_from_slice(b"LIST");
+    ds64.extend_from_slice(&6u64.to_le_bytes());
+
+    let mut file = b"RF64\xff\xff\xff\xffWAVE".to_vec();
+    file.extend(chunk(b"ds64", ds64.len() as u32, &ds64));
+    file.extend(chunk(b"fmt ", 16, &FMT));
+    file.extend(chunk(b"LIST", u32::MAX, b"INFO\0\0"));
+    file.extend(chunk(b"fact", 4, &[0xff; 4]));
+    file.extend(chunk(b"data", u32::MAX, &[1, 2, 3, 4]));
+
+    let header = WaveHeader::read(&mut file.as_slice()).unwrap();
+    assert_eq!(header.data.byte_len, 4);
+    assert_eq!(header.fact.map(|fact| fact.n_frames), Some(4));
+    assert_eq!(
+        header.chunks[2],
+        WaveChunk {
+            id: *b"LIST",
+            offset: 92,
+            byte_len: 6
+        }
+    );
+
+    // a ds64 chunk too short for its table is rejected
+    let len = 12 + 8 + ds64.len();
+    let mut short = file.clone();
+    short[16..20].copy_from_slice(&28u32.to_le_bytes());
+    short.drain(12 + 8 + 28..len);
+    assert_eq!(
+        WaveHeader::read(&mut short.as_slice()).err(),
+        Some(PhonicError::InvalidData)
+    );
+}
@@ -0,0 +1,119 @@
+use phonic_format_wave::{DataChunk, FmtChunk, WaveForm, WaveFormat, WaveFormatTag, WaveHeader};
+use phonic_io_core::{
+    utils::StdIoStream, Format, FormatChunk, FormatObserver, FormatOffset, FormatReader,
+    FormatSeeker,
+};
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+const N_FRAMES: usize = 100;
+const BLOCK_ALIGN: usize = 4;
+
+fn wave_file(trailing: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let samples: Vec<u8> = (0..N_FRAMES * BLOCK_ALIGN).map(|i| i as u8).collect();
+    let header = WaveHeader {
+        form: WaveForm::Riff,
+        fmt: FmtChunk {
+            format_tag: 1,
+            n_channels: 2,
+            sample_rate: 8000,
+            avg_byte_rate: 8000 * BLOCK_ALIGN as u32,
+            block_align: BLOCK_ALIGN as u16,
+            bits_per_sample: 16,
+            ext: None,
+        },
+        fact: None,
+        data: DataChunk {
+            byte_len: samples.len() as u64,
+        },
+        bext: None,
+        ixml: None,
+        chunks: Vec::new(),
+    };
+
+    let mut file = Vec::new();
+    header.write(&mut file).unwrap();
+    file.extend_from_slice(&samples);
+    file.extend_from_slice(trailing);
+
+    (file, samples)
+}
+
+fn open(file: Vec<u8>) -> WaveFormat<Cursor<Vec<u8>>, WaveFormatTag> {
+    let mut format = WaveFormat::new(Cursor::new(file)).unwrap();
+    format.read_data().unwrap();
+    format
+}
+
+#[test]
+fn position_tracks_reads() {
+    let (file, _) = wave_file(&[]);
+    let mut format = open(file);
+    assert_eq!(format.position().unwrap().byte_i, 0);
+
+    let mut buf = [0; 10];
+    format.read(&mut buf).unwrap();
+    assert_eq!(format.position().unwrap().byte_i, 8);
+}
+
+#[test]
+fn seeks_are_clamped_and_aligned() {
+    let (file, samples) = wave_file(&[]);
+    let mut format = open(file);
+
+    let seek = |format: &mut WaveFormat<_, _>, byte_offset| {
+        format
+            .seek(FormatOffset {
+                stream_offset: 0,
+                byte_offset,
+            })
+            .unwrap();
+
+        format.position().unwrap().byte_i
+    };
+
+    assert_eq!(seek(&mut format, 42), 40);
+    assert_eq!(seek(&mut format, -7), 32);
+    assert_eq!(seek(&mut format, -1000), 0);
+    assert_eq!(seek(&mut format, 10_000), samples.len() as u64);
+
+    let mut buf = [0; 8];
+    let FormatChunk::Stream { buf, .. } = format.read(&mut buf).unwrap();
+    assert!(buf.is_empty());
+
+    let offset = FormatOffset {
+        stream_offset: 1,
+        byte_offset: 0,
+    };
+    assert!(format.seek(offset).is_err());
+}
+
+#[test]
+fn reads_stop_at_the_end_of_the_data_chunk() {
+    let (file, samples) = wave_file(b"LIST\x04\x00\x00\x00abcd");
+    let mut stream = StdIoStream::new(open(file).into_default_stream().unwrap());
+
+    let mut buf = Vec::new();
+    stream.read_to_end(&mut buf).unwrap();
+    assert_eq!(buf, samples);
+}
+
+#[test]
+fn std_io_seeks_from_every_origin() {
+    let (file, samples) = wave_file(&[]);
+    let mut stream = StdIoStream::new(open(file).into_default_stream().unwrap());
+    let mut buf = [0; BLOCK_ALIGN];
+
+    assert_eq!(stream.seek(SeekFrom::End(-4)).unwrap(), 396);
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, samples[396..]);
+
+    assert_eq!(stream.seek(SeekFrom::Start(13)).unwrap(), 12);
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, samples[12..16]);
+
+    assert_eq!(stream.seek(SeekFrom::Current(-8)).unwrap(), 8);
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, samples[8..12]);
+
+    assert!(stream.seek(SeekFrom::End(-1000)).is_err());
+}
@@ -0,0 +1,217 @@
+use phonic_format_wave::{
+    WaveForm, WaveFormat, WaveFormatTag, WaveHeader, WaveSupportedCodec, UNKNOWN_LEN,
+};
+use phonic_io_core::{
+    Format, FormatChunk, FormatData, FormatObserver, FormatOffset, FormatReader, FormatSeeker,
+    FormatWriter, StreamSpec,
+};
+use phonic_signal::{KnownSampleType, SignalSpecBuilder};
+use std::io::{self, Cursor, Seek, SeekFrom, Write};
+
+fn unsized_data() -> FormatData<WaveFormatTag> {
+    let decoded_spec = SignalSpecBuilder::new()
+        .with_frame_rate(8_000)
+        .with_channels(1);
+
+    let mut spec = StreamSpec::new().with_decoded_spec(decoded_spec);
+    spec.sample_type = Some(KnownSampleType::I16.into());
+    FormatData::new().with_stream(spec.filled().unwrap())
+}
+
+fn write(format: &mut impl FormatWriter, buf: &[u8]) {
+    format
+        .write(FormatChunk::Stream { stream_i: 0, buf })
+        .unwrap();
+}
+
+fn field(file: &[u8], i: usize) -> u32 {
+    u32::from_le_bytes(file[i..i + 4].try_into().unwrap())
+}
+
+/// A sink that keeps only the start of what is written to it, so files too large to hold can be
+/// written.
+#[derive(Default)]
+struct Sink {
+    head: Vec<u8>,
+    i: u64,
+    len: u64,
+    can_seek: bool,
+}
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for (i, &byte) in (self.i..).zip(buf).take_while(|(i, _)| *i < 256) {
+            match self.head.get_mut(i as usize) {
+                Some(head) => *head = byte,
+                None => self.head.push(byte),
+            }
+        }
+
+        self.i += buf.len() as u64;
+        self.len = self.len.max(self.i);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for Sink {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match pos {
+            SeekFrom::Start(i) if self.can_seek => self.i = i,
+            _ => return Err(io::ErrorKind::Unsupported.into()),
+        }
+
+        Ok(self.i)
+    }
+}
+
+#[test]
+fn sizes_are_patched_on_flush() {
+    let mut format = WaveFormat::<_, WaveFormatTag>::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .with_seekable_writes();
+
+    format.write_data(&unsized_data()).unwrap();
+    write(&mut format, &[1, 2, 3, 4]);
+    format.flush().unwrap();
+
+    let file = format.as_inner().get_ref().clone();
+    let header = WaveHeader::read(&mut file.as_slice()).unwrap();
+    assert_eq!(header.data.byte_len, 4);
+    assert_eq!(field(&file, 4) as usize, file.len() - 8);
+
+    // writing carries on after the data written so far, and is patched in again
+    write(&mut format, &[5, 6]);
+    let file = format.into_inner().into_inner();
+    let header = WaveHeader::read(&mut file.as_slice()).unwrap();
+    assert_eq!(header.data.byte_len, 6);
+    assert_eq!(file[header.data_offset() as usize..], [1, 2, 3, 4, 5, 6]);
+
+    let data = FormatData::<WaveFormatTag>::from(header);
+    assert_eq!(data.streams[0].decoded_spec.n_frames, Some(3));
+}
+
+#[test]
+fn odd_lengths_are_padded_and_counted() {
+    let decoded_spec = SignalSpecBuilder::new()
+        .with_frame_rate(8_000)
+        .with_channels(1);
+
+    let spec = StreamSpec::new()
+        .with_codec(WaveSupportedCodec::MuLaw)
+        .with_decoded_spec(decoded_spec)
+        .filled()
+        .unwrap();
+
+    let mut format = WaveFormat::<_, WaveFormatTag>::new(Cursor::new(Vec::new()))
+        .unwrap()
+        .with_seekable_writes();
+
+    format
+        .write_data(&FormatData::new().with_stream(spec))
+        .unwrap();
+    write(&mut format, &[1, 2, 3]);
+    format.flush().unwrap();
+
+    // the data chunk is padded to an even length, and the fact chunk counts the frames
+    let file = format.as_inner().get_ref().clone();
+    let header = WaveHeader::read(&mut file.as_slice()).unwrap();
+    assert_eq!(header.data.byte_len, 3);
+    assert_eq!(header.fact.map(|fact| fact.n_frames), Some(3));
+    assert_eq!(file.len() as u64, header.byte_len());
+    assert_eq!(field(&file, 4) as usize, file.len() - 8);
+
+    // more data is written over the pad byte
+    write(&mut format, &[4, 5]);
+    let file = format.into_inner().into_inner();
+    let header = WaveHeader::read(&mut file.as_slice()).unwrap();
+    assert_eq!(header.fact.map(|fact| fact.n_frames), Some(5));
+    assert_eq!(file[header.data_offset() as usize..], [1, 2, 3, 4, 5, 0]);
+
+    let data = FormatData::<WaveFormatTag>::from(header);
+    assert_eq!(data.streams[0].decoded_spec.n_frames, Some(5));
+}
+
+#[test]
+fn sizes_are_patched_on_drop() {
+    let mut file = Vec::new();
+    let mut format = WaveFormat::<_, WaveFormatTag>::new(Cursor::new(&mut file))
+        .unwrap()
+        .with_seekable_writes();
+
+    format.write_data(&unsized_data()).unwrap();
+    write(&mut format, &[1, 2, 3, 4]);
+    drop(format);
+
+    let header = WaveHeader::read(&mut file.as_slice()).unwrap();
+    assert_eq!(header.data.byte_len, 4);
+    assert_eq!(field(&file, 4) as usize, file.len() - 8);
+}
+
+#[test]
+fn unseekable_writes_keep_placeholders() {
+    let mut format = WaveFormat::<_, WaveFormatTag>::new(Vec::new()).unwrap();
+    format.write_data(&unsized_data()).unwrap();
+    write(&mut format, &[1, 2, 3, 4, 5, 6]);
+    format.flush().unwrap();
+
+    let file = format.into_inner();
+    let header = WaveHeader::read(&mut file.as_slice()).unwrap();
+    assert!(header.has_unknown_len());
+    assert_eq!(field(&file, 4), UNKNOWN_LEN);
+    assert_eq!(field(&file, header.data_offset() as usize - 4), UNKNOWN_LEN);
+
+    // the data runs to the end of the file when read back
+    let mut format = WaveFormat::<_, WaveFormatTag>::new(Cursor::new(file)).unwrap();
+    format.read_data().unwrap();
+    assert_eq!(format.data().streams[0].decoded_spec.n_frames, None);
+
+    let mut buf = [0; 16];
+    let FormatChunk::Stream { buf, .. } = format.read(&mut buf).unwrap();
+    assert_eq!(buf, [1, 2, 3, 4, 5, 6]);
+
+    format
+        .seek(FormatOffset {
+            stream_offset: 0,
+            byte_offset: -4,
+        })
+        .unwrap();
+    assert_eq!(format.position().unwrap().byte_i, 2);
+
+    // a writer that fails to seek is treated the same
+    let mut format = WaveFormat::<_, WaveFormatTag>::new(Sink::default())
+        .unwrap()
+        .with_seekable_writes();
+
+    format.write_data(&unsized_data()).unwrap();
+    write(&mut format, &[1, 2]);
+    format.flush().unwrap();
+    assert_eq!(field(&format.into_inner().head, 4), UNKNOWN_LEN);
+}
+
+#[test]
+fn large_files_are_patched_to_rf64() {
+    let sink = Sink {
+        can_seek: true,
+        ..Default::default()
+    };
+
+    let mut format = WaveFormat::<_, WaveFormatTag>::new(sink)
+        .unwrap()
+        .with_seekable_writes();
+
+    format.write_data(&unsized_data()).unwrap();
+    let buf = vec![0; 1 << 20];
+    for _ in 0..4_097 {
+        write(&mut format, &buf);
+    }
+
+    let sink = format.into_inner();
+    let header = WaveHeader::read(&mut sink.head.as_slice()).unwrap();
+    assert_eq!(header.form, WaveForm::Rf64);
+    assert_eq!(header.data.byte_len, 4_097 << 20);
+    assert_eq!(sink.len, header.data_offset() + header.data.byte_len);
+}
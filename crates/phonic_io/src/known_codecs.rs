@@ -6,14 +6,50 @@ use phonic_io_core::{utils::TaggedSignal, CodecTag, DynCodecConstructor, DynStre
 #[non_exhaustive]
 pub enum KnownCodec {
     #[cfg(feature = "pcm")]
-    Pcm,
+    Pcm(crate::codecs::pcm::PcmCodecTag),
+
+    #[cfg(feature = "g711")]
+    G711(crate::codecs::g711::G711CodecTag),
+
+    #[cfg(feature = "adpcm")]
+    Adpcm(crate::codecs::adpcm::AdpcmCodecTag),
+
+    #[cfg(feature = "flac")]
+    Flac(crate::codecs::flac::FlacCodecTag),
+
+    #[cfg(feature = "opus")]
+    Opus(crate::codecs::opus::OpusCodecTag),
+
+    #[cfg(feature = "mp3")]
+    Mp3(crate::codecs::mp3::Mp3CodecTag),
+
+    #[cfg(feature = "vorbis")]
+    Vorbis(crate::codecs::vorbis::VorbisCodecTag),
 }
 
 impl CodecTag for KnownCodec {
     fn fill_spec(spec: &mut StreamSpec<Self>) -> Result<(), PhonicError> {
         match spec.codec {
             #[cfg(feature = "pcm")]
-            Some(Self::Pcm) => crate::codecs::pcm::fill_pcm_spec(spec),
+            Some(Self::Pcm(_)) => crate::codecs::pcm::fill_pcm_spec(spec),
+
+            #[cfg(feature = "g711")]
+            Some(Self::G711(_)) => crate::codecs::g711::fill_g711_spec(spec),
+
+            #[cfg(feature = "adpcm")]
+            Some(Self::Adpcm(_)) => crate::codecs::adpcm::fill_adpcm_spec(spec),
+
+            #[cfg(feature = "flac")]
+            Some(Self::Flac(_)) => crate::codecs::flac::fill_flac_spec(spec),
+
+            #[cfg(feature = "opus")]
+            Some(Self::Opus(_)) => crate::codecs::opus::fill_opus_spec(spec),
+
+            #[cfg(feature = "mp3")]
+            Some(Self::Mp3(_)) => crate::codecs::mp3::fill_mp3_spec(spec),
+
+            #[cfg(feature = "vorbis")]
+            Some(Self::Vorbis(_)) => crate::codecs::vorbis::fill_vorbis_spec(spec),
 
             _ => Ok(()),
         }
@@ -27,7 +63,40 @@ impl DynCodecConstructor for KnownCodec {
     ) -> Result<Box<dyn DynStream<Tag = Self>>, PhonicError> {
         match self {
             #[cfg(feature = "pcm")]
-            Self::Pcm => crate::codecs::pcm::pcm_codec_from_signal(signal),
+            Self::Pcm(tag) => crate::codecs::pcm::pcm_codec_from_signal(signal, *tag),
+
+            #[cfg(feature = "g711")]
+            Self::G711(tag) => crate::codecs::g711::g711_codec_from_signal(signal, *tag),
+
+            #[cfg(feature = "adpcm")]
+            Self::Adpcm(tag) => crate::codecs::adpcm::adpcm_codec_from_signal(signal, *tag),
+
+            #[cfg(feature = "flac")]
+            Self::Flac(tag) => crate::codecs::flac::flac_codec_from_signal(signal, *tag),
+
+            #[cfg(feature = "opus")]
+            Self::Opus(tag) => crate::codecs::opus::opus_codec_from_signal(signal, *tag),
+
+            #[cfg(feature = "mp3")]
+            Self::Mp3(tag) => crate::codecs::mp3::mp3_codec_from_signal(signal, *tag),
+
+            #[cfg(feature = "vorbis")]
+            Self::Vorbis(tag) => crate::codecs::vorbis::vorbis_codec_from_signal(signal, *tag),
+
+            // only reachable when no codecs are enabled
+            #[cfg(not(any(
+                feature = "pcm",
+                feature = "g711",
+                feature = "adpcm",
+                feature = "flac",
+                feature = "opus",
+                feature = "mp3",
+                feature = "vorbis"
+            )))]
+            _ => {
+                drop(signal);
+                Err(PhonicError::Unsupported)
+            }
         }
     }
 
@@ -36,19 +105,74 @@ impl DynCodecConstructor for KnownCodec {
     ) -> Result<TaggedSignal, PhonicError> {
         match stream.spec().codec {
             #[cfg(feature = "pcm")]
-            Some(Self::Pcm) => crate::codecs::pcm::pcm_codec_from_stream(stream),
+            Some(Self::Pcm(_)) => crate::codecs::pcm::pcm_codec_from_stream(stream),
+
+            #[cfg(feature = "g711")]
+            Some(Self::G711(_)) => crate::codecs::g711::g711_codec_from_stream(stream),
+
+            #[cfg(feature = "adpcm")]
+            Some(Self::Adpcm(_)) => crate::codecs::adpcm::adpcm_codec_from_stream(stream),
+
+            #[cfg(feature = "flac")]
+            Some(Self::Flac(_)) => crate::codecs::flac::flac_codec_from_stream(stream),
+
+            #[cfg(feature = "opus")]
+            Some(Self::Opus(_)) => crate::codecs::opus::opus_codec_from_stream(stream),
+
+            #[cfg(feature = "mp3")]
+            Some(Self::Mp3(_)) => crate::codecs::mp3::mp3_codec_from_stream(stream),
+
+            #[cfg(feature = "vorbis")]
+            Some(Self::Vorbis(_)) => crate::codecs::vorbis::vorbis_codec_from_stream(stream),
 
             None => Err(PhonicError::MissingData),
-            _ => Err(PhonicError::Unsupported),
+
+            // only reachable when no codecs are enabled
+            #[cfg(not(any(
+                feature = "pcm",
+                feature = "g711",
+                feature = "adpcm",
+                feature = "flac",
+                feature = "opus",
+                feature = "mp3",
+                feature = "vorbis"
+            )))]
+            Some(_) => Err(PhonicError::Unsupported),
         }
     }
 }
 
 #[cfg(feature = "wave")]
-impl From<crate::formats::wave::WaveSupportedCodec> for KnownCodec {
-    fn from(codec: crate::formats::wave::WaveSupportedCodec) -> Self {
+impl TryFrom<crate::formats::wave::WaveSupportedCodec> for KnownCodec {
+    type Error = PhonicError;
+
+    fn try_from(codec: crate::formats::wave::WaveSupportedCodec) -> Result<Self, Self::Error> {
         match codec {
-            crate::formats::wave::WaveSupportedCodec::Pcm => Self::Pcm,
+            #[cfg(feature = "pcm")]
+            crate::formats::wave::WaveSupportedCodec::Pcm => Ok(Self::Pcm(Default::default())),
+
+            #[cfg(feature = "g711")]
+            crate::formats::wave::WaveSupportedCodec::ALaw => {
+                Ok(Self::G711(crate::codecs::g711::G711CodecTag::ALaw))
+            }
+
+            #[cfg(feature = "g711")]
+            crate::formats::wave::WaveSupportedCodec::MuLaw => {
+                Ok(Self::G711(crate::codecs::g711::G711CodecTag::MuLaw))
+            }
+
+            #[cfg(feature = "adpcm")]
+            crate::formats::wave::WaveSupportedCodec::ImaAdpcm => {
+                Ok(Self::Adpcm(crate::codecs::adpcm::AdpcmCodecTag::Ima))
+            }
+
+            #[cfg(feature = "adpcm")]
+            crate::formats::wave::WaveSupportedCodec::MsAdpcm => {
+                Ok(Self::Adpcm(crate::codecs::adpcm::AdpcmCodecTag::Microsoft))
+            }
+
+            #[allow(unreachable_patterns)]
+            _ => Err(PhonicError::Unsupported),
         }
     }
 }
@@ -59,7 +183,153 @@ impl TryFrom<KnownCodec> for crate::formats::wave::WaveSupportedCodec {
 
     fn try_from(codec: KnownCodec) -> Result<Self, Self::Error> {
         match codec {
-            KnownCodec::Pcm => Ok(Self::Pcm),
+            #[cfg(feature = "pcm")]
+            KnownCodec::Pcm(tag) => tag.try_into(),
+
+            #[cfg(feature = "g711")]
+            KnownCodec::G711(tag) => Ok(tag.into()),
+
+            #[cfg(feature = "adpcm")]
+            KnownCodec::Adpcm(tag) => Ok(tag.into()),
+
+            #[allow(unreachable_patterns)]
+            _ => Err(PhonicError::Unsupported),
+        }
+    }
+}
+
+#[cfg(feature = "aiff")]
+impl TryFrom<crate::formats::aiff::AiffSupportedCodec> for KnownCodec {
+    type Error = PhonicError;
+
+    fn try_from(codec: crate::formats::aiff::AiffSupportedCodec) -> Result<Self, Self::Error> {
+        match codec {
+            #[cfg(feature = "pcm")]
+            crate::formats::aiff::AiffSupportedCodec::Pcm(byte_order) => Ok(Self::Pcm(
+                crate::codecs::pcm::PcmCodecTag::new().with_byte_order(byte_order),
+            )),
+
+            #[cfg(feature = "g711")]
+            crate::formats::aiff::AiffSupportedCodec::ALaw => {
+                Ok(Self::G711(crate::codecs::g711::G711CodecTag::ALaw))
+            }
+
+            #[cfg(feature = "g711")]
+            crate::formats::aiff::AiffSupportedCodec::MuLaw => {
+                Ok(Self::G711(crate::codecs::g711::G711CodecTag::MuLaw))
+            }
+
+            #[allow(unreachable_patterns)]
+            _ => Err(PhonicError::Unsupported),
+        }
+    }
+}
+
+#[cfg(feature = "aiff")]
+impl TryFrom<KnownCodec> for crate::formats::aiff::AiffSupportedCodec {
+    type Error = PhonicError;
+
+    fn try_from(codec: KnownCodec) -> Result<Self, Self::Error> {
+        match codec {
+            #[cfg(feature = "pcm")]
+            KnownCodec::Pcm(tag) => tag.try_into(),
+
+            #[cfg(feature = "g711")]
+            KnownCodec::G711(tag) => Ok(tag.into()),
+
+            #[allow(unreachable_patterns)]
+            _ => Err(PhonicError::Unsupported),
+        }
+    }
+}
+
+#[cfg(feature = "au")]
+impl TryFrom<crate::formats::au::AuSupportedCodec> for KnownCodec {
+    type Error = PhonicError;
+
+    fn try_from(codec: crate::formats::au::AuSupportedCodec) -> Result<Self, Self::Error> {
+        match codec {
+            #[cfg(feature = "pcm")]
+            crate::formats::au::AuSupportedCodec::Pcm => Ok(Self::Pcm(
+                crate::codecs::pcm::PcmCodecTag::new()
+                    .with_byte_order(crate::codecs::pcm::ByteOrder::BigEndian),
+            )),
+
+            #[cfg(feature = "g711")]
+            crate::formats::au::AuSupportedCodec::ALaw => {
+                Ok(Self::G711(crate::codecs::g711::G711CodecTag::ALaw))
+            }
+
+            #[cfg(feature = "g711")]
+            crate::formats::au::AuSupportedCodec::MuLaw => {
+                Ok(Self::G711(crate::codecs::g711::G711CodecTag::MuLaw))
+            }
+
+            #[allow(unreachable_patterns)]
+            _ => Err(PhonicError::Unsupported),
+        }
+    }
+}
+
+#[cfg(feature = "au")]
+impl TryFrom<KnownCodec> for crate::formats::au::AuSupportedCodec {
+    type Error = PhonicError;
+
+    fn try_from(codec: KnownCodec) -> Result<Self, Self::Error> {
+        match codec {
+            #[cfg(feature = "pcm")]
+            KnownCodec::Pcm(tag) => tag.try_into(),
+
+            #[cfg(feature = "g711")]
+            KnownCodec::G711(tag) => Ok(tag.into()),
+
+            #[allow(unreachable_patterns)]
+            _ => Err(PhonicError::Unsupported),
+        }
+    }
+}
+
+#[cfg(feature = "ogg")]
+impl TryFrom<crate::formats::ogg::OggSupportedCodec> for KnownCodec {
+    type Error = PhonicError;
+
+    fn try_from(codec: crate::formats::ogg::OggSupportedCodec) -> Result<Self, Self::Error> {
+        match codec {
+            #[cfg(feature = "flac")]
+            crate::formats::ogg::OggSupportedCodec::Flac(tag) => Ok(Self::Flac(tag)),
+
+            #[cfg(feature = "opus")]
+            crate::formats::ogg::OggSupportedCodec::Opus => {
+                Ok(Self::Opus(crate::codecs::opus::OpusCodecTag))
+            }
+
+            #[cfg(feature = "vorbis")]
+            crate::formats::ogg::OggSupportedCodec::Vorbis => {
+                Ok(Self::Vorbis(crate::codecs::vorbis::VorbisCodecTag))
+            }
+
+            #[allow(unreachable_patterns)]
+            _ => Err(PhonicError::Unsupported),
+        }
+    }
+}
+
+#[cfg(feature = "ogg")]
+impl TryFrom<KnownCodec> for crate::formats::ogg::OggSupportedCodec {
+    type Error = PhonicError;
+
+    fn try_from(codec: KnownCodec) -> Result<Self, Self::Error> {
+        match codec {
+            #[cfg(feature = "flac")]
+            KnownCodec::Flac(tag) => Ok(tag.into()),
+
+            #[cfg(feature = "opus")]
+            KnownCodec::Opus(_) => Ok(Self::Opus),
+
+            #[cfg(feature = "vorbis")]
+            KnownCodec::Vorbis(_) => Ok(Self::Vorbis),
+
+            #[allow(unreachable_patterns)]
             _ => Err(PhonicError::Unsupported),
         }
     }
@@ -67,8 +337,8 @@ impl TryFrom<KnownCodec> for crate::formats::wave::WaveSupportedCodec {
 
 #[cfg(feature = "pcm")]
 impl From<crate::codecs::pcm::PcmCodecTag> for KnownCodec {
-    fn from(_: crate::codecs::pcm::PcmCodecTag) -> Self {
-        Self::Pcm
+    fn from(tag: crate::codecs::pcm::PcmCodecTag) -> Self {
+        Self::Pcm(tag)
     }
 }
 
@@ -78,7 +348,128 @@ impl TryFrom<KnownCodec> for crate::codecs::pcm::PcmCodecTag {
 
     fn try_from(codec: KnownCodec) -> Result<Self, Self::Error> {
         match codec {
-            KnownCodec::Pcm => Ok(Self),
+            KnownCodec::Pcm(tag) => Ok(tag),
+            #[allow(unreachable_patterns)]
+            _ => Err(PhonicError::Unsupported),
+        }
+    }
+}
+
+#[cfg(feature = "g711")]
+impl From<crate::codecs::g711::G711CodecTag> for KnownCodec {
+    fn from(tag: crate::codecs::g711::G711CodecTag) -> Self {
+        Self::G711(tag)
+    }
+}
+
+#[cfg(feature = "g711")]
+impl TryFrom<KnownCodec> for crate::codecs::g711::G711CodecTag {
+    type Error = PhonicError;
+
+    fn try_from(codec: KnownCodec) -> Result<Self, Self::Error> {
+        match codec {
+            KnownCodec::G711(tag) => Ok(tag),
+            #[allow(unreachable_patterns)]
+            _ => Err(PhonicError::Unsupported),
+        }
+    }
+}
+
+#[cfg(feature = "adpcm")]
+impl From<crate::codecs::adpcm::AdpcmCodecTag> for KnownCodec {
+    fn from(tag: crate::codecs::adpcm::AdpcmCodecTag) -> Self {
+        Self::Adpcm(tag)
+    }
+}
+
+#[cfg(feature = "adpcm")]
+impl TryFrom<KnownCodec> for crate::codecs::adpcm::AdpcmCodecTag {
+    type Error = PhonicError;
+
+    fn try_from(codec: KnownCodec) -> Result<Self, Self::Error> {
+        match codec {
+            KnownCodec::Adpcm(tag) => Ok(tag),
+            #[allow(unreachable_patterns)]
+            _ => Err(PhonicError::Unsupported),
+        }
+    }
+}
+
+#[cfg(feature = "flac")]
+impl From<crate::codecs::flac::FlacCodecTag> for KnownCodec {
+    fn from(tag: crate::codecs::flac::FlacCodecTag) -> Self {
+        Self::Flac(tag)
+    }
+}
+
+#[cfg(feature = "flac")]
+impl TryFrom<KnownCodec> for crate::codecs::flac::FlacCodecTag {
+    type Error = PhonicError;
+
+    fn try_from(codec: KnownCodec) -> Result<Self, Self::Error> {
+        match codec {
+            KnownCodec::Flac(tag) => Ok(tag),
+            #[allow(unreachable_patterns)]
+            _ => Err(PhonicError::Unsupported),
+        }
+    }
+}
+
+#[cfg(feature = "opus")]
+impl From<crate::codecs::opus::OpusCodecTag> for KnownCodec {
+    fn from(tag: crate::codecs::opus::OpusCodecTag) -> Self {
+        Self::Opus(tag)
+    }
+}
+
+#[cfg(feature = "opus")]
+impl TryFrom<KnownCodec> for crate::codecs::opus::OpusCodecTag {
+    type Error = PhonicError;
+
+    fn try_from(codec: KnownCodec) -> Result<Self, Self::Error> {
+        match codec {
+            KnownCodec::Opus(tag) => Ok(tag),
+            #[allow(unreachable_patterns)]
+            _ => Err(PhonicError::Unsupported),
+        }
+    }
+}
+
+#[cfg(feature = "mp3")]
+impl From<crate::codecs::mp3::Mp3CodecTag> for KnownCodec {
+    fn from(tag: crate::codecs::mp3::Mp3CodecTag) -> Self {
+        Self::Mp3(tag)
+    }
+}
+
+#[cfg(feature = "mp3")]
+impl TryFrom<KnownCodec> for crate::codecs::mp3::Mp3CodecTag {
+    type Error = PhonicError;
+
+    fn try_from(codec: KnownCodec) -> Result<Self, Self::Error> {
+        match codec {
+            KnownCodec::Mp3(tag) => Ok(tag),
+            #[allow(unreachable_patterns)]
+            _ => Err(PhonicError::Unsupported),
+        }
+    }
+}
+
+#[cfg(feature = "vorbis")]
+impl From<crate::codecs::vorbis::VorbisCodecTag> for KnownCodec {
+    fn from(tag: crate::codecs::vorbis::VorbisCodecTag) -> Self {
+        Self::Vorbis(tag)
+    }
+}
+
+#[cfg(feature = "vorbis")]
+impl TryFrom<KnownCodec> for crate::codecs::vorbis::VorbisCodecTag {
+    type Error = PhonicError;
+
+    fn try_from(codec: KnownCodec) -> Result<Self, Self::Error> {
+        match codec {
+            KnownCodec::Vorbis(tag) => Ok(tag),
+            #[allow(unreachable_patterns)]
             _ => Err(PhonicError::Unsupported),
         }
     }
@@ -2,27 +2,49 @@ use crate::KnownCodec;
 use lazy_static::lazy_static;
 use std::collections::HashMap;
 use phonic_core::PhonicError;
-use phonic_format_wave::WAVE_IDENTIFIERS;
 use phonic_io_core::{
     utils::{FormatIdentifier, FormatIdentifiers},
     DynFormat, DynFormatConstructor, FormatData, FormatTag, StdIoSource,
 };
 
-#[derive(Eq, PartialEq, Copy, Clone, Hash, Debug)]
+#[derive(Eq, PartialEq, Ord, PartialOrd, Copy, Clone, Hash, Debug)]
 #[non_exhaustive]
 pub enum KnownFormat {
     #[cfg(feature = "wave")]
     Wave,
-}
 
-lazy_static! {
-    static ref KNOWN_FORMAT_IDENTIFIERS: HashMap<KnownFormat, &'static FormatIdentifiers> = {
-        let mut map = HashMap::new();
+    #[cfg(feature = "aiff")]
+    Aiff,
+
+    #[cfg(feature = "au")]
+    Au,
+
+    #[cfg(feature = "flac")]
+    Flac,
+
+    #[cfg(feature = "ogg")]
+    Ogg,
 
-        #[cfg(feature = "wave")]
-        map.insert(KnownFormat::Wave, &WAVE_IDENTIFIERS);
+    #[cfg(feature = "mp3")]
+    Mp3,
+}
 
-        map
+lazy_static! {
+    pub(crate) static ref KNOWN_FORMAT_IDENTIFIERS: HashMap<KnownFormat, &'static FormatIdentifiers> = {
+        HashMap::from_iter([
+            #[cfg(feature = "wave")]
+            (KnownFormat::Wave, &crate::formats::wave::WAVE_IDENTIFIERS),
+            #[cfg(feature = "aiff")]
+            (KnownFormat::Aiff, &crate::formats::aiff::AIFF_IDENTIFIERS),
+            #[cfg(feature = "au")]
+            (KnownFormat::Au, &crate::formats::au::AU_IDENTIFIERS),
+            #[cfg(feature = "flac")]
+            (KnownFormat::Flac, &crate::formats::flac::FLAC_IDENTIFIERS),
+            #[cfg(feature = "ogg")]
+            (KnownFormat::Ogg, &crate::formats::ogg::OGG_IDENTIFIERS),
+            #[cfg(feature = "mp3")]
+            (KnownFormat::Mp3, &crate::formats::mp3::MP3_IDENTIFIERS),
+        ])
     };
 }
 
@@ -34,7 +56,22 @@ impl FormatTag for KnownFormat {
             #[cfg(feature = "wave")]
             Some(Self::Wave) => crate::formats::wave::fill_wave_data(data),
 
-            _ => return Ok(()),
+            #[cfg(feature = "aiff")]
+            Some(Self::Aiff) => crate::formats::aiff::fill_aiff_data(data),
+
+            #[cfg(feature = "au")]
+            Some(Self::Au) => crate::formats::au::fill_au_data(data),
+
+            #[cfg(feature = "flac")]
+            Some(Self::Flac) => crate::formats::flac::fill_flac_data(data),
+
+            #[cfg(feature = "ogg")]
+            Some(Self::Ogg) => crate::formats::ogg::fill_ogg_data(data),
+
+            #[cfg(feature = "mp3")]
+            Some(Self::Mp3) => crate::formats::mp3::fill_mp3_data(data),
+
+            _ => Ok(()),
         }
     }
 }
@@ -44,12 +81,45 @@ impl DynFormatConstructor for KnownFormat {
         &self,
         inner: S,
     ) -> Result<Box<dyn DynFormat<Tag = Self>>, PhonicError> {
-        Ok(match self {
+        match self {
             #[cfg(feature = "wave")]
-            KnownFormat::Wave => Box::new(crate::formats::wave::WaveFormat::new(inner)?),
+            KnownFormat::Wave => Ok(Box::new(
+                crate::formats::wave::WaveFormat::new(inner)?.with_seekable_writes(),
+            )),
+
+            #[cfg(feature = "aiff")]
+            KnownFormat::Aiff => Ok(Box::new(
+                crate::formats::aiff::AiffFormat::new(inner)?.with_seekable_reads(),
+            )),
+
+            #[cfg(feature = "au")]
+            KnownFormat::Au => Ok(Box::new(crate::formats::au::AuFormat::new(inner)?)),
 
-            _ => return Err(PhonicError::Unsupported),
-        })
+            #[cfg(feature = "flac")]
+            KnownFormat::Flac => Ok(Box::new(crate::formats::flac::FlacFormat::new(inner)?)),
+
+            #[cfg(feature = "ogg")]
+            KnownFormat::Ogg => Ok(Box::new(
+                crate::formats::ogg::OggFormat::new(inner)?.with_seekable_reads(),
+            )),
+
+            #[cfg(feature = "mp3")]
+            KnownFormat::Mp3 => Ok(Box::new(crate::formats::mp3::Mp3Format::new(inner)?)),
+
+            // only reachable when no formats are enabled
+            #[cfg(not(any(
+                feature = "wave",
+                feature = "aiff",
+                feature = "au",
+                feature = "flac",
+                feature = "ogg",
+                feature = "mp3"
+            )))]
+            _ => {
+                drop(inner);
+                Err(PhonicError::Unsupported)
+            }
+        }
     }
 }
 
@@ -79,6 +149,107 @@ impl TryFrom<KnownFormat> for crate::formats::wave::WaveFormatTag {
     fn try_from(format: KnownFormat) -> Result<Self, Self::Error> {
         match format {
             KnownFormat::Wave => Ok(Self),
+            #[allow(unreachable_patterns)]
+            _ => Err(PhonicError::Unsupported),
+        }
+    }
+}
+
+#[cfg(feature = "aiff")]
+impl From<crate::formats::aiff::AiffFormatTag> for KnownFormat {
+    fn from(_: crate::formats::aiff::AiffFormatTag) -> Self {
+        Self::Aiff
+    }
+}
+
+#[cfg(feature = "aiff")]
+impl TryFrom<KnownFormat> for crate::formats::aiff::AiffFormatTag {
+    type Error = PhonicError;
+
+    fn try_from(format: KnownFormat) -> Result<Self, Self::Error> {
+        match format {
+            KnownFormat::Aiff => Ok(Self),
+            #[allow(unreachable_patterns)]
+            _ => Err(PhonicError::Unsupported),
+        }
+    }
+}
+
+#[cfg(feature = "au")]
+impl From<crate::formats::au::AuFormatTag> for KnownFormat {
+    fn from(_: crate::formats::au::AuFormatTag) -> Self {
+        Self::Au
+    }
+}
+
+#[cfg(feature = "au")]
+impl TryFrom<KnownFormat> for crate::formats::au::AuFormatTag {
+    type Error = PhonicError;
+
+    fn try_from(format: KnownFormat) -> Result<Self, Self::Error> {
+        match format {
+            KnownFormat::Au => Ok(Self),
+            #[allow(unreachable_patterns)]
+            _ => Err(PhonicError::Unsupported),
+        }
+    }
+}
+
+#[cfg(feature = "flac")]
+impl From<crate::formats::flac::FlacFormatTag> for KnownFormat {
+    fn from(_: crate::formats::flac::FlacFormatTag) -> Self {
+        Self::Flac
+    }
+}
+
+#[cfg(feature = "flac")]
+impl TryFrom<KnownFormat> for crate::formats::flac::FlacFormatTag {
+    type Error = PhonicError;
+
+    fn try_from(format: KnownFormat) -> Result<Self, Self::Error> {
+        match format {
+            KnownFormat::Flac => Ok(Self),
+            #[allow(unreachable_patterns)]
+            _ => Err(PhonicError::Unsupported),
+        }
+    }
+}
+
+#[cfg(feature = "ogg")]
+impl From<crate::formats::ogg::OggFormatTag> for KnownFormat {
+    fn from(_: crate::formats::ogg::OggFormatTag) -> Self {
+        Self::Ogg
+    }
+}
+
+#[cfg(feature = "ogg")]
+impl TryFrom<KnownFormat> for crate::formats::ogg::OggFormatTag {
+    type Error = PhonicError;
+
+    fn try_from(format: KnownFormat) -> Result<Self, Self::Error> {
+        match format {
+            KnownFormat::Ogg => Ok(Self),
+            #[allow(unreachable_patterns)]
+            _ => Err(PhonicError::Unsupported),
+        }
+    }
+}
+
+#[cfg(feature = "mp3")]
+impl From<crate::formats::mp3::Mp3FormatTag> for KnownFormat {
+    fn from(_: crate::formats::mp3::Mp3FormatTag) -> Self {
+        Self::Mp3
+    }
+}
+
+#[cfg(feature = "mp3")]
+impl TryFrom<KnownFormat> for crate::formats::mp3::Mp3FormatTag {
+    type Error = PhonicError;
+
+    fn try_from(format: KnownFormat) -> Result<Self, Self::Error> {
+        match format {
+            KnownFormat::Mp3 => Ok(Self),
+            #[allow(unreachable_patterns)]
             _ => Err(PhonicError::Unsupported),
         }
     }
@@ -1,16 +1,51 @@
 mod known_codecs;
 mod known_formats;
+mod probe;
 
 pub use known_codecs::*;
 pub use known_formats::*;
+pub use probe::*;
 pub use phonic_io_core::*;
 
 pub mod formats {
     #[cfg(feature = "wave")]
     pub use phonic_format_wave as wave;
+
+    #[cfg(feature = "aiff")]
+    pub use phonic_format_aiff as aiff;
+
+    #[cfg(feature = "au")]
+    pub use phonic_format_au as au;
+
+    #[cfg(feature = "flac")]
+    pub use phonic_format_flac as flac;
+
+    #[cfg(feature = "ogg")]
+    pub use phonic_format_ogg as ogg;
+
+    #[cfg(feature = "mp3")]
+    pub use phonic_format_mp3 as mp3;
 }
 
 pub mod codecs {
     #[cfg(feature = "pcm")]
     pub use phonic_codec_pcm as pcm;
+
+    #[cfg(feature = "g711")]
+    pub use phonic_codec_g711 as g711;
+
+    #[cfg(feature = "adpcm")]
+    pub use phonic_codec_adpcm as adpcm;
+
+    #[cfg(feature = "flac")]
+    pub use phonic_codec_flac as flac;
+
+    #[cfg(feature = "opus")]
+    pub use phonic_codec_opus as opus;
+
+    #[cfg(feature = "mp3")]
+    pub use phonic_codec_mp3 as mp3;
+
+    #[cfg(feature = "vorbis")]
+    pub use phonic_codec_vorbis as vorbis;
 }
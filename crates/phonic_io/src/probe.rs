@@ -0,0 +1,72 @@
+use crate::{KnownFormat, KNOWN_FORMAT_IDENTIFIERS};
+use phonic_core::PhonicError;
+use std::io::{ErrorKind, Read, Seek, SeekFrom};
+
+/// The format that best matches the start of a file, and the fraction of its markers that were
+/// found.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FormatProbe {
+    pub format: KnownFormat,
+    pub confidence: f32,
+}
+
+impl KnownFormat {
+    /// Scores every known format against the bytes at the start of a file. The buffer should
+    /// hold at least [`KnownFormat::probe_len`] bytes unless the file is shorter.
+    ///
+    /// Formats that score the same are told apart by the number of marker bytes they matched,
+    /// and then by the order they are declared in, earlier formats winning.
+    pub fn probe_buf(buf: &[u8]) -> Result<FormatProbe, PhonicError> {
+        KNOWN_FORMAT_IDENTIFIERS
+            .iter()
+            .map(|(format, ids)| {
+                let probe = FormatProbe {
+                    format: *format,
+                    confidence: ids.score_markers(buf),
+                };
+
+                (probe, ids.matched_marker_len(buf))
+            })
+            .filter(|(probe, _)| probe.confidence > 0.0)
+            .max_by(|(a, a_len), (b, b_len)| {
+                a.confidence
+                    .total_cmp(&b.confidence)
+                    .then(a_len.cmp(b_len))
+                    .then(b.format.cmp(&a.format))
+            })
+            .map(|(probe, _)| probe)
+            .ok_or(PhonicError::NotFound)
+    }
+
+    /// Peeks at the start of `reader` to find its format. The reader is rewound to the position
+    /// it was at before probing, even if no format matched.
+    pub fn probe<R: Read + Seek>(reader: &mut R) -> Result<FormatProbe, PhonicError> {
+        let start = reader.stream_position()?;
+        let mut buf = vec![0; Self::probe_len()];
+        let mut len = 0;
+
+        while len < buf.len() {
+            match reader.read(&mut buf[len..]) {
+                Ok(0) => break,
+                Ok(n) => len += n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    reader.seek(SeekFrom::Start(start))?;
+                    return Err(e.into());
+                }
+            }
+        }
+
+        reader.seek(SeekFrom::Start(start))?;
+        Self::probe_buf(&buf[..len])
+    }
+
+    /// The number of bytes needed to check the markers of every known format.
+    pub fn probe_len() -> usize {
+        KNOWN_FORMAT_IDENTIFIERS
+            .values()
+            .map(|ids| ids.marker_len())
+            .max()
+            .unwrap_or(0)
+    }
+}
@@ -15,6 +15,51 @@ pub trait FormatTag: Sized + Eq + Copy + Send + Sync {
 pub struct FormatData<F: FormatTag> {
     pub format: Option<F>,
     pub streams: Vec<StreamSpec<F::Codec>>,
+    pub metadata: FormatMetadata,
+}
+
+/// Descriptive data about a recording, which isn't needed to decode it.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FormatMetadata {
+    /// The frame of the timeline a recording belongs to that its first frame is placed at.
+    pub timeline_start: Option<u64>,
+
+    pub broadcast: Option<BroadcastMetadata>,
+
+    /// An iXML document, which describes the production a recording was made for.
+    pub ixml: Option<String>,
+}
+
+/// The description of a recording made for broadcast, as given by the `bext` chunk of a
+/// broadcast wave file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BroadcastMetadata {
+    pub description: String,
+    pub originator: String,
+    pub originator_reference: String,
+
+    /// The date of the recording as `yyyy-mm-dd`.
+    pub origination_date: String,
+
+    /// The time of day of the recording as `hh:mm:ss`.
+    pub origination_time: String,
+
+    /// The SMPTE unique material identifier, padded with zeros when it is a basic 32-byte umid.
+    pub umid: [u8; 64],
+    pub loudness: Loudness,
+
+    /// The processes the recording went through, as lines of text.
+    pub coding_history: String,
+}
+
+/// The loudness of a recording, in hundredths of a LUFS, LU or dBTP, or `None` when not given.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Loudness {
+    pub value: Option<i16>,
+    pub range: Option<i16>,
+    pub max_true_peak_level: Option<i16>,
+    pub max_momentary_loudness: Option<i16>,
+    pub max_short_term_loudness: Option<i16>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -103,8 +148,8 @@ pub trait FormatSeeker: Format {
     {
         let current_pos = self.position()?;
         self.seek(FormatOffset {
-            stream_offset: current_pos.stream_i as isize - position.stream_i as isize,
-            byte_offset: current_pos.byte_i as i64 - position.byte_i as i64,
+            stream_offset: position.stream_i as isize - current_pos.stream_i as isize,
+            byte_offset: position.byte_i as i64 - current_pos.byte_i as i64,
         })
     }
 }
@@ -114,6 +159,7 @@ impl<F: FormatTag> FormatData<F> {
         Self {
             format: None,
             streams: Vec::new(),
+            metadata: FormatMetadata::new(),
         }
     }
 
@@ -130,6 +176,7 @@ impl<F: FormatTag> FormatData<F> {
                 .into_iter()
                 .map(StreamSpec::with_tag_type)
                 .collect(),
+            metadata: self.metadata,
         }
     }
 
@@ -143,6 +190,11 @@ impl<F: FormatTag> FormatData<F> {
         self
     }
 
+    pub fn with_metadata(mut self, metadata: FormatMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
     pub fn is_empty(&self) -> bool {
         self.streams.is_empty()
     }
@@ -160,7 +212,7 @@ impl<F: FormatTag> FormatData<F> {
         }
 
         self.streams.extend(other_streams);
-        Ok(())
+        self.metadata.merge(&other.metadata)
     }
 
     pub fn fill(&mut self) -> Result<(), PhonicError> {
@@ -173,6 +225,68 @@ impl<F: FormatTag> FormatData<F> {
     }
 }
 
+impl FormatMetadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_timeline_start(mut self, timeline_start: u64) -> Self {
+        self.timeline_start = Some(timeline_start);
+        self
+    }
+
+    pub fn with_broadcast(mut self, broadcast: BroadcastMetadata) -> Self {
+        self.broadcast = Some(broadcast);
+        self
+    }
+
+    pub fn with_ixml(mut self, ixml: impl Into<String>) -> Self {
+        self.ixml = Some(ixml.into());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timeline_start.is_none() && self.broadcast.is_none() && self.ixml.is_none()
+    }
+
+    /// Fills in the fields of `other` that aren't set yet. Fields set in both must agree.
+    pub fn merge(&mut self, other: &Self) -> Result<(), PhonicError> {
+        merge_field(&mut self.timeline_start, &other.timeline_start)?;
+        merge_field(&mut self.broadcast, &other.broadcast)?;
+        merge_field(&mut self.ixml, &other.ixml)
+    }
+}
+
+impl Default for BroadcastMetadata {
+    fn default() -> Self {
+        Self {
+            description: String::new(),
+            originator: String::new(),
+            originator_reference: String::new(),
+            origination_date: String::new(),
+            origination_time: String::new(),
+            umid: [0; 64],
+            loudness: Loudness::default(),
+            coding_history: String::new(),
+        }
+    }
+}
+
+fn merge_field<T: Clone + PartialEq>(
+    field: &mut Option<T>,
+    other: &Option<T>,
+) -> Result<(), PhonicError> {
+    match (field.as_ref(), other) {
+        (_, None) => Ok(()),
+        (None, Some(other)) => {
+            *field = Some(other.clone());
+            Ok(())
+        }
+        (Some(value), Some(other)) if value == other => Ok(()),
+        _ => Err(PhonicError::SignalMismatch),
+    }
+}
+
 impl<T, F> Format for T
 where
     T: Deref,
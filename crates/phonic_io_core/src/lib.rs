@@ -7,3 +7,6 @@ pub use format::*;
 pub use stream::*;
 
 pub mod utils;
+
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
@@ -76,7 +76,7 @@ impl<C: CodecTag> StreamSpec<C> {
     pub fn n_bytes(&self) -> Option<u64> {
         self.avg_bitrate
             .zip(self.decoded_spec.duration())
-            .map(|(r, d)| (r / 8.0 * d.as_secs_f64()) as u64)
+            .map(|(r, d)| (r / 8.0 * d.as_secs_f64()).round() as u64)
     }
 
     pub fn is_empty(&self) -> bool {
@@ -240,7 +240,7 @@ pub trait StreamSeeker: Stream {
     where
         Self: Sized + StreamObserver,
     {
-        self.seek(self.position()? as i64 - position as i64)
+        self.seek(position as i64 - self.position()? as i64)
     }
 }
 
@@ -0,0 +1,135 @@
+use crate::{CodecTag, Stream, StreamObserver, StreamReader, StreamSeeker, StreamSpec, StreamWriter};
+use phonic_core::PhonicError;
+
+/// A stream held in memory, for testing codecs against. Bytes are read from and written over the
+/// buffer at the current position.
+#[derive(Debug, Clone)]
+pub struct BufferStream<C: CodecTag> {
+    spec: StreamSpec<C>,
+    bytes: Vec<u8>,
+    i: usize,
+}
+
+impl<C: CodecTag> BufferStream<C> {
+    pub fn new(spec: StreamSpec<C>, bytes: Vec<u8>) -> Self {
+        Self { spec, bytes, i: 0 }
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+impl<C: CodecTag> Stream for BufferStream<C> {
+    type Tag = C;
+
+    fn spec(&self) -> &StreamSpec<Self::Tag> {
+        &self.spec
+    }
+}
+
+impl<C: CodecTag> StreamObserver for BufferStream<C> {
+    fn position(&self) -> Result<u64, PhonicError> {
+        Ok(self.i as u64)
+    }
+}
+
+impl<C: CodecTag> StreamReader for BufferStream<C> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, PhonicError> {
+        let n = buf.len().min(self.bytes.len() - self.i);
+        buf[..n].copy_from_slice(&self.bytes[self.i..self.i + n]);
+        self.i += n;
+        Ok(n)
+    }
+}
+
+impl<C: CodecTag> StreamWriter for BufferStream<C> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, PhonicError> {
+        let end = self.i + buf.len();
+        if end > self.bytes.len() {
+            self.bytes.resize(end, 0);
+        }
+
+        self.bytes[self.i..end].copy_from_slice(buf);
+        self.i = end;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), PhonicError> {
+        Ok(())
+    }
+}
+
+impl<C: CodecTag> StreamSeeker for BufferStream<C> {
+    fn seek(&mut self, offset: i64) -> Result<(), PhonicError> {
+        match self.i.checked_add_signed(offset as isize) {
+            Some(i) if i <= self.bytes.len() => {
+                self.i = i;
+                Ok(())
+            }
+            _ => Err(PhonicError::InvalidData),
+        }
+    }
+}
+
+/// The order bits are packed into each byte by a [`BitWriter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitOrder {
+    /// Values are written from their highest bit down, filling each byte from its highest bit, as
+    /// mpeg audio frames are coded.
+    MsbFirst,
+
+    /// Values are written from their lowest bit up, filling each byte from its lowest bit, as
+    /// vorbis packets are coded.
+    LsbFirst,
+}
+
+/// Packs values of any number of bits into bytes, for building packets and frames to test
+/// decoders against.
+#[derive(Debug, Clone)]
+pub struct BitWriter {
+    order: BitOrder,
+    bytes: Vec<u8>,
+    n_bits: usize,
+}
+
+impl BitWriter {
+    pub fn new(order: BitOrder) -> Self {
+        Self {
+            order,
+            bytes: Vec::new(),
+            n_bits: 0,
+        }
+    }
+
+    /// Writes the lowest `n_bits` bits of `value`.
+    pub fn write(&mut self, value: u32, n_bits: u32) {
+        for i in 0..n_bits {
+            if self.n_bits.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+
+            let byte = self.bytes.last_mut().unwrap();
+            match self.order {
+                BitOrder::MsbFirst => {
+                    *byte |= ((value >> (n_bits - 1 - i)) as u8 & 1) << (7 - self.n_bits % 8)
+                }
+                BitOrder::LsbFirst => *byte |= ((value >> i) as u8 & 1) << (self.n_bits % 8),
+            }
+
+            self.n_bits += 1;
+        }
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
@@ -4,7 +4,15 @@ use phonic_core::PhonicError;
 pub struct FormatIdentifiers {
     pub file_extensions: &'static [&'static str],
     pub mime_types: &'static [&'static str],
-    pub markers: &'static [&'static [u8]],
+    pub markers: &'static [FormatMarker],
+}
+
+/// A sequence of bytes expected at a fixed offset from the start of a file. Markers that share an
+/// offset are treated as alternatives, only one of them is expected to match.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FormatMarker {
+    pub offset: usize,
+    pub bytes: &'static [u8],
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -20,6 +28,58 @@ impl FormatIdentifiers {
             FormatIdentifier::MimeType(mime) => self.mime_types.contains(mime),
         }
     }
+
+    /// The number of bytes from the start of a file needed to check every marker.
+    pub fn marker_len(&self) -> usize {
+        self.markers
+            .iter()
+            .map(|marker| marker.offset + marker.bytes.len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the fraction of marker bytes matched by the start of a file, from 0 when nothing
+    /// matches to 1 when a marker matches at every offset.
+    pub fn score_markers(&self, buf: &[u8]) -> f32 {
+        match self.marker_lens(buf) {
+            (_, 0) => 0.0,
+            (matched, total) => matched as f32 / total as f32,
+        }
+    }
+
+    /// Returns the number of marker bytes matched by the start of a file.
+    pub fn matched_marker_len(&self, buf: &[u8]) -> usize {
+        self.marker_lens(buf).0
+    }
+
+    /// Returns the number of marker bytes matched and expected, counting the longest marker at
+    /// each offset unless one of them matches.
+    fn marker_lens(&self, buf: &[u8]) -> (usize, usize) {
+        let mut offsets: Vec<(usize, usize, bool)> = Vec::new();
+        for marker in self.markers {
+            let end = marker.offset + marker.bytes.len();
+            let matched = buf.get(marker.offset..end) == Some(marker.bytes);
+
+            match offsets.iter_mut().find(|(offset, ..)| *offset == marker.offset) {
+                Some((_, len, is_match)) if matched => {
+                    *len = marker.bytes.len();
+                    *is_match = true;
+                }
+                Some((_, len, false)) => *len = (*len).max(marker.bytes.len()),
+                Some(_) => {}
+                None => offsets.push((marker.offset, marker.bytes.len(), matched)),
+            }
+        }
+
+        let total: usize = offsets.iter().map(|(_, len, _)| len).sum();
+        let matched: usize = offsets
+            .iter()
+            .filter(|(.., is_match)| *is_match)
+            .map(|(_, len, _)| len)
+            .sum();
+
+        (matched, total)
+    }
 }
 
 impl<'a> TryFrom<&'a Path> for FormatIdentifier<'a> {
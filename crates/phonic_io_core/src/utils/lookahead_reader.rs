@@ -0,0 +1,171 @@
+use std::io::{self, Read, Seek, SeekFrom};
+use phonic_core::PhonicError;
+
+/// The least number of bytes read from the inner reader at once.
+const READ_LEN: usize = 4096;
+
+/// The distance past the bytes read so far beyond which a reader that can seek skips ahead by
+/// seeking rather than by reading.
+const SKIP_LEN: u64 = 64 * 1024;
+
+type SeekStart<T> = fn(&mut T, u64) -> io::Result<u64>;
+
+/// Reads an inner reader by offset, counted from where the inner reader was when it was wrapped.
+///
+/// The bytes read are kept until they are released, so a parser can look ahead and come back
+/// without seeking. Reading before the bytes kept fails with `Unsupported`, unless seeking has
+/// been enabled on an inner reader that can seek, which reads them again.
+pub struct LookaheadReader<T> {
+    inner: T,
+    inner_i: u64,
+    buf: Vec<u8>,
+    buf_i: u64,
+    seek: Option<(SeekStart<T>, u64)>,
+}
+
+/// Reads a [`LookaheadReader`] in order from an offset.
+pub struct OffsetReader<'a, T> {
+    reader: &'a mut LookaheadReader<T>,
+    offset: u64,
+}
+
+impl<T> LookaheadReader<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            inner_i: 0,
+            buf: Vec::new(),
+            buf_i: 0,
+            seek: None,
+        }
+    }
+
+    pub fn as_inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn as_inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Drops the bytes before an offset. Bytes before it that haven't been read yet are skipped.
+    pub fn release(&mut self, offset: u64) {
+        if offset > self.buf_i {
+            let n = (offset - self.buf_i).min(self.buf.len() as u64) as usize;
+            self.buf.drain(..n);
+            self.buf_i = offset;
+        }
+    }
+
+    fn reset(&mut self, offset: u64) {
+        self.inner_i = offset;
+        self.buf_i = offset;
+        self.buf.clear();
+    }
+}
+
+impl<T: Read> LookaheadReader<T> {
+    /// Reads the bytes at an offset into `buf`, returning how many were read, which is less than
+    /// the length of `buf` only at the end of the inner reader.
+    pub fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> Result<usize, PhonicError> {
+        let skip_i = self.inner_i.saturating_add(SKIP_LEN);
+        if offset < self.buf_i || offset > skip_i && self.seek.is_some() {
+            self.seek_to(offset)?;
+        }
+
+        self.fill(offset + buf.len() as u64)?;
+
+        let start = (offset - self.buf_i) as usize;
+        let kept = self.buf.get(start..).unwrap_or(&[]);
+        let n = kept.len().min(buf.len());
+        buf[..n].copy_from_slice(&kept[..n]);
+        Ok(n)
+    }
+
+    /// Returns a reader of the bytes from an offset on.
+    pub fn reader_at(&mut self, offset: u64) -> OffsetReader<'_, T> {
+        OffsetReader {
+            reader: self,
+            offset,
+        }
+    }
+
+    /// Reads from the inner reader until the bytes kept reach an offset, or the inner reader ends.
+    fn fill(&mut self, end: u64) -> Result<(), PhonicError> {
+        // bytes released before they were read are skipped
+        if self.inner_i < self.buf_i {
+            let n = self.buf_i - self.inner_i;
+            self.inner_i += io::copy(&mut (&mut self.inner).take(n), &mut io::sink())?;
+        }
+
+        while self.inner_i < end && self.inner_i >= self.buf_i {
+            let len = self.buf.len();
+            let n_wanted = ((end - self.inner_i) as usize).max(READ_LEN);
+            self.buf.resize(len + n_wanted, 0);
+
+            let result = self.inner.read(&mut self.buf[len..]);
+            let n = *result.as_ref().unwrap_or(&0);
+            self.buf.truncate(len + n);
+            self.inner_i += n as u64;
+
+            match result {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn seek_to(&mut self, offset: u64) -> Result<(), PhonicError> {
+        let (seek, base) = self.seek.ok_or(PhonicError::Unsupported)?;
+        seek(&mut self.inner, base + offset)?;
+        self.reset(offset);
+        Ok(())
+    }
+}
+
+impl<T: Seek> LookaheadReader<T> {
+    /// Lets the reader seek the inner reader, to read released bytes again and to skip far ahead.
+    pub fn enable_seeking(&mut self) -> Result<(), PhonicError> {
+        if self.seek.is_none() {
+            let base = self.inner.stream_position()?.saturating_sub(self.inner_i);
+            self.seek = Some((seek_start::<T>, base));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of bytes from the start of the reader to the end of the inner reader.
+    pub fn byte_len(&mut self) -> Result<u64, PhonicError> {
+        self.enable_seeking()?;
+        let (_, base) = self.seek.ok_or(PhonicError::Unreachable)?;
+
+        let end = self.inner.seek(SeekFrom::End(0))?;
+        if let Err(e) = self.inner.seek(SeekFrom::Start(base + self.inner_i)) {
+            let offset = end.saturating_sub(base);
+            self.reset(offset);
+            return Err(e.into());
+        }
+
+        Ok(end.saturating_sub(base))
+    }
+}
+
+fn seek_start<T: Seek>(inner: &mut T, position: u64) -> io::Result<u64> {
+    inner.seek(SeekFrom::Start(position))
+}
+
+impl<T: Read> Read for OffsetReader<'_, T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.reader.read_at(self.offset, buf)?;
+        self.offset += n as u64;
+        Ok(n)
+    }
+}
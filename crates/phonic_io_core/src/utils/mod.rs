@@ -1,9 +1,11 @@
 mod format_identifier;
+mod lookahead_reader;
 mod std_io_stream;
 mod stream_selector;
 mod tagged_signal;
 
 pub use format_identifier::*;
+pub use lookahead_reader::*;
 pub use std_io_stream::*;
 pub use stream_selector::*;
 pub use tagged_signal::*;
@@ -4,6 +4,20 @@ use phonic_core::PhonicError;
 
 pub struct StdIoStream<T>(T);
 
+impl<T> StdIoStream<T> {
+    pub fn new(inner: T) -> Self {
+        Self(inner)
+    }
+
+    pub fn as_inner(&self) -> &T {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
 impl<T> Read for StdIoStream<T>
 where
     T: StreamReader,
@@ -38,13 +52,15 @@ where
             }
             SeekFrom::Start(position) => {
                 self.0.set_position(position)?;
-                Ok(position)
+                self.0.position().map_err(Into::into)
             }
             SeekFrom::End(offset) => {
                 let len = self.0.spec().n_bytes().ok_or(PhonicError::MissingData)?;
-                let position = (len as i64 + offset) as u64;
+                let position = u64::try_from(len as i64 + offset)
+                    .map_err(|_| PhonicError::InvalidData)?;
+
                 self.0.set_position(position)?;
-                Ok(position)
+                self.0.position().map_err(Into::into)
             }
         }
     }
@@ -73,7 +73,7 @@ impl<T: FormatSeeker + FormatObserver> StreamSeeker for StreamSelector<T> {
     fn seek(&mut self, offset: i64) -> Result<(), PhonicError> {
         let pos = self.inner.position()?;
         self.inner.seek(FormatOffset {
-            stream_offset: pos.stream_i as isize - self.stream_i as isize,
+            stream_offset: self.stream_i as isize - pos.stream_i as isize,
             byte_offset: offset,
         })
     }
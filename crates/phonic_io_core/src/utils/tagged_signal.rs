@@ -1,19 +1,24 @@
 use crate::DynSignal;
 use phonic_core::PhonicError;
 use phonic_signal::{
-    adapters::SampleTypeAdapter, FromKnownSample, KnownSampleType, Signal, SignalReader,
-    SignalSpec, SignalWriter,
+    adapters::{Dither, DitherAdapter, SampleTypeAdapter},
+    FromKnownSample, KnownSample, KnownSampleType, Signal, SignalReader, SignalSpec, SignalWriter,
+    I24, I48, U24, U48,
 };
 
 pub enum TaggedSignal {
     I8(Box<dyn DynSignal<Sample = i8>>),
     I16(Box<dyn DynSignal<Sample = i16>>),
+    I24(Box<dyn DynSignal<Sample = I24>>),
     I32(Box<dyn DynSignal<Sample = i32>>),
+    I48(Box<dyn DynSignal<Sample = I48>>),
     I64(Box<dyn DynSignal<Sample = i64>>),
 
     U8(Box<dyn DynSignal<Sample = u8>>),
     U16(Box<dyn DynSignal<Sample = u16>>),
+    U24(Box<dyn DynSignal<Sample = U24>>),
     U32(Box<dyn DynSignal<Sample = u32>>),
+    U48(Box<dyn DynSignal<Sample = U48>>),
     U64(Box<dyn DynSignal<Sample = u64>>),
 
     F32(Box<dyn DynSignal<Sample = f32>>),
@@ -26,11 +31,15 @@ macro_rules! match_tagged_signal {
         match $signal {
             TaggedSignal::I8($inner) => $rhs,
             TaggedSignal::I16($inner) => $rhs,
+            TaggedSignal::I24($inner) => $rhs,
             TaggedSignal::I32($inner) => $rhs,
+            TaggedSignal::I48($inner) => $rhs,
             TaggedSignal::I64($inner) => $rhs,
             TaggedSignal::U8($inner) => $rhs,
             TaggedSignal::U16($inner) => $rhs,
+            TaggedSignal::U24($inner) => $rhs,
             TaggedSignal::U32($inner) => $rhs,
+            TaggedSignal::U48($inner) => $rhs,
             TaggedSignal::U64($inner) => $rhs,
             TaggedSignal::F32($inner) => $rhs,
             TaggedSignal::F64($inner) => $rhs,
@@ -62,12 +71,16 @@ macro_rules! impl_from_inner {
 impl TaggedSignal {
     impl_unwrap!(unwrap_i8_signal, i8, I8);
     impl_unwrap!(unwrap_i16_signal, i16, I16);
+    impl_unwrap!(unwrap_i24_signal, I24, I24);
     impl_unwrap!(unwrap_i32_signal, i32, I32);
+    impl_unwrap!(unwrap_i48_signal, I48, I48);
     impl_unwrap!(unwrap_i64_signal, i64, I64);
 
     impl_unwrap!(unwrap_u8_signal, u8, U8);
     impl_unwrap!(unwrap_u16_signal, u16, U16);
+    impl_unwrap!(unwrap_u24_signal, U24, U24);
     impl_unwrap!(unwrap_u32_signal, u32, U32);
+    impl_unwrap!(unwrap_u48_signal, U48, U48);
     impl_unwrap!(unwrap_u64_signal, u64, U64);
 
     impl_unwrap!(unwrap_f32_signal, f32, F32);
@@ -81,11 +94,15 @@ impl TaggedSignal {
         match self {
             TaggedSignal::I8(_) => KnownSampleType::I8,
             TaggedSignal::I16(_) => KnownSampleType::I16,
+            TaggedSignal::I24(_) => KnownSampleType::I24,
             TaggedSignal::I32(_) => KnownSampleType::I32,
+            TaggedSignal::I48(_) => KnownSampleType::I48,
             TaggedSignal::I64(_) => KnownSampleType::I64,
             TaggedSignal::U8(_) => KnownSampleType::U8,
             TaggedSignal::U16(_) => KnownSampleType::U16,
+            TaggedSignal::U24(_) => KnownSampleType::U24,
             TaggedSignal::U32(_) => KnownSampleType::U32,
+            TaggedSignal::U48(_) => KnownSampleType::U48,
             TaggedSignal::U64(_) => KnownSampleType::U64,
             TaggedSignal::F32(_) => KnownSampleType::F32,
             TaggedSignal::F64(_) => KnownSampleType::F64,
@@ -99,26 +116,41 @@ impl TaggedSignal {
         match_tagged_signal!(self, signal => Box::new(SampleTypeAdapter::new(signal)))
     }
 
+    pub fn adapt_sample_type_with_dither<S: FromKnownSample + KnownSample + 'static>(
+        self,
+        dither: Dither,
+    ) -> Box<dyn SignalReader<Sample = S>> {
+        match_tagged_signal!(self, signal => Box::new(DitherAdapter::new(signal, dither)))
+    }
+
     pub fn copy_n(&mut self, reader: Self, n: u64, adapt: bool) -> Result<(), PhonicError> {
         match (self, reader) {
             (Self::I8(w), Self::I8(mut r)) => w.copy_n(&mut r, n),
             (Self::I16(w), Self::I16(mut r)) => w.copy_n(&mut r, n),
+            (Self::I24(w), Self::I24(mut r)) => w.copy_n(&mut r, n),
             (Self::I32(w), Self::I32(mut r)) => w.copy_n(&mut r, n),
+            (Self::I48(w), Self::I48(mut r)) => w.copy_n(&mut r, n),
             (Self::I64(w), Self::I64(mut r)) => w.copy_n(&mut r, n),
             (Self::U8(w), Self::U8(mut r)) => w.copy_n(&mut r, n),
             (Self::U16(w), Self::U16(mut r)) => w.copy_n(&mut r, n),
+            (Self::U24(w), Self::U24(mut r)) => w.copy_n(&mut r, n),
             (Self::U32(w), Self::U32(mut r)) => w.copy_n(&mut r, n),
+            (Self::U48(w), Self::U48(mut r)) => w.copy_n(&mut r, n),
             (Self::U64(w), Self::U64(mut r)) => w.copy_n(&mut r, n),
             (Self::F32(w), Self::F32(mut r)) => w.copy_n(&mut r, n),
             (Self::F64(w), Self::F64(mut r)) => w.copy_n(&mut r, n),
             _ if !adapt => Err(PhonicError::SignalMismatch),
             (Self::I8(w), r) => w.copy_n(&mut r.adapt_sample_type(), n),
             (Self::I16(w), r) => w.copy_n(&mut r.adapt_sample_type(), n),
+            (Self::I24(w), r) => w.copy_n(&mut r.adapt_sample_type(), n),
             (Self::I32(w), r) => w.copy_n(&mut r.adapt_sample_type(), n),
+            (Self::I48(w), r) => w.copy_n(&mut r.adapt_sample_type(), n),
             (Self::I64(w), r) => w.copy_n(&mut r.adapt_sample_type(), n),
             (Self::U8(w), r) => w.copy_n(&mut r.adapt_sample_type(), n),
             (Self::U16(w), r) => w.copy_n(&mut r.adapt_sample_type(), n),
+            (Self::U24(w), r) => w.copy_n(&mut r.adapt_sample_type(), n),
             (Self::U32(w), r) => w.copy_n(&mut r.adapt_sample_type(), n),
+            (Self::U48(w), r) => w.copy_n(&mut r.adapt_sample_type(), n),
             (Self::U64(w), r) => w.copy_n(&mut r.adapt_sample_type(), n),
             (Self::F32(w), r) => w.copy_n(&mut r.adapt_sample_type(), n),
             (Self::F64(w), r) => w.copy_n(&mut r.adapt_sample_type(), n),
@@ -128,12 +160,16 @@ impl TaggedSignal {
 
 impl_from_inner!(i8, I8);
 impl_from_inner!(i16, I16);
+impl_from_inner!(I24, I24);
 impl_from_inner!(i32, I32);
+impl_from_inner!(I48, I48);
 impl_from_inner!(i64, I64);
 
 impl_from_inner!(u8, U8);
 impl_from_inner!(u16, U16);
+impl_from_inner!(U24, U24);
 impl_from_inner!(u32, U32);
+impl_from_inner!(U48, U48);
 impl_from_inner!(u64, U64);
 
 impl_from_inner!(f32, F32);
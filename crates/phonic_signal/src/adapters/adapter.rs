@@ -1,7 +1,11 @@
 use crate::{
-    adapters::{ChannelsAdapter, DurationAdapter, FrameRateAdapter, SampleTypeAdapter},
-    Channels, Sample, Signal,
+    adapters::{
+        ChannelMatrix, ChannelsAdapter, Dither, DitherAdapter, DurationAdapter, FrameRateAdapter,
+        ResampleQuality, SampleTypeAdapter,
+    },
+    Channels, FromSample, IntoSample, Sample, Signal, SignalReader, SignalSpec, SignalWriter,
 };
+use phonic_core::PhonicError;
 use std::time::Duration;
 
 pub trait SignalAdapter: Signal + Sized {
@@ -9,14 +13,42 @@ pub trait SignalAdapter: Signal + Sized {
         SampleTypeAdapter::new(self)
     }
 
+    fn adapt_sample_type_with_dither<S: Sample>(self, dither: Dither) -> DitherAdapter<Self, S> {
+        DitherAdapter::new(self, dither)
+    }
+
     fn adapt_frame_rate(self, frame: u32) -> FrameRateAdapter<Self> {
         FrameRateAdapter::new(self, frame)
     }
 
+    fn adapt_frame_rate_with_quality(
+        self,
+        frame_rate: u32,
+        quality: ResampleQuality,
+    ) -> FrameRateAdapter<Self> {
+        FrameRateAdapter::with_quality(self, frame_rate, quality)
+    }
+
+    fn adapt_frame_rate_with_ratio(
+        self,
+        ratio: f64,
+        quality: ResampleQuality,
+    ) -> FrameRateAdapter<Self> {
+        FrameRateAdapter::with_ratio(self, ratio, quality)
+    }
+
     fn adapt_channels(self, channels: impl Into<Channels>) -> ChannelsAdapter<Self> {
         ChannelsAdapter::new(self, channels.into())
     }
 
+    fn adapt_channels_with_matrix(
+        self,
+        channels: impl Into<Channels>,
+        matrix: ChannelMatrix,
+    ) -> ChannelsAdapter<Self> {
+        ChannelsAdapter::with_matrix(self, channels.into(), matrix)
+    }
+
     fn adapt_n_frames(self, n_frames: Option<u64>) -> DurationAdapter<Self> {
         DurationAdapter::new(self, n_frames)
     }
@@ -25,52 +57,104 @@ pub trait SignalAdapter: Signal + Sized {
         DurationAdapter::from_duration(self, duration)
     }
 
-    // fn adapt_reader_spec<S>(self, spec: &SignalSpec) -> Box<dyn SignalReader<Sample = S>>
-    // where
-    //     S: Sample + 'static,
-    //     Self: SignalReader + Sized + 'static,
-    //     Self::Sample: IntoSample<S>,
-    // {
-    //     let src_spec = *self.spec();
-    //     let mut adapter = Box::new(self.adapt_sample_type()) as Box<dyn SignalReader<Sample = S>>;
-
-    //     if src_spec.frame_rate != spec.frame_rate {
-    //         adapter = Box::new(adapter.adapt_frame_rate(spec.frame_rate));
-    //     }
-
-    //     if src_spec.channels != spec.channels {
-    //         adapter = Box::new(adapter.adapt_channels(spec.channels));
-    //     }
-
-    //     if src_spec.n_frames != spec.n_frames {
-    //         adapter = Box::new(adapter.adapt_n_frames(spec.n_frames));
-    //     }
-
-    //     adapter
-    // }
-
-    // fn adapt_writer_spec<S>(self, spec: &SignalSpec) -> Box<dyn SignalWriter<Sample = S>>
-    // where
-    //     S: Sample + IntoSample<Self::Sample> + 'static,
-    //     Self: SignalWriter + Sized + 'static,
-    // {
-    //     let src_spec = *self.spec();
-    //     let mut adapter = Box::new(self.adapt_sample_type()) as Box<dyn SignalWriter<Sample = S>>;
-
-    //     if src_spec.frame_rate != spec.frame_rate {
-    //         adapter = Box::new(adapter.adapt_frame_rate(spec.frame_rate));
-    //     }
-
-    //     if src_spec.channels != spec.channels {
-    //         adapter = Box::new(adapter.adapt_channels(spec.channels));
-    //     }
-
-    //     if src_spec.n_frames != spec.n_frames {
-    //         adapter = Box::new(adapter.adapt_n_frames(spec.n_frames));
-    //     }
-
-    //     adapter
-    // }
+    /// Builds the chain of adapters needed to read this signal as a signal of type `S`
+    /// matching `spec`. Stages that would not change the signal are skipped, except for the
+    /// sample type, which is always adapted and copies samples as they are if `S` matches.
+    fn adapt_reader_spec<S>(
+        self,
+        spec: &SignalSpec,
+    ) -> Result<Box<dyn SignalReader<Sample = S>>, PhonicError>
+    where
+        S: Sample + IntoSample<f64> + FromSample<f64> + 'static,
+        Self: SignalReader + 'static,
+        Self::Sample: IntoSample<S> + 'static,
+    {
+        let inner_spec = *self.spec();
+        validate_specs(&inner_spec, spec)?;
+
+        let mut adapter: Box<dyn SignalReader<Sample = S>> = Box::new(self.adapt_sample_type());
+
+        // channels are mixed down before resampling and mixed up after, so the resampler
+        // always runs on the smaller number of channels.
+        let downmix = spec.channels.count() < inner_spec.channels.count();
+
+        if downmix {
+            adapter = Box::new(adapter.adapt_channels(spec.channels));
+        }
+
+        if inner_spec.frame_rate != spec.frame_rate {
+            adapter = Box::new(adapter.adapt_frame_rate(spec.frame_rate));
+        }
+
+        if !downmix && inner_spec.channels != spec.channels {
+            adapter = Box::new(adapter.adapt_channels(spec.channels));
+        }
+
+        if adapter.spec().n_frames != spec.n_frames {
+            adapter = Box::new(adapter.adapt_n_frames(spec.n_frames));
+        }
+
+        Ok(adapter)
+    }
+
+    /// Builds the chain of adapters needed to write samples of type `S` matching `spec` into
+    /// this signal. Stages that would not change the signal are skipped, except for the
+    /// sample type, as with [`Self::adapt_reader_spec`].
+    fn adapt_writer_spec<S>(
+        self,
+        spec: &SignalSpec,
+    ) -> Result<Box<dyn SignalWriter<Sample = S>>, PhonicError>
+    where
+        S: Sample + IntoSample<Self::Sample> + IntoSample<f64> + FromSample<f64> + 'static,
+        Self: SignalWriter + 'static,
+        Self::Sample: 'static,
+    {
+        let inner_spec = *self.spec();
+        validate_specs(spec, &inner_spec)?;
+
+        let mut adapter: Box<dyn SignalWriter<Sample = S>> = Box::new(self.adapt_sample_type());
+
+        // writer adapters are built from the inside out, so the first stage to process the
+        // written samples is the last one added. written samples are resampled before they
+        // are mixed up to more channels and after they are mixed down to fewer.
+        let upmix = spec.channels.count() < inner_spec.channels.count();
+
+        if upmix {
+            adapter = Box::new(adapter.adapt_channels(spec.channels));
+        }
+
+        if inner_spec.frame_rate != spec.frame_rate {
+            adapter = Box::new(adapter.adapt_frame_rate(spec.frame_rate));
+        }
+
+        if !upmix && inner_spec.channels != spec.channels {
+            adapter = Box::new(adapter.adapt_channels(spec.channels));
+        }
+
+        if adapter.spec().n_frames != spec.n_frames {
+            adapter = Box::new(adapter.adapt_n_frames(spec.n_frames));
+        }
+
+        Ok(adapter)
+    }
+}
+
+/// Checks that a signal matching `src` can be adapted to one matching `dst`. Both need a frame
+/// rate and channels, and at least one source channel has to reach the destination, which
+/// isn't the case when mixing a signal of only lfe channels down to mono.
+fn validate_specs(src: &SignalSpec, dst: &SignalSpec) -> Result<(), PhonicError> {
+    for spec in [src, dst] {
+        if spec.frame_rate == 0 || spec.channels.count() == 0 {
+            return Err(PhonicError::SignalMismatch);
+        }
+    }
+
+    let matrix = ChannelMatrix::from_channels(src.channels, dst.channels);
+    if matrix.coefficients().iter().all(|gain| *gain == 0.0) {
+        return Err(PhonicError::SignalMismatch);
+    }
+
+    Ok(())
 }
 
 impl<T: Signal + Sized> SignalAdapter for T {}
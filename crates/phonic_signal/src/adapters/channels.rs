@@ -1,9 +1,303 @@
-use crate::{Channels, Signal, SignalReader, SignalSpec, SignalWriter};
+use crate::{
+    ChannelLayout, Channels, FromSample, IntoSample, Sample, Signal, SignalObserver, SignalReader,
+    SignalSeeker, SignalSpec, SignalWriter,
+};
 use phonic_core::PhonicError;
+use std::f32::consts::FRAC_1_SQRT_2;
+
+const BUF_FRAMES: usize = 256;
+
+/// A set of gain coefficients that maps each frame of a source signal onto the channels of a
+/// destination signal. Coefficients are stored row major with one row per destination channel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelMatrix {
+    n_src: usize,
+    n_dst: usize,
+    coefficients: Box<[f32]>,
+}
+
+/// The channels a source channel folds into when it is missing from the destination layout,
+/// in order of preference. LFE channels have no entry and are dropped as per ITU-R BS.775.
+fn fold_targets(channel: ChannelLayout) -> &'static [&'static [(ChannelLayout, f32)]] {
+    type L = ChannelLayout;
+    const H: f32 = FRAC_1_SQRT_2;
+
+    match channel {
+        L::FRONT_LEFT | L::FRONT_RIGHT => &[&[(L::FRONT_CENTRE, H)]],
+        L::FRONT_CENTRE => &[&[(L::FRONT_LEFT, H), (L::FRONT_RIGHT, H)]],
+        L::REAR_LEFT => &[&[(L::SIDE_LEFT, 1.0)], &[(L::FRONT_LEFT, H)]],
+        L::REAR_RIGHT => &[&[(L::SIDE_RIGHT, 1.0)], &[(L::FRONT_RIGHT, H)]],
+        L::SIDE_LEFT => &[&[(L::REAR_LEFT, 1.0)], &[(L::FRONT_LEFT, H)]],
+        L::SIDE_RIGHT => &[&[(L::REAR_RIGHT, 1.0)], &[(L::FRONT_RIGHT, H)]],
+        L::REAR_CENTRE => &[
+            &[(L::REAR_LEFT, H), (L::REAR_RIGHT, H)],
+            &[(L::SIDE_LEFT, H), (L::SIDE_RIGHT, H)],
+        ],
+        L::FRONT_LEFT_CENTRE | L::FRONT_LEFT_WIDE | L::FRONT_LEFT_HIGH | L::TOP_FRONT_LEFT => {
+            &[&[(L::FRONT_LEFT, 1.0)]]
+        }
+        L::FRONT_RIGHT_CENTRE | L::FRONT_RIGHT_WIDE | L::FRONT_RIGHT_HIGH | L::TOP_FRONT_RIGHT => {
+            &[&[(L::FRONT_RIGHT, 1.0)]]
+        }
+        L::FRONT_CENTRE_HIGH | L::TOP_FRONT_CENTRE | L::TOP_CENTRE => &[&[(L::FRONT_CENTRE, 1.0)]],
+        L::REAR_LEFT_CENTRE | L::TOP_REAR_LEFT => &[&[(L::REAR_LEFT, 1.0)]],
+        L::REAR_RIGHT_CENTRE | L::TOP_REAR_RIGHT => &[&[(L::REAR_RIGHT, 1.0)]],
+        L::TOP_REAR_CENTRE => &[&[(L::REAR_CENTRE, 1.0)]],
+        _ => &[],
+    }
+}
+
+impl ChannelMatrix {
+    pub fn new(
+        n_src: usize,
+        n_dst: usize,
+        coefficients: impl Into<Box<[f32]>>,
+    ) -> Result<Self, PhonicError> {
+        let coefficients = coefficients.into();
+        if n_src == 0 || n_dst == 0 || coefficients.len() != n_src * n_dst {
+            return Err(PhonicError::SignalMismatch);
+        }
+
+        Ok(Self {
+            n_src,
+            n_dst,
+            coefficients,
+        })
+    }
+
+    /// Builds a mixing matrix from the channel layouts of two signals. Known layouts are
+    /// remixed with ITU-R BS.775 style coefficients, mono signals are duplicated or averaged
+    /// and anything else is mapped positionally. Each destination channel is normalized to
+    /// avoid clipping.
+    pub fn from_channels(src: Channels, dst: Channels) -> Self {
+        let n_src = src.count() as usize;
+        let n_dst = dst.count() as usize;
+
+        let mut matrix = Self {
+            n_src,
+            n_dst,
+            coefficients: vec![0.0; n_src * n_dst].into_boxed_slice(),
+        };
+
+        match (src, dst) {
+            _ if n_src == 0 || n_dst == 0 => return matrix,
+            _ if n_src == 1 => matrix.upmix_mono(dst),
+            _ if n_dst == 1 => matrix.downmix_mono(src),
+            (Channels::Layout(src), Channels::Layout(dst)) => matrix.remix_layouts(src, dst),
+            _ => {
+                for i in 0..n_src.min(n_dst) {
+                    matrix.set(i, i, 1.0);
+                }
+            }
+        }
+
+        matrix.normalize();
+        matrix
+    }
+
+    pub fn n_src(&self) -> usize {
+        self.n_src
+    }
+
+    pub fn n_dst(&self) -> usize {
+        self.n_dst
+    }
+
+    pub fn coefficients(&self) -> &[f32] {
+        &self.coefficients
+    }
+
+    pub fn get(&self, src_i: usize, dst_i: usize) -> f32 {
+        self.coefficients[dst_i * self.n_src + src_i]
+    }
+
+    pub fn set(&mut self, src_i: usize, dst_i: usize, gain: f32) {
+        self.coefficients[dst_i * self.n_src + src_i] = gain;
+    }
+
+    fn upmix_mono(&mut self, dst: Channels) {
+        let layout = dst.layout().copied().unwrap_or_default();
+        if let Some(i) = layout.index_of(ChannelLayout::FRONT_CENTRE) {
+            self.set(0, i, 1.0);
+        } else if layout.contains(ChannelLayout::STEREO) {
+            for channel in ChannelLayout::STEREO.iter() {
+                self.set(0, layout.index_of(channel).unwrap(), 1.0);
+            }
+        } else {
+            for i in 0..self.n_dst.min(2) {
+                self.set(0, i, 1.0);
+            }
+        }
+    }
+
+    fn downmix_mono(&mut self, src: Channels) {
+        let lfe = ChannelLayout::LFE1 | ChannelLayout::LFE2;
+        match src.layout() {
+            Some(layout) => {
+                for (i, channel) in layout.iter().enumerate() {
+                    if !lfe.contains(channel) {
+                        self.set(i, 0, 1.0);
+                    }
+                }
+            }
+            None => {
+                for i in 0..self.n_src {
+                    self.set(i, 0, 1.0);
+                }
+            }
+        }
+    }
+
+    fn remix_layouts(&mut self, src: ChannelLayout, dst: ChannelLayout) {
+        for (src_i, channel) in src.iter().enumerate() {
+            let mut visited = ChannelLayout::default();
+            self.fold(src_i, channel, 1.0, dst, &mut visited);
+        }
+    }
+
+    fn fold(
+        &mut self,
+        src_i: usize,
+        channel: ChannelLayout,
+        gain: f32,
+        dst: ChannelLayout,
+        visited: &mut ChannelLayout,
+    ) {
+        if visited.contains(channel) {
+            return;
+        }
+
+        *visited = *visited | channel;
+        if let Some(dst_i) = dst.index_of(channel) {
+            let gain = self.get(src_i, dst_i) + gain;
+            self.set(src_i, dst_i, gain);
+            return;
+        }
+
+        let targets = fold_targets(channel);
+        let target = targets
+            .iter()
+            .find(|targets| targets.iter().all(|(c, _)| dst.contains(*c)))
+            .or(targets.first());
+
+        for (target, target_gain) in target.into_iter().flat_map(|t| t.iter()) {
+            self.fold(src_i, *target, gain * target_gain, dst, visited);
+        }
+    }
+
+    fn normalize(&mut self) {
+        for row in self.coefficients.chunks_exact_mut(self.n_src) {
+            let gain = row.iter().map(|c| c.abs()).sum::<f32>();
+            if gain > 1.0 {
+                row.iter_mut().for_each(|c| *c /= gain);
+            }
+        }
+    }
+
+    /// If every destination channel is either silent or an unscaled copy of a single source
+    /// channel, returns the source index of each destination channel.
+    fn routes(&self) -> Option<Box<[Option<usize>]>> {
+        self.coefficients
+            .chunks_exact(self.n_src)
+            .map(|row| {
+                let mut route = None;
+                for (i, c) in row.iter().enumerate() {
+                    match *c {
+                        0.0 => continue,
+                        1.0 if route.is_none() => route = Some(i),
+                        _ => return Err(()),
+                    }
+                }
+
+                Ok(route)
+            })
+            .collect::<Result<_, _>>()
+            .ok()
+    }
+}
+
+struct ChannelMixer<S: Sample> {
+    matrix: ChannelMatrix,
+    routes: Option<Box<[Option<usize>]>>,
+    input: Box<[S]>,
+    input_len: usize,
+    output: Box<[S]>,
+    output_range: (usize, usize),
+}
+
+impl<S: Sample> ChannelMixer<S> {
+    fn new(matrix: ChannelMatrix) -> Self {
+        Self {
+            routes: matrix.routes(),
+            input: vec![S::ORIGIN; BUF_FRAMES * matrix.n_src].into_boxed_slice(),
+            input_len: 0,
+            output: vec![S::ORIGIN; BUF_FRAMES * matrix.n_dst].into_boxed_slice(),
+            output_range: (0, 0),
+            matrix,
+        }
+    }
+
+    fn pending(&self) -> &[S] {
+        &self.output[self.output_range.0..self.output_range.1]
+    }
+
+    fn consume(&mut self, n: usize) {
+        self.output_range.0 += n;
+    }
+
+    fn clear(&mut self) {
+        self.input_len = 0;
+        self.output_range = (0, 0);
+    }
+
+    /// Mixes every complete frame in the input buffer into the output buffer and moves any
+    /// trailing partial frame to the front of the input buffer.
+    fn mix(&mut self)
+    where
+        S: IntoSample<f64> + FromSample<f64>,
+    {
+        let n_src = self.matrix.n_src;
+        let n_dst = self.matrix.n_dst;
+        let n_frames = self.input_len / n_src;
+
+        let src = self.input[..n_frames * n_src].chunks_exact(n_src);
+        let dst = self.output[..n_frames * n_dst].chunks_exact_mut(n_dst);
+
+        for (src_frame, dst_frame) in src.zip(dst) {
+            match &self.routes {
+                Some(routes) => {
+                    for (sample, route) in dst_frame.iter_mut().zip(routes.iter()) {
+                        *sample = route.map_or(S::ORIGIN, |i| src_frame[i]);
+                    }
+                }
+                None => {
+                    let rows = self.matrix.coefficients.chunks_exact(n_src);
+                    for (sample, row) in dst_frame.iter_mut().zip(rows) {
+                        let mixed = row
+                            .iter()
+                            .zip(src_frame)
+                            .map(|(gain, s)| *gain as f64 * IntoSample::<f64>::into_sample(*s))
+                            .sum::<f64>();
+
+                        *sample = S::from_sample(mixed);
+                    }
+                }
+            }
+        }
+
+        self.input.copy_within(n_frames * n_src..self.input_len, 0);
+
+        self.input_len -= n_frames * n_src;
+        self.output_range = (0, n_frames * n_dst);
+    }
+}
 
 pub struct ChannelsAdapter<T: Signal> {
     signal: T,
     spec: SignalSpec,
+    matrix: Option<ChannelMatrix>,
+    mixer: Option<ChannelMixer<T::Sample>>,
+    writing: bool,
 }
 
 impl<T: Signal> ChannelsAdapter<T> {
@@ -11,7 +305,51 @@ impl<T: Signal> ChannelsAdapter<T> {
         let mut spec = *signal.spec();
         spec.channels = channels;
 
-        Self { signal, spec }
+        Self {
+            signal,
+            spec,
+            matrix: None,
+            mixer: None,
+            writing: false,
+        }
+    }
+
+    /// Creates an adapter that mixes with a caller supplied matrix. When reading, the matrix
+    /// maps the inner channels onto `channels`. When writing, it maps `channels` onto the
+    /// inner channels.
+    pub fn with_matrix(signal: T, channels: Channels, matrix: ChannelMatrix) -> Self {
+        let mut adapter = Self::new(signal, channels);
+        adapter.matrix = Some(matrix);
+        adapter
+    }
+
+    pub fn as_inner(&self) -> &T {
+        &self.signal
+    }
+
+    pub fn into_inner(self) -> T {
+        self.signal
+    }
+
+    fn init_mixer(&mut self, src: Channels, dst: Channels) -> Result<(), PhonicError> {
+        if self.mixer.is_none() {
+            let matrix = self
+                .matrix
+                .take()
+                .unwrap_or_else(|| ChannelMatrix::from_channels(src, dst));
+
+            if src.count() == 0
+                || matrix.n_src != src.count() as usize
+                || matrix.n_dst != dst.count() as usize
+            {
+                self.matrix = Some(matrix);
+                return Err(PhonicError::SignalMismatch);
+            }
+
+            self.mixer = Some(ChannelMixer::new(matrix));
+        }
+
+        Ok(())
     }
 }
 
@@ -23,18 +361,147 @@ impl<T: Signal> Signal for ChannelsAdapter<T> {
     }
 }
 
-impl<T: SignalReader> SignalReader for ChannelsAdapter<T> {
+impl<T: SignalObserver> SignalObserver for ChannelsAdapter<T> {
+    fn position(&self) -> Result<u64, PhonicError> {
+        let n_inner = self.signal.spec().channels.count() as u64;
+        let n_outer = self.spec.channels.count() as u64;
+        if n_inner == 0 {
+            return Err(PhonicError::SignalMismatch);
+        }
+
+        let inner_position = self.signal.position()?;
+        let Some(mixer) = self.mixer.as_ref() else {
+            return Ok(inner_position / n_inner * n_outer);
+        };
+
+        let n_input = mixer.input_len as u64;
+        let n_pending = mixer.pending().len() as u64;
+
+        // when writing, the pending frames have already been mixed into the inner channels and
+        // the input holds a partial outer frame. when reading it is the other way around.
+        if self.writing {
+            Ok((inner_position + n_pending) / n_inner * n_outer + n_input)
+        } else {
+            Ok((inner_position - n_input) / n_inner * n_outer - n_pending)
+        }
+    }
+}
+
+impl<T> SignalReader for ChannelsAdapter<T>
+where
+    T: SignalReader,
+    T::Sample: IntoSample<f64> + FromSample<f64>,
+{
     fn read(&mut self, buffer: &mut [Self::Sample]) -> Result<usize, PhonicError> {
-        todo!()
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+
+        let src = self.signal.spec().channels;
+        let dst = self.spec.channels;
+        self.init_mixer(src, dst)?;
+
+        let mixer = self.mixer.as_mut().unwrap();
+        loop {
+            let pending = mixer.pending();
+            if !pending.is_empty() {
+                let n = pending.len().min(buffer.len());
+                buffer[..n].copy_from_slice(&pending[..n]);
+                mixer.consume(n);
+
+                return Ok(n);
+            }
+
+            let n_src = mixer.matrix.n_src;
+            let n_dst = mixer.matrix.n_dst;
+            let n_frames = buffer.len().div_ceil(n_dst).min(BUF_FRAMES);
+            let input = &mut mixer.input[mixer.input_len..n_frames * n_src];
+
+            match self.signal.read(input)? {
+                0 => return Ok(0),
+                n => mixer.input_len += n,
+            }
+
+            mixer.mix();
+        }
     }
 }
 
-impl<T: SignalWriter> SignalWriter for ChannelsAdapter<T> {
+impl<T> SignalWriter for ChannelsAdapter<T>
+where
+    T: SignalWriter,
+    T::Sample: IntoSample<f64> + FromSample<f64>,
+{
     fn write(&mut self, buffer: &[Self::Sample]) -> Result<usize, PhonicError> {
-        todo!()
+        self.writing = true;
+        let src = self.spec.channels;
+        let dst = self.signal.spec().channels;
+        self.init_mixer(src, dst)?;
+
+        let mixer = self.mixer.as_mut().unwrap();
+        while !mixer.pending().is_empty() {
+            match self.signal.write(mixer.pending())? {
+                0 => return Ok(0),
+                n => mixer.consume(n),
+            }
+        }
+
+        let n = buffer.len().min(mixer.input.len() - mixer.input_len);
+        mixer.input[mixer.input_len..mixer.input_len + n].copy_from_slice(&buffer[..n]);
+        mixer.input_len += n;
+        mixer.mix();
+
+        while !mixer.pending().is_empty() {
+            match self.signal.write(mixer.pending()) {
+                Ok(0) | Err(PhonicError::NotReady) | Err(PhonicError::Interrupted) => break,
+                Ok(n) => mixer.consume(n),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(n)
     }
 
     fn flush(&mut self) -> Result<(), PhonicError> {
-        todo!()
+        if let Some(mixer) = self.mixer.as_mut() {
+            while !mixer.pending().is_empty() {
+                match self.signal.write(mixer.pending())? {
+                    0 => return Err(PhonicError::EndOfStream),
+                    n => mixer.consume(n),
+                }
+            }
+        }
+
+        self.signal.flush()
+    }
+}
+
+impl<T: SignalSeeker + SignalObserver> SignalSeeker for ChannelsAdapter<T> {
+    fn seek(&mut self, offset: i64) -> Result<(), PhonicError> {
+        let n_inner = self.signal.spec().channels.count() as u64;
+        let n_outer = self.spec.channels.count() as u64;
+        if n_outer == 0 {
+            return Err(PhonicError::SignalMismatch);
+        }
+
+        let position = self
+            .position()?
+            .checked_add_signed(offset)
+            .ok_or(PhonicError::InvalidData)?;
+
+        // frames are mixed as a whole, so the target has to be the start of one
+        if position % n_outer != 0 {
+            return Err(PhonicError::SignalMismatch);
+        }
+
+        if let Some(mixer) = self.mixer.as_mut() {
+            if self.writing && (mixer.input_len > 0 || !mixer.pending().is_empty()) {
+                return Err(PhonicError::NotReady);
+            }
+
+            mixer.clear();
+        }
+
+        self.signal.set_position(position / n_outer * n_inner)
     }
 }
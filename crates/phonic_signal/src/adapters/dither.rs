@@ -0,0 +1,367 @@
+use crate::{
+    FromSample, IntoSample, KnownSample, KnownSampleType, Sample, Signal, SignalObserver,
+    SignalReader, SignalSeeker, SignalSpec, SignalWriter,
+};
+use phonic_core::PhonicError;
+use std::marker::PhantomData;
+
+const BUF_FRAMES: usize = 256;
+const MAX_SHAPING_ORDER: usize = 9;
+
+/// The probability distribution of the noise added to each sample before it is quantized.
+/// Amplitudes are given in units of the target's least significant bit.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DitherNoise {
+    /// No noise is added and samples are only rounded.
+    None,
+
+    /// Uniform noise spanning a single lsb.
+    Rectangular,
+
+    /// Triangular noise spanning two lsbs, the sum of two uniform values.
+    #[default]
+    Triangular,
+
+    /// Triangular noise made from the difference of successive uniform values, which moves most
+    /// of its energy to the upper end of the spectrum.
+    HighPass,
+}
+
+/// Error feedback filters used to move quantization noise away from the frequencies the ear is
+/// most sensitive to. The weighted curves are designed for signals at 44.1 or 48 kHz.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NoiseShaping {
+    #[default]
+    None,
+
+    /// A first order high-pass filter.
+    FirstOrder,
+
+    /// Lipshitz's 5 tap minimally audible curve.
+    Lipshitz,
+
+    /// Wannamaker's 9 tap F-weighted curve.
+    FWeighted,
+}
+
+/// Settings for a [`DitherAdapter`]. The same settings and seed always produce the same output.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Dither {
+    pub noise: DitherNoise,
+    pub shaping: NoiseShaping,
+    pub seed: u64,
+}
+
+/// Quantizes samples to a lower resolution sample type, adding dither noise and optionally
+/// shaping the resulting error. Conversions that would not lose precision are passed through.
+pub struct DitherAdapter<T: Signal, S: Sample> {
+    signal: T,
+    dither: Dither,
+    quantizer: Option<Quantizer>,
+    buffer: Box<[T::Sample]>,
+    pending: (usize, usize),
+    _sample: PhantomData<S>,
+}
+
+impl NoiseShaping {
+    fn coefficients(self) -> &'static [f64] {
+        match self {
+            Self::None => &[],
+            Self::FirstOrder => &[1.0],
+            Self::Lipshitz => &[2.033, -2.165, 1.959, -1.590, 0.6149],
+            Self::FWeighted => &[
+                2.412, -3.370, 3.937, -4.174, 3.353, -2.205, 1.281, -0.569, 0.0847,
+            ],
+        }
+    }
+}
+
+impl Dither {
+    pub fn new(noise: DitherNoise) -> Self {
+        Self {
+            noise,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_shaping(mut self, shaping: NoiseShaping) -> Self {
+        self.shaping = shaping;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+/// A xorshift64* generator seeded through splitmix64, so that nearby seeds are uncorrelated.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+
+        Self(z.max(1))
+    }
+
+    /// Returns a uniformly distributed value in `[0, 1)`.
+    fn next_f64(&mut self) -> f64 {
+        let mut x = self.0;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+
+        (x.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct ChannelState {
+    errors: [f64; MAX_SHAPING_ORDER],
+    error_i: usize,
+    prev_noise: f64,
+}
+
+struct Quantizer {
+    dither: Dither,
+    enabled: bool,
+    scale: f64,
+    min: f64,
+    max: f64,
+    rng: Rng,
+    channels: Box<[ChannelState]>,
+    channel_i: usize,
+}
+
+impl Quantizer {
+    fn new(dither: Dither, src: KnownSampleType, dst: KnownSampleType, n_channels: usize) -> Self {
+        let src_bits = src.byte_size() as u32 * 8;
+        let dst_bits = dst.byte_size() as u32 * 8;
+
+        // below 53 bits every quantization level is exactly representable as an f64
+        let enabled = !dst.is_float()
+            && dst_bits < f64::MANTISSA_DIGITS
+            && (src.is_float() || src_bits > dst_bits);
+
+        let scale = if enabled {
+            (1u64 << (dst_bits - 1)) as f64
+        } else {
+            1.0
+        };
+
+        Self {
+            dither,
+            enabled,
+            scale,
+            min: -scale,
+            max: scale - 1.0,
+            rng: Rng::new(dither.seed),
+            channels: vec![ChannelState::default(); n_channels].into_boxed_slice(),
+            channel_i: 0,
+        }
+    }
+
+    fn noise(&mut self) -> f64 {
+        match self.dither.noise {
+            DitherNoise::None => 0.0,
+            DitherNoise::Rectangular => self.rng.next_f64() - 0.5,
+            DitherNoise::Triangular => self.rng.next_f64() + self.rng.next_f64() - 1.0,
+            DitherNoise::HighPass => {
+                let noise = self.rng.next_f64() - 0.5;
+                let channel = &mut self.channels[self.channel_i];
+                let prev = std::mem::replace(&mut channel.prev_noise, noise);
+                noise - prev
+            }
+        }
+    }
+
+    /// Quantizes a sample in the range `[-1, 1)` and returns it in the same range.
+    fn process(&mut self, sample: f64) -> f64 {
+        let noise = self.noise();
+        let coefficients = self.dither.shaping.coefficients();
+        let channel = &mut self.channels[self.channel_i];
+
+        let mut target = sample * self.scale;
+        for (k, coefficient) in coefficients.iter().enumerate() {
+            let i = (channel.error_i + MAX_SHAPING_ORDER - 1 - k) % MAX_SHAPING_ORDER;
+            target -= coefficient * channel.errors[i];
+        }
+
+        // the error is taken before clipping so that overloads are not fed back into the signal
+        let quantized = (target + noise).round();
+        channel.errors[channel.error_i] = quantized - target;
+        channel.error_i = (channel.error_i + 1) % MAX_SHAPING_ORDER;
+
+        self.channel_i = (self.channel_i + 1) % self.channels.len();
+        quantized.clamp(self.min, self.max) / self.scale
+    }
+
+    /// Clears the error history, which has no relation to the signal after a seek, and moves
+    /// the channel index by `offset` samples.
+    fn seek(&mut self, offset: i64) {
+        let n_channels = self.channels.len() as i64;
+        self.channels.fill(ChannelState::default());
+        self.channel_i = (self.channel_i as i64 + offset).rem_euclid(n_channels) as usize;
+    }
+}
+
+impl<T: Signal, S: Sample> DitherAdapter<T, S> {
+    pub fn new(signal: T, dither: Dither) -> Self {
+        let buf_len = signal.spec().channels.count() as usize * BUF_FRAMES;
+        let buffer = vec![T::Sample::ORIGIN; buf_len].into_boxed_slice();
+
+        Self {
+            signal,
+            dither,
+            quantizer: None,
+            buffer,
+            pending: (0, 0),
+            _sample: PhantomData,
+        }
+    }
+
+    fn init_quantizer(&mut self, src: KnownSampleType, dst: KnownSampleType) {
+        if self.quantizer.is_none() {
+            let n_channels = self.signal.spec().channels.count() as usize;
+            self.quantizer = Some(Quantizer::new(self.dither, src, dst, n_channels));
+        }
+    }
+
+    pub fn dither(&self) -> &Dither {
+        &self.dither
+    }
+
+    pub fn as_inner(&self) -> &T {
+        &self.signal
+    }
+
+    pub fn into_inner(self) -> T {
+        self.signal
+    }
+}
+
+impl<T: Signal, S: Sample> Signal for DitherAdapter<T, S> {
+    type Sample = S;
+
+    fn spec(&self) -> &SignalSpec {
+        self.signal.spec()
+    }
+}
+
+impl<T: SignalObserver, S: Sample> SignalObserver for DitherAdapter<T, S> {
+    fn position(&self) -> Result<u64, PhonicError> {
+        let n_pending = self.pending.1 - self.pending.0;
+        Ok(self.signal.position()? + n_pending as u64)
+    }
+}
+
+impl<T, S> SignalReader for DitherAdapter<T, S>
+where
+    T: SignalReader,
+    T::Sample: KnownSample + IntoSample<S> + IntoSample<f64>,
+    S: KnownSample + FromSample<f64>,
+{
+    fn read(&mut self, buffer: &mut [Self::Sample]) -> Result<usize, PhonicError> {
+        self.init_quantizer(T::Sample::TYPE, S::TYPE);
+        let quantizer = self.quantizer.as_mut().unwrap();
+
+        let buf_len = buffer.len().min(self.buffer.len());
+        let n = self.signal.read(&mut self.buffer[..buf_len])?;
+
+        let inner = self.buffer[..n].iter();
+        if quantizer.enabled {
+            for (inner, outer) in inner.zip(buffer.iter_mut()) {
+                *outer = S::from_sample(quantizer.process(inner.into_sample()));
+            }
+        } else {
+            for (inner, outer) in inner.zip(buffer.iter_mut()) {
+                *outer = inner.into_sample();
+            }
+        }
+
+        Ok(n)
+    }
+}
+
+impl<T, S> SignalWriter for DitherAdapter<T, S>
+where
+    T: SignalWriter,
+    T::Sample: KnownSample + FromSample<f64>,
+    S: KnownSample + IntoSample<T::Sample> + IntoSample<f64>,
+{
+    fn write(&mut self, buffer: &[Self::Sample]) -> Result<usize, PhonicError> {
+        self.init_quantizer(S::TYPE, T::Sample::TYPE);
+
+        while self.pending.0 < self.pending.1 {
+            match self
+                .signal
+                .write(&self.buffer[self.pending.0..self.pending.1])?
+            {
+                0 => return Ok(0),
+                n => self.pending.0 += n,
+            }
+        }
+
+        let quantizer = self.quantizer.as_mut().unwrap();
+        let n = buffer.len().min(self.buffer.len());
+        let outer = buffer[..n].iter();
+        if quantizer.enabled {
+            for (outer, inner) in outer.zip(self.buffer.iter_mut()) {
+                *inner = T::Sample::from_sample(quantizer.process(outer.into_sample()));
+            }
+        } else {
+            for (outer, inner) in outer.zip(self.buffer.iter_mut()) {
+                *inner = outer.into_sample();
+            }
+        }
+
+        self.pending = (0, n);
+        while self.pending.0 < self.pending.1 {
+            match self
+                .signal
+                .write(&self.buffer[self.pending.0..self.pending.1])
+            {
+                Ok(0) | Err(PhonicError::NotReady) | Err(PhonicError::Interrupted) => break,
+                Ok(n) => self.pending.0 += n,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<(), PhonicError> {
+        while self.pending.0 < self.pending.1 {
+            match self
+                .signal
+                .write(&self.buffer[self.pending.0..self.pending.1])?
+            {
+                0 => return Err(PhonicError::EndOfStream),
+                n => self.pending.0 += n,
+            }
+        }
+
+        self.signal.flush()
+    }
+}
+
+impl<T: SignalSeeker, S: Sample> SignalSeeker for DitherAdapter<T, S> {
+    fn seek(&mut self, offset: i64) -> Result<(), PhonicError> {
+        // samples that have been accepted but not yet written have to be flushed first
+        if self.pending.0 < self.pending.1 {
+            return Err(PhonicError::NotReady);
+        }
+
+        self.signal.seek(offset)?;
+        if let Some(quantizer) = self.quantizer.as_mut() {
+            quantizer.seek(offset);
+        }
+
+        Ok(())
+    }
+}
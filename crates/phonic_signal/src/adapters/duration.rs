@@ -1,4 +1,6 @@
-use crate::{Sample, Signal, SignalReader, SignalSpec, SignalWriter};
+use crate::{
+    Sample, Signal, SignalObserver, SignalReader, SignalSeeker, SignalSpec, SignalWriter,
+};
 use std::time::Duration;
 use phonic_core::PhonicError;
 
@@ -26,6 +28,14 @@ impl<T: Signal> DurationAdapter<T> {
         let n_frames = (signal.spec().frame_rate as f64 * duration.as_secs_f64()) as u64;
         Self::new(signal, Some(n_frames))
     }
+
+    pub fn as_inner(&self) -> &T {
+        &self.signal
+    }
+
+    pub fn into_inner(self) -> T {
+        self.signal
+    }
 }
 
 impl<T: Signal> Signal for DurationAdapter<T> {
@@ -36,6 +46,12 @@ impl<T: Signal> Signal for DurationAdapter<T> {
     }
 }
 
+impl<T: Signal> SignalObserver for DurationAdapter<T> {
+    fn position(&self) -> Result<u64, PhonicError> {
+        Ok(self.i)
+    }
+}
+
 impl<T: SignalReader> SignalReader for DurationAdapter<T> {
     fn read(&mut self, buffer: &mut [Self::Sample]) -> Result<usize, PhonicError> {
         if self.spec.n_samples().is_some_and(|n| self.i >= n) {
@@ -104,3 +120,25 @@ impl<T: SignalWriter> SignalWriter for DurationAdapter<T> {
         self.signal.flush()
     }
 }
+
+impl<T: SignalSeeker + SignalObserver> SignalSeeker for DurationAdapter<T> {
+    fn seek(&mut self, offset: i64) -> Result<(), PhonicError> {
+        let position = self
+            .i
+            .checked_add_signed(offset)
+            .ok_or(PhonicError::InvalidData)?;
+
+        if self.spec.n_samples().is_some_and(|n| position > n) {
+            return Err(PhonicError::EndOfStream);
+        }
+
+        // positions past the end of the inner signal fall in the padding
+        let inner_len = self.signal.spec().n_samples();
+        let inner_position = inner_len.map_or(position, |n| position.min(n));
+        self.signal.set_position(inner_position)?;
+
+        self.i = position;
+        self.inner_consumed = inner_position < position;
+        Ok(())
+    }
+}
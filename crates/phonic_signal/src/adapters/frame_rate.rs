@@ -1,20 +1,559 @@
-use crate::{Signal, SignalReader, SignalSpec, SignalWriter};
+use crate::{
+    FromSample, IntoSample, Sample, Signal, SignalObserver, SignalReader, SignalSeeker, SignalSpec,
+    SignalWriter,
+};
 use phonic_core::PhonicError;
+use std::f64::consts::PI;
+
+const BUF_FRAMES: usize = 256;
+const COMPACT_FRAMES: usize = 4096;
+
+/// Trades conversion quality for speed when changing the frame rate of a signal.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ResampleQuality {
+    /// Linear interpolation with no anti-aliasing filter.
+    Fast,
+
+    /// A short Kaiser windowed sinc filter.
+    #[default]
+    Medium,
+
+    /// A long Kaiser windowed sinc filter with a steep transition band.
+    High,
+}
+
+struct ResampleKernel {
+    half_width: usize,
+    resolution: f64,
+    table: Box<[f64]>,
+}
+
+impl ResampleKernel {
+    /// Creates a kernel for `ratio` interpolated frames per source frame.
+    fn new(quality: ResampleQuality, ratio: f64) -> Self {
+        let (zero_crossings, resolution, beta, rolloff) = match quality {
+            ResampleQuality::Fast => return Self::linear(),
+            ResampleQuality::Medium => (8, 128, 6.0, 0.90),
+            ResampleQuality::High => (32, 512, 9.0, 0.95),
+        };
+
+        let cutoff = ratio.min(1.0) * rolloff;
+        let half_width = (zero_crossings as f64 / cutoff).ceil() as usize;
+        let resolution = resolution as f64 * cutoff;
+        let table_len = (half_width as f64 * resolution).ceil() as usize + 2;
+
+        let i0_beta = bessel_i0(beta);
+        let table = (0..table_len)
+            .map(|i| {
+                let t = i as f64 / resolution;
+                if t >= half_width as f64 {
+                    return 0.0;
+                }
+
+                let x = t / half_width as f64;
+                let window = bessel_i0(beta * (1.0 - x * x).sqrt()) / i0_beta;
+                cutoff * sinc(cutoff * t) * window
+            })
+            .collect();
+
+        Self {
+            half_width,
+            resolution,
+            table,
+        }
+    }
+
+    fn linear() -> Self {
+        Self {
+            half_width: 1,
+            resolution: 1.0,
+            table: [1.0, 0.0, 0.0].into(),
+        }
+    }
+
+    fn eval(&self, t: f64) -> f64 {
+        let x = t.abs() * self.resolution;
+        let i = x as usize;
+        if i + 1 >= self.table.len() {
+            return 0.0;
+        }
+
+        let fract = x - i as f64;
+        self.table[i] + (self.table[i + 1] - self.table[i]) * fract
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x == 0.0 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let half_x = x / 2.0;
+
+    for k in 1..64 {
+        term *= half_x / k as f64;
+        let add = term * term;
+        sum += add;
+
+        if add < sum * 1e-16 {
+            break;
+        }
+    }
+
+    sum
+}
+
+/// The read position of a [`Resampler`] between two source frames, and how far it moves for
+/// each interpolated frame.
+#[derive(Clone, Copy)]
+enum Phase {
+    /// Moves `src / dst` frames at a time, with the position kept as the exact fraction
+    /// `fract / dst` so that long streams converted between two frame rates do not drift.
+    Rational { src: u64, dst: u64, fract: u64 },
+
+    /// Moves `step` frames at a time, for ratios that aren't a fraction of two frame rates. The
+    /// position is worked out from the number `n` of frames interpolated so far, so that rounding
+    /// errors don't build up.
+    Real { step: f64, n: u64 },
+}
+
+impl Phase {
+    fn rational(src_rate: u32, dst_rate: u32) -> Self {
+        let divisor = gcd(src_rate as u64, dst_rate as u64).max(1);
+        Self::Rational {
+            src: src_rate as u64 / divisor,
+            dst: dst_rate as u64 / divisor,
+            fract: 0,
+        }
+    }
+
+    /// The number of interpolated frames per source frame.
+    fn ratio(&self) -> f64 {
+        match *self {
+            Self::Rational { src, dst, .. } => dst as f64 / src as f64,
+            Self::Real { step, .. } => 1.0 / step,
+        }
+    }
+
+    fn fract(&self) -> f64 {
+        match *self {
+            Self::Rational { dst, fract, .. } => fract as f64 / dst as f64,
+            Self::Real { step, n } => (n as f64 * step).fract(),
+        }
+    }
+
+    /// Moves on by one interpolated frame, returning the number of source frames passed.
+    fn advance(&mut self) -> usize {
+        match self {
+            Self::Rational { src, dst, fract } => {
+                *fract += *src;
+                let n = *fract / *dst;
+                *fract %= *dst;
+                n as usize
+            }
+            Self::Real { step, n } => {
+                let frame = (*n as f64 * *step) as u64;
+                *n += 1;
+                ((*n as f64 * *step) as u64 - frame) as usize
+            }
+        }
+    }
+
+    /// Returns the number of frames interpolated before source frame `frame` plus the current
+    /// fraction.
+    fn position(&self, frame: u64) -> u64 {
+        match *self {
+            Self::Rational { src, dst, fract } => {
+                ((frame as u128 * dst as u128 + fract as u128) / src as u128) as u64
+            }
+            Self::Real { n, .. } => n,
+        }
+    }
+
+    /// Moves to interpolated frame `position`, returning the source frame it falls on or after.
+    fn seek(&mut self, position: u64) -> u64 {
+        match self {
+            Self::Rational { src, dst, fract } => {
+                let src_position = position as u128 * *src as u128;
+                *fract = (src_position % *dst as u128) as u64;
+                (src_position / *dst as u128) as u64
+            }
+            Self::Real { step, n } => {
+                *n = position;
+                (position as f64 * *step) as u64
+            }
+        }
+    }
+}
+
+/// Band limited interpolation of an interleaved signal at an arbitrary ratio. Ratios of two
+/// frame rates are tracked as exact fractions so long streams do not drift, and filter taps for
+/// phases between table entries are interpolated.
+struct Resampler {
+    n_channels: usize,
+    kernel: ResampleKernel,
+    phase: Phase,
+    window: Vec<f64>,
+    window_start: i64,
+    i: usize,
+    taps: Box<[f64]>,
+    n_pushed: u64,
+    ended: bool,
+}
+
+impl Resampler {
+    fn new(n_channels: usize, phase: Phase, quality: ResampleQuality) -> Self {
+        let kernel = ResampleKernel::new(quality, phase.ratio());
+        let mut resampler = Self {
+            n_channels,
+            phase,
+            window: Vec::new(),
+            window_start: 0,
+            i: 0,
+            taps: vec![0.0; kernel.half_width * 2].into_boxed_slice(),
+            n_pushed: 0,
+            ended: false,
+            kernel,
+        };
+
+        resampler.reset(0);
+        resampler
+    }
+
+    /// The number of frames before the read position the filter needs to see.
+    fn history(&self) -> usize {
+        self.kernel.half_width - 1
+    }
+
+    /// Discards all buffered input and restarts interpolation at source frame `frame` plus the
+    /// fraction of the phase. Input has to be pushed from [`Self::first_input_frame`] onward.
+    fn reset(&mut self, frame: u64) {
+        let history = self.history() as u64;
+        let n_silent = history.saturating_sub(frame) as usize;
+
+        self.window.clear();
+        self.window.resize(n_silent * self.n_channels, 0.0);
+        self.window_start = frame as i64 - history as i64;
+        self.i = history as usize;
+        self.n_pushed = frame.saturating_sub(history);
+        self.ended = false;
+    }
+
+    fn first_input_frame(&self, frame: u64) -> u64 {
+        frame.saturating_sub(self.history() as u64)
+    }
+
+    /// Returns the number of frames that have been interpolated since the start of the source
+    /// signal.
+    fn position(&self) -> u64 {
+        self.phase
+            .position((self.window_start + self.i as i64) as u64)
+    }
+
+    fn push<S: IntoSample<f64> + Sample>(&mut self, frames: &[S]) {
+        self.window
+            .extend(frames.iter().map(|s| IntoSample::<f64>::into_sample(*s)));
+
+        self.n_pushed += (frames.len() / self.n_channels) as u64;
+    }
+
+    fn end(&mut self) {
+        self.ended = true;
+    }
+
+    fn is_finished(&self) -> bool {
+        self.ended && self.window_start + self.i as i64 >= self.n_pushed as i64
+    }
+
+    fn n_window_frames(&self) -> usize {
+        self.window.len() / self.n_channels
+    }
+
+    /// Interpolates the next frame into `frame`, returning false if more input is required.
+    fn next_frame<S: FromSample<f64> + Sample>(&mut self, frame: &mut [S]) -> bool {
+        let half_width = self.kernel.half_width;
+        if self.is_finished() {
+            return false;
+        }
+
+        if self.i + half_width >= self.n_window_frames() {
+            if !self.ended {
+                return false;
+            }
+
+            self.window
+                .resize(self.window.len() + half_width * self.n_channels, 0.0);
+        }
+
+        let fract = self.phase.fract();
+        for (k, tap) in self.taps.iter_mut().enumerate() {
+            *tap = self.kernel.eval(k as f64 + 1.0 - half_width as f64 - fract);
+        }
+
+        let start = (self.i + 1 - half_width) * self.n_channels;
+        let frames = self.window[start..].chunks_exact(self.n_channels);
+        for (c, sample) in frame.iter_mut().enumerate() {
+            let sum = frames
+                .clone()
+                .zip(self.taps.iter())
+                .map(|(f, tap)| f[c] * tap)
+                .sum::<f64>();
+
+            *sample = S::from_sample(sum);
+        }
+
+        self.i += self.phase.advance();
+
+        let n_stale = (self.i + 1 - half_width).min(self.n_window_frames());
+        if n_stale >= COMPACT_FRAMES {
+            self.window.drain(..n_stale * self.n_channels);
+            self.window_start += n_stale as i64;
+            self.i -= n_stale;
+        }
+
+        true
+    }
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+
+    a
+}
 
 pub struct FrameRateAdapter<T: Signal> {
     signal: T,
     spec: SignalSpec,
+    quality: ResampleQuality,
+    ratio: Option<f64>,
+    resampler: Option<Resampler>,
+    input: Box<[T::Sample]>,
+    input_len: usize,
+    output: Box<[T::Sample]>,
+    output_range: (usize, usize),
+    n_written: u64,
+    writing: bool,
 }
 
 impl<T: Signal> FrameRateAdapter<T> {
     pub fn new(signal: T, frame_rate: u32) -> Self {
-        let mut spec = *signal.spec();
+        Self::with_quality(signal, frame_rate, ResampleQuality::default())
+    }
+
+    pub fn with_quality(signal: T, frame_rate: u32, quality: ResampleQuality) -> Self {
+        let inner_spec = signal.spec();
+        let mut spec = *inner_spec;
         spec.frame_rate = frame_rate;
+        spec.n_frames = inner_spec
+            .n_frames
+            .map(|n| convert_n_frames(n, inner_spec.frame_rate, frame_rate));
+
+        let n_channels = spec.channels.count() as usize;
+        Self {
+            signal,
+            spec,
+            quality,
+            ratio: None,
+            resampler: None,
+            input: vec![T::Sample::ORIGIN; BUF_FRAMES * n_channels].into_boxed_slice(),
+            input_len: 0,
+            output: vec![T::Sample::ORIGIN; BUF_FRAMES * n_channels].into_boxed_slice(),
+            output_range: (0, 0),
+            n_written: 0,
+            writing: false,
+        }
+    }
+
+    /// Converts by `ratio` frames per frame of the inner signal, for ratios that aren't a
+    /// fraction of two frame rates, such as when making up for the drift between two clocks. The
+    /// frame rate of the adapter is the inner frame rate times `ratio`, rounded to a whole number.
+    pub fn with_ratio(signal: T, ratio: f64, quality: ResampleQuality) -> Self {
+        let frame_rate = (signal.spec().frame_rate as f64 * ratio).round() as u32;
+        let mut adapter = Self::with_quality(signal, frame_rate, quality);
+        adapter.ratio = Some(ratio);
+        adapter.spec.n_frames = adapter
+            .signal
+            .spec()
+            .n_frames
+            .map(|n| adapter.adapt_n_frames(n));
+
+        adapter
+    }
+
+    pub fn quality(&self) -> ResampleQuality {
+        self.quality
+    }
+
+    pub fn as_inner(&self) -> &T {
+        &self.signal
+    }
+
+    pub fn into_inner(self) -> T {
+        self.signal
+    }
+
+    /// Converts a number of frames of the inner signal to frames of the adapter.
+    fn adapt_n_frames(&self, n_frames: u64) -> u64 {
+        match self.ratio {
+            Some(ratio) => convert_n_frames_by_step(n_frames, 1.0 / ratio),
+            None => convert_n_frames(
+                n_frames,
+                self.signal.spec().frame_rate,
+                self.spec.frame_rate,
+            ),
+        }
+    }
+
+    fn init_resampler(&mut self, src_rate: u32, dst_rate: u32) -> Result<(), PhonicError> {
+        if self.resampler.is_some() {
+            return Ok(());
+        }
+
+        let n_channels = self.spec.channels.count() as usize;
+        if n_channels == 0 {
+            return Err(PhonicError::SignalMismatch);
+        }
+
+        let phase = match self.ratio {
+            None if src_rate == 0 || dst_rate == 0 => return Err(PhonicError::SignalMismatch),
+            None => Phase::rational(src_rate, dst_rate),
+            Some(ratio) if !ratio.is_finite() || ratio <= 0.0 => {
+                return Err(PhonicError::SignalMismatch)
+            }
 
-        Self { signal, spec }
+            // the ratio converts the inner signal to the adapter, so writing steps by its inverse
+            Some(ratio) if self.writing => Phase::Real { step: ratio, n: 0 },
+            Some(ratio) => Phase::Real {
+                step: 1.0 / ratio,
+                n: 0,
+            },
+        };
+
+        self.resampler = Some(Resampler::new(n_channels, phase, self.quality));
+
+        Ok(())
+    }
+
+    /// Pushes every complete frame in the input buffer into the resampler and moves any
+    /// trailing partial frame to the front of the input buffer.
+    fn push_input(&mut self)
+    where
+        T::Sample: IntoSample<f64>,
+    {
+        let resampler = self.resampler.as_mut().unwrap();
+        let n_frames = self.input_len / resampler.n_channels;
+        let n_samples = n_frames * resampler.n_channels;
+
+        resampler.push(&self.input[..n_samples]);
+        self.input.copy_within(n_samples..self.input_len, 0);
+        self.input_len -= n_samples;
+    }
+
+    /// Interpolates up to `n_frames` frames into the output buffer.
+    fn fill_output(&mut self, n_frames: usize) -> usize
+    where
+        T::Sample: FromSample<f64>,
+    {
+        let resampler = self.resampler.as_mut().unwrap();
+        let n_channels = resampler.n_channels;
+        let n_frames = n_frames.min(BUF_FRAMES);
+
+        let mut n = 0;
+        for frame in self.output[..n_frames * n_channels].chunks_exact_mut(n_channels) {
+            if !resampler.next_frame(frame) {
+                break;
+            }
+
+            n += n_channels;
+        }
+
+        self.output_range = (0, n);
+        n
+    }
+
+    fn drain_output(&mut self) -> Result<bool, PhonicError>
+    where
+        T: SignalWriter,
+    {
+        let (mut start, end) = self.output_range;
+        while start < end {
+            match self.signal.write(&self.output[start..end]) {
+                Ok(0) => return Ok(false),
+                Ok(n) => start += n,
+                Err(e) => {
+                    self.output_range.0 = start;
+                    return Err(e);
+                }
+            }
+        }
+
+        self.output_range = (0, 0);
+        Ok(true)
+    }
+
+    /// Pads the end of the written signal so the tail of the filter reaches the inner signal.
+    /// This is called automatically once `n_frames` frames have been written if the length
+    /// of the signal is known.
+    pub fn finish(&mut self) -> Result<(), PhonicError>
+    where
+        T: SignalWriter,
+        T::Sample: IntoSample<f64> + FromSample<f64>,
+    {
+        let Some(resampler) = self.resampler.as_mut() else {
+            return self.signal.flush();
+        };
+
+        resampler.end();
+        loop {
+            if !self.drain_output()? {
+                return Err(PhonicError::EndOfStream);
+            }
+
+            if self.fill_output(BUF_FRAMES) == 0 {
+                break;
+            }
+        }
+
+        self.signal.flush()
     }
 }
 
+fn convert_n_frames(n_frames: u64, src_rate: u32, dst_rate: u32) -> u64 {
+    if src_rate == 0 {
+        return n_frames;
+    }
+
+    let n = n_frames as u128 * dst_rate as u128;
+    n.div_ceil(src_rate as u128) as u64
+}
+
+/// Returns the number of frames a signal of `n_frames` frames is interpolated to when stepping
+/// through it `step` frames at a time, counted the way [`Phase::Real`] steps.
+fn convert_n_frames_by_step(n_frames: u64, step: f64) -> u64 {
+    if !step.is_finite() || step <= 0.0 {
+        return n_frames;
+    }
+
+    let mut n = (n_frames as f64 / step).ceil() as u64;
+    while n > 0 && ((n - 1) as f64 * step) as u64 >= n_frames {
+        n -= 1;
+    }
+
+    while ((n as f64 * step) as u64) < n_frames {
+        n += 1;
+    }
+
+    n
+}
+
 impl<T: Signal> Signal for FrameRateAdapter<T> {
     type Sample = T::Sample;
 
@@ -23,18 +562,155 @@ impl<T: Signal> Signal for FrameRateAdapter<T> {
     }
 }
 
-impl<T: Signal> SignalReader for FrameRateAdapter<T> {
+impl<T: SignalObserver> SignalObserver for FrameRateAdapter<T> {
+    fn position(&self) -> Result<u64, PhonicError> {
+        let n_channels = self.spec.channels.count() as u64;
+        if self.writing {
+            return Ok(self.n_written * n_channels + self.input_len as u64);
+        }
+
+        let Some(resampler) = self.resampler.as_ref() else {
+            let n_frames = self.signal.position_frames()?;
+            return Ok(self.adapt_n_frames(n_frames) * n_channels);
+        };
+
+        let n_pending = (self.output_range.1 - self.output_range.0) as u64;
+        Ok(resampler.position() * n_channels - n_pending)
+    }
+}
+
+impl<T> SignalReader for FrameRateAdapter<T>
+where
+    T: SignalReader,
+    T::Sample: IntoSample<f64> + FromSample<f64>,
+{
     fn read(&mut self, buffer: &mut [Self::Sample]) -> Result<usize, PhonicError> {
-        todo!()
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+
+        self.init_resampler(self.signal.spec().frame_rate, self.spec.frame_rate)?;
+        let n_channels = self.spec.channels.count() as usize;
+
+        loop {
+            let (start, end) = self.output_range;
+            if start < end {
+                let n = (end - start).min(buffer.len());
+                buffer[..n].copy_from_slice(&self.output[start..start + n]);
+                self.output_range.0 += n;
+
+                return Ok(n);
+            }
+
+            if self.fill_output(buffer.len().div_ceil(n_channels)) > 0 {
+                continue;
+            }
+
+            let resampler = self.resampler.as_mut().unwrap();
+            if resampler.is_finished() {
+                return Ok(0);
+            }
+
+            match self.signal.read(&mut self.input[self.input_len..])? {
+                0 => resampler.end(),
+                n => {
+                    self.input_len += n;
+                    self.push_input();
+                }
+            }
+        }
     }
 }
 
-impl<T: Signal> SignalWriter for FrameRateAdapter<T> {
+impl<T> SignalWriter for FrameRateAdapter<T>
+where
+    T: SignalWriter,
+    T::Sample: IntoSample<f64> + FromSample<f64>,
+{
     fn write(&mut self, buffer: &[Self::Sample]) -> Result<usize, PhonicError> {
-        todo!()
+        // a resampler created by a seek converts in the reading direction
+        if !self.writing {
+            self.writing = true;
+            self.resampler = None;
+        }
+
+        self.init_resampler(self.spec.frame_rate, self.signal.spec().frame_rate)?;
+        if !self.drain_output()? {
+            return Ok(0);
+        }
+
+        let n_channels = self.spec.channels.count() as usize;
+        let mut n = buffer.len().min(self.input.len() - self.input_len);
+        if let Some(n_frames) = self.spec.n_frames {
+            let remaining = n_frames.saturating_sub(self.n_written) * n_channels as u64;
+            n = n.min((remaining as usize).saturating_sub(self.input_len));
+        }
+
+        self.input[self.input_len..self.input_len + n].copy_from_slice(&buffer[..n]);
+        self.input_len += n;
+        self.n_written += (self.input_len / n_channels) as u64;
+        self.push_input();
+
+        if self.spec.n_frames.is_some_and(|n| self.n_written >= n) {
+            self.finish()?;
+            return Ok(n);
+        }
+
+        while self.fill_output(BUF_FRAMES) > 0 {
+            match self.drain_output() {
+                Ok(true) => continue,
+                Ok(false) | Err(PhonicError::NotReady) | Err(PhonicError::Interrupted) => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(n)
     }
 
     fn flush(&mut self) -> Result<(), PhonicError> {
-        todo!()
+        if !self.drain_output()? {
+            return Err(PhonicError::EndOfStream);
+        }
+
+        self.signal.flush()
+    }
+}
+
+impl<T: SignalSeeker + SignalObserver> SignalSeeker for FrameRateAdapter<T> {
+    fn seek(&mut self, offset: i64) -> Result<(), PhonicError> {
+        // the filter tail of everything written so far would have to be flushed first
+        if self.writing {
+            return Err(PhonicError::Unsupported);
+        }
+
+        let n_channels = self.spec.channels.count() as u64;
+        if n_channels == 0 {
+            return Err(PhonicError::SignalMismatch);
+        }
+
+        let position = self
+            .position()?
+            .checked_add_signed(offset)
+            .ok_or(PhonicError::InvalidData)?;
+
+        if position % n_channels != 0 {
+            return Err(PhonicError::SignalMismatch);
+        }
+
+        self.init_resampler(self.signal.spec().frame_rate, self.spec.frame_rate)?;
+        let resampler = self.resampler.as_mut().unwrap();
+
+        let mut phase = resampler.phase;
+        let frame = phase.seek(position / n_channels);
+
+        let first_frame = resampler.first_input_frame(frame);
+        self.signal.set_position(first_frame * n_channels)?;
+
+        resampler.phase = phase;
+        resampler.reset(frame);
+        self.input_len = 0;
+        self.output_range = (0, 0);
+
+        Ok(())
     }
 }
@@ -1,11 +1,13 @@
 mod adapter;
 mod channels;
+mod dither;
 mod duration;
 mod frame_rate;
 mod sample_type;
 
 pub use adapter::*;
 pub use channels::*;
+pub use dither::*;
 pub use duration::*;
 pub use frame_rate::*;
 pub use sample_type::*;
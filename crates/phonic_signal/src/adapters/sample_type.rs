@@ -1,7 +1,12 @@
-use crate::{IntoSample, Sample, Signal, SignalReader, SignalSpec, SignalWriter};
+use crate::{
+    IntoSample, Sample, Signal, SignalObserver, SignalReader, SignalSeeker, SignalSpec,
+    SignalWriter,
+};
 use std::marker::PhantomData;
 use phonic_core::PhonicError;
 
+const BUF_FRAMES: usize = 256;
+
 pub struct SampleTypeAdapter<T: Signal, S: Sample> {
     signal: T,
     buffer: Box<[T::Sample]>,
@@ -10,7 +15,7 @@ pub struct SampleTypeAdapter<T: Signal, S: Sample> {
 
 impl<T: Signal, S: Sample> SampleTypeAdapter<T, S> {
     pub fn new(signal: T) -> Self {
-        let buf_len = signal.spec().channels.count() as usize;
+        let buf_len = signal.spec().channels.count() as usize * BUF_FRAMES;
         let buffer = vec![T::Sample::ORIGIN; buf_len].into_boxed_slice();
         Self {
             signal,
@@ -28,6 +33,12 @@ impl<T: Signal, S: Sample> Signal for SampleTypeAdapter<T, S> {
     }
 }
 
+impl<T: SignalObserver, S: Sample> SignalObserver for SampleTypeAdapter<T, S> {
+    fn position(&self) -> Result<u64, PhonicError> {
+        self.signal.position()
+    }
+}
+
 impl<T, S> SignalReader for SampleTypeAdapter<T, S>
 where
     T: SignalReader,
@@ -66,3 +77,9 @@ where
         self.signal.flush()
     }
 }
+
+impl<T: SignalSeeker, S: Sample> SignalSeeker for SampleTypeAdapter<T, S> {
+    fn seek(&mut self, offset: i64) -> Result<(), PhonicError> {
+        self.signal.seek(offset)
+    }
+}
@@ -84,9 +84,35 @@ impl ChannelLayout {
     pub const SURROUND_7_1: Self =
         Self::from_bits(Self::SURROUND_5_1.mask | Self::SIDE_LEFT.mask | Self::SIDE_RIGHT.mask);
 
+    pub const fn bits(&self) -> u32 {
+        self.mask
+    }
+
     pub fn count(&self) -> u16 {
         self.mask.count_ones() as u16
     }
+
+    pub fn contains(&self, other: Self) -> bool {
+        other.mask != 0 && self.mask & other.mask == other.mask
+    }
+
+    /// Returns the interleaved index of a single channel within this layout.
+    pub fn index_of(&self, channel: Self) -> Option<usize> {
+        if channel.count() != 1 || !self.contains(channel) {
+            return None;
+        }
+
+        Some((self.mask & (channel.mask - 1)).count_ones() as usize)
+    }
+
+    /// Iterates over the individual channels of this layout in interleaved order.
+    pub fn iter(&self) -> impl Iterator<Item = Self> {
+        let mask = self.mask;
+        (0..u32::BITS)
+            .map(|i| 1 << i)
+            .filter(move |bit| mask & bit != 0)
+            .map(Self::from_bits)
+    }
 }
 
 impl BitAnd for ChannelLayout {
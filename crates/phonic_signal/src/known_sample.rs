@@ -1,21 +1,21 @@
-use crate::{FromSample, IntoSample, Sample};
-use std::{any::TypeId, mem::size_of};
+use crate::{FromSample, IntoSample, Sample, I24, I48, U24, U48};
 use phonic_core::PhonicError;
+use std::{any::TypeId, mem::size_of};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum KnownSampleType {
     I8,
     I16,
-    // I24,
+    I24,
     I32,
-    // I48,
+    I48,
     I64,
 
     U8,
     U16,
-    // U24,
+    U24,
     U32,
-    // U48,
+    U48,
     U64,
 
     F32,
@@ -30,11 +30,15 @@ pub trait FromKnownSample:
     Sample
     + FromSample<i8>
     + FromSample<i16>
+    + FromSample<I24>
     + FromSample<i32>
+    + FromSample<I48>
     + FromSample<i64>
     + FromSample<u8>
     + FromSample<u16>
+    + FromSample<U24>
     + FromSample<u32>
+    + FromSample<U48>
     + FromSample<u64>
     + FromSample<f32>
     + FromSample<f64>
@@ -45,11 +49,15 @@ pub trait IntoKnownSample:
     Sample
     + IntoSample<i8>
     + IntoSample<i16>
+    + IntoSample<I24>
     + IntoSample<i32>
+    + IntoSample<I48>
     + IntoSample<i64>
     + IntoSample<u8>
     + IntoSample<u16>
+    + IntoSample<U24>
     + IntoSample<u32>
+    + IntoSample<U48>
     + IntoSample<u64>
     + IntoSample<f32>
     + IntoSample<f64>
@@ -66,25 +74,37 @@ macro_rules! impl_known_sample {
 
 impl_known_sample!(i8, I8);
 impl_known_sample!(i16, I16);
+impl_known_sample!(I24, I24);
 impl_known_sample!(i32, I32);
+impl_known_sample!(I48, I48);
 impl_known_sample!(i64, I64);
 impl_known_sample!(u8, U8);
 impl_known_sample!(u16, U16);
+impl_known_sample!(U24, U24);
 impl_known_sample!(u32, U32);
+impl_known_sample!(U48, U48);
 impl_known_sample!(u64, U64);
 impl_known_sample!(f32, F32);
 impl_known_sample!(f64, F64);
 
 impl KnownSampleType {
+    pub fn is_float(self) -> bool {
+        matches!(self, Self::F32 | Self::F64)
+    }
+
     pub fn byte_size(self) -> usize {
         match self {
             Self::I8 => size_of::<i8>(),
             Self::I16 => size_of::<i16>(),
+            Self::I24 => size_of::<I24>(),
             Self::I32 => size_of::<i32>(),
+            Self::I48 => size_of::<I48>(),
             Self::I64 => size_of::<i64>(),
             Self::U8 => size_of::<u8>(),
             Self::U16 => size_of::<u16>(),
+            Self::U24 => size_of::<U24>(),
             Self::U32 => size_of::<u32>(),
+            Self::U48 => size_of::<U48>(),
             Self::U64 => size_of::<u64>(),
             Self::F32 => size_of::<f32>(),
             Self::F64 => size_of::<f64>(),
@@ -100,16 +120,24 @@ impl TryFrom<TypeId> for KnownSampleType {
             Ok(Self::U8)
         } else if id == TypeId::of::<u16>() {
             Ok(Self::U16)
+        } else if id == TypeId::of::<U24>() {
+            Ok(Self::U24)
         } else if id == TypeId::of::<u32>() {
             Ok(Self::U32)
+        } else if id == TypeId::of::<U48>() {
+            Ok(Self::U48)
         } else if id == TypeId::of::<u64>() {
             Ok(Self::U64)
         } else if id == TypeId::of::<i8>() {
             Ok(Self::I8)
         } else if id == TypeId::of::<i16>() {
             Ok(Self::I16)
+        } else if id == TypeId::of::<I24>() {
+            Ok(Self::I24)
         } else if id == TypeId::of::<i32>() {
             Ok(Self::I32)
+        } else if id == TypeId::of::<I48>() {
+            Ok(Self::I48)
         } else if id == TypeId::of::<i64>() {
             Ok(Self::I64)
         } else if id == TypeId::of::<f32>() {
@@ -127,11 +155,15 @@ impl From<KnownSampleType> for TypeId {
         match value {
             KnownSampleType::I8 => TypeId::of::<i8>(),
             KnownSampleType::I16 => TypeId::of::<i16>(),
+            KnownSampleType::I24 => TypeId::of::<I24>(),
             KnownSampleType::I32 => TypeId::of::<i32>(),
+            KnownSampleType::I48 => TypeId::of::<I48>(),
             KnownSampleType::I64 => TypeId::of::<i64>(),
             KnownSampleType::U8 => TypeId::of::<u8>(),
             KnownSampleType::U16 => TypeId::of::<u16>(),
+            KnownSampleType::U24 => TypeId::of::<U24>(),
             KnownSampleType::U32 => TypeId::of::<u32>(),
+            KnownSampleType::U48 => TypeId::of::<U48>(),
             KnownSampleType::U64 => TypeId::of::<u64>(),
             KnownSampleType::F32 => TypeId::of::<f32>(),
             KnownSampleType::F64 => TypeId::of::<f64>(),
@@ -143,11 +175,15 @@ impl<S> FromKnownSample for S where
     S: Sample
         + FromSample<i8>
         + FromSample<i16>
+        + FromSample<I24>
         + FromSample<i32>
+        + FromSample<I48>
         + FromSample<i64>
         + FromSample<u8>
         + FromSample<u16>
+        + FromSample<U24>
         + FromSample<u32>
+        + FromSample<U48>
         + FromSample<u64>
         + FromSample<f32>
         + FromSample<f64>
@@ -158,11 +194,15 @@ impl<S> IntoKnownSample for S where
     S: Sample
         + IntoSample<i8>
         + IntoSample<i16>
+        + IntoSample<I24>
         + IntoSample<i32>
+        + IntoSample<I48>
         + IntoSample<i64>
         + IntoSample<u8>
         + IntoSample<u16>
+        + IntoSample<U24>
         + IntoSample<u32>
+        + IntoSample<U48>
         + IntoSample<u64>
         + IntoSample<f32>
         + IntoSample<f64>
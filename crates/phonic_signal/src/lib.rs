@@ -1,11 +1,16 @@
 mod channels;
 mod known_sample;
+mod packed_sample;
 mod sample;
 mod signal;
 
 pub use channels::*;
 pub use known_sample::*;
+pub use packed_sample::*;
 pub use sample::*;
 pub use signal::*;
 
 pub mod adapters;
+
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
@@ -0,0 +1,208 @@
+use crate::{sample::float_to_int, FromSample, Sample};
+use std::{
+    cmp::Ordering,
+    fmt::{self, Debug, Formatter},
+};
+
+macro_rules! packed_sample {
+    (
+        $(#[$attr:meta])*
+        $name:ident($inner:ty, $n_bytes:literal),
+        min: $min:expr,
+        max: $max:expr,
+        origin: $origin:expr
+    ) => {
+        $(#[$attr])*
+        ///
+        /// The sample is stored as its little endian bytes without any padding, so a slice of
+        /// samples has the same layout as the packed pcm data it was decoded from.
+        #[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+        #[repr(transparent)]
+        pub struct $name([u8; $n_bytes]);
+
+        impl $name {
+            pub const BITS: u32 = $n_bytes * 8;
+            pub const MIN: Self = Self::new($min);
+            pub const MAX: Self = Self::new($max);
+
+            const SHIFT: u32 = <$inner>::BITS - Self::BITS;
+
+            /// Creates a sample from the low bits of `value`, discarding the rest.
+            pub const fn new(value: $inner) -> Self {
+                let src = value.to_le_bytes();
+                let mut bytes = [0; $n_bytes];
+                let mut i = 0;
+                while i < $n_bytes {
+                    bytes[i] = src[i];
+                    i += 1;
+                }
+
+                Self(bytes)
+            }
+
+            /// Returns the value of the sample, sign extended where applicable.
+            pub const fn get(self) -> $inner {
+                let mut bytes = [0; <$inner>::BITS as usize / 8];
+                let mut i = 0;
+                while i < $n_bytes {
+                    bytes[i] = self.0[i];
+                    i += 1;
+                }
+
+                (<$inner>::from_le_bytes(bytes) << Self::SHIFT) >> Self::SHIFT
+            }
+
+            pub const fn from_le_bytes(bytes: [u8; $n_bytes]) -> Self {
+                Self(bytes)
+            }
+
+            pub const fn to_le_bytes(self) -> [u8; $n_bytes] {
+                self.0
+            }
+
+            pub const fn from_be_bytes(mut bytes: [u8; $n_bytes]) -> Self {
+                let mut i = 0;
+                while i < $n_bytes / 2 {
+                    let tmp = bytes[i];
+                    bytes[i] = bytes[$n_bytes - 1 - i];
+                    bytes[$n_bytes - 1 - i] = tmp;
+                    i += 1;
+                }
+
+                Self(bytes)
+            }
+
+            pub const fn to_be_bytes(self) -> [u8; $n_bytes] {
+                Self::from_be_bytes(self.0).0
+            }
+        }
+
+        impl Sample for $name {
+            const ORIGIN: Self = Self::new($origin);
+            const RANGE: (Self, Self) = (Self::MIN, Self::MAX);
+        }
+
+        impl PartialOrd for $name {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $name {
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.get().cmp(&other.get())
+            }
+        }
+
+        impl Debug for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                Debug::fmt(&self.get(), f)
+            }
+        }
+
+        impl From<$name> for $inner {
+            fn from(sample: $name) -> Self {
+                sample.get()
+            }
+        }
+
+        impl_packed_convert!($name, $inner, i8, i16, i32, i64, u8, u16, u32, u64);
+        impl_packed_float_convert!($name, $inner, f32, f64);
+    };
+}
+
+// packed samples convert through the primitive they are stored in, with the value aligned to the
+// most significant bits.
+macro_rules! impl_packed_convert {
+    ($packed:ty, $inner:ty, $($other:ty),*) => {
+        $(
+            impl FromSample<$other> for $packed {
+                #[inline]
+                fn from_sample(s: $other) -> Self {
+                    Self::new(<$inner>::from_sample(s) >> Self::SHIFT)
+                }
+            }
+
+            impl FromSample<$packed> for $other {
+                #[inline]
+                fn from_sample(s: $packed) -> Self {
+                    Self::from_sample(s.get() << <$packed>::SHIFT)
+                }
+            }
+        )*
+    };
+}
+
+// floats are rounded to the width of the packed sample itself, as rounding them to the primitive
+// first and then shifting would truncate the low bits.
+macro_rules! impl_packed_float_convert {
+    ($packed:ty, $inner:ty, $($float:ty),*) => {
+        $(
+            impl FromSample<$float> for $packed {
+                #[inline]
+                fn from_sample(s: $float) -> Self {
+                    let origin = Self::ORIGIN.get() as f64;
+                    let max = Self::MAX.get() as f64;
+                    Self::new(float_to_int(s as f64, Self::BITS, origin, max) as $inner)
+                }
+            }
+
+            impl FromSample<$packed> for $float {
+                #[inline]
+                fn from_sample(s: $packed) -> Self {
+                    Self::from_sample(s.get() << <$packed>::SHIFT)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_packed_to_packed {
+    ($from:ty, $($to:ty),*) => {
+        $(
+            impl FromSample<$from> for $to {
+                #[inline]
+                fn from_sample(s: $from) -> Self {
+                    Self::from_sample(s.get() << <$from>::SHIFT)
+                }
+            }
+        )*
+    };
+}
+
+packed_sample!(
+    /// A signed 24 bit integer sample.
+    I24(i32, 3),
+    min: -(1 << 23),
+    max: (1 << 23) - 1,
+    origin: 0
+);
+
+packed_sample!(
+    /// A signed 48 bit integer sample.
+    I48(i64, 6),
+    min: -(1 << 47),
+    max: (1 << 47) - 1,
+    origin: 0
+);
+
+packed_sample!(
+    /// An unsigned 24 bit integer sample.
+    U24(u32, 3),
+    min: 0,
+    max: (1 << 24) - 1,
+    origin: 1 << 23
+);
+
+packed_sample!(
+    /// An unsigned 48 bit integer sample.
+    U48(u64, 6),
+    min: 0,
+    max: (1 << 48) - 1,
+    origin: 1 << 47
+);
+
+impl_packed_to_packed!(I24, I24, I48, U24, U48);
+impl_packed_to_packed!(I48, I24, I48, U24, U48);
+impl_packed_to_packed!(U24, I24, I48, U24, U48);
+impl_packed_to_packed!(U48, I24, I48, U24, U48);
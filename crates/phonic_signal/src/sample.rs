@@ -28,7 +28,7 @@ macro_rules! impl_int_sample {
 macro_rules! impl_uint_sample {
     ($s:ty, $t:ident) => {
         impl Sample for $s {
-            const ORIGIN: Self = Self::MAX / 2;
+            const ORIGIN: Self = Self::MAX / 2 + 1;
             const RANGE: (Self, Self) = (Self::MIN, Self::MAX);
         }
     };
@@ -82,16 +82,42 @@ macro_rules! impl_convert {
     };
 }
 
+/// Maps a float sample onto an integer sample of `bits` bits centred on `origin`, rounding to
+/// the nearest integer and halfway values away from the origin. +1.0 lands one past the end of
+/// the range and is pulled back to `max`, except for 64 bit integers, whose maximum isn't a
+/// float and is reached by the saturating cast to the integer type instead.
+#[inline]
+pub(crate) fn float_to_int(s: f64, bits: u32, origin: f64, max: f64) -> f64 {
+    let scale = (1u64 << (bits - 1)) as f64;
+    let s = origin + (s.clamped() * scale).round();
+    if s > max {
+        max
+    } else {
+        s
+    }
+}
+
+macro_rules! impl_float_to_int {
+    ($from:ty => $($to:ty),*) => {
+        $(
+            impl_convert!($from, $to, s, {
+                let origin = <$to as Sample>::ORIGIN as f64;
+                float_to_int(s as f64, <$to>::BITS, origin, <$to>::MAX as f64) as $to
+            });
+        )*
+    };
+}
+
 // u8 to ...
 impl_convert!(u8, u8, s, s);
 impl_convert!(u8, u16, s, (s as u16) << 8);
 impl_convert!(u8, u32, s, (s as u32) << 24);
-impl_convert!(u8, u64, s, todo!());
+impl_convert!(u8, u64, s, (s as u64) << 56);
 
 impl_convert!(u8, i8, s, s.wrapping_sub(0x80) as i8);
 impl_convert!(u8, i16, s, ((s.wrapping_sub(0x80) as i8) as i16) << 8);
 impl_convert!(u8, i32, s, ((s.wrapping_sub(0x80) as i8) as i32) << 24);
-impl_convert!(u8, i64, s, todo!());
+impl_convert!(u8, i64, s, ((s.wrapping_sub(0x80) as i8) as i64) << 56);
 
 impl_convert!(u8, f32, s, ((s as f32) / 128.0) - 1.0);
 impl_convert!(u8, f64, s, ((s as f64) / 128.0) - 1.0);
@@ -100,12 +126,12 @@ impl_convert!(u8, f64, s, ((s as f64) / 128.0) - 1.0);
 impl_convert!(u16, u8, s, (s >> 8) as u8);
 impl_convert!(u16, u16, s, s);
 impl_convert!(u16, u32, s, (s as u32) << 16);
-impl_convert!(u16, u64, s, todo!());
+impl_convert!(u16, u64, s, (s as u64) << 48);
 
 impl_convert!(u16, i8, s, (s.wrapping_sub(0x8000) >> 8) as i8);
 impl_convert!(u16, i16, s, s.wrapping_sub(0x8000) as i16);
 impl_convert!(u16, i32, s, ((s.wrapping_sub(0x8000) as i16) as i32) << 16);
-impl_convert!(u16, i64, s, todo!());
+impl_convert!(u16, i64, s, ((s.wrapping_sub(0x8000) as i16) as i64) << 48);
 
 impl_convert!(u16, f32, s, ((s as f32) / 32_768.0) - 1.0);
 impl_convert!(u16, f64, s, ((s as f64) / 32_768.0) - 1.0);
@@ -114,29 +140,59 @@ impl_convert!(u16, f64, s, ((s as f64) / 32_768.0) - 1.0);
 impl_convert!(u32, u8, s, (s >> 24) as u8);
 impl_convert!(u32, u16, s, (s >> 16) as u16);
 impl_convert!(u32, u32, s, s);
-impl_convert!(u32, u64, s, todo!());
+impl_convert!(u32, u64, s, (s as u64) << 32);
 
 impl_convert!(u32, i8, s, (s.wrapping_sub(0x8000_0000) >> 24) as i8);
 impl_convert!(u32, i16, s, (s.wrapping_sub(0x8000_0000) >> 16) as i16);
 impl_convert!(u32, i32, s, s.wrapping_sub(0x8000_0000) as i32);
-impl_convert!(u32, i64, s, todo!());
+impl_convert!(
+    u32,
+    i64,
+    s,
+    ((s.wrapping_sub(0x8000_0000) as i32) as i64) << 32
+);
 
 impl_convert!(u32, f32, s, (((s as f64) / 2_147_483_648.0) - 1.0) as f32);
 impl_convert!(u32, f64, s, ((s as f64) / 2_147_483_648.0) - 1.0);
 
 // u64 to ...
-impl_convert!(u64, u8, s, todo!());
-impl_convert!(u64, u16, s, todo!());
-impl_convert!(u64, u32, s, todo!());
+impl_convert!(u64, u8, s, (s >> 56) as u8);
+impl_convert!(u64, u16, s, (s >> 48) as u16);
+impl_convert!(u64, u32, s, (s >> 32) as u32);
 impl_convert!(u64, u64, s, s);
 
-impl_convert!(u64, i8, s, todo!());
-impl_convert!(u64, i16, s, todo!());
-impl_convert!(u64, i32, s, todo!());
-impl_convert!(u64, i64, s, todo!());
+impl_convert!(
+    u64,
+    i8,
+    s,
+    (s.wrapping_sub(0x8000_0000_0000_0000) >> 56) as i8
+);
+impl_convert!(
+    u64,
+    i16,
+    s,
+    (s.wrapping_sub(0x8000_0000_0000_0000) >> 48) as i16
+);
+impl_convert!(
+    u64,
+    i32,
+    s,
+    (s.wrapping_sub(0x8000_0000_0000_0000) >> 32) as i32
+);
+impl_convert!(u64, i64, s, s.wrapping_sub(0x8000_0000_0000_0000) as i64);
 
-impl_convert!(u64, f32, s, todo!());
-impl_convert!(u64, f64, s, todo!());
+impl_convert!(
+    u64,
+    f32,
+    s,
+    (((s as f64) / 9_223_372_036_854_775_808.0) - 1.0) as f32
+);
+impl_convert!(
+    u64,
+    f64,
+    s,
+    ((s as f64) / 9_223_372_036_854_775_808.0) - 1.0
+);
 
 // i8 to ...
 #[inline(always)]
@@ -147,12 +203,12 @@ fn i8_to_u8(s: i8) -> u8 {
 impl_convert!(i8, u8, s, i8_to_u8(s));
 impl_convert!(i8, u16, s, (i8_to_u8(s) as u16) << 8);
 impl_convert!(i8, u32, s, (i8_to_u8(s) as u32) << 24);
-impl_convert!(i8, u64, s, todo!());
+impl_convert!(i8, u64, s, (i8_to_u8(s) as u64) << 56);
 
 impl_convert!(i8, i8, s, s);
 impl_convert!(i8, i16, s, (s as i16) << 8);
 impl_convert!(i8, i32, s, (s as i32) << 24);
-impl_convert!(i8, i64, s, todo!());
+impl_convert!(i8, i64, s, (s as i64) << 56);
 
 impl_convert!(i8, f32, s, s as f32 / 128.0);
 impl_convert!(i8, f64, s, s as f64 / 128.0);
@@ -166,12 +222,12 @@ fn i16_to_u16(s: i16) -> u16 {
 impl_convert!(i16, u8, s, (i16_to_u16(s) >> 8) as u8);
 impl_convert!(i16, u16, s, i16_to_u16(s));
 impl_convert!(i16, u32, s, (i16_to_u16(s) as u32) << 16);
-impl_convert!(i16, u64, s, todo!());
+impl_convert!(i16, u64, s, (i16_to_u16(s) as u64) << 48);
 
 impl_convert!(i16, i8, s, (s >> 8) as i8);
 impl_convert!(i16, i16, s, s);
 impl_convert!(i16, i32, s, (s as i32) << 16);
-impl_convert!(i16, i64, s, todo!());
+impl_convert!(i16, i64, s, (s as i64) << 48);
 
 impl_convert!(i16, f32, s, s as f32 / 32_768.0);
 impl_convert!(i16, f64, s, s as f64 / 32_768.0);
@@ -185,79 +241,43 @@ fn i32_to_u32(s: i32) -> u32 {
 impl_convert!(i32, u8, s, (i32_to_u32(s) >> 24) as u8);
 impl_convert!(i32, u16, s, (i32_to_u32(s) >> 16) as u16);
 impl_convert!(i32, u32, s, i32_to_u32(s));
-impl_convert!(i32, u64, s, todo!());
+impl_convert!(i32, u64, s, (i32_to_u32(s) as u64) << 32);
 
 impl_convert!(i32, i8, s, (s >> 24) as i8);
 impl_convert!(i32, i16, s, (s >> 16) as i16);
 impl_convert!(i32, i32, s, s);
-impl_convert!(i32, i64, s, todo!());
+impl_convert!(i32, i64, s, (s as i64) << 32);
 
 impl_convert!(i32, f32, s, (s as f64 / 2_147_483_648.0) as f32);
 impl_convert!(i32, f64, s, s as f64 / 2_147_483_648.0);
 
 // i64 to ...
-impl_convert!(i64, u8, s, todo!());
-impl_convert!(i64, u16, s, todo!());
-impl_convert!(i64, u32, s, todo!());
-impl_convert!(i64, u64, s, todo!());
-
-impl_convert!(i64, i8, s, todo!());
-impl_convert!(i64, i16, s, todo!());
-impl_convert!(i64, i32, s, todo!());
+#[inline(always)]
+fn i64_to_u64(s: i64) -> u64 {
+    (s as u64).wrapping_add(0x8000_0000_0000_0000)
+}
+
+impl_convert!(i64, u8, s, (i64_to_u64(s) >> 56) as u8);
+impl_convert!(i64, u16, s, (i64_to_u64(s) >> 48) as u16);
+impl_convert!(i64, u32, s, (i64_to_u64(s) >> 32) as u32);
+impl_convert!(i64, u64, s, i64_to_u64(s));
+
+impl_convert!(i64, i8, s, (s >> 56) as i8);
+impl_convert!(i64, i16, s, (s >> 48) as i16);
+impl_convert!(i64, i32, s, (s >> 32) as i32);
 impl_convert!(i64, i64, s, s);
 
-impl_convert!(i64, f32, s, todo!());
-impl_convert!(i64, f64, s, todo!());
+impl_convert!(i64, f32, s, (s as f64 / 9_223_372_036_854_775_808.0) as f32);
+impl_convert!(i64, f64, s, s as f64 / 9_223_372_036_854_775_808.0);
 
 // f32 to ...
-impl_convert!(f32, u8, s, ((s.clamped() + 1.0) * 128.0) as u8);
-impl_convert!(f32, u16, s, ((s.clamped() + 1.0) * 32_768.0) as u16);
-impl_convert!(
-    f32,
-    u32,
-    s,
-    ((s.clamped() + 1.0) as f64 * 2_147_483_648.0) as u32
-);
-impl_convert!(
-    f32,
-    u64,
-    s,
-    (s.clamped() as f64 * 9_223_372_036_854_775_808.0) as u64
-);
-
-impl_convert!(f32, i8, s, (s.clamped() * 128.0) as i8);
-impl_convert!(f32, i16, s, (s.clamped() * 32_768.0) as i16);
-impl_convert!(f32, i32, s, (s.clamped() as f64 * 2_147_483_648.0) as i32);
-impl_convert!(
-    f32,
-    i64,
-    s,
-    (s.clamped() as f64 * 9_223_372_036_854_775_808.0) as i64
-);
+impl_float_to_int!(f32 => u8, u16, u32, u64, i8, i16, i32, i64);
 
 impl_convert!(f32, f32, s, s);
 impl_convert!(f32, f64, s, s as f64);
 
 // f64 to ...
-impl_convert!(f64, u8, s, ((s.clamped() + 1.0) * 128.0) as u8);
-impl_convert!(f64, u16, s, ((s.clamped() + 1.0) * 32_768.0) as u16);
-impl_convert!(f64, u32, s, ((s.clamped() + 1.0) * 2_147_483_648.0) as u32);
-impl_convert!(
-    f64,
-    u64,
-    s,
-    ((s.clamped() + 1.0) * 9_223_372_036_854_775_808.0) as u64
-);
-
-impl_convert!(f64, i8, s, (s.clamped() * 128.0) as i8);
-impl_convert!(f64, i16, s, (s.clamped() * 32_768.0) as i16);
-impl_convert!(f64, i32, s, (s.clamped() * 2_147_483_648.0) as i32);
-impl_convert!(
-    f64,
-    i64,
-    s,
-    (s.clamped() * 9_223_372_036_854_775_808.0) as i64
-);
+impl_float_to_int!(f64 => u8, u16, u32, u64, i8, i16, i32, i64);
 
 impl_convert!(f64, f32, s, s as f32);
 impl_convert!(f64, f64, s, s);
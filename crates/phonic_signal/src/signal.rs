@@ -5,6 +5,8 @@ use std::{
 };
 use phonic_core::PhonicError;
 
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
 /// A set of parameters that describes an interleaved pcm signal
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct SignalSpec {
@@ -43,6 +45,26 @@ impl SignalSpec {
             .map(|n| Duration::from_secs_f64(n as f64 / self.frame_rate as f64))
     }
 
+    /// Returns the index of the frame playing at `time`, rounded down.
+    pub fn frame_at(&self, time: Duration) -> u64 {
+        let n = time.as_nanos() * self.frame_rate as u128 / NANOS_PER_SEC;
+        n.try_into().unwrap_or(u64::MAX)
+    }
+
+    /// Returns the time at which the frame at index `frame` starts playing, rounded up to the
+    /// nearest nanosecond so that it maps back onto the same frame.
+    pub fn frame_time(&self, frame: u64) -> Duration {
+        if self.frame_rate == 0 {
+            return Duration::ZERO;
+        }
+
+        let nanos = (frame as u128 * NANOS_PER_SEC).div_ceil(self.frame_rate as u128);
+        Duration::new(
+            (nanos / NANOS_PER_SEC) as u64,
+            (nanos % NANOS_PER_SEC) as u32,
+        )
+    }
+
     pub fn merge(mut self, other: &Self) -> Result<Self, PhonicError> {
         if self.frame_rate != other.frame_rate || self.channels.count() != other.channels.count() {
             return Err(PhonicError::SignalMismatch);
@@ -203,7 +225,23 @@ pub trait Signal {
 }
 
 pub trait SignalObserver: Signal {
+    /// Returns the number of samples, counted across all channels, between the start of the
+    /// signal and the next sample to be read or written.
     fn position(&self) -> Result<u64, PhonicError>;
+
+    /// Returns the index of the frame the signal is positioned in.
+    fn position_frames(&self) -> Result<u64, PhonicError> {
+        let n_channels = self.spec().channels.count() as u64;
+        if n_channels == 0 {
+            return Err(PhonicError::SignalMismatch);
+        }
+
+        Ok(self.position()? / n_channels)
+    }
+
+    fn position_time(&self) -> Result<Duration, PhonicError> {
+        Ok(self.spec().frame_time(self.position_frames()?))
+    }
 }
 
 pub trait SignalReader: Signal {
@@ -316,13 +354,55 @@ pub trait SignalWriter: Signal {
 }
 
 pub trait SignalSeeker: Signal {
+    /// Moves the signal by `offset` samples, counted across all channels, relative to its
+    /// current position.
     fn seek(&mut self, offset: i64) -> Result<(), PhonicError>;
 
     fn set_position(&mut self, position: u64) -> Result<(), PhonicError>
     where
-        Self: SignalObserver,
+        Self: Sized + SignalObserver,
+    {
+        self.seek(position as i64 - self.position()? as i64)
+    }
+
+    /// Moves the signal to the start of the frame at index `frame`. Seeking past the last frame
+    /// of a signal with a known length returns [`PhonicError::EndOfStream`].
+    fn set_position_frames(&mut self, frame: u64) -> Result<(), PhonicError>
+    where
+        Self: Sized + SignalObserver,
+    {
+        let spec = self.spec();
+        if spec.n_frames.is_some_and(|n| frame > n) {
+            return Err(PhonicError::EndOfStream);
+        }
+
+        let position = frame
+            .checked_mul(spec.channels.count() as u64)
+            .ok_or(PhonicError::InvalidData)?;
+
+        self.set_position(position)
+    }
+
+    /// Moves the signal by `offset` frames relative to the start of the current frame.
+    fn seek_frames(&mut self, offset: i64) -> Result<(), PhonicError>
+    where
+        Self: Sized + SignalObserver,
+    {
+        let frame = self
+            .position_frames()?
+            .checked_add_signed(offset)
+            .ok_or(PhonicError::InvalidData)?;
+
+        self.set_position_frames(frame)
+    }
+
+    /// Moves the signal to the frame playing at `time`.
+    fn seek_to_time(&mut self, time: Duration) -> Result<(), PhonicError>
+    where
+        Self: Sized + SignalObserver,
     {
-        self.seek(self.position()? as i64 - position as i64)
+        let frame = self.spec().frame_at(time);
+        self.set_position_frames(frame)
     }
 }
 
@@ -0,0 +1,98 @@
+use crate::{
+    Channels, Sample, Signal, SignalObserver, SignalReader, SignalSeeker, SignalSpec, SignalWriter,
+};
+use phonic_core::PhonicError;
+
+/// A signal held in memory, for testing adapters and codecs against. Samples are read from and
+/// written over the buffer at the current position.
+#[derive(Debug, Clone)]
+pub struct BufferSignal<S> {
+    spec: SignalSpec,
+    samples: Vec<S>,
+    i: usize,
+    chunk_len: usize,
+}
+
+impl<S: Sample> BufferSignal<S> {
+    pub fn new(frame_rate: u32, channels: impl Into<Channels>, samples: Vec<S>) -> Self {
+        Self {
+            spec: SignalSpec {
+                frame_rate,
+                channels: channels.into(),
+                n_frames: None,
+            },
+            samples,
+            i: 0,
+            chunk_len: usize::MAX,
+        }
+    }
+
+    pub fn with_n_frames(mut self, n_frames: Option<u64>) -> Self {
+        self.spec.n_frames = n_frames;
+        self
+    }
+
+    /// Limits the number of samples each read or write call moves, so that callers are made to
+    /// handle partial reads and writes.
+    pub fn with_chunk_len(mut self, chunk_len: usize) -> Self {
+        self.chunk_len = chunk_len;
+        self
+    }
+
+    pub fn samples(&self) -> &[S] {
+        &self.samples
+    }
+
+    pub fn into_samples(self) -> Vec<S> {
+        self.samples
+    }
+}
+
+impl<S: Sample> Signal for BufferSignal<S> {
+    type Sample = S;
+
+    fn spec(&self) -> &SignalSpec {
+        &self.spec
+    }
+}
+
+impl<S: Sample> SignalObserver for BufferSignal<S> {
+    fn position(&self) -> Result<u64, PhonicError> {
+        Ok(self.i as u64)
+    }
+}
+
+impl<S: Sample> SignalReader for BufferSignal<S> {
+    fn read(&mut self, buf: &mut [S]) -> Result<usize, PhonicError> {
+        let n = buf.len().min(self.samples.len() - self.i).min(self.chunk_len);
+        buf[..n].copy_from_slice(&self.samples[self.i..self.i + n]);
+        self.i += n;
+        Ok(n)
+    }
+}
+
+impl<S: Sample> SignalWriter for BufferSignal<S> {
+    fn write(&mut self, buf: &[S]) -> Result<usize, PhonicError> {
+        let n = buf.len().min(self.chunk_len);
+        self.samples.truncate(self.i);
+        self.samples.extend_from_slice(&buf[..n]);
+        self.i += n;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<(), PhonicError> {
+        Ok(())
+    }
+}
+
+impl<S: Sample> SignalSeeker for BufferSignal<S> {
+    fn seek(&mut self, offset: i64) -> Result<(), PhonicError> {
+        match self.i.checked_add_signed(offset as isize) {
+            Some(i) if i <= self.samples.len() => {
+                self.i = i;
+                Ok(())
+            }
+            _ => Err(PhonicError::InvalidData),
+        }
+    }
+}
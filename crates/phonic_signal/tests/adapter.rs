@@ -0,0 +1,135 @@
+use phonic_core::PhonicError;
+use phonic_signal::{
+    adapters::SignalAdapter, test_utils::BufferSignal, ChannelLayout, Channels, Sample, Signal,
+    SignalReader, SignalSpec, SignalWriter,
+};
+use std::{cell::RefCell, rc::Rc};
+
+fn spec(frame_rate: u32, channels: impl Into<Channels>, n_frames: u64) -> SignalSpec {
+    SignalSpec {
+        frame_rate,
+        channels: channels.into(),
+        n_frames: Some(n_frames),
+    }
+}
+
+fn read_to_end<T: SignalReader + ?Sized>(signal: &mut T) -> Vec<T::Sample> {
+    let mut output = Vec::new();
+    let mut buf = [T::Sample::ORIGIN; 64];
+    loop {
+        match signal.read(&mut buf).unwrap() {
+            0 => return output,
+            n => output.extend_from_slice(&buf[..n]),
+        }
+    }
+}
+
+/// A signal that can still be looked at once it has been boxed inside an adapter chain.
+struct SharedSignal(Rc<RefCell<BufferSignal<i16>>>, SignalSpec);
+
+impl SharedSignal {
+    fn new(inner: Rc<RefCell<BufferSignal<i16>>>) -> Self {
+        let spec = *inner.borrow().spec();
+        Self(inner, spec)
+    }
+}
+
+impl Signal for SharedSignal {
+    type Sample = i16;
+
+    fn spec(&self) -> &SignalSpec {
+        &self.1
+    }
+}
+
+impl SignalWriter for SharedSignal {
+    fn write(&mut self, buf: &[i16]) -> Result<usize, PhonicError> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> Result<(), PhonicError> {
+        self.0.borrow_mut().flush()
+    }
+}
+
+fn buffer<S: Sample>(frame_rate: u32, n_channels: u16, samples: Vec<S>) -> BufferSignal<S> {
+    let n_frames = samples.len() as u64 / n_channels as u64;
+    BufferSignal::new(frame_rate, n_channels, samples)
+        .with_n_frames(Some(n_frames))
+        .with_chunk_len(37)
+}
+
+#[test]
+fn readers_are_adapted_to_a_spec() {
+    // a constant signal keeps its level through the mixer and the resampler
+    let input = [0.5f32, 0.25].repeat(4_410);
+    let mut reader = buffer(44_100, 2, input)
+        .adapt_reader_spec::<i16>(&spec(48_000, 1, 4_000))
+        .unwrap();
+
+    assert_eq!(*reader.spec(), spec(48_000, 1, 4_000));
+    let output = read_to_end(&mut reader);
+    assert_eq!(output.len(), 4_000);
+    assert!(output[100..3_900]
+        .iter()
+        .all(|s| (*s as i32 - 12_288).abs() <= 16));
+
+    // a signal already matching the spec reads back unchanged
+    let input: Vec<i16> = (0..1_000).collect();
+    let mut reader = buffer(8_000, 2, input.clone())
+        .adapt_reader_spec::<i16>(&spec(8_000, 2, 500))
+        .unwrap();
+
+    assert_eq!(read_to_end(&mut reader), input);
+
+    // a longer spec pads the end with silence
+    let mut reader = buffer(8_000, 1, vec![0.5f64; 10])
+        .adapt_reader_spec::<f64>(&spec(16_000, 2, 30))
+        .unwrap();
+
+    let output = read_to_end(&mut reader);
+    assert_eq!(output.len(), 60);
+    assert!(output[40..].iter().all(|s| *s == 0.0));
+}
+
+#[test]
+fn writers_are_adapted_to_a_spec() {
+    let inner = Rc::new(RefCell::new(
+        buffer(16_000, 2, Vec::<i16>::new()).with_n_frames(Some(1_600)),
+    ));
+
+    let mut writer = SharedSignal::new(inner.clone())
+        .adapt_writer_spec::<f64>(&spec(8_000, 1, 800))
+        .unwrap();
+
+    writer.write_exact(&vec![0.25; 800]).unwrap();
+    writer.flush().unwrap();
+
+    let inner = inner.borrow();
+    let samples = inner.samples();
+    assert_eq!(samples.len(), 3_200);
+    assert!(samples[200..3_000]
+        .iter()
+        .all(|s| (*s as i32 - 8_192).abs() <= 16));
+}
+
+#[test]
+fn incompatible_specs_are_rejected() {
+    let reader = || buffer(8_000, 1, vec![0.0f32; 8]);
+    for spec in [spec(0, 1, 8), spec(8_000, 0, 8)] {
+        let err = reader().adapt_reader_spec::<f32>(&spec).err();
+        assert_eq!(err, Some(PhonicError::SignalMismatch));
+
+        let err = reader().adapt_writer_spec::<f32>(&spec).err();
+        assert_eq!(err, Some(PhonicError::SignalMismatch));
+    }
+
+    // lfe channels are dropped when mixed down to mono
+    let lfe = ChannelLayout::LFE1 | ChannelLayout::LFE2;
+    let reader = BufferSignal::new(8_000, lfe, vec![0.5f32; 8]);
+    let err = reader
+        .adapt_reader_spec::<f32>(&spec(8_000, ChannelLayout::MONO, 4))
+        .err();
+
+    assert_eq!(err, Some(PhonicError::SignalMismatch));
+}
@@ -0,0 +1,127 @@
+use phonic_core::PhonicError;
+use phonic_signal::{
+    adapters::{ChannelMatrix, SignalAdapter},
+    test_utils::BufferSignal,
+    ChannelLayout, Channels, Sample, SignalReader, SignalWriter,
+};
+use std::f32::consts::FRAC_1_SQRT_2;
+
+fn read_to_end<T: SignalReader>(signal: &mut T) -> Vec<T::Sample> {
+    let mut output = Vec::new();
+    let mut buf = [T::Sample::ORIGIN; 64];
+    loop {
+        match signal.read(&mut buf).unwrap() {
+            0 => return output,
+            n => output.extend_from_slice(&buf[..n]),
+        }
+    }
+}
+
+fn assert_close(actual: &[f32], expected: &[f32]) {
+    assert_eq!(actual.len(), expected.len());
+    for (i, (a, e)) in actual.iter().zip(expected).enumerate() {
+        assert!((a - e).abs() < 1e-6, "{i}: {actual:?} != {expected:?}");
+    }
+}
+
+#[test]
+fn surround_downmixes_to_stereo() {
+    let matrix = ChannelMatrix::from_channels(
+        ChannelLayout::SURROUND_5_1.into(),
+        ChannelLayout::STEREO.into(),
+    );
+
+    // the centre and rear channels are mixed in at -3 dB, the lfe is dropped, and each row is
+    // scaled down so that it sums to one
+    let gain = 1.0 + 2.0 * FRAC_1_SQRT_2;
+    let (front, side) = (1.0 / gain, FRAC_1_SQRT_2 / gain);
+    assert_close(
+        matrix.coefficients(),
+        &[
+            front, 0.0, side, 0.0, side, 0.0, //
+            0.0, front, side, 0.0, 0.0, side,
+        ],
+    );
+
+    // fl, fr, fc, lfe, rl, rr
+    let input = vec![0.5, -0.5, 0.25, 1.0, 0.125, -0.125];
+    let mut reader = BufferSignal::new(48_000, ChannelLayout::SURROUND_5_1, input)
+        .adapt_channels(ChannelLayout::STEREO);
+
+    let output = read_to_end(&mut reader);
+    let left = 0.5 * front + 0.25 * side + 0.125 * side;
+    let right = -0.5 * front + 0.25 * side - 0.125 * side;
+    assert_close(&output, &[left, right]);
+}
+
+#[test]
+fn rows_are_normalized() {
+    let matrix = ChannelMatrix::from_channels(Channels::Count(4), Channels::Count(1));
+    assert_eq!(matrix.coefficients(), [0.25; 4]);
+
+    // stereo to mono averages, mono to stereo copies
+    let mut reader = BufferSignal::new(8_000, ChannelLayout::STEREO, vec![1.0, 0.5, -1.0, -0.5])
+        .adapt_channels(1);
+    assert_close(&read_to_end(&mut reader), &[0.75, -0.75]);
+
+    let mut reader = BufferSignal::new(8_000, 1, vec![0.5, -0.25]).adapt_channels(2);
+    assert_close(&read_to_end(&mut reader), &[0.5, 0.5, -0.25, -0.25]);
+
+    // rows that don't add up to more than one are left as they are
+    let matrix = ChannelMatrix::from_channels(
+        ChannelLayout::STEREO.into(),
+        ChannelLayout::SURROUND_5_1.into(),
+    );
+
+    assert_eq!(
+        matrix.coefficients(),
+        [
+            1.0, 0.0, //
+            0.0, 1.0, //
+            0.0, 0.0, //
+            0.0, 0.0, //
+            0.0, 0.0, //
+            0.0, 0.0,
+        ]
+    );
+}
+
+#[test]
+fn stereo_upmixes_to_surround() {
+    let input = vec![0.5, -0.5, 0.25, -0.25];
+    let mut reader = BufferSignal::new(48_000, ChannelLayout::STEREO, input)
+        .adapt_channels(ChannelLayout::SURROUND_5_1);
+
+    assert_eq!(
+        read_to_end(&mut reader),
+        [0.5, -0.5, 0.0, 0.0, 0.0, 0.0, 0.25, -0.25, 0.0, 0.0, 0.0, 0.0]
+    );
+}
+
+#[test]
+fn custom_matrices_are_used() {
+    // a downmix that keeps the left channel and the right channel at half gain
+    let matrix = ChannelMatrix::new(2, 1, [1.0, 0.5]).unwrap();
+    let input = vec![0.5, 0.5, -0.25, 1.0];
+    let mut reader = BufferSignal::new(8_000, 2, input).adapt_channels_with_matrix(1, matrix);
+    assert_close(&read_to_end(&mut reader), &[0.75, 0.25]);
+
+    // an upmix that writes a mono signal to the left channel and an inverted copy to the right
+    let matrix = ChannelMatrix::new(1, 2, [1.0, -1.0]).unwrap();
+    let mut writer = BufferSignal::new(8_000, 2, Vec::new()).adapt_channels_with_matrix(1, matrix);
+    writer.write_exact(&[0.5, -0.25]).unwrap();
+    writer.flush().unwrap();
+    assert_eq!(writer.into_inner().into_samples(), [0.5, -0.5, -0.25, 0.25]);
+
+    // a matrix that doesn't fit the channels is rejected when first used
+    let matrix = ChannelMatrix::new(3, 1, [1.0; 3]).unwrap();
+    let mut reader =
+        BufferSignal::new(8_000, 2, vec![0.0; 4]).adapt_channels_with_matrix(1, matrix);
+    assert!(matches!(
+        reader.read(&mut [0.0; 2]),
+        Err(PhonicError::SignalMismatch)
+    ));
+
+    assert!(ChannelMatrix::new(2, 2, [1.0; 3]).is_err());
+    assert!(ChannelMatrix::new(0, 2, []).is_err());
+}
@@ -0,0 +1,149 @@
+use phonic_signal::{
+    adapters::{Dither, DitherNoise, NoiseShaping, SignalAdapter},
+    test_utils::BufferSignal,
+    Sample, SignalReader, SignalWriter,
+};
+use std::f64::consts::PI;
+
+fn buffer<S: Sample>(samples: Vec<S>) -> BufferSignal<S> {
+    BufferSignal::new(44_100, 2, samples).with_chunk_len(37)
+}
+
+const LSB: f64 = 1.0 / 32_768.0;
+
+fn sine(len: usize, amplitude: f64) -> Vec<f64> {
+    (0..len)
+        .map(|i| (i as f64 * 0.0123).sin() * amplitude)
+        .collect()
+}
+
+fn dither_read(input: Vec<f64>, dither: Dither) -> Vec<i16> {
+    let mut reader = buffer(input).adapt_sample_type_with_dither::<i16>(dither);
+    let mut output = Vec::new();
+    let mut buf = [0i16; 100];
+
+    loop {
+        match reader.read(&mut buf).unwrap() {
+            0 => break,
+            n => output.extend_from_slice(&buf[..n]),
+        }
+    }
+
+    output
+}
+
+fn errors(input: &[f64], output: &[i16]) -> Vec<f64> {
+    input
+        .iter()
+        .zip(output)
+        .map(|(i, o)| (*o as f64 * LSB - i) / LSB)
+        .collect()
+}
+
+#[test]
+fn dither_is_deterministic() {
+    let input = sine(4096, 0.5);
+    let dither = Dither::new(DitherNoise::Triangular).with_shaping(NoiseShaping::Lipshitz);
+
+    let a = dither_read(input.clone(), dither.with_seed(7));
+    let b = dither_read(input.clone(), dither.with_seed(7));
+    let c = dither_read(input, dither.with_seed(8));
+
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+}
+
+#[test]
+fn reader_and_writer_match() {
+    let input = sine(4096, 0.5);
+    let dither = Dither::new(DitherNoise::HighPass).with_shaping(NoiseShaping::FWeighted);
+    let read = dither_read(input.clone(), dither);
+
+    let mut writer = buffer::<i16>(Vec::new()).adapt_sample_type_with_dither(dither);
+    writer.write_exact(&input).unwrap();
+    writer.flush().unwrap();
+
+    assert_eq!(read, writer.into_inner().into_samples());
+}
+
+#[test]
+fn dither_error_is_bounded() {
+    let input = sine(8192, 0.9);
+
+    for (noise, bound) in [
+        (DitherNoise::None, 0.5),
+        (DitherNoise::Rectangular, 1.0),
+        (DitherNoise::Triangular, 1.5),
+        (DitherNoise::HighPass, 1.5),
+    ] {
+        let output = dither_read(input.clone(), Dither::new(noise));
+        let max = errors(&input, &output)
+            .into_iter()
+            .fold(0.0, |max, e| e.abs().max(max));
+
+        assert!(max <= bound, "{noise:?}: {max}");
+    }
+}
+
+#[test]
+fn dither_preserves_low_level_signals() {
+    // a constant below half an lsb disappears when rounded, but survives on average when dithered
+    let input = vec![0.3 * LSB; 65_536];
+
+    let rounded = dither_read(input.clone(), Dither::new(DitherNoise::None));
+    assert!(rounded.iter().all(|s| *s == 0));
+
+    let dithered = dither_read(input, Dither::new(DitherNoise::Triangular));
+    let mean = dithered.iter().map(|s| *s as f64).sum::<f64>() / dithered.len() as f64;
+    assert!((mean - 0.3).abs() < 0.02, "{mean}");
+}
+
+#[test]
+fn noise_shaping_reduces_low_frequency_noise() {
+    // the power of the error at a handful of frequencies between 100 Hz and 2 kHz
+    let low_band_power = |shaping| {
+        let input = sine(16_384, 0.5);
+        let dither = Dither::new(DitherNoise::Triangular).with_shaping(shaping);
+        let output = dither_read(input.clone(), dither);
+        let left: Vec<f64> = errors(&input, &output).into_iter().step_by(2).collect();
+
+        (1..=20)
+            .map(|k| {
+                let w = 2.0 * PI * (k as f64 * 100.0) / 44_100.0;
+                let (re, im) = left
+                    .iter()
+                    .enumerate()
+                    .fold((0.0, 0.0), |(re, im), (i, e)| {
+                        (re + e * (w * i as f64).cos(), im + e * (w * i as f64).sin())
+                    });
+
+                (re * re + im * im) / left.len() as f64
+            })
+            .sum::<f64>()
+    };
+
+    let flat = low_band_power(NoiseShaping::None);
+    for shaping in [
+        NoiseShaping::FirstOrder,
+        NoiseShaping::Lipshitz,
+        NoiseShaping::FWeighted,
+    ] {
+        let shaped = low_band_power(shaping);
+        assert!(shaped < flat / 4.0, "{shaping:?}: {shaped} >= {flat}");
+    }
+}
+
+#[test]
+fn lossless_conversions_are_not_dithered() {
+    let input: Vec<i16> = (i16::MIN..i16::MAX).step_by(8).collect();
+    let mut reader = buffer(input.clone())
+        .adapt_sample_type_with_dither::<i32>(Dither::new(DitherNoise::Triangular));
+
+    let mut output = vec![0i32; input.len()];
+    reader.read_exact(&mut output).unwrap();
+
+    assert!(input
+        .iter()
+        .zip(output)
+        .all(|(i, o)| (*i as i32) << 16 == o));
+}
@@ -0,0 +1,224 @@
+use phonic_core::PhonicError;
+use phonic_signal::{
+    adapters::{ResampleQuality, SignalAdapter},
+    test_utils::BufferSignal,
+    Signal, SignalObserver, SignalReader, SignalSeeker, SignalWriter,
+};
+use std::f64::consts::PI;
+
+fn sine(frame_rate: u32, frequency: f64, n_frames: usize) -> Vec<f64> {
+    (0..n_frames)
+        .map(|i| (2.0 * PI * frequency * i as f64 / frame_rate as f64).sin() * 0.5)
+        .collect()
+}
+
+fn buffer(frame_rate: u32, samples: Vec<f64>) -> BufferSignal<f64> {
+    let n_frames = samples.len() as u64;
+    BufferSignal::new(frame_rate, 1, samples)
+        .with_n_frames(Some(n_frames))
+        .with_chunk_len(37)
+}
+
+fn read_to_end<T: SignalReader<Sample = f64>>(signal: &mut T) -> Vec<f64> {
+    let mut output = Vec::new();
+    let mut buf = [0.0; 100];
+    loop {
+        match signal.read(&mut buf).unwrap() {
+            0 => return output,
+            n => output.extend_from_slice(&buf[..n]),
+        }
+    }
+}
+
+/// The largest difference between two signals, away from the edges where the filter runs off
+/// the end of the input.
+fn max_error(actual: &[f64], expected: &[f64]) -> f64 {
+    let n = actual.len().min(expected.len());
+    actual[200..n - 200]
+        .iter()
+        .zip(&expected[200..n - 200])
+        .fold(0.0, |max, (a, e)| (a - e).abs().max(max))
+}
+
+/// The amplitude of the component of a signal at `frequency`.
+fn amplitude(signal: &[f64], frame_rate: u32, frequency: f64) -> f64 {
+    let w = 2.0 * PI * frequency / frame_rate as f64;
+    let (re, im) = signal
+        .iter()
+        .enumerate()
+        .fold((0.0, 0.0), |(re, im), (i, s)| {
+            (re + s * (w * i as f64).cos(), im + s * (w * i as f64).sin())
+        });
+
+    2.0 * (re * re + im * im).sqrt() / signal.len() as f64
+}
+
+#[test]
+fn sines_keep_their_frequency() {
+    for (quality, bound) in [
+        (ResampleQuality::Fast, 2e-3),
+        (ResampleQuality::Medium, 1e-3),
+        (ResampleQuality::High, 1e-4),
+    ] {
+        let input = sine(44_100, 1_000.0, 44_100);
+        let mut reader = buffer(44_100, input).adapt_frame_rate_with_quality(48_000, quality);
+        let output = read_to_end(&mut reader);
+
+        let error = max_error(&output, &sine(48_000, 1_000.0, 48_000));
+        assert!(error < bound, "{quality:?}: {error}");
+    }
+}
+
+#[test]
+fn passband_and_dc_gain_is_unity() {
+    let mut reader = buffer(44_100, vec![0.5; 4_410]).adapt_frame_rate(48_000);
+    let output = read_to_end(&mut reader);
+    assert!(max_error(&output, &[0.5; 4_800]) < 1e-3);
+
+    for (src_rate, dst_rate) in [(44_100, 48_000), (48_000, 44_100), (48_000, 16_000)] {
+        let input = sine(src_rate, 5_000.0, src_rate as usize / 10);
+        let mut reader = buffer(src_rate, input).adapt_frame_rate(dst_rate);
+        let output = read_to_end(&mut reader);
+
+        let gain = amplitude(&output[200..output.len() - 200], dst_rate, 5_000.0) / 0.5;
+        assert!(
+            (gain - 1.0).abs() < 0.01,
+            "{src_rate} -> {dst_rate}: {gain}"
+        );
+    }
+
+    // frequencies above the nyquist frequency of the new rate are filtered out
+    let input = sine(48_000, 12_000.0, 4_800);
+    let mut reader = buffer(48_000, input).adapt_frame_rate(16_000);
+    let output = read_to_end(&mut reader);
+    let peak = output[100..output.len() - 100]
+        .iter()
+        .fold(0.0, |max: f64, s| s.abs().max(max));
+
+    assert!(peak < 0.005, "{peak}");
+}
+
+#[test]
+fn frame_counts_follow_the_ratio() {
+    for (src_rate, dst_rate, n_frames, expected) in [
+        (44_100, 48_000, 44_100, 48_000),
+        (48_000, 44_100, 48_000, 44_100),
+        (44_100, 48_000, 1_000, 1_089),
+        (8_000, 12_000, 3, 5),
+    ] {
+        let mut reader = buffer(src_rate, vec![0.25; n_frames]).adapt_frame_rate(dst_rate);
+        assert_eq!(reader.spec().n_frames, Some(expected));
+
+        let output = read_to_end(&mut reader);
+        assert_eq!(output.len() as u64, expected, "{src_rate} -> {dst_rate}");
+        assert_eq!(reader.position_frames().unwrap(), expected);
+    }
+
+    // a writer of known length pads the tail of the filter once the last frame is written
+    let mut writer = BufferSignal::new(48_000, 1, Vec::new())
+        .with_n_frames(Some(48_000))
+        .adapt_frame_rate(44_100);
+
+    assert_eq!(writer.spec().n_frames, Some(44_100));
+    writer.write_exact(&vec![0.25; 44_100]).unwrap();
+    writer.flush().unwrap();
+    assert_eq!(writer.into_inner().into_samples().len(), 48_000);
+}
+
+#[test]
+fn positions_do_not_drift() {
+    // 147 source frames for every 160 frames read, with no rounding error building up
+    let input = vec![0.0; 441_000];
+    let mut reader = buffer(44_100, input).adapt_frame_rate(48_000);
+    let mut buf = vec![0.0; 4_800];
+    for i in 1..=100 {
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(reader.position_frames().unwrap(), i * 4_800);
+    }
+
+    // the filter reads ahead of the output, but no further than its own width
+    let inner = reader.as_inner().position().unwrap();
+    assert!((441_000 - 200..=441_000).contains(&inner), "{inner}");
+}
+
+#[test]
+fn seeking_matches_reading() {
+    let input = sine(44_100, 440.0, 4_410);
+    let mut reader = buffer(44_100, input.clone()).adapt_frame_rate(48_000);
+    let expected = read_to_end(&mut reader);
+
+    let mut reader = buffer(44_100, input).adapt_frame_rate(48_000);
+    for frame in [1_000, 1_001, 0, 4_799, 2_400] {
+        reader.set_position_frames(frame).unwrap();
+        assert_eq!(reader.position_frames().unwrap(), frame);
+
+        let mut buf = vec![0.0; 100.min(4_800 - frame as usize)];
+        reader.read_exact(&mut buf).unwrap();
+
+        let frame = frame as usize;
+        assert_eq!(buf, expected[frame..frame + buf.len()], "{frame}");
+        assert_eq!(
+            reader.position_frames().unwrap(),
+            (frame + buf.len()) as u64
+        );
+    }
+}
+
+#[test]
+fn real_ratios_convert_without_a_frame_rate_ratio() {
+    // a semitone, which no ratio of two whole frame rates gives exactly
+    let ratio = 2.0f64.powf(1.0 / 12.0);
+    let input = sine(48_000, 1_000.0, 48_000);
+    let mut reader =
+        buffer(48_000, input.clone()).adapt_frame_rate_with_ratio(ratio, ResampleQuality::High);
+
+    assert_eq!(reader.spec().frame_rate, 50_854);
+    assert_eq!(reader.spec().n_frames, Some(50_855));
+
+    let output = read_to_end(&mut reader);
+    assert_eq!(output.len(), 50_855);
+    assert_eq!(reader.position_frames().unwrap(), 50_855);
+
+    let expected = (0..output.len())
+        .map(|i| (2.0 * PI * 1_000.0 * i as f64 / (48_000.0 * ratio)).sin() * 0.5)
+        .collect::<Vec<_>>();
+
+    let error = max_error(&output, &expected);
+    assert!(error < 1e-4, "{error}");
+
+    let mut reader = buffer(48_000, input).adapt_frame_rate_with_ratio(ratio, Default::default());
+    let expected = read_to_end(&mut reader);
+    for frame in [40_000, 1_001, 0, 50_854, 24_000] {
+        reader.set_position_frames(frame).unwrap();
+        assert_eq!(reader.position_frames().unwrap(), frame);
+
+        let mut buf = vec![0.0; 100.min(50_855 - frame as usize)];
+        reader.read_exact(&mut buf).unwrap();
+
+        let frame = frame as usize;
+        assert_eq!(buf, expected[frame..frame + buf.len()], "{frame}");
+    }
+
+    // writing converts back to the frame rate of the inner signal, where the 50 855 frames last
+    // as long as 48 000.7 frames
+    let mut writer = BufferSignal::new(48_000, 1, Vec::new())
+        .adapt_frame_rate_with_ratio(ratio, ResampleQuality::Fast);
+
+    writer.write_exact(&vec![0.25; 50_855]).unwrap();
+    writer.finish().unwrap();
+    assert_eq!(writer.into_inner().into_samples().len(), 48_001);
+}
+
+#[test]
+fn invalid_ratios_are_rejected() {
+    for ratio in [0.0, -1.5, f64::NAN, f64::INFINITY] {
+        let mut reader = buffer(48_000, vec![0.0; 100])
+            .adapt_frame_rate_with_ratio(ratio, ResampleQuality::Fast);
+
+        assert_eq!(
+            reader.read(&mut [0.0; 10]),
+            Err(PhonicError::SignalMismatch),
+            "{ratio}"
+        );
+    }
+}
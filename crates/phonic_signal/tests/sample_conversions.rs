@@ -0,0 +1,311 @@
+use phonic_signal::{FromSample, Sample, I24, I48, U24, U48};
+use std::fmt::Debug;
+
+trait TestSample: Sample + Debug + PartialOrd {
+    /// The number of bits of precision available in the range `[-1.0, 1.0)`.
+    const PRECISION: u32;
+    const IS_FLOAT: bool;
+
+    fn to_f64(self) -> f64;
+    fn values() -> Vec<Self>;
+}
+
+macro_rules! impl_int_test_sample {
+    ($s:ty, $bits:expr, $is_signed:expr) => {
+        impl TestSample for $s {
+            const PRECISION: u32 = $bits;
+            const IS_FLOAT: bool = false;
+
+            fn to_f64(self) -> f64 {
+                let offset = if $is_signed { 0.0 } else { 1.0 };
+                self as f64 / (1u64 << ($bits - 1)) as f64 - offset
+            }
+
+            fn values() -> Vec<Self> {
+                let step = 1i128 << ($bits - 8);
+                let mut values: Vec<Self> = (0..256)
+                    .map(|i| (Self::MIN as i128 + i * step) as Self)
+                    .collect();
+
+                values.extend([
+                    Self::MIN,
+                    Self::MIN + 1,
+                    Self::ORIGIN - 1,
+                    Self::ORIGIN,
+                    Self::ORIGIN + 1,
+                    Self::MAX - 1,
+                    Self::MAX,
+                ]);
+
+                values.sort();
+                values
+            }
+        }
+    };
+}
+
+macro_rules! impl_float_test_sample {
+    ($s:ty, $bits:expr) => {
+        impl TestSample for $s {
+            const PRECISION: u32 = $bits;
+            const IS_FLOAT: bool = true;
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn values() -> Vec<Self> {
+                let mut values: Vec<Self> = (0..=256).map(|i| i as Self / 128.0 - 1.0).collect();
+                values.extend([-0.3, 0.3, -1e-6, 1e-6, 0.999_999]);
+                values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                values
+            }
+        }
+    };
+}
+
+impl_int_test_sample!(i8, 8, true);
+impl_int_test_sample!(i16, 16, true);
+impl_int_test_sample!(i32, 32, true);
+impl_int_test_sample!(i64, 64, true);
+
+impl_int_test_sample!(u8, 8, false);
+impl_int_test_sample!(u16, 16, false);
+impl_int_test_sample!(u32, 32, false);
+impl_int_test_sample!(u64, 64, false);
+
+macro_rules! impl_packed_test_sample {
+    ($s:ty, $inner:ty, $bits:expr, $is_signed:expr) => {
+        impl TestSample for $s {
+            const PRECISION: u32 = $bits;
+            const IS_FLOAT: bool = false;
+
+            fn to_f64(self) -> f64 {
+                let offset = if $is_signed { 0.0 } else { 1.0 };
+                self.get() as f64 / (1u64 << ($bits - 1)) as f64 - offset
+            }
+
+            fn values() -> Vec<Self> {
+                let step = 1i128 << ($bits - 8);
+                let min = Self::MIN.get() as i128;
+                let origin = Self::ORIGIN.get() as i128;
+                let max = Self::MAX.get() as i128;
+
+                let mut values: Vec<Self> = (0..256)
+                    .map(|i| min + i * step)
+                    .chain([min, min + 1, origin - 1, origin, origin + 1, max - 1, max])
+                    .map(|v| Self::new(v as $inner))
+                    .collect();
+
+                values.sort();
+                values
+            }
+        }
+    };
+}
+
+impl_packed_test_sample!(I24, i32, 24, true);
+impl_packed_test_sample!(I48, i64, 48, true);
+impl_packed_test_sample!(U24, u32, 24, false);
+impl_packed_test_sample!(U48, u64, 48, false);
+
+impl_float_test_sample!(f32, 24);
+impl_float_test_sample!(f64, 53);
+
+fn tolerance<A: TestSample, B: TestSample>() -> f64 {
+    let bits = A::PRECISION.min(B::PRECISION);
+    2.0 / (1u64 << (bits - 1)) as f64
+}
+
+fn check_origin<A, B>()
+where
+    A: TestSample,
+    B: TestSample + FromSample<A>,
+{
+    assert_eq!(
+        B::from_sample(A::ORIGIN),
+        B::ORIGIN,
+        "{} origin -> {}",
+        std::any::type_name::<A>(),
+        std::any::type_name::<B>()
+    );
+}
+
+fn check_extremes<A, B>()
+where
+    A: TestSample,
+    B: TestSample + FromSample<A>,
+{
+    let (a_min, a_max) = A::RANGE;
+    let (b_min, _) = B::RANGE;
+    let name = format!(
+        "{} -> {}",
+        std::any::type_name::<A>(),
+        std::any::type_name::<B>()
+    );
+
+    assert_eq!(B::from_sample(a_min), b_min, "{name}: min");
+
+    let max = B::from_sample(a_max).to_f64();
+    let expected = a_max.to_f64().min(1.0);
+    assert!(
+        (max - expected).abs() <= tolerance::<A, B>(),
+        "{name}: max {max} != {expected}"
+    );
+}
+
+fn check_values<A, B>()
+where
+    A: TestSample + FromSample<B>,
+    B: TestSample + FromSample<A>,
+{
+    let name = format!(
+        "{} -> {}",
+        std::any::type_name::<A>(),
+        std::any::type_name::<B>()
+    );
+
+    let mut prev: Option<B> = None;
+    for a in A::values() {
+        let b = B::from_sample(a);
+        let expected = a.to_f64().clamp(-1.0, 1.0);
+        assert!(
+            (b.to_f64() - expected).abs() <= tolerance::<A, B>(),
+            "{name}: {a:?} -> {b:?}"
+        );
+
+        assert!(
+            prev.is_none_or(|prev| prev <= b),
+            "{name}: {a:?} -> {b:?} is not monotonic"
+        );
+        prev = Some(b);
+
+        let round_trip = A::from_sample(b);
+        if !A::IS_FLOAT && B::PRECISION >= A::PRECISION {
+            assert_eq!(round_trip, a, "{name}: {a:?} does not round trip");
+        } else {
+            assert!(
+                (round_trip.to_f64() - a.to_f64()).abs() <= tolerance::<A, B>(),
+                "{name}: {a:?} -> {round_trip:?} round trip"
+            );
+        }
+    }
+}
+
+macro_rules! test_conversions_from {
+    ($name:ident, $from:ty) => {
+        #[test]
+        fn $name() {
+            test_conversions_from!(
+                @to $from, i8, i16, I24, i32, I48, i64, u8, u16, U24, u32, U48, u64, f32, f64
+            );
+        }
+    };
+    (@to $from:ty, $($to:ty),*) => {
+        $(
+            check_origin::<$from, $to>();
+            check_extremes::<$from, $to>();
+            check_values::<$from, $to>();
+        )*
+    };
+}
+
+test_conversions_from!(from_i8, i8);
+test_conversions_from!(from_i16, i16);
+test_conversions_from!(from_i24, I24);
+test_conversions_from!(from_i32, i32);
+test_conversions_from!(from_i48, I48);
+test_conversions_from!(from_i64, i64);
+
+test_conversions_from!(from_u8, u8);
+test_conversions_from!(from_u16, u16);
+test_conversions_from!(from_u24, U24);
+test_conversions_from!(from_u32, u32);
+test_conversions_from!(from_u48, U48);
+test_conversions_from!(from_u64, u64);
+
+test_conversions_from!(from_f32, f32);
+test_conversions_from!(from_f64, f64);
+
+#[test]
+fn exhaustive_16_bit_round_trip() {
+    for s in i16::MIN..=i16::MAX {
+        assert_eq!(i16::from_sample(i64::from_sample(s)), s);
+        assert_eq!(i16::from_sample(u64::from_sample(s)), s);
+        assert_eq!(i16::from_sample(f32::from_sample(s)), s);
+        assert_eq!(i16::from_sample(f64::from_sample(s)), s);
+    }
+
+    for s in u16::MIN..=u16::MAX {
+        assert_eq!(u16::from_sample(i64::from_sample(s)), s);
+        assert_eq!(u16::from_sample(u64::from_sample(s)), s);
+        assert_eq!(u16::from_sample(f32::from_sample(s)), s);
+        assert_eq!(u16::from_sample(f64::from_sample(s)), s);
+    }
+}
+
+#[test]
+fn exhaustive_24_bit_round_trip() {
+    for s in I24::MIN.get()..=I24::MAX.get() {
+        let s = I24::new(s);
+        assert_eq!(I24::from_sample(f32::from_sample(s)), s);
+        assert_eq!(I24::from_sample(U24::from_sample(s)), s);
+        assert_eq!(I24::from_sample(I48::from_sample(s)), s);
+    }
+}
+
+#[test]
+fn floats_are_rounded() {
+    // halfway between two steps rounds away from zero, and anything short of it rounds back
+    let lsb = 1.0 / 32_768.0;
+    assert_eq!(i16::from_sample(0.5 * lsb), 1);
+    assert_eq!(i16::from_sample(-0.5 * lsb), -1);
+    assert_eq!(i16::from_sample(0.49 * lsb), 0);
+    assert_eq!(i16::from_sample(-0.49f32 * lsb as f32), 0);
+    assert_eq!(i16::from_sample(2.5 * lsb), 3);
+    assert_eq!(i16::from_sample(2.4f32 * lsb as f32), 2);
+    assert_eq!(u16::from_sample(0.5 * lsb), 0x8001);
+    assert_eq!(u16::from_sample(-1.5 * lsb), 0x7ffe);
+
+    let lsb = 1.0 / 128.0;
+    assert_eq!(i8::from_sample(0.5f32 * lsb as f32), 1);
+    assert_eq!(i8::from_sample(-0.5 * lsb), -1);
+    assert_eq!(u8::from_sample(0.5 * lsb), 0x81);
+
+    let lsb = 1.0 / 8_388_608.0;
+    assert_eq!(I24::from_sample(0.5 * lsb).get(), 1);
+    assert_eq!(I24::from_sample(-0.5 * lsb).get(), -1);
+    assert_eq!(I24::from_sample(-0.49 * lsb).get(), 0);
+    assert_eq!(U24::from_sample(-0.5 * lsb).get(), 0x7f_ffff);
+
+    let lsb = 1.0 / 2_147_483_648.0;
+    assert_eq!(i32::from_sample(0.5 * lsb), 1);
+    assert_eq!(i32::from_sample(-0.5 * lsb), -1);
+    assert_eq!(u32::from_sample(-0.5 * lsb), 0x7fff_ffff);
+
+    // +1.0 is the largest value of every integer type
+    assert_eq!(i8::from_sample(1.0f32), i8::MAX);
+    assert_eq!(u8::from_sample(1.0f64), u8::MAX);
+    assert_eq!(i16::from_sample(1.0f32), i16::MAX);
+    assert_eq!(u16::from_sample(1.0f64), u16::MAX);
+    assert_eq!(I24::from_sample(1.0f32), I24::MAX);
+    assert_eq!(U24::from_sample(1.0f64), U24::MAX);
+    assert_eq!(i32::from_sample(1.0f32), i32::MAX);
+    assert_eq!(u32::from_sample(1.0f64), u32::MAX);
+    assert_eq!(I48::from_sample(1.0f64), I48::MAX);
+    assert_eq!(U48::from_sample(1.0f32), U48::MAX);
+    assert_eq!(i64::from_sample(1.0f64), i64::MAX);
+    assert_eq!(u64::from_sample(1.0f32), u64::MAX);
+}
+
+#[test]
+fn packed_byte_order() {
+    let s = I24::new(-2);
+    assert_eq!(s.to_le_bytes(), [0xfe, 0xff, 0xff]);
+    assert_eq!(s.to_be_bytes(), [0xff, 0xff, 0xfe]);
+    assert_eq!(I24::from_be_bytes([0x80, 0x00, 0x01]).get(), -0x7f_ffff);
+    assert_eq!(
+        U48::from_le_bytes([1, 0, 0, 0, 0, 0x80]).get(),
+        0x8000_0000_0001
+    );
+}
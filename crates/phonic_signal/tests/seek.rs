@@ -0,0 +1,142 @@
+use phonic_core::PhonicError;
+use phonic_signal::{
+    adapters::{Dither, DitherNoise, SignalAdapter},
+    test_utils::BufferSignal,
+    Sample, Signal, SignalObserver, SignalReader, SignalSeeker, SignalSpec, SignalWriter,
+};
+use std::time::Duration;
+
+fn buffer(frame_rate: u32, n_channels: u16, samples: Vec<f32>) -> BufferSignal<f32> {
+    let n_frames = samples.len() as u64 / n_channels as u64;
+    BufferSignal::new(frame_rate, n_channels, samples)
+        .with_n_frames(Some(n_frames))
+        .with_chunk_len(37)
+}
+
+fn ramp(n_frames: usize, n_channels: usize) -> Vec<f32> {
+    (0..n_frames * n_channels)
+        .map(|i| ((i / n_channels) as f32 * 0.01).sin() * 0.5 + (i % n_channels) as f32 * 0.1)
+        .collect()
+}
+
+fn read_to_end<T: SignalReader>(signal: &mut T) -> Vec<T::Sample> {
+    let mut output = Vec::new();
+    let mut buf = [T::Sample::ORIGIN; 64];
+    loop {
+        match signal.read(&mut buf).unwrap() {
+            0 => return output,
+            n => output.extend_from_slice(&buf[..n]),
+        }
+    }
+}
+
+#[test]
+fn frame_and_time_positions() {
+    let mut signal = buffer(8_000, 2, ramp(8_000, 2));
+
+    signal.set_position(300).unwrap();
+    assert_eq!(signal.position().unwrap(), 300);
+    assert_eq!(signal.position_frames().unwrap(), 150);
+
+    signal.seek_frames(-50).unwrap();
+    assert_eq!(signal.position().unwrap(), 200);
+
+    signal.seek_to_time(Duration::from_millis(250)).unwrap();
+    assert_eq!(signal.position_frames().unwrap(), 2_000);
+    assert_eq!(signal.position_time().unwrap(), Duration::from_millis(250));
+
+    // seeking by frames realigns a position in the middle of a frame
+    signal.seek(1).unwrap();
+    signal.seek_frames(1).unwrap();
+    assert_eq!(signal.position().unwrap(), 4_002);
+
+    signal.seek_to_time(Duration::from_secs(1)).unwrap();
+    assert!(signal.read(&mut [0.0; 2]).unwrap() == 0);
+
+    let err = signal.seek_to_time(Duration::from_millis(1_001));
+    assert!(matches!(err, Err(PhonicError::EndOfStream)));
+
+    let err = signal.seek_frames(-8_001);
+    assert!(matches!(err, Err(PhonicError::InvalidData)));
+}
+
+#[test]
+fn frame_times_round_trip() {
+    let spec = SignalSpec {
+        frame_rate: 44_100,
+        channels: 1.into(),
+        n_frames: None,
+    };
+
+    for frame in (0..1_000_000).step_by(997) {
+        assert_eq!(spec.frame_at(spec.frame_time(frame)), frame);
+    }
+}
+
+#[test]
+fn seek_through_adapters() {
+    let input = ramp(4_000, 1);
+    let adapt = |signal: BufferSignal<f32>| {
+        signal
+            .adapt_sample_type::<f64>()
+            .adapt_channels(2)
+            .adapt_frame_rate(12_000)
+            .adapt_sample_type_with_dither::<i16>(Dither::new(DitherNoise::None))
+    };
+
+    let mut adapter = adapt(buffer(8_000, 1, input.clone()));
+    assert_eq!(adapter.spec().n_frames, Some(6_000));
+    let expected = read_to_end(&mut adapter);
+    assert_eq!(adapter.position_frames().unwrap(), 6_000);
+
+    let mut adapter = adapt(buffer(8_000, 1, input));
+    for time in [250, 100, 0, 400] {
+        adapter.seek_to_time(Duration::from_millis(time)).unwrap();
+
+        let frame = time as usize * 12;
+        assert_eq!(adapter.position_frames().unwrap(), frame as u64);
+
+        let mut buf = [0i16; 200];
+        adapter.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, &expected[frame * 2..frame * 2 + 200], "{time} ms");
+    }
+
+    let err = adapter.seek_to_time(Duration::from_millis(501));
+    assert!(matches!(err, Err(PhonicError::EndOfStream)));
+}
+
+#[test]
+fn seek_past_the_end_of_a_padded_signal() {
+    let mut adapter = buffer(1_000, 1, ramp(100, 1)).adapt_n_frames(Some(150));
+
+    adapter.set_position_frames(120).unwrap();
+    assert_eq!(adapter.as_inner().position().unwrap(), 100);
+    assert!(read_to_end(&mut adapter).iter().all(|s| *s == 0.0));
+
+    adapter.set_position_frames(90).unwrap();
+    let output = read_to_end(&mut adapter);
+    assert_eq!(output.len(), 60);
+    assert_eq!(&output[..10], &ramp(100, 1)[90..]);
+}
+
+#[test]
+fn channel_adapters_seek_whole_frames() {
+    let mut adapter = buffer(1_000, 2, ramp(100, 2)).adapt_channels(3);
+
+    let mut buf = [0.0; 4];
+    adapter.read(&mut buf).unwrap();
+    assert_eq!(adapter.position().unwrap(), 4);
+    assert!(matches!(adapter.seek(1), Err(PhonicError::SignalMismatch)));
+
+    adapter.seek(2).unwrap();
+    assert_eq!(adapter.position_frames().unwrap(), 2);
+    assert_eq!(adapter.as_inner().position().unwrap(), 4);
+
+    let mut writer = buffer(1_000, 2, Vec::new()).adapt_channels(1);
+    writer.write_exact(&[0.5; 10]).unwrap();
+    assert_eq!(writer.position().unwrap(), 10);
+
+    writer.flush().unwrap();
+    writer.set_position(4).unwrap();
+    assert_eq!(writer.as_inner().position().unwrap(), 8);
+}